@@ -18,6 +18,8 @@
 use durs_core::commands::dbex::DbExOpt;
 use durs_core::commands::keys::KeysOpt;
 use durs_core::commands::modules::{DisableOpt, EnableOpt, ListModulesOpt};
+use durs_core::commands::network::NetworkOpt;
+use durs_core::commands::profiles::ProfilesOpt;
 use durs_core::commands::reset::ResetOpt;
 use durs_core::commands::start::StartOpt;
 use durs_core::commands::{
@@ -118,6 +120,14 @@ impl DursCliOpt {
                 options,
                 command: DursCommandEnum::Core(DursCoreCommand::ListModulesOpt(opts)),
             },
+            DursCliSubCommand::NetworkOpt(opts) => DursCommand {
+                options,
+                command: DursCommandEnum::Core(DursCoreCommand::NetworkOpt(opts)),
+            },
+            DursCliSubCommand::ProfilesOpt(opts) => DursCommand {
+                options,
+                command: DursCommandEnum::Core(DursCoreCommand::ProfilesOpt(opts)),
+            },
             DursCliSubCommand::ResetOpt(opts) => DursCommand {
                 options,
                 command: DursCommandEnum::Core(DursCoreCommand::ResetOpt(opts)),
@@ -160,6 +170,12 @@ pub enum DursCliSubCommand {
     /// List available modules
     #[structopt(name = "modules", setting(structopt::clap::AppSettings::ColoredHelp))]
     ListModulesOpt(ListModulesOpt),
+    /// Network informations
+    #[structopt(name = "network", setting(structopt::clap::AppSettings::ColoredHelp))]
+    NetworkOpt(NetworkOpt),
+    /// Profiles operations
+    #[structopt(name = "profiles", setting(structopt::clap::AppSettings::ColoredHelp))]
+    ProfilesOpt(ProfilesOpt),
     /// Reset data or conf or all
     #[structopt(name = "reset", setting(structopt::clap::AppSettings::ColoredHelp))]
     ResetOpt(ResetOpt),
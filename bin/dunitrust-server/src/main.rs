@@ -37,12 +37,14 @@ use crate::init::init;
 use durs_core::durs_plug;
 #[cfg(not(target_arch = "arm"))]
 pub use durs_gva::GvaModule;
+pub use durs_notify::NotifyModule;
 #[cfg(unix)]
 pub use durs_tui::TuiModule;
 use log::error;
 use structopt::StructOpt;
 //pub use durs_skeleton::SkeletonModule;
 pub use durs_ws2p::WS2PModule;
+pub use durs_ws2p_client::WS2PClientModule;
 pub use durs_ws2p_v1_legacy::WS2Pv1Module;
 
 /// Dunitrust cli main macro
@@ -66,7 +68,12 @@ macro_rules! durs_cli_main {
 fn main() {
     durs_cli_main!(durs_plug!(
         [WS2Pv1Module, WS2PModule],
-        [TuiModule, GvaModule /*, SkeletonModule ,DasaModule*/]
+        [
+            TuiModule,
+            GvaModule,
+            WS2PClientModule,
+            NotifyModule /*, SkeletonModule ,DasaModule*/
+        ]
     ))
 }
 #[cfg(unix)]
@@ -74,10 +81,17 @@ fn main() {
 fn main() {
     durs_cli_main!(durs_plug!(
         [WS2Pv1Module, WS2PModule],
-        [TuiModule /*, SkeletonModule*/]
+        [
+            TuiModule,
+            WS2PClientModule,
+            NotifyModule /*, SkeletonModule*/
+        ]
     ))
 }
 #[cfg(windows)]
 fn main() {
-    durs_cli_main!(durs_plug!([WS2Pv1Module, WS2PModule], []))
+    durs_cli_main!(durs_plug!(
+        [WS2Pv1Module, WS2PModule],
+        [WS2PClientModule, NotifyModule]
+    ))
 }
@@ -0,0 +1,46 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Dunitrust configuration of the background saving of the blockchain databases
+
+use std::time::Duration;
+
+#[derive(Debug, Copy, Clone, Deserialize, PartialEq, Eq, Serialize)]
+/// Background saving of the blockchain databases: the stores changed by the applied blocks are
+/// saved once the interval is elapsed since the first unsaved write, or once the number of
+/// unsaved writes reaches the threshold.
+#[serde(default)]
+pub struct AutoSaveConf {
+    /// Maximum duration between a write and its save (in seconds)
+    pub interval_in_secs: u64,
+    /// Maximum number of unsaved writes (blocks applied or reverted)
+    pub writes_threshold: usize,
+}
+
+impl Default for AutoSaveConf {
+    fn default() -> Self {
+        AutoSaveConf {
+            interval_in_secs: 10,
+            writes_threshold: 100,
+        }
+    }
+}
+
+impl AutoSaveConf {
+    /// Maximum duration between a write and its save
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_in_secs)
+    }
+}
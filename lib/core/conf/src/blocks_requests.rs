@@ -0,0 +1,52 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Dunitrust configuration of the blocks requested to the other nodes
+
+#[derive(Debug, Copy, Clone, Deserialize, PartialEq, Eq, Serialize)]
+/// Requests of blocks to the other nodes.
+/// The blocks are requested by chunks, the next chunk being requested while the received one
+/// is applied.
+#[serde(default)]
+pub struct BlocksRequestsConf {
+    /// Number of blocks per request
+    pub chunk_size: u32,
+    /// Maximum number of blocks requested at the same time
+    pub max_blocks_request: u32,
+    /// Maximum number of received blocks waiting to be applied,
+    /// no more chunk is requested until the applied blocks make room
+    pub max_queued_blocks: u32,
+}
+
+impl Default for BlocksRequestsConf {
+    fn default() -> Self {
+        BlocksRequestsConf {
+            chunk_size: 50,
+            max_blocks_request: 500,
+            max_queued_blocks: 1_000,
+        }
+    }
+}
+
+impl BlocksRequestsConf {
+    /// Number of blocks per request (at least one)
+    pub fn chunk_size(&self) -> u32 {
+        std::cmp::max(self.chunk_size, 1)
+    }
+    /// Maximum number of chunks requested at the same time (at least one)
+    pub fn max_pending_chunks(&self) -> usize {
+        std::cmp::max(self.max_blocks_request / self.chunk_size(), 1) as usize
+    }
+}
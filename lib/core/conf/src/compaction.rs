@@ -0,0 +1,79 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Dunitrust database compaction configuration
+
+#[derive(Debug, Copy, Clone, Deserialize, PartialEq, Eq, Serialize)]
+/// Scheduled compaction of the blockchain database.
+/// The compaction needs an exclusive access to the database,
+/// so it is performed when the node starts during the maintenance window.
+#[serde(default)]
+pub struct CompactionConf {
+    /// Enable the scheduled compaction
+    pub enabled: bool,
+    /// Beginning of the maintenance window (hour of the day, local time)
+    pub window_start_hour: u8,
+    /// End of the maintenance window (hour of the day, local time, excluded)
+    pub window_end_hour: u8,
+    /// Minimum delay between two compactions (in days)
+    pub interval_days: u32,
+}
+
+impl Default for CompactionConf {
+    fn default() -> Self {
+        CompactionConf {
+            enabled: false,
+            window_start_hour: 2,
+            window_end_hour: 5,
+            interval_days: 7,
+        }
+    }
+}
+
+impl CompactionConf {
+    /// Is the given hour of the day in the maintenance window ?
+    pub fn in_window(&self, hour: u8) -> bool {
+        if self.window_start_hour <= self.window_end_hour {
+            hour >= self.window_start_hour && hour < self.window_end_hour
+        } else {
+            // The window overlaps midnight
+            hour >= self.window_start_hour || hour < self.window_end_hour
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compaction_window() {
+        let conf = CompactionConf::default();
+        assert!(!conf.in_window(1));
+        assert!(conf.in_window(2));
+        assert!(conf.in_window(4));
+        assert!(!conf.in_window(5));
+
+        let conf = CompactionConf {
+            window_start_hour: 23,
+            window_end_hour: 1,
+            ..CompactionConf::default()
+        };
+        assert!(conf.in_window(23));
+        assert!(conf.in_window(0));
+        assert!(!conf.in_window(1));
+        assert!(!conf.in_window(12));
+    }
+}
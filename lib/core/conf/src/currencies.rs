@@ -0,0 +1,128 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Modules default configuration per currency.
+//!
+//! Defaults are declared in one embedded TOML file per currency, with one table per module,
+//! so adding a currency or updating its seed nodes does not require changing the modules.
+
+use dubp_currency_params::CurrencyName;
+use durs_module::{ModuleConfError, ModuleStaticName};
+use serde::de::DeserializeOwned;
+
+/// Embedded modules defaults, by currency name
+static CURRENCIES_MODULES_DEFAULTS: &[(&str, &str)] = &[
+    ("g1", include_str!("../resources/currencies/g1.toml")),
+    (
+        "g1-test",
+        include_str!("../resources/currencies/g1-test.toml"),
+    ),
+];
+
+/// Get the default configuration of a module for a currency.
+/// Returns `None` if the currency or the module has no declared defaults.
+pub fn get_module_currency_defaults<T: DeserializeOwned>(
+    currency_name: &CurrencyName,
+    module_name: ModuleStaticName,
+) -> Result<Option<T>, ModuleConfError> {
+    let raw_defaults = if let Some((_, raw_defaults)) = CURRENCIES_MODULES_DEFAULTS
+        .iter()
+        .find(|(currency, _)| *currency == currency_name.0)
+    {
+        raw_defaults
+    } else {
+        return Ok(None);
+    };
+
+    let mut currency_defaults: toml::value::Table =
+        toml::from_str(raw_defaults).map_err(|e| invalid_defaults(currency_name, e))?;
+
+    if let Some(module_defaults) = currency_defaults.remove(module_name.0) {
+        Ok(Some(
+            module_defaults
+                .try_into()
+                .map_err(|e| invalid_defaults(currency_name, e))?,
+        ))
+    } else {
+        Ok(None)
+    }
+}
+
+#[inline]
+fn invalid_defaults(currency_name: &CurrencyName, e: toml::de::Error) -> ModuleConfError {
+    ModuleConfError::InvalidField {
+        field_name: "currency_defaults",
+        cause: format!("invalid defaults for currency {}: {}", currency_name.0, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct SyncEndpointDefault {
+        raw_endpoint: String,
+        issuer: String,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Ws2pDefaults {
+        sync_endpoints: Vec<SyncEndpointDefault>,
+    }
+
+    #[test]
+    fn test_embedded_currencies_defaults_are_valid() {
+        for (currency, raw_defaults) in CURRENCIES_MODULES_DEFAULTS {
+            assert!(
+                toml::from_str::<toml::value::Table>(raw_defaults).is_ok(),
+                "invalid defaults for currency {}",
+                currency
+            );
+        }
+    }
+
+    #[test]
+    fn test_get_module_currency_defaults() -> Result<(), ModuleConfError> {
+        let g1_test = CurrencyName("g1-test".to_owned());
+
+        let ws2p_defaults: Option<Ws2pDefaults> =
+            get_module_currency_defaults(&g1_test, ModuleStaticName("ws2p1"))?;
+        assert_eq!(
+            Some(Ws2pDefaults {
+                sync_endpoints: vec![SyncEndpointDefault {
+                    raw_endpoint: "WS2P 17ae4dd9 ts.gt.elo.tf 80 ws2p".to_owned(),
+                    issuer: "42jMJtb8chXrpHMAMcreVdyPJK7LtWjEeRqkPw4eSEVp".to_owned(),
+                }],
+            }),
+            ws2p_defaults
+        );
+
+        assert_eq!(
+            None,
+            get_module_currency_defaults::<Ws2pDefaults>(&g1_test, ModuleStaticName("gva"))?
+        );
+        assert_eq!(
+            None,
+            get_module_currency_defaults::<Ws2pDefaults>(
+                &CurrencyName("unknown".to_owned()),
+                ModuleStaticName("ws2p1")
+            )?
+        );
+
+        Ok(())
+    }
+}
@@ -116,11 +116,20 @@ mod tests {
                     memory_usage: ResourceUsage::Medium,
                     disk_space_usage: ResourceUsage::Large,
                 }),
+                storage_mode: None,
+                tx_search_index: None,
+                fork_resolution: None,
+                blocks_requests: None,
+                sandboxes: None,
+                autosave: None,
+                compaction: None,
+                feature_flags: None,
                 disabled: Some(hashset![
                     ModuleName("tui".to_owned()),
                     ModuleName("gva".to_owned())
                 ]),
                 enabled: None,
+                lang: None,
             }),
             load_env_global_user_conf()?,
         );
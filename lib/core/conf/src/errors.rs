@@ -26,6 +26,9 @@ pub enum DursConfError {
     /// File error
     #[fail(display = "{}", _0)]
     FileErr(DursConfFileError),
+    /// Module conf error
+    #[fail(display = "{}", _0)]
+    ModuleConfErr(durs_module::ModuleConfError),
 }
 
 /// Error with configuration file
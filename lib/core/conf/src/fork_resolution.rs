@@ -0,0 +1,58 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Dunitrust fork resolution configuration
+
+#[derive(Debug, Copy, Clone, Deserialize, PartialEq, Eq, Serialize)]
+/// Fork branch chosen when several branches are eligible
+#[serde(rename_all = "snake_case")]
+pub enum ForkBranchPriority {
+    /// The branch with the highest head
+    Highest,
+    /// The branch requiring the shallowest rollback, then the highest one
+    ShallowestRollback,
+}
+
+impl Default for ForkBranchPriority {
+    fn default() -> Self {
+        ForkBranchPriority::Highest
+    }
+}
+
+#[derive(Debug, Copy, Clone, Deserialize, PartialEq, Eq, Serialize)]
+/// Fork resolution rules
+#[serde(default)]
+pub struct ForkResolutionConf {
+    /// Number of blocks by which a fork branch must be ahead of the local blockchain to switch to it
+    pub advance_blocks: u32,
+    /// Blockchain time (in seconds) by which the head of a fork branch must be ahead of the local current block to switch to it
+    pub advance_time: u64,
+    /// Fork branch chosen when several branches are eligible
+    pub priority: ForkBranchPriority,
+    /// Depth (in blocks) beyond which the blocks of the local blockchain are final:
+    /// the node never reverts them, whatever the fork branch (default: fork window size)
+    pub finality_depth: Option<u32>,
+}
+
+impl Default for ForkResolutionConf {
+    fn default() -> Self {
+        ForkResolutionConf {
+            advance_blocks: 3,
+            advance_time: 900,
+            priority: ForkBranchPriority::default(),
+            finality_depth: None,
+        }
+    }
+}
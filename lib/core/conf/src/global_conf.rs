@@ -17,6 +17,13 @@
 
 pub mod v2;
 
+use crate::autosave::AutoSaveConf;
+use crate::blocks_requests::BlocksRequestsConf;
+use crate::compaction::CompactionConf;
+use crate::fork_resolution::ForkResolutionConf;
+use crate::sandboxes::SandboxesConf;
+use crate::storage::StorageMode;
+use dubp_currency_params::feature_flags::FeatureFlags;
 use durs_common_tools::fatal_error;
 use durs_module::{DursGlobalConfTrait, ModuleName};
 
@@ -36,6 +43,65 @@ pub enum DuRsGlobalUserConf {
     V2(v2::DuRsGlobalUserConfV2),
 }
 
+impl DuRsGlobalConf {
+    /// Blockchain storage mode
+    pub fn storage_mode(&self) -> StorageMode {
+        match *self {
+            DuRsGlobalConf::V1(_) => StorageMode::Full,
+            DuRsGlobalConf::V2(ref conf_v2) => conf_v2.storage_mode,
+        }
+    }
+    /// Transactions search index (output conditions and comment words)
+    pub fn tx_search_index(&self) -> bool {
+        match *self {
+            DuRsGlobalConf::V1(_) => false,
+            DuRsGlobalConf::V2(ref conf_v2) => conf_v2.tx_search_index,
+        }
+    }
+    /// Fork resolution rules
+    pub fn fork_resolution(&self) -> ForkResolutionConf {
+        match *self {
+            DuRsGlobalConf::V1(_) => ForkResolutionConf::default(),
+            DuRsGlobalConf::V2(ref conf_v2) => conf_v2.fork_resolution,
+        }
+    }
+    /// Requests of blocks to the other nodes
+    pub fn blocks_requests(&self) -> BlocksRequestsConf {
+        match *self {
+            DuRsGlobalConf::V1(_) => BlocksRequestsConf::default(),
+            DuRsGlobalConf::V2(ref conf_v2) => conf_v2.blocks_requests,
+        }
+    }
+    /// Size limits of the pools of pending documents
+    pub fn sandboxes(&self) -> SandboxesConf {
+        match *self {
+            DuRsGlobalConf::V1(_) => SandboxesConf::default(),
+            DuRsGlobalConf::V2(ref conf_v2) => conf_v2.sandboxes,
+        }
+    }
+    /// Background saving of the blockchain databases
+    pub fn autosave(&self) -> AutoSaveConf {
+        match *self {
+            DuRsGlobalConf::V1(_) => AutoSaveConf::default(),
+            DuRsGlobalConf::V2(ref conf_v2) => conf_v2.autosave,
+        }
+    }
+    /// Scheduled compaction of the blockchain database
+    pub fn compaction(&self) -> CompactionConf {
+        match *self {
+            DuRsGlobalConf::V1(_) => CompactionConf::default(),
+            DuRsGlobalConf::V2(ref conf_v2) => conf_v2.compaction,
+        }
+    }
+    /// Protocol experiments activated on the currency
+    pub fn feature_flags(&self) -> FeatureFlags {
+        match *self {
+            DuRsGlobalConf::V1(_) => FeatureFlags::default(),
+            DuRsGlobalConf::V2(ref conf_v2) => conf_v2.feature_flags.clone(),
+        }
+    }
+}
+
 impl DursGlobalConfTrait for DuRsGlobalConf {
     type GlobalUserConf = DuRsGlobalUserConf;
 
@@ -53,4 +119,10 @@ impl DursGlobalConfTrait for DuRsGlobalConf {
             DuRsGlobalConf::V2(ref conf_v2) => conf_v2.default_sync_module.clone(),
         }
     }
+    fn lang(&self) -> Option<String> {
+        match *self {
+            DuRsGlobalConf::V1(_) => None,
+            DuRsGlobalConf::V2(ref conf_v2) => conf_v2.lang.clone(),
+        }
+    }
 }
@@ -15,9 +15,16 @@
 
 //! Dunitrust global configuration V2
 
+use crate::autosave::AutoSaveConf;
+use crate::blocks_requests::BlocksRequestsConf;
+use crate::compaction::CompactionConf;
 use crate::constants;
+use crate::fork_resolution::ForkResolutionConf;
 use crate::resources::ResourcesUsage;
+use crate::sandboxes::SandboxesConf;
+use crate::storage::StorageMode;
 use crate::v1::DuRsConfV1;
+use dubp_currency_params::feature_flags::FeatureFlags;
 use dubp_currency_params::CurrencyName;
 use durs_module::ModuleName;
 use std::collections::HashSet;
@@ -33,10 +40,28 @@ pub struct DuRsGlobalUserConfV2 {
     pub default_sync_module: Option<ModuleName>,
     /// Ressources usage
     pub resources_usage: Option<ResourcesUsage>,
+    /// Blockchain storage mode
+    pub storage_mode: Option<StorageMode>,
+    /// Transactions search index
+    pub tx_search_index: Option<bool>,
+    /// Fork resolution rules
+    pub fork_resolution: Option<ForkResolutionConf>,
+    /// Requests of blocks to the other nodes
+    pub blocks_requests: Option<BlocksRequestsConf>,
+    /// Size limits of the pools of pending documents
+    pub sandboxes: Option<SandboxesConf>,
+    /// Background saving of the blockchain databases
+    pub autosave: Option<AutoSaveConf>,
+    /// Scheduled compaction of the blockchain database
+    pub compaction: Option<CompactionConf>,
+    /// Protocol experiments activated on the currency
+    pub feature_flags: Option<FeatureFlags>,
     /// Disabled modules
     pub disabled: Option<HashSet<ModuleName>>,
     /// Enabled modules
     pub enabled: Option<HashSet<ModuleName>>,
+    /// Language of messages displayed to the user
+    pub lang: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize, PartialEq, Serialize)]
@@ -50,10 +75,38 @@ pub struct DuRsGlobalConfV2 {
     pub default_sync_module: ModuleName,
     /// Ressources usage
     pub resources_usage: ResourcesUsage,
+    /// Blockchain storage mode
+    #[serde(default)]
+    pub storage_mode: StorageMode,
+    /// Index the output conditions and the comment words of the transactions, for search
+    /// (only the transactions applied after enabling it are indexed)
+    #[serde(default)]
+    pub tx_search_index: bool,
+    /// Fork resolution rules
+    #[serde(default)]
+    pub fork_resolution: ForkResolutionConf,
+    /// Requests of blocks to the other nodes
+    #[serde(default)]
+    pub blocks_requests: BlocksRequestsConf,
+    /// Size limits of the pools of pending documents
+    #[serde(default)]
+    pub sandboxes: SandboxesConf,
+    /// Background saving of the blockchain databases
+    #[serde(default)]
+    pub autosave: AutoSaveConf,
+    /// Scheduled compaction of the blockchain database
+    #[serde(default)]
+    pub compaction: CompactionConf,
+    /// Protocol experiments activated on the currency (block height from which each one is active)
+    #[serde(default, skip_serializing_if = "FeatureFlags::is_empty")]
+    pub feature_flags: FeatureFlags,
     /// Disabled modules
     pub disabled: HashSet<ModuleName>,
     /// Enabled modules
     pub enabled: HashSet<ModuleName>,
+    /// Language of messages displayed to the user (system locale if not defined)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lang: Option<String>,
 }
 
 impl Default for DuRsGlobalConfV2 {
@@ -63,8 +116,17 @@ impl Default for DuRsGlobalConfV2 {
             my_node_id: crate::generate_random_node_id(),
             default_sync_module: ModuleName(String::from(constants::DEFAULT_DEFAULT_SYNC_MODULE)),
             resources_usage: ResourcesUsage::default(),
+            storage_mode: StorageMode::default(),
+            tx_search_index: false,
+            fork_resolution: ForkResolutionConf::default(),
+            blocks_requests: BlocksRequestsConf::default(),
+            sandboxes: SandboxesConf::default(),
+            autosave: AutoSaveConf::default(),
+            compaction: CompactionConf::default(),
+            feature_flags: FeatureFlags::default(),
             disabled: HashSet::with_capacity(0),
             enabled: HashSet::with_capacity(0),
+            lang: None,
         }
     }
 }
@@ -76,8 +138,17 @@ impl From<DuRsConfV1> for DuRsGlobalConfV2 {
             my_node_id: conf_v1.my_node_id,
             default_sync_module: ModuleName(String::from(constants::DEFAULT_DEFAULT_SYNC_MODULE)),
             resources_usage: ResourcesUsage::default(),
+            storage_mode: StorageMode::default(),
+            tx_search_index: false,
+            fork_resolution: ForkResolutionConf::default(),
+            blocks_requests: BlocksRequestsConf::default(),
+            sandboxes: SandboxesConf::default(),
+            autosave: AutoSaveConf::default(),
+            compaction: CompactionConf::default(),
+            feature_flags: FeatureFlags::default(),
             disabled: conf_v1.disabled,
             enabled: conf_v1.enabled,
+            lang: None,
         }
     }
 }
@@ -94,8 +165,23 @@ impl DuRsGlobalConfV2 {
             resources_usage: global_user_conf
                 .resources_usage
                 .unwrap_or(self.resources_usage),
+            storage_mode: global_user_conf.storage_mode.unwrap_or(self.storage_mode),
+            tx_search_index: global_user_conf
+                .tx_search_index
+                .unwrap_or(self.tx_search_index),
+            fork_resolution: global_user_conf
+                .fork_resolution
+                .unwrap_or(self.fork_resolution),
+            blocks_requests: global_user_conf
+                .blocks_requests
+                .unwrap_or(self.blocks_requests),
+            sandboxes: global_user_conf.sandboxes.unwrap_or(self.sandboxes),
+            autosave: global_user_conf.autosave.unwrap_or(self.autosave),
+            compaction: global_user_conf.compaction.unwrap_or(self.compaction),
+            feature_flags: global_user_conf.feature_flags.unwrap_or(self.feature_flags),
             disabled: global_user_conf.disabled.unwrap_or(self.disabled),
             enabled: global_user_conf.enabled.unwrap_or(self.enabled),
+            lang: global_user_conf.lang.or(self.lang),
         }
     }
 }
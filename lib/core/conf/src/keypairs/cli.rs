@@ -28,10 +28,42 @@
 )]
 
 use crate::*;
+use durs_module::i18n::{Catalog, I18n};
 #[cfg(test)]
 use mockall::*;
 use std::io;
 
+/// French translations of keypairs cli messages
+pub static FR_CATALOG: Catalog = &[
+    (
+        "Clear your network keypair?",
+        "Réinitialiser votre trousseau réseau ?",
+    ),
+    (
+        "Clear your member keypair?",
+        "Supprimer votre trousseau membre ?",
+    ),
+    (
+        "Generating a new network keypair!",
+        "Génération d'un nouveau trousseau réseau !",
+    ),
+    (
+        "Deleting member keypair!",
+        "Suppression du trousseau membre !",
+    ),
+    ("Network key: {}", "Clé réseau : {}"),
+    ("No member key configured", "Aucune clé membre configurée"),
+    ("Member key: {}", "Clé membre : {}"),
+    (
+        "Modify your network keypair?",
+        "Modifier votre trousseau réseau ?",
+    ),
+    (
+        "Modify your member keypair?",
+        "Modifier votre trousseau membre ?",
+    ),
+];
+
 #[cfg_attr(test, automock)]
 trait UserPasswordInput {
     fn get_password(&self, prompt: &str) -> std::io::Result<String>;
@@ -91,16 +123,21 @@ fn inner_modify_member_keys<T: UserPasswordInput>(
 }
 
 /// Ask user for confirmation and Clear keys command
-pub fn clear_keys(network: bool, member: bool, mut key_pairs: DuniterKeyPairs) -> DuniterKeyPairs {
+pub fn clear_keys(
+    i18n: &I18n,
+    network: bool,
+    member: bool,
+    mut key_pairs: DuniterKeyPairs,
+) -> DuniterKeyPairs {
     if network {
-        if let Ok("y") = question_prompt("Clear your network keypair?", &["y", "n"]) {
-            println!("Generating a new network keypair!");
+        if let Ok("y") = question_prompt(i18n.tr("Clear your network keypair?"), &["y", "n"]) {
+            println!("{}", i18n.tr("Generating a new network keypair!"));
             clear_network_key(&mut key_pairs);
         }
     }
     if member {
-        if let Ok("y") = question_prompt("Clear your member keypair?", &["y", "n"]) {
-            println!("Deleting member keypair!");
+        if let Ok("y") = question_prompt(i18n.tr("Clear your member keypair?"), &["y", "n"]) {
+            println!("{}", i18n.tr("Deleting member keypair!"));
             clear_member_key(&mut key_pairs);
         }
     }
@@ -120,23 +157,26 @@ fn clear_member_key(key_pairs: &mut DuniterKeyPairs) {
 }
 
 /// Show keys command
-pub fn show_keys(key_pairs: DuniterKeyPairs) {
-    show_network_keys(&key_pairs);
-    show_member_keys(&key_pairs);
+pub fn show_keys(i18n: &I18n, key_pairs: DuniterKeyPairs) {
+    show_network_keys(i18n, &key_pairs);
+    show_member_keys(i18n, &key_pairs);
 }
 
 #[inline]
 /// Show network keys
-pub fn show_network_keys(key_pairs: &DuniterKeyPairs) {
-    println!("Network key: {}", key_pairs.network_keypair);
+pub fn show_network_keys(i18n: &I18n, key_pairs: &DuniterKeyPairs) {
+    println!(
+        "{}",
+        i18n.tr_args("Network key: {}", &[&key_pairs.network_keypair])
+    );
 }
 
 #[inline]
 /// Show member keys
-pub fn show_member_keys(key_pairs: &DuniterKeyPairs) {
+pub fn show_member_keys(i18n: &I18n, key_pairs: &DuniterKeyPairs) {
     match &key_pairs.member_keypair {
-        None => println!("No member key configured"),
-        Some(key) => println!("Member key: {}", key),
+        None => println!("{}", i18n.tr("No member key configured")),
+        Some(key) => println!("{}", i18n.tr_args("Member key: {}", &[key])),
     }
 }
 
@@ -192,17 +232,20 @@ fn salt_password_prompt<T: UserPasswordInput>(stdin: T) -> Result<KeyPairEnum, C
 }
 
 /// The wizard key function
-pub fn key_wizard(mut key_pairs: DuniterKeyPairs) -> Result<DuniterKeyPairs, CliError> {
-    let mut answer = question_prompt("Modify your network keypair?", &["y", "n"])?;
+pub fn key_wizard(
+    i18n: &I18n,
+    mut key_pairs: DuniterKeyPairs,
+) -> Result<DuniterKeyPairs, CliError> {
+    let mut answer = question_prompt(i18n.tr("Modify your network keypair?"), &["y", "n"])?;
     if answer == "y" {
         key_pairs.network_keypair = salt_password_prompt(std::io::stdin())?;
     }
 
-    answer = question_prompt("Modify your member keypair?", &["y", "n", "d"])?;
+    answer = question_prompt(i18n.tr("Modify your member keypair?"), &["y", "n", "d"])?;
     if answer == "y" {
         key_pairs.member_keypair = Some(salt_password_prompt(std::io::stdin())?);
     } else if answer == "d" {
-        println!("Deleting member keypair!");
+        println!("{}", i18n.tr("Deleting member keypair!"));
         clear_member_key(&mut key_pairs);
     }
 
@@ -32,18 +32,31 @@
 #[macro_use]
 extern crate serde_derive;
 
+mod autosave;
+mod blocks_requests;
+mod compaction;
 pub mod constants;
+pub mod currencies;
 mod env;
 pub mod errors;
 pub mod file;
+mod fork_resolution;
 mod global_conf;
 pub mod keypairs;
 pub mod modules_conf;
 mod resources;
+mod sandboxes;
+mod storage;
 mod v1;
 
+pub use crate::autosave::AutoSaveConf;
+pub use crate::blocks_requests::BlocksRequestsConf;
+pub use crate::compaction::CompactionConf;
 pub use crate::errors::DursConfError;
+pub use crate::fork_resolution::{ForkBranchPriority, ForkResolutionConf};
 pub use crate::keypairs::DuniterKeyPairs;
+pub use crate::sandboxes::SandboxesConf;
+pub use crate::storage::StorageMode;
 
 use crate::constants::MODULES_DATAS_FOLDER;
 use crate::global_conf::v2::DuRsGlobalConfV2;
@@ -256,6 +269,16 @@ pub fn get_datas_path(profile_path: PathBuf) -> PathBuf {
 /// Returns the path to the folder containing the user data of the running profile
 // Warning: This function cannot use the macro fatal_error! because the logger is not yet initialized, so it must use panic !
 pub fn get_profile_path(profiles_path: &Option<PathBuf>, profile_name: &str) -> PathBuf {
+    let mut profile_path = get_profiles_path(profiles_path);
+    profile_path.push(profile_name);
+    if !profile_path.as_path().exists() {
+        fs::create_dir(profile_path.as_path()).expect("Impossible to create your profile dir !");
+    }
+    profile_path
+}
+
+/// Returns the path to the folder containing all the user profiles
+pub fn get_profiles_path(profiles_path: &Option<PathBuf>) -> PathBuf {
     // Define and create datas directory if not exist
     let profiles_path: PathBuf = if let Some(profiles_path) = profiles_path {
         profiles_path.clone()
@@ -275,12 +298,7 @@ pub fn get_profile_path(profiles_path: &Option<PathBuf>, profile_name: &str) ->
             )
         });
     }
-    let mut profile_path = profiles_path;
-    profile_path.push(profile_name);
-    if !profile_path.as_path().exists() {
-        fs::create_dir(profile_path.as_path()).expect("Impossible to create your profile dir !");
-    }
-    profile_path
+    profiles_path
 }
 
 /// Load configuration.
@@ -16,6 +16,7 @@
 //! Dunitrust modules configuration
 
 use crate::constants;
+use crate::errors::DursConfError;
 use crate::keypairs::DuniterKeyPairs;
 use crate::DuRsConf;
 use dubp_currency_params::CurrencyName;
@@ -24,6 +25,7 @@ use durs_message::DursMsg;
 use durs_module::{
     DursConfTrait, DursModule, ModuleConfError, ModuleName, ModuleStaticName, RequiredKeysContent,
 };
+use std::path::PathBuf;
 
 /// Module configurations and required keys
 pub type ModuleConfsAndKeys<M> = (
@@ -111,6 +113,27 @@ pub fn get_module_conf_and_keys<M: DursModule<DuRsConf, DursMsg>>(
     ))
 }
 
+/// Reload the conf of a module from the configuration file of the profile,
+/// to take into account the changes made while the node is running
+pub fn reload_module_conf<M: DursModule<DuRsConf, DursMsg>>(
+    profile_path: PathBuf,
+) -> Result<M::ModuleConf, DursConfError> {
+    let conf = crate::file::load_conf_from_file(profile_path).map_err(DursConfError::FileErr)?;
+    let conf = conf.override_global_conf(
+        crate::env::load_env_global_user_conf().map_err(DursConfError::EnvVarErr)?,
+    );
+    let module_conf_json = conf.modules().get(&M::name().to_string().as_str()).cloned();
+
+    let (module_conf, _) = ModulesConf::get_module_conf::<M>(
+        Some(&conf.get_currency()),
+        &conf.get_global_conf(),
+        module_conf_json,
+    )
+    .map_err(DursConfError::ModuleConfErr)?;
+
+    Ok(module_conf)
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -0,0 +1,52 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Dunitrust configuration of the blocks requested to the other nodes
+//! Dunitrust configuration of the sandboxes (pools of pending documents)
+
+#[derive(Debug, Copy, Clone, Deserialize, PartialEq, Eq, Serialize)]
+/// Size limits of the pools of pending documents, protecting the node from mempool flooding.
+/// When a pool is full, its least recently submitted document is evicted to make room.
+#[serde(default)]
+pub struct SandboxesConf {
+    /// Maximum number of pending transactions
+    pub max_txs: usize,
+    /// Maximum number of pending transactions per issuer
+    pub max_txs_per_issuer: usize,
+    /// Maximum number of pending identities (a public key can have only one pending identity)
+    pub max_identities: usize,
+    /// Maximum number of pending memberships
+    pub max_memberships: usize,
+    /// Maximum number of pending memberships per issuer
+    pub max_memberships_per_issuer: usize,
+    /// Maximum number of pending certifications
+    pub max_certifications: usize,
+    /// Maximum number of pending certifications per issuer
+    pub max_certifications_per_issuer: usize,
+}
+
+impl Default for SandboxesConf {
+    fn default() -> Self {
+        SandboxesConf {
+            max_txs: 5_000,
+            max_txs_per_issuer: 50,
+            max_identities: 1_000,
+            max_memberships: 1_000,
+            max_memberships_per_issuer: 2,
+            max_certifications: 5_000,
+            max_certifications_per_issuer: 100,
+        }
+    }
+}
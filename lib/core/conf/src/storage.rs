@@ -0,0 +1,33 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Dunitrust storage mode configuration
+
+#[derive(Debug, Copy, Clone, Deserialize, PartialEq, Serialize)]
+/// Blockchain storage mode
+#[serde(rename_all = "lowercase")]
+pub enum StorageMode {
+    /// Store all blocks with their transactions
+    Full,
+    /// Store only block headers and the indexes needed for validation,
+    /// transactions are dropped beyond the transactions window
+    Light,
+}
+
+impl Default for StorageMode {
+    fn default() -> Self {
+        StorageMode::Full
+    }
+}
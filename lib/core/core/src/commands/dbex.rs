@@ -15,12 +15,24 @@
 
 //! Durs-core cli : dbex subcommands.
 
-use crate::commands::DursExecutableCoreCommand;
+use crate::commands::{open_bc_db, DursExecutableCoreCommand};
 use crate::dbex;
 use crate::errors::DursCoreError;
+use crate::profile_lock::{format_timestamp, ProfileLock};
 use crate::DursCore;
+use dubp_common_doc::Blockstamp;
+use dubp_user_docs::amount::Separators;
+use dup_crypto::keys::PubKey;
 use durs_bc::dbex::{DbExBcQuery, DbExQuery, DbExTxQuery, DbExWotQuery};
+use durs_bc::reindex::ReindexedStore;
+use durs_bc::reserve_proof::ReserveStatement;
+use durs_bc::BlockchainModule;
 use durs_conf::DuRsConf;
+use durs_module::i18n::Locale;
+use durs_module::metrics_history::{self, MetricsHistory};
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
 
 #[derive(StructOpt, Debug, Clone)]
 #[structopt(name = "dbex", setting(structopt::clap::AppSettings::ColoredHelp))]
@@ -37,24 +49,105 @@ pub struct DbExOpt {
 #[derive(StructOpt, Debug, Clone)]
 /// dbex subcommands
 pub enum DbExSubCommand {
+    /// Back up the blockchain database (the node can be running)
+    #[structopt(name = "backup", setting(structopt::clap::AppSettings::ColoredHelp))]
+    BackupOpt(BackupOpt),
     /// Pubkeys’ balances explorer
     #[structopt(name = "balance", setting(structopt::clap::AppSettings::ColoredHelp))]
     BalanceOpt(BalanceOpt),
     /// Display blocks current frame
     #[structopt(name = "blocks", setting(structopt::clap::AppSettings::ColoredHelp))]
     BlocksOpt(BlocksOpt),
+    /// Audit the consistency of the blockchain database (balances, monetary mass and wot index)
+    #[structopt(name = "check", setting(structopt::clap::AppSettings::ColoredHelp))]
+    CheckOpt(CheckOpt),
+    /// Compact the blockchain database (the node must be stopped)
+    #[structopt(name = "compact", setting(structopt::clap::AppSettings::ColoredHelp))]
+    CompactOpt(CompactOpt),
     /// Web of Trust distances explorer
     #[structopt(name = "distance", setting(structopt::clap::AppSettings::ColoredHelp))]
     DistanceOpt(DistanceOpt),
+    /// Export the distance rule test vector of the current block (JSON format shared with duniter-ts)
+    #[structopt(
+        name = "distance-vector",
+        setting(structopt::clap::AppSettings::ColoredHelp)
+    )]
+    DistanceVectorOpt(DistanceVectorOpt),
+    /// Export a checksummed snapshot of the chain state (current indexes and last blocks)
+    #[structopt(
+        name = "export-snapshot",
+        setting(structopt::clap::AppSettings::ColoredHelp)
+    )]
+    ExportSnapshotOpt(ExportSnapshotOpt),
     /// Forks tree explorer
     #[structopt(name = "forks", setting(structopt::clap::AppSettings::ColoredHelp))]
     ForksOpt(ForksOpt),
+    /// Bootstrap an empty local blockchain from a snapshot of the chain state
+    #[structopt(
+        name = "import-snapshot",
+        setting(structopt::clap::AppSettings::ColoredHelp)
+    )]
+    ImportSnapshotOpt(ImportSnapshotOpt),
     /// Member explorer
     #[structopt(name = "member", setting(structopt::clap::AppSettings::ColoredHelp))]
     MemberOpt(MemberOpt),
+    /// Expiry calendar of the membership and the certifications of a member
+    #[structopt(
+        name = "member-expirations",
+        setting(structopt::clap::AppSettings::ColoredHelp)
+    )]
+    MemberExpirationsOpt(MemberOpt),
     /// Members explorer
     #[structopt(name = "members")]
     MembersOpt(MembersOpt),
+    /// Recent history of the key metrics of the node
+    #[structopt(name = "metrics", setting(structopt::clap::AppSettings::ColoredHelp))]
+    MetricsOpt(MetricsOpt),
+    /// Migrate the blockchain database to the current version of its schema (the node must be stopped)
+    #[structopt(name = "migrate", setting(structopt::clap::AppSettings::ColoredHelp))]
+    MigrateOpt(MigrateOpt),
+    /// Rebuild a single derived index from the main blocks (the node must be stopped)
+    #[structopt(name = "reindex", setting(structopt::clap::AppSettings::ColoredHelp))]
+    ReindexOpt(ReindexOpt),
+    /// Export the statement of the balances of public keys at the current block, signed with the member key
+    #[structopt(
+        name = "reserve-proof",
+        setting(structopt::clap::AppSettings::ColoredHelp)
+    )]
+    ReserveProofOpt(ReserveProofOpt),
+    /// Replace the blockchain database by a backup (the node must be stopped)
+    #[structopt(name = "restore", setting(structopt::clap::AppSettings::ColoredHelp))]
+    RestoreOpt(RestoreOpt),
+    /// Size of the blockchain database (the node can be running)
+    #[structopt(name = "stat", setting(structopt::clap::AppSettings::ColoredHelp))]
+    StatOpt(StatOpt),
+    /// Current universal dividend and monetary mass
+    #[structopt(name = "ud", setting(structopt::clap::AppSettings::ColoredHelp))]
+    UdOpt(UdOpt),
+    /// Universal dividends created for a member
+    #[structopt(
+        name = "ud-history",
+        setting(structopt::clap::AppSettings::ColoredHelp)
+    )]
+    UdHistoryOpt(UdHistoryOpt),
+    /// Verify a statement of balances against the local blockchain
+    #[structopt(
+        name = "verify-reserve-proof",
+        setting(structopt::clap::AppSettings::ColoredHelp)
+    )]
+    VerifyReserveProofOpt(VerifyReserveProofOpt),
+}
+
+#[derive(StructOpt, Debug, Copy, Clone)]
+/// CheckOpt
+pub struct CheckOpt {}
+
+#[derive(StructOpt, Debug, Copy, Clone)]
+/// CompactOpt
+pub struct CompactOpt {
+    /// Remove the lock of the profile left by a node that did not stop properly
+    #[structopt(long = "force-unlock")]
+    pub force_unlock: bool,
 }
 
 #[derive(StructOpt, Debug, Copy, Clone)]
@@ -65,10 +158,37 @@ pub struct DistanceOpt {
     pub reverse: bool,
 }
 
+#[derive(StructOpt, Debug, Clone)]
+/// DistanceVectorOpt
+pub struct DistanceVectorOpt {
+    /// Output file (to export the vector of a given block, sync with `--end` before)
+    #[structopt(parse(from_os_str))]
+    pub output: PathBuf,
+}
+
+#[derive(StructOpt, Debug, Clone)]
+/// ExportSnapshotOpt
+pub struct ExportSnapshotOpt {
+    /// Snapshot file
+    #[structopt(parse(from_os_str))]
+    pub file: PathBuf,
+}
+
 #[derive(StructOpt, Debug, Copy, Clone)]
 /// ForksOpt
 pub struct ForksOpt {}
 
+#[derive(StructOpt, Debug, Clone)]
+/// ImportSnapshotOpt
+pub struct ImportSnapshotOpt {
+    /// Snapshot file
+    #[structopt(parse(from_os_str))]
+    pub file: PathBuf,
+    /// Remove the lock of the profile left by a node that did not stop properly
+    #[structopt(long = "force-unlock")]
+    pub force_unlock: bool,
+}
+
 #[derive(StructOpt, Debug, Copy, Clone)]
 /// MembersOpt
 pub struct MembersOpt {
@@ -87,6 +207,14 @@ pub struct MemberOpt {
     pub uid: String,
 }
 
+#[derive(StructOpt, Debug, Clone)]
+/// BackupOpt
+pub struct BackupOpt {
+    /// Backup folder
+    #[structopt(parse(from_os_str))]
+    pub folder: PathBuf,
+}
+
 #[derive(StructOpt, Debug, Clone)]
 /// BalanceOpt
 pub struct BalanceOpt {
@@ -98,29 +226,185 @@ pub struct BalanceOpt {
 /// BlocksOpt
 pub struct BlocksOpt {}
 
+#[derive(StructOpt, Debug, Clone)]
+/// MetricsOpt
+pub struct MetricsOpt {
+    /// Show the values recorded since this duration (e.g. 30m, 24h, 7d)
+    #[structopt(long = "since", default_value = "24h")]
+    pub since: String,
+    /// Show only this metric
+    pub metric: Option<String>,
+}
+
+#[derive(StructOpt, Debug, Copy, Clone)]
+/// MigrateOpt
+pub struct MigrateOpt {
+    /// Only display the migrations to apply
+    #[structopt(long = "dry-run")]
+    pub dry_run: bool,
+    /// Remove the lock of the profile left by a node that did not stop properly
+    #[structopt(long = "force-unlock")]
+    pub force_unlock: bool,
+}
+
+#[derive(StructOpt, Debug, Copy, Clone)]
+/// ReindexOpt
+pub struct ReindexOpt {
+    /// Index to rebuild: balances, certs or ms
+    #[structopt(long = "store")]
+    pub store: ReindexedStore,
+    /// Remove the lock of the profile left by a node that did not stop properly
+    #[structopt(long = "force-unlock")]
+    pub force_unlock: bool,
+}
+
+#[derive(StructOpt, Debug, Clone)]
+/// ReserveProofOpt
+pub struct ReserveProofOpt {
+    /// Public keys whose balances are stated
+    #[structopt(required = true)]
+    pub pubkeys: Vec<String>,
+    /// Expected current block (the statement is refused if the local blockchain is not at this block)
+    #[structopt(short = "b", long = "blockstamp")]
+    pub blockstamp: Option<String>,
+    /// Output file (standard output by default)
+    #[structopt(short = "o", long = "output", parse(from_os_str))]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(StructOpt, Debug, Clone)]
+/// RestoreOpt
+pub struct RestoreOpt {
+    /// Backup folder
+    #[structopt(parse(from_os_str))]
+    pub folder: PathBuf,
+    /// Remove the lock of the profile left by a node that did not stop properly
+    #[structopt(long = "force-unlock")]
+    pub force_unlock: bool,
+}
+
+#[derive(StructOpt, Debug, Copy, Clone)]
+/// StatOpt
+pub struct StatOpt {
+    /// Count the entries of each store (iterate over all the database)
+    #[structopt(long = "storage")]
+    pub storage: bool,
+}
+
+#[derive(StructOpt, Debug, Copy, Clone)]
+/// UdOpt
+pub struct UdOpt {
+    #[structopt(short = "r", long = "relative")]
+    /// show monetary mass in universal dividends
+    pub relative: bool,
+}
+
+#[derive(StructOpt, Debug, Clone)]
+/// UdHistoryOpt
+pub struct UdHistoryOpt {
+    /// public key or uid
+    pub member: String,
+}
+
+#[derive(StructOpt, Debug, Clone)]
+/// VerifyReserveProofOpt
+pub struct VerifyReserveProofOpt {
+    /// Statement file
+    #[structopt(parse(from_os_str))]
+    pub file: PathBuf,
+}
+
 impl DursExecutableCoreCommand for DbExOpt {
     fn execute(self, durs_core: DursCore<DuRsConf>) -> Result<(), DursCoreError> {
+        let separators = match durs_core.soft_meta_datas.i18n.locale() {
+            Locale::Fr => Separators::FRENCH,
+            Locale::En => Separators::ENGLISH,
+        };
         let profile_path = durs_core.soft_meta_datas.profile_path;
 
         match self.subcommand {
+            DbExSubCommand::BackupOpt(backup_opts) => {
+                let bc_db = open_bc_db(&profile_path)?;
+                let summary = BlockchainModule::backup_db(&bc_db, &backup_opts.folder)
+                    .map_err(DursCoreError::FailBackupDb)?;
+                println!("{}", summary);
+            }
             DbExSubCommand::BalanceOpt(balance_opts) => dbex(
                 profile_path,
                 self.csv,
-                &DbExQuery::TxQuery(DbExTxQuery::Balance(balance_opts.address)),
+                &DbExQuery::TxQuery(DbExTxQuery::Balance {
+                    address: balance_opts.address.into(),
+                    separators,
+                }),
             ),
+            DbExSubCommand::CheckOpt(_check_opts) => {
+                let bc_db = open_bc_db(&profile_path)?;
+                if let Some(report) =
+                    BlockchainModule::audit_db(&bc_db).map_err(DursCoreError::FailAuditDb)?
+                {
+                    for divergence in &report.divergences {
+                        println!("{}", divergence);
+                    }
+                    println!("{}", report);
+                    if !report.divergences.is_empty() {
+                        return Err(DursCoreError::DbDivergences(report.divergences.len()));
+                    }
+                } else {
+                    println!("{}", durs_bc::dbex::EMPTY_BLOCKCHAIN);
+                }
+            }
+            DbExSubCommand::CompactOpt(compact_opts) => {
+                let _profile_lock = ProfileLock::acquire(&profile_path, compact_opts.force_unlock)?;
+                let summary = crate::compaction::compact(&profile_path)?;
+                println!("{}", summary);
+            }
             DbExSubCommand::DistanceOpt(distance_opts) => dbex(
                 profile_path,
                 self.csv,
                 &DbExQuery::WotQuery(DbExWotQuery::AllDistances(distance_opts.reverse)),
             ),
+            DbExSubCommand::DistanceVectorOpt(distance_vector_opts) => dbex(
+                profile_path,
+                self.csv,
+                &DbExQuery::WotQuery(DbExWotQuery::ExportDistanceVector(
+                    distance_vector_opts.output,
+                )),
+            ),
+            DbExSubCommand::ExportSnapshotOpt(export_snapshot_opts) => {
+                let bc_db = open_bc_db(&profile_path)?;
+                let summary = BlockchainModule::export_snapshot(
+                    &bc_db,
+                    profile_path,
+                    &export_snapshot_opts.file,
+                )
+                .map_err(DursCoreError::FailExportSnapshot)?;
+                println!("{}", summary);
+            }
             DbExSubCommand::ForksOpt(_forks_opts) => {
                 dbex(profile_path, self.csv, &DbExQuery::ForkTreeQuery)
             }
+            DbExSubCommand::ImportSnapshotOpt(import_snapshot_opts) => {
+                let _profile_lock =
+                    ProfileLock::acquire(&profile_path, import_snapshot_opts.force_unlock)?;
+                let bc_db = open_bc_db(&profile_path)?;
+                let summary = BlockchainModule::import_snapshot(
+                    &bc_db,
+                    profile_path,
+                    &import_snapshot_opts.file,
+                )
+                .map_err(DursCoreError::FailImportSnapshot)?;
+                println!("{}", summary);
+            }
             DbExSubCommand::MemberOpt(member_opts) => dbex(
                 profile_path,
                 self.csv,
                 &DbExQuery::WotQuery(DbExWotQuery::MemberDatas(member_opts.uid.into())),
             ),
+            DbExSubCommand::MemberExpirationsOpt(member_opts) => dbex(
+                profile_path,
+                self.csv,
+                &DbExQuery::WotQuery(DbExWotQuery::MemberExpirations(member_opts.uid.into())),
+            ),
             DbExSubCommand::MembersOpt(members_opts) => {
                 if members_opts.expire {
                     dbex(
@@ -141,6 +425,151 @@ impl DursExecutableCoreCommand for DbExOpt {
                 self.csv,
                 &DbExQuery::BcQuery(DbExBcQuery::CountBlocksPerIssuer),
             ),
+            DbExSubCommand::MetricsOpt(metrics_opts) => {
+                let since = metrics_history::parse_duration(&metrics_opts.since)
+                    .ok_or(DursCoreError::InvalidDuration)?;
+                let since = durs_common_tools::fns::time::current_timestamp().saturating_sub(since);
+                let metrics_history = MetricsHistory::new(&durs_conf::get_datas_path(profile_path));
+                let metrics = if let Some(metric) = metrics_opts.metric {
+                    vec![metric]
+                } else {
+                    metrics_history
+                        .metrics()
+                        .map_err(DursCoreError::FailReadMetricsHistory)?
+                };
+                if self.csv {
+                    println!("METRIC,TIME,VALUE");
+                }
+                for metric in metrics {
+                    let values = metrics_history
+                        .read(&metric, since)
+                        .map_err(DursCoreError::FailReadMetricsHistory)?;
+                    if self.csv {
+                        for (time, value) in values {
+                            println!("{},{},{}", metric, time, value);
+                        }
+                    } else {
+                        println!("{} ({} values):", metric, values.len());
+                        for (time, value) in values {
+                            println!("  {}  {}", format_timestamp(time as i64), value);
+                        }
+                    }
+                }
+            }
+            DbExSubCommand::MigrateOpt(migrate_opts) => {
+                let _profile_lock = ProfileLock::acquire(&profile_path, migrate_opts.force_unlock)?;
+                let mut bc_db = open_bc_db(&profile_path)?;
+                let migrations =
+                    BlockchainModule::migrate_db(&mut bc_db, profile_path, migrate_opts.dry_run)
+                        .map_err(DursCoreError::FailMigrateBcDb)?;
+                if migrations.is_empty() {
+                    println!("The blockchain database is up to date.");
+                } else {
+                    if migrate_opts.dry_run {
+                        println!("Migrations to apply:");
+                    } else {
+                        println!("Applied migrations:");
+                    }
+                    for migration in migrations {
+                        println!("  {}", migration);
+                    }
+                }
+            }
+            DbExSubCommand::ReindexOpt(reindex_opts) => {
+                let _profile_lock = ProfileLock::acquire(&profile_path, reindex_opts.force_unlock)?;
+                let bc_db = open_bc_db(&profile_path)?;
+                let summary = BlockchainModule::reindex(&bc_db, reindex_opts.store)
+                    .map_err(DursCoreError::FailReindex)?;
+                println!("{}", summary);
+            }
+            DbExSubCommand::ReserveProofOpt(reserve_proof_opts) => {
+                let member_keypair = durs_core
+                    .keypairs
+                    .member_keypair
+                    .ok_or(DursCoreError::MissingMemberKeypair)?;
+                let pubkeys = reserve_proof_opts
+                    .pubkeys
+                    .iter()
+                    .map(|pubkey| PubKey::from_str(pubkey))
+                    .collect::<Result<Vec<PubKey>, _>>()
+                    .map_err(|_| DursCoreError::InvalidPubkey)?;
+                let blockstamp = reserve_proof_opts
+                    .blockstamp
+                    .map(|blockstamp| Blockstamp::from_string(&blockstamp))
+                    .transpose()
+                    .map_err(|_| DursCoreError::InvalidBlockstamp)?;
+                let bc_db = open_bc_db(&profile_path)?;
+                let statement = BlockchainModule::create_reserve_statement(
+                    &bc_db,
+                    &member_keypair,
+                    &pubkeys,
+                    blockstamp,
+                )
+                .map_err(DursCoreError::FailReserveProof)?;
+                if let Some(output) = reserve_proof_opts.output {
+                    fs::write(output, statement.to_string())
+                        .map_err(DursCoreError::FailWriteReserveProof)?;
+                } else {
+                    print!("{}", statement);
+                }
+            }
+            DbExSubCommand::RestoreOpt(restore_opts) => {
+                let _profile_lock = ProfileLock::acquire(&profile_path, restore_opts.force_unlock)?;
+                let summary = BlockchainModule::restore_db(profile_path, &restore_opts.folder)
+                    .map_err(DursCoreError::FailRestoreDb)?;
+                println!("{}", summary);
+            }
+            DbExSubCommand::StatOpt(stat_opts) => {
+                let bc_db = open_bc_db(&profile_path)?;
+                let stats = bc_db.stats().map_err(DursCoreError::FailReadBcDbStats)?;
+                println!("Blockchain database: {}", stats);
+                if stats.free_pages > 0 {
+                    println!(
+                        "{} MiB can be reclaimed with `dbex compact` (the node must be stopped).",
+                        stats.free_pages * stats.page_size / (1024 * 1024)
+                    );
+                }
+                if stat_opts.storage {
+                    let entries_count = bc_db
+                        .entries_count()
+                        .map_err(DursCoreError::FailReadBcDbStats)?;
+                    for (store_name, count) in entries_count {
+                        println!("  {:<8} {:>12} entries", store_name, count);
+                    }
+                }
+            }
+            DbExSubCommand::UdOpt(ud_opts) => dbex(
+                profile_path,
+                self.csv,
+                &DbExQuery::BcQuery(DbExBcQuery::CurrentUd {
+                    relative: ud_opts.relative,
+                    separators,
+                }),
+            ),
+            DbExSubCommand::UdHistoryOpt(ud_history_opts) => dbex(
+                profile_path,
+                self.csv,
+                &DbExQuery::TxQuery(DbExTxQuery::UdHistory {
+                    member: ud_history_opts.member.into(),
+                    separators,
+                }),
+            ),
+            DbExSubCommand::VerifyReserveProofOpt(verify_reserve_proof_opts) => {
+                let statement = ReserveStatement::from_str(
+                    &fs::read_to_string(verify_reserve_proof_opts.file)
+                        .map_err(DursCoreError::FailReadReserveProof)?,
+                )
+                .map_err(DursCoreError::FailReserveProof)?;
+                let bc_db = open_bc_db(&profile_path)?;
+                BlockchainModule::verify_reserve_statement(&bc_db, &statement)
+                    .map_err(DursCoreError::FailReserveProof)?;
+                println!(
+                    "Valid statement of {} balances at block {}, signed by {}.",
+                    statement.balances.len(),
+                    statement.blockstamp,
+                    statement.issuer
+                );
+            }
         }
 
         Ok(())
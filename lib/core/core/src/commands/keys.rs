@@ -133,14 +133,15 @@ impl DursExecutableCoreCommand for KeysOpt {
         let profile_path = durs_core.soft_meta_datas.profile_path;
         let keypairs_file = durs_core.options.keypairs_file;
         let keypairs = durs_core.keypairs;
+        let i18n = &durs_core.soft_meta_datas.i18n;
 
         match self.subcommand {
             KeysSubCommand::Wizard(_) => {
-                let new_keypairs = key_wizard(keypairs)?;
+                let new_keypairs = key_wizard(i18n, keypairs)?;
                 save_keypairs(profile_path, &keypairs_file, &new_keypairs)
                     .map_err(DursCoreError::FailWriteKeypairsFile)
                     .and_then(|_| {
-                        show_keys(new_keypairs);
+                        show_keys(i18n, new_keypairs);
                         Ok(())
                     })
             }
@@ -150,7 +151,7 @@ impl DursExecutableCoreCommand for KeysOpt {
                     save_keypairs(profile_path, &keypairs_file, &new_keypairs)
                         .map_err(DursCoreError::FailWriteKeypairsFile)
                         .and_then(|_| {
-                            show_network_keys(&new_keypairs);
+                            show_network_keys(i18n, &new_keypairs);
                             Ok(())
                         })
                 }
@@ -159,13 +160,14 @@ impl DursExecutableCoreCommand for KeysOpt {
                     save_keypairs(profile_path, &keypairs_file, &new_keypairs)
                         .map_err(DursCoreError::FailWriteKeypairsFile)
                         .and_then(|_| {
-                            show_member_keys(&new_keypairs);
+                            show_member_keys(i18n, &new_keypairs);
                             Ok(())
                         })
                 }
             },
             KeysSubCommand::Clear(clear_opt) => {
                 let new_keypairs = clear_keys(
+                    i18n,
                     clear_opt.key.is_network(),
                     clear_opt.key.is_member(),
                     keypairs,
@@ -173,12 +175,12 @@ impl DursExecutableCoreCommand for KeysOpt {
                 save_keypairs(profile_path, &keypairs_file, &new_keypairs)
                     .map_err(DursCoreError::FailWriteKeypairsFile)
                     .and_then(|_| {
-                        show_keys(new_keypairs);
+                        show_keys(i18n, new_keypairs);
                         Ok(())
                     })
             }
             KeysSubCommand::Show(_) => {
-                show_keys(keypairs);
+                show_keys(i18n, keypairs);
                 Ok(())
             }
         }
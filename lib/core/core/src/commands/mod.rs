@@ -18,6 +18,8 @@
 pub mod dbex;
 pub mod keys;
 pub mod modules;
+pub mod network;
+pub mod profiles;
 pub mod reset;
 pub mod start;
 
@@ -25,15 +27,18 @@ use crate::constants::DEFAULT_USER_PROFILE;
 use crate::errors::DursCoreError;
 use crate::DursCore;
 pub use dbex::*;
+use durs_bc::BlockchainModule;
 use durs_conf::DuRsConf;
 use durs_dbs_tools::kv_db_old::KvFileDbHandler;
 pub use durs_network::cli::sync::SyncOpt;
 pub use keys::KeysOpt;
 use log::Level;
 pub use modules::*;
+pub use network::NetworkOpt;
+pub use profiles::ProfilesOpt;
 pub use reset::*;
 pub use start::*;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Dunitrust core options
 pub struct DursCoreOptions {
@@ -91,15 +96,28 @@ pub enum DursCommandEnum<T: ExecutableModuleCommand> {
     Other(T),
 }
 
-impl<T: ExecutableModuleCommand> DursCommand<T> {
-    fn open_bc_db(&self, profile_path: &PathBuf) -> Result<KvFileDbHandler, DursCoreError> {
-        let bc_db_path = durs_conf::get_blockchain_db_path(profile_path.clone());
-        durs_dbs_tools::kv_db_old::KvFileDbHandler::open_db(
-            bc_db_path.as_path(),
-            &durs_bc_db_reader::bc_db_schema(),
-        )
+/// Open the blockchain database of the profile
+pub(crate) fn open_bc_db(profile_path: &Path) -> Result<KvFileDbHandler, DursCoreError> {
+    let bc_db_path = durs_conf::get_blockchain_db_path(profile_path.to_path_buf());
+    KvFileDbHandler::open_db(bc_db_path.as_path(), &durs_bc_db_reader::bc_db_schema())
         .map_err(DursCoreError::FailOpenBcDb)
+}
+
+/// Migrate the blockchain database of the profile to the current version of its schema
+/// (the profile must be locked)
+pub(crate) fn migrate_bc_db(
+    bc_db: &mut KvFileDbHandler,
+    profile_path: &Path,
+) -> Result<(), DursCoreError> {
+    for migration in BlockchainModule::migrate_db(bc_db, profile_path.to_path_buf(), false)
+        .map_err(DursCoreError::FailMigrateBcDb)?
+    {
+        info!("Blockchain database migrated: {}", migration);
     }
+    Ok(())
+}
+
+impl<T: ExecutableModuleCommand> DursCommand<T> {
     /// Execute Dunitrust command
     pub fn execute<PlugFunc>(
         self,
@@ -111,11 +129,9 @@ impl<T: ExecutableModuleCommand> DursCommand<T> {
         PlugFunc: FnMut(&mut DursCore<DuRsConf>) -> Result<(), DursCoreError>,
     {
         let profile_path = self.options.define_profile_path();
-        let bc_db = self.open_bc_db(&profile_path)?;
 
         match self.command {
             DursCommandEnum::Core(core_cmd) => DursCore::execute_core_command(
-                bc_db,
                 core_cmd,
                 self.options,
                 plug_modules,
@@ -147,6 +163,10 @@ pub enum DursCoreCommand {
     DbExOpt(DbExOpt),
     /// Keys operations
     KeysOpt(KeysOpt),
+    /// Network informations
+    NetworkOpt(NetworkOpt),
+    /// Profiles operations
+    ProfilesOpt(ProfilesOpt),
 }
 
 /// InvalidInput
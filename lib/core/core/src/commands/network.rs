@@ -0,0 +1,100 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Durs-core cli : network subcommands.
+
+use super::InvalidInput;
+use crate::commands::DursExecutableCoreCommand;
+use crate::errors::DursCoreError;
+use crate::DursCore;
+use durs_conf::DuRsConf;
+use durs_network::map::{NetworkMap, NETWORK_MAP_FILENAME};
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+#[derive(StructOpt, Debug, Clone)]
+#[structopt(name = "network", setting(structopt::clap::AppSettings::ColoredHelp))]
+/// Network informations
+pub struct NetworkOpt {
+    #[structopt(subcommand)]
+    /// NetworkSubCommand
+    pub subcommand: NetworkSubCommand,
+}
+
+#[derive(StructOpt, Debug, Clone)]
+/// Network subcommands
+pub enum NetworkSubCommand {
+    /// Export known network topology
+    #[structopt(name = "map", setting(structopt::clap::AppSettings::ColoredHelp))]
+    Map(NetworkMapOpt),
+}
+
+#[derive(Debug, Copy, Clone)]
+/// Network map export format
+pub enum NetworkMapFormat {
+    /// JSON
+    Json,
+    /// Graphviz DOT
+    Dot,
+}
+
+impl FromStr for NetworkMapFormat {
+    type Err = InvalidInput;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        match source {
+            "json" => Ok(NetworkMapFormat::Json),
+            "dot" => Ok(NetworkMapFormat::Dot),
+            _ => Err(InvalidInput("Network map format: json, dot.")),
+        }
+    }
+}
+
+#[derive(StructOpt, Debug, Clone)]
+/// Export known network topology
+pub struct NetworkMapOpt {
+    /// Export format: json, dot
+    #[structopt(short = "f", long = "format", default_value = "json")]
+    pub format: NetworkMapFormat,
+    /// Output file (standard output by default)
+    #[structopt(short = "o", long = "output", parse(from_os_str))]
+    pub output: Option<PathBuf>,
+}
+
+impl DursExecutableCoreCommand for NetworkOpt {
+    fn execute(self, durs_core: DursCore<DuRsConf>) -> Result<(), DursCoreError> {
+        match self.subcommand {
+            NetworkSubCommand::Map(opts) => {
+                let map_file_path =
+                    durs_conf::get_datas_path(durs_core.soft_meta_datas.profile_path)
+                        .join(NETWORK_MAP_FILENAME);
+                let network_map =
+                    NetworkMap::load(&map_file_path).map_err(DursCoreError::FailReadNetworkMap)?;
+                let export = match opts.format {
+                    NetworkMapFormat::Json => serde_json::to_string_pretty(&network_map)
+                        .expect("Fail to serialize network map !"),
+                    NetworkMapFormat::Dot => network_map.to_dot(),
+                };
+                if let Some(output) = opts.output {
+                    fs::write(output, export).map_err(DursCoreError::FailWriteNetworkMap)
+                } else {
+                    println!("{}", export);
+                    Ok(())
+                }
+            }
+        }
+    }
+}
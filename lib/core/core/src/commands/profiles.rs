@@ -0,0 +1,139 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Durs-core cli : profiles subcommands.
+
+use crate::commands::DursExecutableCoreCommand;
+use crate::errors::DursCoreError;
+use crate::profile_lock;
+use crate::DursCore;
+use durs_conf::DuRsConf;
+use std::fs;
+use std::path::Path;
+
+#[derive(StructOpt, Debug, Copy, Clone)]
+#[structopt(name = "profiles", setting(structopt::clap::AppSettings::ColoredHelp))]
+/// Profiles operations
+pub struct ProfilesOpt {
+    #[structopt(subcommand)]
+    /// ProfilesSubCommand
+    pub subcommand: ProfilesSubCommand,
+}
+
+#[derive(StructOpt, Debug, Copy, Clone)]
+/// Profiles subcommands
+pub enum ProfilesSubCommand {
+    /// List local profiles
+    #[structopt(name = "list", setting(structopt::clap::AppSettings::ColoredHelp))]
+    List(ListProfilesOpt),
+}
+
+#[derive(StructOpt, Debug, Copy, Clone)]
+/// List local profiles
+pub struct ListProfilesOpt {}
+
+impl DursExecutableCoreCommand for ProfilesOpt {
+    fn execute(self, durs_core: DursCore<DuRsConf>) -> Result<(), DursCoreError> {
+        match self.subcommand {
+            ProfilesSubCommand::List(_opts) => {
+                let profiles_path = durs_conf::get_profiles_path(&durs_core.options.profiles_path);
+                let mut profiles = fs::read_dir(&profiles_path)
+                    .map_err(DursCoreError::FailListProfiles)?
+                    .filter_map(Result::ok)
+                    .map(|entry| entry.path())
+                    .filter(|path| path.is_dir())
+                    .collect::<Vec<_>>();
+                profiles.sort();
+
+                println!(
+                    "{:<20} {:<12} {:>10}  LAST RUN",
+                    "PROFILE", "CURRENCY", "SIZE"
+                );
+                for profile_path in profiles {
+                    println!("{}", profile_line(&profile_path));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn profile_line(profile_path: &Path) -> String {
+    let profile_name = profile_path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let datas_path = profile_path.join(durs_conf::constants::MODULES_DATAS_FOLDER);
+    let currency = if datas_path.exists() {
+        dubp_currency_params::db::get_currency_name(datas_path)
+            .ok()
+            .and_then(|currency| currency)
+            .map(|currency| currency.0)
+    } else {
+        None
+    };
+    let last_run = match profile_lock::read_profile_lock(profile_path) {
+        Some(lock_infos) => format!(
+            "{} ({}, PID {})",
+            profile_lock::format_timestamp(lock_infos.start_time),
+            if lock_infos.is_running() {
+                "running"
+            } else {
+                "stale lock"
+            },
+            lock_infos.pid
+        ),
+        None => profile_lock::read_profile_last_run(profile_path)
+            .map(profile_lock::format_timestamp)
+            .unwrap_or_else(|| "never".to_owned()),
+    };
+
+    format!(
+        "{:<20} {:<12} {:>10}  {}",
+        profile_name,
+        currency.unwrap_or_else(|| "-".to_owned()),
+        format_size(dir_size(profile_path)),
+        last_run
+    )
+}
+
+fn dir_size(path: &Path) -> u64 {
+    fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .map(|entry| match entry.metadata() {
+                    Ok(ref metadata) if metadata.is_dir() => dir_size(&entry.path()),
+                    Ok(metadata) => metadata.len(),
+                    Err(_) => 0,
+                })
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+fn format_size(size: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if size < 1024 {
+        return format!("{} B", size);
+    }
+    let mut size = size as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
@@ -18,7 +18,10 @@
 use super::InvalidInput;
 use crate::commands::DursExecutableCoreCommand;
 use crate::errors::DursCoreError;
+use crate::profile_lock::ProfileLock;
 use crate::DursCore;
+use dubp_common_doc::BlockNumber;
+use durs_bc::BlockchainModule;
 use durs_conf::DuRsConf;
 use std::fs;
 use std::str::FromStr;
@@ -32,6 +35,8 @@ pub enum ResetType {
     Conf,
     /// Reset all
     All,
+    /// Revert the local blockchain to a given block
+    Blockchain,
 }
 
 impl FromStr for ResetType {
@@ -42,7 +47,10 @@ impl FromStr for ResetType {
             "data" => Ok(ResetType::Datas),
             "conf" => Ok(ResetType::Conf),
             "all" => Ok(ResetType::All),
-            _ => Err(InvalidInput("Kind of data to be reseted: data, conf, all.")),
+            "blockchain" => Ok(ResetType::Blockchain),
+            _ => Err(InvalidInput(
+                "Kind of data to be reseted: data, conf, all, blockchain.",
+            )),
         }
     }
 }
@@ -50,8 +58,14 @@ impl FromStr for ResetType {
 #[derive(StructOpt, Debug, Copy, Clone)]
 /// Reset data or configuration
 pub struct ResetOpt {
-    /// Kind of data to be reseted: data, conf, all
+    /// Kind of data to be reseted: data, conf, all, blockchain
     pub reset_type: ResetType,
+    /// Number of the block to revert the local blockchain to (blockchain only)
+    #[structopt(long = "to", value_name = "BLOCK_NUMBER")]
+    pub to: Option<u32>,
+    /// Remove the lock of the profile left by a node that did not stop properly (blockchain only)
+    #[structopt(long = "force-unlock")]
+    pub force_unlock: bool,
 }
 
 impl DursExecutableCoreCommand for ResetOpt {
@@ -72,6 +86,20 @@ impl DursExecutableCoreCommand for ResetOpt {
             }
             ResetType::All => fs::remove_dir_all(profile_path.as_path())
                 .map_err(DursCoreError::FailRemoveProfileDir),
+            ResetType::Blockchain => {
+                let target = self.to.ok_or(DursCoreError::ResetBlockchainWithoutTarget)?;
+                let _profile_lock = ProfileLock::acquire(&profile_path, self.force_unlock)?;
+                let bc_db = durs_dbs_tools::kv_db_old::KvFileDbHandler::open_db(
+                    durs_conf::get_blockchain_db_path(profile_path.clone()).as_path(),
+                    &durs_bc_db_reader::bc_db_schema(),
+                )
+                .map_err(DursCoreError::FailOpenBcDb)?;
+                let summary =
+                    BlockchainModule::revert_to(&bc_db, profile_path, BlockNumber(target))
+                        .map_err(DursCoreError::FailRevertBlockchain)?;
+                println!("{}", summary);
+                Ok(())
+            }
         }
     }
 }
@@ -18,4 +18,12 @@
 #[derive(StructOpt, Debug, Copy, Clone)]
 #[structopt(name = "start", setting(structopt::clap::AppSettings::ColoredHelp))]
 /// start durs server
-pub struct StartOpt {}
+pub struct StartOpt {
+    /// Remove the lock of the profile left by a node that did not stop properly
+    #[structopt(long = "force-unlock")]
+    pub force_unlock: bool,
+    /// Capture a CPU profile during the first SECONDS seconds and write its flamegraph in the profile directory
+    #[cfg(feature = "pprof")]
+    #[structopt(long = "cpu-profile", value_name = "SECONDS")]
+    pub cpu_profile: Option<u64>,
+}
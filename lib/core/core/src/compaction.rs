@@ -0,0 +1,66 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Compaction of the blockchain database of a profile.
+
+use crate::constants::PROFILE_LAST_COMPACTION_FILENAME;
+use crate::errors::DursCoreError;
+use chrono::Timelike;
+use durs_bc::compaction::CompactionSummary;
+use durs_bc::BlockchainModule;
+use durs_conf::CompactionConf;
+use std::fs;
+use std::path::Path;
+
+/// Compact the blockchain database of the profile.
+/// The profile must be locked and the database must not be opened by the current process.
+pub fn compact(profile_path: &Path) -> Result<CompactionSummary, DursCoreError> {
+    let summary = BlockchainModule::compact_db(profile_path.to_path_buf())
+        .map_err(DursCoreError::FailCompactDb)?;
+    if let Err(e) = fs::write(
+        profile_path.join(PROFILE_LAST_COMPACTION_FILENAME),
+        chrono::Utc::now().timestamp().to_string(),
+    ) {
+        warn!("Fail to write last compaction time of the profile: {}", e);
+    }
+    Ok(summary)
+}
+
+/// Compact the blockchain database if we are in the maintenance window
+/// and the last compaction is old enough
+pub fn run_scheduled_compaction(profile_path: &Path, conf: CompactionConf) {
+    if !conf.enabled || !conf.in_window(chrono::Local::now().hour() as u8) {
+        return;
+    }
+    if let Some(last_compaction) = read_last_compaction(profile_path) {
+        if chrono::Utc::now().timestamp() - last_compaction < i64::from(conf.interval_days) * 86_400
+        {
+            return;
+        }
+    }
+
+    info!("Scheduled compaction of the blockchain database...");
+    match compact(profile_path) {
+        Ok(summary) => info!("{}", summary),
+        Err(e) => warn!("Fail to compact the blockchain database: {}", e),
+    }
+}
+
+/// Read the time of the last compaction of the blockchain database (unix timestamp)
+fn read_last_compaction(profile_path: &Path) -> Option<i64> {
+    fs::read_to_string(profile_path.join(PROFILE_LAST_COMPACTION_FILENAME))
+        .ok()
+        .and_then(|content| content.trim().parse().ok())
+}
@@ -17,3 +17,12 @@
 
 /// Default user profile
 pub static DEFAULT_USER_PROFILE: &str = "default";
+
+/// Name of the lock file of a profile used by a running node
+pub static PROFILE_LOCK_FILENAME: &str = "durs.lock";
+
+/// Name of the file storing the start time of the last node run on a profile
+pub static PROFILE_LAST_RUN_FILENAME: &str = "last_run";
+
+/// Name of the file storing the time of the last compaction of the blockchain database of a profile
+pub static PROFILE_LAST_COMPACTION_FILENAME: &str = "last_compaction";
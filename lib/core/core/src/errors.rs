@@ -27,12 +27,66 @@ pub enum DursCoreError {
     /// Generic error that impl Fail
     #[fail(display = "{}", _0)]
     Error(Error),
+    /// The audit of the blockchain database found inconsistencies
+    #[fail(display = "{} divergences found in blockchain DB", _0)]
+    DbDivergences(usize),
+    /// Fail to audit blockchain DB
+    #[fail(display = "Fail to audit blockchain DB: {:?}", _0)]
+    FailAuditDb(durs_dbs_tools::DbError),
     /// Fail to open blockchain DB.
     #[fail(display = "Fail to open blockchain DB: {:?}", _0)]
     FailOpenBcDb(durs_dbs_tools::DbError),
+    /// Fail to list profiles
+    #[fail(display = "Fail to list profiles: {}", _0)]
+    FailListProfiles(std::io::Error),
+    /// Fail to lock profile
+    #[fail(display = "Fail to lock profile: {}", _0)]
+    FailLockProfile(std::io::Error),
+    /// Fail to read network map
+    #[fail(display = "Fail to read network map: {}", _0)]
+    FailReadNetworkMap(durs_network::map::NetworkMapError),
     /// Fail to read currency params DB
     #[fail(display = "Fail to read currency params DB: {}", _0)]
     FailReadCurrencyParamsDb(CurrencyParamsDbError),
+    /// Fail to revert the local blockchain
+    #[fail(display = "Fail to revert the local blockchain: {}", _0)]
+    FailRevertBlockchain(durs_bc::revert::RevertError),
+    /// Fail to back up the blockchain database
+    #[fail(display = "Fail to back up blockchain DB: {}", _0)]
+    FailBackupDb(durs_bc::backup::BackupError),
+    /// Fail to migrate the blockchain database
+    #[fail(display = "Fail to migrate blockchain DB: {}", _0)]
+    FailMigrateBcDb(durs_dbs_tools::DbError),
+    /// Fail to read the statistics of the blockchain database
+    #[fail(display = "Fail to read blockchain DB statistics: {}", _0)]
+    FailReadBcDbStats(durs_dbs_tools::DbError),
+    /// Fail to compact the blockchain database
+    #[fail(display = "Fail to compact blockchain DB: {}", _0)]
+    FailCompactDb(durs_bc::compaction::CompactionError),
+    /// Fail to export a snapshot of the chain state
+    #[fail(display = "Fail to export snapshot: {}", _0)]
+    FailExportSnapshot(durs_bc::snapshot::SnapshotError),
+    /// Fail to import a snapshot of the chain state
+    #[fail(display = "Fail to import snapshot: {}", _0)]
+    FailImportSnapshot(durs_bc::snapshot::SnapshotError),
+    /// Fail to restore the blockchain database
+    #[fail(display = "Fail to restore blockchain DB: {}", _0)]
+    FailRestoreDb(durs_bc::backup::BackupError),
+    /// Fail to create or verify a statement of balances
+    #[fail(display = "Fail to prove reserve: {}", _0)]
+    FailReserveProof(durs_bc::reserve_proof::ReserveProofError),
+    /// Fail to read the metrics history
+    #[fail(display = "Fail to read metrics history: {}", _0)]
+    FailReadMetricsHistory(std::io::Error),
+    /// Fail to read a statement of balances
+    #[fail(display = "Fail to read statement of balances: {}", _0)]
+    FailReadReserveProof(std::io::Error),
+    /// Fail to write a statement of balances
+    #[fail(display = "Fail to write statement of balances: {}", _0)]
+    FailWriteReserveProof(std::io::Error),
+    /// Fail to rebuild an index of the blockchain database
+    #[fail(display = "Fail to reindex: {}", _0)]
+    FailReindex(durs_bc::reindex::ReindexError),
     /// Fail to remove configuration file
     #[fail(display = "Fail to remove configuration file: {}", _0)]
     FailRemoveConfFile(std::io::Error),
@@ -42,18 +96,70 @@ pub enum DursCoreError {
     /// Fail to remove datas directory
     #[fail(display = "Fail to remove datas directory: {}", _0)]
     FailRemoveDatasDir(std::io::Error),
+    /// Fail to set blockchain storage mode
+    #[fail(display = "Fail to set blockchain storage mode: {:?}", _0)]
+    FailSetStorageMode(durs_dbs_tools::DbError),
     /// Fail to update configuration file
     #[fail(display = "Fail to update configuration file: {}", _0)]
     FailUpdateConf(std::io::Error),
+    /// Fail to write network map export
+    #[fail(display = "Fail to write network map export: {}", _0)]
+    FailWriteNetworkMap(std::io::Error),
     /// Fail to write keypairs file
     #[fail(display = "could not write keypairs file: {}", _0)]
     FailWriteKeypairsFile(std::io::Error),
     /// Error on initialization of the logger
     #[fail(display = "Error on initialization of the logger: {}", _0)]
     InitLoggerError(InitLoggerError),
+    /// Two modules are configured to listen on the same port
+    #[fail(
+        display = "Modules '{}' ({}:{}) and '{}' ({}:{}) are configured to listen on the same port, please change the configuration of one of them.",
+        first_module, first_host, port, second_module, second_host, port
+    )]
+    ListenPortConflict {
+        /// Port
+        port: u16,
+        /// Module declaring the port first
+        first_module: ModuleStaticName,
+        /// Host of the first module
+        first_host: String,
+        /// Module declaring the port second
+        second_module: ModuleStaticName,
+        /// Host of the second module
+        second_host: String,
+    },
+    /// A module cannot listen on its configured address
+    #[fail(
+        display = "Module '{}' cannot listen on {}:{}: {}. Is another program already using this port?",
+        module_name, host, port, error
+    )]
+    ListenPortUnavailable {
+        /// Module name
+        module_name: ModuleStaticName,
+        /// Host
+        host: String,
+        /// Port
+        port: u16,
+        /// Error details
+        error: std::io::Error,
+    },
+    /// Invalid blockstamp in parameter
+    #[fail(display = "Invalid blockstamp, expected format: NUMBER-HASH.")]
+    InvalidBlockstamp,
+    /// Invalid duration in parameter
+    #[fail(display = "Invalid duration, expected format: NUMBER followed by s, m, h or d.")]
+    InvalidDuration,
+    /// Invalid public key in parameter
+    #[fail(display = "Invalid public key.")]
+    InvalidPubkey,
     /// Error at configuration loading
     #[fail(display = "Error at configuration loading: {}", _0)]
     LoadConfError(durs_conf::DursConfError),
+    /// The command requires the member keypair
+    #[fail(
+        display = "This command requires a member keypair, please set it with the keys wizard."
+    )]
+    MissingMemberKeypair,
     /// Plug module error
     #[fail(display = "Error on loading module '{}': {}", module_name, error)]
     PlugModuleError {
@@ -62,6 +168,23 @@ pub enum DursCoreError {
         /// Error details
         error: PlugModuleError,
     },
+    /// Profile already used by another process
+    #[fail(
+        display = "This profile is already used by another process (PID {}, started at {}). If no node is running on this profile, use the --force-unlock option.",
+        pid, start_time
+    )]
+    ProfileLocked {
+        /// PID of the process holding the lock
+        pid: String,
+        /// Start time of the process holding the lock
+        start_time: String,
+    },
+    /// Blockchain reset without target block
+    #[fail(display = "Please specify the number of the block to revert to with the --to option.")]
+    ResetBlockchainWithoutTarget,
+    /// Network sync of a new node without currency
+    #[fail(display = "Unknown currency, please specify it with the --currency option.")]
+    SyncWithoutCurrency,
     /// Sync without source and without option local
     #[fail(display = "Please specify the url of a trusted node or use the --local option.")]
     SyncWithoutSource,
@@ -0,0 +1,29 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Dunitrust core translations.
+
+use durs_module::i18n::{Catalog, Locale};
+
+/// French translations of core messages
+static FR_CATALOG: Catalog = &[("{} (disabled)", "{} (désactivé)")];
+
+/// Catalogs of core messages (including the messages displayed by the configuration cli)
+pub fn core_catalogs() -> Vec<(Locale, Catalog)> {
+    vec![
+        (Locale::Fr, FR_CATALOG),
+        (Locale::Fr, durs_conf::keypairs::cli::FR_CATALOG),
+    ]
+}
@@ -36,13 +36,21 @@ extern crate structopt;
 
 mod change_conf;
 pub mod commands;
+mod compaction;
 mod constants;
 pub mod errors;
+mod i18n;
 mod logger;
+mod ports;
+mod profile_lock;
+#[cfg(feature = "pprof")]
+mod profiler;
 mod router;
 
 use crate::commands::*;
 use crate::errors::DursCoreError;
+use crate::ports::PortsReservation;
+use crate::profile_lock::ProfileLock;
 use dubp_currency_params::CurrencyName;
 use durs_bc::{dbex::DbExQuery, BlockchainModule};
 use durs_common_tools::fatal_error;
@@ -50,6 +58,7 @@ pub use durs_conf::{
     constants::KEYPAIRS_FILENAME, keypairs::cli::*, ChangeGlobalConf, DuRsConf, DuniterKeyPairs,
 };
 use durs_message::*;
+use durs_module::i18n::{I18n, Locale};
 use durs_module::*;
 use durs_network::NetworkModule;
 use std::collections::HashMap;
@@ -94,6 +103,8 @@ pub struct DursCore<DC: DursConfTrait> {
     pub modules_names: Vec<ModuleStaticName>,
     /// Threads handlers that execute plugged modules
     pub threads: HashMap<ModuleStaticName, thread::JoinHandle<()>>,
+    /// Listen addresses of the modules, collected when plugging modules without starting them
+    ports_reservation: Option<PortsReservation>,
 }
 
 #[derive(Debug, Clone)]
@@ -116,6 +127,10 @@ impl DursCore<DuRsConf> {
         soft_version: &'static str,
     ) -> Result<(), DursCoreError> {
         let mut durs_core = DursCore::<DuRsConf>::init(soft_name, soft_version, durs_core_opts, 0)?;
+        durs_core
+            .soft_meta_datas
+            .i18n
+            .add_catalogs(&M::i18n_catalogs());
         // Load module conf and keys
         let module_conf_json = durs_core
             .soft_meta_datas
@@ -157,7 +172,6 @@ impl DursCore<DuRsConf> {
 
     /// Execute core command
     pub fn execute_core_command<PlugFunc>(
-        bc_db: durs_dbs_tools::kv_db_old::KvFileDbHandler,
         core_command: DursCoreCommand,
         durs_core_opts: DursCoreOptions,
         mut plug_modules: PlugFunc,
@@ -187,18 +201,46 @@ impl DursCore<DuRsConf> {
                 ));
                 plug_modules(&mut durs_core)
             }
-            DursCoreCommand::StartOpt(_opts) => {
+            DursCoreCommand::StartOpt(opts) => {
+                let _profile_lock = ProfileLock::acquire(&profile_path, opts.force_unlock)?;
+                // The compaction replaces the database file, so it must be done before opening it
+                compaction::run_scheduled_compaction(
+                    &profile_path,
+                    durs_core
+                        .soft_meta_datas
+                        .conf
+                        .get_global_conf()
+                        .compaction(),
+                );
+                let mut bc_db = open_bc_db(&profile_path)?;
+                migrate_bc_db(&mut bc_db, &profile_path)?;
+                durs_core.set_storage_mode(&bc_db)?;
                 durs_core.server_command = Some(ServerMode::Start());
 
+                #[cfg(feature = "pprof")]
+                {
+                    if let Some(cpu_profile_duration) = opts.cpu_profile {
+                        profiler::capture_cpu_profile(
+                            profile_path.clone(),
+                            std::time::Duration::from_secs(cpu_profile_duration),
+                        );
+                    }
+                }
+
                 durs_core.router_sender = Some(router::start_router(
                     durs_core.run_duration_in_secs,
                     profile_path,
                     durs_core.soft_meta_datas.conf.clone(),
                 ));
+                durs_core.reserve_ports(&mut plug_modules)?;
                 plug_modules(&mut durs_core)?;
                 durs_core.start(bc_db)
             }
             DursCoreCommand::SyncOpt(opts) => {
+                let _profile_lock = ProfileLock::acquire(&profile_path, opts.force_unlock)?;
+                let mut bc_db = open_bc_db(&profile_path)?;
+                migrate_bc_db(&mut bc_db, &profile_path)?;
+                durs_core.set_storage_mode(&bc_db)?;
                 if opts.local_path.is_some() {
                     // Launch local sync
                     BlockchainModule::local_sync(
@@ -210,6 +252,13 @@ impl DursCore<DuRsConf> {
                     .map_err(DursCoreError::Error)?;
                     Ok(())
                 } else if opts.source.is_some() {
+                    if durs_core.currency_name.is_none() {
+                        durs_core.currency_name = Some(CurrencyName(
+                            opts.currency
+                                .clone()
+                                .ok_or(DursCoreError::SyncWithoutCurrency)?,
+                        ));
+                    }
                     durs_core.server_command = Some(ServerMode::Sync(opts));
 
                     durs_core.router_sender = Some(router::start_router(
@@ -217,6 +266,7 @@ impl DursCore<DuRsConf> {
                         profile_path,
                         durs_core.soft_meta_datas.conf.clone(),
                     ));
+                    durs_core.reserve_ports(&mut plug_modules)?;
                     plug_modules(&mut durs_core)?;
                     durs_core.start(bc_db)
                 } else {
@@ -226,8 +276,21 @@ impl DursCore<DuRsConf> {
             DursCoreCommand::DbExOpt(opts) => opts.execute(durs_core),
             DursCoreCommand::ResetOpt(opts) => opts.execute(durs_core),
             DursCoreCommand::KeysOpt(opts) => opts.execute(durs_core),
+            DursCoreCommand::NetworkOpt(opts) => opts.execute(durs_core),
+            DursCoreCommand::ProfilesOpt(opts) => opts.execute(durs_core),
         }
     }
+    /// Write the storage mode of the profile (and its optional indexes) in blockchain database
+    fn set_storage_mode(
+        &self,
+        bc_db: &durs_dbs_tools::kv_db_old::KvFileDbHandler,
+    ) -> Result<(), DursCoreError> {
+        let global_conf = self.soft_meta_datas.conf.get_global_conf();
+        BlockchainModule::set_storage_mode(bc_db, global_conf.storage_mode())
+            .map_err(DursCoreError::FailSetStorageMode)?;
+        BlockchainModule::set_tx_search_index(bc_db, global_conf.tx_search_index())
+            .map_err(DursCoreError::FailSetStorageMode)
+    }
     /// Initialize Dunitrust core
     fn init(
         soft_name: &'static str,
@@ -246,6 +309,14 @@ impl DursCore<DuRsConf> {
             &durs_core_opts,
         )?;
 
+        // Name the threads of the global rayon pool
+        if let Err(e) = rayon::ThreadPoolBuilder::new()
+            .thread_name(|i| format!("rayon::{}", i))
+            .build_global()
+        {
+            warn!("Fail to name the threads of the global rayon pool: {}", e);
+        }
+
         // Load global conf
         let (conf, keypairs) =
             durs_conf::load_conf(profile_path.clone(), &durs_core_opts.keypairs_file)
@@ -258,6 +329,10 @@ impl DursCore<DuRsConf> {
         ))
         .map_err(DursCoreError::FailReadCurrencyParamsDb)?;
 
+        // Select language of messages displayed to the user
+        let mut i18n = I18n::new(Locale::select(conf.get_global_conf().lang().as_deref()));
+        i18n.add_catalogs(&i18n::core_catalogs());
+
         // Instanciate durs core
         Ok(DursCore {
             currency_name,
@@ -273,10 +348,25 @@ impl DursCore<DuRsConf> {
                 profile_path,
                 soft_name,
                 soft_version,
+                i18n,
             },
             threads: HashMap::new(),
+            ports_reservation: None,
         })
     }
+    /// Collect the listen addresses of the modules to be started
+    /// and fail if two of them conflict or if a port is already bound
+    fn reserve_ports<PlugFunc>(&mut self, plug_modules: &mut PlugFunc) -> Result<(), DursCoreError>
+    where
+        PlugFunc: FnMut(&mut DursCore<DuRsConf>) -> Result<(), DursCoreError>,
+    {
+        self.ports_reservation = Some(PortsReservation::default());
+        let plug_result = plug_modules(self);
+        let ports_reservation = self.ports_reservation.take().unwrap_or_default();
+        self.network_modules_count = 0;
+        plug_result?;
+        ports_reservation.check()
+    }
     /// Start durs server
     pub fn start(
         mut self,
@@ -308,17 +398,50 @@ impl DursCore<DuRsConf> {
             .expect("Fatal error: fail to send expected modules count to router thread !");
 
         // Send blockchain module registration to router thread
+        let member_keypair = self.keypairs.member_keypair.clone();
+        let mut blockchain_roles = vec![
+            ModuleRole::BlockchainDatas,
+            ModuleRole::BlockValidation,
+            ModuleRole::CurrencyPool,
+            ModuleRole::WotPool,
+        ];
+        if member_keypair.is_some() {
+            blockchain_roles.push(ModuleRole::BlockGeneration);
+        }
         router_sender
             .send(RouterThreadMessage::ModuleRegistration {
                 static_name: BlockchainModule::name(),
                 sender: blockchain_sender,
-                roles: vec![ModuleRole::BlockchainDatas, ModuleRole::BlockValidation],
+                roles: blockchain_roles,
                 events_subscription: vec![ModuleEvent::NewBlockFromNetwork, ModuleEvent::SyncEvent],
                 reserved_apis_parts: vec![],
                 endpoints: vec![],
             })
             .expect("Fatal error: fail to send blockchain registration to router thread !");
 
+        // Get fork resolution rules
+        let fork_resolution = self
+            .soft_meta_datas
+            .conf
+            .get_global_conf()
+            .fork_resolution();
+
+        // Get blocks requests conf
+        let blocks_requests = self
+            .soft_meta_datas
+            .conf
+            .get_global_conf()
+            .blocks_requests();
+
+        // Get size limits of the pools of pending documents
+        let sandboxes = self.soft_meta_datas.conf.get_global_conf().sandboxes();
+
+        // Get protocol experiments activated on the currency
+        let feature_flags = self.soft_meta_datas.conf.get_global_conf().feature_flags();
+
+        // Get databases autosave configuration
+        let autosave = self.soft_meta_datas.conf.get_global_conf().autosave();
+
         // Get profile path
         let profile_path = self.soft_meta_datas.profile_path;
 
@@ -341,8 +464,13 @@ impl DursCore<DuRsConf> {
             bc_db,
             router_sender.clone(),
             profile_path,
-            RequiredKeysContent::MemberKeyPair(None),
+            RequiredKeysContent::MemberKeyPair(member_keypair),
             cautious_mode,
+            fork_resolution,
+            blocks_requests,
+            sandboxes,
+            feature_flags,
+            autosave,
         );
         info!("Success to load Blockchain module.");
 
@@ -382,6 +510,7 @@ impl DursCore<DuRsConf> {
     fn plug_network_<NM: NetworkModule<DuRsConf, DursMsg>>(
         &mut self,
     ) -> Result<(), PlugModuleError> {
+        self.soft_meta_datas.i18n.add_catalogs(&NM::i18n_catalogs());
         let enabled = enabled::<DuRsConf, DursMsg, NM>(&self.soft_meta_datas.conf);
         if enabled {
             self.network_modules_count += 1;
@@ -421,6 +550,11 @@ impl DursCore<DuRsConf> {
                             self.keypairs.clone(),
                         )?;
 
+                    if let Some(ref mut ports_reservation) = self.ports_reservation {
+                        ports_reservation.declare(NM::name(), NM::listen_addrs(&module_conf));
+                        return Ok(());
+                    }
+
                     let sync_params = network_sync.clone();
                     let thread_builder = thread::Builder::new().name(NM::name().0.into());
                     self.threads.insert(
@@ -475,6 +609,7 @@ impl DursCore<DuRsConf> {
         &mut self,
         is_network_module: bool,
     ) -> Result<(), PlugModuleError> {
+        self.soft_meta_datas.i18n.add_catalogs(&M::i18n_catalogs());
         let enabled = enabled::<DuRsConf, DursMsg, M>(&self.soft_meta_datas.conf);
         if enabled {
             let (launch_module, sync_opts) = match self.server_command {
@@ -506,6 +641,11 @@ impl DursCore<DuRsConf> {
                         self.keypairs.clone(),
                     )?;
 
+                if let Some(ref mut ports_reservation) = self.ports_reservation {
+                    ports_reservation.declare(M::name(), M::listen_addrs(&module_conf));
+                    return Ok(());
+                }
+
                 let thread_builder = thread::Builder::new().name(M::name().0.into());
                 self.threads.insert(
                     M::name(),
@@ -549,7 +689,12 @@ impl DursCore<DuRsConf> {
                 if enabled {
                     println!("{}", M::name().to_string());
                 } else {
-                    println!("{} (disabled)", M::name().to_string());
+                    println!(
+                        "{}",
+                        self.soft_meta_datas
+                            .i18n
+                            .tr_args("{} (disabled)", &[&M::name()])
+                    );
                 }
             }
         }
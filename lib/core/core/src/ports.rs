@@ -0,0 +1,96 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Check the listen addresses of the modules before starting them.
+
+use crate::errors::DursCoreError;
+use durs_module::ModuleStaticName;
+use std::net::{SocketAddr, TcpListener, ToSocketAddrs};
+
+#[derive(Debug, Clone)]
+/// Socket on which a module will listen
+struct Listener {
+    module_name: ModuleStaticName,
+    host: String,
+    port: u16,
+    addrs: Vec<SocketAddr>,
+}
+
+impl Listener {
+    /// Two listeners conflict if they share a port on a common interface
+    fn conflicts_with(&self, other: &Listener) -> bool {
+        self.port == other.port
+            && self.addrs.iter().any(|addr| {
+                other.addrs.iter().any(|other_addr| {
+                    addr.ip() == other_addr.ip()
+                        || (addr.is_ipv4() == other_addr.is_ipv4()
+                            && (addr.ip().is_unspecified() || other_addr.ip().is_unspecified()))
+                })
+            })
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+/// Listen addresses declared by the modules to be started
+pub struct PortsReservation {
+    listeners: Vec<Listener>,
+}
+
+impl PortsReservation {
+    /// Declare the listen addresses of a module
+    pub fn declare(&mut self, module_name: ModuleStaticName, listen_addrs: Vec<(String, u16)>) {
+        for (host, port) in listen_addrs {
+            let addrs = (host.as_str(), port)
+                .to_socket_addrs()
+                .map(Iterator::collect)
+                .unwrap_or_default();
+            self.listeners.push(Listener {
+                module_name,
+                host,
+                port,
+                addrs,
+            });
+        }
+    }
+    /// Check that no two modules listen on the same port and that all ports are free
+    pub fn check(&self) -> Result<(), DursCoreError> {
+        for (i, listener) in self.listeners.iter().enumerate() {
+            if let Some(other) = self.listeners[..i]
+                .iter()
+                .find(|other| other.conflicts_with(listener))
+            {
+                return Err(DursCoreError::ListenPortConflict {
+                    port: listener.port,
+                    first_module: other.module_name,
+                    first_host: other.host.clone(),
+                    second_module: listener.module_name,
+                    second_host: listener.host.clone(),
+                });
+            }
+        }
+        for listener in &self.listeners {
+            // The listener is dropped immediately, which releases the port for the module
+            TcpListener::bind((listener.host.as_str(), listener.port)).map_err(|error| {
+                DursCoreError::ListenPortUnavailable {
+                    module_name: listener.module_name,
+                    host: listener.host.clone(),
+                    port: listener.port,
+                    error,
+                }
+            })?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,143 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Prevent several nodes from running concurrently on the same profile.
+
+use crate::constants::{PROFILE_LAST_RUN_FILENAME, PROFILE_LOCK_FILENAME};
+use crate::errors::DursCoreError;
+use chrono::TimeZone;
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// Content of a profile lock file
+pub struct ProfileLockInfos {
+    /// PID of the process holding the lock
+    pub pid: u32,
+    /// Start time of the process holding the lock (unix timestamp)
+    pub start_time: i64,
+}
+
+impl ProfileLockInfos {
+    fn parse(content: &str) -> Option<ProfileLockInfos> {
+        let mut lines = content.lines();
+        let pid = lines.next()?.trim().parse().ok()?;
+        let start_time = lines.next()?.trim().parse().ok()?;
+        Some(ProfileLockInfos { pid, start_time })
+    }
+    /// Does the process holding the lock still run ?
+    pub fn is_running(&self) -> bool {
+        let proc_path = Path::new("/proc");
+        if proc_path.exists() {
+            proc_path.join(self.pid.to_string()).exists()
+        } else {
+            // Unable to check, the process is presumed to run
+            true
+        }
+    }
+}
+
+#[derive(Debug)]
+/// Lock on a profile, released when dropped
+pub struct ProfileLock {
+    lock_file_path: PathBuf,
+}
+
+impl ProfileLock {
+    /// Lock the profile for the current process
+    pub fn acquire(profile_path: &Path, force_unlock: bool) -> Result<ProfileLock, DursCoreError> {
+        let lock_file_path = profile_path.join(PROFILE_LOCK_FILENAME);
+        if force_unlock {
+            if let Some(lock_infos) = read_profile_lock(profile_path) {
+                warn!(
+                    "Force unlock of the profile locked by process {}.",
+                    lock_infos.pid
+                );
+            }
+            if let Err(e) = fs::remove_file(&lock_file_path) {
+                if e.kind() != ErrorKind::NotFound {
+                    return Err(DursCoreError::FailLockProfile(e));
+                }
+            }
+        }
+
+        let mut lock_file = match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&lock_file_path)
+        {
+            Ok(lock_file) => lock_file,
+            Err(ref e) if e.kind() == ErrorKind::AlreadyExists => {
+                let lock_infos = read_profile_lock(profile_path);
+                if let Some(lock_infos) = lock_infos {
+                    if !lock_infos.is_running() {
+                        warn!(
+                            "Process {} that locked the profile is no longer running.",
+                            lock_infos.pid
+                        );
+                    }
+                }
+                return Err(DursCoreError::ProfileLocked {
+                    pid: lock_infos
+                        .map(|lock_infos| lock_infos.pid.to_string())
+                        .unwrap_or_else(|| "?".to_owned()),
+                    start_time: lock_infos
+                        .map(|lock_infos| format_timestamp(lock_infos.start_time))
+                        .unwrap_or_else(|| "?".to_owned()),
+                });
+            }
+            Err(e) => return Err(DursCoreError::FailLockProfile(e)),
+        };
+        let start_time = chrono::Utc::now().timestamp().to_string();
+        writeln!(lock_file, "{}\n{}", std::process::id(), start_time)
+            .map_err(DursCoreError::FailLockProfile)?;
+        if let Err(e) = fs::write(profile_path.join(PROFILE_LAST_RUN_FILENAME), start_time) {
+            warn!("Fail to write last run time of the profile: {}", e);
+        }
+
+        Ok(ProfileLock { lock_file_path })
+    }
+}
+
+impl Drop for ProfileLock {
+    fn drop(&mut self) {
+        if let Err(e) = fs::remove_file(&self.lock_file_path) {
+            error!("Fail to unlock profile: {}", e);
+        }
+    }
+}
+
+/// Read the lock of a profile (None if the profile is not locked)
+pub fn read_profile_lock(profile_path: &Path) -> Option<ProfileLockInfos> {
+    fs::read_to_string(profile_path.join(PROFILE_LOCK_FILENAME))
+        .ok()
+        .and_then(|content| ProfileLockInfos::parse(&content))
+}
+
+/// Read the start time of the last node run on a profile (unix timestamp)
+pub fn read_profile_last_run(profile_path: &Path) -> Option<i64> {
+    fs::read_to_string(profile_path.join(PROFILE_LAST_RUN_FILENAME))
+        .ok()
+        .and_then(|content| content.trim().parse().ok())
+}
+
+/// Format an unix timestamp in local time
+pub fn format_timestamp(timestamp: i64) -> String {
+    chrono::Local
+        .timestamp(timestamp, 0)
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string()
+}
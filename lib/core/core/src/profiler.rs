@@ -0,0 +1,60 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Capture a CPU profile of the whole node and dump it as a flamegraph in the profile directory.
+
+use std::fs::File;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+/// Sampling frequency of the CPU profiler (in Hz)
+static CPU_PROFILE_FREQUENCY: &i32 = &100;
+
+/// Profile all threads of the node during `duration` in a dedicated thread,
+/// then write the flamegraph to `<profile_path>/flamegraph-<date>.svg`
+pub fn capture_cpu_profile(profile_path: PathBuf, duration: Duration) {
+    let thread_builder = thread::Builder::new().name("profiler".to_owned());
+    if let Err(e) = thread_builder.spawn(move || {
+        let guard = match pprof::ProfilerGuard::new(*CPU_PROFILE_FREQUENCY) {
+            Ok(guard) => guard,
+            Err(e) => {
+                error!("Fail to start CPU profiler: {}", e);
+                return;
+            }
+        };
+        info!("CPU profiling during {} seconds...", duration.as_secs());
+        thread::sleep(duration);
+
+        let flamegraph_path = profile_path.join(format!(
+            "flamegraph-{}.svg",
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        ));
+        let result = guard
+            .report()
+            .build()
+            .map_err(|e| e.to_string())
+            .and_then(|report| {
+                let file = File::create(&flamegraph_path).map_err(|e| e.to_string())?;
+                report.flamegraph(file).map_err(|e| e.to_string())
+            });
+        match result {
+            Ok(()) => info!("CPU flamegraph written in {:?}.", flamegraph_path),
+            Err(e) => error!("Fail to write CPU flamegraph: {}", e),
+        }
+    }) {
+        error!("Fail to spawn profiler thread: {}", e);
+    }
+}
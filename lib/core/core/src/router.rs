@@ -16,6 +16,7 @@
 //! Relay messages between durs modules.
 
 use durs_common_tools::fatal_error;
+use durs_common_tools::fns::thread::spawn_child;
 use durs_conf::DuRsConf;
 use durs_message::*;
 use durs_module::*;
@@ -326,7 +327,8 @@ pub fn start_router(
     ) = mpsc::channel();
 
     // Create router thread
-    thread::spawn(move || {
+    let thread_builder = thread::Builder::new().name("router".to_owned());
+    thread_builder.spawn(move || {
         // Create broadcasting thread channel
         let (broadcasting_sender, broadcasting_receiver): (
             mpsc::Sender<RouterThreadMessage<DursMsg>>,
@@ -334,7 +336,7 @@ pub fn start_router(
         ) = mpsc::channel();
 
         // Create broadcasting thread
-        thread::spawn(move || {
+        spawn_child("broadcasting", move || {
             start_broadcasting_thread(start_time, &broadcasting_receiver);
         });
 
@@ -343,7 +345,7 @@ pub fn start_router(
             mpsc::channel();
 
         // Create conf thread
-        thread::spawn(move || {
+        spawn_child("conf", move || {
             start_conf_thread(profile_path.clone(), conf, &conf_receiver);
         });
 
@@ -501,7 +503,8 @@ pub fn start_router(
             }
         }
         info!("Router thread stop.")
-    });
+    })
+    .expect("Fatal error: fail to spawn router thread !");
 
     router_sender
 }
@@ -15,8 +15,9 @@
 
 use crate::*;
 use dubp_block_doc::BlockDocument;
-use dubp_common_doc::Blockstamp;
+use dubp_common_doc::{BlockNumber, Blockstamp};
 use dubp_user_docs::documents::UserDocumentDUBP;
+use dup_crypto::hashs::Hash;
 use durs_network::events::NetworkEvent;
 
 /// The DURS event message.
@@ -43,6 +44,19 @@ pub enum MemPoolEvent {
     StoreNewDocInPool(Box<UserDocumentDUBP>),
 }
 
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+/// Number of pending documents in the mempools of the blockchain module
+pub struct MempoolStats {
+    /// Pending transactions
+    pub transactions: usize,
+    /// Pending identities
+    pub identities: usize,
+    /// Pending memberships
+    pub memberships: usize,
+    /// Pending certifications
+    pub certifications: usize,
+}
+
 #[derive(Clone, Debug, PartialEq)]
 /// Blockchain module events
 pub enum BlockchainEvent {
@@ -50,12 +64,44 @@ pub enum BlockchainEvent {
     CurrencyParameters(dubp_currency_params::CurrencyParameters),
     /// Stack up new valid block in local blockchain
     StackUpValidBlock(Box<BlockDocument>),
+    /// New fork branch in the local database
+    NewFork {
+        /// Head of the fork branch
+        head: Blockstamp,
+        /// Number of the last block common to the fork branch and the local blockchain
+        fork_point: BlockNumber,
+        /// Number of blocks to revert to switch to the fork branch
+        rollback_depth: u32,
+    },
     /// Revert blocks in local blockchain
-    RevertBlocks(Vec<BlockDocument>),
+    RevertBlocks {
+        /// Reverted blocks, from the old current block down to the fork point
+        blocks: Vec<BlockDocument>,
+        /// Number of reverted blocks
+        rollback_depth: u32,
+        /// Head of the branch applied after the rollback
+        new_head: Blockstamp,
+    },
     /// Receive new valid pending document
     NewValidPendingDoc(UserDocumentDUBP),
     /// Receive new refused pending document
     RefusedPendingDoc(UserDocumentDUBP),
     /// Receive new refused pending block
     RefusedBlock(Blockstamp),
+    /// A received document failed validation
+    DocumentRejected {
+        /// Hash of the document
+        doc_hash: Hash,
+        /// Reason of the rejection, displayable to the user
+        reason: String,
+    },
+    /// Occupancy of the mempools
+    MempoolStats(MempoolStats),
+    /// Network sync progression
+    SyncProgress {
+        /// Number of the last block received
+        current: BlockNumber,
+        /// Number of the target block
+        target: BlockNumber,
+    },
 }
@@ -14,18 +14,19 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use crate::*;
-use dubp_common_doc::BlockNumber;
+use dubp_common_doc::{BlockNumber, Blockstamp};
+use dubp_user_docs::documents::transaction::TransactionDocument;
 use dup_crypto::hashs::Hash;
 use dup_crypto::keys::*;
-use durs_network::requests::OldNetworkRequest;
+use durs_network::requests::{NetworkRequest, OldNetworkRequest};
 
 #[derive(Clone, Debug, PartialEq)]
 /// Modules request content
 pub enum DursReqContent {
     /// Request to the old network module
     OldNetworkRequest(OldNetworkRequest),
-    /// Network request (Not yet implemented)
-    NetworkRequest(),
+    /// Request to the network module
+    NetworkRequest(NetworkRequest),
     /// Blockchain datas request
     BlockchainRequest(BlockchainRequest),
     /// Mem pool datas request
@@ -57,9 +58,15 @@ pub enum BlockchainRequest {
     },
     /// Usernames corresponding to the public keys in parameter
     UIDs(Vec<PubKey>),
+    /// Metrics of the application of the last blocks
+    Metrics,
+    /// Unconsumed sources spendable by the single signature of a public key
+    UtxosOfPubkey(PubKey),
+    /// Balance of a public key (amount of its unconsumed sources)
+    BalanceOfPubkey(PubKey),
 }
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 /// Inter-module request for mem pool data
 pub enum MemPoolRequest {
     /// All pending identities with their pending certifications
@@ -68,4 +75,14 @@ pub enum MemPoolRequest {
     AllPendingIdentitiesWithoutCerts(usize),
     /// All pending datas for given pubkey
     PendingWotDatasForPubkey(PubKey),
+    /// Pending documents that can be included in the block following the given blockstamp
+    DocumentsForNextBlock(Blockstamp),
+    /// All pending transactions
+    PendingTransactions,
+    /// Submit a transaction to the mempool
+    SubmitTransaction(Box<TransactionDocument>),
+    /// Is the username neither used by an identity of the blockchain nor by a pending identity ?
+    UidAvailability(String),
+    /// Is the public key neither used by an identity of the blockchain nor by a pending identity ?
+    PubkeyAvailability(PubKey),
 }
@@ -20,11 +20,14 @@ use dubp_user_docs::documents::certification::CertificationDocument;
 use dubp_user_docs::documents::identity::IdentityDocument;
 use dubp_user_docs::documents::membership::MembershipDocument;
 use dubp_user_docs::documents::revocation::RevocationDocumentV10;
+use dubp_user_docs::documents::transaction::{TransactionDocument, TxAmount, TxBase};
+use dubp_user_docs::documents::UserDocumentDUBP;
 use dup_crypto::hashs::Hash;
 use dup_crypto::keys::*;
 use durs_module::ModuleReqId;
 use durs_network::requests::NetworkResponse;
 use std::collections::HashMap;
+use std::fmt;
 
 /// Dunitrust request response message
 #[derive(Clone, Debug, PartialEq)]
@@ -69,6 +72,95 @@ pub enum BlockchainResponse {
     UIDs(HashMap<PubKey, Option<String>>),
     /// Identities
     Identities(Vec<IdentityDocument>),
+    /// Metrics of the application of the last blocks
+    Metrics(BlockApplyMetrics),
+    /// Unconsumed sources spendable by the single signature of a public key
+    UtxosOfPubkey(PubKey, Vec<PubkeySource>),
+    /// Balance of a public key
+    BalanceOfPubkey(PubKey, TxAmount, TxBase),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Identifier of a source
+pub enum PubkeySourceId {
+    /// Transaction output
+    Utxo {
+        /// Hash of the transaction
+        tx_hash: Hash,
+        /// Index of the output in the transaction
+        output_index: usize,
+    },
+    /// Universal dividend created by a block
+    Ud(BlockNumber),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Unconsumed source of a public key
+pub struct PubkeySource {
+    /// Identifier of the source
+    pub id: PubkeySourceId,
+    /// Amount
+    pub amount: TxAmount,
+    /// Base
+    pub base: TxBase,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+/// Stage of the application of a block
+pub enum BlockApplyStage {
+    /// Check the proof of work and the hashs
+    PowAndHashs,
+    /// Check the local rules
+    LocalRules,
+    /// Check the global rules
+    GlobalRules,
+    /// Update the web of trust
+    WotUpdate,
+    /// Write the block
+    BlockWrite,
+    /// Write the web of trust indexes
+    WotIndexesWrite,
+    /// Write the currency indexes (sources and dividends)
+    CurrencyIndexesWrite,
+}
+
+impl fmt::Display for BlockApplyStage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BlockApplyStage::PowAndHashs => write!(f, "pow and hashs"),
+            BlockApplyStage::LocalRules => write!(f, "local rules"),
+            BlockApplyStage::GlobalRules => write!(f, "global rules"),
+            BlockApplyStage::WotUpdate => write!(f, "wot update"),
+            BlockApplyStage::BlockWrite => write!(f, "block write"),
+            BlockApplyStage::WotIndexesWrite => write!(f, "wot indexes write"),
+            BlockApplyStage::CurrencyIndexesWrite => write!(f, "currency indexes write"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Time spent in a stage of the application of the last blocks
+pub struct BlockApplyStageMetrics {
+    /// Stage
+    pub stage: BlockApplyStage,
+    /// Total time spent in the stage (in microseconds)
+    pub total_micros: u64,
+    /// Maximum time spent in the stage by a single block (in microseconds)
+    pub max_micros: u64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+/// Metrics of the application of the last blocks
+pub struct BlockApplyMetrics {
+    /// Number of measured blocks
+    pub blocks_count: usize,
+    /// Time spent in each stage
+    pub stages: Vec<BlockApplyStageMetrics>,
+    /// Histogram of the apply durations: upper bound of each bucket (in milliseconds) and number of blocks.
+    /// The last bucket has no upper bound.
+    pub histogram: Vec<(Option<u64>, usize)>,
+    /// Slowest block and its apply duration (in microseconds)
+    pub slowest_block: Option<(Blockstamp, u64)>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -80,4 +172,136 @@ pub enum MemPoolResponse {
     AllPendingIdentitiesWithoutCerts(ModuleReqId, HashMap<Hash, PendingIdtyDatas>),
     /// All pending datas for given pubkey
     PendingWotDatasForPubkey(ModuleReqId, Box<PendingIdtyDatas>),
+    /// Pending documents that can be included in the next block
+    DocumentsForNextBlock(ModuleReqId, Vec<UserDocumentDUBP>),
+    /// All pending transactions
+    PendingTransactions(ModuleReqId, Vec<TransactionDocument>),
+    /// Result of a transaction submission: hash of the admitted transaction or rejection reason
+    TransactionSubmission(ModuleReqId, Result<Hash, TxRejection>),
+    /// Availability of a username or a public key for a new identity
+    IdentityAvailability(ModuleReqId, Result<(), IdtyCollision>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Collision of a new identity with an identity of the blockchain or a pending identity
+pub enum IdtyCollision {
+    /// The username is used by an identity of the blockchain
+    UidInBlockchain,
+    /// The username is used by a pending identity
+    UidPending,
+    /// The public key is used by an identity of the blockchain
+    PubkeyInBlockchain,
+    /// The public key is used by a pending identity
+    PubkeyPending,
+}
+
+impl fmt::Display for IdtyCollision {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            IdtyCollision::UidInBlockchain => {
+                write!(f, "the username is used by an identity of the blockchain")
+            }
+            IdtyCollision::UidPending => write!(f, "the username is used by a pending identity"),
+            IdtyCollision::PubkeyInBlockchain => {
+                write!(f, "the public key is used by an identity of the blockchain")
+            }
+            IdtyCollision::PubkeyPending => {
+                write!(f, "the public key is used by a pending identity")
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// Reason why a submitted transaction is rejected
+pub enum TxRejection {
+    /// The currency is not yet known by the node
+    UnknownCurrency,
+    /// The transaction is already pending
+    AlreadyPending,
+    /// The transaction document is invalid
+    InvalidDocument(String),
+    /// The transaction blockstamp does not reference a block of the local blockchain
+    UnknownBlockstamp(Blockstamp),
+    /// The transaction blockstamp is older than the transactions window
+    Expired,
+    /// The transaction can't be written before this median time
+    Locktime(u64),
+    /// The output conditions are invalid
+    InvalidOutput {
+        /// Index of the output
+        output_index: usize,
+    },
+    /// The source spent by the input does not exist or is already consumed
+    UnknownSource {
+        /// Index of the input
+        input_index: usize,
+    },
+    /// The source spent by the input is already spent by a pending transaction
+    DoubleSpend {
+        /// Index of the input
+        input_index: usize,
+        /// Hash of the pending transaction
+        pending_tx: Hash,
+    },
+    /// The unlock proofs of the input don't satisfy the conditions of the source
+    InvalidUnlock {
+        /// Index of the input
+        input_index: usize,
+    },
+    /// The source spent by the input is still locked by a time condition
+    LockedSource {
+        /// Index of the input
+        input_index: usize,
+    },
+    /// An issuer of the transaction has too many pending transactions
+    IssuerQuota(PubKey),
+}
+
+impl fmt::Display for TxRejection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TxRejection::UnknownCurrency => write!(f, "the currency is not yet known by the node"),
+            TxRejection::AlreadyPending => write!(f, "the transaction is already pending"),
+            TxRejection::InvalidDocument(ref reason) => {
+                write!(f, "invalid transaction document: {}", reason)
+            }
+            TxRejection::UnknownBlockstamp(blockstamp) => {
+                write!(f, "unknown blockstamp {}", blockstamp)
+            }
+            TxRejection::Expired => write!(f, "the transaction blockstamp is too old"),
+            TxRejection::Locktime(median_time) => write!(
+                f,
+                "the transaction can't be written before median time {}",
+                median_time
+            ),
+            TxRejection::InvalidOutput { output_index } => {
+                write!(f, "output {}: invalid conditions", output_index)
+            }
+            TxRejection::UnknownSource { input_index } => write!(
+                f,
+                "input {}: the source does not exist or is already consumed",
+                input_index
+            ),
+            TxRejection::DoubleSpend {
+                input_index,
+                pending_tx,
+            } => write!(
+                f,
+                "input {}: the source is already spent by pending transaction {}",
+                input_index, pending_tx
+            ),
+            TxRejection::InvalidUnlock { input_index } => write!(
+                f,
+                "input {}: the unlock proofs don't satisfy the source conditions",
+                input_index
+            ),
+            TxRejection::LockedSource { input_index } => {
+                write!(f, "input {}: the source is still locked", input_index)
+            }
+            TxRejection::IssuerQuota(issuer) => {
+                write!(f, "the issuer {} has too many pending transactions", issuer)
+            }
+        }
+    }
 }
@@ -0,0 +1,156 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Localization of messages displayed to the user.
+//!
+//! Messages are identified by their english text (gettext-style), each module can provide
+//! catalogs translating its messages in other languages.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+
+/// Environment variable used to force the language
+pub static DURS_LANG_ENV_VAR: &str = "DURS_LANG";
+
+/// Environment variables describing the system locale, by priority order
+static SYSTEM_LOCALE_ENV_VARS: &[&str] = &["LC_ALL", "LC_MESSAGES", "LANG"];
+
+/// Translations of english messages (msgid, msgstr)
+pub type Catalog = &'static [(&'static str, &'static str)];
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+/// Supported languages
+pub enum Locale {
+    /// English (messages are written in english in the source code)
+    #[default]
+    En,
+    /// French
+    Fr,
+}
+
+impl Locale {
+    /// Parse locale from a language tag ("fr", "fr-FR", "fr_FR.UTF-8", etc)
+    pub fn from_tag(tag: &str) -> Option<Locale> {
+        let lang = tag
+            .split(&['_', '-', '.'][..])
+            .next()
+            .unwrap_or("")
+            .to_lowercase();
+        match lang.as_str() {
+            "en" | "c" | "posix" => Some(Locale::En),
+            "fr" => Some(Locale::Fr),
+            _ => None,
+        }
+    }
+    /// Select locale by priority order: DURS_LANG environment variable,
+    /// configuration, system locale and finally english.
+    pub fn select(conf_lang: Option<&str>) -> Locale {
+        std::env::var(DURS_LANG_ENV_VAR)
+            .ok()
+            .and_then(|tag| Locale::from_tag(&tag))
+            .or_else(|| conf_lang.and_then(Locale::from_tag))
+            .or_else(|| {
+                SYSTEM_LOCALE_ENV_VARS
+                    .iter()
+                    .filter_map(|var| std::env::var(var).ok())
+                    .find(|tag| !tag.is_empty())
+                    .and_then(|tag| Locale::from_tag(&tag))
+            })
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+/// Translate messages in the selected locale
+pub struct I18n {
+    locale: Locale,
+    messages: HashMap<&'static str, &'static str>,
+}
+
+impl I18n {
+    /// Create translator for a given locale (without any catalog)
+    pub fn new(locale: Locale) -> Self {
+        I18n {
+            locale,
+            messages: HashMap::new(),
+        }
+    }
+    /// Selected locale
+    pub fn locale(&self) -> Locale {
+        self.locale
+    }
+    /// Add catalogs (only the catalogs of the selected locale are kept)
+    pub fn add_catalogs(&mut self, catalogs: &[(Locale, Catalog)]) {
+        for (locale, catalog) in catalogs {
+            if *locale == self.locale {
+                self.messages.extend(catalog.iter().copied());
+            }
+        }
+    }
+    /// Translate message (the message is returned as is if there is no translation)
+    pub fn tr<'a>(&self, msgid: &'a str) -> &'a str {
+        self.messages.get(msgid).copied().unwrap_or(msgid)
+    }
+    /// Translate message and replace each `{}` by the corresponding argument
+    pub fn tr_args(&self, msgid: &str, args: &[&dyn Display]) -> String {
+        let mut args = args.iter();
+        let mut parts = self.tr(msgid).split("{}");
+        let mut message = parts.next().unwrap_or("").to_owned();
+        for part in parts {
+            if let Some(arg) = args.next() {
+                message.push_str(&arg.to_string());
+            }
+            message.push_str(part);
+        }
+        message
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static FR_CATALOG: Catalog = &[
+        ("Show keys", "Afficher les clés"),
+        (
+            "Pubkey '{}' is not banned.",
+            "La clé '{}' n'est pas bannie.",
+        ),
+    ];
+
+    #[test]
+    fn parse_locale_tag() {
+        assert_eq!(Some(Locale::Fr), Locale::from_tag("fr_FR.UTF-8"));
+        assert_eq!(Some(Locale::Fr), Locale::from_tag("fr-BE"));
+        assert_eq!(Some(Locale::En), Locale::from_tag("C"));
+        assert_eq!(None, Locale::from_tag("eo"));
+    }
+
+    #[test]
+    fn translate_messages() {
+        let mut i18n = I18n::new(Locale::Fr);
+        i18n.add_catalogs(&[(Locale::Fr, FR_CATALOG)]);
+        assert_eq!("Afficher les clés", i18n.tr("Show keys"));
+        assert_eq!("Unknown message", i18n.tr("Unknown message"));
+        assert_eq!(
+            "La clé 'abc' n'est pas bannie.",
+            i18n.tr_args("Pubkey '{}' is not banned.", &[&"abc"])
+        );
+
+        let mut i18n = I18n::new(Locale::En);
+        i18n.add_catalogs(&[(Locale::Fr, FR_CATALOG)]);
+        assert_eq!("Show keys", i18n.tr("Show keys"));
+    }
+}
@@ -32,6 +32,8 @@
 #[macro_use]
 extern crate serde_derive;
 
+pub mod i18n;
+pub mod metrics_history;
 #[cfg(feature = "module-test")]
 pub mod module_test;
 
@@ -41,6 +43,7 @@ use durs_common_tools::fatal_error;
 use durs_common_tools::traits::merge::Merge;
 use durs_network_documents::network_endpoint::{ApiPart, EndpointEnum};
 use failure::Fail;
+use i18n::{Catalog, I18n, Locale};
 use serde::de::DeserializeOwned;
 use serde::ser::{Serialize, Serializer};
 use std::collections::HashSet;
@@ -117,6 +120,8 @@ pub trait DursGlobalConfTrait:
     fn my_node_id(&self) -> u32;
     /// Get default sync module
     fn default_sync_module(&self) -> ModuleName;
+    /// Get language of messages displayed to the user
+    fn lang(&self) -> Option<String>;
 }
 
 /// Dunitrust configuration trait
@@ -171,6 +176,8 @@ pub struct SoftwareMetaDatas<DC: DursConfTrait> {
     pub soft_name: &'static str,
     /// Software version
     pub soft_version: &'static str,
+    /// Translator of messages displayed to the user
+    pub i18n: I18n,
 }
 
 /// The different modules of Duniter-rs can exchange messages with the type of their choice,
@@ -226,6 +233,10 @@ pub enum ModuleEvent {
     NewTxinPool,
     /// A new wot document has been integrated into the local mempool
     NewWotDocInPool,
+    /// A received document has been rejected by the local mempool
+    RejectedDocument,
+    /// The occupancy of the local mempools has changed
+    MempoolStatsChange,
     /// A new valid HEAD has been received from the network
     NewValidHeadFromNetwork,
     /// Change in connections with other nodes (disconnection of a connection or establishment of a new connection)
@@ -236,6 +247,8 @@ pub enum ModuleEvent {
     NewValidPeerFromNodeNetwork,
     /// Synchronisation event
     SyncEvent,
+    /// A message from the operator of a directly connected node has been received
+    NewOperatorMessage,
 }
 
 #[derive(Clone, Debug)]
@@ -438,10 +451,18 @@ pub trait DursModule<DC: DursConfTrait, M: ModuleMessage> {
         global_conf: &DC::GlobalConf,
         module_user_conf: Option<Self::ModuleUserConf>,
     ) -> Result<(Self::ModuleConf, Option<Self::ModuleUserConf>), ModuleConfError>;
+    /// Host and port of each socket on which the module listens with this configuration
+    fn listen_addrs(_module_conf: &Self::ModuleConf) -> Vec<(String, u16)> {
+        vec![]
+    }
     /// Define if module have a cli subcommand
     fn have_subcommand() -> bool {
         false
     }
+    /// Translations of the messages displayed by the module
+    fn i18n_catalogs() -> Vec<(Locale, Catalog)> {
+        vec![]
+    }
     /// Execute injected subcommand
     fn exec_subcommand(
         _soft_meta_datas: &SoftwareMetaDatas<DC>,
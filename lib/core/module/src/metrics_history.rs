@@ -0,0 +1,202 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Recent history of the key metrics of the node, kept in the profile without any external
+//! monitoring stack.
+//!
+//! Each metric is a ring buffer file of fixed size: one slot per period of
+//! `METRICS_HISTORY_RESOLUTION_IN_SECS`, the oldest periods being overwritten after
+//! `METRICS_HISTORY_SLOTS_COUNT` periods. A slot is the start time of its period followed by the
+//! last value recorded during this period (two 64 bits little-endian unsigned integers).
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// Name of the folder of the metrics history files (in the datas folder)
+pub static METRICS_HISTORY_DIRNAME: &str = "metrics_history";
+
+/// Extension of the metrics history files
+static METRICS_HISTORY_FILE_EXTENSION: &str = "bin";
+
+/// Duration of a period of the metrics history (in seconds)
+pub static METRICS_HISTORY_RESOLUTION_IN_SECS: &u64 = &300;
+
+/// Number of periods kept in the metrics history (7 days)
+pub static METRICS_HISTORY_SLOTS_COUNT: &u64 = &2_016;
+
+/// Size of a slot (in bytes)
+const SLOT_SIZE: u64 = 16;
+
+#[derive(Clone, Debug)]
+/// Metrics history files of a profile
+pub struct MetricsHistory {
+    dir_path: PathBuf,
+}
+
+impl MetricsHistory {
+    /// Metrics history stored in the datas folder `datas_path`
+    pub fn new(datas_path: &Path) -> MetricsHistory {
+        MetricsHistory {
+            dir_path: datas_path.join(METRICS_HISTORY_DIRNAME),
+        }
+    }
+    fn file_path(&self, metric: &str) -> PathBuf {
+        self.dir_path
+            .join(metric)
+            .with_extension(METRICS_HISTORY_FILE_EXTENSION)
+    }
+    /// Record the value of a metric at `time` (UNIX timestamp), it replaces the value previously
+    /// recorded during the same period
+    pub fn record(&self, metric: &str, time: u64, value: u64) -> Result<(), io::Error> {
+        fs::create_dir_all(&self.dir_path)?;
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .open(self.file_path(metric))?;
+        write_slot(&mut file, time, value)
+    }
+    /// Values of a metric recorded since `since` (UNIX timestamp), the oldest first
+    pub fn read(&self, metric: &str, since: u64) -> Result<Vec<(u64, u64)>, io::Error> {
+        match fs::File::open(self.file_path(metric)) {
+            Ok(mut file) => read_slots(&mut file, since),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(vec![]),
+            Err(e) => Err(e),
+        }
+    }
+    /// Names of the recorded metrics, sorted
+    pub fn metrics(&self) -> Result<Vec<String>, io::Error> {
+        let entries = match fs::read_dir(&self.dir_path) {
+            Ok(entries) => entries,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e),
+        };
+        let mut metrics = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) == Some(METRICS_HISTORY_FILE_EXTENSION)
+            {
+                if let Some(metric) = path.file_stem().and_then(|stem| stem.to_str()) {
+                    metrics.push(metric.to_owned());
+                }
+            }
+        }
+        metrics.sort();
+        Ok(metrics)
+    }
+}
+
+fn write_slot<F: Seek + Write>(file: &mut F, time: u64, value: u64) -> Result<(), io::Error> {
+    let period = time / *METRICS_HISTORY_RESOLUTION_IN_SECS;
+    let slot_index = period % *METRICS_HISTORY_SLOTS_COUNT;
+    let mut slot = [0u8; SLOT_SIZE as usize];
+    slot[..8].copy_from_slice(&(period * *METRICS_HISTORY_RESOLUTION_IN_SECS).to_le_bytes());
+    slot[8..].copy_from_slice(&value.to_le_bytes());
+    file.seek(SeekFrom::Start(slot_index * SLOT_SIZE))?;
+    file.write_all(&slot)
+}
+
+fn read_slots<F: Read>(file: &mut F, since: u64) -> Result<Vec<(u64, u64)>, io::Error> {
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)?;
+    let since_period_start = since - since % *METRICS_HISTORY_RESOLUTION_IN_SECS;
+    let mut values: Vec<(u64, u64)> = bytes
+        .chunks_exact(SLOT_SIZE as usize)
+        .map(|slot| {
+            let mut time = [0u8; 8];
+            let mut value = [0u8; 8];
+            time.copy_from_slice(&slot[..8]);
+            value.copy_from_slice(&slot[8..]);
+            (u64::from_le_bytes(time), u64::from_le_bytes(value))
+        })
+        // Empty slots (never written or gaps in the file) have a null time
+        .filter(|(time, _)| *time > 0 && *time >= since_period_start)
+        .collect();
+    values.sort_unstable_by_key(|(time, _)| *time);
+    Ok(values)
+}
+
+/// Parse a duration such as `30m`, `24h` or `7d` (in seconds, a number alone is in seconds)
+pub fn parse_duration(duration: &str) -> Option<u64> {
+    let (number, unit_in_secs) = match duration.chars().last()? {
+        's' => (&duration[..duration.len() - 1], 1),
+        'm' => (&duration[..duration.len() - 1], 60),
+        'h' => (&duration[..duration.len() - 1], 3_600),
+        'd' => (&duration[..duration.len() - 1], 86_400),
+        _ => (duration, 1),
+    };
+    number.parse::<u64>().ok()?.checked_mul(unit_in_secs)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_ring_buffer() -> Result<(), io::Error> {
+        let resolution = *METRICS_HISTORY_RESOLUTION_IN_SECS;
+        let retention = resolution * *METRICS_HISTORY_SLOTS_COUNT;
+        let start = 1_500_000_000 - 1_500_000_000 % resolution;
+        let mut file = Cursor::new(Vec::new());
+
+        write_slot(&mut file, start + 10, 1)?;
+        // The last value of a period replaces the previous ones
+        write_slot(&mut file, start + resolution + 10, 2)?;
+        write_slot(&mut file, start + resolution + 20, 3)?;
+        // Skipped period
+        write_slot(&mut file, start + 3 * resolution, 4)?;
+        file.set_position(0);
+        assert_eq!(
+            vec![
+                (start, 1),
+                (start + resolution, 3),
+                (start + 3 * resolution, 4)
+            ],
+            read_slots(&mut file, start)?
+        );
+        file.set_position(0);
+        assert_eq!(
+            vec![(start + resolution, 3), (start + 3 * resolution, 4)],
+            read_slots(&mut file, start + resolution + 30)?
+        );
+
+        // A full retention later, the first period is overwritten
+        write_slot(&mut file, start + retention, 5)?;
+        file.set_position(0);
+        assert_eq!(
+            vec![
+                (start + resolution, 3),
+                (start + 3 * resolution, 4),
+                (start + retention, 5)
+            ],
+            read_slots(&mut file, start)?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(Some(86_400), parse_duration("24h"));
+        assert_eq!(Some(1_800), parse_duration("30m"));
+        assert_eq!(Some(604_800), parse_duration("7d"));
+        assert_eq!(Some(42), parse_duration("42"));
+        assert_eq!(None, parse_duration("h"));
+        assert_eq!(None, parse_duration("24y"));
+    }
+}
@@ -31,6 +31,9 @@ pub struct SyncOpt {
     /// End block
     #[structopt(short = "e", long = "end")]
     pub end: Option<u32>,
+    /// Remove the lock of the profile left by a node that did not stop properly
+    #[structopt(long = "force-unlock")]
+    pub force_unlock: bool,
     /// Path to directory that contain blockchain json files
     #[structopt(short = "l", long = "local")]
     #[structopt(parse(from_os_str))]
@@ -40,6 +40,8 @@ pub enum NetworkEvent {
     ReceiveHeads(Vec<NetworkHead>),
     /// Synchronisation event
     SyncEvent(SyncEvent),
+    /// Receiving an operator message from a directly connected node
+    ReceiveOperatorMessage(NodeFullId, String),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -42,6 +42,8 @@ use std::sync::mpsc;
 
 pub mod cli;
 pub mod events;
+pub mod map;
+pub mod metrics;
 pub mod requests;
 
 /// ApiModule
@@ -0,0 +1,187 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Known network topology, saved by network modules and exported for network-health dashboards.
+
+use durs_network_documents::network_head::NetworkHead;
+use durs_network_documents::NodeFullId;
+use failure::Fail;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+/// Name of the file where network modules save the network map (in the datas folder)
+pub static NETWORK_MAP_FILENAME: &str = "network_map.json";
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+/// Node of the network map
+pub struct NetworkMapNode {
+    /// Public key of the node
+    pub pubkey: String,
+    /// Node id
+    pub node_id: String,
+    /// Uid of the node owner
+    pub uid: Option<String>,
+    /// Software name (from the node HEAD)
+    pub software: Option<String>,
+    /// Software version (from the node HEAD)
+    pub soft_version: Option<String>,
+    /// Current blockstamp of the node (from the node HEAD)
+    pub blockstamp: Option<String>,
+    /// Declared endpoints (from the node peer card)
+    pub endpoints: BTreeSet<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+/// Known network topology
+pub struct NetworkMap {
+    /// Known nodes, by node full id
+    pub nodes: BTreeMap<String, NetworkMapNode>,
+    /// Observed connections between nodes (by node full ids)
+    pub connections: BTreeSet<(String, String)>,
+}
+
+#[derive(Debug, Fail)]
+/// Network map error
+pub enum NetworkMapError {
+    /// I/O error
+    #[fail(display = "I/O error: {}", _0)]
+    IoError(std::io::Error),
+    /// JSON error
+    #[fail(display = "JSON error: {}", _0)]
+    JsonError(serde_json::Error),
+}
+
+impl From<std::io::Error> for NetworkMapError {
+    fn from(e: std::io::Error) -> Self {
+        NetworkMapError::IoError(e)
+    }
+}
+
+impl From<serde_json::Error> for NetworkMapError {
+    fn from(e: serde_json::Error) -> Self {
+        NetworkMapError::JsonError(e)
+    }
+}
+
+impl NetworkMap {
+    /// Load network map from file
+    pub fn load(file_path: &Path) -> Result<NetworkMap, NetworkMapError> {
+        Ok(serde_json::from_slice(&fs::read(file_path)?)?)
+    }
+    /// Save network map in file
+    pub fn save(&self, file_path: &Path) -> Result<(), NetworkMapError> {
+        fs::write(file_path, serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+    fn get_mut_node(&mut self, node_full_id: NodeFullId) -> &mut NetworkMapNode {
+        self.nodes
+            .entry(node_full_id.to_string())
+            .or_insert_with(|| NetworkMapNode {
+                pubkey: node_full_id.1.to_string(),
+                node_id: node_full_id.0.to_string(),
+                ..NetworkMapNode::default()
+            })
+    }
+    /// Add a node from its HEAD
+    pub fn add_head(&mut self, head: &NetworkHead) {
+        let node = self.get_mut_node(head.node_full_id());
+        node.uid = head.uid();
+        node.software = Some(head.software());
+        node.soft_version = Some(head.soft_version());
+        node.blockstamp = Some(head.blockstamp().to_string());
+    }
+    /// Add an endpoint declared by a node
+    pub fn add_endpoint(&mut self, node_full_id: NodeFullId, raw_endpoint: &str) {
+        self.get_mut_node(node_full_id)
+            .endpoints
+            .insert(raw_endpoint.to_owned());
+    }
+    /// Add an observed connection between two nodes
+    pub fn add_connection(&mut self, node_a: NodeFullId, node_b: NodeFullId) {
+        self.get_mut_node(node_a);
+        self.get_mut_node(node_b);
+        self.connections
+            .insert((node_a.to_string(), node_b.to_string()));
+    }
+    /// Export network map in DOT format
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("graph network {\n");
+        for (node_full_id, node) in &self.nodes {
+            let mut label = node.uid.clone().unwrap_or_else(|| node.pubkey.clone());
+            if let (Some(ref software), Some(ref soft_version)) =
+                (&node.software, &node.soft_version)
+            {
+                label.push_str(&format!("\\n{} {}", software, soft_version));
+            }
+            if let Some(ref blockstamp) = node.blockstamp {
+                label.push_str(&format!("\\n{}", blockstamp));
+            }
+            dot.push_str(&format!(
+                "    \"{}\" [label=\"{}\"];\n",
+                node_full_id,
+                label.replace('"', "\\\"")
+            ));
+        }
+        for (node_a, node_b) in &self.connections {
+            dot.push_str(&format!("    \"{}\" -- \"{}\";\n", node_a, node_b));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dup_crypto::keys::PubKey;
+    use durs_network_documents::NodeId;
+    use std::str::FromStr;
+
+    #[test]
+    fn network_map_to_dot() -> Result<(), NetworkMapError> {
+        let node_a = NodeFullId(
+            NodeId(1),
+            PubKey::from_str("D9D2zaJoWYWveii1JRYLVK3J4Z7ZH3QczoKrnQeiM6mx")
+                .expect("invalid pubkey"),
+        );
+        let node_b = NodeFullId(
+            NodeId(2),
+            PubKey::from_str("2ny7YAdmzReQxAayyJZsyVYwYhVyax2thKcGknmQy5nQ")
+                .expect("invalid pubkey"),
+        );
+
+        let mut network_map = NetworkMap::default();
+        network_map.add_endpoint(node_a, "WS2P 1 g1.duniter.org 443 /ws2p");
+        network_map.add_connection(node_a, node_b);
+
+        assert_eq!(2, network_map.nodes.len());
+        assert_eq!(
+            network_map.to_dot(),
+            "graph network {\n    \
+             \"1-D9D2zaJoWYWveii1JRYLVK3J4Z7ZH3QczoKrnQeiM6mx\" [label=\"D9D2zaJoWYWveii1JRYLVK3J4Z7ZH3QczoKrnQeiM6mx\"];\n    \
+             \"2-2ny7YAdmzReQxAayyJZsyVYwYhVyax2thKcGknmQy5nQ\" [label=\"2ny7YAdmzReQxAayyJZsyVYwYhVyax2thKcGknmQy5nQ\"];\n    \
+             \"1-D9D2zaJoWYWveii1JRYLVK3J4Z7ZH3QczoKrnQeiM6mx\" -- \"2-2ny7YAdmzReQxAayyJZsyVYwYhVyax2thKcGknmQy5nQ\";\n\
+             }\n"
+        );
+
+        let file = tempfile::NamedTempFile::new()?;
+        network_map.save(file.path())?;
+        assert_eq!(network_map, NetworkMap::load(file.path())?);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,150 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Connection metrics of network modules, to display the live network health.
+
+use failure::Fail;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// Name of the file where network modules save their metrics (in the datas folder)
+pub static NETWORK_METRICS_FILENAME: &str = "network_metrics.json";
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+/// Metrics of a peer
+pub struct PeerMetrics {
+    /// Uid of the peer owner
+    pub uid: Option<String>,
+    /// Number of requests answered by the peer
+    pub answered_requests: u64,
+    /// Average response latency of the peer (in milliseconds)
+    pub average_latency_ms: Option<u64>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+/// Connection metrics of a network module
+pub struct NetworkMetrics {
+    /// Number of established connections (incoming and outgoing)
+    pub established_connections: usize,
+    /// Number of failed handshakes since the start of the node
+    pub handshake_failures: u64,
+    /// Number of requests awaiting a response
+    pub requests_in_flight: usize,
+    /// Metrics of each peer, by node full id
+    pub peers: BTreeMap<String, PeerMetrics>,
+}
+
+#[derive(Debug, Fail)]
+/// Network metrics error
+pub enum NetworkMetricsError {
+    /// I/O error
+    #[fail(display = "I/O error: {}", _0)]
+    IoError(std::io::Error),
+    /// JSON error
+    #[fail(display = "JSON error: {}", _0)]
+    JsonError(serde_json::Error),
+}
+
+impl From<std::io::Error> for NetworkMetricsError {
+    fn from(e: std::io::Error) -> Self {
+        NetworkMetricsError::IoError(e)
+    }
+}
+
+impl From<serde_json::Error> for NetworkMetricsError {
+    fn from(e: serde_json::Error) -> Self {
+        NetworkMetricsError::JsonError(e)
+    }
+}
+
+impl NetworkMetrics {
+    /// Load network metrics from file
+    pub fn load(file_path: &Path) -> Result<NetworkMetrics, NetworkMetricsError> {
+        Ok(serde_json::from_slice(&fs::read(file_path)?)?)
+    }
+    /// Save network metrics in file
+    pub fn save(&self, file_path: &Path) -> Result<(), NetworkMetricsError> {
+        fs::write(file_path, serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for NetworkMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Established connections: {}",
+            self.established_connections
+        )?;
+        writeln!(f, "Handshake failures: {}", self.handshake_failures)?;
+        writeln!(f, "Requests in flight: {}", self.requests_in_flight)?;
+        for (node_full_id, peer) in &self.peers {
+            let latency = if let Some(average_latency_ms) = peer.average_latency_ms {
+                format!("{} ms", average_latency_ms)
+            } else {
+                "-".to_owned()
+            };
+            writeln!(
+                f,
+                "{} ({}): {} answered requests, average latency {}",
+                node_full_id,
+                peer.uid.as_deref().unwrap_or("-"),
+                peer.answered_requests,
+                latency
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_and_display_network_metrics() -> Result<(), NetworkMetricsError> {
+        let mut network_metrics = NetworkMetrics {
+            established_connections: 1,
+            handshake_failures: 2,
+            requests_in_flight: 3,
+            peers: BTreeMap::new(),
+        };
+        network_metrics.peers.insert(
+            "1-D9D2zaJoWYWveii1JRYLVK3J4Z7ZH3QczoKrnQeiM6mx".to_owned(),
+            PeerMetrics {
+                uid: Some("elois".to_owned()),
+                answered_requests: 4,
+                average_latency_ms: Some(120),
+            },
+        );
+
+        assert_eq!(
+            network_metrics.to_string(),
+            "Established connections: 1\n\
+             Handshake failures: 2\n\
+             Requests in flight: 3\n\
+             1-D9D2zaJoWYWveii1JRYLVK3J4Z7ZH3QczoKrnQeiM6mx (elois): 4 answered requests, average latency 120 ms\n"
+        );
+
+        let file = tempfile::NamedTempFile::new()?;
+        network_metrics.save(file.path())?;
+        assert_eq!(network_metrics, NetworkMetrics::load(file.path())?);
+
+        Ok(())
+    }
+}
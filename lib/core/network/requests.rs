@@ -15,6 +15,7 @@
 
 //! Defined network requests.
 
+use crate::metrics::NetworkMetrics;
 use crate::*;
 use dubp_block_doc::BlockDocument;
 use dubp_common_doc::blockstamp::Blockstamp;
@@ -36,6 +37,20 @@ pub enum OldNetworkRequest {
     GetHeadsCache(ModuleReqFullId),
     /// Get a list of known endpoints
     GetEndpoints(ModuleReqFullId),
+    /// Get connection metrics
+    GetMetrics(ModuleReqFullId),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+/// Request addressed to the network module
+pub enum NetworkRequest {
+    /// Send an operator message to the directly connected nodes
+    SendOperatorMessage {
+        /// Recipient node (all directly connected nodes if None)
+        to: Option<NodeFullId>,
+        /// Text of the message
+        text: String,
+    },
 }
 
 impl OldNetworkRequest {
@@ -47,7 +62,8 @@ impl OldNetworkRequest {
             | OldNetworkRequest::GetRequirementsPending(ref req_id, _)
             | OldNetworkRequest::GetConsensus(ref req_id)
             | OldNetworkRequest::GetHeadsCache(ref req_id)
-            | OldNetworkRequest::GetEndpoints(ref req_id) => *req_id,
+            | OldNetworkRequest::GetEndpoints(ref req_id)
+            | OldNetworkRequest::GetMetrics(ref req_id) => *req_id,
         }
     }
     /// Get request identitifier
@@ -84,6 +100,10 @@ pub enum NetworkResponse {
     Consensus(ModuleReqFullId, Result<Blockstamp, NetworkConsensusError>),
     /// HeadsCache
     HeadsCache(ModuleReqFullId, Box<NetworkHead>),
+    /// Connection metrics
+    Metrics(ModuleReqFullId, Box<NetworkMetrics>),
+    /// No peer answered the request in time
+    RequestTimeout(ModuleReqFullId),
 }
 
 impl NetworkResponse {
@@ -95,7 +115,9 @@ impl NetworkResponse {
             | NetworkResponse::Chunk(ref req_id, _, _)
             | NetworkResponse::PendingDocuments(ref req_id, _)
             | NetworkResponse::Consensus(ref req_id, _)
-            | NetworkResponse::HeadsCache(ref req_id, _) => *req_id,
+            | NetworkResponse::HeadsCache(ref req_id, _)
+            | NetworkResponse::Metrics(ref req_id, _)
+            | NetworkResponse::RequestTimeout(ref req_id) => *req_id,
         }
     }
     /// Get request identifier
@@ -0,0 +1,180 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Minimal module built with durs-sdk: it counts the blocks stacked by the node.
+//!
+//! Run it with `cargo run --example hello`, the module is started with a fake router.
+
+#![deny(missing_docs)]
+
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate serde_derive;
+
+use durs_sdk::conf::merge_user_confs;
+use durs_sdk::prelude::*;
+use structopt::StructOpt;
+
+/// Name of the module
+static MODULE_NAME: &str = "hello";
+
+/// Configuration provided by the user
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HelloUserConf {
+    greeting: Option<String>,
+}
+
+impl Merge for HelloUserConf {
+    fn merge(self, other: Self) -> Self {
+        HelloUserConf {
+            greeting: self.greeting.or(other.greeting),
+        }
+    }
+}
+
+/// Module configuration
+#[derive(Debug, Clone, PartialEq)]
+pub struct HelloConf {
+    greeting: String,
+}
+
+impl Default for HelloConf {
+    fn default() -> Self {
+        HelloConf {
+            greeting: String::from("Hello"),
+        }
+    }
+}
+
+#[derive(StructOpt, Debug, Clone)]
+#[structopt(name = "hello")]
+/// Hello subcommand options
+pub struct HelloOpt {
+    /// New greeting
+    pub greeting: String,
+}
+
+/// Hello module
+#[derive(Debug, Copy, Clone)]
+pub struct HelloModule;
+
+impl DursModule<DuRsConf, DursMsg> for HelloModule {
+    type ModuleUserConf = HelloUserConf;
+    type ModuleConf = HelloConf;
+    type ModuleOpt = HelloOpt;
+
+    fn name() -> ModuleStaticName {
+        ModuleStaticName(MODULE_NAME)
+    }
+    fn priority() -> ModulePriority {
+        ModulePriority::Optional
+    }
+    fn ask_required_keys() -> RequiredKeys {
+        RequiredKeys::None
+    }
+    fn have_subcommand() -> bool {
+        true
+    }
+    fn generate_module_conf(
+        _currency_name: Option<&CurrencyName>,
+        _global_conf: &<DuRsConf as DursConfTrait>::GlobalConf,
+        module_user_conf: Option<Self::ModuleUserConf>,
+    ) -> Result<(Self::ModuleConf, Option<Self::ModuleUserConf>), ModuleConfError> {
+        let mut conf = HelloConf::default();
+        if let Some(HelloUserConf {
+            greeting: Some(ref greeting),
+        }) = module_user_conf
+        {
+            conf.greeting = greeting.clone();
+        }
+        Ok((conf, module_user_conf))
+    }
+    fn exec_subcommand(
+        _soft_meta_datas: &SoftwareMetaDatas<DuRsConf>,
+        _keys: RequiredKeysContent,
+        _module_conf: Self::ModuleConf,
+        module_user_conf: Option<Self::ModuleUserConf>,
+        subcommand_args: Self::ModuleOpt,
+    ) -> Option<Self::ModuleUserConf> {
+        // The new greeting replaces the current one, the other fields are kept
+        merge_user_confs(
+            Some(HelloUserConf {
+                greeting: Some(subcommand_args.greeting),
+            }),
+            module_user_conf,
+        )
+    }
+    fn start(
+        _soft_meta_datas: &SoftwareMetaDatas<DuRsConf>,
+        _keys: RequiredKeysContent,
+        conf: Self::ModuleConf,
+        router_sender: mpsc::Sender<RouterThreadMessage<DursMsg>>,
+    ) -> Result<(), failure::Error> {
+        let receiver = router::register(
+            &router_sender,
+            Self::name(),
+            vec![ModuleRole::UserInterface],
+            vec![ModuleEvent::NewValidBlock],
+        )?;
+
+        let mut blocks_count = 0;
+        while let Ok(msg) = receiver.recv() {
+            match msg {
+                DursMsg::Stop => break,
+                DursMsg::Event {
+                    event_content: DursEvent::BlockchainEvent(ref blockchain_event),
+                    ..
+                } => {
+                    if let BlockchainEvent::StackUpValidBlock(_) = **blockchain_event {
+                        blocks_count += 1;
+                        info!("{}, {} new block(s) !", conf.greeting, blocks_count);
+                    }
+                }
+                _ => {}
+            }
+        }
+        println!("{}, {} block(s) stacked.", conf.greeting, blocks_count);
+        Ok(())
+    }
+}
+
+fn main() -> Result<(), failure::Error> {
+    let (router_sender, router_receiver) = mpsc::channel();
+    let module_thread = std::thread::spawn(move || {
+        let soft_meta_datas = SoftwareMetaDatas {
+            conf: DuRsConf::default(),
+            profile_path: std::env::temp_dir(),
+            soft_name: "hello-example",
+            soft_version: "0.1.0",
+            i18n: Default::default(),
+        };
+        let (conf, _) =
+            HelloModule::generate_module_conf(None, &soft_meta_datas.conf.get_global_conf(), None)
+                .expect("invalid conf");
+        HelloModule::start(
+            &soft_meta_datas,
+            RequiredKeysContent::None,
+            conf,
+            router_sender,
+        )
+    });
+
+    // Fake router: stop the module as soon as it is registered
+    if let Ok(RouterThreadMessage::ModuleRegistration { sender, .. }) = router_receiver.recv() {
+        sender.send(DursMsg::Stop)?;
+    }
+    module_thread.join().expect("module thread panicked")
+}
@@ -0,0 +1,75 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Configuration of a module.
+//!
+//! The configuration provided by the user (`DursModule::ModuleUserConf`) is read from
+//! several sources (configuration file, environment variables), merged with `Merge`.
+
+pub use durs_common_tools::traits::merge::Merge;
+pub use durs_conf::{get_datas_path, DuRsConf};
+pub use durs_module::{DursConfTrait, DursGlobalConfTrait, ModuleConfError};
+
+/// Merge two optional user configurations, fields of `main` take precedence
+pub fn merge_user_confs<C: Merge>(main: Option<C>, fallback: Option<C>) -> Option<C> {
+    match (main, fallback) {
+        (Some(main), Some(fallback)) => Some(main.merge(fallback)),
+        (main, fallback) => main.or(fallback),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Default, PartialEq)]
+    struct UserConf {
+        field1: Option<usize>,
+        field2: Option<usize>,
+    }
+
+    impl Merge for UserConf {
+        fn merge(self, other: Self) -> Self {
+            UserConf {
+                field1: self.field1.or(other.field1),
+                field2: self.field2.or(other.field2),
+            }
+        }
+    }
+
+    #[test]
+    fn merge_optional_user_confs() {
+        let main = UserConf {
+            field1: Some(1),
+            field2: None,
+        };
+        let fallback = UserConf {
+            field1: Some(3),
+            field2: Some(4),
+        };
+        assert_eq!(
+            Some(UserConf {
+                field1: Some(1),
+                field2: Some(4),
+            }),
+            merge_user_confs(Some(main), Some(fallback))
+        );
+        assert_eq!(
+            Some(UserConf::default()),
+            merge_user_confs(None, Some(UserConf::default()))
+        );
+        assert_eq!(None, merge_user_confs::<UserConf>(None, None));
+    }
+}
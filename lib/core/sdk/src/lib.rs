@@ -0,0 +1,89 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Stable SDK to build Dunitrust modules.
+//!
+//! This crate gathers the minimum needed to write a module: the `DursModule` trait,
+//! the messages exchanged with the other modules, the registration in the router and the
+//! configuration utilities. A module depending only on `durs-sdk` is not affected by the
+//! refactoring of the internal crates.
+//!
+//! # Stability
+//!
+//! `durs-sdk` follows semantic versioning: an item exported by this crate is only removed or
+//! modified in a new major version. The internal crates it re-exports from are not covered
+//! by this promise, so import everything through this crate.
+//!
+//! # Writing a module
+//!
+//! ```ignore
+//! use durs_sdk::prelude::*;
+//!
+//! fn start(
+//!     soft_meta_datas: &SoftwareMetaDatas<DuRsConf>,
+//!     keys: RequiredKeysContent,
+//!     conf: MyConf,
+//!     router_sender: mpsc::Sender<RouterThreadMessage<DursMsg>>,
+//! ) -> Result<(), failure::Error> {
+//!     let receiver = router::register(
+//!         &router_sender,
+//!         ModuleStaticName("my-module"),
+//!         vec![ModuleRole::UserInterface],
+//!         vec![ModuleEvent::NewValidBlock],
+//!     )?;
+//!     while let Ok(msg) = receiver.recv() {
+//!         if let DursMsg::Stop = msg {
+//!             break;
+//!         }
+//!     }
+//!     Ok(())
+//! }
+//! ```
+//!
+//! A complete module is available in the `examples` directory of this crate.
+
+#![deny(
+    clippy::option_unwrap_used,
+    clippy::result_unwrap_used,
+    missing_docs,
+    missing_debug_implementations,
+    missing_copy_implementations,
+    trivial_casts,
+    trivial_numeric_casts,
+    unsafe_code,
+    unstable_features,
+    unused_import_braces,
+    unused_qualifications
+)]
+
+pub mod conf;
+pub mod message;
+pub mod module;
+pub mod router;
+
+pub use durs_common_tools::fatal_error;
+pub use failure;
+
+/// Everything needed to implement a module
+pub mod prelude {
+    pub use crate::conf::{DuRsConf, DursConfTrait, Merge, ModuleConfError};
+    pub use crate::message::{BlockchainEvent, DursEvent, DursMsg, NetworkEvent};
+    pub use crate::module::{
+        CurrencyName, DursModule, ModuleEvent, ModulePriority, ModuleRole, ModuleStaticName,
+        RequiredKeys, RequiredKeysContent, RouterThreadMessage, SoftwareMetaDatas,
+    };
+    pub use crate::router;
+    pub use std::sync::mpsc;
+}
@@ -0,0 +1,128 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Communication with the router, which relays the messages between modules.
+
+use durs_message::events::DursEvent;
+use durs_message::DursMsg;
+use durs_module::{ModuleEvent, ModuleRole, ModuleStaticName, RouterThreadMessage};
+use failure::Fail;
+use std::sync::mpsc;
+
+/// The router is disconnected (the node is stopping)
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Fail)]
+#[fail(display = "Router disconnected")]
+pub struct RouterDisconnected;
+
+impl<T> From<mpsc::SendError<T>> for RouterDisconnected {
+    fn from(_: mpsc::SendError<T>) -> Self {
+        RouterDisconnected
+    }
+}
+
+/// Register a module in the router, returns the receiver of the messages sent to the module
+pub fn register(
+    router_sender: &mpsc::Sender<RouterThreadMessage<DursMsg>>,
+    static_name: ModuleStaticName,
+    roles: Vec<ModuleRole>,
+    events_subscription: Vec<ModuleEvent>,
+) -> Result<mpsc::Receiver<DursMsg>, RouterDisconnected> {
+    let (sender, receiver) = mpsc::channel();
+    router_sender.send(RouterThreadMessage::ModuleRegistration {
+        static_name,
+        sender,
+        roles,
+        events_subscription,
+        reserved_apis_parts: vec![],
+        endpoints: vec![],
+    })?;
+    Ok(receiver)
+}
+
+/// Send an event to the modules subscribed to `event_type`
+pub fn send_event(
+    router_sender: &mpsc::Sender<RouterThreadMessage<DursMsg>>,
+    event_from: ModuleStaticName,
+    event_type: ModuleEvent,
+    event_content: DursEvent,
+) -> Result<(), RouterDisconnected> {
+    router_sender.send(RouterThreadMessage::ModuleMessage(DursMsg::Event {
+        event_from,
+        event_type,
+        event_content,
+    }))?;
+    Ok(())
+}
+
+/// Ask the router to stop the node
+pub fn stop(
+    router_sender: &mpsc::Sender<RouterThreadMessage<DursMsg>>,
+) -> Result<(), RouterDisconnected> {
+    router_sender.send(RouterThreadMessage::ModuleMessage(DursMsg::Stop))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use durs_message::ArbitraryDatas;
+
+    #[test]
+    fn register_and_send_event() -> Result<(), RouterDisconnected> {
+        let (router_sender, router_receiver) = mpsc::channel();
+        let receiver = register(
+            &router_sender,
+            ModuleStaticName("test"),
+            vec![ModuleRole::UserInterface],
+            vec![ModuleEvent::NewValidBlock],
+        )?;
+        if let Ok(RouterThreadMessage::ModuleRegistration {
+            static_name,
+            sender,
+            events_subscription,
+            ..
+        }) = router_receiver.recv()
+        {
+            assert_eq!(ModuleStaticName("test"), static_name);
+            assert_eq!(vec![ModuleEvent::NewValidBlock], events_subscription);
+            // Messages sent by the router are received by the module
+            sender.send(DursMsg::Stop)?;
+            assert_eq!(Ok(DursMsg::Stop), receiver.recv());
+        } else {
+            panic!("expected module registration");
+        }
+
+        let event_content = DursEvent::ArbitraryDatas(ArbitraryDatas::Text("hello".to_owned()));
+        send_event(
+            &router_sender,
+            ModuleStaticName("test"),
+            ModuleEvent::NewValidBlock,
+            event_content.clone(),
+        )?;
+        if let Ok(RouterThreadMessage::ModuleMessage(DursMsg::Event {
+            event_content: received_event_content,
+            ..
+        })) = router_receiver.recv()
+        {
+            assert_eq!(event_content, received_event_content);
+        } else {
+            panic!("expected event");
+        }
+
+        drop(router_receiver);
+        assert_eq!(Err(RouterDisconnected), stop(&router_sender));
+        Ok(())
+    }
+}
@@ -61,6 +61,13 @@ pub fn get_currency_params(
     }))
 }
 
+/// Get currency name and genesis block parameters
+pub fn get_genesis_block_params(
+    datas_path: PathBuf,
+) -> Result<Option<(CurrencyName, GenesisBlockParams)>, CurrencyParamsDbError> {
+    read_currency_params_db(datas_path)
+}
+
 fn read_currency_params_db(
     mut datas_path: PathBuf,
 ) -> Result<CurrencyParamsDbDatas, CurrencyParamsDbError> {
@@ -0,0 +1,109 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Feature flags of a currency: protocol experiments activated from a given block height.
+//!
+//! A test currency (g1-test) can run an experiment ahead of g1 by activating its feature in the
+//! configuration of the nodes, the rules engine and the serializers consult the flags of the
+//! block they handle.
+
+use dubp_common_doc::BlockNumber;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "snake_case")]
+/// Protocol experiment
+pub enum ProtocolFeature {
+    /// A block can't contain several certifications from the same issuer
+    UniqueCertIssuerPerBlock,
+}
+
+impl fmt::Display for ProtocolFeature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match *self {
+                ProtocolFeature::UniqueCertIssuerPerBlock => "unique_cert_issuer_per_block",
+            }
+        )
+    }
+}
+
+impl FromStr for ProtocolFeature {
+    type Err = String;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        match source {
+            "unique_cert_issuer_per_block" => Ok(ProtocolFeature::UniqueCertIssuerPerBlock),
+            _ => Err(format!("unknown protocol feature '{}'", source)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+/// Block height from which each protocol experiment is active
+pub struct FeatureFlags(BTreeMap<ProtocolFeature, BlockNumber>);
+
+impl FeatureFlags {
+    /// Activate `feature` from the block `from_block` (included)
+    pub fn activate(&mut self, feature: ProtocolFeature, from_block: BlockNumber) {
+        self.0.insert(feature, from_block);
+    }
+    /// Check if `feature` is active in the block `block_number`
+    pub fn is_active(&self, feature: ProtocolFeature, block_number: BlockNumber) -> bool {
+        self.0
+            .get(&feature)
+            .map_or(false, |from_block| block_number >= *from_block)
+    }
+    /// Features active in the block `block_number`, sorted
+    pub fn active_features(&self, block_number: BlockNumber) -> Vec<ProtocolFeature> {
+        self.0
+            .iter()
+            .filter(|(_, from_block)| block_number >= **from_block)
+            .map(|(feature, _)| *feature)
+            .collect()
+    }
+    /// Activation height of each feature
+    pub fn activations(&self) -> &BTreeMap<ProtocolFeature, BlockNumber> {
+        &self.0
+    }
+    /// Check if no feature is activated
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_feature_flags() {
+        let mut flags = FeatureFlags::default();
+        let feature = ProtocolFeature::UniqueCertIssuerPerBlock;
+        assert!(!flags.is_active(feature, BlockNumber(0)));
+
+        flags.activate(feature, BlockNumber(100));
+        assert!(!flags.is_active(feature, BlockNumber(99)));
+        assert!(flags.is_active(feature, BlockNumber(100)));
+        assert_eq!(vec![feature], flags.active_features(BlockNumber(150)));
+        assert_eq!(Ok(feature), ProtocolFeature::from_str(&feature.to_string()));
+    }
+}
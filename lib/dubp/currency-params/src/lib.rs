@@ -30,6 +30,7 @@
 
 pub mod constants;
 pub mod db;
+pub mod feature_flags;
 pub mod genesis_block_params;
 
 use crate::constants::*;
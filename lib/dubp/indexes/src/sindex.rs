@@ -41,6 +41,24 @@ impl Into<Vec<u8>> for UniqueIdUTXOv10 {
     }
 }
 
+impl UniqueIdUTXOv10 {
+    /// Read an identifier from its binary representation
+    pub fn from_bytes(bytes: &[u8]) -> Option<UniqueIdUTXOv10> {
+        if bytes.len() != UTXO_ID_SIZE {
+            return None;
+        }
+        let mut hash = [0u8; Hash::SIZE_IN_BYTES];
+        hash.copy_from_slice(&bytes[..Hash::SIZE_IN_BYTES]);
+        let mut output_index = [0u8; 4];
+        output_index.copy_from_slice(&bytes[Hash::SIZE_IN_BYTES..UTXO_ID_SIZE]);
+
+        Some(UniqueIdUTXOv10(
+            Hash(hash),
+            OutputIndex(u32::from_be_bytes(output_index) as usize),
+        ))
+    }
+}
+
 #[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 /// Index of a V10 source
 pub enum SourceUniqueIdV10 {
@@ -49,3 +67,18 @@ pub enum SourceUniqueIdV10 {
     /// universal Dividend
     UD(PubKey, BlockNumber),
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_utxo_id_bytes_round_trip() {
+        let utxo_id = UniqueIdUTXOv10(Hash([7u8; 32]), OutputIndex(3));
+        let bytes: Vec<u8> = utxo_id.into();
+
+        assert_eq!(Some(utxo_id), UniqueIdUTXOv10::from_bytes(&bytes));
+        assert_eq!(None, UniqueIdUTXOv10::from_bytes(&bytes[1..]));
+    }
+}
@@ -0,0 +1,336 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Human-readable formatting and parsing of amounts.
+//!
+//! Amounts are stored with a unit base: the real value is `amount × 10^base` cents
+//! of the currency unit.
+
+use crate::documents::transaction::{TxAmount, TxBase};
+
+/// Separators used to display amounts
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Separators {
+    /// Decimal separator
+    pub decimal: char,
+    /// Thousands separator (digits are not grouped if `None`)
+    pub thousands: Option<char>,
+}
+
+impl Separators {
+    /// English separators (1,234.56)
+    pub const ENGLISH: Separators = Separators {
+        decimal: '.',
+        thousands: Some(','),
+    };
+    /// French separators (1 234,56)
+    pub const FRENCH: Separators = Separators {
+        decimal: ',',
+        thousands: Some(' '),
+    };
+}
+
+/// Unit of displayed amounts
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AmountUnit {
+    /// Currency unit (symbol), amounts are displayed with 2 decimals
+    Currency(String),
+    /// Amounts are displayed relatively to the universal dividend
+    Ud {
+        /// Amount of the universal dividend
+        amount: TxAmount,
+        /// Unit base of the universal dividend
+        base: TxBase,
+    },
+}
+
+/// Format of displayed amounts
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AmountFormat {
+    /// Unit
+    pub unit: AmountUnit,
+    /// Separators
+    pub separators: Separators,
+}
+
+/// Error on amount parsing
+#[derive(Copy, Clone, Debug, Fail, PartialEq, Eq)]
+pub enum AmountParseError {
+    /// Invalid format
+    #[fail(display = "invalid amount format")]
+    InvalidFormat,
+    /// More than 2 decimals
+    #[fail(display = "too many decimals (2 at most)")]
+    TooManyDecimals,
+    /// Amount can't be expressed in the unit base
+    #[fail(display = "amount can't be expressed with unit base {}", _0)]
+    PrecisionLoss(usize),
+    /// Amount too large
+    #[fail(display = "amount too large")]
+    Overflow,
+}
+
+/// Symbol of the currency unit
+pub fn currency_symbol(currency_name: &str) -> String {
+    match currency_name {
+        "g1" => "Ğ1".to_owned(),
+        "g1-test" => "ĞT".to_owned(),
+        name => name.to_uppercase(),
+    }
+}
+
+/// Value in cents of the currency unit (`amount × 10^base`)
+pub fn amount_in_cents(amount: TxAmount, base: TxBase) -> Option<i128> {
+    10i128
+        .checked_pow(base.0 as u32)
+        .and_then(|multiplier| (amount.0 as i128).checked_mul(multiplier))
+}
+
+/// Format amount (ex: "1,234.56 Ğ1" or "12.30 DU")
+pub fn format_amount(amount: TxAmount, base: TxBase, format: &AmountFormat) -> String {
+    let cents = amount_in_cents(amount, base).unwrap_or_else(|| saturated(amount.0 < 0));
+    let (hundredths, unit) = match format.unit {
+        AmountUnit::Currency(ref symbol) => (cents, symbol.as_str()),
+        AmountUnit::Ud {
+            amount: ud_amount,
+            base: ud_base,
+        } => {
+            let hundredths = match amount_in_cents(ud_amount, ud_base) {
+                Some(ud_cents) if ud_cents > 0 => cents
+                    .checked_mul(100)
+                    .map(|n| rounded_div(n, ud_cents))
+                    .unwrap_or_else(|| saturated(cents < 0)),
+                _ => 0,
+            };
+            (hundredths, "DU")
+        }
+    };
+    format!(
+        "{} {}",
+        format_hundredths(hundredths, format.separators),
+        unit
+    )
+}
+
+/// Parse amount and express it in the given unit base.
+/// The unit and the thousands separators are optional.
+pub fn parse_amount(
+    source: &str,
+    base: TxBase,
+    format: &AmountFormat,
+) -> Result<TxAmount, AmountParseError> {
+    let unit = match format.unit {
+        AmountUnit::Currency(ref symbol) => symbol.as_str(),
+        AmountUnit::Ud { .. } => "DU",
+    };
+    let source = source.trim();
+    let source = source.strip_suffix(unit).unwrap_or(source);
+    let digits: String = source
+        .chars()
+        .filter(|c| !c.is_whitespace() && Some(*c) != format.separators.thousands)
+        .map(|c| {
+            if c == format.separators.decimal {
+                '.'
+            } else {
+                c
+            }
+        })
+        .collect();
+    let (negative, digits) = match digits.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, &digits[..]),
+    };
+    let mut parts = digits.splitn(2, '.');
+    let integer_part = parts.next().unwrap_or("");
+    let decimal_part = parts.next().unwrap_or("");
+    if (integer_part.is_empty() && decimal_part.is_empty())
+        || !integer_part.chars().all(|c| c.is_ascii_digit())
+        || !decimal_part.chars().all(|c| c.is_ascii_digit())
+    {
+        return Err(AmountParseError::InvalidFormat);
+    }
+    if decimal_part.len() > 2 {
+        return Err(AmountParseError::TooManyDecimals);
+    }
+    let integer: i128 = if integer_part.is_empty() {
+        0
+    } else {
+        integer_part
+            .parse()
+            .map_err(|_| AmountParseError::Overflow)?
+    };
+    let decimals: i128 = format!("{:0<2}", decimal_part)
+        .parse()
+        .map_err(|_| AmountParseError::InvalidFormat)?;
+    let hundredths = integer
+        .checked_mul(100)
+        .and_then(|n| n.checked_add(decimals))
+        .ok_or(AmountParseError::Overflow)?;
+
+    let cents = match format.unit {
+        AmountUnit::Currency(_) => hundredths,
+        AmountUnit::Ud {
+            amount: ud_amount,
+            base: ud_base,
+        } => {
+            let ud_cents = amount_in_cents(ud_amount, ud_base).ok_or(AmountParseError::Overflow)?;
+            rounded_div(
+                hundredths
+                    .checked_mul(ud_cents)
+                    .ok_or(AmountParseError::Overflow)?,
+                100,
+            )
+        }
+    };
+    let divisor = amount_in_cents(TxAmount(1), base).ok_or(AmountParseError::Overflow)?;
+    if cents % divisor != 0 {
+        return Err(AmountParseError::PrecisionLoss(base.0));
+    }
+    let amount = cents / divisor;
+    if amount > isize::MAX as i128 {
+        return Err(AmountParseError::Overflow);
+    }
+    Ok(TxAmount(if negative {
+        -(amount as isize)
+    } else {
+        amount as isize
+    }))
+}
+
+fn saturated(negative: bool) -> i128 {
+    if negative {
+        i128::MIN
+    } else {
+        i128::MAX
+    }
+}
+
+/// Division rounded to the nearest integer (divisor must be positive)
+fn rounded_div(numerator: i128, divisor: i128) -> i128 {
+    let rounded = (numerator.abs() / divisor)
+        + if (numerator.abs() % divisor) * 2 >= divisor {
+            1
+        } else {
+            0
+        };
+    if numerator < 0 {
+        -rounded
+    } else {
+        rounded
+    }
+}
+
+fn format_hundredths(hundredths: i128, separators: Separators) -> String {
+    let abs_hundredths = hundredths.unsigned_abs();
+    let integer_digits = (abs_hundredths / 100).to_string();
+    let mut formatted = String::with_capacity(integer_digits.len() * 2);
+    if hundredths < 0 {
+        formatted.push('-');
+    }
+    // Position of the next thousands separator (digits are grouped by 3 from the right)
+    let mut next_separator = (integer_digits.len() - 1) % 3 + 1;
+    for (i, digit) in integer_digits.chars().enumerate() {
+        if i == next_separator {
+            if let Some(thousands_separator) = separators.thousands {
+                formatted.push(thousands_separator);
+            }
+            next_separator += 3;
+        }
+        formatted.push(digit);
+    }
+    formatted.push(separators.decimal);
+    formatted.push_str(&format!("{:02}", abs_hundredths % 100));
+    formatted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn g1(separators: Separators) -> AmountFormat {
+        AmountFormat {
+            unit: AmountUnit::Currency(currency_symbol("g1")),
+            separators,
+        }
+    }
+
+    #[test]
+    fn format_amounts() {
+        assert_eq!(
+            "1,234.56 Ğ1",
+            format_amount(TxAmount(123_456), TxBase(0), &g1(Separators::ENGLISH))
+        );
+        assert_eq!(
+            "123 450,00 Ğ1",
+            format_amount(TxAmount(12_345), TxBase(3), &g1(Separators::FRENCH))
+        );
+        assert_eq!(
+            "-0.05 Ğ1",
+            format_amount(TxAmount(-5), TxBase(0), &g1(Separators::ENGLISH))
+        );
+        let du_format = AmountFormat {
+            unit: AmountUnit::Ud {
+                amount: TxAmount(1_002),
+                base: TxBase(0),
+            },
+            separators: Separators::ENGLISH,
+        };
+        assert_eq!(
+            "12.50 DU",
+            format_amount(TxAmount(12_525), TxBase(0), &du_format)
+        );
+    }
+
+    #[test]
+    fn parse_amounts() {
+        let english = g1(Separators::ENGLISH);
+        assert_eq!(
+            Ok(TxAmount(123_456)),
+            parse_amount("1,234.56 Ğ1", TxBase(0), &english)
+        );
+        assert_eq!(Ok(TxAmount(1_200)), parse_amount("12", TxBase(0), &english));
+        assert_eq!(
+            Ok(TxAmount(1_200)),
+            parse_amount("1 200", TxBase(2), &english)
+        );
+        assert_eq!(
+            Err(AmountParseError::PrecisionLoss(1)),
+            parse_amount("1.05", TxBase(1), &english)
+        );
+        assert_eq!(
+            Err(AmountParseError::TooManyDecimals),
+            parse_amount("1.055", TxBase(0), &english)
+        );
+        assert_eq!(
+            Err(AmountParseError::InvalidFormat),
+            parse_amount("1.2a", TxBase(0), &english)
+        );
+        assert_eq!(
+            Ok(TxAmount(-123_456)),
+            parse_amount("-1 234,56", TxBase(0), &g1(Separators::FRENCH))
+        );
+        let du_format = AmountFormat {
+            unit: AmountUnit::Ud {
+                amount: TxAmount(1_000),
+                base: TxBase(0),
+            },
+            separators: Separators::ENGLISH,
+        };
+        assert_eq!(
+            Ok(TxAmount(2_500)),
+            parse_amount("2.5 DU", TxBase(0), &du_format)
+        );
+    }
+}
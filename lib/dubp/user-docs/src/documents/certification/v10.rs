@@ -108,21 +108,21 @@ pub struct CertificationDocumentV10 {
 /// identity document for jsonification
 pub struct CertificationDocumentV10Stringified {
     /// Name of the currency.
-    currency: String,
+    pub currency: String,
     /// Document issuer
-    issuer: String,
+    pub issuer: String,
     /// issuer of target identity.
-    target: String,
+    pub target: String,
     /// Username of target identity
-    identity_username: String,
+    pub identity_username: String,
     /// Target Identity document blockstamp.
-    identity_blockstamp: String,
+    pub identity_blockstamp: String,
     /// Target Identity document signature.
-    identity_sig: String,
+    pub identity_sig: String,
     /// Blockstamp
-    blockstamp: String,
+    pub blockstamp: String,
     /// Document signature
-    signature: String,
+    pub signature: String,
 }
 
 impl ToStringObject for CertificationDocumentV10 {
@@ -390,6 +390,18 @@ impl TransactionDocumentV10 {
             self.hash.expect("unreach")
         }
     }
+    /// Get transaction locktime
+    pub fn get_locktime(&self) -> u64 {
+        self.locktime
+    }
+    /// Get inputs unlocks
+    pub fn get_unlocks(&self) -> &[TransactionInputUnlocksV10] {
+        &self.unlocks
+    }
+    /// Get transaction comment
+    pub fn get_comment(&self) -> &str {
+        &self.comment
+    }
     /// Lightens the transaction (for example to store it while minimizing the space required)
     /// WARNING: do not remove the hash as it's necessary to reverse the transaction !
     pub fn reduce(&mut self) {
@@ -35,6 +35,7 @@ extern crate pretty_assertions;
 #[macro_use]
 extern crate serde_derive;
 
+pub mod amount;
 pub mod documents;
 pub mod parsers;
 
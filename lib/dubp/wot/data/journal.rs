@@ -0,0 +1,445 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Append-only journal persisting a `WebOfTrust`.
+//!
+//! The journal directory contains a snapshot of the whole graph and a log of all the
+//! operations applied since this snapshot. Each successful operation is appended to the log,
+//! and the graph is rewritten (then the log is emptied) only every `snapshot_interval`
+//! operations. At open, the snapshot is loaded and the log is replayed.
+//!
+//! Snapshot and log are tagged with a generation number, so a log that was not reset after
+//! a snapshot (crash between the two writes) is detected and ignored. A truncated record at
+//! the end of the log (crash during an append) is discarded.
+
+use super::{NewLinkResult, RemLinkResult, WebOfTrust, WotId};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Snapshot file name
+pub static SNAPSHOT_FILE_NAME: &str = "wot.snapshot";
+
+/// Journal file name
+pub static JOURNAL_FILE_NAME: &str = "wot.journal";
+
+/// Default number of journaled operations between two snapshots
+pub static DEFAULT_SNAPSHOT_INTERVAL: &usize = &10_000;
+
+/// Operation recorded in the journal
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WotOperation {
+    /// A node has been added
+    NodeAdded,
+    /// The last node has been removed
+    NodeRemoved,
+    /// A link has been added (source, target)
+    LinkAdded(WotId, WotId),
+    /// A link has been removed (source, target)
+    LinkRemoved(WotId, WotId),
+    /// The enabled state of a node has changed
+    EnabledChanged(WotId, bool),
+    /// The maximum number of links per node has changed
+    MaxLinkChanged(usize),
+}
+
+impl WotOperation {
+    /// Apply operation on a web of trust
+    pub fn apply<W: WebOfTrust>(self, wot: &mut W) {
+        match self {
+            WotOperation::NodeAdded => {
+                wot.add_node();
+            }
+            WotOperation::NodeRemoved => {
+                wot.rem_node();
+            }
+            WotOperation::LinkAdded(source, target) => {
+                wot.add_link(source, target);
+            }
+            WotOperation::LinkRemoved(source, target) => {
+                wot.rem_link(source, target);
+            }
+            WotOperation::EnabledChanged(id, enabled) => {
+                wot.set_enabled(id, enabled);
+            }
+            WotOperation::MaxLinkChanged(max_link) => wot.set_max_link(max_link),
+        }
+    }
+}
+
+/// Journal error
+#[derive(Debug)]
+pub enum WotJournalError {
+    /// Error with the file system
+    FileSystemError(io::Error),
+    /// Serialization/Deserialization error
+    SerdeError(bincode::Error),
+}
+
+impl From<io::Error> for WotJournalError {
+    fn from(e: io::Error) -> Self {
+        WotJournalError::FileSystemError(e)
+    }
+}
+
+impl From<bincode::Error> for WotJournalError {
+    fn from(e: bincode::Error) -> Self {
+        WotJournalError::SerdeError(e)
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Snapshot<W> {
+    generation: u64,
+    wot: W,
+}
+
+/// Web of trust persisted with an append-only journal
+#[derive(Debug)]
+pub struct WotJournal<W: WebOfTrust> {
+    dir_path: PathBuf,
+    generation: u64,
+    journal: BufWriter<File>,
+    ops_count: usize,
+    snapshot_interval: usize,
+    wot: W,
+}
+
+impl<W: WebOfTrust> WotJournal<W> {
+    /// Open journal stored in directory `path` (created if it does not exist)
+    /// and rebuild the web of trust from the last snapshot and the operations log.
+    pub fn open(path: &Path) -> Result<WotJournal<W>, WotJournalError> {
+        fs::create_dir_all(path)?;
+
+        let snapshot_path = path.join(SNAPSHOT_FILE_NAME);
+        let Snapshot {
+            generation,
+            mut wot,
+        } = if snapshot_path.exists() {
+            bincode::deserialize_from(BufReader::new(File::open(&snapshot_path)?))?
+        } else {
+            Snapshot {
+                generation: 0,
+                wot: W::default(),
+            }
+        };
+
+        let journal_path = path.join(JOURNAL_FILE_NAME);
+        let (ops, valid_len) = if journal_path.exists() {
+            read_journal(&journal_path, generation)?
+        } else {
+            (vec![], 0)
+        };
+
+        let journal = if valid_len == 0 {
+            create_journal(&journal_path, generation)?
+        } else {
+            let file = OpenOptions::new().append(true).open(&journal_path)?;
+            // Discard a possibly truncated last record
+            file.set_len(valid_len)?;
+            file
+        };
+
+        let ops_count = ops.len();
+        for op in ops {
+            op.apply(&mut wot);
+        }
+
+        Ok(WotJournal {
+            dir_path: path.to_owned(),
+            generation,
+            journal: BufWriter::new(journal),
+            ops_count,
+            snapshot_interval: *DEFAULT_SNAPSHOT_INTERVAL,
+            wot,
+        })
+    }
+    /// Set the number of journaled operations between two snapshots
+    pub fn set_snapshot_interval(&mut self, snapshot_interval: usize) {
+        self.snapshot_interval = snapshot_interval;
+    }
+    /// Get web of trust
+    #[inline]
+    pub fn wot(&self) -> &W {
+        &self.wot
+    }
+    /// Number of operations journaled since the last snapshot
+    #[inline]
+    pub fn ops_count(&self) -> usize {
+        self.ops_count
+    }
+    /// Add a new node.
+    pub fn add_node(&mut self) -> Result<WotId, WotJournalError> {
+        let id = self.wot.add_node();
+        self.append(WotOperation::NodeAdded)?;
+        Ok(id)
+    }
+    /// Remove the last node.
+    pub fn rem_node(&mut self) -> Result<Option<WotId>, WotJournalError> {
+        if self.wot.size() == 0 {
+            return Ok(None);
+        }
+        let new_top = self.wot.rem_node();
+        self.append(WotOperation::NodeRemoved)?;
+        Ok(new_top)
+    }
+    /// Set the maximum number of links per user.
+    pub fn set_max_link(&mut self, max_link: usize) -> Result<(), WotJournalError> {
+        self.wot.set_max_link(max_link);
+        self.append(WotOperation::MaxLinkChanged(max_link))
+    }
+    /// Set the enabled state of given node.
+    pub fn set_enabled(
+        &mut self,
+        id: WotId,
+        enabled: bool,
+    ) -> Result<Option<bool>, WotJournalError> {
+        let result = self.wot.set_enabled(id, enabled);
+        if result.is_some() {
+            self.append(WotOperation::EnabledChanged(id, enabled))?;
+        }
+        Ok(result)
+    }
+    /// Try to add a link from the source to the target.
+    pub fn add_link(
+        &mut self,
+        source: WotId,
+        target: WotId,
+    ) -> Result<NewLinkResult, WotJournalError> {
+        let result = self.wot.add_link(source, target);
+        if let NewLinkResult::Ok(_) = result {
+            self.append(WotOperation::LinkAdded(source, target))?;
+        }
+        Ok(result)
+    }
+    /// Try to remove a link from the source to the target.
+    pub fn rem_link(
+        &mut self,
+        source: WotId,
+        target: WotId,
+    ) -> Result<RemLinkResult, WotJournalError> {
+        let result = self.wot.rem_link(source, target);
+        if let RemLinkResult::Removed(_) = result {
+            self.append(WotOperation::LinkRemoved(source, target))?;
+        }
+        Ok(result)
+    }
+    /// Flush journaled operations to the disk
+    pub fn flush(&mut self) -> Result<(), WotJournalError> {
+        self.journal.flush()?;
+        self.journal.get_ref().sync_data()?;
+        Ok(())
+    }
+    /// Write the whole web of trust in a new snapshot and empty the journal
+    pub fn snapshot(&mut self) -> Result<(), WotJournalError> {
+        let generation = self.generation + 1;
+        self.journal.flush()?;
+
+        let snapshot_path = self.dir_path.join(SNAPSHOT_FILE_NAME);
+        let tmp_snapshot_path = self.dir_path.join(format!("{}.tmp", SNAPSHOT_FILE_NAME));
+        {
+            let mut file = BufWriter::new(File::create(&tmp_snapshot_path)?);
+            bincode::serialize_into(
+                &mut file,
+                &Snapshot {
+                    generation,
+                    wot: &self.wot,
+                },
+            )?;
+            file.flush()?;
+            file.get_ref().sync_all()?;
+        }
+        fs::rename(&tmp_snapshot_path, &snapshot_path)?;
+
+        self.journal = BufWriter::new(create_journal(
+            &self.dir_path.join(JOURNAL_FILE_NAME),
+            generation,
+        )?);
+        self.generation = generation;
+        self.ops_count = 0;
+
+        Ok(())
+    }
+    fn append(&mut self, op: WotOperation) -> Result<(), WotJournalError> {
+        let record = bincode::serialize(&op)?;
+        self.journal
+            .write_all(&(record.len() as u32).to_le_bytes())?;
+        self.journal.write_all(&record)?;
+        self.ops_count += 1;
+
+        if self.ops_count >= self.snapshot_interval {
+            self.snapshot()
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Create an empty journal file for given generation
+fn create_journal(journal_path: &Path, generation: u64) -> Result<File, WotJournalError> {
+    let mut file = File::create(journal_path)?;
+    file.write_all(&generation.to_le_bytes())?;
+    file.sync_all()?;
+    Ok(file)
+}
+
+/// Read all complete records of a journal file.
+/// Returns the operations and the length of the valid part of the file
+/// (0 if the journal does not belong to the current snapshot).
+fn read_journal(
+    journal_path: &Path,
+    generation: u64,
+) -> Result<(Vec<WotOperation>, u64), WotJournalError> {
+    let mut bytes = Vec::new();
+    File::open(journal_path)?.read_to_end(&mut bytes)?;
+
+    if bytes.len() < 8 {
+        return Ok((vec![], 0));
+    }
+    let mut gen_bytes = [0u8; 8];
+    gen_bytes.copy_from_slice(&bytes[..8]);
+    if u64::from_le_bytes(gen_bytes) != generation {
+        return Ok((vec![], 0));
+    }
+
+    let mut ops = Vec::new();
+    let mut cursor = 8;
+    while bytes.len() >= cursor + 4 {
+        let mut len_bytes = [0u8; 4];
+        len_bytes.copy_from_slice(&bytes[cursor..cursor + 4]);
+        let record_end = cursor + 4 + u32::from_le_bytes(len_bytes) as usize;
+        if bytes.len() < record_end {
+            break;
+        }
+        match bincode::deserialize(&bytes[cursor + 4..record_end]) {
+            Ok(op) => ops.push(op),
+            Err(_) => break,
+        }
+        cursor = record_end;
+    }
+
+    Ok((ops, cursor as u64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::rusty::RustyWebOfTrust;
+    use crate::data::HasLinkResult;
+
+    #[test]
+    fn replay_journal_on_open() -> Result<(), WotJournalError> {
+        let tmp_dir = tempfile::tempdir()?;
+
+        {
+            let mut journal = WotJournal::<RustyWebOfTrust>::open(tmp_dir.path())?;
+            journal.set_max_link(3)?;
+            assert_eq!(journal.add_node()?, WotId(0));
+            assert_eq!(journal.add_node()?, WotId(1));
+            assert_eq!(journal.add_node()?, WotId(2));
+            assert_eq!(journal.add_link(WotId(0), WotId(1))?, NewLinkResult::Ok(1));
+            assert_eq!(journal.add_link(WotId(2), WotId(1))?, NewLinkResult::Ok(2));
+            assert_eq!(
+                journal.rem_link(WotId(0), WotId(1))?,
+                RemLinkResult::Removed(1)
+            );
+            assert_eq!(journal.set_enabled(WotId(2), false)?, Some(false));
+            // Failed operations are not journaled
+            assert_eq!(
+                journal.add_link(WotId(0), WotId(0))?,
+                NewLinkResult::SelfLinkingForbidden()
+            );
+            assert_eq!(journal.ops_count(), 8);
+            journal.flush()?;
+        }
+
+        let journal = WotJournal::<RustyWebOfTrust>::open(tmp_dir.path())?;
+        let wot = journal.wot();
+        assert_eq!(wot.size(), 3);
+        assert_eq!(wot.get_max_link(), 3);
+        assert_eq!(wot.has_link(WotId(0), WotId(1)), HasLinkResult::Link(false));
+        assert_eq!(wot.has_link(WotId(2), WotId(1)), HasLinkResult::Link(true));
+        assert_eq!(wot.is_enabled(WotId(2)), Some(false));
+
+        Ok(())
+    }
+
+    #[test]
+    fn snapshot_and_discard_truncated_record() -> Result<(), WotJournalError> {
+        let tmp_dir = tempfile::tempdir()?;
+
+        {
+            let mut journal = WotJournal::<RustyWebOfTrust>::open(tmp_dir.path())?;
+            journal.set_snapshot_interval(3);
+            journal.add_node()?;
+            journal.add_node()?;
+            journal.add_node()?;
+            // Snapshot done, journal emptied
+            assert_eq!(journal.ops_count(), 0);
+            journal.add_link(WotId(0), WotId(1))?;
+            journal.flush()?;
+        }
+
+        // Simulate a crash in the middle of an append
+        let journal_path = tmp_dir.path().join(JOURNAL_FILE_NAME);
+        let mut file = OpenOptions::new().append(true).open(&journal_path)?;
+        file.write_all(&[42u8, 0, 0, 0, 1])?;
+
+        let mut journal = WotJournal::<RustyWebOfTrust>::open(tmp_dir.path())?;
+        assert_eq!(journal.wot().size(), 3);
+        assert_eq!(
+            journal.wot().has_link(WotId(0), WotId(1)),
+            HasLinkResult::Link(true)
+        );
+        assert_eq!(journal.ops_count(), 1);
+
+        // New records are appended after the last valid one
+        journal.add_link(WotId(1), WotId(2))?;
+        journal.flush()?;
+        let journal = WotJournal::<RustyWebOfTrust>::open(tmp_dir.path())?;
+        assert_eq!(
+            journal.wot().has_link(WotId(1), WotId(2)),
+            HasLinkResult::Link(true)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn ignore_stale_journal() -> Result<(), WotJournalError> {
+        let tmp_dir = tempfile::tempdir()?;
+        let journal_path = tmp_dir.path().join(JOURNAL_FILE_NAME);
+
+        let stale_journal = {
+            let mut journal = WotJournal::<RustyWebOfTrust>::open(tmp_dir.path())?;
+            journal.add_node()?;
+            journal.add_node()?;
+            journal.flush()?;
+            let stale_journal = fs::read(&journal_path)?;
+            journal.snapshot()?;
+            stale_journal
+        };
+
+        // Simulate a crash between snapshot writing and journal reset
+        fs::write(&journal_path, stale_journal)?;
+
+        let journal = WotJournal::<RustyWebOfTrust>::open(tmp_dir.path())?;
+        assert_eq!(journal.wot().size(), 2);
+        assert_eq!(journal.ops_count(), 0);
+
+        Ok(())
+    }
+}
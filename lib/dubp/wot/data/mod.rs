@@ -17,10 +17,13 @@
 //! `LegacyWebOfTrust` is almost a translation of the legacy C++ coden while
 //! `RustyWebOfTrust` is a brand new implementation with a more "rusty" style.
 
+pub mod journal;
 pub mod rusty;
+pub mod wotb;
 
 use serde::de::{self, Deserialize, DeserializeOwned, Deserializer, Visitor};
 use serde::{Serialize, Serializer};
+use std::collections::HashSet;
 use std::fmt::{self, Debug};
 
 /// Wrapper for a node id.
@@ -165,6 +168,35 @@ pub trait WebOfTrust: Clone + Debug + Default + DeserializeOwned + Send + Serial
     /// Try to remove a link from the source to the target.
     fn rem_link(&mut self, source: WotId, target: WotId) -> RemLinkResult;
 
+    /// Try to remove several links, returns the result of each removal.
+    fn remove_links_batch(&mut self, links: &[(WotId, WotId)]) -> Vec<RemLinkResult> {
+        links
+            .iter()
+            .map(|(source, target)| self.rem_link(*source, *target))
+            .collect()
+    }
+
+    /// Remove a whole bucket of expired links, returns the number of links removed.
+    /// Links that no longer exist are skipped, but if a source or a target is unknown,
+    /// nothing is removed and the faulty result is returned.
+    fn expire_pass(&mut self, bucket: &[(WotId, WotId)]) -> Result<usize, RemLinkResult> {
+        for (source, target) in bucket {
+            match self.has_link(*source, *target) {
+                HasLinkResult::UnknownSource() => return Err(RemLinkResult::UnknownSource()),
+                HasLinkResult::UnknownTarget() => return Err(RemLinkResult::UnknownTarget()),
+                HasLinkResult::Link(_) => {}
+            }
+        }
+
+        let mut removed_count = 0;
+        for result in self.remove_links_batch(bucket) {
+            if let RemLinkResult::Removed(_) = result {
+                removed_count += 1;
+            }
+        }
+        Ok(removed_count)
+    }
+
     /// Test if there is a link from the source to the target.
     fn has_link(&self, source: WotId, target: WotId) -> HasLinkResult;
 
@@ -184,4 +216,46 @@ pub trait WebOfTrust: Clone + Debug + Default + DeserializeOwned + Send + Serial
 
     /// Get non sentries array.
     fn get_non_sentries(&self, sentry_requirement: usize) -> Vec<WotId>;
+
+    /// Get the set of nodes reachable from `node` within `k` steps,
+    /// following links in either direction (the node itself is excluded).
+    /// Returns `None` if this node doesn't exist.
+    fn neighborhood(&self, node: WotId, k: u32) -> Option<HashSet<WotId>> {
+        if node.0 >= self.size() {
+            return None;
+        }
+
+        // Issued links are not indexed, compute them from received links
+        let mut links_target = vec![Vec::new(); self.size()];
+        for target in 0..self.size() {
+            for source in self.get_links_source(WotId(target))? {
+                links_target[source.0].push(WotId(target));
+            }
+        }
+
+        let mut area = HashSet::new();
+        area.insert(node);
+        let mut border = vec![node];
+        for _ in 0..k {
+            let mut next_border = Vec::new();
+            for id in border {
+                for neighbor in self
+                    .get_links_source(id)?
+                    .into_iter()
+                    .chain(links_target[id.0].iter().cloned())
+                {
+                    if area.insert(neighbor) {
+                        next_border.push(neighbor);
+                    }
+                }
+            }
+            if next_border.is_empty() {
+                break;
+            }
+            border = next_border;
+        }
+        area.remove(&node);
+
+        Some(area)
+    }
 }
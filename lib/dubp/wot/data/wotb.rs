@@ -0,0 +1,209 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Import/export of the binary file written by the wotb C++ addon of duniter-ts (`wotb.bin`).
+//!
+//! All integers are 32 bits little-endian signed integers:
+//!
+//! ```text
+//! max_cert | nodes_count | node_0 | node_1 | ...
+//! node_i = enabled (1 byte) | issued_count | links_count | source_0 | source_1 | ...
+//! ```
+//!
+//! The links of a node are the certifications it received, identified by their source.
+
+use super::{NewLinkResult, WebOfTrust, WotId};
+use std::convert::TryFrom;
+use std::io;
+use std::path::Path;
+
+/// wotb file error
+#[derive(Debug)]
+pub enum WotbFileError {
+    /// Error with the file system
+    FileSystemError(io::Error),
+    /// The file ends before the announced datas
+    UnexpectedEnd,
+    /// The file contains datas after the last node
+    TrailingBytes(usize),
+    /// A count or a node id is negative
+    NegativeValue(i32),
+    /// A link can't be added to the web of trust
+    InvalidLink(WotId, WotId, NewLinkResult),
+    /// The issued count of a node doesn't match its links
+    WrongIssuedCount(WotId),
+}
+
+impl From<io::Error> for WotbFileError {
+    fn from(e: io::Error) -> Self {
+        WotbFileError::FileSystemError(e)
+    }
+}
+
+struct WotbReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> WotbReader<'a> {
+    fn read_bool(&mut self) -> Result<bool, WotbFileError> {
+        let (byte, rest) = self
+            .bytes
+            .split_first()
+            .ok_or(WotbFileError::UnexpectedEnd)?;
+        self.bytes = rest;
+        Ok(*byte != 0)
+    }
+    fn read_usize(&mut self) -> Result<usize, WotbFileError> {
+        if self.bytes.len() < 4 {
+            return Err(WotbFileError::UnexpectedEnd);
+        }
+        let (int_bytes, rest) = self.bytes.split_at(4);
+        self.bytes = rest;
+        let value = i32::from_le_bytes([int_bytes[0], int_bytes[1], int_bytes[2], int_bytes[3]]);
+        usize::try_from(value).map_err(|_| WotbFileError::NegativeValue(value))
+    }
+}
+
+fn write_usize(bytes: &mut Vec<u8>, value: usize) {
+    bytes.extend_from_slice(&(value as i32).to_le_bytes());
+}
+
+/// Load a web of trust from the content of a wotb file
+pub fn from_wotb_bytes<W: WebOfTrust>(bytes: &[u8]) -> Result<W, WotbFileError> {
+    let mut reader = WotbReader { bytes };
+    let max_cert = reader.read_usize()?;
+    let nodes_count = reader.read_usize()?;
+
+    // Links are added once all the nodes exist, with a maximum large enough to accept the
+    // certifications issued before a decrease of `max_cert`.
+    let mut wot = W::new(usize::max_value());
+    for _ in 0..nodes_count {
+        wot.add_node();
+    }
+    let mut issued_counts = Vec::new();
+    for target in 0..nodes_count {
+        let target = WotId(target);
+        let enabled = reader.read_bool()?;
+        issued_counts.push(reader.read_usize()?);
+        let links_count = reader.read_usize()?;
+        for _ in 0..links_count {
+            let source = WotId(reader.read_usize()?);
+            match wot.add_link(source, target) {
+                NewLinkResult::Ok(_) => {}
+                result => return Err(WotbFileError::InvalidLink(source, target, result)),
+            }
+        }
+        wot.set_enabled(target, enabled);
+    }
+    if !reader.bytes.is_empty() {
+        return Err(WotbFileError::TrailingBytes(reader.bytes.len()));
+    }
+    for (id, issued_count) in issued_counts.into_iter().enumerate() {
+        if wot.issued_count(WotId(id)) != Some(issued_count) {
+            return Err(WotbFileError::WrongIssuedCount(WotId(id)));
+        }
+    }
+    wot.set_max_link(max_cert);
+
+    Ok(wot)
+}
+
+/// Write a web of trust in the wotb format (sources of each node are sorted)
+pub fn to_wotb_bytes<W: WebOfTrust>(wot: &W) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    write_usize(&mut bytes, wot.get_max_link());
+    write_usize(&mut bytes, wot.size());
+    for id in 0..wot.size() {
+        let id = WotId(id);
+        let mut sources = wot.get_links_source(id).unwrap_or_default();
+        sources.sort_unstable_by_key(|source| source.0);
+        bytes.push(wot.is_enabled(id).unwrap_or_default() as u8);
+        write_usize(&mut bytes, wot.issued_count(id).unwrap_or_default());
+        write_usize(&mut bytes, sources.len());
+        for source in sources {
+            write_usize(&mut bytes, source.0);
+        }
+    }
+    bytes
+}
+
+/// Read a web of trust from a wotb file
+pub fn read_wotb_file<W: WebOfTrust>(file_path: &Path) -> Result<W, WotbFileError> {
+    from_wotb_bytes(&durs_common_tools::fns::bin_file::read_bin_file(file_path)?)
+}
+
+/// Write a web of trust in a wotb file
+pub fn write_wotb_file<W: WebOfTrust>(wot: &W, file_path: &Path) -> Result<(), WotbFileError> {
+    durs_common_tools::fns::bin_file::write_bin_file(file_path, &to_wotb_bytes(wot))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::rusty::RustyWebOfTrust;
+    use crate::data::HasLinkResult;
+
+    /// wotb file of 4 nodes with max_cert = 3: node 3 is disabled,
+    /// links are 1 -> 0, 0 -> 1, 2 -> 1, 3 -> 2 and 0 -> 3.
+    static FIXTURE_PATH: &str = "tests/wotb_legacy.bin";
+
+    #[test]
+    fn test_wotb_file_round_trip() -> Result<(), WotbFileError> {
+        let wot: RustyWebOfTrust = read_wotb_file(Path::new(FIXTURE_PATH))?;
+
+        assert_eq!(4, wot.size());
+        assert_eq!(3, wot.get_max_link());
+        assert_eq!(vec![WotId(3)], wot.get_disabled());
+        assert_eq!(Some(2), wot.issued_count(WotId(0)));
+        for (source, target) in &[(1, 0), (0, 1), (2, 1), (3, 2), (0, 3)] {
+            assert_eq!(
+                HasLinkResult::Link(true),
+                wot.has_link(WotId(*source), WotId(*target))
+            );
+        }
+        assert_eq!(HasLinkResult::Link(false), wot.has_link(WotId(1), WotId(2)));
+
+        let tmp_dir = tempfile::tempdir()?;
+        let file_path = tmp_dir.path().join("wotb.bin");
+        write_wotb_file(&wot, &file_path)?;
+        assert_eq!(
+            durs_common_tools::fns::bin_file::read_bin_file(Path::new(FIXTURE_PATH))?,
+            durs_common_tools::fns::bin_file::read_bin_file(&file_path)?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_wotb_bytes() {
+        let bytes = to_wotb_bytes(&RustyWebOfTrust::new(3));
+        assert!(from_wotb_bytes::<RustyWebOfTrust>(&bytes).is_ok());
+
+        let mut truncated = to_wotb_bytes(&RustyWebOfTrust::new(3));
+        truncated.pop();
+        match from_wotb_bytes::<RustyWebOfTrust>(&truncated) {
+            Err(WotbFileError::UnexpectedEnd) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        let mut trailing = bytes;
+        trailing.push(0);
+        match from_wotb_bytes::<RustyWebOfTrust>(&trailing) {
+            Err(WotbFileError::TrailingBytes(1)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
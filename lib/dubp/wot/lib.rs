@@ -43,6 +43,7 @@
 pub mod data;
 pub mod operations;
 
+pub use crate::data::journal::WotJournal;
 pub use crate::data::{WebOfTrust, WotId};
 
 #[cfg(test)]
@@ -52,6 +53,7 @@ mod tests {
     use crate::operations::centrality::*;
     use crate::operations::distance::*;
     use crate::operations::path::*;
+    use std::collections::HashSet;
     use std::path::Path;
 
     /// Test translated from https://github.com/duniter/wot/blob/master/tests/test.js
@@ -250,6 +252,45 @@ mod tests {
          * 3 --> 2
          */
         assert_eq!(wot.size(), 12);
+        assert_eq!(wot.neighborhood(WotId(23), 1), None);
+        assert_eq!(
+            wot.neighborhood(WotId(0), 1),
+            Some([WotId(2), WotId(5)].iter().cloned().collect())
+        );
+        assert_eq!(
+            wot.neighborhood(WotId(0), 2),
+            Some([WotId(2), WotId(3), WotId(5)].iter().cloned().collect())
+        );
+        assert_eq!(
+            wot.neighborhood(WotId(3), 2),
+            Some([WotId(0), WotId(1), WotId(2)].iter().cloned().collect())
+        );
+        assert_eq!(wot.neighborhood(WotId(11), 3), Some(HashSet::new()));
+
+        // - we expire links on a copy of the wot
+        let mut expired_wot = wot.clone();
+        assert_eq!(
+            expired_wot.remove_links_batch(&[(WotId(3), WotId(1)), (WotId(3), WotId(1))]),
+            vec![RemLinkResult::Removed(0), RemLinkResult::UnknownCert(0)]
+        );
+        assert_eq!(
+            expired_wot.expire_pass(&[(WotId(2), WotId(0)), (WotId(23), WotId(0))]),
+            Err(RemLinkResult::UnknownSource())
+        );
+        assert_eq!(
+            expired_wot.has_link(WotId(2), WotId(0)),
+            HasLinkResult::Link(true)
+        );
+        assert_eq!(
+            expired_wot.expire_pass(&[
+                (WotId(2), WotId(0)),
+                (WotId(5), WotId(0)),
+                (WotId(3), WotId(1))
+            ]),
+            Ok(2)
+        );
+        assert_eq!(expired_wot.get_links_source(WotId(0)), Some(vec![]));
+        assert_eq!(expired_wot.issued_count(WotId(3)), Some(1));
         assert_eq!(wot.get_sentries(1).len(), 1);
         assert_eq!(wot.get_sentries(1)[0], WotId(2));
         assert_eq!(wot.get_sentries(2).len(), 0);
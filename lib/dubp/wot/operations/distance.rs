@@ -17,6 +17,7 @@
 
 use crate::data::WebOfTrust;
 use crate::data::WotId;
+use dup_crypto::hashs::Hash;
 use rayon::prelude::*;
 use std::collections::HashSet;
 
@@ -50,6 +51,49 @@ pub struct WotDistance {
     pub outdistanced: bool,
 }
 
+/// Parameters of the sentries sampling for `DistanceCalculator::compute_sampled_distance`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct WotDistanceSampling {
+    /// Maximum number of sentries evaluated.
+    pub sample_size: u32,
+    /// Required confidence (between 0 and 1) to conclude from the sample.
+    pub confidence: f64,
+}
+
+/// Results of `DistanceCalculator::compute_sampled_distance` when sentries were sampled.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct WotSampledDistance {
+    /// Sentries count
+    pub sentries: u32,
+    /// Sampled sentries count
+    pub sampled_sentries: u32,
+    /// Sampled sentries reached count
+    pub sampled_success: u32,
+    /// Maximum deviation between the sampled and the real success ratio at the required confidence
+    pub margin: f64,
+    /// Is the node outdistanced ? `None` if the sample doesn't allow to conclude.
+    pub outdistanced: Option<bool>,
+}
+
+/// Results of `DistanceCalculator::compute_sampled_distance`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum WotDistanceEvaluation {
+    /// There is not more sentries than the sample size, the distance is exact.
+    Exact(WotDistance),
+    /// The distance rule was evaluated against a sample of sentries.
+    Sampled(WotSampledDistance),
+}
+
+impl WotDistanceEvaluation {
+    /// Is the node outdistanced ? `None` if a sampled evaluation doesn't allow to conclude.
+    pub fn outdistanced(&self) -> Option<bool> {
+        match self {
+            WotDistanceEvaluation::Exact(distance) => Some(distance.outdistanced),
+            WotDistanceEvaluation::Sampled(distance) => distance.outdistanced,
+        }
+    }
+}
+
 /// Compute distance between nodes of a `WebOfTrust`.
 pub trait DistanceCalculator<T: WebOfTrust> {
     /// Compute distance between a node and the network.
@@ -68,6 +112,59 @@ pub trait DistanceCalculator<T: WebOfTrust> {
     /// Test if a node is outdistanced in the network.
     /// Returns `Node` if this node doesn't exist.
     fn is_outdistanced(&self, wot: &T, params: WotDistanceParameters) -> Option<bool>;
+
+    /// Compute distance between a node and a pseudo-random sample of sentries.
+    /// The sample only depends on `seed` (typically the current blockstamp),
+    /// so all nodes draw the same sentries.
+    /// Returns `None` if this node doesn't exist.
+    fn compute_sampled_distance(
+        &self,
+        wot: &T,
+        params: WotDistanceParameters,
+        sampling: WotDistanceSampling,
+        seed: &[u8],
+    ) -> Option<WotDistanceEvaluation>;
+}
+
+/// Get the nodes reached from `node` in `step_max` steps, and those reached at the last step.
+fn reached_area<T: WebOfTrust + Sync>(
+    wot: &T,
+    node: WotId,
+    step_max: u32,
+) -> (HashSet<WotId>, HashSet<WotId>) {
+    let mut area = HashSet::new();
+    area.insert(node);
+    let mut border = HashSet::new();
+    border.insert(node);
+
+    for _ in 0..step_max {
+        border = border
+            .par_iter()
+            .map(|&id| {
+                wot.get_links_source(id)
+                    .expect("get_links_source must return a value")
+                    .iter()
+                    .filter(|source| !area.contains(source))
+                    .cloned()
+                    .collect::<HashSet<_>>()
+            })
+            .reduce(HashSet::new, |mut acc, sources| {
+                for source in sources {
+                    acc.insert(source);
+                }
+                acc
+            });
+        area.extend(border.iter());
+    }
+
+    (area, border)
+}
+
+/// Rank of a sentry in the sample drawn with `seed`
+fn sample_rank(seed: &[u8], sentry: WotId) -> Hash {
+    let mut datas = seed.to_vec();
+    datas.extend_from_slice(&(sentry.0 as u64).to_le_bytes());
+    Hash::compute(&datas)
 }
 
 /// Calculate distances between 2 members in a `WebOfTrust`.
@@ -87,30 +184,7 @@ impl<T: WebOfTrust + Sync> DistanceCalculator<T> for RustyDistanceCalculator {
             return None;
         }
 
-        let mut area = HashSet::new();
-        area.insert(node);
-        let mut border = HashSet::new();
-        border.insert(node);
-
-        for _ in 0..step_max {
-            border = border
-                .par_iter()
-                .map(|&id| {
-                    wot.get_links_source(id)
-                        .expect("get_links_source must return a value")
-                        .iter()
-                        .filter(|source| !area.contains(source))
-                        .cloned()
-                        .collect::<HashSet<_>>()
-                })
-                .reduce(HashSet::new, |mut acc, sources| {
-                    for source in sources {
-                        acc.insert(source);
-                    }
-                    acc
-                });
-            area.extend(border.iter());
-        }
+        let (area, border) = reached_area(wot, node, step_max);
 
         let sentries: Vec<_> = wot.get_sentries(sentry_requirement as usize);
         let mut success = area.iter().filter(|n| sentries.contains(n)).count() as u32;
@@ -138,6 +212,60 @@ impl<T: WebOfTrust + Sync> DistanceCalculator<T> for RustyDistanceCalculator {
         Self::compute_distance(&self, wot, params).map(|result| result.outdistanced)
     }
 
+    fn compute_sampled_distance(
+        &self,
+        wot: &T,
+        params: WotDistanceParameters,
+        sampling: WotDistanceSampling,
+        seed: &[u8],
+    ) -> Option<WotDistanceEvaluation> {
+        if params.node.0 >= wot.size() {
+            return None;
+        }
+
+        let mut sentries: Vec<(Hash, WotId)> = wot
+            .get_sentries(params.sentry_requirement as usize)
+            .into_iter()
+            .filter(|sentry| *sentry != params.node)
+            .map(|sentry| (sample_rank(seed, sentry), sentry))
+            .collect();
+        if sentries.len() <= sampling.sample_size as usize {
+            return self
+                .compute_distance(wot, params)
+                .map(WotDistanceEvaluation::Exact);
+        }
+        let sentries_count = sentries.len() as u32;
+        sentries.sort_unstable_by_key(|(rank, _)| *rank);
+        sentries.truncate(sampling.sample_size as usize);
+
+        let (area, _) = reached_area(wot, params.node, params.step_max);
+        let sampled_success = sentries
+            .iter()
+            .filter(|(_, sentry)| area.contains(sentry))
+            .count() as u32;
+
+        // Hoeffding inequality: P(|sampled_ratio - ratio| >= margin) <= 2 * exp(-2 * n * margin²)
+        let sampled_ratio = f64::from(sampled_success) / f64::from(sampling.sample_size);
+        let margin = ((2.0 / (1.0 - sampling.confidence)).ln()
+            / (2.0 * f64::from(sampling.sample_size)))
+        .sqrt();
+        let outdistanced = if sampled_ratio - margin >= params.x_percent {
+            Some(false)
+        } else if sampled_ratio + margin < params.x_percent {
+            Some(true)
+        } else {
+            None
+        };
+
+        Some(WotDistanceEvaluation::Sampled(WotSampledDistance {
+            sentries: sentries_count,
+            sampled_sentries: sampling.sample_size,
+            sampled_success,
+            margin,
+            outdistanced,
+        }))
+    }
+
     fn compute_distances(
         &self,
         wot: &T,
@@ -184,3 +312,85 @@ impl<T: WebOfTrust + Sync> DistanceCalculator<T> for RustyDistanceCalculator {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::rusty::RustyWebOfTrust;
+
+    /// Node 0 certified by a ring of `sentries` sentries
+    fn ring_wot(sentries: usize) -> RustyWebOfTrust {
+        let mut wot = RustyWebOfTrust::new(2);
+        for _ in 0..=sentries {
+            wot.add_node();
+        }
+        for i in 1..=sentries {
+            wot.add_link(WotId(i), WotId(0));
+            wot.add_link(WotId(i), WotId(i % sentries + 1));
+        }
+        wot
+    }
+
+    #[test]
+    fn sampled_distance() {
+        let wot = ring_wot(400);
+        let sampling = WotDistanceSampling {
+            sample_size: 100,
+            confidence: 0.95,
+        };
+        let params = WotDistanceParameters {
+            node: WotId(0),
+            sentry_requirement: 1,
+            step_max: 1,
+            x_percent: 0.8,
+        };
+
+        // All sentries certify node 0
+        let evaluation = RustyDistanceCalculator
+            .compute_sampled_distance(&wot, params, sampling, b"42-seed")
+            .expect("node 0 must exist");
+        if let WotDistanceEvaluation::Sampled(sampled) = evaluation {
+            assert_eq!(400, sampled.sentries);
+            assert_eq!(100, sampled.sampled_success);
+        } else {
+            panic!("expect sampled evaluation");
+        }
+        assert_eq!(Some(false), evaluation.outdistanced());
+
+        // Node 1 is only certified by 2 nodes
+        let params = WotDistanceParameters {
+            node: WotId(1),
+            ..params
+        };
+        let evaluation = RustyDistanceCalculator
+            .compute_sampled_distance(&wot, params, sampling, b"42-seed")
+            .expect("node 1 must exist");
+        assert_eq!(Some(true), evaluation.outdistanced());
+        // The sample only depends on the seed
+        assert_eq!(
+            Some(evaluation),
+            RustyDistanceCalculator.compute_sampled_distance(&wot, params, sampling, b"42-seed")
+        );
+
+        // Not more sentries than the sample size
+        let wot = ring_wot(50);
+        assert_eq!(
+            RustyDistanceCalculator
+                .compute_distance(&wot, params)
+                .map(WotDistanceEvaluation::Exact),
+            RustyDistanceCalculator.compute_sampled_distance(&wot, params, sampling, b"42-seed")
+        );
+        assert_eq!(
+            None,
+            RustyDistanceCalculator.compute_sampled_distance(
+                &wot,
+                WotDistanceParameters {
+                    node: WotId(51),
+                    ..params
+                },
+                sampling,
+                b"42-seed"
+            )
+        );
+    }
+}
@@ -0,0 +1,286 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//! Test vectors of the distance rule, in a JSON format shared with duniter-ts.
+//!
+//! A test vector contains the state of a `WebOfTrust` at a given block, the distance
+//! parameters and the expected distance of each member. Both implementations must
+//! compute the same distances from the same vectors.
+
+use crate::data::{NewLinkResult, WebOfTrust, WotId};
+use crate::operations::distance::{DistanceCalculator, WotDistance, WotDistanceParameters};
+use serde::{Deserialize, Serialize};
+
+/// Node of a distance test vector
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DistanceVectorNode {
+    /// Public key of the identity, allows each implementation to find its own node id
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pubkey: Option<String>,
+    /// Is the node a member ?
+    pub enabled: bool,
+    /// Nodes certifying this node, sorted by id
+    pub certifiers: Vec<WotId>,
+}
+
+/// Expected distance of a member
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DistanceVectorResult {
+    /// Evaluated node
+    pub node: WotId,
+    /// Sentries count
+    pub sentries: u32,
+    /// Reached sentries count
+    pub success: u32,
+    /// Sentries reached at the last step count
+    pub success_at_border: u32,
+    /// Reached nodes count
+    pub reached: u32,
+    /// Nodes reached at the last step count
+    pub reached_at_border: u32,
+    /// Is the node outdistanced ?
+    pub outdistanced: bool,
+}
+
+impl DistanceVectorResult {
+    fn new(node: WotId, distance: WotDistance) -> Self {
+        DistanceVectorResult {
+            node,
+            sentries: distance.sentries,
+            success: distance.success,
+            success_at_border: distance.success_at_border,
+            reached: distance.reached,
+            reached_at_border: distance.reached_at_border,
+            outdistanced: distance.outdistanced,
+        }
+    }
+}
+
+/// Distance rule test vector
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DistanceVector {
+    /// Currency name
+    pub currency: String,
+    /// Blockstamp of the block at which the `WebOfTrust` is exported
+    pub blockstamp: String,
+    /// Maximum number of links a node can issue (sigStock)
+    pub max_links: usize,
+    /// Links count received AND issued to be a sentry
+    pub sentry_requirement: u32,
+    /// Maximum distance to the sentries (stepMax)
+    pub step_max: u32,
+    /// Required proportion of reached sentries (xpercent)
+    pub x_percent: f64,
+    /// Nodes of the `WebOfTrust`, the index of a node is its id
+    pub nodes: Vec<DistanceVectorNode>,
+    /// Expected distance of each member
+    pub results: Vec<DistanceVectorResult>,
+}
+
+/// Difference between the expected and the computed distance of a member
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DistanceVectorMismatch {
+    /// Expected distance
+    pub expected: DistanceVectorResult,
+    /// Computed distance (`None` if the node doesn't exist)
+    pub computed: Option<DistanceVectorResult>,
+}
+
+impl DistanceVector {
+    /// Export the distance of all members of `wot`.
+    /// `pubkeys` gives the public key of each node, it can be empty.
+    #[allow(clippy::too_many_arguments)]
+    pub fn export<T: WebOfTrust, C: DistanceCalculator<T>>(
+        calculator: &C,
+        wot: &T,
+        currency: String,
+        blockstamp: String,
+        pubkeys: &[String],
+        sentry_requirement: u32,
+        step_max: u32,
+        x_percent: f64,
+    ) -> DistanceVector {
+        let nodes = (0..wot.size())
+            .map(|id| {
+                let mut certifiers = wot.get_links_source(WotId(id)).unwrap_or_default();
+                certifiers.sort_unstable_by_key(|certifier| certifier.0);
+                DistanceVectorNode {
+                    pubkey: pubkeys.get(id).cloned(),
+                    enabled: wot.is_enabled(WotId(id)).unwrap_or(false),
+                    certifiers,
+                }
+            })
+            .collect();
+        let results = wot
+            .get_enabled()
+            .into_iter()
+            .filter_map(|node| {
+                calculator
+                    .compute_distance(
+                        wot,
+                        WotDistanceParameters {
+                            node,
+                            sentry_requirement,
+                            step_max,
+                            x_percent,
+                        },
+                    )
+                    .map(|distance| DistanceVectorResult::new(node, distance))
+            })
+            .collect();
+
+        DistanceVector {
+            currency,
+            blockstamp,
+            max_links: wot.get_max_link(),
+            sentry_requirement,
+            step_max,
+            x_percent,
+            nodes,
+            results,
+        }
+    }
+    /// Rebuild the `WebOfTrust` of the test vector.
+    /// Returns the link that can't be added if the vector is inconsistent.
+    pub fn to_wot<T: WebOfTrust>(&self) -> Result<T, (WotId, WotId, NewLinkResult)> {
+        let mut wot = T::new(self.max_links);
+        for _ in &self.nodes {
+            wot.add_node();
+        }
+        for (id, node) in self.nodes.iter().enumerate() {
+            for certifier in &node.certifiers {
+                match wot.add_link(*certifier, WotId(id)) {
+                    NewLinkResult::Ok(_) => {}
+                    result => return Err((*certifier, WotId(id), result)),
+                }
+            }
+            wot.set_enabled(WotId(id), node.enabled);
+        }
+        Ok(wot)
+    }
+    /// Replay the test vector, returns the members whose distance differs from the expected one
+    pub fn replay<T: WebOfTrust, C: DistanceCalculator<T>>(
+        &self,
+        calculator: &C,
+        wot: &T,
+    ) -> Vec<DistanceVectorMismatch> {
+        self.results
+            .iter()
+            .filter_map(|expected| {
+                let computed = calculator
+                    .compute_distance(
+                        wot,
+                        WotDistanceParameters {
+                            node: expected.node,
+                            sentry_requirement: self.sentry_requirement,
+                            step_max: self.step_max,
+                            x_percent: self.x_percent,
+                        },
+                    )
+                    .map(|distance| DistanceVectorResult::new(expected.node, distance));
+                if computed == Some(*expected) {
+                    None
+                } else {
+                    Some(DistanceVectorMismatch {
+                        expected: *expected,
+                        computed,
+                    })
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::rusty::RustyWebOfTrust;
+    use crate::operations::distance::RustyDistanceCalculator;
+    use std::fs;
+
+    /// Replay all the test vectors of `tests/distance_vectors`, including those exported by duniter-ts
+    #[test]
+    fn replay_distance_vectors() {
+        let mut vectors_count = 0;
+        for entry in fs::read_dir("tests/distance_vectors").expect("fail to read vectors dir") {
+            let path = entry.expect("fail to read vectors dir").path();
+            let vector: DistanceVector =
+                serde_json::from_str(&fs::read_to_string(&path).expect("fail to read vector file"))
+                    .expect("fail to parse vector file");
+            let wot: RustyWebOfTrust = vector.to_wot().expect("inconsistent vector");
+
+            assert_eq!(
+                Vec::<DistanceVectorMismatch>::new(),
+                vector.replay(&RustyDistanceCalculator, &wot),
+                "{}",
+                path.display()
+            );
+
+            // Export the rebuilt wot give back the same vector
+            let pubkeys: Vec<String> = vector
+                .nodes
+                .iter()
+                .filter_map(|node| node.pubkey.clone())
+                .collect();
+            assert_eq!(
+                vector,
+                DistanceVector::export(
+                    &RustyDistanceCalculator,
+                    &wot,
+                    vector.currency.clone(),
+                    vector.blockstamp.clone(),
+                    &pubkeys,
+                    vector.sentry_requirement,
+                    vector.step_max,
+                    vector.x_percent,
+                ),
+                "{}",
+                path.display()
+            );
+            vectors_count += 1;
+        }
+        assert!(vectors_count > 0);
+    }
+
+    #[test]
+    fn replay_detects_mismatch() {
+        let mut wot = RustyWebOfTrust::new(3);
+        for _ in 0..3 {
+            wot.add_node();
+        }
+        wot.add_link(WotId(1), WotId(0));
+        wot.add_link(WotId(2), WotId(1));
+        wot.add_link(WotId(0), WotId(2));
+        let mut vector = DistanceVector::export(
+            &RustyDistanceCalculator,
+            &wot,
+            "test".to_owned(),
+            "0-0".to_owned(),
+            &[],
+            1,
+            1,
+            0.8,
+        );
+        assert_eq!(3, vector.results.len());
+        assert!(vector.replay(&RustyDistanceCalculator, &wot).is_empty());
+
+        vector.results[0].outdistanced = !vector.results[0].outdistanced;
+        let mismatches = vector.replay(&RustyDistanceCalculator, &wot);
+        assert_eq!(1, mismatches.len());
+        assert_eq!(vector.results[0], mismatches[0].expected);
+    }
+}
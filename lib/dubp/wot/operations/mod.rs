@@ -18,4 +18,5 @@
 pub mod centrality;
 pub mod density;
 pub mod distance;
+pub mod distance_vectors;
 pub mod path;
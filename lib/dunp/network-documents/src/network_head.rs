@@ -123,6 +123,24 @@ impl NetworkHead {
             _ => fatal_error!("This HEAD version is not supported !"),
         }
     }
+    /// Get software name of head issuer
+    pub fn software(&self) -> String {
+        match *self {
+            NetworkHead::V2(ref head_v2) => match head_v2.message_v2 {
+                NetworkHeadMessage::V2(ref head_message_v2) => head_message_v2.software.clone(),
+            },
+            _ => fatal_error!("This HEAD version is not supported !"),
+        }
+    }
+    /// Get software version of head issuer
+    pub fn soft_version(&self) -> String {
+        match *self {
+            NetworkHead::V2(ref head_v2) => match head_v2.message_v2 {
+                NetworkHeadMessage::V2(ref head_message_v2) => head_message_v2.soft_version.clone(),
+            },
+            _ => fatal_error!("This HEAD version is not supported !"),
+        }
+    }
     /// Change uid of head issuer
     pub fn set_uid(&mut self, uid: &str) {
         match *self {
@@ -30,12 +30,45 @@ use pest::Parser;
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 /// Peer card V10
 pub struct PeerCardV10 {
+    /// Currency name
+    pub currency_name: CurrencyName,
     /// Peer card Blockstamp
     pub blockstamp: Blockstamp,
     /// Peer card issuer
     pub issuer: PubKey,
     /// Peer card endpoints list
     pub endpoints: Vec<EndpointEnum>,
+    /// Signature
+    pub sig: Option<Sig>,
+}
+
+impl TextSignable for PeerCardV10 {
+    fn as_signable_text(&self) -> String {
+        format!(
+            "Version: 10\nType: Peer\nCurrency: {currency}\nPublicKey: {pubkey}\nBlock: {blockstamp}\nEndpoints:\n{endpoints}\n",
+            currency = self.currency_name.0,
+            pubkey = self.issuer.to_base58(),
+            blockstamp = self.blockstamp,
+            endpoints = self
+                .endpoints
+                .iter()
+                .map(|ep| match ep {
+                    EndpointEnum::V1(ep_v1) => ep_v1.raw_endpoint.clone(),
+                    EndpointEnum::V2(ep_v2) => ep_v2.to_string(),
+                })
+                .collect::<Vec<String>>()
+                .join("\n"),
+        )
+    }
+    fn issuer_pubkey(&self) -> PubKey {
+        self.issuer
+    }
+    fn signature(&self) -> Option<Sig> {
+        self.sig
+    }
+    fn set_signature(&mut self, signature: Sig) {
+        self.sig = Some(signature);
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -329,6 +362,52 @@ mod tests {
         }
     }
 
+    #[test]
+    fn peer_card_v10_sign_and_verify() {
+        let keypair1 = keypair1();
+        let signator = SignatorEnum::Ed25519(unwrap!(
+            keypair1.generate_signator(),
+            "Fail to gen signator"
+        ));
+        let issuer = PubKey::Ed25519(keypair1.public_key());
+        let mut peer_card_v10 = PeerCardV10 {
+            currency_name: CurrencyName(String::from("g1")),
+            blockstamp: unwrap!(Blockstamp::from_string(
+                "50-000003B3C39F1CF0A7B3D6A9D8B7D2A1C69BC83EA4D62E29F5E4A5C8AF4BAD12"
+            )),
+            issuer,
+            endpoints: vec![EndpointEnum::V1(unwrap!(EndpointV1::parse_from_raw(
+                "WS2P 11111111 g1.durs.info 443 ws2p",
+                issuer,
+                0,
+                0
+            )))],
+            sig: None,
+        };
+        assert_eq!(
+            format!(
+                "Version: 10
+Type: Peer
+Currency: g1
+PublicKey: {}
+Block: 50-000003B3C39F1CF0A7B3D6A9D8B7D2A1C69BC83EA4D62E29F5E4A5C8AF4BAD12
+Endpoints:
+WS2P 11111111 g1.durs.info 443 ws2p
+",
+                issuer
+            ),
+            peer_card_v10.as_signable_text()
+        );
+        // Sign
+        peer_card_v10
+            .sign(&signator)
+            .expect("fail to sign peer card");
+        // Verify signature
+        peer_card_v10
+            .verify()
+            .expect("Fail to verify PeerCardV10 !");
+    }
+
     #[test]
     fn peer_card_v11_sign_and_verify() {
         let keypair1 = keypair1();
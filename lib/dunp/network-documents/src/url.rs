@@ -182,6 +182,18 @@ impl Url {
             Url::UrlWithoutScheme(url_without_scheme) => url_without_scheme.path(),
         }
     }
+    pub fn host(&self) -> Option<String> {
+        match self {
+            Url::Url(url) => url.host_str().map(ToOwned::to_owned),
+            Url::UrlWithoutScheme(url_without_scheme) => Some(url_without_scheme.host.to_string()),
+        }
+    }
+    pub fn port(&self) -> Option<u16> {
+        match self {
+            Url::Url(url) => url.port(),
+            Url::UrlWithoutScheme(url_without_scheme) => url_without_scheme.port,
+        }
+    }
     pub fn to_listenable_addr(&self, default_scheme: &str) -> std::io::Result<Vec<SocketAddr>> {
         self.to_listenable_addr_with_default_port(default_scheme, default_port)
     }
@@ -16,6 +16,7 @@
 //! Define blocks entities and requests
 
 pub mod fork_tree;
+pub mod header;
 
 use crate::constants::*;
 use crate::*;
@@ -141,7 +142,11 @@ pub fn get_block_in_local_blockchain<DB: BcDbInReadTx>(
     db: &DB,
     block_number: BlockNumber,
 ) -> Result<Option<BlockDocument>, DbError> {
-    Ok(get_db_block_in_local_blockchain(db, block_number)?.map(|dal_block| dal_block.block))
+    db.db()
+        .get_int_store(MAIN_BLOCKS)
+        .get(db.r(), block_number.0)?
+        .map(header::block_document_from_db_value)
+        .transpose()
 }
 
 /// Get block in local blockchain
@@ -168,7 +173,7 @@ pub fn get_blocks_in_local_blockchain<DB: BcDbInReadTx>(
     let mut current_block_number = first_block_number;
 
     while let Some(v) = bc_store.get(db.r(), current_block_number.0)? {
-        blocks.push(from_db_value::<BlockDb>(v)?.block);
+        blocks.push(header::block_document_from_db_value(v)?);
         count -= 1;
         if count > 0 {
             current_block_number = BlockNumber(current_block_number.0 + 1);
@@ -202,15 +207,15 @@ pub fn get_current_frame<DB: BcDbInReadTx>(
 ) -> Result<HashMap<PubKey, usize>, DbError> {
     let frame_begin = current_block.number().0 - u32::from(current_block.current_frame_size());
 
-    let blocks = get_blocks_in_local_blockchain(
+    let headers = header::get_block_headers_in_local_blockchain(
         db,
         BlockNumber(frame_begin),
         current_block.current_frame_size().into(),
     )?;
 
     let mut current_frame: HashMap<PubKey, usize> = HashMap::new();
-    for block in blocks {
-        let issuer = block.issuers()[0];
+    for header in headers {
+        let issuer = header.issuers()[0];
         let issuer_count_blocks = if let Some(issuer_count_blocks) = current_frame.get(&issuer) {
             issuer_count_blocks + 1
         } else {
@@ -0,0 +1,224 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Partial reads of stored blocks.
+//!
+//! A `BlockDb` is stored as a sequential binary value: the header fields of the block come first,
+//! followed by the block content (documents and transactions), then by the `expire_certs` field.
+//! Reading a struct that mirrors only the leading fields stops after them, so the rest of the
+//! value is never decoded.
+
+use crate::constants::*;
+use crate::*;
+use dubp_block_doc::BlockDocument;
+use dubp_common_doc::{BlockHash, BlockNumber, Blockstamp};
+use dubp_currency_params::genesis_block_params::v10::BlockV10Parameters;
+use dubp_currency_params::CurrencyName;
+use dup_crypto::hashs::Hash;
+use dup_crypto::keys::*;
+use durs_common_tools::UsizeSer32;
+use durs_dbs_tools::DbError;
+use serde::Deserialize;
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+/// Header of a block V10, laid out exactly like the first fields of `BlockDocumentV10`
+pub struct BlockHeaderV10Db {
+    /// Version of the block
+    pub version: UsizeSer32,
+    /// Nonce
+    pub nonce: u64,
+    /// number
+    pub number: BlockNumber,
+    /// Minimal proof of work difficulty
+    pub pow_min: UsizeSer32,
+    /// Local time of the block issuer
+    pub time: u64,
+    /// Average time
+    pub median_time: u64,
+    /// Members count
+    pub members_count: UsizeSer32,
+    /// Monetary mass
+    pub monetary_mass: u64,
+    /// Unit base (power of ten)
+    pub unit_base: UsizeSer32,
+    /// Number of compute members in the current frame
+    pub issuers_count: UsizeSer32,
+    /// Current frame size (in blocks)
+    pub issuers_frame: UsizeSer32,
+    /// Current frame variation buffer
+    pub issuers_frame_var: isize,
+    /// Currency.
+    pub currency: CurrencyName,
+    /// Document issuer (there should be only one).
+    pub issuers: Vec<PubKey>,
+    /// Document signature (there should be only one).
+    pub signatures: Vec<Sig>,
+    /// The hash is None, when the block is generated but the proof of work has not yet started
+    pub hash: Option<BlockHash>,
+    /// Currency parameters (only in genesis block)
+    pub parameters: Option<BlockV10Parameters>,
+    /// Hash of the previous block
+    pub previous_hash: Option<Hash>,
+    /// Issuer of the previous block
+    pub previous_issuer: Option<PubKey>,
+    /// Hash of the deterministic content of the block
+    pub inner_hash: Option<Hash>,
+    /// Amount of new dividend created at this block, None if no dividend is created at this block
+    pub dividend: Option<UsizeSer32>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+/// Header of a block, laid out exactly like the start of `BlockDocument`
+pub enum BlockHeaderDb {
+    /// Header of a block V10
+    V10(BlockHeaderV10Db),
+}
+
+impl BlockHeaderDb {
+    /// Get block number
+    pub fn number(&self) -> BlockNumber {
+        match self {
+            BlockHeaderDb::V10(header) => header.number,
+        }
+    }
+    /// Get block hash
+    pub fn hash(&self) -> Option<BlockHash> {
+        match self {
+            BlockHeaderDb::V10(header) => header.hash,
+        }
+    }
+    /// Get blockstamp
+    pub fn blockstamp(&self) -> Blockstamp {
+        Blockstamp {
+            id: self.number(),
+            hash: self.hash().unwrap_or_default(),
+        }
+    }
+    /// Get common time (median time)
+    pub fn common_time(&self) -> u64 {
+        match self {
+            BlockHeaderDb::V10(header) => header.median_time,
+        }
+    }
+    /// Get current frame size
+    pub fn current_frame_size(&self) -> UsizeSer32 {
+        match self {
+            BlockHeaderDb::V10(header) => header.issuers_frame,
+        }
+    }
+    /// Get block issuers
+    pub fn issuers(&self) -> &[PubKey] {
+        match self {
+            BlockHeaderDb::V10(header) => &header.issuers,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+/// Leading fields of a stored `BlockDb`, without the block content
+struct BlockDbHeaderOnly {
+    block: BlockHeaderDb,
+}
+
+#[derive(Deserialize)]
+/// Leading fields of a stored `BlockDb`, without `expire_certs`
+struct BlockDbDocumentOnly {
+    block: BlockDocument,
+}
+
+/// Read only the header of a stored block
+#[inline]
+pub fn block_header_from_db_value(v: DbValue) -> Result<BlockHeaderDb, DbError> {
+    Ok(from_db_value::<BlockDbHeaderOnly>(v)?.block)
+}
+
+/// Read only the document of a stored block
+#[inline]
+pub fn block_document_from_db_value(v: DbValue) -> Result<BlockDocument, DbError> {
+    Ok(from_db_value::<BlockDbDocumentOnly>(v)?.block)
+}
+
+/// Get block header in local blockchain
+pub fn get_block_header_in_local_blockchain<DB: BcDbInReadTx>(
+    db: &DB,
+    block_number: BlockNumber,
+) -> Result<Option<BlockHeaderDb>, DbError> {
+    db.db()
+        .get_int_store(MAIN_BLOCKS)
+        .get(db.r(), block_number.0)?
+        .map(block_header_from_db_value)
+        .transpose()
+}
+
+/// Get several block headers in local blockchain
+pub fn get_block_headers_in_local_blockchain<DB: BcDbInReadTx>(
+    db: &DB,
+    first_block_number: BlockNumber,
+    count: u32,
+) -> Result<Vec<BlockHeaderDb>, DbError> {
+    let bc_store = db.db().get_int_store(MAIN_BLOCKS);
+    let mut headers = Vec::with_capacity(count as usize);
+
+    for block_number in first_block_number.0..first_block_number.0.saturating_add(count) {
+        if let Some(v) = bc_store.get(db.r(), block_number)? {
+            headers.push(block_header_from_db_value(v)?);
+        } else {
+            break;
+        }
+    }
+    Ok(headers)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::blocks::BlockDb;
+    use dubp_block_doc::block::BlockDocumentTrait;
+    use dubp_common_doc::traits::Document;
+    use durs_wot::WotId;
+    use std::collections::HashMap;
+
+    #[test]
+    fn read_block_header_and_document_only() -> Result<(), DbError> {
+        let mut block_v10 = dubp_blocks_tests_tools::mocks::gen_mock_normal_block_v10();
+        block_v10.hash = Some(BlockHash(Hash([42; 32])));
+        let block = BlockDocument::V10(block_v10);
+        let mut expire_certs = HashMap::new();
+        expire_certs.insert((WotId(1), WotId(2)), BlockNumber(3));
+        let bin_block_db = durs_dbs_tools::to_bytes(&BlockDb {
+            block: block.clone(),
+            expire_certs: Some(expire_certs),
+        })?;
+
+        let header = block_header_from_db_value(DbValue::Blob(&bin_block_db))?;
+        assert_eq!(block.number(), header.number());
+        assert_eq!(block.blockstamp(), header.blockstamp());
+        assert_eq!(block.common_time(), header.common_time());
+        assert_eq!(block.current_frame_size(), header.current_frame_size());
+        assert_eq!(block.issuers(), header.issuers());
+        let BlockDocument::V10(ref block_v10) = block;
+        let BlockHeaderDb::V10(ref header_v10) = header;
+        assert_eq!(block_v10.previous_hash, header_v10.previous_hash);
+        assert_eq!(block_v10.dividend, header_v10.dividend);
+
+        assert_eq!(
+            block,
+            block_document_from_db_value(DbValue::Blob(&bin_block_db))?
+        );
+
+        Ok(())
+    }
+}
@@ -18,6 +18,16 @@
 /// Default page size for requests responses
 pub static DEFAULT_PAGE_SIZE: &usize = &50;
 
+/// Number of most recent blocks whose transactions are kept in light storage mode
+pub static LIGHT_STORAGE_TX_WINDOW: &u32 = &1_000;
+
+/// Version of the schema of the blockchain database
+/// (must be incremented when the stores or the format of their values change)
+pub static CURRENT_DB_VERSION: &usize = &1;
+
+/// Name of the wot database file (in the blockchain database folder)
+pub static WOT_DB_FILENAME: &str = "wot.db";
+
 ////////////////////////////////
 // BLOCKCHAIN DATABASE STORES //
 ////////////////////////////////
@@ -49,9 +59,20 @@ pub static CERTS_BY_CREATED_BLOCK: &str = "cert";
 /// Unused universal dividends
 pub static DIVIDENDS: &str = "du";
 
+/// Universal dividends history (BlockNumber in big endian, CurrentUdDb)
+pub static UDS_HISTORY: &str = "udh";
+
 /// Unused Transaction Output (UniqueIdUTXOv10, TransactionOutput)
 pub static UTXOS: &str = "utxo";
 
 /// Consumed UTXOs (BlockNumber, UTXO)
 /// Used only to revert a block
 pub static CONSUMED_UTXOS: &str = "cutxo";
+
+/// Transactions by output condition, only if the transactions search index is enabled
+/// (Atomic output condition, Vec<Hash>)
+pub static TXS_BY_CONDITION: &str = "txc";
+
+/// Transactions by comment word, only if the transactions search index is enabled
+/// (Lowercase comment word, Vec<Hash>)
+pub static TXS_BY_COMMENT_WORD: &str = "txw";
@@ -43,6 +43,10 @@ pub enum CurrentMetaDataKey {
     NextWotId,
     /// Current Universal Dividend
     CurrentUd,
+    /// Light storage mode (transactions are pruned beyond the transactions window)
+    LightStorage,
+    /// Transactions search index (output conditions and comment words of the transactions)
+    TxSearchIndex,
 }
 
 impl CurrentMetaDataKey {
@@ -56,24 +60,26 @@ impl CurrentMetaDataKey {
             Self::ForkTree => 4,
             Self::NextWotId => 5,
             Self::CurrentUd => 6,
+            Self::LightStorage => 7,
+            Self::TxSearchIndex => 8,
         }
     }
 }
 
-/// Get DB version
-pub fn get_db_version<DB: DbReadable>(db: &DB) -> Result<usize, DbError> {
+/// Get DB version (None if the database was created before the versioning of its schema)
+pub fn get_db_version<DB: DbReadable>(db: &DB) -> Result<Option<usize>, DbError> {
     db.read(|r| {
         if let Some(v) = db
             .get_int_store(CURRENT_METADATA)
             .get(&r, CurrentMetaDataKey::DbVersion.to_u32())?
         {
             if let DbValue::U64(db_version) = v {
-                Ok(db_version as usize)
+                Ok(Some(db_version as usize))
             } else {
                 Err(DbError::DBCorrupted)
             }
         } else {
-            Err(DbError::DBCorrupted)
+            Ok(None)
         }
     })
 }
@@ -125,6 +131,40 @@ pub fn get_current_common_time_<DB: BcDbInReadTx>(db: &DB) -> Result<u64, DbErro
     }
 }
 
+/// Is database in light storage mode ?
+pub fn is_light_storage<DB: BcDbInReadTx>(db: &DB) -> Result<bool, DbError> {
+    if let Some(v) = db
+        .db()
+        .get_int_store(CURRENT_METADATA)
+        .get(db.r(), CurrentMetaDataKey::LightStorage.to_u32())?
+    {
+        if let DbValue::U64(light_storage) = v {
+            Ok(light_storage != 0)
+        } else {
+            Err(DbError::DBCorrupted)
+        }
+    } else {
+        Ok(false)
+    }
+}
+
+/// Is the transactions search index enabled ?
+pub fn is_tx_search_index<DB: BcDbInReadTx>(db: &DB) -> Result<bool, DbError> {
+    if let Some(v) = db
+        .db()
+        .get_int_store(CURRENT_METADATA)
+        .get(db.r(), CurrentMetaDataKey::TxSearchIndex.to_u32())?
+    {
+        if let DbValue::U64(tx_search_index) = v {
+            Ok(tx_search_index != 0)
+        } else {
+            Err(DbError::DBCorrupted)
+        }
+    } else {
+        Ok(false)
+    }
+}
+
 /// Get fork tree root
 pub fn get_fork_tree<DB: BcDbInReadTx>(db: &DB) -> Result<ForkTree, DbError> {
     if let Some(v) = db
@@ -156,6 +196,18 @@ pub fn get_greatest_wot_id_<DB: BcDbInReadTx>(db: &DB) -> Result<WotId, DbError>
     }
 }
 
+/// Get all universal dividends created, sorted by block number
+pub fn get_uds_history<DB: BcDbInReadTx>(db: &DB) -> Result<Vec<CurrentUdDb>, DbError> {
+    let mut uds = Vec::new();
+    for entry in db.db().get_store(UDS_HISTORY).iter_start(db.r())? {
+        let (_k, v_opt) = entry?;
+        if let Some(v) = v_opt {
+            uds.push(from_db_value::<CurrentUdDb>(v)?);
+        }
+    }
+    Ok(uds)
+}
+
 /// Get current UD
 pub fn get_current_ud<DB: BcDbInReadTx>(db: &DB) -> Result<Option<CurrentUdDb>, DbError> {
     Ok(db
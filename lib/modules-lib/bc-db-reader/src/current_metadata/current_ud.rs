@@ -17,10 +17,11 @@
 
 use dubp_block_doc::BlockDocument;
 use dubp_common_doc::BlockNumber;
+use dubp_currency_params::CurrencyParameters;
 use durs_common_tools::UsizeSer32;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CurrentUdDb {
     pub amount: usize,
     pub base: usize,
@@ -30,6 +31,80 @@ pub struct CurrentUdDb {
     pub common_time: u64,
 }
 
+impl CurrentUdDb {
+    /// Get the UD created by a block (if any)
+    pub fn from_block(block_doc: &BlockDocument) -> Option<CurrentUdDb> {
+        let BlockDocument::V10(ref block_doc_v10) = block_doc;
+        block_doc_v10
+            .dividend
+            .map(|UsizeSer32(dividend)| CurrentUdDb {
+                amount: dividend,
+                base: block_doc_v10.unit_base.into(),
+                block_number: block_doc_v10.number,
+                members_count: block_doc_v10.members_count.into(),
+                monetary_mass: block_doc_v10.monetary_mass,
+                common_time: block_doc_v10.median_time,
+            })
+    }
+    /// Project the next UD according to the currency parameters
+    pub fn project_next_ud(&self, currency_params: &CurrencyParameters) -> NextUdProjection {
+        let time = next_period_time(
+            currency_params.ud_time0,
+            currency_params.dt,
+            self.common_time,
+        );
+        let reevaluation_time = next_period_time(
+            currency_params.ud_reeval_time0,
+            currency_params.dt_reeval,
+            self.common_time,
+        );
+        let reevaluation = time >= reevaluation_time;
+        let amount = if reevaluation && self.members_count > 0 && currency_params.dt > 0 {
+            // UD(t+1) = UD(t) + c² * M(t) / N(t) / (dt_reeval / dt)
+            let unit = 10f64.powi(self.base as i32);
+            let ud = self.amount as f64 * unit;
+            let reevals_per_dt = currency_params.dt_reeval as f64 / currency_params.dt as f64;
+            let growth = currency_params.c * currency_params.c * self.monetary_mass as f64
+                / self.members_count as f64
+                / reevals_per_dt;
+            ((ud + growth) / unit).ceil() as usize
+        } else {
+            self.amount
+        };
+        NextUdProjection {
+            time,
+            amount,
+            base: self.base,
+            reevaluation,
+            reevaluation_time,
+        }
+    }
+}
+
+/// Projection of the next universal dividend
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NextUdProjection {
+    /// Blockchain time from which the next UD can be created
+    pub time: u64,
+    /// Estimated amount (in the base of the current UD)
+    pub amount: usize,
+    /// Base of the estimated amount
+    pub base: usize,
+    /// The next UD is reevaluated
+    pub reevaluation: bool,
+    /// Blockchain time of the next reevaluation
+    pub reevaluation_time: u64,
+}
+
+/// Get the first time of the period `time0 + k * period` strictly after `after`
+fn next_period_time(time0: u64, period: u64, after: u64) -> u64 {
+    if after < time0 || period == 0 {
+        time0
+    } else {
+        time0 + ((after - time0) / period + 1) * period
+    }
+}
+
 #[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
 pub struct CurrentUdDbInternal {
     current: Option<CurrentUdDb>,
@@ -44,17 +119,9 @@ impl Into<Option<CurrentUdDb>> for CurrentUdDbInternal {
 
 impl CurrentUdDbInternal {
     pub fn update(&mut self, block_doc: &BlockDocument) {
-        let BlockDocument::V10(ref block_doc_v10) = block_doc;
-        if let Some(UsizeSer32(dividend)) = block_doc_v10.dividend {
+        if let Some(current_ud) = CurrentUdDb::from_block(block_doc) {
             self.previous = self.current;
-            self.current = Some(CurrentUdDb {
-                amount: dividend,
-                base: block_doc_v10.unit_base.into(),
-                block_number: block_doc_v10.number,
-                members_count: block_doc_v10.members_count.into(),
-                monetary_mass: block_doc_v10.monetary_mass,
-                common_time: block_doc_v10.median_time,
-            })
+            self.current = Some(current_ud);
         }
     }
     pub fn revert(&mut self) {
@@ -62,3 +129,61 @@ impl CurrentUdDbInternal {
         self.previous = None;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dubp_currency_params::genesis_block_params::v10::BlockV10Parameters;
+    use dubp_currency_params::CurrencyName;
+
+    fn currency_params() -> CurrencyParameters {
+        let mut currency_params = CurrencyParameters::from((
+            &CurrencyName("test".to_owned()),
+            BlockV10Parameters::default(),
+        ));
+        currency_params.c = 0.1;
+        currency_params.dt = 100;
+        currency_params.dt_reeval = 200;
+        currency_params.ud_time0 = 1_000;
+        currency_params.ud_reeval_time0 = 1_200;
+        currency_params
+    }
+
+    #[test]
+    fn project_next_ud() {
+        let current_ud = CurrentUdDb {
+            amount: 1_000,
+            base: 0,
+            block_number: BlockNumber(10),
+            members_count: 10,
+            monetary_mass: 100_000,
+            common_time: 1_005,
+        };
+        assert_eq!(
+            NextUdProjection {
+                time: 1_100,
+                amount: 1_000,
+                base: 0,
+                reevaluation: false,
+                reevaluation_time: 1_200,
+            },
+            current_ud.project_next_ud(&currency_params())
+        );
+
+        // UD(t+1) = 1000 + 0.01 * 100_000 / 10 / 2
+        let current_ud = CurrentUdDb {
+            common_time: 1_110,
+            ..current_ud
+        };
+        assert_eq!(
+            NextUdProjection {
+                time: 1_200,
+                amount: 1_050,
+                base: 0,
+                reevaluation: true,
+                reevaluation_time: 1_200,
+            },
+            current_ud.project_next_ud(&currency_params())
+        );
+    }
+}
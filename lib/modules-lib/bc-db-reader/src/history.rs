@@ -0,0 +1,428 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Point-in-time queries: balance and identity state of a public key as of a given block,
+//! and history of the transactions of a public key.
+//!
+//! The current indexes only describe the current state, so these queries replay the main blocks
+//! from the genesis block to the requested block, only keeping the sources and the state of the
+//! requested public key. The transactions of the old blocks are not stored in light storage
+//! mode, the history is not available in this mode.
+
+use crate::indexes::sources::SourceAmount;
+use crate::*;
+use dubp_block_doc::block::v10::BlockDocumentV10;
+use dubp_block_doc::BlockDocument;
+use dubp_common_doc::traits::Document;
+use dubp_common_doc::BlockNumber;
+use dubp_indexes::sindex::UniqueIdUTXOv10;
+use dubp_user_docs::documents::transaction::*;
+use dup_crypto::hashs::Hash;
+use dup_crypto::keys::PubKey;
+use durs_common_tools::UsizeSer32;
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// State of an identity at a given block
+pub enum IdentityStateAt {
+    /// The identity is not yet written in the blockchain
+    Unknown,
+    /// Member
+    Member,
+    /// Excluded (not member) but not revoked
+    Excluded,
+    /// Revoked
+    Revoked,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Direction of a transaction, for a given public key
+pub enum TxDirection {
+    /// The public key is an issuer of the transaction
+    Sent,
+    /// The public key is not an issuer, but an output is spendable by its single signature
+    Received,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+/// Position of a transaction in the blockchain
+pub struct TxHistoryCursor {
+    /// Number of the block containing the transaction
+    pub block_number: BlockNumber,
+    /// Index of the transaction in the block
+    pub tx_index: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// Transaction sent or received by a public key
+pub struct TxHistoryEntry {
+    /// Position of the transaction
+    pub cursor: TxHistoryCursor,
+    /// Blockchain time of the block containing the transaction
+    pub blockchain_time: u64,
+    /// Transaction hash
+    pub hash: Hash,
+    /// Direction of the transaction
+    pub direction: TxDirection,
+    /// Issuers of the transaction
+    pub issuers: Vec<PubKey>,
+    /// Comment of the transaction
+    pub comment: String,
+    /// Amount sent (outputs to other conditions than the single signature of the public key)
+    /// or received (outputs to the single signature of the public key)
+    pub amount: SourceAmount,
+}
+
+impl TxHistoryEntry {
+    /// Entry of the transaction `tx` in the history of `pubkey`, None if `pubkey` neither sends
+    /// nor receives money with this transaction
+    fn new(
+        pubkey: &PubKey,
+        block: &BlockDocumentV10,
+        tx_index: usize,
+        tx: &TransactionDocumentV10,
+    ) -> Option<TxHistoryEntry> {
+        let conditions = UTXOConditionsGroup::Single(TransactionOutputCondition::Sig(*pubkey));
+        let direction = if tx.issuers().contains(pubkey) {
+            TxDirection::Sent
+        } else if tx
+            .get_outputs()
+            .iter()
+            .any(|output| output.conditions.conditions == conditions)
+        {
+            TxDirection::Received
+        } else {
+            return None;
+        };
+        let amount = tx
+            .get_outputs()
+            .iter()
+            .filter(|output| {
+                (output.conditions.conditions == conditions) == (direction == TxDirection::Received)
+            })
+            .fold(SourceAmount::default(), |amount, output| {
+                amount + SourceAmount(output.amount, output.base)
+            });
+
+        Some(TxHistoryEntry {
+            cursor: TxHistoryCursor {
+                block_number: block.number,
+                tx_index,
+            },
+            blockchain_time: block.median_time,
+            hash: tx.get_hash_opt().unwrap_or_else(|| tx.compute_hash()),
+            direction,
+            issuers: tx.issuers().clone(),
+            comment: tx.get_comment().to_owned(),
+            amount,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Filters and pagination of the transactions history
+pub struct TxsHistoryFilter {
+    /// Only the transactions of this direction (all directions if None)
+    pub direction: Option<TxDirection>,
+    /// Only the transactions written from this block
+    pub from: BlockNumber,
+    /// Only the transactions written up to this block (current block if None)
+    pub to: Option<BlockNumber>,
+    /// Maximum number of transactions of the page
+    pub first: usize,
+    /// Only the transactions after this position (end of the previous page)
+    pub after: Option<TxHistoryCursor>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+/// Page of the transactions history, in blockchain order
+pub struct TxsHistoryPage {
+    /// Transactions of the page
+    pub txs: Vec<TxHistoryEntry>,
+    /// Are there other transactions after this page?
+    pub has_next_page: bool,
+}
+
+/// Sources and identity state of a public key, replayed block by block
+#[derive(Clone, Debug)]
+struct PubkeyHistory {
+    pubkey: PubKey,
+    state: IdentityStateAt,
+    /// Unconsumed universal dividends (by creation block)
+    uds: HashMap<BlockNumber, SourceAmount>,
+    /// Unconsumed transaction outputs spendable by the single signature of the public key
+    utxos: HashMap<UniqueIdUTXOv10, SourceAmount>,
+}
+
+impl PubkeyHistory {
+    fn new(pubkey: PubKey) -> PubkeyHistory {
+        PubkeyHistory {
+            pubkey,
+            state: IdentityStateAt::Unknown,
+            uds: HashMap::new(),
+            utxos: HashMap::new(),
+        }
+    }
+    /// Apply the membership changes, the universal dividend and the transactions of a block
+    fn apply_block(&mut self, block: &BlockDocumentV10) {
+        // Membership changes apply before the universal dividend of the block
+        if block
+            .joiners
+            .iter()
+            .chain(block.actives.iter())
+            .any(|membership| membership.issuers()[0] == self.pubkey)
+        {
+            self.state = IdentityStateAt::Member;
+        }
+        if block.excluded.contains(&self.pubkey) {
+            self.state = IdentityStateAt::Excluded;
+        }
+        if block
+            .revoked
+            .iter()
+            .any(|revocation| revocation.to_compact_document().issuer == self.pubkey)
+        {
+            self.state = IdentityStateAt::Revoked;
+        }
+
+        if let Some(UsizeSer32(du_amount)) = block.dividend {
+            if du_amount > 0 && self.state == IdentityStateAt::Member {
+                self.uds.insert(
+                    block.number,
+                    SourceAmount(TxAmount(du_amount as isize), TxBase(block.unit_base.into())),
+                );
+            }
+        }
+
+        let conditions = UTXOConditionsGroup::Single(TransactionOutputCondition::Sig(self.pubkey));
+        for tx in &block.transactions {
+            for input in tx.get_inputs() {
+                match *input {
+                    TransactionInputV10::D(_, _, pubkey, block_number) => {
+                        if pubkey == self.pubkey {
+                            self.uds.remove(&block_number);
+                        }
+                    }
+                    TransactionInputV10::T(_, _, hash, output_index) => {
+                        self.utxos.remove(&UniqueIdUTXOv10(hash, output_index));
+                    }
+                }
+            }
+            let tx_hash = tx.get_hash_opt().unwrap_or_else(|| tx.compute_hash());
+            for (index, output) in tx.get_outputs().iter().enumerate() {
+                if output.conditions.conditions == conditions {
+                    self.utxos.insert(
+                        UniqueIdUTXOv10(tx_hash, OutputIndex(index)),
+                        SourceAmount(output.amount, output.base),
+                    );
+                }
+            }
+        }
+    }
+    fn balance(&self) -> SourceAmount {
+        self.uds
+            .values()
+            .chain(self.utxos.values())
+            .fold(SourceAmount::default(), |balance, amount| balance + *amount)
+    }
+}
+
+/// Replay the main blocks up to `block_number` (included) for `pubkey`, None if the history
+/// is not available (block after the current block or light storage mode)
+fn replay<DB: BcDbInReadTx>(
+    db: &DB,
+    pubkey: &PubKey,
+    block_number: BlockNumber,
+) -> Result<Option<PubkeyHistory>, DbError> {
+    match crate::current_metadata::get_current_blockstamp(db)? {
+        Some(current_blockstamp) if block_number <= current_blockstamp.id => {}
+        _ => return Ok(None),
+    }
+    if crate::current_metadata::is_light_storage(db)? {
+        return Ok(None);
+    }
+    let mut history = PubkeyHistory::new(*pubkey);
+    for number in 0..=block_number.0 {
+        let BlockDocument::V10(block) =
+            crate::blocks::get_block_in_local_blockchain(db, BlockNumber(number))?
+                .ok_or(DbError::DBCorrupted)?;
+        history.apply_block(&block);
+    }
+    Ok(Some(history))
+}
+
+/// Get the balance of `pubkey` (amount of the sources spendable by its single signature) as of
+/// the block `block_number`, None if the history is not available
+pub fn get_balance_at<DB: BcDbInReadTx>(
+    db: &DB,
+    pubkey: &PubKey,
+    block_number: BlockNumber,
+) -> Result<Option<SourceAmount>, DbError> {
+    Ok(replay(db, pubkey, block_number)?.map(|history| history.balance()))
+}
+
+/// Get the identity state of `pubkey` as of the block `block_number`, None if the history is
+/// not available
+pub fn get_identity_state_at<DB: BcDbInReadTx>(
+    db: &DB,
+    pubkey: &PubKey,
+    block_number: BlockNumber,
+) -> Result<Option<IdentityStateAt>, DbError> {
+    Ok(replay(db, pubkey, block_number)?.map(|history| history.state))
+}
+
+/// Get a page of the transactions sent or received by `pubkey`, in blockchain order.
+/// The blocks of the interval are read until the page is full. In light storage mode, only the
+/// transactions of the last blocks are stored.
+pub fn get_txs_history<DB: BcDbInReadTx>(
+    db: &DB,
+    pubkey: &PubKey,
+    filter: TxsHistoryFilter,
+) -> Result<TxsHistoryPage, DbError> {
+    let current_block_number =
+        if let Some(current_blockstamp) = crate::current_metadata::get_current_blockstamp(db)? {
+            current_blockstamp.id
+        } else {
+            return Ok(TxsHistoryPage::default());
+        };
+    let to = filter.to.map_or(current_block_number, |to| {
+        std::cmp::min(to, current_block_number)
+    });
+    let from = match filter.after {
+        Some(after) if after.block_number > filter.from => after.block_number,
+        _ => filter.from,
+    };
+
+    let mut page = TxsHistoryPage::default();
+    for number in from.0..=to.0 {
+        let BlockDocument::V10(block) =
+            crate::blocks::get_block_in_local_blockchain(db, BlockNumber(number))?
+                .ok_or(DbError::DBCorrupted)?;
+        for (tx_index, tx) in block.transactions.iter().enumerate() {
+            let cursor = TxHistoryCursor {
+                block_number: block.number,
+                tx_index,
+            };
+            if filter.after.map_or(false, |after| cursor <= after) {
+                continue;
+            }
+            if let Some(entry) = TxHistoryEntry::new(pubkey, &block, tx_index, tx) {
+                if filter.direction.map_or(true, |d| d == entry.direction) {
+                    if page.txs.len() == filter.first {
+                        page.has_next_page = true;
+                        return Ok(page);
+                    }
+                    page.txs.push(entry);
+                }
+            }
+        }
+    }
+    Ok(page)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use dubp_common_doc::{BlockHash, Blockstamp};
+    use dup_crypto::hashs::Hash;
+    use dup_crypto::keys::*;
+
+    fn gen_block(number: u32) -> BlockDocumentV10 {
+        dubp_blocks_tests_tools::mocks::gen_empty_timed_block_v10(
+            Blockstamp {
+                id: BlockNumber(number),
+                hash: BlockHash(Hash([number as u8; 32])),
+            },
+            0,
+            Hash([0u8; 32]),
+        )
+    }
+
+    #[test]
+    fn test_pubkey_history() {
+        let pubkey = PubKey::Ed25519(
+            ed25519::PublicKey::from_base58("2ny7YAdmzReQxAayyJZsyVYwYhVyax2thKcGknmQy5nQ")
+                .expect("Fail to parse pubkey"),
+        );
+        let mut history = PubkeyHistory::new(pubkey);
+        history.state = IdentityStateAt::Member;
+
+        // Universal dividend of block #1
+        let mut block = gen_block(1);
+        block.dividend = Some(UsizeSer32(1_000));
+        history.apply_block(&block);
+        assert_eq!(SourceAmount(TxAmount(1_000), TxBase(0)), history.balance());
+
+        // The transaction consumes the dividend and sends back 999 units to its issuer
+        let mut block = gen_block(2);
+        let TransactionDocument::V10(tx) = dubp_user_docs_tests_tools::mocks::tx::first_g1_tx_doc();
+        block.transactions = vec![tx];
+        history.apply_block(&block);
+        assert_eq!(SourceAmount(TxAmount(999), TxBase(0)), history.balance());
+
+        // No dividend after the exclusion
+        let mut block = gen_block(3);
+        block.excluded = vec![pubkey];
+        block.dividend = Some(UsizeSer32(1_000));
+        history.apply_block(&block);
+        assert_eq!(IdentityStateAt::Excluded, history.state);
+        assert_eq!(SourceAmount(TxAmount(999), TxBase(0)), history.balance());
+    }
+
+    #[test]
+    fn test_tx_history_entry() {
+        let mut block = gen_block(2);
+        block.median_time = 1_500_000_000;
+        let TransactionDocument::V10(tx) = dubp_user_docs_tests_tools::mocks::tx::first_g1_tx_doc();
+        let issuer = tx.issuers()[0];
+        let recipient = tx
+            .get_outputs()
+            .iter()
+            .filter_map(|output| {
+                if let UTXOConditionsGroup::Single(TransactionOutputCondition::Sig(pubkey)) =
+                    output.conditions.conditions
+                {
+                    Some(pubkey)
+                } else {
+                    None
+                }
+            })
+            .find(|pubkey| *pubkey != issuer)
+            .expect("first g1 tx must have a recipient");
+
+        let sent = TxHistoryEntry::new(&issuer, &block, 0, &tx).expect("issuer sends the tx");
+        assert_eq!(TxDirection::Sent, sent.direction);
+        assert_eq!(SourceAmount(TxAmount(1), TxBase(0)), sent.amount);
+        assert_eq!(1_500_000_000, sent.blockchain_time);
+        assert_eq!(
+            TxHistoryCursor {
+                block_number: BlockNumber(2),
+                tx_index: 0,
+            },
+            sent.cursor
+        );
+
+        let received =
+            TxHistoryEntry::new(&recipient, &block, 0, &tx).expect("recipient receives the tx");
+        assert_eq!(TxDirection::Received, received.direction);
+        assert_eq!(SourceAmount(TxAmount(1), TxBase(0)), received.amount);
+        assert_eq!(sent.hash, received.hash);
+        assert_eq!(tx.get_comment(), received.comment.as_str());
+
+        let other = dup_crypto_tests_tools::mocks::pubkey('A');
+        assert_eq!(None, TxHistoryEntry::new(&other, &block, 0, &tx));
+    }
+}
@@ -18,3 +18,4 @@
 pub mod certs;
 pub mod identities;
 pub mod sources;
+pub mod transactions;
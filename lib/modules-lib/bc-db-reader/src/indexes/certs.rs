@@ -20,6 +20,52 @@ use dubp_common_doc::BlockNumber;
 use durs_dbs_tools::DbError;
 use durs_wot::WotId;
 use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Reason why an issuer can't write a new certification
+pub enum CertQuotaError {
+    /// The issuer certified less than `sig_period` ago, it can certify again from this timestamp
+    SigPeriod(u64),
+    /// The issuer already has `sig_stock` active certifications
+    SigStock(usize),
+}
+
+impl fmt::Display for CertQuotaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CertQuotaError::SigPeriod(chainable_on) => write!(
+                f,
+                "the issuer certified too recently, it can certify again from {}",
+                chainable_on
+            ),
+            CertQuotaError::SigStock(issued_count) => write!(
+                f,
+                "the issuer already has {} active certifications",
+                issued_count
+            ),
+        }
+    }
+}
+
+/// Check that an issuer can write a new certification in a block of median time `median_time`.
+/// `issued_count` is the number of active certifications of the issuer.
+pub fn check_cert_quota(
+    cert_chainable_on: Option<u64>,
+    issued_count: usize,
+    median_time: u64,
+    sig_stock: usize,
+) -> Result<(), CertQuotaError> {
+    if let Some(cert_chainable_on) = cert_chainable_on {
+        if cert_chainable_on > median_time {
+            return Err(CertQuotaError::SigPeriod(cert_chainable_on));
+        }
+    }
+    if issued_count >= sig_stock {
+        return Err(CertQuotaError::SigStock(issued_count));
+    }
+    Ok(())
+}
 
 /// Find certifications that emitted in indicated blocks expiring
 pub fn find_expire_certs<DB: BcDbInReadTx>(
@@ -46,9 +92,86 @@ pub fn find_expire_certs<DB: BcDbInReadTx>(
     Ok(all_expire_certs)
 }
 
+/// Get the active certifications issued or received by `wot_id` (source, target), with the
+/// block in which each one was created, from the genesis block to block `current`
+pub fn get_member_certs<DB: BcDbInReadTx>(
+    db: &DB,
+    wot_id: WotId,
+    current: BlockNumber,
+) -> Result<Vec<(BlockNumber, WotId, WotId)>, DbError> {
+    let mut member_certs = Vec::new();
+    for block_id in 0..=current.0 {
+        for entry_result in db
+            .db()
+            .get_multi_int_store(CERTS_BY_CREATED_BLOCK)
+            .get(db.r(), block_id)?
+        {
+            if let Some(value) = entry_result?.1 {
+                if let DbValue::U64(cert) = value {
+                    let (source, target) = cert_from_u64(cert);
+                    if source == wot_id || target == wot_id {
+                        member_certs.push((BlockNumber(block_id), source, target));
+                    }
+                } else {
+                    return Err(DbError::DBCorrupted);
+                }
+            }
+        }
+    }
+    Ok(member_certs)
+}
+
 #[inline]
 fn cert_from_u64(cert: u64) -> (WotId, WotId) {
     let (source, target) = durs_common_tools::fns::_u64::to_2_u32(cert);
 
     (WotId(source as usize), WotId(target as usize))
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_check_cert_quota() {
+        assert_eq!(Ok(()), check_cert_quota(None, 0, 100, 10));
+        assert_eq!(Ok(()), check_cert_quota(Some(100), 9, 100, 10));
+        assert_eq!(
+            Err(CertQuotaError::SigPeriod(101)),
+            check_cert_quota(Some(101), 0, 100, 10)
+        );
+        assert_eq!(
+            Err(CertQuotaError::SigStock(10)),
+            check_cert_quota(Some(50), 10, 100, 10)
+        );
+    }
+
+    #[test]
+    fn test_get_member_certs() -> Result<(), DbError> {
+        let db = crate::tests::open_tmp_db()?;
+        db.write(|mut w| {
+            for (block_id, source, target) in &[(1, 0, 1), (1, 2, 3), (3, 1, 2), (4, 2, 0)] {
+                db.get_multi_int_store(CERTS_BY_CREATED_BLOCK).put(
+                    w.as_mut(),
+                    *block_id,
+                    &DbValue::U64(durs_common_tools::fns::_u64::from_2_u32(*source, *target)),
+                )?;
+            }
+            Ok(WriteResp::from(w))
+        })?;
+
+        assert_eq!(
+            vec![
+                (BlockNumber(1), WotId(0), WotId(1)),
+                (BlockNumber(3), WotId(1), WotId(2)),
+            ],
+            db.r(|db_r| get_member_certs(db_r, WotId(1), BlockNumber(4)))?
+        );
+        assert_eq!(
+            vec![(BlockNumber(1), WotId(0), WotId(1))],
+            db.r(|db_r| get_member_certs(db_r, WotId(0), BlockNumber(3)))?
+        );
+        Ok(())
+    }
+}
@@ -95,6 +95,13 @@ pub struct IdentityDb {
     pub cert_chainable_on: Vec<u64>,
 }
 
+impl IdentityDb {
+    /// Timestamp from which the identity can write its next certification (None if it never certified)
+    pub fn next_cert_chainable_on(&self) -> Option<u64> {
+        self.cert_chainable_on.last().cloned()
+    }
+}
+
 /// Get identities in databases
 pub fn get_identities<DB: BcDbInReadTx>(
     db: &DB,
@@ -135,6 +142,30 @@ pub fn get_identities<DB: BcDbInReadTx>(
     }
 }
 
+/// Get a page of at most `limit` identities sorted by wot id, from the wot id `from` (included),
+/// without reading the others. Returns the identities and the cursor of the next page (None on
+/// the last page).
+pub fn get_identities_page<DB: BcDbInReadTx>(
+    db: &DB,
+    from: WotId,
+    limit: usize,
+) -> Result<(Vec<IdentityDb>, Option<WotId>), DbError> {
+    let next_wot_id = crate::current_metadata::get_greatest_wot_id_(db)?;
+    let mut identities = Vec::with_capacity(limit);
+    let mut wot_id = from.0;
+    while wot_id < next_wot_id.0 && identities.len() < limit {
+        if let Some(db_idty) = get_identity_by_wot_id(db, WotId(wot_id))? {
+            identities.push(db_idty);
+        }
+        wot_id += 1;
+    }
+    if wot_id < next_wot_id.0 {
+        Ok((identities, Some(WotId(wot_id))))
+    } else {
+        Ok((identities, None))
+    }
+}
+
 /// Get identity by pubkey
 pub fn get_identity_by_pubkey<DB: BcDbInReadTx>(
     db: &DB,
@@ -146,7 +177,18 @@ pub fn get_identity_by_pubkey<DB: BcDbInReadTx>(
         .flatten())
 }
 
-/// Get identity by pubkey
+/// Get identity by uid
+pub fn get_identity_by_uid<DB: BcDbInReadTx>(
+    db: &DB,
+    uid: &str,
+) -> Result<Option<IdentityDb>, DbError> {
+    Ok(get_wot_id_from_uid(db, uid)?
+        .map(|wot_id| get_identity_by_wot_id(db, wot_id))
+        .transpose()?
+        .flatten())
+}
+
+/// Get identity by wot id
 #[inline]
 pub fn get_identity_by_wot_id<DB: BcDbInReadTx>(
     db: &DB,
@@ -168,6 +210,15 @@ pub fn get_idty_state_by_pubkey<DB: BcDbInReadTx>(
     Ok(get_identity_by_pubkey(db, pubkey)?.map(|db_idty| db_idty.state))
 }
 
+/// Get the timestamp from which the identity of `pubkey` can write its next certification
+#[inline]
+pub fn get_cert_chainable_on_by_pubkey<DB: BcDbInReadTx>(
+    db: &DB,
+    pubkey: &PubKey,
+) -> Result<Option<u64>, DbError> {
+    Ok(get_identity_by_pubkey(db, pubkey)?.and_then(|db_idty| db_idty.next_cert_chainable_on()))
+}
+
 /// Get uid from pubkey
 #[inline]
 pub fn get_uid<DB: BcDbInReadTx>(db: &DB, pubkey: &PubKey) -> Result<Option<String>, DbError> {
@@ -347,6 +398,14 @@ mod test {
             db.r(|db_r| get_identities(db_r, filters, BlockNumber(5)))?
         );
 
+        // Test cursor pages
+        let (page, cursor) = db.r(|db_r| get_identities_page(db_r, WotId(0), 3))?;
+        assert_eq!(&mock_identities[..3], &page[..]);
+        assert_eq!(Some(WotId(3)), cursor);
+        let (page, cursor) = db.r(|db_r| get_identities_page(db_r, WotId(3), 3))?;
+        assert_eq!(&mock_identities[3..], &page[..]);
+        assert_eq!(None, cursor);
+
         Ok(())
     }
 }
@@ -15,16 +15,19 @@
 
 //! Sources stored index.
 
-use crate::constants::UTXOS;
+use crate::constants::{DIVIDENDS, UTXOS};
+use crate::current_metadata::current_ud::CurrentUdDb;
+use crate::paging::PagingFilter;
 use crate::*;
 use dubp_common_doc::BlockNumber;
-use dubp_indexes::sindex::UniqueIdUTXOv10;
+use dubp_indexes::sindex::{SourceUniqueIdV10, UniqueIdUTXOv10};
 use dubp_user_docs::documents::transaction::*;
+use dup_crypto::keys::*;
 use durs_common_tools::fatal_error;
 use durs_dbs_tools::DbError;
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::ops::{Add, Sub};
 
 #[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, PartialEq, PartialOrd, Serialize)]
@@ -137,6 +140,203 @@ pub fn get_utxo_v10<DB: BcDbInReadTx>(
         .transpose()
 }
 
+/// Is the universal dividend created for `pubkey` at block `block_number` still unconsumed ?
+pub fn is_unconsumed_ud<DB: BcDbInReadTx>(
+    db: &DB,
+    pubkey: &PubKey,
+    block_number: BlockNumber,
+) -> Result<bool, DbError> {
+    for entry_result in db
+        .db()
+        .get_multi_store(DIVIDENDS)
+        .get(db.r(), &pubkey.to_bytes_vector())?
+    {
+        if let Some(value) = entry_result?.1 {
+            if let DbValue::U64(ud_block_number) = value {
+                if ud_block_number == u64::from(block_number.0) {
+                    return Ok(true);
+                }
+            } else {
+                return Err(DbError::DBCorrupted);
+            }
+        }
+    }
+    Ok(false)
+}
+
+/// Amount of the universal dividends created by each block
+fn get_uds_amounts<DB: BcDbInReadTx>(
+    db: &DB,
+) -> Result<HashMap<BlockNumber, SourceAmount>, DbError> {
+    Ok(crate::current_metadata::get_uds_history(db)?
+        .into_iter()
+        .map(|ud| {
+            (
+                ud.block_number,
+                SourceAmount(TxAmount(ud.amount as isize), TxBase(ud.base)),
+            )
+        })
+        .collect())
+}
+
+/// Get the unconsumed universal dividends and transaction outputs spendable by the single
+/// signature of `pubkey`
+pub fn get_sources_of_pubkey<DB: BcDbInReadTx>(
+    db: &DB,
+    pubkey: &PubKey,
+) -> Result<Vec<(SourceUniqueIdV10, SourceAmount)>, DbError> {
+    let uds_amounts = get_uds_amounts(db)?;
+    let mut sources = Vec::new();
+
+    // Universal dividends
+    for entry_result in db
+        .db()
+        .get_multi_store(DIVIDENDS)
+        .get(db.r(), &pubkey.to_bytes_vector())?
+    {
+        if let Some(value) = entry_result?.1 {
+            if let DbValue::U64(ud_block_number) = value {
+                let block_number = BlockNumber(ud_block_number as u32);
+                sources.push((
+                    SourceUniqueIdV10::UD(*pubkey, block_number),
+                    *uds_amounts.get(&block_number).ok_or(DbError::DBCorrupted)?,
+                ));
+            } else {
+                return Err(DbError::DBCorrupted);
+            }
+        }
+    }
+
+    // Transaction outputs
+    let conditions = UTXOConditionsGroup::Single(TransactionOutputCondition::Sig(*pubkey));
+    for entry in db.db().get_store(UTXOS).iter_start(db.r())? {
+        if let (key, Some(value)) = entry? {
+            let output: TransactionOutputV10 = from_db_value(value)?;
+            if output.conditions.conditions == conditions {
+                sources.push((
+                    SourceUniqueIdV10::UTXO(
+                        UniqueIdUTXOv10::from_bytes(key).ok_or(DbError::DBCorrupted)?,
+                    ),
+                    SourceAmount(output.amount, output.base),
+                ));
+            }
+        }
+    }
+
+    Ok(sources)
+}
+
+/// Get a page of the unconsumed transaction outputs whose conditions are exactly `conditions`
+/// (an output script), sorted by source id. Only the page fields of `paging` are used, the
+/// reading stops at the end of the page.
+pub fn get_utxos_of_conditions<DB: BcDbInReadTx>(
+    db: &DB,
+    conditions: &UTXOConditionsGroup,
+    paging: PagingFilter,
+) -> Result<Vec<(UniqueIdUTXOv10, TransactionOutputV10)>, DbError> {
+    let mut utxos = Vec::new();
+    let mut i = 0;
+    for entry in db.db().get_store(UTXOS).iter_start(db.r())? {
+        if let (key, Some(value)) = entry? {
+            let output: TransactionOutputV10 = from_db_value(value)?;
+            if output.conditions.conditions == *conditions {
+                if paging.is_in_page(i) {
+                    utxos.push((
+                        UniqueIdUTXOv10::from_bytes(key).ok_or(DbError::DBCorrupted)?,
+                        output,
+                    ));
+                }
+                i += 1;
+                if paging.is_after_page(i) {
+                    break;
+                }
+            }
+        }
+    }
+    Ok(utxos)
+}
+
+/// Get the universal dividends created between blocks `from` and `to` (included) for `pubkey`,
+/// sorted by block number, each one with its availability (`true` if still unconsumed)
+pub fn get_ud_history<DB: BcDbInReadTx>(
+    db: &DB,
+    pubkey: &PubKey,
+    from: BlockNumber,
+    to: Option<BlockNumber>,
+) -> Result<Vec<(CurrentUdDb, bool)>, DbError> {
+    let mut unconsumed = HashSet::new();
+    for entry_result in db
+        .db()
+        .get_multi_store(DIVIDENDS)
+        .get(db.r(), &pubkey.to_bytes_vector())?
+    {
+        if let Some(value) = entry_result?.1 {
+            if let DbValue::U64(ud_block_number) = value {
+                unconsumed.insert(BlockNumber(ud_block_number as u32));
+            } else {
+                return Err(DbError::DBCorrupted);
+            }
+        }
+    }
+
+    Ok(crate::current_metadata::get_uds_history(db)?
+        .into_iter()
+        .filter(|ud| ud.block_number >= from && to.map_or(true, |to| ud.block_number <= to))
+        .map(|ud| (ud, unconsumed.contains(&ud.block_number)))
+        .collect())
+}
+
+/// Get the balances of the given public keys: amount of the unconsumed universal dividends and
+/// transaction outputs spendable by the single signature of each public key
+pub fn get_balances<DB: BcDbInReadTx>(
+    db: &DB,
+    pubkeys: &[PubKey],
+) -> Result<Vec<SourceAmount>, DbError> {
+    let uds_amounts = get_uds_amounts(db)?;
+    let mut balances = vec![SourceAmount::default(); pubkeys.len()];
+
+    // Universal dividends
+    for (balance, pubkey) in balances.iter_mut().zip(pubkeys.iter()) {
+        for entry_result in db
+            .db()
+            .get_multi_store(DIVIDENDS)
+            .get(db.r(), &pubkey.to_bytes_vector())?
+        {
+            if let Some(value) = entry_result?.1 {
+                if let DbValue::U64(ud_block_number) = value {
+                    *balance = *balance
+                        + *uds_amounts
+                            .get(&BlockNumber(ud_block_number as u32))
+                            .ok_or(DbError::DBCorrupted)?;
+                } else {
+                    return Err(DbError::DBCorrupted);
+                }
+            }
+        }
+    }
+
+    // Transaction outputs
+    let pubkeys_indexes: HashMap<PubKey, usize> = pubkeys
+        .iter()
+        .enumerate()
+        .map(|(index, pubkey)| (*pubkey, index))
+        .collect();
+    for entry in db.db().get_store(UTXOS).iter_start(db.r())? {
+        if let (_, Some(value)) = entry? {
+            let output: TransactionOutputV10 = from_db_value(value)?;
+            if let UTXOConditionsGroup::Single(TransactionOutputCondition::Sig(ref pubkey)) =
+                output.conditions.conditions
+            {
+                if let Some(index) = pubkeys_indexes.get(pubkey) {
+                    balances[*index] = balances[*index] + SourceAmount(output.amount, output.base);
+                }
+            }
+        }
+    }
+
+    Ok(balances)
+}
+
 /// Get block consumed sources
 pub fn get_block_consumed_sources_<DB: BcDbInReadTx>(
     db: &DB,
@@ -148,3 +348,126 @@ pub fn get_block_consumed_sources_<DB: BcDbInReadTx>(
         .map(from_db_value)
         .transpose()
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use dup_crypto::hashs::Hash;
+    use dup_crypto_tests_tools::mocks::pubkey;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_sources_of_pubkey() -> Result<(), DbError> {
+        let db = crate::tests::open_tmp_db()?;
+        let ud = CurrentUdDb {
+            amount: 1_000,
+            base: 0,
+            block_number: BlockNumber(3),
+            members_count: 2,
+            monetary_mass: 2_000,
+            common_time: 0,
+        };
+        let utxo_id = UniqueIdUTXOv10(Hash([1u8; 32]), OutputIndex(0));
+        let other_utxo_id = UniqueIdUTXOv10(Hash([2u8; 32]), OutputIndex(1));
+        let output = TransactionOutputV10::from_str(&format!("15:0:SIG({})", pubkey('A')))
+            .expect("fail to parse output");
+        let other_output = TransactionOutputV10::from_str(&format!("7:0:SIG({})", pubkey('B')))
+            .expect("fail to parse output");
+
+        db.write(|mut w| {
+            db.get_store(UDS_HISTORY).put(
+                w.as_mut(),
+                ud.block_number.0.to_be_bytes(),
+                &DbValue::Blob(&durs_dbs_tools::to_bytes(&ud)?),
+            )?;
+            db.get_multi_store(DIVIDENDS).put(
+                w.as_mut(),
+                &pubkey('A').to_bytes_vector(),
+                &DbValue::U64(3),
+            )?;
+            for (id, output) in &[(utxo_id, &output), (other_utxo_id, &other_output)] {
+                let id_bytes: Vec<u8> = (*id).into();
+                db.get_store(UTXOS).put(
+                    w.as_mut(),
+                    &id_bytes,
+                    &DbValue::Blob(&durs_dbs_tools::to_bytes(output)?),
+                )?;
+            }
+            Ok(WriteResp::from(w))
+        })?;
+
+        assert_eq!(
+            vec![
+                (
+                    SourceUniqueIdV10::UD(pubkey('A'), BlockNumber(3)),
+                    SourceAmount(TxAmount(1_000), TxBase(0))
+                ),
+                (
+                    SourceUniqueIdV10::UTXO(utxo_id),
+                    SourceAmount(TxAmount(15), TxBase(0))
+                ),
+            ],
+            db.r(|db_r| get_sources_of_pubkey(db_r, &pubkey('A')))?
+        );
+        assert_eq!(
+            vec![
+                SourceAmount(TxAmount(1_015), TxBase(0)),
+                SourceAmount(TxAmount(7), TxBase(0)),
+                SourceAmount::default(),
+            ],
+            db.r(|db_r| get_balances(db_r, &[pubkey('A'), pubkey('B'), pubkey('C')]))?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_ud_history() -> Result<(), DbError> {
+        let db = crate::tests::open_tmp_db()?;
+        let ud = CurrentUdDb {
+            amount: 1_000,
+            base: 0,
+            block_number: BlockNumber(3),
+            members_count: 2,
+            monetary_mass: 2_000,
+            common_time: 0,
+        };
+        let next_ud = CurrentUdDb {
+            amount: 1_010,
+            block_number: BlockNumber(5),
+            monetary_mass: 4_020,
+            common_time: 100,
+            ..ud
+        };
+
+        db.write(|mut w| {
+            for ud in &[ud, next_ud] {
+                db.get_store(UDS_HISTORY).put(
+                    w.as_mut(),
+                    ud.block_number.0.to_be_bytes(),
+                    &DbValue::Blob(&durs_dbs_tools::to_bytes(ud)?),
+                )?;
+            }
+            db.get_multi_store(DIVIDENDS).put(
+                w.as_mut(),
+                &pubkey('A').to_bytes_vector(),
+                &DbValue::U64(5),
+            )?;
+            Ok(WriteResp::from(w))
+        })?;
+
+        assert_eq!(
+            vec![(ud, false), (next_ud, true)],
+            db.r(|db_r| get_ud_history(db_r, &pubkey('A'), BlockNumber(0), None))?
+        );
+        assert_eq!(
+            vec![(next_ud, true)],
+            db.r(|db_r| get_ud_history(db_r, &pubkey('A'), BlockNumber(4), None))?
+        );
+        assert_eq!(
+            vec![(ud, false)],
+            db.r(|db_r| get_ud_history(db_r, &pubkey('A'), BlockNumber(0), Some(BlockNumber(4))))?
+        );
+        Ok(())
+    }
+}
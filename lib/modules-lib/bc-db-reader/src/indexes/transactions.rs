@@ -0,0 +1,176 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Transactions search index: output conditions and comment words of the transactions.
+//!
+//! This index is optional (see `current_metadata::is_tx_search_index`), it only contains the
+//! transactions applied since it is enabled.
+
+use crate::constants::{TXS_BY_COMMENT_WORD, TXS_BY_CONDITION};
+use crate::paging::PagingFilter;
+use crate::*;
+use dubp_user_docs::documents::transaction::*;
+use dup_crypto::hashs::Hash;
+use dup_crypto::keys::PubKey;
+use durs_dbs_tools::DbError;
+use std::collections::BTreeSet;
+
+/// Atomic conditions (SIG, XHX, CLTV or CSV) of an output, without duplicates
+pub fn output_conditions(conditions: &UTXOConditionsGroup) -> Vec<String> {
+    let mut atomic_conditions = BTreeSet::new();
+    collect_conditions(conditions, &mut atomic_conditions);
+    atomic_conditions.into_iter().collect()
+}
+
+fn collect_conditions(conditions: &UTXOConditionsGroup, atomic_conditions: &mut BTreeSet<String>) {
+    match *conditions {
+        UTXOConditionsGroup::Single(ref condition) => {
+            atomic_conditions.insert(condition.to_string());
+        }
+        UTXOConditionsGroup::Brackets(ref group) => collect_conditions(group, atomic_conditions),
+        UTXOConditionsGroup::And(ref group_1, ref group_2)
+        | UTXOConditionsGroup::Or(ref group_1, ref group_2) => {
+            collect_conditions(group_1, atomic_conditions);
+            collect_conditions(group_2, atomic_conditions);
+        }
+    }
+}
+
+/// Atomic conditions of all the outputs of a transaction, without duplicates
+pub fn tx_conditions(tx_doc: &TransactionDocumentV10) -> Vec<String> {
+    let mut atomic_conditions = BTreeSet::new();
+    for output in tx_doc.get_outputs() {
+        collect_conditions(&output.conditions.conditions, &mut atomic_conditions);
+    }
+    atomic_conditions.into_iter().collect()
+}
+
+/// Lowercase words of a transaction comment, without duplicates
+pub fn comment_words(comment: &str) -> Vec<String> {
+    comment
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<BTreeSet<String>>()
+        .into_iter()
+        .collect()
+}
+
+fn get_txs_hashes<DB: BcDbInReadTx>(
+    db: &DB,
+    store_name: &str,
+    key: &str,
+    paging: Option<PagingFilter>,
+) -> Result<Vec<Hash>, DbError> {
+    let mut hashes = Vec::new();
+    for (i, entry_result) in db
+        .db()
+        .get_multi_store(store_name)
+        .get(db.r(), key)?
+        .enumerate()
+    {
+        if let Some(ref paging) = paging {
+            if paging.is_after_page(i) {
+                break;
+            } else if !paging.is_in_page(i) {
+                continue;
+            }
+        }
+        if let Some(value) = entry_result?.1 {
+            if let DbValue::Blob(hash_bytes) = value {
+                if hash_bytes.len() != 32 {
+                    return Err(DbError::DBCorrupted);
+                }
+                let mut hash = [0u8; 32];
+                hash.copy_from_slice(hash_bytes);
+                hashes.push(Hash(hash));
+            } else {
+                return Err(DbError::DBCorrupted);
+            }
+        }
+    }
+    Ok(hashes)
+}
+
+/// Hashes of the transactions having an output with the condition `condition`
+pub fn get_txs_hashes_by_condition<DB: BcDbInReadTx>(
+    db: &DB,
+    condition: &TransactionOutputCondition,
+) -> Result<Vec<Hash>, DbError> {
+    get_txs_hashes(db, TXS_BY_CONDITION, &condition.to_string(), None)
+}
+
+/// Get a page of the hashes of the transactions having an output spendable by the single
+/// signature of `pubkey`, sorted by hash. Only the page fields of `paging` are used.
+pub fn get_txs_hashes_of_pubkey<DB: BcDbInReadTx>(
+    db: &DB,
+    pubkey: &PubKey,
+    paging: PagingFilter,
+) -> Result<Vec<Hash>, DbError> {
+    get_txs_hashes(
+        db,
+        TXS_BY_CONDITION,
+        &TransactionOutputCondition::Sig(*pubkey).to_string(),
+        Some(paging),
+    )
+}
+
+/// Hashes of the transactions whose comment contains the word `word` (case insensitive)
+pub fn get_txs_hashes_by_comment_word<DB: BcDbInReadTx>(
+    db: &DB,
+    word: &str,
+) -> Result<Vec<Hash>, DbError> {
+    get_txs_hashes(db, TXS_BY_COMMENT_WORD, &word.to_lowercase(), None)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use dup_crypto_tests_tools::mocks::pubkey;
+
+    #[test]
+    fn test_output_conditions() {
+        let sig_a = UTXOConditionsGroup::Single(TransactionOutputCondition::Sig(pubkey('A')));
+        let conditions = UTXOConditionsGroup::Or(
+            Box::new(sig_a.clone()),
+            Box::new(UTXOConditionsGroup::Brackets(Box::new(
+                UTXOConditionsGroup::And(
+                    Box::new(sig_a),
+                    Box::new(UTXOConditionsGroup::Single(
+                        TransactionOutputCondition::Cltv(1_500_000_000),
+                    )),
+                ),
+            ))),
+        );
+
+        assert_eq!(
+            vec![
+                "CLTV(1500000000)".to_owned(),
+                format!("SIG({})", pubkey('A')),
+            ],
+            output_conditions(&conditions)
+        );
+    }
+
+    #[test]
+    fn test_comment_words() {
+        assert_eq!(
+            vec!["for".to_owned(), "pizza".to_owned(), "the".to_owned()],
+            comment_words("Pizza for the PIZZA!")
+        );
+        assert!(comment_words("").is_empty());
+    }
+}
@@ -32,6 +32,7 @@ pub mod blocks;
 pub mod constants;
 pub mod currency_params;
 pub mod current_metadata;
+pub mod history;
 pub mod indexes;
 pub mod paging;
 pub mod tools;
@@ -65,8 +66,11 @@ pub fn bc_db_schema() -> KvFileDbSchema {
             CERTS_BY_CREATED_BLOCK.to_owned() => KvFileDbStoreType::MultiIntKey,
             WOT_ID_INDEX.to_owned() => KvFileDbStoreType::Single,
             DIVIDENDS.to_owned() => KvFileDbStoreType::Multi,
+            UDS_HISTORY.to_owned() => KvFileDbStoreType::Single,
             UTXOS.to_owned() => KvFileDbStoreType::Single,
             CONSUMED_UTXOS.to_owned() => KvFileDbStoreType::SingleIntKey,
+            TXS_BY_CONDITION.to_owned() => KvFileDbStoreType::Multi,
+            TXS_BY_COMMENT_WORD.to_owned() => KvFileDbStoreType::Multi,
         ],
     }
 }
@@ -52,4 +52,9 @@ impl PagingFilter {
     pub fn is_in_page(&self, i: usize) -> bool {
         i >= self.page_size * self.page_number && i < self.page_size * (self.page_number + 1)
     }
+    #[inline]
+    /// Checks if a given element index is located after the current page (the reading can stop)
+    pub fn is_after_page(&self, i: usize) -> bool {
+        i >= self.page_size * (self.page_number + 1)
+    }
 }
@@ -18,11 +18,14 @@
 
 use crate::blocks::BlockDb;
 use crate::current_metadata::current_ud::CurrentUdDb;
+#[cfg(feature = "client-indexer")]
+use crate::history::{TxsHistoryFilter, TxsHistoryPage};
 use crate::indexes::identities::{IdentityDb, IdentityStateDb};
 use crate::{BcDbWithReaderStruct, DbReadable, DbReader};
 use dubp_common_doc::{BlockNumber, Blockstamp};
 use dup_crypto::keys::PubKey;
 use durs_dbs_tools::DbError;
+use durs_wot::WotId;
 #[cfg(feature = "mock")]
 use mockall::*;
 
@@ -88,7 +91,24 @@ pub trait BcDbInReadTx: BcDbWithReader {
     fn get_idty_state_by_pubkey(&self, pubkey: &PubKey)
         -> Result<Option<IdentityStateDb>, DbError>;
     fn get_identity_by_pubkey(&self, pubkey: &PubKey) -> Result<Option<IdentityDb>, DbError>;
+    fn get_identity_by_uid(&self, uid: &str) -> Result<Option<IdentityDb>, DbError>;
+    fn get_identity_by_wot_id(&self, wot_id: WotId) -> Result<Option<IdentityDb>, DbError>;
+    fn get_wot_id(&self, pubkey: &PubKey) -> Result<Option<WotId>, DbError>;
+    #[cfg(feature = "client-indexer")]
+    fn get_member_certs(
+        &self,
+        wot_id: WotId,
+        current: BlockNumber,
+    ) -> Result<Vec<(BlockNumber, WotId, WotId)>, DbError>;
+    fn get_cert_chainable_on_by_pubkey(&self, pubkey: &PubKey) -> Result<Option<u64>, DbError>;
     fn get_current_ud(&self) -> Result<Option<CurrentUdDb>, DbError>;
+    fn get_uds_history(&self) -> Result<Vec<CurrentUdDb>, DbError>;
+    #[cfg(feature = "client-indexer")]
+    fn get_txs_history(
+        &self,
+        pubkey: &PubKey,
+        filter: TxsHistoryFilter,
+    ) -> Result<TxsHistoryPage, DbError>;
 }
 
 impl<T> BcDbInReadTx for T
@@ -138,7 +158,45 @@ where
         crate::indexes::identities::get_identity_by_pubkey(self, pubkey)
     }
     #[inline]
+    fn get_identity_by_uid(&self, uid: &str) -> Result<Option<IdentityDb>, DbError> {
+        crate::indexes::identities::get_identity_by_uid(self, uid)
+    }
+    #[inline]
+    fn get_identity_by_wot_id(&self, wot_id: WotId) -> Result<Option<IdentityDb>, DbError> {
+        crate::indexes::identities::get_identity_by_wot_id(self, wot_id)
+    }
+    #[inline]
+    fn get_wot_id(&self, pubkey: &PubKey) -> Result<Option<WotId>, DbError> {
+        crate::indexes::identities::get_wot_id(self, pubkey)
+    }
+    #[cfg(feature = "client-indexer")]
+    #[inline]
+    fn get_member_certs(
+        &self,
+        wot_id: WotId,
+        current: BlockNumber,
+    ) -> Result<Vec<(BlockNumber, WotId, WotId)>, DbError> {
+        crate::indexes::certs::get_member_certs(self, wot_id, current)
+    }
+    #[inline]
+    fn get_cert_chainable_on_by_pubkey(&self, pubkey: &PubKey) -> Result<Option<u64>, DbError> {
+        crate::indexes::identities::get_cert_chainable_on_by_pubkey(self, pubkey)
+    }
+    #[inline]
     fn get_current_ud(&self) -> Result<Option<CurrentUdDb>, DbError> {
         crate::current_metadata::get_current_ud(self)
     }
+    #[inline]
+    fn get_uds_history(&self) -> Result<Vec<CurrentUdDb>, DbError> {
+        crate::current_metadata::get_uds_history(self)
+    }
+    #[cfg(feature = "client-indexer")]
+    #[inline]
+    fn get_txs_history(
+        &self,
+        pubkey: &PubKey,
+        filter: TxsHistoryFilter,
+    ) -> Result<TxsHistoryPage, DbError> {
+        crate::history::get_txs_history(self, pubkey, filter)
+    }
 }
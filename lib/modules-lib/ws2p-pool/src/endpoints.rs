@@ -0,0 +1,143 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Known endpoints of the pool, persisted in a file.
+
+use durs_network_documents::network_endpoint::EndpointEnum;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+
+/// Name of the endpoints file, in the datas directory of the node
+pub static POOL_ENDPOINTS_FILENAME: &str = "ws2p_endpoints.bin";
+
+/// Header written at the beginning of the endpoints file
+static POOL_ENDPOINTS_MAGIC: &[u8] = b"DWS2PPOL";
+
+/// Version of the endpoints file format
+static POOL_ENDPOINTS_VERSION: &u32 = &1;
+
+/// Endpoints file error
+#[derive(Debug)]
+pub enum PoolEndpointsError {
+    /// I/O error
+    IoErr(std::io::Error),
+    /// Serialization error
+    SerdeErr(bincode::Error),
+    /// Not an endpoints file
+    InvalidHeader,
+    /// Endpoints file written by a more recent version
+    UnknownVersion(u32),
+}
+
+impl From<std::io::Error> for PoolEndpointsError {
+    fn from(e: std::io::Error) -> Self {
+        PoolEndpointsError::IoErr(e)
+    }
+}
+
+impl From<bincode::Error> for PoolEndpointsError {
+    fn from(e: bincode::Error) -> Self {
+        PoolEndpointsError::SerdeErr(e)
+    }
+}
+
+/// Known endpoints, in insertion order
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PoolEndpoints {
+    endpoints: Vec<EndpointEnum>,
+    modified: bool,
+}
+
+impl PoolEndpoints {
+    /// Load endpoints file (no endpoints if the file does not exist)
+    pub fn load(file_path: &Path) -> Result<PoolEndpoints, PoolEndpointsError> {
+        if !file_path.exists() {
+            return Ok(PoolEndpoints::default());
+        }
+        let mut bin_endpoints = Vec::new();
+        File::open(file_path)?.read_to_end(&mut bin_endpoints)?;
+        if !bin_endpoints.starts_with(POOL_ENDPOINTS_MAGIC) {
+            return Err(PoolEndpointsError::InvalidHeader);
+        }
+        let bin_endpoints = &bin_endpoints[POOL_ENDPOINTS_MAGIC.len()..];
+        let version: u32 = bincode::deserialize(bin_endpoints)?;
+        if version > *POOL_ENDPOINTS_VERSION {
+            return Err(PoolEndpointsError::UnknownVersion(version));
+        }
+        Ok(PoolEndpoints {
+            endpoints: bincode::deserialize(&bin_endpoints[4..])?,
+            modified: false,
+        })
+    }
+    /// Write endpoints in file if they were modified since last load or save
+    pub fn save(&mut self, file_path: &Path) -> Result<bool, PoolEndpointsError> {
+        if !self.modified {
+            return Ok(false);
+        }
+        let mut bin_endpoints = POOL_ENDPOINTS_MAGIC.to_vec();
+        bin_endpoints.extend(bincode::serialize(POOL_ENDPOINTS_VERSION)?);
+        bin_endpoints.extend(bincode::serialize(&self.endpoints)?);
+        File::create(file_path)?.write_all(&bin_endpoints)?;
+        self.modified = false;
+        Ok(true)
+    }
+    /// Known endpoints
+    pub fn endpoints(&self) -> &[EndpointEnum] {
+        &self.endpoints
+    }
+    /// Insert an endpoint, returns `false` if it was already known
+    pub fn insert(&mut self, endpoint: EndpointEnum) -> bool {
+        if self.endpoints.contains(&endpoint) {
+            false
+        } else {
+            self.endpoints.push(endpoint);
+            self.modified = true;
+            true
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use durs_network_documents::network_endpoint::EndpointV2;
+
+    #[test]
+    fn save_and_load_endpoints() -> Result<(), PoolEndpointsError> {
+        let tmp_dir = tempfile::tempdir()?;
+        let file_path = tmp_dir.path().join("endpoints.bin");
+        assert_eq!(PoolEndpoints::default(), PoolEndpoints::load(&file_path)?);
+
+        let endpoint = EndpointV2::parse_from_raw("WS2P V2 g1.dunitrust.org 443 ws2p")
+            .expect("invalid endpoint");
+        let mut endpoints = PoolEndpoints::default();
+        assert!(endpoints.insert(endpoint.clone()));
+        assert!(!endpoints.insert(endpoint.clone()));
+        assert!(endpoints.save(&file_path)?);
+        // Not modified since last save
+        assert!(!endpoints.save(&file_path)?);
+
+        let loaded_endpoints = PoolEndpoints::load(&file_path)?;
+        assert_eq!(&[endpoint], loaded_endpoints.endpoints());
+
+        std::fs::write(&file_path, b"not an endpoints file")?;
+        if let Err(PoolEndpointsError::InvalidHeader) = PoolEndpoints::load(&file_path) {
+            Ok(())
+        } else {
+            panic!("expected InvalidHeader error")
+        }
+    }
+}
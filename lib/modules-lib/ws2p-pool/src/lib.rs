@@ -0,0 +1,210 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Connections pool shared by the WS2P modules.
+//!
+//! A node can run the WS2Pv1 and WS2Pv2 modules at the same time: both modules register their
+//! established connections in the same pool, so that a peer is never connected twice and the
+//! quotas are shared.
+
+#![deny(
+    clippy::option_unwrap_used,
+    clippy::result_unwrap_used,
+    missing_docs,
+    missing_debug_implementations,
+    missing_copy_implementations,
+    trivial_casts,
+    unsafe_code,
+    unstable_features,
+    unused_import_braces,
+    unused_qualifications
+)]
+
+pub mod endpoints;
+
+use durs_network_documents::NodeFullId;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Pool shared by all the modules of the process
+static SHARED_POOL: Lazy<WS2PPool> = Lazy::new(WS2PPool::default);
+
+/// WS2P API version of a connection
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum WS2PApi {
+    /// WS2Pv1
+    V1,
+    /// WS2Pv2
+    V2,
+}
+
+/// Direction of a connection
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ConnectionDirection {
+    /// Connection opened by the local node
+    Outgoing,
+    /// Connection opened by the remote node
+    Incoming,
+}
+
+/// Connection registered in the pool
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PoolConnection {
+    /// API of the module owning the connection
+    pub api: WS2PApi,
+    /// Direction of the connection
+    pub direction: ConnectionDirection,
+}
+
+/// Error returned by the pool
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PoolError {
+    /// The node is already connected with another API
+    AlreadyConnected(WS2PApi),
+}
+
+/// Established connections of all WS2P modules, indexed by remote node
+#[derive(Debug, Clone, Default)]
+pub struct WS2PPool(Arc<Mutex<HashMap<NodeFullId, PoolConnection>>>);
+
+impl WS2PPool {
+    /// Get the pool shared by all the modules of the process
+    pub fn shared() -> WS2PPool {
+        SHARED_POOL.clone()
+    }
+    fn connections(&self) -> MutexGuard<'_, HashMap<NodeFullId, PoolConnection>> {
+        // A panic while the lock is held cannot leave the map in an inconsistent state
+        self.0
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+    /// Register an established connection.
+    /// Fails if the node is already connected with another API.
+    pub fn register(
+        &self,
+        api: WS2PApi,
+        node_full_id: NodeFullId,
+        direction: ConnectionDirection,
+    ) -> Result<(), PoolError> {
+        let mut connections = self.connections();
+        if let Some(connection) = connections.get(&node_full_id) {
+            if connection.api != api {
+                return Err(PoolError::AlreadyConnected(connection.api));
+            }
+        }
+        connections.insert(node_full_id, PoolConnection { api, direction });
+        Ok(())
+    }
+    /// Unregister a connection, returns `false` if the node is not connected with this API
+    pub fn unregister(&self, api: WS2PApi, node_full_id: &NodeFullId) -> bool {
+        let mut connections = self.connections();
+        match connections.get(node_full_id) {
+            Some(connection) if connection.api == api => {
+                connections.remove(node_full_id);
+                true
+            }
+            _ => false,
+        }
+    }
+    /// Get the connection with a node, if any
+    pub fn connection(&self, node_full_id: &NodeFullId) -> Option<PoolConnection> {
+        self.connections().get(node_full_id).copied()
+    }
+    /// Is the node connected with another API than `api` ?
+    pub fn is_connected_with_other_api(&self, api: WS2PApi, node_full_id: &NodeFullId) -> bool {
+        if let Some(connection) = self.connection(node_full_id) {
+            connection.api != api
+        } else {
+            false
+        }
+    }
+    /// Number of established connections in this direction (all APIs)
+    pub fn count(&self, direction: ConnectionDirection) -> usize {
+        self.connections()
+            .values()
+            .filter(|connection| connection.direction == direction)
+            .count()
+    }
+    /// Number of connections that can still be opened in this direction within `quota`
+    pub fn free_rooms(&self, direction: ConnectionDirection, quota: usize) -> usize {
+        quota.saturating_sub(self.count(direction))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dup_crypto::keys::PubKey;
+    use durs_network_documents::NodeId;
+
+    fn node(id: u32) -> NodeFullId {
+        NodeFullId(NodeId(id), PubKey::default())
+    }
+
+    #[test]
+    fn a_node_is_connected_with_only_one_api() {
+        let pool = WS2PPool::default();
+        assert_eq!(
+            Ok(()),
+            pool.register(WS2PApi::V1, node(1), ConnectionDirection::Outgoing)
+        );
+        assert_eq!(
+            Err(PoolError::AlreadyConnected(WS2PApi::V1)),
+            pool.register(WS2PApi::V2, node(1), ConnectionDirection::Incoming)
+        );
+        assert!(pool.is_connected_with_other_api(WS2PApi::V2, &node(1)));
+        assert!(!pool.is_connected_with_other_api(WS2PApi::V1, &node(1)));
+
+        // Only the owner of a connection can unregister it
+        assert!(!pool.unregister(WS2PApi::V2, &node(1)));
+        assert!(pool.unregister(WS2PApi::V1, &node(1)));
+        assert_eq!(
+            Ok(()),
+            pool.register(WS2PApi::V2, node(1), ConnectionDirection::Incoming)
+        );
+        assert_eq!(
+            Some(PoolConnection {
+                api: WS2PApi::V2,
+                direction: ConnectionDirection::Incoming,
+            }),
+            pool.connection(&node(1))
+        );
+    }
+
+    #[test]
+    fn quotas_are_shared_between_apis() {
+        let pool = WS2PPool::default();
+        assert_eq!(
+            Ok(()),
+            pool.register(WS2PApi::V1, node(1), ConnectionDirection::Outgoing)
+        );
+        assert_eq!(
+            Ok(()),
+            pool.register(WS2PApi::V2, node(2), ConnectionDirection::Outgoing)
+        );
+        assert_eq!(
+            Ok(()),
+            pool.register(WS2PApi::V2, node(3), ConnectionDirection::Incoming)
+        );
+        assert_eq!(1, pool.free_rooms(ConnectionDirection::Outgoing, 3));
+        assert_eq!(0, pool.free_rooms(ConnectionDirection::Outgoing, 2));
+        assert_eq!(1, pool.free_rooms(ConnectionDirection::Incoming, 2));
+
+        // The shared pool is the same for all its users
+        let shared_pool = WS2PPool::shared();
+        assert!(Arc::ptr_eq(&shared_pool.0, &WS2PPool::shared().0));
+    }
+}
@@ -0,0 +1,381 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Background saving of the blockchain and wot databases.
+//!
+//! The writes mark the stores they change as dirty. Once the autosave interval is elapsed since
+//! the first unsaved write, or once the number of unsaved writes reaches the threshold, a copy of
+//! the dirty stores is sent to a saver thread which flushes them, then commits the write journal.
+//! A new write waits for the end of the save in progress, so the journal always describes the
+//! state of the saved files.
+
+use crate::write_journal::WriteJournal;
+use crate::*;
+use durs_conf::AutoSaveConf;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Instant;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Store tracked by the autosave
+pub enum DbStore {
+    /// Blockchain database
+    Blockchain,
+    /// Wot database
+    Wot,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+/// Stores changed since the last save
+pub struct DirtyStores {
+    /// The blockchain database is changed
+    pub blockchain: bool,
+    /// The wot database is changed
+    pub wot: bool,
+}
+
+impl DirtyStores {
+    /// Mark a store as changed
+    pub fn mark(&mut self, store: DbStore) {
+        match store {
+            DbStore::Blockchain => self.blockchain = true,
+            DbStore::Wot => self.wot = true,
+        }
+    }
+    /// Add the stores changed by another write
+    pub fn merge(&mut self, other: DirtyStores) {
+        self.blockchain |= other.blockchain;
+        self.wot |= other.wot;
+    }
+    /// Check if no store is changed
+    pub fn is_clean(&self) -> bool {
+        !self.blockchain && !self.wot
+    }
+}
+
+/// Save sent to the saver thread
+struct SaveRequest {
+    dirty_stores: DirtyStores,
+    /// Copy of the wot (if changed)
+    wot: Option<WotDB>,
+    /// Current blockstamp of the saved state
+    current_blockstamp: Blockstamp,
+}
+
+#[derive(Debug)]
+/// Background saver of the blockchain and wot databases
+pub struct AutoSaver {
+    conf: AutoSaveConf,
+    dirty_stores: DirtyStores,
+    unsaved_writes: usize,
+    first_unsaved_write: Option<Instant>,
+    /// A write is recorded as pending in the journal and not yet sent to the saver thread
+    write_begun: bool,
+    /// A save is in progress in the saver thread
+    saving: bool,
+    requests_sender: Option<mpsc::Sender<SaveRequest>>,
+    results_receiver: mpsc::Receiver<Result<Blockstamp, DbError>>,
+    saver_thread: Option<thread::JoinHandle<()>>,
+}
+
+impl AutoSaver {
+    /// Start the saver thread of the databases in the given folder
+    pub fn start(dbs_path: &Path, conf: AutoSaveConf) -> Result<AutoSaver, DbError> {
+        // The environment of the blockchain database is shared by the handlers of the process
+        let db = open_db(dbs_path)?;
+        let dbs_path = dbs_path.to_path_buf();
+        let (requests_sender, requests_receiver) = mpsc::channel::<SaveRequest>();
+        let (results_sender, results_receiver) = mpsc::channel();
+        let saver_thread = thread::Builder::new()
+            .name("bc_autosave".to_owned())
+            .spawn(move || {
+                let mut wot_db = None;
+                for request in requests_receiver {
+                    let result = save(&db, Some(&dbs_path), &mut wot_db, request);
+                    if results_sender.send(result).is_err() {
+                        break;
+                    }
+                }
+            })
+            .map_err(DbError::FileSystemError)?;
+        Ok(AutoSaver {
+            conf,
+            dirty_stores: DirtyStores::default(),
+            unsaved_writes: 0,
+            first_unsaved_write: None,
+            write_begun: false,
+            saving: false,
+            requests_sender: Some(requests_sender),
+            results_receiver,
+            saver_thread: Some(saver_thread),
+        })
+    }
+    /// Wait for the end of the save in progress, then record the start of a write in the journal
+    /// if it is not already recorded
+    pub fn begin_write(
+        &mut self,
+        write_journal: &mut WriteJournal,
+        current_blockstamp: Blockstamp,
+    ) -> Result<(), DbError> {
+        self.wait_saved()?;
+        if !self.write_begun {
+            write_journal.begin(current_blockstamp)?;
+            self.write_begun = true;
+        }
+        Ok(())
+    }
+    /// Record the stores changed by a write
+    pub fn end_write(&mut self, dirty_stores: DirtyStores) {
+        if !dirty_stores.is_clean() {
+            self.dirty_stores.merge(dirty_stores);
+            self.unsaved_writes += 1;
+            if self.first_unsaved_write.is_none() {
+                self.first_unsaved_write = Some(Instant::now());
+            }
+        }
+    }
+    /// Check if the dirty stores must be saved
+    pub fn is_save_due(&self, now: Instant) -> bool {
+        !self.saving
+            && !self.dirty_stores.is_clean()
+            && (self.unsaved_writes >= self.conf.writes_threshold
+                || self
+                    .first_unsaved_write
+                    .map_or(false, |first_unsaved_write| {
+                        now.duration_since(first_unsaved_write) >= self.conf.interval()
+                    }))
+    }
+    /// Send the dirty stores to the saver thread if their save is due, returns true if sent
+    pub fn save_if_due(
+        &mut self,
+        wot_databases: &WotsV10DBs,
+        current_blockstamp: Blockstamp,
+    ) -> Result<bool, DbError> {
+        self.poll_saved()?;
+        if self.is_save_due(Instant::now()) {
+            self.send_save(wot_databases, current_blockstamp)?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+    /// Save all dirty stores and wait for the end of the save (before stopping)
+    pub fn flush(
+        &mut self,
+        wot_databases: &mut WotsV10DBs,
+        current_blockstamp: Blockstamp,
+    ) -> Result<(), DbError> {
+        self.wait_saved()?;
+        if !self.dirty_stores.is_clean() {
+            self.send_save(wot_databases, current_blockstamp)?;
+            self.wait_saved()?;
+        } else if self.write_begun {
+            // Nothing changed since the start of the write
+            wot_databases.write_journal.commit(current_blockstamp)?;
+            self.write_begun = false;
+        }
+        Ok(())
+    }
+    fn send_save(
+        &mut self,
+        wot_databases: &WotsV10DBs,
+        current_blockstamp: Blockstamp,
+    ) -> Result<(), DbError> {
+        let wot = if self.dirty_stores.wot {
+            Some(wot_databases.wot_db.read(Clone::clone)?)
+        } else {
+            None
+        };
+        let request = SaveRequest {
+            dirty_stores: self.dirty_stores,
+            wot,
+            current_blockstamp,
+        };
+        if let Some(ref requests_sender) = self.requests_sender {
+            requests_sender
+                .send(request)
+                .map_err(|_| saver_thread_stopped())?;
+        }
+        self.dirty_stores = DirtyStores::default();
+        self.unsaved_writes = 0;
+        self.first_unsaved_write = None;
+        // The saver thread commits the journal, the next write will record a new one
+        self.write_begun = false;
+        self.saving = true;
+        Ok(())
+    }
+    fn poll_saved(&mut self) -> Result<(), DbError> {
+        if self.saving {
+            match self.results_receiver.try_recv() {
+                Ok(result) => self.saved(result)?,
+                Err(mpsc::TryRecvError::Empty) => {}
+                Err(mpsc::TryRecvError::Disconnected) => return Err(saver_thread_stopped()),
+            }
+        }
+        Ok(())
+    }
+    fn wait_saved(&mut self) -> Result<(), DbError> {
+        if self.saving {
+            let result = self
+                .results_receiver
+                .recv()
+                .map_err(|_| saver_thread_stopped())?;
+            self.saved(result)?;
+        }
+        Ok(())
+    }
+    fn saved(&mut self, result: Result<Blockstamp, DbError>) -> Result<(), DbError> {
+        self.saving = false;
+        let saved_blockstamp = result?;
+        debug!("Databases saved at {}.", saved_blockstamp);
+        Ok(())
+    }
+}
+
+impl Drop for AutoSaver {
+    fn drop(&mut self) {
+        // Closing the requests channel stops the saver thread
+        self.requests_sender = None;
+        if let Some(saver_thread) = self.saver_thread.take() {
+            if saver_thread.join().is_err() {
+                error!("The databases saver thread panicked.");
+            }
+        }
+    }
+}
+
+fn saver_thread_stopped() -> DbError {
+    DbError::WriteAbort {
+        reason: "the saver thread is stopped".to_owned(),
+    }
+}
+
+/// Flush the dirty stores, then commit the write journal (in the saver thread)
+fn save(
+    db: &Db,
+    dbs_path: Option<&PathBuf>,
+    wot_db: &mut Option<BinFreeStructDb<WotDB>>,
+    request: SaveRequest,
+) -> Result<Blockstamp, DbError> {
+    if let Some(wot) = request.wot {
+        if wot_db.is_none() {
+            *wot_db = Some(open_free_struct_db::<WotDB>(dbs_path, WOT_DB_FILENAME)?);
+        }
+        if let Some(ref wot_db) = wot_db {
+            wot_db.write(|db_wot| *db_wot = wot)?;
+            wot_db.save()?;
+        }
+    }
+    if request.dirty_stores.blockchain {
+        db.save()?;
+    }
+    WriteJournal::open(dbs_path).commit(request.current_blockstamp)?;
+    Ok(request.current_blockstamp)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::write_journal::WriteJournalState;
+    use dubp_common_doc::BlockHash;
+    use durs_wot::data::WebOfTrust;
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_dirty_stores() {
+        let mut dirty_stores = DirtyStores::default();
+        assert!(dirty_stores.is_clean());
+        dirty_stores.mark(DbStore::Wot);
+        assert_eq!(
+            DirtyStores {
+                blockchain: false,
+                wot: true
+            },
+            dirty_stores
+        );
+        dirty_stores.merge(DirtyStores {
+            blockchain: true,
+            wot: false,
+        });
+        assert_eq!(
+            DirtyStores {
+                blockchain: true,
+                wot: true
+            },
+            dirty_stores
+        );
+    }
+
+    #[test]
+    fn test_autosave() -> Result<(), DbError> {
+        let tmp_dir = tempdir().map_err(DbError::FileSystemError)?;
+        let dbs_path = tmp_dir.path().to_owned();
+        let _db = open_db(&dbs_path)?;
+        let mut wot_databases = WotsV10DBs::open(Some(&dbs_path));
+        let mut autosaver = AutoSaver::start(
+            &dbs_path,
+            AutoSaveConf {
+                interval_in_secs: 3_600,
+                writes_threshold: 2,
+            },
+        )?;
+        let blockstamp = Blockstamp {
+            id: BlockNumber(1),
+            hash: BlockHash(Hash([1; 32])),
+        };
+        let wot_dirty = DirtyStores {
+            blockchain: true,
+            wot: true,
+        };
+
+        autosaver.begin_write(&mut wot_databases.write_journal, Blockstamp::default())?;
+        wot_databases.wot_db.write(|wot| {
+            wot.add_node();
+        })?;
+        autosaver.end_write(wot_dirty);
+        assert!(!autosaver.is_save_due(Instant::now()));
+        assert!(autosaver.is_save_due(Instant::now() + Duration::from_secs(3_600)));
+        autosaver.end_write(DirtyStores::default());
+        assert!(!autosaver.is_save_due(Instant::now()));
+        autosaver.end_write(wot_dirty);
+        assert!(autosaver.save_if_due(&wot_databases, blockstamp)?);
+
+        // The next write waits for the end of the save
+        autosaver.begin_write(&mut wot_databases.write_journal, blockstamp)?;
+        assert_eq!(
+            Some(WriteJournalState::Pending),
+            WriteJournal::open(Some(&dbs_path))
+                .read()?
+                .map(|entry| entry.state)
+        );
+        autosaver.flush(&mut wot_databases, blockstamp)?;
+        assert_eq!(
+            Some(WriteJournalState::Committed),
+            WriteJournal::open(Some(&dbs_path))
+                .read()?
+                .map(|entry| entry.state)
+        );
+        assert_eq!(
+            1,
+            WotsV10DBs::open(Some(&dbs_path))
+                .wot_db
+                .read(WebOfTrust::size)?
+        );
+
+        Ok(())
+    }
+}
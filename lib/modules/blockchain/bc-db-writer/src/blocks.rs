@@ -18,7 +18,7 @@
 pub mod fork_tree;
 
 use crate::*;
-use dubp_block_doc::block::BlockDocumentTrait;
+use dubp_block_doc::block::{BlockDocument, BlockDocumentTrait};
 use dubp_common_doc::traits::Document;
 use durs_bc_db_reader::blocks::fork_tree::ForkTree;
 use durs_bc_db_reader::blocks::BlockDb;
@@ -34,18 +34,20 @@ pub fn insert_new_head_block(
     fork_tree: Option<&mut ForkTree>,
     dal_block: BlockDb,
 ) -> Result<(), DbError> {
-    // Serialize datas
-    let bin_dal_block = durs_dbs_tools::to_bytes(&dal_block)?;
-
-    let main_blocks_store = db.get_int_store(MAIN_BLOCKS);
-    let fork_blocks_store = db.get_store(FORK_BLOCKS);
+    let mut batch = WriteBatch::new();
+    prepare_new_head_block(&mut batch, fork_tree, &dal_block)?;
+    batch.apply(db, w)
+}
 
-    // Insert block in MAIN_BLOCKS store
-    main_blocks_store.put(
-        w.as_mut(),
-        *dal_block.block.number(),
-        &Db::db_value(&bin_dal_block)?,
-    )?;
+/// Prepare the insertion of a new head block in `batch` (the block is serialized once, outside
+/// of the write transaction)
+pub fn prepare_new_head_block(
+    batch: &mut WriteBatch,
+    fork_tree: Option<&mut ForkTree>,
+    dal_block: &BlockDb,
+) -> Result<(), DbError> {
+    // Serialize datas
+    let bin_dal_block = durs_dbs_tools::to_bytes(dal_block)?;
 
     if let Some(fork_tree) = fork_tree {
         // Insert head block in fork tree
@@ -53,22 +55,24 @@ pub fn insert_new_head_block(
             crate::blocks::fork_tree::insert_new_head_block(fork_tree, dal_block.blockstamp())?;
         // Insert head block in ForkBlocks
         let blockstamp_bytes: Vec<u8> = dal_block.blockstamp().into();
-        fork_blocks_store.put(
-            w.as_mut(),
-            &blockstamp_bytes,
-            &Db::db_value(&bin_dal_block)?,
-        )?;
+        batch.put(
+            FORK_BLOCKS,
+            blockstamp_bytes,
+            BatchValue::Blob(bin_dal_block.clone()),
+        );
         // Remove too old blocks
         for blockstamp in removed_blockstamps {
             let blockstamp_bytes: Vec<u8> = blockstamp.into();
-            if fork_blocks_store
-                .get(w.as_ref(), &blockstamp_bytes)?
-                .is_some()
-            {
-                fork_blocks_store.delete(w.as_mut(), &blockstamp_bytes)?;
-            }
+            batch.delete(FORK_BLOCKS, blockstamp_bytes);
         }
     }
+
+    // Insert block in MAIN_BLOCKS store
+    batch.put(
+        MAIN_BLOCKS,
+        dal_block.block.number().0,
+        BatchValue::Blob(bin_dal_block),
+    );
     Ok(())
 }
 
@@ -79,6 +83,28 @@ pub fn remove_block(db: &Db, w: &mut DbWriter, block_number: BlockNumber) -> Res
     Ok(())
 }
 
+/// Drop the transactions of a block of the local blockchain (light storage mode)
+pub fn prune_block_transactions(
+    db: &Db,
+    w: &mut DbWriter,
+    block_number: BlockNumber,
+) -> Result<(), DbError> {
+    let main_blocks_store = db.get_int_store(MAIN_BLOCKS);
+    let dal_block_opt = main_blocks_store
+        .get(w.as_ref(), block_number.0)?
+        .map(from_db_value::<BlockDb>)
+        .transpose()?;
+    if let Some(mut dal_block) = dal_block_opt {
+        let BlockDocument::V10(ref mut block_v10) = dal_block.block;
+        if !block_v10.transactions.is_empty() {
+            block_v10.transactions.clear();
+            let bin_dal_block = durs_dbs_tools::to_bytes(&dal_block)?;
+            main_blocks_store.put(w.as_mut(), block_number.0, &Db::db_value(&bin_dal_block)?)?;
+        }
+    }
+    Ok(())
+}
+
 /// Insert new fork Block in databases
 pub fn insert_new_fork_block(
     db: &Db,
@@ -135,3 +161,43 @@ pub fn insert_new_fork_block(
         Ok(false)
     }
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use durs_bc_db_reader::blocks::get_block_in_local_blockchain;
+
+    #[test]
+    fn test_prune_block_transactions() -> Result<(), DbError> {
+        let db = crate::tests::open_tmp_db()?;
+        let block = dubp_blocks_tests_tools::mocks::gen_mock_normal_block_v10();
+        let block_number = block.number;
+        assert!(!block.transactions.is_empty());
+
+        db.write(|mut w| {
+            insert_new_head_block(
+                &db,
+                &mut w,
+                None,
+                BlockDb {
+                    block: BlockDocument::V10(block.clone()),
+                    expire_certs: None,
+                },
+            )?;
+            prune_block_transactions(&db, &mut w, block_number)?;
+            Ok(WriteResp::from(w))
+        })?;
+
+        let BlockDocument::V10(pruned_block) = db
+            .read(|r| {
+                get_block_in_local_blockchain(&BcDbRwWithReader { db: &db, r }, block_number)
+            })?
+            .expect("block must be stored");
+        assert!(pruned_block.transactions.is_empty());
+        assert_eq!(block.inner_hash, pruned_block.inner_hash);
+        assert_eq!(block.hash, pruned_block.hash);
+
+        Ok(())
+    }
+}
@@ -19,12 +19,72 @@ use crate::*;
 use dubp_block_doc::block::BlockDocumentTrait;
 use dubp_block_doc::BlockDocument;
 use dubp_common_doc::traits::Document;
-use durs_bc_db_reader::constants::CURRENT_METADATA;
-use durs_bc_db_reader::current_metadata::current_ud::CurrentUdDbInternal;
+use durs_bc_db_reader::constants::{CURRENT_METADATA, UDS_HISTORY};
+use durs_bc_db_reader::current_metadata::current_ud::{CurrentUdDb, CurrentUdDbInternal};
 use durs_bc_db_reader::current_metadata::CurrentMetaDataKey;
 use durs_bc_db_reader::from_db_value;
+use durs_bc_db_reader::BcDbRead;
 use durs_bc_db_reader::DbValue;
 
+/// Write the version of the database schema if it is not written yet, return the version
+pub fn init_db_version(db: &Db) -> Result<usize, DbError> {
+    if let Some(db_version) = durs_bc_db_reader::current_metadata::get_db_version(db)? {
+        Ok(db_version)
+    } else {
+        // The databases written before the versioning of the schema are at the first version
+        let db_version = if db
+            .r(|db_r| durs_bc_db_reader::current_metadata::get_current_blockstamp(db_r))?
+            .is_some()
+        {
+            1
+        } else {
+            *durs_bc_db_reader::constants::CURRENT_DB_VERSION
+        };
+        set_db_version(db, db_version)?;
+        Ok(db_version)
+    }
+}
+
+/// Write the version of the database schema
+pub fn set_db_version(db: &Db, db_version: usize) -> Result<(), DbError> {
+    db.write(|mut w| {
+        db.get_int_store(CURRENT_METADATA).put(
+            w.as_mut(),
+            CurrentMetaDataKey::DbVersion.to_u32(),
+            &DbValue::U64(db_version as u64),
+        )?;
+        Ok(WriteResp::from(w))
+    })
+}
+
+/// Enable or disable light storage mode, return previous mode
+pub fn set_light_storage(db: &Db, w: &mut DbWriter, light_storage: bool) -> Result<bool, DbError> {
+    let previous_light_storage =
+        durs_bc_db_reader::current_metadata::is_light_storage(&BcDbRwWithWriter { db, w })?;
+    db.get_int_store(CURRENT_METADATA).put(
+        w.as_mut(),
+        CurrentMetaDataKey::LightStorage.to_u32(),
+        &DbValue::U64(light_storage as u64),
+    )?;
+    Ok(previous_light_storage)
+}
+
+/// Enable or disable transactions search index, return previous state
+pub fn set_tx_search_index(
+    db: &Db,
+    w: &mut DbWriter,
+    tx_search_index: bool,
+) -> Result<bool, DbError> {
+    let previous_tx_search_index =
+        durs_bc_db_reader::current_metadata::is_tx_search_index(&BcDbRwWithWriter { db, w })?;
+    db.get_int_store(CURRENT_METADATA).put(
+        w.as_mut(),
+        CurrentMetaDataKey::TxSearchIndex.to_u32(),
+        &DbValue::U64(tx_search_index as u64),
+    )?;
+    Ok(previous_tx_search_index)
+}
+
 /// Update CURRENT_METADATA
 pub fn update_current_metadata(
     db: &Db,
@@ -46,8 +106,7 @@ pub fn update_current_metadata(
         &DbValue::U64(new_current_block.common_time()),
     )?;
     // Update current UD
-    let BlockDocument::V10(ref block_v10) = new_current_block;
-    if block_v10.dividend.is_some() {
+    if let Some(new_ud) = CurrentUdDb::from_block(new_current_block) {
         let mut current_ud_internal = db
             .get_int_store(CURRENT_METADATA)
             .get(w.as_ref(), CurrentMetaDataKey::CurrentUd.to_u32())?
@@ -61,6 +120,13 @@ pub fn update_current_metadata(
             CurrentMetaDataKey::CurrentUd.to_u32(),
             &DbValue::Blob(&current_ud_internal_bytes),
         )?;
+        // Add UD to history
+        let new_ud_bytes = durs_dbs_tools::to_bytes(&new_ud)?;
+        db.get_store(UDS_HISTORY).put(
+            w.as_mut(),
+            new_ud.block_number.0.to_be_bytes(),
+            &DbValue::Blob(&new_ud_bytes),
+        )?;
     }
 
     Ok(())
@@ -102,6 +168,12 @@ pub fn revert_current_metadata(
             CurrentMetaDataKey::CurrentUd.to_u32(),
             &DbValue::Blob(&current_ud_internal_bytes),
         )?;
+        // Remove UD from history
+        let ud_key = block_v10.number.0.to_be_bytes();
+        let uds_history_store = db.get_store(UDS_HISTORY);
+        if uds_history_store.get(w.as_ref(), ud_key)?.is_some() {
+            uds_history_store.delete(w.as_mut(), ud_key)?;
+        }
     }
 
     Ok(())
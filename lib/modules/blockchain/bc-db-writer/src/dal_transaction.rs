@@ -0,0 +1,113 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Write spanning the blockchain database and the wot databases.
+//!
+//! A `DalTransaction` records the write in the write journal before any change, so the databases
+//! are either all persisted by `commit()`, or none of them: if the node stops before the commit,
+//! the recovery at the next start rolls the wot databases back to their state at `begin()` and
+//! the blockchain database keeps its last saved state.
+
+use crate::*;
+
+/// Write spanning the blockchain database and the wot databases
+pub struct DalTransaction<'a> {
+    db: &'a Db,
+    wot_databases: &'a mut WotsV10DBs,
+}
+
+impl<'a> DalTransaction<'a> {
+    /// Begin a write from the current blockstamp
+    pub fn begin(
+        db: &'a Db,
+        wot_databases: &'a mut WotsV10DBs,
+        current_blockstamp: Blockstamp,
+    ) -> Result<DalTransaction<'a>, DbError> {
+        wot_databases.write_journal.begin(current_blockstamp)?;
+        Ok(DalTransaction { db, wot_databases })
+    }
+    /// Record the block being applied (or reverted)
+    pub fn applying(&mut self, blockstamp: Blockstamp) -> Result<(), DbError> {
+        self.wot_databases.write_journal.applying(blockstamp)
+    }
+    /// Blockchain database
+    pub fn db(&self) -> &Db {
+        self.db
+    }
+    /// Wot databases
+    pub fn wot_databases(&self) -> &WotsV10DBs {
+        self.wot_databases
+    }
+    /// Write in the blockchain database (visible to the readers but not persisted until the commit)
+    pub fn write<D, F>(&self, f: F) -> Result<D, DbError>
+    where
+        F: FnOnce(DbWriter) -> Result<WriteResp<D>, DbError>,
+    {
+        self.db.write(f)
+    }
+    /// Persist all databases, then record the write as committed at the current blockstamp
+    pub fn commit(self, current_blockstamp: Blockstamp) -> Result<(), DbError> {
+        self.wot_databases.wot_db.save()?;
+        self.db.save()?;
+        self.wot_databases.write_journal.commit(current_blockstamp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::write_journal::{WriteJournal, WriteJournalState};
+    use dubp_common_doc::BlockHash;
+    use durs_bc_db_reader::constants::CURRENT_METADATA;
+    use durs_bc_db_reader::current_metadata::CurrentMetaDataKey;
+    use durs_bc_db_reader::DbValue;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_dal_transaction() -> Result<(), DbError> {
+        let tmp_dir = tempdir().map_err(DbError::FileSystemError)?;
+        let dbs_path = tmp_dir.path().to_owned();
+        let db = open_db(&dbs_path)?;
+        let mut wot_databases = WotsV10DBs::open(Some(&dbs_path));
+        let blockstamp = Blockstamp {
+            id: BlockNumber(1),
+            hash: BlockHash(Hash([1; 32])),
+        };
+        let read_journal_state = || {
+            WriteJournal::open(Some(&dbs_path))
+                .read()
+                .map(|entry| entry.map(|entry| entry.state))
+        };
+
+        let mut dal_tx = DalTransaction::begin(&db, &mut wot_databases, Blockstamp::default())?;
+        dal_tx.applying(blockstamp)?;
+        dal_tx.write(|mut w| {
+            db.get_int_store(CURRENT_METADATA).put(
+                w.as_mut(),
+                CurrentMetaDataKey::CurrentBlockchainTime.to_u32(),
+                &DbValue::U64(42),
+            )?;
+            Ok(WriteResp::from(w))
+        })?;
+        // Interrupted before the commit, the write is pending
+        assert_eq!(Some(WriteJournalState::Pending), read_journal_state()?);
+
+        dal_tx.commit(blockstamp)?;
+        assert_eq!(Some(WriteJournalState::Committed), read_journal_state()?);
+
+        Ok(())
+    }
+}
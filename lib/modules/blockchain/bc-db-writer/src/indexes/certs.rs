@@ -52,6 +52,17 @@ pub fn write_certification(
         &DbValue::Blob(&bin_member_datas),
     )?;
     // Add cert in certs_db
+    index_certification(db, w, source, target, created_block_id)
+}
+
+/// Add a certification to the index of the certifications by created block
+pub fn index_certification(
+    db: &Db,
+    w: &mut DbWriter,
+    source: WotId,
+    target: WotId,
+    created_block_id: BlockNumber,
+) -> Result<(), DbError> {
     db.get_multi_int_store(CERTS_BY_CREATED_BLOCK).put(
         w.as_mut(),
         created_block_id.0,
@@ -108,6 +108,16 @@ pub fn create_identity(
     db.get_int_store(IDENTITIES)
         .put(w.as_mut(), wot_id.0 as u32, &DbValue::Blob(&bin_idty))?;
     // Write membership
+    index_membership(db, w, wot_id, ms_created_block_id)
+}
+
+/// Add a membership to the index of the memberships by created block
+pub fn index_membership(
+    db: &Db,
+    w: &mut DbWriter,
+    wot_id: WotId,
+    ms_created_block_id: BlockNumber,
+) -> Result<(), DbError> {
     db.get_multi_int_store(MBS_BY_CREATED_BLOCK).put(
         w.as_mut(),
         ms_created_block_id.0,
@@ -279,12 +289,7 @@ pub fn renewal_identity(
         &DbValue::Blob(&bin_idty),
     )?;
     // Update MsExpirV10DB
-    db.get_multi_int_store(MBS_BY_CREATED_BLOCK).put(
-        w.as_mut(),
-        ms_created_block_id.0,
-        &DbValue::U64(idty_wot_id.0 as u64),
-    )?;
-    Ok(())
+    index_membership(db, w, idty_wot_id, ms_created_block_id)
 }
 
 /// Remove identity from databases
@@ -23,6 +23,7 @@ use durs_common_tools::fatal_error;
 use crate::*;
 use dubp_indexes::sindex::{SourceUniqueIdV10, UniqueIdUTXOv10};
 use durs_bc_db_reader::indexes::sources::UTXOV10;
+use durs_bc_db_reader::indexes::transactions::{comment_words, tx_conditions};
 
 #[derive(Debug)]
 /// Transaction error
@@ -107,6 +108,10 @@ pub fn revert_tx<S: std::hash::BuildHasher>(
             )?;
         }
     }
+    // Remove transaction from search index
+    if durs_bc_db_reader::current_metadata::is_tx_search_index(&BcDbRwWithWriter { db, w })? {
+        unindex_tx_search(db, w, tx_doc_v10, tx_hash)?;
+    }
     Ok(())
 }
 
@@ -214,6 +219,58 @@ pub fn apply_and_write_tx(
             &DbValue::Blob(&utxo_value_bytes[..]),
         )?;
     }
+    // Index transaction for search
+    if durs_bc_db_reader::current_metadata::is_tx_search_index(&BcDbRwWithWriter { db, w })? {
+        index_tx_search(db, w, tx_doc_v10, tx_hash)?;
+    }
+    Ok(())
+}
+
+/// Index the output conditions and the comment words of a transaction
+fn index_tx_search(
+    db: &Db,
+    w: &mut DbWriter,
+    tx_doc_v10: &TransactionDocumentV10,
+    tx_hash: Hash,
+) -> Result<(), DbError> {
+    for condition in tx_conditions(tx_doc_v10) {
+        db.get_multi_store(TXS_BY_CONDITION).put(
+            w.as_mut(),
+            &condition,
+            &DbValue::Blob(&tx_hash.0),
+        )?;
+    }
+    for word in comment_words(tx_doc_v10.get_comment()) {
+        db.get_multi_store(TXS_BY_COMMENT_WORD).put(
+            w.as_mut(),
+            &word,
+            &DbValue::Blob(&tx_hash.0),
+        )?;
+    }
+    Ok(())
+}
+
+/// Remove a transaction from the search index
+fn unindex_tx_search(
+    db: &Db,
+    w: &mut DbWriter,
+    tx_doc_v10: &TransactionDocumentV10,
+    tx_hash: Hash,
+) -> Result<(), DbError> {
+    for condition in tx_conditions(tx_doc_v10) {
+        db.get_multi_store(TXS_BY_CONDITION).delete(
+            w.as_mut(),
+            &condition,
+            &DbValue::Blob(&tx_hash.0),
+        )?;
+    }
+    for word in comment_words(tx_doc_v10.get_comment()) {
+        db.get_multi_store(TXS_BY_COMMENT_WORD).delete(
+            w.as_mut(),
+            &word,
+            &DbValue::Blob(&tx_hash.0),
+        )?;
+    }
     Ok(())
 }
 
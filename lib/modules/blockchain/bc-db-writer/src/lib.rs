@@ -32,20 +32,26 @@
 #[macro_use]
 extern crate log;
 
+pub mod autosave;
 pub mod blocks;
 pub mod current_metadata;
+pub mod dal_transaction;
 pub mod indexes;
+pub mod migrations;
+pub mod write_journal;
 pub mod writers;
 
 pub use durs_dbs_tools::kv_db_old::{
-    KvFileDbHandler, KvFileDbRead as DbReadable, KvFileDbRoHandler, KvFileDbSchema,
-    KvFileDbStoreType, KvFileDbValue, KvFileDbWriter as DbWriter, WriteResp,
+    BatchKey, BatchValue, KvFileDbHandler, KvFileDbRead as DbReadable, KvFileDbRoHandler,
+    KvFileDbSchema, KvFileDbStats, KvFileDbStoreType, KvFileDbValue, KvFileDbWriter as DbWriter,
+    Migration, WriteBatch, WriteResp, KV_FILE_DB_DATA_FILENAME,
 };
 pub use durs_dbs_tools::{
     open_free_struct_db, open_free_struct_file_db, open_free_struct_memory_db,
 };
 pub use durs_dbs_tools::{BinFreeStructDb, DbError};
 
+use crate::write_journal::WriteJournal;
 use dubp_common_doc::{BlockNumber, Blockstamp};
 use dubp_indexes::sindex::UniqueIdUTXOv10;
 use dubp_user_docs::documents::transaction::*;
@@ -64,10 +70,14 @@ pub type DbReader = KvFileDbRoHandler;
 /// Database containing the wot graph (each node of the graph in an u32)
 pub type WotDB = RustyWebOfTrust;
 
+pub use durs_bc_db_reader::constants::WOT_DB_FILENAME;
+
 /// Open database
 #[inline]
 pub fn open_db(path: &Path) -> Result<Db, DbError> {
-    Db::open_db(path, &durs_bc_db_reader::bc_db_schema())
+    let db = Db::open_db(path, &durs_bc_db_reader::bc_db_schema())?;
+    current_metadata::init_db_version(&db)?;
+    Ok(db)
 }
 
 /// R/W Database with reader
@@ -100,14 +110,17 @@ impl<'w, 'db: 'w> durs_common_tools::traits::NotMock for BcDbRwWithWriter<'w, 'd
 pub struct WotsV10DBs {
     /// Store wot graph
     pub wot_db: BinFreeStructDb<WotDB>,
+    /// Journal of the writes in blockchain and wot databases
+    pub write_journal: WriteJournal,
 }
 
 impl WotsV10DBs {
     /// Open wot databases from their respective files
     pub fn open(db_path: Option<&PathBuf>) -> WotsV10DBs {
         WotsV10DBs {
-            wot_db: open_free_struct_db::<RustyWebOfTrust>(db_path, "wot.db")
+            wot_db: open_free_struct_db::<RustyWebOfTrust>(db_path, WOT_DB_FILENAME)
                 .expect("Fail to open WotDB"),
+            write_journal: WriteJournal::open(db_path),
         }
     }
     /// Save wot databases from their respective files
@@ -0,0 +1,77 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Migrations of the blockchain database schema.
+//! To change the schema: increment `CURRENT_DB_VERSION` and register the migration
+//! from the previous version in `bc_db_migrations()`.
+
+use crate::*;
+use durs_bc_db_reader::constants::CURRENT_DB_VERSION;
+use durs_dbs_tools::kv_db_old::{Migration, MigrationOptions, MigrationRegistry};
+
+/// Name of the folder where the database is backed up before a migration
+/// (in the blockchain database folder)
+pub static MIGRATION_BACKUP_DIRNAME: &str = "backup_before_migration";
+
+/// Migrations of the blockchain database schema
+pub fn bc_db_migrations() -> MigrationRegistry {
+    MigrationRegistry::new(*CURRENT_DB_VERSION, vec![])
+}
+
+/// Migrate the blockchain database to the current version of the schema
+/// (the database is backed up in the `MIGRATION_BACKUP_DIRNAME` folder before the first migration).
+/// Return the applied migrations (the migrations to apply in dry-run mode).
+/// The version of a database written before the versioning of the schema is written even in dry-run mode.
+///
+/// The database must not be used by any other handler during the migration.
+pub fn migrate_db(db: &mut Db, dbs_path: &Path, dry_run: bool) -> Result<Vec<Migration>, DbError> {
+    let db_version = current_metadata::init_db_version(db)?;
+    bc_db_migrations().migrate(
+        db,
+        db_version,
+        &MigrationOptions {
+            dry_run,
+            backup_path: Some(dbs_path.join(MIGRATION_BACKUP_DIRNAME)),
+        },
+        current_metadata::set_db_version,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn migrate_current_db() -> Result<(), DbError> {
+        let tmp_dir = tempdir().map_err(DbError::FileSystemError)?;
+        let mut db = open_db(tmp_dir.path())?;
+
+        assert!(migrate_db(&mut db, tmp_dir.path(), false)?.is_empty());
+        assert_eq!(
+            Some(*CURRENT_DB_VERSION),
+            durs_bc_db_reader::current_metadata::get_db_version(&db)?
+        );
+        assert!(!tmp_dir.path().join(MIGRATION_BACKUP_DIRNAME).exists());
+
+        // A database of a more recent software is refused
+        current_metadata::set_db_version(&db, *CURRENT_DB_VERSION + 1)?;
+        match migrate_db(&mut db, tmp_dir.path(), true) {
+            Err(DbError::UnsupportedVersion { .. }) => Ok(()),
+            r => panic!("unexpected migration result: {:?}", r),
+        }
+    }
+}
@@ -0,0 +1,274 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Write-ahead journal shared by the blockchain database and the file databases.
+//!
+//! The blockchain database stores (blocks, identities, certifications, balances) are written in
+//! atomic transactions, but the file databases (wot) are saved separately, so a crash in the middle
+//! of a block application leaves them out of step. Before each write, the journal saves the undo
+//! data of the file databases (a copy of their files) and records the write as pending, then records
+//! each block being applied. Once all databases are saved, the write is committed.
+//! A running flag file also detects an unclean shutdown.
+
+use crate::*;
+use durs_bc_db_reader::{from_db_value, DbValue};
+use serde_derive::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+/// Name of the file flagging a running node (in the blockchain database folder)
+pub static RUNNING_FLAG_FILENAME: &str = "running.lock";
+/// Name of the write journal file (in the blockchain database folder)
+pub static WRITE_JOURNAL_FILENAME: &str = "write_journal.bin";
+/// Extension of the undo data files
+pub static UNDO_FILE_EXTENSION: &str = "undo";
+/// File databases saved separately from the blockchain database
+static FILE_STORES: &[&str] = &[WOT_DB_FILENAME];
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
+/// State of the last write
+pub enum WriteJournalState {
+    /// The write has started but the databases are not all saved
+    Pending,
+    /// The databases are all saved
+    Committed,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
+/// Last write recorded in the journal
+pub struct WriteJournalEntry {
+    /// State of the write
+    pub state: WriteJournalState,
+    /// Current blockstamp before the write (if pending) or after it (if committed)
+    pub blockstamp: Blockstamp,
+    /// Blockstamp of the block being applied (if pending)
+    pub applying: Option<Blockstamp>,
+}
+
+#[derive(Clone, Debug)]
+/// Write journal (does nothing for in-memory databases)
+pub struct WriteJournal {
+    dir_path: Option<PathBuf>,
+    pending: Option<WriteJournalEntry>,
+}
+
+impl WriteJournal {
+    /// Open the write journal of the databases in the given folder
+    pub fn open(db_path: Option<&PathBuf>) -> WriteJournal {
+        WriteJournal {
+            dir_path: db_path.cloned(),
+            pending: None,
+        }
+    }
+    /// Save the undo data of the file databases, then record the start of a write from the current blockstamp
+    pub fn begin(&mut self, current_blockstamp: Blockstamp) -> Result<(), DbError> {
+        if let Some(ref dir_path) = self.dir_path {
+            for file_store in FILE_STORES {
+                let file_path = dir_path.join(file_store);
+                if file_path.exists() {
+                    copy_synced(&file_path, &undo_file_path(dir_path, file_store))?;
+                }
+            }
+        }
+        let entry = WriteJournalEntry {
+            state: WriteJournalState::Pending,
+            blockstamp: current_blockstamp,
+            applying: None,
+        };
+        self.write_entry(entry)?;
+        self.pending = Some(entry);
+        Ok(())
+    }
+    /// Record the block being applied by the pending write
+    pub fn applying(&mut self, blockstamp: Blockstamp) -> Result<(), DbError> {
+        if let Some(mut entry) = self.pending {
+            entry.applying = Some(blockstamp);
+            self.write_entry(entry)?;
+            self.pending = Some(entry);
+        }
+        Ok(())
+    }
+    /// Record that the databases are all saved at the current blockstamp
+    pub fn commit(&mut self, current_blockstamp: Blockstamp) -> Result<(), DbError> {
+        self.pending = None;
+        self.write_entry(WriteJournalEntry {
+            state: WriteJournalState::Committed,
+            blockstamp: current_blockstamp,
+            applying: None,
+        })
+    }
+    /// Read the last write recorded in the journal
+    pub fn read(&self) -> Result<Option<WriteJournalEntry>, DbError> {
+        if let Some(ref dir_path) = self.dir_path {
+            let file_path = dir_path.join(WRITE_JOURNAL_FILENAME);
+            if file_path.exists() {
+                let bytes = fs::read(file_path).map_err(DbError::FileSystemError)?;
+                return Ok(Some(from_db_value(DbValue::Blob(&bytes))?));
+            }
+        }
+        Ok(None)
+    }
+    /// Restore the file databases from the undo data of the last write (they must be re-opened)
+    pub fn rollback_file_stores(&self) -> Result<bool, DbError> {
+        let mut rolled_back = false;
+        if let Some(ref dir_path) = self.dir_path {
+            for file_store in FILE_STORES {
+                let undo_file_path = undo_file_path(dir_path, file_store);
+                if undo_file_path.exists() {
+                    copy_synced(&undo_file_path, &dir_path.join(file_store))?;
+                    rolled_back = true;
+                }
+            }
+        }
+        Ok(rolled_back)
+    }
+    /// Create the running flag file
+    pub fn set_running_flag(&self) -> Result<(), DbError> {
+        if let Some(ref dir_path) = self.dir_path {
+            fs::File::create(dir_path.join(RUNNING_FLAG_FILENAME))
+                .map_err(DbError::FileSystemError)?;
+        }
+        Ok(())
+    }
+    /// Remove the running flag file
+    pub fn clear_running_flag(&self) -> Result<(), DbError> {
+        if let Some(ref dir_path) = self.dir_path {
+            let file_path = dir_path.join(RUNNING_FLAG_FILENAME);
+            if file_path.exists() {
+                fs::remove_file(file_path).map_err(DbError::FileSystemError)?;
+            }
+        }
+        Ok(())
+    }
+    /// Check if the running flag file exists (the previous run did not stop cleanly)
+    pub fn running_flag_exists(&self) -> bool {
+        if let Some(ref dir_path) = self.dir_path {
+            dir_path.join(RUNNING_FLAG_FILENAME).exists()
+        } else {
+            false
+        }
+    }
+    /// Check if the databases must be recovered: the previous run did not stop cleanly,
+    /// or a write was interrupted before its commit
+    pub fn needs_recovery(&self) -> bool {
+        self.running_flag_exists()
+            || match self.read() {
+                Ok(Some(entry)) => entry.state == WriteJournalState::Pending,
+                Ok(None) => false,
+                Err(_) => true,
+            }
+    }
+    /// Write the journal entry in a temporary file, then rename it so the journal is never partial
+    fn write_entry(&self, entry: WriteJournalEntry) -> Result<(), DbError> {
+        if let Some(ref dir_path) = self.dir_path {
+            let tmp_file_path = dir_path.join(format!("{}.tmp", WRITE_JOURNAL_FILENAME));
+            let mut tmp_file =
+                fs::File::create(&tmp_file_path).map_err(DbError::FileSystemError)?;
+            tmp_file
+                .write_all(&durs_dbs_tools::to_bytes(&entry)?)
+                .map_err(DbError::FileSystemError)?;
+            tmp_file.sync_all().map_err(DbError::FileSystemError)?;
+            fs::rename(tmp_file_path, dir_path.join(WRITE_JOURNAL_FILENAME))
+                .map_err(DbError::FileSystemError)?;
+        }
+        Ok(())
+    }
+}
+
+#[inline]
+fn undo_file_path(dir_path: &Path, file_store: &str) -> PathBuf {
+    dir_path.join(format!("{}.{}", file_store, UNDO_FILE_EXTENSION))
+}
+
+/// Copy a file through a temporary file, so the destination is never partial
+fn copy_synced(from: &Path, to: &Path) -> Result<(), DbError> {
+    let tmp_path = to.with_extension("tmp");
+    fs::copy(from, &tmp_path).map_err(DbError::FileSystemError)?;
+    fs::File::open(&tmp_path)
+        .and_then(|tmp_file| tmp_file.sync_all())
+        .map_err(DbError::FileSystemError)?;
+    fs::rename(tmp_path, to).map_err(DbError::FileSystemError)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use dubp_common_doc::BlockHash;
+    use dup_crypto::hashs::Hash;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_write_journal() -> Result<(), DbError> {
+        let tmp_dir = tempdir().map_err(DbError::FileSystemError)?;
+        let mut journal = WriteJournal::open(Some(&tmp_dir.path().to_owned()));
+        let blockstamp = Blockstamp {
+            id: BlockNumber(42),
+            hash: BlockHash(Hash([3; 32])),
+        };
+
+        assert_eq!(None, journal.read()?);
+        assert!(!journal.needs_recovery());
+        journal.begin(Blockstamp::default())?;
+        journal.applying(blockstamp)?;
+        assert!(journal.needs_recovery());
+        assert_eq!(
+            Some(WriteJournalEntry {
+                state: WriteJournalState::Pending,
+                blockstamp: Blockstamp::default(),
+                applying: Some(blockstamp),
+            }),
+            journal.read()?
+        );
+        journal.commit(blockstamp)?;
+        assert!(!journal.needs_recovery());
+        assert_eq!(
+            Some(WriteJournalEntry {
+                state: WriteJournalState::Committed,
+                blockstamp,
+                applying: None,
+            }),
+            journal.read()?
+        );
+
+        assert!(!journal.running_flag_exists());
+        journal.set_running_flag()?;
+        assert!(journal.running_flag_exists());
+        journal.clear_running_flag()?;
+        assert!(!journal.running_flag_exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_rollback_file_stores() -> Result<(), DbError> {
+        let tmp_dir = tempdir().map_err(DbError::FileSystemError)?;
+        let wot_db_path = tmp_dir.path().join(WOT_DB_FILENAME);
+        let mut journal = WriteJournal::open(Some(&tmp_dir.path().to_owned()));
+
+        assert!(!journal.rollback_file_stores()?);
+        fs::write(&wot_db_path, b"before").map_err(DbError::FileSystemError)?;
+        journal.begin(Blockstamp::default())?;
+        fs::write(&wot_db_path, b"after").map_err(DbError::FileSystemError)?;
+        assert!(journal.rollback_file_stores()?);
+        assert_eq!(
+            b"before".to_vec(),
+            fs::read(&wot_db_path).map_err(DbError::FileSystemError)?
+        );
+
+        Ok(())
+    }
+}
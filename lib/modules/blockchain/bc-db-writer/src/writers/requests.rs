@@ -68,6 +68,7 @@ impl BlocksDBsWriteQuery {
                 trace!("BlocksDBsWriteQuery::WriteBlock...");
                 block_db.block.reduce();
                 crate::current_metadata::update_current_metadata(db, w, &block_db.block)?;
+                let block_number = block_db.block.number();
                 if sync_target.is_none()
                     || block_db.blockstamp().id.0 + fork_window_size as u32
                         >= sync_target.expect("safe unwrap").id.0
@@ -76,6 +77,23 @@ impl BlocksDBsWriteQuery {
                 } else {
                     crate::blocks::insert_new_head_block(db, w, None, block_db)?;
                 }
+                if durs_bc_db_reader::current_metadata::is_light_storage(&BcDbRwWithWriter {
+                    db,
+                    w,
+                })? {
+                    // Transactions are kept as long as the block can be reverted
+                    let tx_window = std::cmp::max(
+                        *durs_bc_db_reader::constants::LIGHT_STORAGE_TX_WINDOW,
+                        fork_window_size as u32,
+                    );
+                    if block_number.0 >= tx_window {
+                        blocks::prune_block_transactions(
+                            db,
+                            w,
+                            BlockNumber(block_number.0 - tx_window),
+                        )?;
+                    }
+                }
             }
             BlocksDBsWriteQuery::RevertBlock(block_db) => {
                 trace!("BlocksDBsWriteQuery::WriteBlock...");
@@ -0,0 +1,361 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Sub-module auditing the consistency of the blockchain database.
+//!
+//! The balances are re-derived from the unconsumed transaction outputs and universal dividends,
+//! their sum is compared with the monetary mass of the block headers, and the wot id index is
+//! compared with the identities store.
+
+use crate::snapshot;
+use crate::*;
+use dubp_user_docs::documents::transaction::TransactionOutputV10;
+use durs_bc_db_reader::blocks::header::BlockHeaderDb;
+use durs_bc_db_reader::constants::*;
+use durs_bc_db_reader::current_metadata::current_ud::CurrentUdDb;
+use durs_bc_db_reader::{from_db_value, BcDbInReadTx, BcDbWithReader, DbReadable, DbValue};
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// Inconsistency between the indexes of the blockchain database
+pub enum Divergence {
+    /// A universal dividend of the public key refers to a block that did not create any dividend
+    UnknownDividend(PubKey, BlockNumber),
+    /// The sum of all sources differs from the monetary mass of the current block
+    MonetaryMass {
+        /// Sum of the unconsumed transaction outputs and universal dividends
+        sources: i128,
+        /// Monetary mass of the current block header
+        header: u64,
+    },
+    /// The monetary mass of a block header doesn't follow from the previous block header
+    HeaderMonetaryMass {
+        /// Block number
+        block_number: BlockNumber,
+        /// Monetary mass computed from the previous block header
+        expected: u64,
+        /// Monetary mass of the block header
+        header: u64,
+    },
+    /// The universal dividend created by a block differs from its header
+    UdHistory(BlockNumber),
+    /// The public key is indexed with a wot id whose identity is missing or has another public key
+    WotIndex(PubKey, WotId),
+    /// The identity of the public key is not in the wot id index
+    MissingInWotIndex(PubKey, WotId),
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Divergence::UnknownDividend(pubkey, block_number) => write!(
+                f,
+                "{} owns a dividend of block #{} which created no dividend",
+                pubkey, block_number
+            ),
+            Divergence::MonetaryMass { sources, header } => write!(
+                f,
+                "the sum of the sources is {} but the monetary mass of the current block is {}",
+                sources, header
+            ),
+            Divergence::HeaderMonetaryMass {
+                block_number,
+                expected,
+                header,
+            } => write!(
+                f,
+                "the monetary mass of block #{} is {} but should be {}",
+                block_number, header, expected
+            ),
+            Divergence::UdHistory(block_number) => write!(
+                f,
+                "the universal dividend of block #{} differs from the block header",
+                block_number
+            ),
+            Divergence::WotIndex(pubkey, wot_id) => write!(
+                f,
+                "{} is indexed with wot id {} which is not its identity",
+                pubkey, wot_id.0
+            ),
+            Divergence::MissingInWotIndex(pubkey, wot_id) => write!(
+                f,
+                "the identity {} of {} is not in the wot id index",
+                wot_id.0, pubkey
+            ),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// Result of the audit of the blockchain database
+pub struct AuditReport {
+    /// Current blockstamp
+    pub blockstamp: Blockstamp,
+    /// Number of unconsumed sources (transaction outputs and universal dividends)
+    pub sources_count: usize,
+    /// Number of block headers checked
+    pub headers_count: usize,
+    /// Number of identities checked
+    pub identities_count: usize,
+    /// Inconsistencies found
+    pub divergences: Vec<Divergence>,
+}
+
+impl fmt::Display for AuditReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Blockchain database audited at block {}: {} sources, {} block headers and {} identities checked, {} divergences found.",
+            self.blockstamp,
+            self.sources_count,
+            self.headers_count,
+            self.identities_count,
+            self.divergences.len()
+        )
+    }
+}
+
+/// Amount of a source in the smallest unit of the currency
+fn units(amount: isize, base: usize) -> i128 {
+    amount as i128 * 10i128.pow(base as u32)
+}
+
+/// Audit the consistency of the blockchain database
+///
+/// Returns `None` if the local blockchain is empty.
+pub fn audit_db(db: &Db) -> Result<Option<AuditReport>, DbError> {
+    db.r(|db_r| {
+        let blockstamp = if let Some(blockstamp) =
+            durs_bc_db_reader::current_metadata::get_current_blockstamp(db_r)?
+        {
+            blockstamp
+        } else {
+            return Ok(None);
+        };
+        let mut divergences = Vec::new();
+
+        let uds_history = durs_bc_db_reader::current_metadata::get_uds_history(db_r)?;
+        let (sources_count, sources) = audit_sources(db_r, &uds_history, &mut divergences)?;
+        let headers_count = audit_headers(db_r, blockstamp.id, &uds_history, &mut divergences)?;
+        if let Some(BlockHeaderDb::V10(current_header)) =
+            durs_bc_db_reader::blocks::header::get_block_header_in_local_blockchain(
+                db_r,
+                blockstamp.id,
+            )?
+        {
+            if sources != i128::from(current_header.monetary_mass) {
+                divergences.push(Divergence::MonetaryMass {
+                    sources,
+                    header: current_header.monetary_mass,
+                });
+            }
+        }
+        let identities_count = audit_wot_index(db_r, &mut divergences)?;
+
+        Ok(Some(AuditReport {
+            blockstamp,
+            sources_count,
+            headers_count,
+            identities_count,
+            divergences,
+        }))
+    })
+}
+
+/// Re-derive the balances of all public keys and return the number and the sum of the sources
+fn audit_sources<DB: BcDbWithReader>(
+    db: &DB,
+    uds_history: &[CurrentUdDb],
+    divergences: &mut Vec<Divergence>,
+) -> Result<(usize, i128), DbError> {
+    let uds_amounts: HashMap<BlockNumber, i128> = uds_history
+        .iter()
+        .map(|ud| (ud.block_number, units(ud.amount as isize, ud.base)))
+        .collect();
+    let mut sources_count = 0;
+    let mut sum = 0;
+
+    // Universal dividends
+    let dividends_store = db.db().get_multi_store(DIVIDENDS);
+    for pubkey_bytes in snapshot::members_pubkeys(db)? {
+        let pubkey = PubKey::from_bytes(&pubkey_bytes).map_err(|_| DbError::DBCorrupted)?;
+        for entry in dividends_store.get(db.r(), &pubkey_bytes)? {
+            if let Some(value) = entry?.1 {
+                let block_number = if let DbValue::U64(block_number) = value {
+                    BlockNumber(block_number as u32)
+                } else {
+                    return Err(DbError::DBCorrupted);
+                };
+                sources_count += 1;
+                if let Some(amount) = uds_amounts.get(&block_number) {
+                    sum += amount;
+                } else {
+                    divergences.push(Divergence::UnknownDividend(pubkey, block_number));
+                }
+            }
+        }
+    }
+
+    // Transaction outputs
+    for entry in db.db().get_store(UTXOS).iter_start(db.r())? {
+        if let (_, Some(value)) = entry? {
+            let output: TransactionOutputV10 = from_db_value(value)?;
+            sources_count += 1;
+            sum += units((output.amount).0, (output.base).0);
+        }
+    }
+
+    Ok((sources_count, sum))
+}
+
+/// Check the monetary mass of each block header against the previous one and the universal
+/// dividends history, and return the number of headers checked
+///
+/// Blocks missing from the database (e.g. before the block of an imported snapshot) are skipped.
+fn audit_headers<DB: BcDbInReadTx>(
+    db: &DB,
+    current_block_number: BlockNumber,
+    uds_history: &[CurrentUdDb],
+    divergences: &mut Vec<Divergence>,
+) -> Result<usize, DbError> {
+    let uds_by_block: HashMap<BlockNumber, &CurrentUdDb> =
+        uds_history.iter().map(|ud| (ud.block_number, ud)).collect();
+    let mut headers_count = 0;
+    let mut previous_monetary_mass = None;
+
+    for block_number in 0..=current_block_number.0 {
+        let block_number = BlockNumber(block_number);
+        let header = if let Some(BlockHeaderDb::V10(header)) =
+            durs_bc_db_reader::blocks::header::get_block_header_in_local_blockchain(
+                db,
+                block_number,
+            )? {
+            header
+        } else {
+            previous_monetary_mass = None;
+            continue;
+        };
+        headers_count += 1;
+
+        let created_ud = header.dividend.map(|dividend| {
+            (
+                dividend.0,
+                header.unit_base.0,
+                dividend.0 as u64
+                    * 10u64.pow(header.unit_base.0 as u32)
+                    * header.members_count.0 as u64,
+            )
+        });
+        let ud_history_matches = match (created_ud, uds_by_block.get(&block_number)) {
+            (Some((amount, base, _)), Some(ud)) => ud.amount == amount && ud.base == base,
+            (None, None) => true,
+            _ => false,
+        };
+        if !ud_history_matches {
+            divergences.push(Divergence::UdHistory(block_number));
+        }
+
+        if let Some(previous_monetary_mass) = previous_monetary_mass {
+            let expected = previous_monetary_mass + created_ud.map_or(0, |(_, _, mass)| mass);
+            if expected != header.monetary_mass {
+                divergences.push(Divergence::HeaderMonetaryMass {
+                    block_number,
+                    expected,
+                    header: header.monetary_mass,
+                });
+            }
+        }
+        previous_monetary_mass = Some(header.monetary_mass);
+    }
+
+    Ok(headers_count)
+}
+
+/// Check that the wot id index and the identities store agree, and return the number of identities
+fn audit_wot_index<DB: BcDbInReadTx>(
+    db: &DB,
+    divergences: &mut Vec<Divergence>,
+) -> Result<usize, DbError> {
+    let wot_index = durs_bc_db_reader::indexes::identities::get_wot_index(db)?;
+    let mut identities_count = 0;
+
+    let mut wot_index_entries: Vec<(&PubKey, &WotId)> = wot_index.iter().collect();
+    wot_index_entries.sort_by_key(|(_, wot_id)| wot_id.0);
+    for (pubkey, wot_id) in wot_index_entries {
+        let idty_pubkey =
+            durs_bc_db_reader::indexes::identities::get_identity_by_wot_id(db, *wot_id)?
+                .map(|idty| idty.idty_doc.issuers()[0]);
+        if idty_pubkey != Some(*pubkey) {
+            divergences.push(Divergence::WotIndex(*pubkey, *wot_id));
+        }
+    }
+
+    let greatest_wot_id = durs_bc_db_reader::current_metadata::get_greatest_wot_id_(db)?;
+    for wot_id in 0..=greatest_wot_id.0 {
+        let wot_id = WotId(wot_id);
+        if let Some(idty) =
+            durs_bc_db_reader::indexes::identities::get_identity_by_wot_id(db, wot_id)?
+        {
+            identities_count += 1;
+            let pubkey = idty.idty_doc.issuers()[0];
+            if wot_index.get(&pubkey) != Some(&wot_id) {
+                divergences.push(Divergence::MissingInWotIndex(pubkey, wot_id));
+            }
+        }
+    }
+
+    Ok(identities_count)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use dup_crypto_tests_tools::mocks::pubkey;
+
+    #[test]
+    fn audit_empty_blockchain() -> Result<(), DbError> {
+        let db = crate::tests::open_tmp_db()?;
+
+        assert_eq!(None, audit_db(&db)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn audit_wot_index_without_identity() -> Result<(), DbError> {
+        let db = crate::tests::open_tmp_db()?;
+        db.write(|mut w| {
+            db.get_store(WOT_ID_INDEX).put(
+                w.as_mut(),
+                &pubkey('A').to_bytes_vector(),
+                &DbValue::U64(0),
+            )?;
+            Ok(WriteResp::from(w))
+        })?;
+
+        let mut divergences = Vec::new();
+        let identities_count = db.r(|db_r| audit_wot_index(db_r, &mut divergences))?;
+
+        assert_eq!(0, identities_count);
+        assert_eq!(
+            vec![Divergence::WotIndex(pubkey('A'), WotId(0))],
+            divergences
+        );
+
+        Ok(())
+    }
+}
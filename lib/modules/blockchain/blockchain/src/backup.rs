@@ -0,0 +1,222 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Sub-module backing up and restoring the blockchain database.
+//! The backup is a consistent copy of the database file, made while the node is running.
+//! The wot database is not backed up, it is rebuilt from the blockchain database at restore.
+
+use crate::*;
+use durs_bc_db_reader::constants::CURRENT_DB_VERSION;
+use failure::Fail;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Fail)]
+/// Backup error
+pub enum BackupError {
+    /// Database error
+    #[fail(display = "{}", _0)]
+    DbError(DbError),
+    /// The local blockchain (or the backup) is empty
+    #[fail(display = "the blockchain is empty")]
+    EmptyBlockchain,
+    /// The backup does not contain all the stores of the database schema
+    #[fail(display = "the stores of the backup do not match the database schema")]
+    IncompatibleSchema,
+    /// The backup was made with another version of the database schema
+    #[fail(
+        display = "the backup database version is {:?}, expected {}",
+        found, expected
+    )]
+    IncompatibleVersion {
+        /// Version of the database schema of the software
+        expected: usize,
+        /// Version of the database schema of the backup
+        found: Option<usize>,
+    },
+    /// Fail to copy the database file
+    #[fail(display = "I/O error: {}", _0)]
+    IoError(std::io::Error),
+    /// The backup is a blockchain of another currency
+    #[fail(
+        display = "the backup is a blockchain of currency {}, the local blockchain is a blockchain of currency {}",
+        backup, local
+    )]
+    OtherCurrency {
+        /// Currency of the backup
+        backup: CurrencyName,
+        /// Currency of the local blockchain
+        local: CurrencyName,
+    },
+    /// Recovery error
+    #[fail(display = "{}", _0)]
+    RecoveryError(recovery::RecoveryError),
+}
+
+impl From<DbError> for BackupError {
+    fn from(e: DbError) -> Self {
+        BackupError::DbError(e)
+    }
+}
+
+impl From<std::io::Error> for BackupError {
+    fn from(e: std::io::Error) -> Self {
+        BackupError::IoError(e)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// Summary of a backup
+pub struct BackupSummary {
+    /// Currency of the backed up blockchain
+    pub currency: CurrencyName,
+    /// Current blockstamp of the backed up blockchain
+    pub current_blockstamp: Blockstamp,
+    /// Size of the database file (in bytes)
+    pub size: u64,
+}
+
+impl fmt::Display for BackupSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Backup of the {} blockchain at block {} ({} MiB).",
+            self.currency,
+            self.current_blockstamp,
+            self.size / (1024 * 1024)
+        )
+    }
+}
+
+/// Copy the blockchain database into the `backup_path` folder.
+/// The database can be used by the node during the backup, the writes are only delayed.
+pub fn backup_db(db: &Db, backup_path: &Path) -> Result<BackupSummary, BackupError> {
+    if db
+        .r(|db_r| durs_bc_db_reader::current_metadata::get_current_blockstamp(db_r))?
+        .is_none()
+    {
+        return Err(BackupError::EmptyBlockchain);
+    }
+    durs_bc_db_writer::current_metadata::init_db_version(db)?;
+
+    db.backup_to(backup_path)?;
+
+    read_backup(backup_path)
+}
+
+/// Replace the blockchain database by the backup stored in the `backup_path` folder,
+/// then rebuild the wot database.
+///
+/// The database must not be used by any other handler, including handlers of the current process.
+pub fn restore_db(dbs_path: &Path, backup_path: &Path) -> Result<BackupSummary, BackupError> {
+    let summary = read_backup(backup_path)?;
+
+    let db_file = dbs_path.join(KV_FILE_DB_DATA_FILENAME);
+    if db_file.is_file() {
+        let db = Db::open_db_detached(dbs_path, &durs_bc_db_reader::bc_db_schema())?;
+        if let Some(local) =
+            db.r(|db_r| durs_bc_db_reader::current_metadata::get_currency_name(db_r))?
+        {
+            if local != summary.currency {
+                return Err(BackupError::OtherCurrency {
+                    backup: summary.currency,
+                    local,
+                });
+            }
+        }
+    } else {
+        fs::create_dir_all(dbs_path)?;
+    }
+
+    // Replace the database file (the environment is closed)
+    let tmp_db_file = dbs_path.join(format!("{}.restore", KV_FILE_DB_DATA_FILENAME));
+    fs::copy(backup_path.join(KV_FILE_DB_DATA_FILENAME), &tmp_db_file)?;
+    fs::File::open(&tmp_db_file)?.sync_all()?;
+    fs::rename(&tmp_db_file, &db_file)?;
+
+    // The wot database of the replaced blockchain is discarded and rebuilt by the recovery
+    let wot_db_path = dbs_path.join(WOT_DB_FILENAME);
+    if wot_db_path.exists() {
+        fs::remove_file(wot_db_path)?;
+    }
+    let db = Db::open_db_detached(dbs_path, &durs_bc_db_reader::bc_db_schema())?;
+    let recovery_summary = recovery::recover(&db, dbs_path).map_err(BackupError::RecoveryError)?;
+    info!("{}", recovery_summary);
+
+    Ok(summary)
+}
+
+/// Verify that the backup can be restored by the current software and read its summary
+fn read_backup(backup_path: &Path) -> Result<BackupSummary, BackupError> {
+    let backup_db = match durs_bc_db_reader::open_db_ro(backup_path) {
+        Ok(backup_db) => backup_db,
+        // A store of the schema is missing
+        Err(DbError::StoreError(_)) => return Err(BackupError::IncompatibleSchema),
+        Err(e) => return Err(e.into()),
+    };
+
+    let db_version = durs_bc_db_reader::current_metadata::get_db_version(&backup_db)?;
+    if db_version != Some(*CURRENT_DB_VERSION) {
+        return Err(BackupError::IncompatibleVersion {
+            expected: *CURRENT_DB_VERSION,
+            found: db_version,
+        });
+    }
+
+    let (currency, current_blockstamp) = backup_db.r(|db_r| {
+        Ok((
+            durs_bc_db_reader::current_metadata::get_currency_name(db_r)?,
+            durs_bc_db_reader::current_metadata::get_current_blockstamp(db_r)?,
+        ))
+    })?;
+    match (currency, current_blockstamp) {
+        (Some(currency), Some(current_blockstamp)) => Ok(BackupSummary {
+            currency,
+            current_blockstamp,
+            size: fs::metadata(backup_path.join(KV_FILE_DB_DATA_FILENAME))?.len(),
+        }),
+        _ => Err(BackupError::EmptyBlockchain),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn backup_empty_blockchain() -> Result<(), DbError> {
+        let db = crate::tests::open_tmp_db()?;
+        let backup_dir = tempdir().map_err(DbError::FileSystemError)?;
+
+        match backup_db(&db, backup_dir.path()) {
+            Err(BackupError::EmptyBlockchain) => Ok(()),
+            r => panic!("unexpected backup result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn restore_missing_backup() -> Result<(), DbError> {
+        let dbs_dir = tempdir().map_err(DbError::FileSystemError)?;
+        let backup_dir = tempdir().map_err(DbError::FileSystemError)?;
+
+        match restore_db(dbs_dir.path(), backup_dir.path()) {
+            Err(BackupError::DbError(DbError::DBNotExist)) => Ok(()),
+            r => panic!("unexpected restore result: {:?}", r),
+        }
+    }
+}
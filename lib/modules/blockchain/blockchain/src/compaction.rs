@@ -0,0 +1,221 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Sub-module compacting the blockchain database.
+//! The database file never shrinks: the pages freed by the pruning are only reused by later writes.
+//! The compaction copies all entries into a new database, then replaces the database file with it.
+
+use crate::snapshot::{self, StoreSnapshot};
+use crate::*;
+use dubp_common_doc::BlockNumber;
+use durs_bc_db_reader::constants::*;
+use durs_bc_db_reader::current_metadata::CurrentMetaDataKey;
+use failure::Fail;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// All current metadata
+static ALL_METADATA: &[CurrentMetaDataKey] = &[
+    CurrentMetaDataKey::DbVersion,
+    CurrentMetaDataKey::CurrencyName,
+    CurrentMetaDataKey::CurrentBlockstamp,
+    CurrentMetaDataKey::CurrentBlockchainTime,
+    CurrentMetaDataKey::ForkTree,
+    CurrentMetaDataKey::NextWotId,
+    CurrentMetaDataKey::CurrentUd,
+    CurrentMetaDataKey::LightStorage,
+];
+
+#[derive(Debug, Fail)]
+/// Compaction error
+pub enum CompactionError {
+    /// Database error
+    #[fail(display = "{}", _0)]
+    DbError(DbError),
+    /// The local blockchain is empty
+    #[fail(display = "the local blockchain is empty")]
+    EmptyBlockchain,
+    /// Fail to write or replace the database files
+    #[fail(display = "I/O error: {}", _0)]
+    IoError(std::io::Error),
+    /// Recovery error
+    #[fail(display = "{}", _0)]
+    RecoveryError(recovery::RecoveryError),
+}
+
+impl From<DbError> for CompactionError {
+    fn from(e: DbError) -> Self {
+        CompactionError::DbError(e)
+    }
+}
+
+impl From<std::io::Error> for CompactionError {
+    fn from(e: std::io::Error) -> Self {
+        CompactionError::IoError(e)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Summary of the compaction
+pub struct CompactionSummary {
+    /// Statistics of the database file before the compaction
+    pub before: KvFileDbStats,
+    /// Statistics of the database file after the compaction
+    pub after: KvFileDbStats,
+}
+
+impl CompactionSummary {
+    /// Number of free pages removed from the database file
+    pub fn reclaimed_pages(&self) -> usize {
+        self.before
+            .pages_count
+            .saturating_sub(self.after.pages_count)
+    }
+}
+
+impl fmt::Display for CompactionSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Blockchain database compacted from {} to {} pages of {} bytes ({} free pages reclaimed, {} MiB).",
+            self.before.pages_count,
+            self.after.pages_count,
+            self.after.page_size,
+            self.reclaimed_pages(),
+            self.reclaimed_pages() * self.after.page_size / (1024 * 1024)
+        )
+    }
+}
+
+/// Compact the blockchain database: copy all its entries into a new database,
+/// then atomically replace the database file.
+///
+/// The database must not be used by any other handler, including handlers of the current process.
+pub fn compact_db(dbs_path: &Path) -> Result<CompactionSummary, CompactionError> {
+    let schema = durs_bc_db_reader::bc_db_schema();
+    let compaction_path = dbs_path.join(COMPACTION_DIRNAME);
+    let (before, after) = {
+        let db = Db::open_db_detached(dbs_path, &schema)?;
+
+        // Recover databases if the previous run did not stop cleanly or a write was interrupted
+        if write_journal::WriteJournal::open(Some(&dbs_path.to_path_buf())).needs_recovery() {
+            let summary =
+                recovery::recover(&db, dbs_path).map_err(CompactionError::RecoveryError)?;
+            info!("{}", summary);
+        }
+
+        let current_blockstamp = db
+            .r(|db_r| durs_bc_db_reader::current_metadata::get_current_blockstamp(db_r))?
+            .ok_or(CompactionError::EmptyBlockchain)?;
+
+        // Remove the remains of an interrupted compaction
+        if compaction_path.exists() {
+            fs::remove_dir_all(&compaction_path)?;
+        }
+        fs::create_dir(&compaction_path)?;
+
+        let compacted_db = Db::open_db_detached(&compaction_path, &schema)?;
+        copy_stores(&db, &compacted_db, current_blockstamp.id)?;
+        compacted_db.save()?;
+        (db.stats()?, compacted_db.stats()?)
+    };
+
+    // Replace the database file (the environments are closed)
+    fs::rename(
+        compaction_path.join(KV_FILE_DB_DATA_FILENAME),
+        dbs_path.join(KV_FILE_DB_DATA_FILENAME),
+    )?;
+    fs::remove_dir_all(&compaction_path)?;
+
+    Ok(CompactionSummary { before, after })
+}
+
+/// Copy all entries, by chunks to bound the memory usage
+fn copy_stores(db: &Db, compacted_db: &Db, current_number: BlockNumber) -> Result<(), DbError> {
+    let chunk_size = *COMPACTION_CHUNK_SIZE;
+
+    // Stores indexed by block number
+    let mut chunk_begin = 0;
+    while chunk_begin <= current_number.0 {
+        let chunk = chunk_begin..=std::cmp::min(chunk_begin + chunk_size - 1, current_number.0);
+        copy_chunk(db, compacted_db, |db_r| {
+            Ok(vec![
+                snapshot::export_int_store(db_r, MAIN_BLOCKS, chunk.clone())?,
+                snapshot::export_int_store(db_r, CONSUMED_UTXOS, chunk.clone())?,
+                snapshot::export_multi_int_store(db_r, MBS_BY_CREATED_BLOCK, chunk.clone())?,
+                snapshot::export_multi_int_store(db_r, CERTS_BY_CREATED_BLOCK, chunk.clone())?,
+            ])
+        })?;
+        chunk_begin += chunk_size;
+    }
+
+    // Stores indexed by identity
+    let members_pubkeys = db.r(|db_r| snapshot::members_pubkeys(db_r))?;
+    for (chunk_index, pubkeys) in members_pubkeys.chunks(chunk_size as usize).enumerate() {
+        let first_wot_id = chunk_index as u32 * chunk_size;
+        let wot_ids = first_wot_id..first_wot_id + pubkeys.len() as u32;
+        copy_chunk(db, compacted_db, |db_r| {
+            Ok(vec![
+                snapshot::export_int_store(db_r, IDENTITIES, wot_ids.clone())?,
+                snapshot::export_multi_store(db_r, DIVIDENDS, pubkeys)?,
+            ])
+        })?;
+    }
+
+    // Other stores
+    copy_chunk(db, compacted_db, |db_r| {
+        Ok(vec![
+            snapshot::export_int_store(
+                db_r,
+                CURRENT_METADATA,
+                ALL_METADATA.iter().map(|key| key.to_u32()),
+            )?,
+            snapshot::export_store(db_r, WOT_ID_INDEX)?,
+            snapshot::export_store(db_r, UDS_HISTORY)?,
+            snapshot::export_store(db_r, UTXOS)?,
+            snapshot::export_store(db_r, FORK_BLOCKS)?,
+            snapshot::export_store(db_r, ORPHAN_BLOCKSTAMP)?,
+        ])
+    })
+}
+
+fn copy_chunk<F>(db: &Db, compacted_db: &Db, export: F) -> Result<(), DbError>
+where
+    F: Fn(&BcDbRwWithReader) -> Result<Vec<StoreSnapshot>, DbError>,
+{
+    let stores = db.r(export)?;
+    compacted_db.write(|mut w| {
+        snapshot::import_stores(compacted_db, &mut w, &stores)?;
+        Ok(WriteResp::from(w))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn compact_empty_blockchain() -> Result<(), DbError> {
+        let tmp_dir = tempdir().map_err(DbError::FileSystemError)?;
+
+        match compact_db(tmp_dir.path()) {
+            Err(CompactionError::EmptyBlockchain) => Ok(()),
+            r => panic!("unexpected compaction result: {:?}", r),
+        }
+    }
+}
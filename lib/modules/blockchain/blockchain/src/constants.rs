@@ -36,3 +36,42 @@ pub static REQUEST_FORK_BLOCKS_FREQUENCY_IN_SEC: &u64 = &180;
 
 /// Blocks Delay threshold
 pub static BLOCKS_DELAY_THRESHOLD: &u32 = &5;
+
+/// Maximum waiting time for blocks from the sync network module (in seconds)
+pub static NETWORK_SYNC_TIMEOUT_IN_SEC: &u64 = &300;
+
+/// Maximum number of times the missing blocks are requested to the network during a sync
+pub static NETWORK_SYNC_MAX_RETRIES: &usize = &3;
+
+/// Minimal interval between two logs of the same repetitive warning (in seconds)
+pub static REPEATED_LOG_INTERVAL_IN_SECS: &u64 = &60;
+
+/// Number of blocks over which the block apply timings are aggregated
+pub static APPLY_TIMINGS_WINDOW_IN_BLOCKS: &u32 = &1_000;
+
+/// Name of the file where the block apply timings are exported (in the datas folder)
+pub static APPLY_TIMINGS_FILENAME: &str = "apply_timings.json";
+
+/// Number of last applied blocks kept in the block apply metrics
+pub static APPLY_METRICS_WINDOW_IN_BLOCKS: &usize = &500;
+
+/// Upper bounds of the buckets of the block apply durations histogram (in milliseconds)
+pub static APPLY_METRICS_HISTOGRAM_BOUNDS_IN_MILLIS: &[u64] = &[10, 50, 100, 500, 1_000, 5_000];
+
+/// Apply duration above which a block is logged as slow (in milliseconds)
+pub static SLOW_BLOCK_APPLY_THRESHOLD_IN_MILLIS: &u64 = &1_000;
+
+/// Maximum waiting time for the mempool documents before assembling the next block (in seconds)
+pub static MEMPOOL_DOCUMENTS_TIMEOUT_IN_SECS: &u64 = &5;
+
+/// Maximum number of proof of work workers
+pub static MAX_PROVER_WORKERS: &usize = &4;
+
+/// Number of keys copied per write transaction when compacting the database
+pub static COMPACTION_CHUNK_SIZE: &u32 = &1_000;
+
+/// Name of the folder where the compacted database is written (in the blockchain database folder)
+pub static COMPACTION_DIRNAME: &str = "compaction";
+
+/// Maximum number of blocks written in the same write transaction when synchronizing
+pub static SYNC_BLOCKS_WRITE_BATCH_MAX: &usize = &250;
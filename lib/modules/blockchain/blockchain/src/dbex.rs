@@ -18,12 +18,16 @@
 use crate::*;
 use dubp_block_doc::block::BlockDocumentTrait;
 use dubp_common_doc::BlockNumber;
+use dubp_user_docs::amount::{format_amount, AmountFormat, AmountUnit, Separators};
+use dubp_user_docs::documents::transaction::{TxAmount, TxBase};
 use dup_crypto::keys::*;
 use durs_bc_db_reader::constants::*;
+use durs_bc_db_reader::tools::get_sentry_requirement;
 use durs_bc_db_reader::{BcDbRead, BcDbRo, DbValue};
 use durs_wot::data::rusty::RustyWebOfTrust;
 use durs_wot::data::WebOfTrust;
 use durs_wot::operations::distance::{DistanceCalculator, WotDistance, WotDistanceParameters};
+use durs_wot::operations::distance_vectors::DistanceVector;
 use std::str::FromStr;
 use std::time::*;
 use unwrap::unwrap;
@@ -34,19 +38,40 @@ pub static EMPTY_BLOCKCHAIN: &str = "No blockchain, please sync your node to get
 static PUB_KEY: &str = "PUBKEY";
 static BLOCK: &str = "BLOCK";
 static USERNAME: &str = "USERNAME";
+static EXPIRATION_TYPE: &str = "TYPE";
+static EXPIRE_TIME: &str = "EXPIRE_TIME";
 
 #[derive(Debug, Copy, Clone)]
 /// Query for blockchain databases explorer
 pub enum DbExBcQuery {
     /// Count blocks per issuer
     CountBlocksPerIssuer,
+    /// Show current universal dividend and monetary mass
+    CurrentUd {
+        /// Show monetary mass relatively to the universal dividend
+        relative: bool,
+        /// Separators used to display amounts
+        separators: Separators,
+    },
 }
 
 #[derive(Debug, Clone)]
 /// Query for tx databases explorer
 pub enum DbExTxQuery {
     /// Ask balance of an address (pubkey or uid)
-    Balance(String),
+    Balance {
+        /// Address
+        address: UidOrPubkey,
+        /// Separators used to display amounts
+        separators: Separators,
+    },
+    /// Show the universal dividends created for a member
+    UdHistory {
+        /// Member
+        member: UidOrPubkey,
+        /// Separators used to display amounts
+        separators: Separators,
+    },
 }
 
 #[derive(Debug, Clone)]
@@ -54,12 +79,16 @@ pub enum DbExTxQuery {
 pub enum DbExWotQuery {
     /// Ask distance of all members
     AllDistances(bool),
+    /// Export the distance test vector of the current block in a file
+    ExportDistanceVector(PathBuf),
     /// Show members expire date
     ExpireMembers(bool),
     /// Show members list
     ListMembers(bool),
     /// Ask member datas
     MemberDatas(UidOrPubkey),
+    /// Show the expiry dates of the membership and the certifications of a member
+    MemberExpirations(UidOrPubkey),
 }
 
 /// Username or public key
@@ -115,10 +144,21 @@ fn open_bc_db_ro(profile_path: PathBuf) -> Option<BcDbRo> {
 pub fn dbex(profile_path: PathBuf, csv: bool, query: &DbExQuery) {
     match *query {
         DbExQuery::ForkTreeQuery => dbex_fork_tree(profile_path, csv),
+        DbExQuery::BcQuery(DbExBcQuery::CurrentUd {
+            relative,
+            separators,
+        }) => dbex_current_ud(profile_path, relative, separators),
         DbExQuery::BcQuery(bc_query) => {
             dbex_bc(profile_path, csv, bc_query).expect("Error: fail to open DB.")
         }
-        DbExQuery::TxQuery(ref tx_query) => dbex_tx(profile_path, csv, tx_query),
+        DbExQuery::TxQuery(DbExTxQuery::UdHistory {
+            ref member,
+            separators,
+        }) => dbex_ud_history(profile_path, member, separators),
+        DbExQuery::TxQuery(DbExTxQuery::Balance {
+            ref address,
+            separators,
+        }) => dbex_balance(profile_path, address, separators),
         DbExQuery::WotQuery(ref wot_query) => dbex_wot(profile_path, csv, wot_query),
     }
 }
@@ -192,6 +232,148 @@ pub fn dbex_bc(profile_path: PathBuf, _csv: bool, _query: DbExBcQuery) -> Result
     Ok(())
 }
 
+/// Print current universal dividend
+pub fn dbex_current_ud(profile_path: PathBuf, relative: bool, separators: Separators) {
+    let currency_name = match dubp_currency_params::db::get_currency_name(
+        durs_conf::get_datas_path(profile_path.clone()),
+    ) {
+        Ok(Some(currency_name)) => currency_name,
+        Ok(None) => {
+            println!("{}", EMPTY_BLOCKCHAIN);
+            return;
+        }
+        Err(e) => {
+            println!("Fail to read currency params DB: {}", e);
+            return;
+        }
+    };
+    let db = if let Some(db) = open_bc_db_ro(profile_path) {
+        db
+    } else {
+        return;
+    };
+    let current_ud = if let Some(current_ud) = db
+        .r(|db_r| durs_bc_db_reader::current_metadata::get_current_ud(db_r))
+        .expect("fail to get current UD")
+    {
+        current_ud
+    } else {
+        println!("No universal dividend yet.");
+        return;
+    };
+
+    let ud_amount = TxAmount(current_ud.amount as isize);
+    let ud_base = TxBase(current_ud.base);
+    let currency_format = AmountFormat {
+        unit: AmountUnit::Currency(dubp_user_docs::amount::currency_symbol(&currency_name.0)),
+        separators,
+    };
+    let mass_format = if relative {
+        AmountFormat {
+            unit: AmountUnit::Ud {
+                amount: ud_amount,
+                base: ud_base,
+            },
+            separators,
+        }
+    } else {
+        currency_format.clone()
+    };
+    println!(
+        "Universal dividend (block #{}): {}",
+        current_ud.block_number,
+        format_amount(ud_amount, ud_base, &currency_format)
+    );
+    println!(
+        "Monetary mass: {} ({} members)",
+        format_amount(
+            TxAmount(current_ud.monetary_mass as isize),
+            TxBase(0),
+            &mass_format
+        ),
+        current_ud.members_count
+    );
+}
+
+/// Print the universal dividends created for a member since its join
+pub fn dbex_ud_history(profile_path: PathBuf, member: &UidOrPubkey, separators: Separators) {
+    let currency_name = match dubp_currency_params::db::get_currency_name(
+        durs_conf::get_datas_path(profile_path.clone()),
+    ) {
+        Ok(Some(currency_name)) => currency_name,
+        Ok(None) => {
+            println!("{}", EMPTY_BLOCKCHAIN);
+            return;
+        }
+        Err(e) => {
+            println!("Fail to read currency params DB: {}", e);
+            return;
+        }
+    };
+    let db = if let Some(db) = open_bc_db_ro(profile_path) {
+        db
+    } else {
+        return;
+    };
+    let idty_opt = db
+        .r(|db_r| match member {
+            UidOrPubkey::Uid(ref uid) => {
+                if let Some(wot_id) =
+                    durs_bc_db_reader::indexes::identities::get_wot_id_from_uid(db_r, uid)?
+                {
+                    durs_bc_db_reader::indexes::identities::get_identity_by_wot_id(db_r, wot_id)
+                } else {
+                    Ok(None)
+                }
+            }
+            UidOrPubkey::Pubkey(ref pubkey) => {
+                durs_bc_db_reader::indexes::identities::get_identity_by_pubkey(db_r, pubkey)
+            }
+        })
+        .expect("fail to get identity");
+    let idty = if let Some(idty) = idty_opt {
+        idty
+    } else {
+        println!("Unknown member.");
+        return;
+    };
+    let pubkey = idty.idty_doc.issuers()[0];
+    let uds = db
+        .r(|db_r| {
+            durs_bc_db_reader::indexes::sources::get_ud_history(
+                db_r,
+                &pubkey,
+                idty.joined_on.id,
+                idty.expired_on.map(|blockstamp| blockstamp.id),
+            )
+        })
+        .expect("fail to get universal dividends history");
+
+    let currency_format = AmountFormat {
+        unit: AmountUnit::Currency(dubp_user_docs::amount::currency_symbol(&currency_name.0)),
+        separators,
+    };
+    println!(
+        "{} universal dividends created for {} ({}):",
+        uds.len(),
+        idty.idty_doc.username(),
+        pubkey
+    );
+    for (ud, available) in uds {
+        println!(
+            "#{} (time {}): {}{}",
+            ud.block_number,
+            ud.common_time,
+            format_amount(
+                TxAmount(ud.amount as isize),
+                TxBase(ud.base),
+                &currency_format
+            ),
+            if available { "" } else { " (consumed)" }
+        );
+    }
+}
+
 /// Print fork tree
 pub fn dbex_fork_tree(profile_path: PathBuf, _csv: bool) {
     // Open DB
@@ -226,65 +408,66 @@ pub fn dbex_fork_tree(profile_path: PathBuf, _csv: bool) {
     }
 }
 
-/// Execute DbExTxQuery
-pub fn dbex_tx(profile_path: PathBuf, _csv: bool, _query: &DbExTxQuery) {
-    // Get db path
-    let _db_path = durs_conf::get_blockchain_db_path(profile_path);
-
-    unimplemented!();
-
-    /*// Open DB
-    let load_db_begin = SystemTime::now();
+/// Print the balance of an address (pubkey or uid)
+pub fn dbex_balance(profile_path: PathBuf, address: &UidOrPubkey, separators: Separators) {
+    let currency_name = match dubp_currency_params::db::get_currency_name(
+        durs_conf::get_datas_path(profile_path.clone()),
+    ) {
+        Ok(Some(currency_name)) => currency_name,
+        Ok(None) => {
+            println!("{}", EMPTY_BLOCKCHAIN);
+            return;
+        }
+        Err(e) => {
+            println!("Fail to read currency params DB: {}", e);
+            return;
+        }
+    };
     let db = if let Some(db) = open_bc_db_ro(profile_path) {
         db
     } else {
         return;
     };
-    let load_dbs_duration = SystemTime::now()
-        .duration_since(load_db_begin)
-        .expect("duration_since error !");
-    println!(
-        "Databases loaded in {}.{:03} seconds.",
-        load_dbs_duration.as_secs(),
-        load_dbs_duration.subsec_millis()
-    );
-    let req_process_begin = SystemTime::now();
-    match *query {
-        DbExTxQuery::Balance(ref address_str) => {
-            let pubkey = if let Ok(ed25519_pubkey) = ed25519::PublicKey::from_base58(address_str) {
-                PubKey::Ed25519(ed25519_pubkey)
-            } else if let Some(pubkey) =
-                durs_bc_db_reader::indexes::identities::get_wot_id_from_uid(&db, address_str)
-                    .expect("get_uid : DbError")
-            {
-                pubkey
-            } else {
-                println!("This address doesn't exist!");
-                return;
-            };
-            let address = UTXOConditionsGroup::Single(TransactionOutputCondition::Sig(pubkey));
-            let address_balance = durs_bc_db_reader::indexes::balance::get_address_balance(
-                &currency_databases.balances_db,
-                &address,
-            )
-            .expect("get_address_balance : DbError")
-            .expect("Address not found in balances DB.");
-            println!(
-                "Balance={},{} Ğ1",
-                (address_balance.0).0 / 100,
-                (address_balance.0).0 % 100
-            );
-        }
-    }
+    let pubkey_opt = db
+        .r(|db_r| match address {
+            UidOrPubkey::Uid(ref uid) => {
+                if let Some(wot_id) =
+                    durs_bc_db_reader::indexes::identities::get_wot_id_from_uid(db_r, uid)?
+                {
+                    Ok(
+                        durs_bc_db_reader::indexes::identities::get_identity_by_wot_id(
+                            db_r, wot_id,
+                        )?
+                        .map(|idty| idty.idty_doc.issuers()[0]),
+                    )
+                } else {
+                    Ok(None)
+                }
+            }
+            UidOrPubkey::Pubkey(pubkey) => Ok(Some(*pubkey)),
+        })
+        .expect("fail to get identity");
+    let pubkey = if let Some(pubkey) = pubkey_opt {
+        pubkey
+    } else {
+        println!("This address doesn't exist!");
+        return;
+    };
+    let balances = db
+        .r(|db_r| durs_bc_db_reader::indexes::sources::get_balances(db_r, &[pubkey]))
+        .expect("fail to get balance");
 
-    let req_process_duration = SystemTime::now()
-        .duration_since(req_process_begin)
-        .expect("duration_since error");
-    println!(
-        "Request processed in  {}.{:06} seconds.",
-        req_process_duration.as_secs(),
-        req_process_duration.subsec_micros()
-    );*/
+    let currency_format = AmountFormat {
+        unit: AmountUnit::Currency(dubp_user_docs::amount::currency_symbol(&currency_name.0)),
+        separators,
+    };
+    for balance in balances {
+        println!(
+            "Balance of {}: {}",
+            pubkey,
+            format_amount(balance.0, balance.1, &currency_format)
+        );
+    }
 }
 
 /// Execute DbExWotQuery
@@ -316,7 +499,7 @@ pub fn dbex_wot(profile_path: PathBuf, csv: bool, query: &DbExWotQuery) {
         println!("{}", EMPTY_BLOCKCHAIN);
         return;
     }
-    let currency_params = unwrap!(currency_params_db_datas).1;
+    let (currency_name, currency_params) = unwrap!(currency_params_db_datas);
 
     // get wot_index
     let wot_index = db
@@ -403,24 +586,68 @@ pub fn dbex_wot(profile_path: PathBuf, csv: bool, query: &DbExWotQuery) {
                 compute_distances_duration.subsec_millis()
             );
         }
+        DbExWotQuery::ExportDistanceVector(ref output_path) => {
+            let current_blockstamp = if let Some(current_blockstamp) = db
+                .r(|db_r| durs_bc_db_reader::current_metadata::get_current_blockstamp(db_r))
+                .expect("DbError")
+            {
+                current_blockstamp
+            } else {
+                println!("{}", EMPTY_BLOCKCHAIN);
+                return;
+            };
+            let step_max = currency_params.step_max as u32;
+            let distance_vector = wot_db
+                .read(|db| {
+                    let pubkeys: Vec<String> = (0..db.size())
+                        .map(|id| {
+                            wot_reverse_index
+                                .get(&WotId(id))
+                                .map(ToString::to_string)
+                                .unwrap_or_default()
+                        })
+                        .collect();
+                    DistanceVector::export(
+                        DISTANCE_CALCULATOR,
+                        db,
+                        currency_name.to_string(),
+                        current_blockstamp.to_string(),
+                        &pubkeys,
+                        get_sentry_requirement(members_count, step_max),
+                        step_max,
+                        currency_params.x_percent,
+                    )
+                })
+                .expect("Fail to read WotDB");
+            let json_vector = serde_json::to_string_pretty(&distance_vector)
+                .expect("Fail to serialize distance vector");
+            match std::fs::write(output_path, json_vector) {
+                Ok(()) => println!(
+                    "Distance test vector of block {} written in {}.",
+                    current_blockstamp,
+                    output_path.display()
+                ),
+                Err(e) => println!("Fail to write {}: {}", output_path.display(), e),
+            }
+        }
         DbExWotQuery::ExpireMembers(ref reverse) => {
             // Open blockchain database
             let db = durs_bc_db_reader::open_db_ro(&db_path.as_path()).expect("Fail to open DB.");
             // Get blocks_times
-            let all_blocks = db
+            let all_headers = db
                 .r(|db_r| {
-                    durs_bc_db_reader::blocks::get_blocks_in_local_blockchain(
+                    durs_bc_db_reader::blocks::header::get_block_headers_in_local_blockchain(
                         db_r,
                         BlockNumber(0),
                         10_000_000,
                     )
                 })
-                .expect("Fail to get all blocks");
-            let current_bc_number = all_blocks.last().expect("empty blockchain").number();
-            let current_bc_time = all_blocks.last().expect("empty blockchain").common_time();
-            let blocks_times: HashMap<BlockNumber, u64> = all_blocks
+                .expect("Fail to get all blocks headers");
+            let current_bc_number = all_headers.last().expect("empty blockchain").number();
+            let current_bc_time = all_headers.last().expect("empty blockchain").common_time();
+            let blocks_times: HashMap<BlockNumber, u64> = all_headers
                 .iter()
-                .map(|block| (block.number(), block.common_time()))
+                .map(|header| (header.number(), header.common_time()))
                 .collect();
             // Get expire_dates
             let min_created_ms_time = current_bc_time - currency_params.ms_validity;
@@ -520,6 +747,100 @@ pub fn dbex_wot(profile_path: PathBuf, csv: bool, query: &DbExWotQuery) {
                 println!("{:?} not found !", uid_or_pubkey);
             }
         }
+        DbExWotQuery::MemberExpirations(ref uid_or_pubkey) => {
+            let wot_id_opt = match uid_or_pubkey {
+                UidOrPubkey::Uid(ref uid) => db
+                    .r(|db_r| {
+                        durs_bc_db_reader::indexes::identities::get_wot_id_from_uid(db_r, uid)
+                    })
+                    .expect("get_wot_id_from_uid() : DbError !"),
+                UidOrPubkey::Pubkey(ref pubkey) => wot_index.get(pubkey).copied(),
+            };
+            let wot_id = if let Some(wot_id) = wot_id_opt {
+                wot_id
+            } else {
+                println!("{:?} not found !", uid_or_pubkey);
+                return;
+            };
+            // (type, other member, created block, expire time)
+            let (current_time, mut expirations) = db
+                .r(|db_r| {
+                    let block_time = |block_id: BlockNumber| -> Result<u64, DbError> {
+                        Ok(
+                            durs_bc_db_reader::blocks::header::get_block_header_in_local_blockchain(
+                                db_r, block_id,
+                            )?
+                            .ok_or(DbError::DBCorrupted)?
+                            .common_time(),
+                        )
+                    };
+                    let current_blockstamp =
+                        durs_bc_db_reader::current_metadata::get_current_blockstamp(db_r)?
+                            .ok_or(DbError::DBCorrupted)?;
+                    let idty = durs_bc_db_reader::indexes::identities::get_identity_by_wot_id(
+                        db_r, wot_id,
+                    )?
+                    .ok_or(DbError::DBCorrupted)?;
+                    let mut expirations = vec![(
+                        "membership",
+                        None,
+                        idty.ms_created_block_id,
+                        block_time(idty.ms_created_block_id)? + currency_params.ms_validity,
+                    )];
+                    for (created_block_id, source, target) in
+                        durs_bc_db_reader::indexes::certs::get_member_certs(
+                            db_r,
+                            wot_id,
+                            current_blockstamp.id,
+                        )?
+                    {
+                        let (expiration_type, other) = if target == wot_id {
+                            ("received cert", source)
+                        } else {
+                            ("issued cert", target)
+                        };
+                        expirations.push((
+                            expiration_type,
+                            Some(other),
+                            created_block_id,
+                            block_time(created_block_id)? + currency_params.sig_validity,
+                        ));
+                    }
+                    Ok((block_time(current_blockstamp.id)?, expirations))
+                })
+                .expect("Fail to read db");
+            expirations.sort_by_key(|(_, _, _, expire_time)| *expire_time);
+
+            if csv {
+                println!(
+                    "{},{},{},{}",
+                    &EXPIRATION_TYPE, &USERNAME, &BLOCK, &EXPIRE_TIME
+                );
+            } else {
+                println!(
+                    "Expirations of {} (current time {}):",
+                    wot_uid_index[&wot_id], current_time
+                );
+            }
+            for (expiration_type, other, created_block_id, expire_time) in expirations {
+                let uid = other.map_or(&wot_uid_index[&wot_id], |other| &wot_uid_index[&other]);
+                if csv {
+                    println!(
+                        "{},{},{},{}",
+                        expiration_type, uid, created_block_id, expire_time
+                    );
+                } else {
+                    println!(
+                        "{} {}: created in block #{}, expires at time {} (in {} days)",
+                        expiration_type,
+                        uid,
+                        created_block_id,
+                        expire_time,
+                        expire_time.saturating_sub(current_time) / 86_400
+                    );
+                }
+            }
+        }
         _ => {}
     }
 }
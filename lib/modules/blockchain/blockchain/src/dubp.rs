@@ -16,6 +16,7 @@
 //! Sub-module that checks and applies the content of a block according to the DUBP (DUBP DUniter Blockchain Protocol).
 
 pub mod apply;
+pub mod calculators;
 pub mod check;
 
 use crate::dubp::apply::{ApplyValidBlockError, WriteBlockQueries};
@@ -28,6 +29,8 @@ use dubp_common_doc::BlockNumber;
 use durs_bc_db_reader::blocks::BlockDb;
 use durs_bc_db_reader::DbError;
 use durs_bc_db_writer::{BcDbRwWithWriter, Db, DbWriter};
+use durs_message::responses::BlockApplyStage;
+use std::time::Instant;
 use unwrap::unwrap;
 
 #[derive(Debug, Clone)]
@@ -70,6 +73,7 @@ pub fn check_and_apply_block(
     w: &mut DbWriter,
     block_doc: BlockDocument,
 ) -> Result<CheckAndApplyBlockReturn, BlockError> {
+    bc.apply_metrics.begin_block();
     match check::check_block(bc, &BcDbRwWithWriter { db, w }, &block_doc)? {
         check::BlockChainability::FullyValidAndChainableBLock => {
             treat_chainable_block(bc, db, w, block_doc)
@@ -106,6 +110,19 @@ fn treat_chainable_block(
         );
     }
 
+    // Remove documents written or made invalid by this block from the mempools
+    if let (BlockDocument::V10(ref block_doc_v10), Some(currency_params)) =
+        (&block_doc, bc.currency_params)
+    {
+        bc.tx_mempool
+            .apply_block(block_doc_v10, currency_params.tx_window);
+        bc.wot_mempool.apply_block(
+            block_doc_v10,
+            crate::wot_mempool::WotWindows::from(&currency_params),
+        );
+    }
+
+    let now = Instant::now();
     let write_block_queries: WriteBlockQueries = crate::dubp::apply::apply_valid_block(
         db,
         w,
@@ -114,6 +131,8 @@ fn treat_chainable_block(
         &bc.wot_databases.wot_db,
         &expire_certs,
     )?;
+    bc.apply_metrics
+        .add(BlockApplyStage::WotUpdate, now.elapsed());
 
     Ok(CheckAndApplyBlockReturn::ValidMainBlock(
         write_block_queries,
@@ -28,7 +28,7 @@ use durs_bc_db_writer::{BcDbRwWithWriter, BinFreeStructDb, Db, DbError, DbWriter
 use durs_common_tools::{fatal_error, UsizeSer32};
 use durs_wot::data::NewLinkResult;
 use durs_wot::{WebOfTrust, WotId};
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 
 #[derive(Debug, Clone)]
 /// Stores all queries to apply in database to "apply" the block
@@ -197,17 +197,22 @@ pub fn apply_valid_block_v10<W: WebOfTrust>(
         trace!("stack_up_valid_block: apply cert...success.");
     }
     if !expire_certs.is_empty() {
-        let mut blocks_already_expire = HashSet::new();
-        for ((source, target), created_block_id) in expire_certs {
-            if !blocks_already_expire.contains(created_block_id) {
-                wot_dbs_requests.push(WotsDBsWriteQuery::ExpireCerts(*created_block_id));
-                blocks_already_expire.insert(*created_block_id);
-            }
+        let mut expiry_buckets: HashMap<BlockNumber, Vec<(WotId, WotId)>> = HashMap::new();
+        for (link, created_block_id) in expire_certs {
+            expiry_buckets
+                .entry(*created_block_id)
+                .or_default()
+                .push(*link);
+        }
+        for (created_block_id, bucket) in expiry_buckets {
+            wot_dbs_requests.push(WotsDBsWriteQuery::ExpireCerts(created_block_id));
+            let mut expire_result = Ok(0);
             wot_db
                 .write(|db| {
-                    let _ = db.rem_link(*source, *target);
+                    expire_result = db.expire_pass(&bucket);
                 })
                 .expect("Fail to write in WotDB");
+            expire_result.map_err(|_| ApplyValidBlockError::DBsCorrupted)?;
         }
     }
     if let Some(UsizeSer32(du_amount)) = block.dividend {
@@ -0,0 +1,620 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Deterministic calculators of the time and difficulty fields of a block.
+//! They are used both to check the received blocks and to generate the next block,
+//! to guarantee identical results.
+
+use dubp_common_doc::BlockNumber;
+use dubp_currency_params::CurrencyParameters;
+use dup_crypto::keys::PubKey;
+use durs_bc_db_reader::blocks::header::{BlockHeaderDb, BlockHeaderV10Db};
+use durs_bc_db_reader::current_metadata::current_ud::CurrentUdDb;
+use durs_bc_db_reader::{BcDbInReadTx, DbError};
+use std::collections::{HashMap, HashSet};
+
+/// Calculators error
+#[derive(Debug)]
+pub enum CalculatorsError {
+    /// Database error
+    DbError(DbError),
+    /// A block needed by the calculators is missing
+    MissingBlock(BlockNumber),
+}
+
+impl From<DbError> for CalculatorsError {
+    fn from(e: DbError) -> Self {
+        CalculatorsError::DbError(e)
+    }
+}
+
+/// Headers of the `count` blocks ending at block `last`
+fn v10_headers<DB: BcDbInReadTx>(
+    db: &DB,
+    last: BlockNumber,
+    count: u32,
+) -> Result<Vec<BlockHeaderV10Db>, DbError> {
+    let first = BlockNumber((last.0 + 1).saturating_sub(count));
+    Ok(
+        durs_bc_db_reader::blocks::header::get_block_headers_in_local_blockchain(db, first, count)?
+            .into_iter()
+            .map(|BlockHeaderDb::V10(header)| header)
+            .collect(),
+    )
+}
+
+/// Header of block `number`
+fn v10_header<DB: BcDbInReadTx>(
+    db: &DB,
+    number: BlockNumber,
+) -> Result<BlockHeaderV10Db, CalculatorsError> {
+    durs_bc_db_reader::blocks::header::get_block_header_in_local_blockchain(db, number)?
+        .map(|BlockHeaderDb::V10(header)| header)
+        .ok_or(CalculatorsError::MissingBlock(number))
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+/// Calculated fields of a block
+pub struct CalculatedFields {
+    /// Median time
+    pub median_time: u64,
+    /// Issuers frame fields
+    pub issuers_frame: IssuersFrame,
+    /// Common difficulty
+    pub pow_min: usize,
+    /// Personalized difficulty of the issuer
+    pub personal_difficulty: usize,
+    /// Universal dividend
+    pub dividend: Option<usize>,
+    /// Unit base
+    pub unit_base: usize,
+}
+
+#[derive(Clone, Debug)]
+/// Previous blocks datas needed to compute the fields of the block `number`
+pub struct CalculatorsDatas {
+    /// Number of the computed block
+    pub number: BlockNumber,
+    /// Header of the previous block
+    pub previous: BlockHeaderV10Db,
+    /// Headers of the last `previous.issuers_frame` blocks
+    pub frame: Vec<BlockHeaderV10Db>,
+    /// Times of the last `medianTimeBlocks` blocks
+    pub times: Vec<u64>,
+    /// Median time of the first block of the last `dtDiffEval` blocks
+    pub range_median_time: u64,
+    /// Last universal dividend created before the block
+    pub current_ud: Option<CurrentUdDb>,
+}
+
+impl CalculatorsDatas {
+    /// Read the datas needed to compute the fields of the block `number` in the local blockchain
+    pub fn read<DB: BcDbInReadTx>(
+        db: &DB,
+        number: BlockNumber,
+        currency_params: &CurrencyParameters,
+    ) -> Result<Self, CalculatorsError> {
+        let previous_number = BlockNumber(number.0 - 1);
+        let previous = v10_header(db, previous_number)?;
+        let frame = v10_headers(
+            db,
+            previous_number,
+            usize::from(previous.issuers_frame) as u32,
+        )?;
+        let times = v10_headers(
+            db,
+            previous_number,
+            (currency_params.median_time_blocks as u32).min(number.0),
+        )?
+        .into_iter()
+        .map(|header| header.time)
+        .collect();
+        let range_number =
+            BlockNumber(number.0 - (currency_params.dt_diff_eval as u32).min(number.0));
+        let range_median_time = v10_header(db, range_number)?.median_time;
+        let current_ud = durs_bc_db_reader::current_metadata::get_current_ud(db)?;
+
+        Ok(CalculatorsDatas {
+            number,
+            previous,
+            frame,
+            times,
+            range_median_time,
+            current_ud,
+        })
+    }
+    /// Median time of the block
+    pub fn median_time(&self) -> u64 {
+        median_time(self.times.clone())
+    }
+    /// Issuers frame fields of the block
+    pub fn issuers_frame(&self) -> IssuersFrame {
+        issuers_frame(&self.previous, &self.frame)
+    }
+    /// Common difficulty of the block
+    pub fn pow_min(&self, currency_params: &CurrencyParameters) -> usize {
+        pow_min(
+            &self.previous,
+            self.number,
+            self.median_time(),
+            self.range_median_time,
+            currency_params,
+        )
+    }
+    /// All calculated fields of the block issued by `issuer`
+    pub fn calculated_fields(
+        &self,
+        issuer: &PubKey,
+        currency_params: &CurrencyParameters,
+    ) -> CalculatedFields {
+        let median_time = self.median_time();
+        let pow_min = self.pow_min(currency_params);
+        let (dividend, unit_base) = dividend(
+            self.current_ud,
+            median_time,
+            currency_params,
+            usize::from(self.previous.unit_base),
+        );
+        CalculatedFields {
+            median_time,
+            issuers_frame: self.issuers_frame(),
+            pow_min,
+            personal_difficulty: personal_difficulty(
+                issuer,
+                pow_min,
+                &self.previous,
+                &self.frame,
+                currency_params,
+            ),
+            dividend,
+            unit_base,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+/// Issuers frame fields of a block
+pub struct IssuersFrame {
+    /// Number of different issuers in the previous frame
+    pub issuers_count: usize,
+    /// Frame size (in blocks)
+    pub issuers_frame: usize,
+    /// Frame variation buffer
+    pub issuers_frame_var: isize,
+}
+
+/// Median of a list of values (average of the two central values if the list is even)
+pub fn median(mut values: Vec<u64>) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.sort_unstable();
+    let len = values.len();
+    if len % 2 == 0 {
+        (values[len / 2 - 1] + values[len / 2]) as f64 / 2.0
+    } else {
+        values[len / 2] as f64
+    }
+}
+
+/// Median time of the next block (BR_G11), `times` are the times of the last `medianTimeBlocks` blocks
+pub fn median_time(times: Vec<u64>) -> u64 {
+    median(times).floor() as u64
+}
+
+/// Maximum generation time of a block (in seconds)
+fn max_gen_time(currency_params: &CurrencyParameters) -> f64 {
+    (currency_params.avg_gen_time as f64 * 1.189).ceil()
+}
+
+/// Minimum generation time of a block (in seconds)
+fn min_gen_time(currency_params: &CurrencyParameters) -> f64 {
+    (currency_params.avg_gen_time as f64 / 1.189).floor()
+}
+
+/// Maximum gap between the time and the median time of a block (in seconds)
+pub fn max_acceleration(currency_params: &CurrencyParameters) -> u64 {
+    (max_gen_time(currency_params) * currency_params.median_time_blocks as f64).ceil() as u64
+}
+
+/// Issuers frame of the next block (BR_G04, BR_G05 and BR_G06),
+/// `frame` contains the headers of the last `previous.issuers_frame` blocks
+pub fn issuers_frame(previous: &BlockHeaderV10Db, frame: &[BlockHeaderV10Db]) -> IssuersFrame {
+    let issuers_count = frame
+        .iter()
+        .filter_map(|header| header.issuers.first())
+        .collect::<HashSet<&PubKey>>()
+        .len();
+    let previous_frame = usize::from(previous.issuers_frame);
+    let previous_var = previous.issuers_frame_var;
+    let issuers_frame = if previous_var > 0 {
+        previous_frame + 1
+    } else if previous_var < 0 {
+        previous_frame.saturating_sub(1)
+    } else {
+        previous_frame
+    };
+    let delta = 5 * (issuers_count as isize - usize::from(previous.issuers_count) as isize);
+    let issuers_frame_var = if previous_var > 0 {
+        previous_var + delta - 1
+    } else if previous_var < 0 {
+        previous_var + delta + 1
+    } else {
+        previous_var + delta
+    };
+
+    IssuersFrame {
+        issuers_count,
+        issuers_frame,
+        issuers_frame_var,
+    }
+}
+
+/// Universal dividend and unit base of the block (BR_G13 and BR_G14),
+/// `current_ud` is the last universal dividend created before the block
+pub fn dividend(
+    current_ud: Option<CurrentUdDb>,
+    median_time: u64,
+    currency_params: &CurrencyParameters,
+    previous_unit_base: usize,
+) -> (Option<usize>, usize) {
+    let (amount, base) = if let Some(current_ud) = current_ud {
+        let next_ud = current_ud.project_next_ud(currency_params);
+        if median_time < next_ud.time {
+            return (None, previous_unit_base);
+        }
+        (next_ud.amount, next_ud.base)
+    } else if median_time >= currency_params.ud_time0 {
+        (currency_params.ud0, previous_unit_base)
+    } else {
+        return (None, previous_unit_base);
+    };
+
+    if amount >= 1_000_000 {
+        (Some((amount as f64 / 10.0).ceil() as usize), base + 1)
+    } else {
+        (Some(amount), base)
+    }
+}
+
+/// Common difficulty of the next block.
+/// The difficulty is only re-evaluated every `dtDiffEval` blocks, according to the speed of the
+/// last `dtDiffEval` blocks (`range_median_time` is the median time of the first of them).
+pub fn pow_min(
+    previous: &BlockHeaderV10Db,
+    number: BlockNumber,
+    median_time: u64,
+    range_median_time: u64,
+    currency_params: &CurrencyParameters,
+) -> usize {
+    let previous_pow_min = usize::from(previous.pow_min);
+    let dt_diff_eval = currency_params.dt_diff_eval as u32;
+    if dt_diff_eval == 0 || number.0 % dt_diff_eval != 0 {
+        return previous_pow_min;
+    }
+
+    let range = dt_diff_eval.min(number.0);
+    let elapsed = median_time.saturating_sub(range_median_time);
+    let speed = if elapsed == 0 {
+        100.0
+    } else {
+        f64::from(range) / elapsed as f64
+    };
+    let max_speed = 1.0 / min_gen_time(currency_params);
+    let min_speed = 1.0 / max_gen_time(currency_params);
+
+    if speed >= max_speed {
+        if (previous_pow_min + 2) % 16 == 0 {
+            previous_pow_min + 2
+        } else {
+            previous_pow_min + 1
+        }
+    } else if speed <= min_speed {
+        if previous_pow_min % 16 == 0 {
+            previous_pow_min.saturating_sub(2)
+        } else {
+            previous_pow_min.saturating_sub(1)
+        }
+    } else {
+        previous_pow_min
+    }
+}
+
+/// Personalized difficulty of `issuer` for the next block (BR_G18),
+/// `frame` contains the headers of the last `previous.issuers_frame` blocks
+pub fn personal_difficulty(
+    issuer: &PubKey,
+    pow_min: usize,
+    previous: &BlockHeaderV10Db,
+    frame: &[BlockHeaderV10Db],
+    currency_params: &CurrencyParameters,
+) -> usize {
+    let mut blocks_per_issuer: HashMap<&PubKey, u64> = HashMap::new();
+    for header in frame {
+        if let Some(header_issuer) = header.issuers.first() {
+            *blocks_per_issuer.entry(header_issuer).or_insert(0) += 1;
+        }
+    }
+    let personal_blocks_count = blocks_per_issuer.get(issuer).cloned().unwrap_or(0);
+    let median_of_blocks_in_frame = if blocks_per_issuer.is_empty() {
+        1.0
+    } else {
+        median(blocks_per_issuer.values().cloned().collect())
+    };
+
+    let (previous_issuers_count, blocks_since) = if let Some(last_personal_block) = frame
+        .iter()
+        .rev()
+        .find(|header| header.issuers.first() == Some(issuer))
+    {
+        (
+            usize::from(last_personal_block.issuers_count),
+            previous.number.0 - last_personal_block.number.0,
+        )
+    } else {
+        (0, 0)
+    };
+
+    let personal_excess =
+        (((personal_blocks_count + 1) as f64 / median_of_blocks_in_frame) - 1.0).max(0.0);
+    let personal_handicap = ((1.0 + personal_excess).ln() / 1.189f64.ln()).floor() as usize;
+    let exclusion_factor = (currency_params.percent_rot * previous_issuers_count as f64
+        / (1.0 + f64::from(blocks_since)))
+    .floor() as usize;
+
+    let mut difficulty = pow_min.max(pow_min * exclusion_factor) + personal_handicap;
+    if (difficulty + 1) % 16 == 0 {
+        difficulty += 1;
+    }
+    difficulty
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use dubp_blocks_tests_tools::mocks::block_params::gen_mock_currency_parameters;
+    use dubp_currency_params::CurrencyName;
+    use dup_crypto_tests_tools::mocks::pubkey;
+    use durs_common_tools::UsizeSer32;
+
+    fn header(number: u32, issuer: char, issuers_count: usize) -> BlockHeaderV10Db {
+        BlockHeaderV10Db {
+            version: UsizeSer32(10),
+            nonce: 0,
+            number: BlockNumber(number),
+            pow_min: UsizeSer32(70),
+            time: 0,
+            median_time: 0,
+            members_count: UsizeSer32(3),
+            monetary_mass: 0,
+            unit_base: UsizeSer32(0),
+            issuers_count: UsizeSer32(issuers_count),
+            issuers_frame: UsizeSer32(4),
+            issuers_frame_var: 0,
+            currency: CurrencyName("test".to_owned()),
+            issuers: vec![pubkey(issuer)],
+            signatures: vec![],
+            hash: None,
+            parameters: None,
+            previous_hash: None,
+            previous_issuer: None,
+            inner_hash: None,
+            dividend: None,
+        }
+    }
+
+    #[test]
+    fn test_median_time() {
+        assert_eq!(3, median_time(vec![5, 1, 3]));
+        assert_eq!(2, median_time(vec![4, 1, 3, 1]));
+    }
+
+    #[test]
+    fn test_issuers_frame() {
+        let frame = vec![
+            header(7, 'A', 1),
+            header(8, 'B', 1),
+            header(9, 'A', 2),
+            header(10, 'C', 2),
+        ];
+        let mut previous = frame[3].clone();
+        assert_eq!(
+            IssuersFrame {
+                issuers_count: 3,
+                issuers_frame: 4,
+                issuers_frame_var: 5,
+            },
+            issuers_frame(&previous, &frame)
+        );
+
+        previous.issuers_frame_var = 5;
+        assert_eq!(
+            IssuersFrame {
+                issuers_count: 3,
+                issuers_frame: 5,
+                issuers_frame_var: 9,
+            },
+            issuers_frame(&previous, &frame)
+        );
+    }
+
+    #[test]
+    fn test_pow_min() {
+        let currency_params = gen_mock_currency_parameters();
+        let previous = header(199, 'A', 1);
+
+        // Not an evaluation block
+        assert_eq!(
+            70,
+            pow_min(&previous, BlockNumber(150), 1_000, 0, &currency_params)
+        );
+        // Too fast
+        assert_eq!(
+            71,
+            pow_min(&previous, BlockNumber(200), 1_000, 0, &currency_params)
+        );
+        // Too slow
+        assert_eq!(
+            69,
+            pow_min(&previous, BlockNumber(200), 100_000, 0, &currency_params)
+        );
+        // Right speed
+        assert_eq!(
+            70,
+            pow_min(&previous, BlockNumber(200), 10_000, 0, &currency_params)
+        );
+    }
+
+    /// Parameters of the g1 currency used by the calculators
+    fn g1_currency_parameters() -> CurrencyParameters {
+        CurrencyParameters {
+            median_time_blocks: 24,
+            avg_gen_time: 300,
+            dt_diff_eval: 12,
+            percent_rot: 0.67,
+            ..gen_mock_currency_parameters()
+        }
+    }
+
+    #[test]
+    fn test_g1_max_acceleration() {
+        assert_eq!(8_568, max_acceleration(&g1_currency_parameters()));
+    }
+
+    #[test]
+    fn test_g1_pow_min() {
+        let currency_params = g1_currency_parameters();
+        let mut previous = header(23, 'A', 1);
+
+        // Speed limits: 12 blocks in 12 * 252 s or in 12 * 357 s
+        previous.pow_min = UsizeSer32(86);
+        assert_eq!(
+            87,
+            pow_min(&previous, BlockNumber(24), 3_024, 0, &currency_params)
+        );
+        assert_eq!(
+            86,
+            pow_min(&previous, BlockNumber(24), 3_025, 0, &currency_params)
+        );
+        assert_eq!(
+            86,
+            pow_min(&previous, BlockNumber(24), 4_283, 0, &currency_params)
+        );
+        assert_eq!(
+            85,
+            pow_min(&previous, BlockNumber(24), 4_284, 0, &currency_params)
+        );
+        // Difficulties multiple of 16 are skipped
+        previous.pow_min = UsizeSer32(78);
+        assert_eq!(
+            80,
+            pow_min(&previous, BlockNumber(24), 3_024, 0, &currency_params)
+        );
+        previous.pow_min = UsizeSer32(80);
+        assert_eq!(
+            78,
+            pow_min(&previous, BlockNumber(24), 4_284, 0, &currency_params)
+        );
+    }
+
+    #[test]
+    fn test_g1_personal_difficulty() {
+        let currency_params = g1_currency_parameters();
+        let frame = vec![
+            header(7, 'A', 1),
+            header(8, 'B', 1),
+            header(9, 'A', 2),
+            header(10, 'C', 3),
+        ];
+        let previous = frame[3].clone();
+
+        // Exclusion factor of the issuer of the last block: floor(0.67 * 3 / 1) = 2
+        assert_eq!(
+            144,
+            personal_difficulty(&pubkey('C'), 70, &previous, &frame, &currency_params)
+        );
+        // Issuer of half the frame
+        assert_eq!(
+            76,
+            personal_difficulty(&pubkey('A'), 70, &previous, &frame, &currency_params)
+        );
+    }
+
+    #[test]
+    fn test_personal_difficulty() {
+        let currency_params = gen_mock_currency_parameters();
+        let frame = vec![
+            header(7, 'A', 1),
+            header(8, 'B', 1),
+            header(9, 'A', 2),
+            header(10, 'C', 2),
+        ];
+        let previous = frame[3].clone();
+
+        // Issuer outside the frame
+        assert_eq!(
+            70,
+            personal_difficulty(&pubkey('D'), 70, &previous, &frame, &currency_params)
+        );
+        // Issuer of the last block
+        assert_eq!(
+            74,
+            personal_difficulty(&pubkey('C'), 70, &previous, &frame, &currency_params)
+        );
+        // Issuer of half the frame
+        assert_eq!(
+            76,
+            personal_difficulty(&pubkey('A'), 70, &previous, &frame, &currency_params)
+        );
+    }
+
+    #[test]
+    fn test_dividend() {
+        let currency_params = gen_mock_currency_parameters();
+
+        // Before the first UD
+        assert_eq!((None, 0), dividend(None, 99, &currency_params, 0));
+        // First UD
+        assert_eq!((Some(10), 0), dividend(None, 100, &currency_params, 0));
+
+        let current_ud = CurrentUdDb {
+            amount: 999_999,
+            base: 0,
+            block_number: BlockNumber(10),
+            members_count: 0,
+            monetary_mass: 0,
+            common_time: 100,
+        };
+        // Not yet time for the next UD
+        assert_eq!(
+            (None, 0),
+            dividend(Some(current_ud), 1_099, &currency_params, 0)
+        );
+        // Next UD changes the unit base
+        assert_eq!(
+            (Some(100_000), 1),
+            dividend(
+                Some(CurrentUdDb {
+                    amount: 1_000_000,
+                    ..current_ud
+                }),
+                1_100,
+                &currency_params,
+                0
+            )
+        );
+    }
+}
@@ -15,10 +15,12 @@
 
 //! Sub-module checking if a block complies with all the rules of the (DUBP DUniter Blockchain Protocol).
 
+pub mod chunk;
 pub mod global;
 pub mod hashs;
 pub mod local;
 pub mod pow;
+pub mod tx_inputs;
 
 use crate::dubp::BlockError;
 use crate::BlockchainModule;
@@ -26,8 +28,13 @@ use dubp_block_doc::block::BlockDocumentTrait;
 use dubp_block_doc::BlockDocument;
 use dubp_common_doc::traits::Document;
 use dubp_common_doc::{BlockNumber, Blockstamp};
-use durs_bc_db_reader::BcDbInReadTx;
+use dup_crypto::keys::PubKey;
+use durs_bc_db_reader::indexes::certs::{check_cert_quota, CertQuotaError};
+use durs_bc_db_reader::{BcDbInReadTx, BcDbRead};
+use durs_common_tools::fatal_error;
 use durs_common_tools::traits::bool_ext::BoolExt;
+use durs_message::responses::BlockApplyStage;
+use std::time::Instant;
 use unwrap::unwrap;
 
 #[derive(Debug)]
@@ -51,6 +58,7 @@ pub fn check_block<DB: BcDbInReadTx>(
     block_doc: &BlockDocument,
 ) -> Result<BlockChainability, BlockError> {
     let already_have_block;
+    let now = Instant::now();
     if bc.cautious_mode {
         // Check if we already have the block
         // VERY IMPORTANT: there are cases where it's legitimate to check a block that we already have.
@@ -79,6 +87,8 @@ pub fn check_block<DB: BcDbInReadTx>(
         crate::dubp::check::hashs::check_block_hashes(block_doc).map_err(CheckBlockError::Hashs)?;
         already_have_block = false;
     };
+    bc.apply_metrics
+        .add(BlockApplyStage::PowAndHashs, now.elapsed());
 
     // Check block chainability
     if (block_doc.number().0 == 0 && bc.current_blockstamp == Blockstamp::default())
@@ -90,18 +100,26 @@ pub fn check_block<DB: BcDbInReadTx>(
             debug!("check_block: block {} chainable!", block_doc.blockstamp());
 
             // Local verification
+            let now = Instant::now();
             local::verify_local_validity_block(block_doc, bc.currency_params)
                 .map_err(CheckBlockError::Local)?;
+            bc.apply_metrics
+                .add(BlockApplyStage::LocalRules, now.elapsed());
 
             // Verify block validity (check all protocol rule, very long !)
             if block_doc.number() > BlockNumber(0) {
+                let now = Instant::now();
                 global::verify_global_validity_block(
                     block_doc,
                     db,
+                    &unwrap!(bc.currency_params),
+                    &bc.feature_flags,
                     &bc.wot_index,
                     &bc.wot_databases.wot_db,
                 )
                 .map_err(CheckBlockError::Global)?;
+                bc.apply_metrics
+                    .add(BlockApplyStage::GlobalRules, now.elapsed());
             }
 
             debug!(
@@ -122,3 +140,35 @@ pub fn check_block<DB: BcDbInReadTx>(
         Ok(BlockChainability::LocalValidAndUnchainableBlock)
     }
 }
+
+/// Check that the issuer of a pending certification can write it in the next block
+/// (sig_period and sig_stock rules)
+pub fn check_cert_admission(bc: &BlockchainModule, issuer: &PubKey) -> Result<(), CertQuotaError> {
+    let sig_stock = if let Some(currency_params) = bc.currency_params {
+        currency_params.sig_stock
+    } else {
+        return Ok(());
+    };
+    let current_block_number = bc.current_blockstamp.id;
+    let (cert_chainable_on, median_time) = bc
+        .db()
+        .r(|db_r| {
+            Ok((
+                db_r.get_cert_chainable_on_by_pubkey(issuer)?,
+                durs_bc_db_reader::blocks::header::get_block_header_in_local_blockchain(
+                    db_r,
+                    current_block_number,
+                )?
+                .map(|header| header.common_time())
+                .unwrap_or(0),
+            ))
+        })
+        .unwrap_or_else(|e| fatal_error!("Fail to read blockchain DB: {:?}", e));
+
+    check_cert_quota(
+        cert_chainable_on,
+        global::issued_certs_count(&bc.wot_index, &bc.wot_databases.wot_db, issuer),
+        median_time,
+        sig_stock,
+    )
+}
@@ -0,0 +1,207 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Sub-module checking the rules that depend only on the block itself.
+//! These rules don't need any index, so a whole chunk of blocks can be checked in parallel.
+
+use dubp_block_doc::block::BlockDocumentTrait;
+use dubp_block_doc::BlockDocument;
+use dubp_common_doc::traits::Document;
+use dubp_common_doc::BlockNumber;
+use failure::Fail;
+use rayon::prelude::*;
+use rules_engine::rule::{Rule, RuleFn, RuleNumber};
+use rules_engine::{EngineError, Protocol, ProtocolVersion, RulesEngine, RulesGroup};
+use std::collections::BTreeMap;
+use unwrap::unwrap;
+
+/// Datas of the chunk rules
+#[derive(Debug)]
+pub struct ChunkRuleDatas<'a> {
+    pub(crate) block: &'a BlockDocument,
+}
+
+#[derive(Clone, Debug, Eq, Fail, PartialEq)]
+pub enum ChunkRuleError {
+    #[fail(display = "invalid block hashs: {}", _0)]
+    InvalidHashs(String),
+    #[fail(display = "invalid block signature: {}", _0)]
+    InvalidBlockSignature(String),
+    #[fail(display = "invalid identity signature: {}", _0)]
+    InvalidIdentitySignature(String),
+    #[fail(display = "invalid membership signature: {}", _0)]
+    InvalidMembershipSignature(String),
+    #[fail(display = "invalid transaction: {}", _0)]
+    InvalidTransaction(String),
+}
+
+#[derive(Debug)]
+/// Invalid block in a chunk
+pub struct ChunkVerifyError {
+    /// Number of the first invalid block
+    pub block_number: BlockNumber,
+    /// Broken rule
+    pub error: EngineError<ChunkRuleError>,
+}
+
+/// Check the rules that depend only on the block itself, for all blocks of the chunk.
+/// Blocks are checked in parallel, and so are the rules of each block.
+pub fn verify_chunk(blocks: &[BlockDocument]) -> Result<(), ChunkVerifyError> {
+    let engine = RulesEngine::new(get_all_rules());
+    let protocol = get_chunk_protocol();
+
+    let mut errors: Vec<ChunkVerifyError> = blocks
+        .par_iter()
+        .filter_map(|block| {
+            engine
+                .apply_protocol(
+                    protocol.clone(),
+                    ProtocolVersion(11),
+                    &mut ChunkRuleDatas { block },
+                    &mut (),
+                )
+                .err()
+                .map(|error| ChunkVerifyError {
+                    block_number: block.number(),
+                    error,
+                })
+        })
+        .collect();
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        errors.sort_by_key(|e| e.block_number);
+        Err(errors.swap_remove(0))
+    }
+}
+
+#[inline]
+fn get_chunk_protocol() -> Protocol {
+    Protocol::new(maplit::btreemap![
+        ProtocolVersion(11) => vec![RulesGroup::pr(vec![1usize, 2, 3, 4, 5])].into()
+    ])
+}
+
+type ChunkRule<'d> = Rule<ChunkRuleDatas<'d>, (), ChunkRuleError>;
+
+#[inline]
+fn get_all_rules<'d>() -> BTreeMap<RuleNumber, ChunkRule<'d>> {
+    maplit::btreemap![
+        RuleNumber(1) => rule(1, hashs),
+        RuleNumber(2) => rule(2, block_signature),
+        RuleNumber(3) => rule(3, identities_signatures),
+        RuleNumber(4) => rule(4, memberships_signatures),
+        RuleNumber(5) => rule(5, transactions),
+    ]
+}
+
+#[inline]
+fn rule<'d>(
+    rule_number: usize,
+    v10: fn(&ChunkRuleDatas<'d>) -> Result<(), ChunkRuleError>,
+) -> ChunkRule<'d> {
+    unwrap!(Rule::new(
+        RuleNumber(rule_number),
+        maplit::btreemap![
+            ProtocolVersion(10) => RuleFn::Ref(v10),
+        ]
+    ))
+}
+
+fn hashs(rule_datas: &ChunkRuleDatas) -> Result<(), ChunkRuleError> {
+    crate::dubp::check::hashs::check_block_hashes(rule_datas.block)
+        .map_err(|e| ChunkRuleError::InvalidHashs(format!("{:?}", e)))
+}
+
+fn block_signature(rule_datas: &ChunkRuleDatas) -> Result<(), ChunkRuleError> {
+    let BlockDocument::V10(ref block) = rule_datas.block;
+    // Blocks signatures are only verifiable since v12
+    if usize::from(block.version) >= 12 {
+        block
+            .verify_signatures()
+            .map_err(|e| ChunkRuleError::InvalidBlockSignature(format!("{:?}", e)))?;
+    }
+    Ok(())
+}
+
+fn identities_signatures(rule_datas: &ChunkRuleDatas) -> Result<(), ChunkRuleError> {
+    let BlockDocument::V10(ref block) = rule_datas.block;
+    for identity in &block.identities {
+        identity
+            .verify_signatures()
+            .map_err(|e| ChunkRuleError::InvalidIdentitySignature(format!("{:?}", e)))?;
+    }
+    Ok(())
+}
+
+fn memberships_signatures(rule_datas: &ChunkRuleDatas) -> Result<(), ChunkRuleError> {
+    let BlockDocument::V10(ref block) = rule_datas.block;
+    for membership in block
+        .joiners
+        .iter()
+        .chain(block.actives.iter())
+        .chain(block.leavers.iter())
+    {
+        membership
+            .verify_signatures()
+            .map_err(|e| ChunkRuleError::InvalidMembershipSignature(format!("{:?}", e)))?;
+    }
+    Ok(())
+}
+
+fn transactions(rule_datas: &ChunkRuleDatas) -> Result<(), ChunkRuleError> {
+    let BlockDocument::V10(ref block) = rule_datas.block;
+    for tx in &block.transactions {
+        crate::dubp::check::local::tx_doc::local_verify_tx_doc_v10(block.version, tx)
+            .map_err(|e| ChunkRuleError::InvalidTransaction(format!("{:?}", e)))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use dubp_blocks_tests_tools::mocks::gen_mock_normal_block_v10;
+
+    fn gen_valid_block(number: u32) -> BlockDocument {
+        let mut block = gen_mock_normal_block_v10();
+        block.number = BlockNumber(number);
+        block.inner_hash = Some(block.compute_inner_hash());
+        block.hash = Some(block.compute_hash());
+        BlockDocument::V10(block)
+    }
+
+    #[test]
+    fn test_verify_chunk() {
+        let mut blocks: Vec<BlockDocument> = (1..=8).map(gen_valid_block).collect();
+        assert!(verify_chunk(&blocks).is_ok());
+
+        // The first invalid block is reported
+        for number in &[6usize, 3] {
+            let BlockDocument::V10(ref mut block) = blocks[*number];
+            block.hash = None;
+        }
+        let err = verify_chunk(&blocks).expect_err("chunk must be invalid");
+        assert_eq!(BlockNumber(4), err.block_number);
+        match err.error {
+            EngineError::RuleError(rule_error) => {
+                assert_eq!(RuleNumber(1), rule_error.rule_number)
+            }
+            e => panic!("unexpected error: {:?}", e),
+        }
+    }
+}
@@ -21,9 +21,12 @@ mod rules;
 pub use self::rules::InvalidRuleError;
 
 use self::rules::RuleNotSyncDatas;
+use crate::dubp::calculators::{CalculatorsDatas, CalculatorsError};
 use dubp_block_doc::block::{BlockDocument, BlockDocumentTrait};
 use dubp_common_doc::traits::Document;
 use dubp_common_doc::BlockNumber;
+use dubp_currency_params::feature_flags::FeatureFlags;
+use dubp_currency_params::CurrencyParameters;
 use dup_crypto::keys::PubKey;
 use durs_bc_db_reader::{BcDbInReadTx, DbError};
 use durs_bc_db_writer::BinFreeStructDb;
@@ -46,11 +49,29 @@ impl From<DbError> for GlobalVerifyBlockError {
     }
 }
 
+/// Number of active certifications issued by `pubkey`
+pub fn issued_certs_count<W: WebOfTrust>(
+    wot_index: &HashMap<PubKey, WotId>,
+    wot_db: &BinFreeStructDb<W>,
+    pubkey: &PubKey,
+) -> usize {
+    if let Some(wot_id) = wot_index.get(pubkey) {
+        wot_db
+            .read(|wot| wot.issued_count(*wot_id))
+            .expect("Fail to read WotDB")
+            .unwrap_or(0)
+    } else {
+        0
+    }
+}
+
 pub fn verify_global_validity_block<DB, W>(
     block: &BlockDocument,
     db: &DB,
-    _wot_index: &HashMap<PubKey, WotId>,
-    _wot_db: &BinFreeStructDb<W>,
+    currency_params: &CurrencyParameters,
+    feature_flags: &FeatureFlags,
+    wot_index: &HashMap<PubKey, WotId>,
+    wot_db: &BinFreeStructDb<W>,
 ) -> Result<(), GlobalVerifyBlockError>
 where
     DB: BcDbInReadTx,
@@ -72,18 +93,40 @@ where
     (block.version() >= previous_block.version())
         .or_err(GlobalVerifyBlockError::VersionDecrease)?;
 
+    // Get the certifications stocks of the certifiers
+    let BlockDocument::V10(ref block_v10) = block;
+    let certs_stocks = block_v10
+        .certifications
+        .iter()
+        .map(|certification| {
+            let issuer = certification.to_compact_document().issuer;
+            (issuer, issued_certs_count(wot_index, wot_db, &issuer))
+        })
+        .collect();
+
+    // Compute the expected values of the calculated fields
+    let calculated_fields = CalculatorsDatas::read(db, block.number(), currency_params)
+        .map_err(|e| match e {
+            CalculatorsError::DbError(e) => GlobalVerifyBlockError::DbError(e),
+            CalculatorsError::MissingBlock(_) => GlobalVerifyBlockError::NoPreviousBlock,
+        })?
+        .calculated_fields(&block.issuers()[0], currency_params);
+
     // Define rules datas
     let mut rules_datas = rules::RuleDatas {
         block,
         previous_block: &previous_block,
+        currency_params,
+        certs_stocks,
+        calculated_fields,
     };
     let mut rules_not_sync_datas = RuleNotSyncDatas { db };
 
-    // Apply protocol v10
+    // Apply protocol v10 and the rules of the protocol experiments active in the block
     let engine = RulesEngine::new(rules::all_rules::get_all_rules());
     engine
         .apply_protocol(
-            protocol_versions::get_blockchain_protocol(),
+            protocol_versions::get_blockchain_protocol(feature_flags, block.number()),
             ProtocolVersion(11),
             &mut rules_datas,
             &mut rules_not_sync_datas,
@@ -12,16 +12,57 @@
 //
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
-
 //! Sub-module manage blockchain protocol versions.
 
 mod v11;
 
-use rules_engine::{Protocol, ProtocolVersion};
+use dubp_common_doc::BlockNumber;
+use dubp_currency_params::feature_flags::{FeatureFlags, ProtocolFeature};
+use rules_engine::{Protocol, ProtocolRules, ProtocolVersion, RulesGroup};
+
+/// Rules applied in addition to the protocol rules when a feature is active
+fn feature_rules(feature: ProtocolFeature) -> RulesGroup {
+    match feature {
+        ProtocolFeature::UniqueCertIssuerPerBlock => RulesGroup::s1(1001),
+    }
+}
 
 #[inline]
-pub fn get_blockchain_protocol() -> Protocol {
+pub fn get_blockchain_protocol(
+    feature_flags: &FeatureFlags,
+    block_number: BlockNumber,
+) -> Protocol {
+    let ProtocolRules(mut v11_rules) = v11::get_protocol_rules();
+    v11_rules.extend(
+        feature_flags
+            .active_features(block_number)
+            .into_iter()
+            .map(feature_rules),
+    );
     Protocol::new(maplit::btreemap![
-        ProtocolVersion(11) => v11::get_protocol_rules()
+        ProtocolVersion(11) => v11_rules.into()
     ])
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_feature_rules() {
+        let mut feature_flags = FeatureFlags::default();
+        feature_flags.activate(ProtocolFeature::UniqueCertIssuerPerBlock, BlockNumber(10));
+
+        let rules_groups = |block_number| {
+            unwrap::unwrap!(
+                get_blockchain_protocol(&feature_flags, BlockNumber(block_number))
+                    .get(ProtocolVersion(11))
+                    .cloned()
+            )
+            .0
+        };
+        assert_eq!(v11::get_protocol_rules().0, rules_groups(9));
+        assert_eq!(Some(&RulesGroup::s1(1001)), rules_groups(10).last());
+    }
+}
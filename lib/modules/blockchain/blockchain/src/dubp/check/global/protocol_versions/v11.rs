@@ -19,5 +19,8 @@ use rules_engine::{ProtocolRules, RulesGroup};
 
 #[inline]
 pub fn get_protocol_rules() -> ProtocolRules {
-    vec![RulesGroup::ser(vec![3usize, 100])].into()
+    vec![RulesGroup::ser(vec![
+        3usize, 4, 5, 6, 11, 13, 14, 17, 18, 66, 67, 100,
+    ])]
+    .into()
 }
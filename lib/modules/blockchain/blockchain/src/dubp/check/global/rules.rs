@@ -17,20 +17,38 @@
 
 pub mod all_rules;
 mod br_g03;
+mod br_g04;
+mod br_g05;
+mod br_g06;
 mod br_g100;
+mod br_g11;
+mod br_g13;
+mod br_g14;
+mod br_g17;
+mod br_g18;
+mod br_g66;
+mod br_g67;
+mod br_x01;
 
+use crate::dubp::calculators::CalculatedFields;
 use dubp_block_doc::BlockDocument;
-//use dup_crypto::keys::PubKey;
+use dubp_currency_params::CurrencyParameters;
+use dup_crypto::keys::PubKey;
 use durs_bc_db_reader::indexes::identities::IdentityStateDb;
 use durs_bc_db_reader::{BcDbInReadTx, DbError};
 //use durs_wot::*;
 use failure::Fail;
-//use std::collections::HashMap;
+use std::collections::HashMap;
 
 #[derive(Debug)]
 pub struct RuleDatas<'a> {
     pub(crate) block: &'a BlockDocument,
     pub(crate) previous_block: &'a BlockDocument,
+    pub(crate) currency_params: &'a CurrencyParameters,
+    /// Number of active certifications issued by each certifier of the block
+    pub(crate) certs_stocks: HashMap<PubKey, usize>,
+    /// Expected values of the calculated fields of the block
+    pub(crate) calculated_fields: CalculatedFields,
     //db: &'a Db,
     //wot_db: &BinFreeStructDb<W>,
     //wot_index: HashMap<PubKey, NodeId>,
@@ -54,9 +72,27 @@ pub enum InvalidRuleError {
     #[fail(display = "BR_G100: issuer is not a member (issuer_state={:?})", _0)]
     NotMemberIssuer(IdentityStateDb),
     #[fail(display = "BR_G04: wrong issuers count")]
-    _WrongIssuersCount,
+    WrongIssuersCount,
     #[fail(display = "BR_G05: wrong issuers frame size")]
-    _WrongIssuersFrame,
+    WrongIssuersFrame,
+    #[fail(display = "BR_G06: wrong issuers frame variation")]
+    WrongIssuersFrameVar,
+    #[fail(display = "BR_G11: wrong median time")]
+    WrongMedianTime,
+    #[fail(display = "BR_G13: wrong universal dividend")]
+    WrongDividend,
+    #[fail(display = "BR_G14: wrong unit base")]
+    WrongUnitBase,
+    #[fail(display = "BR_G17: wrong common difficulty")]
+    WrongPowMin,
+    #[fail(display = "BR_G18: proof of work insufficient for the personalized difficulty")]
+    InsufficientPersonalizedPow,
+    #[fail(display = "BR_G66: certification stock exceeded for issuer {}", _0)]
+    CertStockExceeded(PubKey),
+    #[fail(display = "BR_G67: certification period not elapsed for issuer {}", _0)]
+    CertPeriodNotElapsed(PubKey),
+    #[fail(display = "BR_X01: several certifications from issuer {}", _0)]
+    SeveralCertsFromIssuer(PubKey),
 }
 
 impl From<DbError> for InvalidRuleError {
@@ -16,7 +16,18 @@
 //! Sub-module define all rules of blockchain protocol.
 
 use super::br_g03;
+use super::br_g04;
+use super::br_g05;
+use super::br_g06;
 use super::br_g100;
+use super::br_g11;
+use super::br_g13;
+use super::br_g14;
+use super::br_g17;
+use super::br_g18;
+use super::br_g66;
+use super::br_g67;
+use super::br_x01;
 use super::{RuleDatas, RuleNotSyncDatas};
 use crate::dubp::check::global::rules::InvalidRuleError;
 use durs_bc_db_reader::BcDbInReadTx;
@@ -28,6 +39,17 @@ pub fn get_all_rules<'d, 'db, DB: BcDbInReadTx>(
 ) -> BTreeMap<RuleNumber, Rule<RuleDatas<'d>, RuleNotSyncDatas<'db, DB>, InvalidRuleError>> {
     maplit::btreemap![
         RuleNumber(3) => br_g03::rule(),
+        RuleNumber(4) => br_g04::rule(),
+        RuleNumber(5) => br_g05::rule(),
+        RuleNumber(6) => br_g06::rule(),
+        RuleNumber(11) => br_g11::rule(),
+        RuleNumber(13) => br_g13::rule(),
+        RuleNumber(14) => br_g14::rule(),
+        RuleNumber(17) => br_g17::rule(),
+        RuleNumber(18) => br_g18::rule(),
+        RuleNumber(66) => br_g66::rule(),
+        RuleNumber(67) => br_g67::rule(),
         RuleNumber(100) => br_g100::rule(),
+        RuleNumber(1001) => br_x01::rule(),
     ]
 }
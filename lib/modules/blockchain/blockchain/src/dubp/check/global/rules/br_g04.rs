@@ -0,0 +1,49 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Rule BR_G04 - issuersCount
+
+use super::{InvalidRuleError, RuleDatas, RuleNotSyncDatas};
+use dubp_block_doc::BlockDocument;
+use durs_bc_db_reader::BcDbInReadTx;
+use durs_common_tools::traits::bool_ext::BoolExt;
+use rules_engine::rule::{Rule, RuleFn, RuleNumber};
+use rules_engine::ProtocolVersion;
+use unwrap::unwrap;
+
+#[inline]
+pub fn rule<'d, 'db, DB: BcDbInReadTx>(
+) -> Rule<RuleDatas<'d>, RuleNotSyncDatas<'db, DB>, InvalidRuleError> {
+    unwrap!(Rule::new(
+        RuleNumber(4),
+        maplit::btreemap![
+            ProtocolVersion(10) => RuleFn::Ref(v10),
+        ]
+    ))
+}
+
+fn v10(rule_datas: &RuleDatas) -> Result<(), InvalidRuleError> {
+    let RuleDatas {
+        ref block,
+        ref calculated_fields,
+        ..
+    } = rule_datas;
+    let BlockDocument::V10(ref block) = block;
+
+    (usize::from(block.issuers_count) == calculated_fields.issuers_frame.issuers_count)
+        .or_err(InvalidRuleError::WrongIssuersCount)?;
+
+    Ok(())
+}
@@ -0,0 +1,88 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Rule BR_G05 - issuersFrame
+
+use super::{InvalidRuleError, RuleDatas, RuleNotSyncDatas};
+use dubp_block_doc::BlockDocument;
+use durs_bc_db_reader::BcDbInReadTx;
+use durs_common_tools::traits::bool_ext::BoolExt;
+use rules_engine::rule::{Rule, RuleFn, RuleNumber};
+use rules_engine::ProtocolVersion;
+use unwrap::unwrap;
+
+#[inline]
+pub fn rule<'d, 'db, DB: BcDbInReadTx>(
+) -> Rule<RuleDatas<'d>, RuleNotSyncDatas<'db, DB>, InvalidRuleError> {
+    unwrap!(Rule::new(
+        RuleNumber(5),
+        maplit::btreemap![
+            ProtocolVersion(10) => RuleFn::Ref(v10),
+        ]
+    ))
+}
+
+fn v10(rule_datas: &RuleDatas) -> Result<(), InvalidRuleError> {
+    let RuleDatas {
+        ref block,
+        ref calculated_fields,
+        ..
+    } = rule_datas;
+    let BlockDocument::V10(ref block) = block;
+
+    (usize::from(block.issuers_frame) == calculated_fields.issuers_frame.issuers_frame)
+        .or_err(InvalidRuleError::WrongIssuersFrame)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::dubp::calculators::{CalculatedFields, IssuersFrame};
+    use durs_common_tools::UsizeSer32;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_br_g05_issuers_frame() {
+        let pubkey = dup_crypto_tests_tools::mocks::pubkey('A');
+        let mut block = dubp_blocks_tests_tools::mocks::gen_empty_issued_block_v10(pubkey);
+        block.issuers_frame = UsizeSer32(41);
+        let block = BlockDocument::V10(block);
+        let currency_params =
+            dubp_blocks_tests_tools::mocks::block_params::gen_mock_currency_parameters();
+
+        for (expected_issuers_frame, expected) in
+            [(41, Ok(())), (40, Err(InvalidRuleError::WrongIssuersFrame))].iter()
+        {
+            let datas = RuleDatas {
+                block: &block,
+                previous_block: &block,
+                currency_params: &currency_params,
+                certs_stocks: HashMap::new(),
+                calculated_fields: CalculatedFields {
+                    issuers_frame: IssuersFrame {
+                        issuers_frame: *expected_issuers_frame,
+                        ..IssuersFrame::default()
+                    },
+                    ..CalculatedFields::default()
+                },
+            };
+
+            assert_eq!(*expected, v10(&datas));
+        }
+    }
+}
@@ -56,9 +56,11 @@ fn v10<DB: BcDbInReadTx>(
 mod tests {
 
     use super::*;
+    use crate::dubp::calculators::CalculatedFields;
     use dubp_block_doc::BlockDocument;
     use durs_bc_db_reader::MockBcDbInReadTx;
     use mockall::predicate::eq;
+    use std::collections::HashMap;
 
     #[test]
     fn test_br_g100_issuer_not_exist() {
@@ -74,9 +76,14 @@ mod tests {
             .with(eq(pubkey))
             .returning(|_| Ok(None));
 
+        let currency_params =
+            dubp_blocks_tests_tools::mocks::block_params::gen_mock_currency_parameters();
         let mut datas = RuleDatas {
             block: &block,
             previous_block: &block,
+            currency_params: &currency_params,
+            certs_stocks: HashMap::new(),
+            calculated_fields: CalculatedFields::default(),
         };
         let mut not_sync_datas = RuleNotSyncDatas { db: &mock_db };
 
@@ -100,9 +107,14 @@ mod tests {
             .with(eq(pubkey))
             .returning(|_| Ok(Some(IdentityStateDb::Member(vec![1]))));
 
+        let currency_params =
+            dubp_blocks_tests_tools::mocks::block_params::gen_mock_currency_parameters();
         let mut datas = RuleDatas {
             block: &block,
             previous_block: &block,
+            currency_params: &currency_params,
+            certs_stocks: HashMap::new(),
+            calculated_fields: CalculatedFields::default(),
         };
         let mut not_sync_datas = RuleNotSyncDatas { db: &mock_db };
 
@@ -0,0 +1,89 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Rule BR_G13 - UniversalDividend
+
+use super::{InvalidRuleError, RuleDatas, RuleNotSyncDatas};
+use dubp_block_doc::BlockDocument;
+use durs_bc_db_reader::BcDbInReadTx;
+use durs_common_tools::traits::bool_ext::BoolExt;
+use rules_engine::rule::{Rule, RuleFn, RuleNumber};
+use rules_engine::ProtocolVersion;
+use unwrap::unwrap;
+
+#[inline]
+pub fn rule<'d, 'db, DB: BcDbInReadTx>(
+) -> Rule<RuleDatas<'d>, RuleNotSyncDatas<'db, DB>, InvalidRuleError> {
+    unwrap!(Rule::new(
+        RuleNumber(13),
+        maplit::btreemap![
+            ProtocolVersion(10) => RuleFn::Ref(v10),
+        ]
+    ))
+}
+
+fn v10(rule_datas: &RuleDatas) -> Result<(), InvalidRuleError> {
+    let RuleDatas {
+        ref block,
+        ref calculated_fields,
+        ..
+    } = rule_datas;
+    let BlockDocument::V10(ref block) = block;
+
+    (block.dividend.map(usize::from) == calculated_fields.dividend)
+        .or_err(InvalidRuleError::WrongDividend)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::dubp::calculators::CalculatedFields;
+    use durs_common_tools::UsizeSer32;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_br_g13_dividend() {
+        let pubkey = dup_crypto_tests_tools::mocks::pubkey('A');
+        let mut block = dubp_blocks_tests_tools::mocks::gen_empty_issued_block_v10(pubkey);
+        block.dividend = Some(UsizeSer32(1_000));
+        let block = BlockDocument::V10(block);
+        let currency_params =
+            dubp_blocks_tests_tools::mocks::block_params::gen_mock_currency_parameters();
+
+        for (expected_dividend, expected) in [
+            (Some(1_000), Ok(())),
+            (Some(999), Err(InvalidRuleError::WrongDividend)),
+            (None, Err(InvalidRuleError::WrongDividend)),
+        ]
+        .iter()
+        {
+            let datas = RuleDatas {
+                block: &block,
+                previous_block: &block,
+                currency_params: &currency_params,
+                certs_stocks: HashMap::new(),
+                calculated_fields: CalculatedFields {
+                    dividend: *expected_dividend,
+                    ..CalculatedFields::default()
+                },
+            };
+
+            assert_eq!(*expected, v10(&datas));
+        }
+    }
+}
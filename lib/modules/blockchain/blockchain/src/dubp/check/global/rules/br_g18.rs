@@ -0,0 +1,97 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Rule BR_G18 - PoW of personalized difficulty
+
+use super::{InvalidRuleError, RuleDatas, RuleNotSyncDatas};
+use crate::dubp::check::pow::verify_hash_pattern;
+use dubp_block_doc::block::BlockDocumentTrait;
+use durs_bc_db_reader::BcDbInReadTx;
+use durs_common_tools::traits::bool_ext::BoolExt;
+use rules_engine::rule::{Rule, RuleFn, RuleNumber};
+use rules_engine::ProtocolVersion;
+use unwrap::unwrap;
+
+#[inline]
+pub fn rule<'d, 'db, DB: BcDbInReadTx>(
+) -> Rule<RuleDatas<'d>, RuleNotSyncDatas<'db, DB>, InvalidRuleError> {
+    unwrap!(Rule::new(
+        RuleNumber(18),
+        maplit::btreemap![
+            ProtocolVersion(10) => RuleFn::Ref(v10),
+        ]
+    ))
+}
+
+fn v10(rule_datas: &RuleDatas) -> Result<(), InvalidRuleError> {
+    let RuleDatas {
+        ref block,
+        ref calculated_fields,
+        ..
+    } = rule_datas;
+
+    let hash = block
+        .hash()
+        .ok_or(InvalidRuleError::InsufficientPersonalizedPow)?;
+    verify_hash_pattern(hash.0, calculated_fields.personal_difficulty)
+        .is_ok()
+        .or_err(InvalidRuleError::InsufficientPersonalizedPow)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::dubp::calculators::CalculatedFields;
+    use dubp_block_doc::BlockDocument;
+    use dubp_common_doc::BlockHash;
+    use dup_crypto::hashs::Hash;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_br_g18_personalized_pow() {
+        let pubkey = dup_crypto_tests_tools::mocks::pubkey('A');
+        let mut block = dubp_blocks_tests_tools::mocks::gen_empty_issued_block_v10(pubkey);
+        block.hash = Some(BlockHash(
+            Hash::from_hex("000003619ACBF80298F074D8339175901425BC97EF528ED02EBD73CD4CA5C559")
+                .expect("invalid hash"),
+        ));
+        let block = BlockDocument::V10(block);
+        let currency_params =
+            dubp_blocks_tests_tools::mocks::block_params::gen_mock_currency_parameters();
+
+        for (personal_difficulty, expected) in [
+            (70, Ok(())),
+            (80, Err(InvalidRuleError::InsufficientPersonalizedPow)),
+        ]
+        .iter()
+        {
+            let datas = RuleDatas {
+                block: &block,
+                previous_block: &block,
+                currency_params: &currency_params,
+                certs_stocks: HashMap::new(),
+                calculated_fields: CalculatedFields {
+                    personal_difficulty: *personal_difficulty,
+                    ..CalculatedFields::default()
+                },
+            };
+
+            assert_eq!(*expected, v10(&datas));
+        }
+    }
+}
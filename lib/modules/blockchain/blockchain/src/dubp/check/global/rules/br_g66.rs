@@ -0,0 +1,108 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Rule BR_G66 - certification stock
+
+use super::{InvalidRuleError, RuleDatas, RuleNotSyncDatas};
+use dubp_block_doc::BlockDocument;
+use dup_crypto::keys::PubKey;
+use durs_bc_db_reader::BcDbInReadTx;
+use durs_common_tools::traits::bool_ext::BoolExt;
+use rules_engine::rule::{Rule, RuleFn, RuleNumber};
+use rules_engine::ProtocolVersion;
+use std::collections::HashMap;
+use unwrap::unwrap;
+
+#[inline]
+pub fn rule<'d, 'db, DB: BcDbInReadTx>(
+) -> Rule<RuleDatas<'d>, RuleNotSyncDatas<'db, DB>, InvalidRuleError> {
+    unwrap!(Rule::new(
+        RuleNumber(66),
+        maplit::btreemap![
+            ProtocolVersion(10) => RuleFn::Ref(v10),
+        ]
+    ))
+}
+
+fn v10(rule_datas: &RuleDatas) -> Result<(), InvalidRuleError> {
+    let RuleDatas {
+        ref block,
+        currency_params,
+        ref certs_stocks,
+        ..
+    } = rule_datas;
+    let BlockDocument::V10(ref block) = block;
+
+    let mut block_certs_count: HashMap<PubKey, usize> = HashMap::new();
+    for certification in &block.certifications {
+        *block_certs_count
+            .entry(certification.to_compact_document().issuer)
+            .or_insert(0) += 1;
+    }
+    for (issuer, count) in block_certs_count {
+        let stock = certs_stocks.get(&issuer).cloned().unwrap_or(0);
+        (stock + count <= currency_params.sig_stock)
+            .or_err(InvalidRuleError::CertStockExceeded(issuer))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::dubp::calculators::CalculatedFields;
+    use dubp_blocks_tests_tools::mocks::block_params::gen_mock_currency_parameters;
+    use dubp_common_doc::traits::text::TextDocumentFormat;
+    use dubp_common_doc::BlockNumber;
+    use dubp_user_docs::documents::certification::CompactCertificationDocumentV10;
+    use dup_crypto::keys::{ed25519, Sig};
+
+    fn gen_block_with_cert(issuer: PubKey) -> BlockDocument {
+        let mut block = dubp_blocks_tests_tools::mocks::gen_empty_issued_block_v10(issuer);
+        block.certifications = vec![TextDocumentFormat::Compact(
+            CompactCertificationDocumentV10 {
+                issuer,
+                target: dup_crypto_tests_tools::mocks::pubkey('B'),
+                block_number: BlockNumber(0),
+                signature: Sig::Ed25519(ed25519::Signature([0u8; 64])),
+            },
+        )];
+        BlockDocument::V10(block)
+    }
+
+    #[test]
+    fn test_br_g66_cert_stock() {
+        let issuer = dup_crypto_tests_tools::mocks::pubkey('A');
+        let block = gen_block_with_cert(issuer);
+        let currency_params = gen_mock_currency_parameters();
+
+        let mut datas = RuleDatas {
+            block: &block,
+            previous_block: &block,
+            currency_params: &currency_params,
+            certs_stocks: maplit::hashmap![issuer => currency_params.sig_stock - 1],
+            calculated_fields: CalculatedFields::default(),
+        };
+        assert_eq!(Ok(()), v10(&datas));
+
+        datas.certs_stocks = maplit::hashmap![issuer => currency_params.sig_stock];
+        assert_eq!(
+            Err(InvalidRuleError::CertStockExceeded(issuer)),
+            v10(&datas)
+        );
+    }
+}
@@ -0,0 +1,120 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Rule BR_G67 - certification period
+
+use super::{InvalidRuleError, RuleDatas, RuleNotSyncDatas};
+use dubp_block_doc::block::BlockDocumentTrait;
+use dubp_block_doc::BlockDocument;
+use durs_bc_db_reader::BcDbInReadTx;
+use durs_common_tools::traits::bool_ext::BoolExt;
+use rules_engine::rule::{Rule, RuleFn, RuleNumber};
+use rules_engine::ProtocolVersion;
+use unwrap::unwrap;
+
+#[inline]
+pub fn rule<'d, 'db, DB: BcDbInReadTx>(
+) -> Rule<RuleDatas<'d>, RuleNotSyncDatas<'db, DB>, InvalidRuleError> {
+    unwrap!(Rule::new(
+        RuleNumber(67),
+        maplit::btreemap![
+            ProtocolVersion(10) => RuleFn::RefMut(v10),
+        ]
+    ))
+}
+
+fn v10<DB: BcDbInReadTx>(
+    datas: &mut RuleDatas,
+    not_sync_datas: &mut RuleNotSyncDatas<DB>,
+) -> Result<(), InvalidRuleError> {
+    let RuleDatas {
+        ref block,
+        previous_block,
+        ..
+    } = datas;
+    let RuleNotSyncDatas { db } = not_sync_datas;
+    let BlockDocument::V10(ref block) = block;
+    let previous_median_time = previous_block.common_time();
+
+    for certification in &block.certifications {
+        let issuer = certification.to_compact_document().issuer;
+        if let Some(cert_chainable_on) = db.get_cert_chainable_on_by_pubkey(&issuer)? {
+            (cert_chainable_on <= previous_median_time)
+                .or_err(InvalidRuleError::CertPeriodNotElapsed(issuer))?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::dubp::calculators::CalculatedFields;
+    use dubp_blocks_tests_tools::mocks::block_params::gen_mock_currency_parameters;
+    use dubp_common_doc::traits::text::TextDocumentFormat;
+    use dubp_common_doc::BlockNumber;
+    use dubp_user_docs::documents::certification::CompactCertificationDocumentV10;
+    use dup_crypto::keys::{ed25519, Sig};
+    use durs_bc_db_reader::MockBcDbInReadTx;
+    use mockall::predicate::eq;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_br_g67_cert_period() {
+        let issuer = dup_crypto_tests_tools::mocks::pubkey('A');
+        let mut block = dubp_blocks_tests_tools::mocks::gen_empty_issued_block_v10(issuer);
+        block.certifications = vec![TextDocumentFormat::Compact(
+            CompactCertificationDocumentV10 {
+                issuer,
+                target: dup_crypto_tests_tools::mocks::pubkey('B'),
+                block_number: BlockNumber(0),
+                signature: Sig::Ed25519(ed25519::Signature([0u8; 64])),
+            },
+        )];
+        let mut previous_block = block.clone();
+        previous_block.median_time = 100;
+        let block = BlockDocument::V10(block);
+        let previous_block = BlockDocument::V10(previous_block);
+        let currency_params = gen_mock_currency_parameters();
+
+        for (cert_chainable_on, expected) in [
+            (100, Ok(())),
+            (101, Err(InvalidRuleError::CertPeriodNotElapsed(issuer))),
+        ]
+        .iter()
+        {
+            let cert_chainable_on = *cert_chainable_on;
+            let mut mock_db = MockBcDbInReadTx::new();
+            mock_db
+                .expect_get_cert_chainable_on_by_pubkey()
+                .times(1)
+                .with(eq(issuer))
+                .returning(move |_| Ok(Some(cert_chainable_on)));
+
+            let mut datas = RuleDatas {
+                block: &block,
+                previous_block: &previous_block,
+                currency_params: &currency_params,
+                certs_stocks: HashMap::new(),
+                calculated_fields: CalculatedFields::default(),
+            };
+            let mut not_sync_datas = RuleNotSyncDatas { db: &mock_db };
+
+            assert_eq!(*expected, v10(&mut datas, &mut not_sync_datas));
+        }
+    }
+}
@@ -0,0 +1,104 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Experimental rule BR_X01 - unique certification issuer per block
+//! (feature `unique_cert_issuer_per_block`)
+
+use super::{InvalidRuleError, RuleDatas, RuleNotSyncDatas};
+use dubp_block_doc::BlockDocument;
+use durs_bc_db_reader::BcDbInReadTx;
+use durs_common_tools::traits::bool_ext::BoolExt;
+use rules_engine::rule::{Rule, RuleFn, RuleNumber};
+use rules_engine::ProtocolVersion;
+use std::collections::HashSet;
+use unwrap::unwrap;
+
+#[inline]
+pub fn rule<'d, 'db, DB: BcDbInReadTx>(
+) -> Rule<RuleDatas<'d>, RuleNotSyncDatas<'db, DB>, InvalidRuleError> {
+    unwrap!(Rule::new(
+        RuleNumber(1001),
+        maplit::btreemap![
+            ProtocolVersion(10) => RuleFn::Ref(v10),
+        ]
+    ))
+}
+
+fn v10(rule_datas: &RuleDatas) -> Result<(), InvalidRuleError> {
+    let RuleDatas { ref block, .. } = rule_datas;
+    let BlockDocument::V10(ref block) = block;
+
+    let mut issuers = HashSet::new();
+    for certification in &block.certifications {
+        let issuer = certification.to_compact_document().issuer;
+        issuers
+            .insert(issuer)
+            .or_err(InvalidRuleError::SeveralCertsFromIssuer(issuer))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::dubp::calculators::CalculatedFields;
+    use dubp_blocks_tests_tools::mocks::block_params::gen_mock_currency_parameters;
+    use dubp_common_doc::traits::text::TextDocumentFormat;
+    use dubp_common_doc::BlockNumber;
+    use dubp_user_docs::documents::certification::{
+        CertificationDocumentV10, CompactCertificationDocumentV10,
+    };
+    use dup_crypto::keys::{ed25519, PubKey, Sig};
+
+    fn cert(issuer: PubKey, target: char) -> TextDocumentFormat<CertificationDocumentV10> {
+        TextDocumentFormat::Compact(CompactCertificationDocumentV10 {
+            issuer,
+            target: dup_crypto_tests_tools::mocks::pubkey(target),
+            block_number: BlockNumber(0),
+            signature: Sig::Ed25519(ed25519::Signature([0u8; 64])),
+        })
+    }
+
+    #[test]
+    fn test_br_x01_unique_cert_issuer() {
+        let issuer = dup_crypto_tests_tools::mocks::pubkey('A');
+        let mut block_v10 = dubp_blocks_tests_tools::mocks::gen_empty_issued_block_v10(issuer);
+        block_v10.certifications = vec![cert(issuer, 'B')];
+        let block = BlockDocument::V10(block_v10.clone());
+        let currency_params = gen_mock_currency_parameters();
+
+        let datas = RuleDatas {
+            block: &block,
+            previous_block: &block,
+            currency_params: &currency_params,
+            certs_stocks: maplit::hashmap![],
+            calculated_fields: CalculatedFields::default(),
+        };
+        assert_eq!(Ok(()), v10(&datas));
+
+        block_v10.certifications.push(cert(issuer, 'C'));
+        let block = BlockDocument::V10(block_v10);
+        let datas = RuleDatas {
+            block: &block,
+            ..datas
+        };
+        assert_eq!(
+            Err(InvalidRuleError::SeveralCertsFromIssuer(issuer)),
+            v10(&datas)
+        );
+    }
+}
@@ -0,0 +1,230 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Verifies that each input of a transaction can spend its source.
+
+use dubp_common_doc::traits::Document;
+use dubp_user_docs::documents::transaction::{
+    TransactionDocumentTrait, TransactionDocumentV10, TransactionInputV10,
+    TransactionOutputCondition, TransactionUnlockProof, UTXOConditionsGroup,
+};
+use dup_crypto::hashs::Hash;
+use dup_crypto::keys::PubKey;
+use durs_message::responses::TxRejection;
+
+/// Check the outputs conditions and the unlock proofs of each input.
+///
+/// `source_conditions` gives the conditions of the source spent by an input,
+/// or `None` if this source does not exist or is already consumed.
+pub fn check_tx_inputs<F>(
+    tx: &TransactionDocumentV10,
+    median_time: u64,
+    mut source_conditions: F,
+) -> Result<(), TxRejection>
+where
+    F: FnMut(&TransactionInputV10) -> Option<UTXOConditionsGroup>,
+{
+    for (output_index, output) in tx.get_outputs().iter().enumerate() {
+        if !output.check() {
+            return Err(TxRejection::InvalidOutput { output_index });
+        }
+    }
+
+    for (input_index, input) in tx.get_inputs().iter().enumerate() {
+        let conditions =
+            source_conditions(input).ok_or(TxRejection::UnknownSource { input_index })?;
+        let proofs = tx
+            .get_unlocks()
+            .iter()
+            .find(|unlocks| unlocks.index == input_index)
+            .map(|unlocks| &unlocks.unlocks[..])
+            .unwrap_or(&[]);
+
+        if !eval_conditions(&conditions, tx.issuers(), proofs, None) {
+            return Err(TxRejection::InvalidUnlock { input_index });
+        }
+        if !eval_conditions(&conditions, tx.issuers(), proofs, Some(median_time)) {
+            return Err(TxRejection::LockedSource { input_index });
+        }
+    }
+    Ok(())
+}
+
+/// Conditions of a universal dividend source
+pub fn ud_conditions(pubkey: PubKey) -> UTXOConditionsGroup {
+    UTXOConditionsGroup::Single(TransactionOutputCondition::Sig(pubkey))
+}
+
+/// Evaluate conditions against unlock proofs.
+/// If `median_time` is `None`, time conditions are considered satisfied.
+fn eval_conditions(
+    conditions: &UTXOConditionsGroup,
+    issuers: &[PubKey],
+    proofs: &[TransactionUnlockProof],
+    median_time: Option<u64>,
+) -> bool {
+    match *conditions {
+        UTXOConditionsGroup::Single(ref condition) => {
+            eval_condition(condition, issuers, proofs, median_time)
+        }
+        UTXOConditionsGroup::Brackets(ref group) => {
+            eval_conditions(group, issuers, proofs, median_time)
+        }
+        UTXOConditionsGroup::And(ref left, ref right) => {
+            eval_conditions(left, issuers, proofs, median_time)
+                && eval_conditions(right, issuers, proofs, median_time)
+        }
+        UTXOConditionsGroup::Or(ref left, ref right) => {
+            eval_conditions(left, issuers, proofs, median_time)
+                || eval_conditions(right, issuers, proofs, median_time)
+        }
+    }
+}
+
+fn eval_condition(
+    condition: &TransactionOutputCondition,
+    issuers: &[PubKey],
+    proofs: &[TransactionUnlockProof],
+    median_time: Option<u64>,
+) -> bool {
+    match *condition {
+        TransactionOutputCondition::Sig(ref pubkey) => proofs.iter().any(|proof| match *proof {
+            TransactionUnlockProof::Sig(issuer_index) => issuers.get(issuer_index) == Some(pubkey),
+            _ => false,
+        }),
+        TransactionOutputCondition::Xhx(ref hash) => proofs.iter().any(|proof| match *proof {
+            TransactionUnlockProof::Xhx(ref code) => Hash::compute_str(code) == *hash,
+            _ => false,
+        }),
+        TransactionOutputCondition::Cltv(timestamp) => {
+            median_time.map_or(true, |median_time| median_time >= timestamp)
+        }
+        // The write time of the source is not known here, the condition is checked when the
+        // transaction is written in a block.
+        TransactionOutputCondition::Csv(_) => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use dubp_common_doc::traits::DocumentBuilder;
+    use dubp_common_doc::{BlockHash, BlockNumber, Blockstamp};
+    use dubp_user_docs::documents::transaction::*;
+    use dup_crypto::keys::*;
+    use std::str::FromStr;
+
+    fn gen_tx(unlocks: &str) -> TransactionDocumentV10 {
+        let issuer = dup_crypto_tests_tools::mocks::pubkey('A');
+        let builder = TransactionDocumentV10Builder {
+            currency: "test",
+            blockstamp: &Blockstamp {
+                id: BlockNumber(10),
+                hash: BlockHash(Hash::default()),
+            },
+            locktime: &0,
+            issuers: &[issuer],
+            inputs: &[
+                TransactionInputV10::from_str(&format!("10:0:D:{}:1", issuer))
+                    .expect("invalid input"),
+                TransactionInputV10::from_str(&format!("10:0:T:{}:0", Hash::default()))
+                    .expect("invalid input"),
+            ],
+            unlocks: &[
+                TransactionInputUnlocksV10::from_str("0:SIG(0)").expect("invalid unlock"),
+                TransactionInputUnlocksV10::from_str(unlocks).expect("invalid unlock"),
+            ],
+            outputs: &[
+                TransactionOutputV10::from_str(&format!("20:0:SIG({})", issuer))
+                    .expect("invalid output"),
+            ],
+            comment: "",
+            hash: None,
+        };
+        let TransactionDocument::V10(tx) = TransactionDocumentBuilder::V10(builder)
+            .build_with_signature(vec![Sig::Ed25519(ed25519::Signature([0u8; 64]))]);
+        tx
+    }
+
+    fn conditions(conditions: &str) -> UTXOConditionsGroup {
+        TransactionOutputV10::from_str(&format!("10:0:{}", conditions))
+            .expect("invalid output")
+            .conditions
+            .conditions
+    }
+
+    #[test]
+    fn test_check_tx_inputs() {
+        let issuer = dup_crypto_tests_tools::mocks::pubkey('A');
+        let other = dup_crypto_tests_tools::mocks::pubkey('B');
+        let xhx = Hash::compute_str("code");
+        let tx = gen_tx("1:SIG(0)");
+
+        // Unknown source
+        assert_eq!(
+            Err(TxRejection::UnknownSource { input_index: 1 }),
+            check_tx_inputs(&tx, 100, |input| match *input {
+                TransactionInputV10::D(_, _, pubkey, _) => Some(ud_conditions(pubkey)),
+                TransactionInputV10::T(..) => None,
+            })
+        );
+        // Valid signature
+        assert_eq!(
+            Ok(()),
+            check_tx_inputs(&tx, 100, |_| Some(ud_conditions(issuer)))
+        );
+        // Signature of another key
+        assert_eq!(
+            Err(TxRejection::InvalidUnlock { input_index: 1 }),
+            check_tx_inputs(&tx, 100, |input| match *input {
+                TransactionInputV10::D(..) => Some(ud_conditions(issuer)),
+                TransactionInputV10::T(..) => Some(ud_conditions(other)),
+            })
+        );
+        // Locked source
+        let locked = conditions(&format!("(SIG({}) && CLTV(200))", issuer));
+        assert_eq!(
+            Err(TxRejection::LockedSource { input_index: 1 }),
+            check_tx_inputs(&tx, 100, |input| match *input {
+                TransactionInputV10::D(..) => Some(ud_conditions(issuer)),
+                TransactionInputV10::T(..) => Some(locked.clone()),
+            })
+        );
+        assert_eq!(
+            Ok(()),
+            check_tx_inputs(&tx, 200, |input| match *input {
+                TransactionInputV10::D(..) => Some(ud_conditions(issuer)),
+                TransactionInputV10::T(..) => Some(locked.clone()),
+            })
+        );
+        // Hash lock
+        let hash_locked = conditions(&format!("(SIG({}) || XHX({}))", other, xhx));
+        assert_eq!(
+            Ok(()),
+            check_tx_inputs(&gen_tx("1:XHX(code)"), 100, |input| match *input {
+                TransactionInputV10::D(..) => Some(ud_conditions(issuer)),
+                TransactionInputV10::T(..) => Some(hash_locked.clone()),
+            })
+        );
+        assert_eq!(
+            Err(TxRejection::InvalidUnlock { input_index: 1 }),
+            check_tx_inputs(&gen_tx("1:XHX(wrong)"), 100, |input| match *input {
+                TransactionInputV10::D(..) => Some(ud_conditions(issuer)),
+                TransactionInputV10::T(..) => Some(hash_locked.clone()),
+            })
+        );
+    }
+}
@@ -17,6 +17,7 @@
 
 use crate::*;
 use dubp_common_doc::BlockNumber;
+use durs_conf::BlocksRequestsConf;
 use durs_message::*;
 use durs_module::ModuleReqId;
 use durs_network::requests::OldNetworkRequest;
@@ -50,11 +51,108 @@ pub fn request_chunk(
 ) -> (ModuleReqId, OldNetworkRequest) {
     let req = OldNetworkRequest::GetBlocks(
         ModuleReqFullId(BlockchainModule::name(), req_id),
-        *CHUNK_SIZE,
+        bc.blocks_requests.chunk_size(),
         from,
     );
     (request_network(bc, req_id, &req), req)
 }
+
+/// Request identifier used neither by the pending requests of the module nor by `other_requests`
+fn free_req_id(
+    bc: &BlockchainModule,
+    other_requests: &HashMap<ModuleReqId, OldNetworkRequest>,
+) -> ModuleReqId {
+    let mut req_id = ModuleReqId(0);
+    while bc.pending_network_requests.contains_key(&req_id) || other_requests.contains_key(&req_id)
+    {
+        req_id = ModuleReqId(req_id.0 + 1);
+    }
+    req_id
+}
+
+/// First block of the next chunk to request to keep the blocks requests pipelined,
+/// `None` if nothing has to be requested yet: the target is reached, `max_blocks_request` blocks
+/// are already requested or `max_queued_blocks` received blocks are waiting to be applied.
+pub fn next_pipelined_chunk(
+    conf: &BlocksRequestsConf,
+    next_from: BlockNumber,
+    target: BlockNumber,
+    pending_chunks: usize,
+    queued_blocks: usize,
+) -> Option<BlockNumber> {
+    if next_from > target
+        || pending_chunks >= conf.max_pending_chunks()
+        || queued_blocks >= conf.max_queued_blocks as usize
+    {
+        None
+    } else {
+        Some(next_from)
+    }
+}
+
+/// Request the chunks following the block `next_from - 1` until the pipeline is full,
+/// `pending_requests` being the chunk requests in progress. Returns the first block not requested.
+pub fn fill_blocks_pipeline(
+    bc: &BlockchainModule,
+    mut next_from: BlockNumber,
+    target: BlockNumber,
+    pending_requests: &mut HashMap<ModuleReqId, OldNetworkRequest>,
+    queued_blocks: usize,
+) -> BlockNumber {
+    let pending_chunks = |requests: &HashMap<ModuleReqId, OldNetworkRequest>| {
+        requests
+            .values()
+            .filter(|req| {
+                if let OldNetworkRequest::GetBlocks(..) = req {
+                    true
+                } else {
+                    false
+                }
+            })
+            .count()
+    };
+    while let Some(from) = next_pipelined_chunk(
+        &bc.blocks_requests,
+        next_from,
+        target,
+        pending_chunks(pending_requests),
+        queued_blocks,
+    ) {
+        let req_id = free_req_id(bc, pending_requests);
+        let (req_id, req) = request_chunk(bc, req_id, from.0);
+        pending_requests.insert(req_id, req);
+        next_from = BlockNumber(from.0 + bc.blocks_requests.chunk_size());
+    }
+    next_from
+}
+/// Request the next main blocks while the received chunk ending at `received_end` (excluded)
+/// is applied, `queued_blocks` being the number of blocks of this chunk
+pub fn pipeline_main_blocks(
+    bc: &mut BlockchainModule,
+    received_end: BlockNumber,
+    queued_blocks: usize,
+) {
+    let target = bc.consensus.id;
+    if target <= bc.current_blockstamp.id {
+        return;
+    }
+    let mut pending_requests = std::mem::replace(&mut bc.pending_network_requests, HashMap::new());
+    let next_from = pending_requests
+        .values()
+        .filter_map(|req| {
+            if let OldNetworkRequest::GetBlocks(_, count, from) = req {
+                Some(BlockNumber(from + count))
+            } else {
+                None
+            }
+        })
+        .chain(std::iter::once(received_end))
+        .max()
+        .unwrap_or(received_end);
+    fill_blocks_pipeline(bc, next_from, target, &mut pending_requests, queued_blocks);
+    bc.pending_network_requests = pending_requests;
+}
+
 /// Requests blocks from current to `to`
 pub fn request_blocks_to(
     bc: &BlockchainModule,
@@ -67,8 +165,9 @@ pub fn request_blocks_to(
     };
     info!("BlockchainModule : request_blocks_to({}-{})", from, to);
     if bc.current_blockstamp.id < to {
-        let real_to = if (to.0 - bc.current_blockstamp.id.0) > *MAX_BLOCKS_REQUEST {
-            bc.current_blockstamp.id.0 + *MAX_BLOCKS_REQUEST
+        let max_blocks_request = bc.blocks_requests.max_blocks_request;
+        let real_to = if (to.0 - bc.current_blockstamp.id.0) > max_blocks_request {
+            bc.current_blockstamp.id.0 + max_blocks_request
         } else {
             to.0
         };
@@ -78,23 +177,69 @@ pub fn request_blocks_to(
     }
 }
 
-/// Requets previous blocks from specific orphan block
-#[inline]
+/// Requests the blocks preceding an orphan block, so that its branch can be linked to the fork tree
 pub fn request_orphan_previous(
-    _bc: &BlockchainModule,
-    _orphan_block_number: BlockNumber,
+    bc: &BlockchainModule,
+    orphan_block_number: BlockNumber,
 ) -> HashMap<ModuleReqId, OldNetworkRequest> {
-    /*if orphan_block_number.0
-        > bc.current_blockstamp.id.0 - *durs_bc_db_writer::constants::FORK_WINDOW_SIZE as u32
-        && orphan_block_number.0 <= bc.current_blockstamp.id.0 + *CHUNK_SIZE
+    let fork_window_size = if let Some(currency_params) = bc.currency_params {
+        currency_params.fork_window_size as u32
+    } else {
+        return HashMap::with_capacity(0);
+    };
+    if let Some((from, to)) = orphan_missing_range(
+        &bc.blocks_requests,
+        bc.current_blockstamp.id,
+        fork_window_size,
+        orphan_block_number,
+    ) {
+        // The parent of the orphan block may already be requested
+        let already_requested = bc.pending_network_requests.values().any(|req| {
+            if let OldNetworkRequest::GetBlocks(_, count, req_from) = req {
+                *req_from <= to.0 && to.0 < req_from + count
+            } else {
+                false
+            }
+        });
+        if already_requested {
+            HashMap::with_capacity(0)
+        } else {
+            debug!(
+                "blockchain: request missing parents of orphan block #{}",
+                orphan_block_number
+            );
+            request_blocks_from_to(bc, from, to)
+        }
+    } else {
+        HashMap::with_capacity(0)
+    }
+}
+
+/// Range of the blocks to request to link an orphan block to the fork tree.
+/// The blocks out of the fork window are not requested because they would be refused,
+/// and the blocks too far ahead are left to the requests of the next main blocks.
+fn orphan_missing_range(
+    conf: &BlocksRequestsConf,
+    current_block_number: BlockNumber,
+    fork_window_size: u32,
+    orphan_block_number: BlockNumber,
+) -> Option<(BlockNumber, BlockNumber)> {
+    if orphan_block_number.0 == 0
+        || orphan_block_number.0 > current_block_number.0 + conf.max_blocks_request
     {
-        request_blocks_from_to(
-            bc,
-            orphan_block_number.0 - *CHUNK_SIZE + 1,
-            orphan_block_number.0,
-        )
-    } else {*/
-    HashMap::with_capacity(0)
+        return None;
+    }
+    let fork_window_begin = current_block_number.0.saturating_sub(fork_window_size);
+    let to = orphan_block_number.0 - 1;
+    if to < fork_window_begin {
+        None
+    } else {
+        let from = std::cmp::max(
+            fork_window_begin,
+            orphan_block_number.0.saturating_sub(conf.chunk_size()),
+        );
+        Some((BlockNumber(from), BlockNumber(to)))
+    }
 }
 
 /// Requests blocks from `from` to `to`
@@ -108,15 +253,80 @@ pub fn request_blocks_from_to(
     let to = to.0;
     let mut requests_ids = HashMap::new();
     while from <= to {
-        let mut req_id = ModuleReqId(0);
-        while bc.pending_network_requests.contains_key(&req_id)
-            || requests_ids.contains_key(&req_id)
-        {
-            req_id = ModuleReqId(req_id.0 + 1);
-        }
+        let req_id = free_req_id(bc, &requests_ids);
         let (req_id, req) = request_chunk(bc, req_id, from);
         requests_ids.insert(req_id, req);
-        from += *CHUNK_SIZE;
+        from += bc.blocks_requests.chunk_size();
     }
     requests_ids
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_orphan_missing_range() {
+        let conf = BlocksRequestsConf::default();
+        // Missing parents of a fork branch
+        assert_eq!(
+            Some((BlockNumber(950), BlockNumber(999))),
+            orphan_missing_range(&conf, BlockNumber(1_000), 100, BlockNumber(1_000))
+        );
+        // Missing parents near the fork window begin
+        assert_eq!(
+            Some((BlockNumber(900), BlockNumber(919))),
+            orphan_missing_range(&conf, BlockNumber(1_000), 100, BlockNumber(920))
+        );
+        // Orphan block out of fork window
+        assert_eq!(
+            None,
+            orphan_missing_range(&conf, BlockNumber(1_000), 100, BlockNumber(900))
+        );
+        // Orphan block too far ahead
+        assert_eq!(
+            None,
+            orphan_missing_range(&conf, BlockNumber(1_000), 100, BlockNumber(1_501))
+        );
+    }
+
+    #[test]
+    fn test_next_pipelined_chunk() {
+        let conf = BlocksRequestsConf::default();
+        let max_pending_chunks = conf.max_pending_chunks();
+        let max_queued_blocks = conf.max_queued_blocks as usize;
+
+        assert_eq!(
+            Some(BlockNumber(100)),
+            next_pipelined_chunk(&conf, BlockNumber(100), BlockNumber(1_000), 0, 0)
+        );
+        // Target reached
+        assert_eq!(
+            None,
+            next_pipelined_chunk(&conf, BlockNumber(1_001), BlockNumber(1_000), 0, 0)
+        );
+        // Enough blocks requested
+        assert_eq!(
+            None,
+            next_pipelined_chunk(
+                &conf,
+                BlockNumber(100),
+                BlockNumber(1_000),
+                max_pending_chunks,
+                0
+            )
+        );
+        // Backpressure
+        assert_eq!(
+            None,
+            next_pipelined_chunk(
+                &conf,
+                BlockNumber(100),
+                BlockNumber(1_000),
+                0,
+                max_queued_blocks
+            )
+        );
+    }
+}
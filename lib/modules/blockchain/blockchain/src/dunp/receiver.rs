@@ -16,22 +16,55 @@
 //! Sub-module managing the reception of messages from the inter-node network layer
 //! (received by the intermediaries of events transmitted by the network module).
 
+use crate::constants::REPEATED_LOG_INTERVAL_IN_SECS;
 use crate::dubp::apply::exec_currency_queries;
 use crate::*;
 use dubp_common_doc::traits::Document;
+use dubp_common_doc::BlockNumber;
+use dubp_user_docs::documents::certification::CertificationDocument;
+use dubp_user_docs::documents::identity::IdentityDocument;
+use dubp_user_docs::documents::membership::MembershipDocument;
+use dubp_user_docs::documents::transaction::TransactionDocument;
 use dubp_user_docs::documents::UserDocumentDUBP;
+use durs_common_tools::log_once_per;
+use std::time::Instant;
 use unwrap::unwrap;
 
-pub fn receive_user_documents(_bc: &mut BlockchainModule, network_documents: &[UserDocumentDUBP]) {
+pub fn receive_user_documents(bc: &mut BlockchainModule, network_documents: &[UserDocumentDUBP]) {
     for network_document in network_documents {
         match network_document {
-            UserDocumentDUBP::Certification(_) => {}
-            UserDocumentDUBP::Identity(_) => {}
-            UserDocumentDUBP::Membership(_) => {}
+            UserDocumentDUBP::Certification(cert) => {
+                let CertificationDocument::V10(ref cert) = **cert;
+                let issuer = cert.issuers()[0];
+                if let Err(e) = check::check_cert_admission(bc, &issuer) {
+                    debug!("Reject certification of {}: {:?}", issuer, e);
+                    events::sent::send_document_rejected(
+                        bc,
+                        wot_mempool::doc_hash(cert),
+                        e.to_string(),
+                    );
+                } else {
+                    wot_mempool::receive_certification(bc, cert.clone());
+                }
+            }
+            UserDocumentDUBP::Identity(IdentityDocument::V10(idty)) => {
+                wot_mempool::receive_identity(bc, idty.clone());
+            }
+            UserDocumentDUBP::Membership(MembershipDocument::V10(membership)) => {
+                wot_mempool::receive_membership(bc, membership.clone());
+            }
             UserDocumentDUBP::Revocation(_) => {}
-            UserDocumentDUBP::Transaction(_) => {}
+            UserDocumentDUBP::Transaction(tx) => {
+                let TransactionDocument::V10(ref tx) = **tx;
+                if let Err(rejection) = mempool::receive_tx(bc, tx.clone()) {
+                    debug!("Reject transaction: {}", rejection);
+                }
+            }
         }
     }
+    if !network_documents.is_empty() {
+        events::sent::send_mempool_stats(bc);
+    }
 }
 
 pub fn receive_blocks(bc: &mut BlockchainModule, blocks: Vec<BlockDocument>) {
@@ -39,12 +72,15 @@ pub fn receive_blocks(bc: &mut BlockchainModule, blocks: Vec<BlockDocument>) {
     let mut save_dbs = false;
     let mut save_wots_dbs = false;
     let mut first_orphan = true;
+    bc.begin_write();
     for block in blocks.into_iter() {
         let blockstamp = block.blockstamp();
 
         // For eventually rollback
         let mut new_bc_branch_opt = None;
 
+        bc.applying_write(blockstamp);
+
         // Open write db transaction
         let db = bc.take_db();
         db.write(|mut w| {
@@ -59,6 +95,7 @@ pub fn receive_blocks(bc: &mut BlockchainModule, blocks: Vec<BlockDocument>) {
                         bc.current_blockstamp = new_current_block.blockstamp();
 
                         // Apply db requests
+                        let now = Instant::now();
                         bc_db_query.apply(
                             &db,
                             &mut w,
@@ -66,12 +103,21 @@ pub fn receive_blocks(bc: &mut BlockchainModule, blocks: Vec<BlockDocument>) {
                             unwrap!(bc.currency_params).fork_window_size,
                             None,
                         )?;
+                        bc.apply_metrics
+                            .add(BlockApplyStage::BlockWrite, now.elapsed());
+                        let now = Instant::now();
                         for query in &wot_dbs_queries {
                             query
                                 .apply(&db, &mut w, &blockstamp, &unwrap!(bc.currency_params))
                                 .expect("Fatal error : Fail to apply WotsDBsWriteRequest !");
                         }
+                        bc.apply_metrics
+                            .add(BlockApplyStage::WotIndexesWrite, now.elapsed());
+                        let now = Instant::now();
                         exec_currency_queries(&db, &mut w, blockstamp.id, tx_dbs_queries)?;
+                        bc.apply_metrics
+                            .add(BlockApplyStage::CurrencyIndexesWrite, now.elapsed());
+                        bc.apply_metrics.end_block(blockstamp);
                         if !wot_dbs_queries.is_empty() {
                             save_wots_dbs = true;
                         }
@@ -88,10 +134,12 @@ pub fn receive_blocks(bc: &mut BlockchainModule, blocks: Vec<BlockDocument>) {
                     }
                     CheckAndApplyBlockReturn::ForkBlock => {
                         info!("blockchain: new fork block(#{})", blockstamp);
+                        send_new_fork_event(bc, blockstamp);
                         if let Ok(Some(new_bc_branch)) = fork_algo::fork_resolution_algo(
                             &BcDbRwWithWriter { db: &db, w: &w },
                             &bc.fork_tree,
                             unwrap!(bc.currency_params).fork_window_size,
+                            &bc.fork_resolution,
                             bc.current_blockstamp,
                             &bc.invalid_forks,
                         ) {
@@ -108,7 +156,13 @@ pub fn receive_blocks(bc: &mut BlockchainModule, blocks: Vec<BlockDocument>) {
                 },
                 Err(e) => match e {
                     BlockError::InvalidBlock(e) => {
-                        warn!("InvalidBlock #{}: {:?}", blockstamp.id.0, e);
+                        log_once_per!(
+                            *REPEATED_LOG_INTERVAL_IN_SECS,
+                            log::Level::Warn,
+                            "InvalidBlock #{}: {:?}",
+                            blockstamp.id.0,
+                            e
+                        );
                         crate::events::sent::send_event(
                             bc,
                             &BlockchainEvent::RefusedBlock(blockstamp),
@@ -144,15 +198,30 @@ pub fn receive_blocks(bc: &mut BlockchainModule, blocks: Vec<BlockDocument>) {
         if let Some(new_bc_branch) = new_bc_branch_opt {
             info!("blockchain: apply_rollback({:?})", new_bc_branch);
             rollback::apply_rollback(bc, new_bc_branch);
+            save_dbs = true;
+            save_wots_dbs = true;
         }
     }
     // Save databases
-    if save_dbs {
-        bc.db()
-            .save()
-            .unwrap_or_else(|_| fatal_error!("DB corrupted, please reset data."));
-    }
-    if save_wots_dbs {
-        bc.wot_databases.save_dbs();
+    bc.end_write(DirtyStores {
+        blockchain: save_dbs,
+        wot: save_wots_dbs,
+    });
+}
+
+/// Send event NewFork if the fork block is linked to the local blockchain
+fn send_new_fork_event(bc: &BlockchainModule, fork_head: Blockstamp) {
+    if let Some(node_id) = bc.fork_tree.find_node_with_blockstamp(&fork_head) {
+        let branch = bc.fork_tree.get_fork_branch(node_id);
+        if let Some(first_block) = branch.first() {
+            events::sent::send_event(
+                bc,
+                &BlockchainEvent::NewFork {
+                    head: fork_head,
+                    fork_point: BlockNumber(first_block.id.0.saturating_sub(1)),
+                    rollback_depth: fork_algo::rollback_depth(bc.current_blockstamp, &branch),
+                },
+            );
+        }
     }
 }
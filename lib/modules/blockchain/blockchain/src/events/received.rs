@@ -38,7 +38,7 @@ pub fn receive_event(
         },
         DursEvent::MemPoolEvent(mempool_event) => {
             if let MemPoolEvent::FindNextBlock(next_block_box) = mempool_event {
-                dunp::receiver::receive_blocks(bc, vec![next_block_box.deref().clone()]);
+                generation::receive_self_block(bc, next_block_box.deref().clone());
             }
         }
         _ => {} // Others modules events
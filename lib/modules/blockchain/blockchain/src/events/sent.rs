@@ -16,15 +16,20 @@
 //! Sub-module managing the events emitted by the blockchain module.
 
 use crate::*;
+use dup_crypto::hashs::Hash;
 use durs_common_tools::fatal_error;
-use durs_message::events::BlockchainEvent;
+use durs_message::events::{BlockchainEvent, MempoolStats};
 use durs_module::ModuleEvent;
 
 /// Send blockchain event
 pub fn send_event(bc: &BlockchainModule, event: &BlockchainEvent) {
     let module_event = match event {
         BlockchainEvent::StackUpValidBlock(_) => ModuleEvent::NewValidBlock,
-        BlockchainEvent::RevertBlocks(_) => ModuleEvent::RevertBlocks,
+        BlockchainEvent::NewFork { .. } => ModuleEvent::NewFork,
+        BlockchainEvent::RevertBlocks { .. } => ModuleEvent::RevertBlocks,
+        BlockchainEvent::SyncProgress { .. } => ModuleEvent::SyncEvent,
+        BlockchainEvent::DocumentRejected { .. } => ModuleEvent::RejectedDocument,
+        BlockchainEvent::MempoolStats(_) => ModuleEvent::MempoolStatsChange,
         _ => return,
     };
     bc.router_sender
@@ -35,3 +40,34 @@ pub fn send_event(bc: &BlockchainModule, event: &BlockchainEvent) {
         }))
         .unwrap_or_else(|_| fatal_error!("Fail to send BlockchainEvent to router"));
 }
+
+/// Send event DocumentRejected (a received document failed validation)
+pub fn send_document_rejected(bc: &BlockchainModule, doc_hash: Hash, reason: String) {
+    send_event(bc, &BlockchainEvent::DocumentRejected { doc_hash, reason });
+}
+
+/// Send event MempoolStats (current occupancy of the mempools)
+pub fn send_mempool_stats(bc: &BlockchainModule) {
+    send_event(
+        bc,
+        &BlockchainEvent::MempoolStats(MempoolStats {
+            transactions: bc.tx_mempool.txs_count(),
+            identities: bc.wot_mempool.identities_count(),
+            memberships: bc.wot_mempool.memberships_count(),
+            certifications: bc.wot_mempool.certifications_count(),
+        }),
+    );
+}
+
+/// Send event NewValidBlockFromSelf (valid block issued by the local node)
+pub fn send_block_from_self_event(bc: &BlockchainModule, block: BlockDocument) {
+    bc.router_sender
+        .send(RouterThreadMessage::ModuleMessage(DursMsg::Event {
+            event_from: ModuleStaticName(MODULE_NAME),
+            event_type: ModuleEvent::NewValidBlockFromSelf,
+            event_content: DursEvent::BlockchainEvent(Box::new(
+                BlockchainEvent::StackUpValidBlock(Box::new(block)),
+            )),
+        }))
+        .unwrap_or_else(|_| fatal_error!("Fail to send BlockchainEvent to router"));
+}
@@ -18,80 +18,113 @@ use dubp_common_doc::Blockstamp;
 use durs_bc_db_reader::blocks::fork_tree::ForkTree;
 use durs_bc_db_reader::BcDbInReadTx;
 use durs_bc_db_writer::DbError;
+use durs_conf::{ForkBranchPriority, ForkResolutionConf};
 use std::collections::HashSet;
 
-/// Number of advance blocks required
-pub static ADVANCE_BLOCKS: &u32 = &3;
-/// Advance blockchain time required (in seconds)
-pub static ADVANCE_TIME: &u64 = &900;
+/// Depth beyond which the blocks of the local blockchain are final (never beyond the fork window)
+fn finality_depth(fork_window_size: usize, fork_resolution: &ForkResolutionConf) -> u32 {
+    let fork_window_size = fork_window_size as u32;
+    fork_resolution
+        .finality_depth
+        .map_or(fork_window_size, |depth| depth.min(fork_window_size))
+}
+
+/// Number of blocks to revert to switch from `current_blockstamp` to `branch`
+pub fn rollback_depth(current_blockstamp: Blockstamp, branch: &[Blockstamp]) -> u32 {
+    if let Some(first_block) = branch.first() {
+        (current_blockstamp.id.0 + 1).saturating_sub(first_block.id.0)
+    } else {
+        0
+    }
+}
 
 pub fn fork_resolution_algo<DB: BcDbInReadTx>(
     db: &DB,
     fork_tree: &ForkTree,
     fork_window_size: usize,
+    fork_resolution: &ForkResolutionConf,
     current_blockstamp: Blockstamp,
     invalid_blocks: &HashSet<Blockstamp>,
 ) -> Result<Option<Vec<Blockstamp>>, DbError> {
     let current_bc_time = durs_bc_db_reader::current_metadata::get_current_common_time_(db)?;
+    let finality_depth = finality_depth(fork_window_size, fork_resolution);
 
     debug!(
-        "fork_resolution_algo({}, {})",
-        fork_window_size, current_bc_time
+        "fork_resolution_algo({}, {}, {:?})",
+        fork_window_size, current_bc_time, fork_resolution
     );
 
-    let mut sheets = fork_tree.get_sheets();
-
-    sheets.sort_unstable_by(|s1, s2| s2.1.id.cmp(&s1.1.id));
-
-    for sheet in sheets {
-        if sheet.1 != current_blockstamp {
-            let branch = fork_tree.get_fork_branch(sheet.0);
-
-            if branch.is_empty() {
-                continue;
-            }
-
-            let branch_head_blockstamp = branch.last().expect("safe unwrap");
-            let branch_head_median_time =
-                durs_bc_db_reader::blocks::get_fork_block(db, *branch_head_blockstamp)?
-                    .unwrap_or_else(|| {
-                        panic!(
+    let mut eligible_branches = Vec::new();
+    for (sheet_id, sheet_blockstamp) in fork_tree.get_sheets() {
+        if sheet_blockstamp == current_blockstamp {
+            continue;
+        }
+        let branch = fork_tree.get_fork_branch(sheet_id);
+        let branch_head_blockstamp = if let Some(head) = branch.last() {
+            *head
+        } else {
+            continue;
+        };
+        let branch_head_median_time =
+            durs_bc_db_reader::blocks::get_fork_block(db, branch_head_blockstamp)?
+                .unwrap_or_else(|| {
+                    panic!(
                         "Db corrupted: fork block {} referenced in fork tree but not exist in db.",
                         branch_head_blockstamp
                     )
-                    })
-                    .block
-                    .common_time();
+                })
+                .block
+                .common_time();
 
-            if branch_head_blockstamp.id.0 >= current_blockstamp.id.0 + *ADVANCE_BLOCKS
-                && branch_head_median_time >= current_bc_time + *ADVANCE_TIME
-                && branch[0].id.0 + fork_window_size as u32 > current_blockstamp.id.0
-            {
-                debug!(
-                    "fork_resolution_algo() found eligible fork branch #{}:",
-                    branch_head_blockstamp
+        if branch_head_blockstamp.id.0 >= current_blockstamp.id.0 + fork_resolution.advance_blocks
+            && branch_head_median_time >= current_bc_time + fork_resolution.advance_time
+        {
+            let depth = rollback_depth(current_blockstamp, &branch);
+            if depth > finality_depth {
+                error!(
+                    "CRITICAL: refuse to switch to fork branch #{}: it requires to revert {} blocks, beyond the finality depth ({} blocks). It may be a long-range attack or a buggy peer.",
+                    branch_head_blockstamp, depth, finality_depth
                 );
-                let mut valid_branch = true;
-                for blockstamp in &branch {
-                    if invalid_blocks.contains(blockstamp) {
-                        valid_branch = false;
-                        break;
-                    }
-                }
-
-                if valid_branch {
-                    debug!(
-                        "fork_resolution_algo() found valid fork branch #{}:",
-                        branch_head_blockstamp
-                    );
-                    return Ok(Some(branch));
-                }
+                continue;
+            }
+            debug!(
+                "fork_resolution_algo() found eligible fork branch #{}:",
+                branch_head_blockstamp
+            );
+            if branch
+                .iter()
+                .any(|blockstamp| invalid_blocks.contains(blockstamp))
+            {
+                continue;
             }
+            debug!(
+                "fork_resolution_algo() found valid fork branch #{}:",
+                branch_head_blockstamp
+            );
+            eligible_branches.push(branch);
         }
     }
 
-    debug!("fork_resolution_algo() return Ok(None)");
-    Ok(None)
+    let head_number = |branch: &Vec<Blockstamp>| branch.last().map(|head| head.id);
+    let best_branch = match fork_resolution.priority {
+        ForkBranchPriority::Highest => eligible_branches
+            .into_iter()
+            .max_by_key(|branch| head_number(branch)),
+        ForkBranchPriority::ShallowestRollback => {
+            eligible_branches.into_iter().max_by_key(|branch| {
+                (
+                    std::cmp::Reverse(rollback_depth(current_blockstamp, branch)),
+                    head_number(branch),
+                )
+            })
+        }
+    };
+
+    debug!(
+        "fork_resolution_algo() return {:?}",
+        best_branch.as_ref().and_then(|branch| branch.last())
+    );
+    Ok(best_branch)
 }
 
 #[cfg(test)]
@@ -112,6 +145,10 @@ mod tests {
         // Get FORK_WINDOW_SIZE value
         let fork_window_size = *dubp_currency_params::constants::DEFAULT_FORK_WINDOW_SIZE;
 
+        // Default fork resolution rules
+        let fork_resolution = ForkResolutionConf::default();
+        let advance_time = fork_resolution.advance_time;
+
         // Begin with no invalid blocks
         let invalid_blocks: HashSet<Blockstamp> = HashSet::new();
 
@@ -161,7 +198,7 @@ mod tests {
                         id: BlockNumber(fork_point.number().0 + i + 1),
                         hash: BlockHash(dup_crypto_tests_tools::mocks::hash('A')),
                     },
-                    ADVANCE_TIME - 1,
+                    advance_time - 1,
                     if i == 0 {
                         fork_point.hash().expect("safe unwrap").0
                     } else {
@@ -182,6 +219,7 @@ mod tests {
                 &BcDbRwWithReader { db: &db, r },
                 &fork_tree,
                 fork_window_size,
+                &fork_resolution,
                 current_blockstamp,
                 &invalid_blocks
             ))?
@@ -203,7 +241,7 @@ mod tests {
                         block: BlockDocument::V10(
                             dubp_blocks_tests_tools::mocks::gen_empty_timed_block_v10(
                                 determining_blockstamp,
-                                *ADVANCE_TIME,
+                                advance_time,
                                 dup_crypto_tests_tools::mocks::hash('A'),
                             )
                         ),
@@ -226,6 +264,7 @@ mod tests {
                 &BcDbRwWithReader { db: &db, r },
                 &fork_tree,
                 fork_window_size,
+                &fork_resolution,
                 current_blockstamp,
                 &invalid_blocks
             ))?
@@ -240,7 +279,7 @@ mod tests {
                         id: BlockNumber(fork_point.number().0 + i + 1),
                         hash: BlockHash(dup_crypto_tests_tools::mocks::hash('B')),
                     },
-                    ADVANCE_TIME * 2,
+                    advance_time * 2,
                     if i == 0 {
                         fork_point.hash().expect("safe unwrap").0
                     } else {
@@ -251,6 +290,22 @@ mod tests {
             .collect();
         insert_fork_blocks(&db, &mut fork_tree, &new_main_blocks)?;
 
+        // Must not refork beyond the finality depth (4 blocks to revert)
+        assert_eq!(
+            None,
+            db.read(|r| fork_resolution_algo(
+                &BcDbRwWithReader { db: &db, r },
+                &fork_tree,
+                fork_window_size,
+                &ForkResolutionConf {
+                    finality_depth: Some(3),
+                    ..fork_resolution
+                },
+                current_blockstamp,
+                &invalid_blocks
+            ))?
+        );
+
         // Must refork
         assert_eq!(
             Some(new_main_blocks.iter().map(|b| b.blockstamp()).collect()),
@@ -258,6 +313,7 @@ mod tests {
                 &BcDbRwWithReader { db: &db, r },
                 &fork_tree,
                 fork_window_size,
+                &fork_resolution,
                 current_blockstamp,
                 &invalid_blocks
             ))?
@@ -267,6 +323,34 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_finality_depth() {
+        let mut fork_resolution = ForkResolutionConf::default();
+        assert_eq!(100, finality_depth(100, &fork_resolution));
+        fork_resolution.finality_depth = Some(10);
+        assert_eq!(10, finality_depth(100, &fork_resolution));
+        // The finality depth can not exceed the fork window
+        fork_resolution.finality_depth = Some(1_000);
+        assert_eq!(100, finality_depth(100, &fork_resolution));
+    }
+
+    #[test]
+    fn test_rollback_depth() {
+        let blockstamp = |number: u32| Blockstamp {
+            id: BlockNumber(number),
+            hash: BlockHash(dup_crypto_tests_tools::mocks::hash('A')),
+        };
+        assert_eq!(0, rollback_depth(blockstamp(10), &[]));
+        assert_eq!(0, rollback_depth(blockstamp(10), &[blockstamp(11)]));
+        assert_eq!(
+            3,
+            rollback_depth(
+                blockstamp(10),
+                &[blockstamp(8), blockstamp(9), blockstamp(10), blockstamp(11)]
+            )
+        );
+    }
+
     fn insert_fork_blocks(
         db: &Db,
         fork_tree: &mut ForkTree,
@@ -19,6 +19,7 @@ use crate::*;
 use dubp_common_doc::traits::Document;
 use dubp_common_doc::Blockstamp;
 use durs_common_tools::fatal_error;
+use std::time::Instant;
 use unwrap::unwrap;
 
 pub fn apply_rollback(bc: &mut BlockchainModule, new_bc_branch: Vec<Blockstamp>) {
@@ -32,6 +33,7 @@ pub fn apply_rollback(bc: &mut BlockchainModule, new_bc_branch: Vec<Blockstamp>)
     // Open write db transaction
     let db = bc.take_db();
     let mut new_branch_blocks = Vec::with_capacity(new_bc_branch.len());
+    let mut reverted_blocks = Vec::new();
     let db_tx_result = db.write(|mut w| {
         // Rollback (revert old branch)
         while bc.current_blockstamp.id.0 > last_common_block_number {
@@ -44,6 +46,7 @@ pub fn apply_rollback(bc: &mut BlockchainModule, new_bc_branch: Vec<Blockstamp>)
             }) {
                 let blockstamp = dal_block.block.blockstamp();
                 debug!("try to revert block #{}", blockstamp);
+                reverted_blocks.push(dal_block.block.clone());
                 let ValidBlockRevertReqs {
                     new_current_blockstamp,
                     block_query,
@@ -100,6 +103,7 @@ pub fn apply_rollback(bc: &mut BlockchainModule, new_bc_branch: Vec<Blockstamp>)
                             bc.current_blockstamp = *blockstamp;
 
                             // Apply db requests
+                            let now = Instant::now();
                             bc_db_query
                                 .apply(
                                     &db,
@@ -109,12 +113,21 @@ pub fn apply_rollback(bc: &mut BlockchainModule, new_bc_branch: Vec<Blockstamp>)
                                     None,
                                 )
                                 .expect("Fatal error : Fail to apply DBWriteRequest !");
+                            bc.apply_metrics
+                                .add(BlockApplyStage::BlockWrite, now.elapsed());
+                            let now = Instant::now();
                             for query in &wot_dbs_queries {
                                 query
                                     .apply(&db, &mut w, &blockstamp, &unwrap!(bc.currency_params))
                                     .expect("Fatal error : Fail to apply WotsDBsWriteRequest !");
                             }
+                            bc.apply_metrics
+                                .add(BlockApplyStage::WotIndexesWrite, now.elapsed());
+                            let now = Instant::now();
                             exec_currency_queries(&db, &mut w, blockstamp.id, tx_dbs_queries)?;
+                            bc.apply_metrics
+                                .add(BlockApplyStage::CurrencyIndexesWrite, now.elapsed());
+                            bc.apply_metrics.end_block(*blockstamp);
                         }
                         CheckAndApplyBlockReturn::ForkBlock
                         | CheckAndApplyBlockReturn::OrphanBlock => {
@@ -168,11 +181,21 @@ pub fn apply_rollback(bc: &mut BlockchainModule, new_bc_branch: Vec<Blockstamp>)
 
     match db_tx_result {
         Ok(()) => {
-            // Save db
-            bc.wot_databases.save_dbs();
-            bc.db()
-                .save()
-                .unwrap_or_else(|_| fatal_error!("DB corrupted, please reset data."));
+            // Send event RevertBlocks
+            info!(
+                "Blockchain: switch from {} to fork branch {} (rollback depth: {})",
+                old_current_blockstamp,
+                bc.current_blockstamp,
+                reverted_blocks.len()
+            );
+            events::sent::send_event(
+                bc,
+                &BlockchainEvent::RevertBlocks {
+                    rollback_depth: reverted_blocks.len() as u32,
+                    blocks: reverted_blocks,
+                    new_head: bc.current_blockstamp,
+                },
+            );
             // Send events stackUpValidBlock
             for db_block in new_branch_blocks {
                 events::sent::send_event(
@@ -20,9 +20,11 @@ use crate::*;
 use dubp_block_doc::block::BlockDocumentTrait;
 use dubp_common_doc::traits::Document;
 use durs_bc_db_reader::BcDbRead;
+use std::time::Instant;
 use unwrap::unwrap;
 
 pub fn apply_stackable_blocks(bc: &mut BlockchainModule) {
+    let mut write_begun = false;
     'blocks: loop {
         let stackable_blocks =
             bc.db()
@@ -34,12 +36,17 @@ pub fn apply_stackable_blocks(bc: &mut BlockchainModule) {
         if stackable_blocks.is_empty() {
             break 'blocks;
         }
+        if !write_begun {
+            bc.begin_write();
+            write_begun = true;
+        }
 
         for stackable_block in stackable_blocks {
             debug!("stackable_block({})", stackable_block.block.number());
 
             let stackable_block_number = stackable_block.block.number();
             let stackable_block_blockstamp = stackable_block.block.blockstamp();
+            bc.applying_write(stackable_block_blockstamp);
 
             // Apply db requests
             let db = bc.take_db();
@@ -53,6 +60,7 @@ pub fn apply_stackable_blocks(bc: &mut BlockchainModule) {
                         let new_current_block = bc_db_query.get_block_doc_copy();
                         let blockstamp = new_current_block.blockstamp();
 
+                        let now = Instant::now();
                         bc_db_query
                             .apply(
                                 &db,
@@ -62,13 +70,22 @@ pub fn apply_stackable_blocks(bc: &mut BlockchainModule) {
                                 None,
                             )
                             .expect("DB error : Fail to apply block query !");
+                        bc.apply_metrics
+                            .add(BlockApplyStage::BlockWrite, now.elapsed());
+                        let now = Instant::now();
                         for query in &wot_dbs_queries {
                             query
                                 .apply(&db, &mut w, &blockstamp, &unwrap!(bc.currency_params))
                                 .expect("DB error : Fail to apply wot queries !");
                         }
+                        bc.apply_metrics
+                            .add(BlockApplyStage::WotIndexesWrite, now.elapsed());
+                        let now = Instant::now();
                         exec_currency_queries(&db, &mut w, blockstamp.id, tx_dbs_queries)
                             .expect("DB error : Fail to apply currency queries !");
+                        bc.apply_metrics
+                            .add(BlockApplyStage::CurrencyIndexesWrite, now.elapsed());
+                        bc.apply_metrics.end_block(blockstamp);
                         durs_bc_db_writer::blocks::fork_tree::save_fork_tree(
                             &db,
                             &mut w,
@@ -122,9 +139,11 @@ pub fn apply_stackable_blocks(bc: &mut BlockchainModule) {
         // If we reach this point, it is that none of the stackable blocks are valid
         break 'blocks;
     }
-    // Save database
-    bc.db()
-        .save()
-        .unwrap_or_else(|_| fatal_error!("DB corrupted, please reset data."));
-    bc.wot_databases.save_dbs();
+    // Save databases
+    if write_begun {
+        bc.end_write(DirtyStores {
+            blockchain: true,
+            wot: true,
+        });
+    }
 }
@@ -0,0 +1,257 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Sub-module generating the next blocks with the member key of the node.
+
+mod assembly;
+mod prover;
+
+use self::prover::Prover;
+use crate::dubp::calculators::CalculatorsError;
+use crate::*;
+use dubp_common_doc::BlockNumber;
+use dubp_user_docs::documents::UserDocumentDUBP;
+use std::mem;
+use threadpool::ThreadPool;
+
+/// Error when generating a block
+#[derive(Debug)]
+pub enum GenerationError {
+    /// Database error
+    DbError(DbError),
+    /// A block needed to compute the next block is missing
+    MissingBlock(BlockNumber),
+    /// Fail to create a signator with the member keypair
+    SignError(SignError),
+    /// The currency parameters are not yet known
+    UnknownCurrencyParams,
+}
+
+impl From<DbError> for GenerationError {
+    fn from(e: DbError) -> Self {
+        GenerationError::DbError(e)
+    }
+}
+
+impl From<CalculatorsError> for GenerationError {
+    fn from(e: CalculatorsError) -> Self {
+        match e {
+            CalculatorsError::DbError(e) => GenerationError::DbError(e),
+            CalculatorsError::MissingBlock(number) => GenerationError::MissingBlock(number),
+        }
+    }
+}
+
+impl From<SignError> for GenerationError {
+    fn from(e: SignError) -> Self {
+        GenerationError::SignError(e)
+    }
+}
+
+/// Step of the generation of the next block
+enum GenerationStep {
+    /// Wait for a new current block
+    Idle,
+    /// Wait for the mempool documents
+    WaitDocuments {
+        /// Blockstamp of the block on top of which the block is generated
+        previous: Blockstamp,
+        /// Pending mempool requests
+        pending_requests: HashSet<ModuleReqId>,
+        /// Received documents
+        documents: Vec<UserDocumentDUBP>,
+        /// Time of the mempool requests
+        since: SystemTime,
+    },
+    /// Prove the assembled block
+    Proving(Prover),
+}
+
+/// Block generator (BlockGeneration role)
+pub struct BlockGenerator {
+    /// Member keypair
+    keypair: KeyPairEnum,
+    /// Proof of work workers
+    pool: ThreadPool,
+    /// Current step
+    step: GenerationStep,
+    /// Blockstamp on top of which the last block was generated
+    last_previous: Option<Blockstamp>,
+    /// Identifier of the next mempool request
+    next_req_id: u32,
+}
+
+impl BlockGenerator {
+    /// Create a block generator issuing blocks with the member keypair
+    pub fn new(keypair: KeyPairEnum) -> BlockGenerator {
+        let workers_count = num_cpus::get().min(*MAX_PROVER_WORKERS).max(1);
+        BlockGenerator {
+            keypair,
+            pool: ThreadPool::with_name("prover".to_owned(), workers_count),
+            step: GenerationStep::Idle,
+            last_previous: None,
+            next_req_id: 0,
+        }
+    }
+    /// Advance the generation of the block following the current block
+    fn advance(&mut self, bc: &mut BlockchainModule, now: SystemTime) {
+        let current = bc.current_blockstamp;
+        self.step = match mem::replace(&mut self.step, GenerationStep::Idle) {
+            GenerationStep::Idle => {
+                if bc.currency_params.is_none()
+                    || current == Blockstamp::default()
+                    || self.last_previous == Some(current)
+                {
+                    GenerationStep::Idle
+                } else {
+                    self.request_documents(bc, current, now)
+                }
+            }
+            GenerationStep::WaitDocuments {
+                previous,
+                pending_requests,
+                documents,
+                since,
+            } => {
+                if previous != current {
+                    GenerationStep::Idle
+                } else if pending_requests.is_empty()
+                    || now.duration_since(since).expect("duration_since error")
+                        > Duration::from_secs(*MEMPOOL_DOCUMENTS_TIMEOUT_IN_SECS)
+                {
+                    self.last_previous = Some(previous);
+                    match self.start_proving(bc, now, documents) {
+                        Ok(prover) => GenerationStep::Proving(prover),
+                        Err(e) => {
+                            error!("Fail to generate the block following {}: {:?}", previous, e);
+                            GenerationStep::Idle
+                        }
+                    }
+                } else {
+                    GenerationStep::WaitDocuments {
+                        previous,
+                        pending_requests,
+                        documents,
+                        since,
+                    }
+                }
+            }
+            GenerationStep::Proving(prover) => {
+                if prover.previous != current {
+                    debug!("Current block changed, abort proof of work.");
+                    GenerationStep::Idle
+                } else if let Some(block) = prover.try_recv() {
+                    info!("Generated block #{}.", block.number);
+                    receive_self_block(bc, BlockDocument::V10(block));
+                    GenerationStep::Idle
+                } else {
+                    GenerationStep::Proving(prover)
+                }
+            }
+        };
+    }
+    /// Request to the mempools the documents to include in the next block
+    fn request_documents(
+        &mut self,
+        bc: &BlockchainModule,
+        previous: Blockstamp,
+        now: SystemTime,
+    ) -> GenerationStep {
+        let req_id = ModuleReqId(self.next_req_id);
+        self.next_req_id = self.next_req_id.wrapping_add(1);
+        if bc
+            .router_sender
+            .send(RouterThreadMessage::ModuleMessage(DursMsg::Request {
+                req_from: BlockchainModule::name(),
+                // The blockchain module holds the CurrencyPool role too,
+                // so the response contains the pending transactions
+                req_to: ModuleRole::WotPool,
+                req_id,
+                req_content: DursReqContent::MemPoolRequest(MemPoolRequest::DocumentsForNextBlock(
+                    previous,
+                )),
+            }))
+            .is_err()
+        {
+            debug!("Fail to send MemPoolRequest to router");
+        }
+        let mut pending_requests = HashSet::new();
+        pending_requests.insert(req_id);
+        GenerationStep::WaitDocuments {
+            previous,
+            pending_requests,
+            documents: Vec::new(),
+            since: now,
+        }
+    }
+    /// Assemble the next block and start its proof of work
+    fn start_proving(
+        &self,
+        bc: &BlockchainModule,
+        now: SystemTime,
+        documents: Vec<UserDocumentDUBP>,
+    ) -> Result<Prover, GenerationError> {
+        let now = now
+            .duration_since(UNIX_EPOCH)
+            .expect("duration_since error")
+            .as_secs();
+        let (block, difficulty) =
+            assembly::assemble_next_block(bc, self.keypair.public_key(), now, documents)?;
+        info!(
+            "Start proof of work of block #{} with difficulty {}.",
+            block.number, difficulty
+        );
+        Ok(Prover::start(&self.pool, &self.keypair, block, difficulty)?)
+    }
+}
+
+/// Advance the generation of the next block (if the node generates blocks)
+pub fn generate(bc: &mut BlockchainModule, now: SystemTime) {
+    if let Some(mut generator) = bc.block_generator.take() {
+        generator.advance(bc, now);
+        bc.block_generator = Some(generator);
+    }
+}
+
+/// Receive mempool documents requested for the next block
+pub fn receive_documents(
+    bc: &mut BlockchainModule,
+    req_id: ModuleReqId,
+    received_documents: Vec<UserDocumentDUBP>,
+) {
+    if let Some(ref mut generator) = bc.block_generator {
+        if let GenerationStep::WaitDocuments {
+            ref mut pending_requests,
+            ref mut documents,
+            ..
+        } = generator.step
+        {
+            if pending_requests.remove(&req_id) {
+                documents.extend(received_documents);
+            }
+        }
+    }
+}
+
+/// Submit a block issued by the local node to the normal validation path
+pub fn receive_self_block(bc: &mut BlockchainModule, block: BlockDocument) {
+    let blockstamp = block.blockstamp();
+    dunp::receiver::receive_blocks(bc, vec![block.clone()]);
+    if bc.current_blockstamp == blockstamp {
+        events::sent::send_block_from_self_event(bc, block);
+    } else {
+        warn!("Self generated block {} refused.", blockstamp);
+    }
+}
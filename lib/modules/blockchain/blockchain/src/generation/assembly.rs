@@ -0,0 +1,179 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Assemble the next block from the local blockchain and the mempool documents.
+
+use super::GenerationError;
+use crate::dubp::calculators::{self, CalculatedFields, CalculatorsDatas};
+use crate::dubp::check::check_cert_admission;
+use crate::BlockchainModule;
+use dubp_block_doc::block::{BlockDocumentTrait, BlockDocumentV10};
+use dubp_common_doc::traits::text::TextDocumentFormat;
+use dubp_common_doc::traits::Document;
+use dubp_common_doc::BlockNumber;
+use dubp_currency_params::CurrencyParameters;
+use dubp_user_docs::documents::certification::CertificationDocument;
+use dubp_user_docs::documents::identity::IdentityDocument;
+use dubp_user_docs::documents::membership::v10::MembershipType;
+use dubp_user_docs::documents::membership::MembershipDocument;
+use dubp_user_docs::documents::revocation::RevocationDocument;
+use dubp_user_docs::documents::transaction::TransactionDocument;
+use dubp_user_docs::documents::UserDocumentDUBP;
+use dup_crypto::keys::*;
+use durs_bc_db_reader::BcDbRead;
+use durs_common_tools::UsizeSer32;
+use durs_wot::WebOfTrust;
+use std::collections::HashSet;
+
+/// Check if `pubkey` is the key of a current member
+fn is_member(bc: &BlockchainModule, pubkey: &PubKey) -> bool {
+    if let Some(wot_id) = bc.wot_index.get(pubkey) {
+        bc.wot_databases
+            .wot_db
+            .read(|wot| wot.is_enabled(*wot_id))
+            .expect("Fail to read WotDB")
+            == Some(true)
+    } else {
+        false
+    }
+}
+
+/// Assemble the block following the current block, returns the block (without proof of work)
+/// and the personalized difficulty of the issuer.
+pub fn assemble_next_block(
+    bc: &BlockchainModule,
+    issuer: PubKey,
+    now: u64,
+    documents: Vec<UserDocumentDUBP>,
+) -> Result<(BlockDocumentV10, usize), GenerationError> {
+    let currency_params = bc
+        .currency_params
+        .ok_or(GenerationError::UnknownCurrencyParams)?;
+    let previous_number = bc.current_blockstamp.id;
+    let number = BlockNumber(previous_number.0 + 1);
+    let db = bc.db();
+
+    // Read previous blocks
+    let calculators_datas =
+        db.r(|db_r| Ok(CalculatorsDatas::read(db_r, number, &currency_params)))??;
+    let previous = &calculators_datas.previous;
+
+    // Times and difficulty
+    let CalculatedFields {
+        median_time,
+        issuers_frame,
+        pow_min,
+        personal_difficulty,
+        dividend,
+        unit_base,
+    } = calculators_datas.calculated_fields(&issuer, &currency_params);
+    let time = now
+        .max(median_time)
+        .min(median_time + calculators::max_acceleration(&currency_params));
+
+    // Sort documents
+    let mut identities = Vec::new();
+    let mut memberships = Vec::new();
+    let mut certifications = Vec::new();
+    let mut revoked = Vec::new();
+    let mut transactions = Vec::new();
+    let mut certifiers = HashSet::new();
+    for document in documents {
+        match document {
+            UserDocumentDUBP::Identity(IdentityDocument::V10(idty)) => identities.push(idty),
+            UserDocumentDUBP::Membership(MembershipDocument::V10(ms)) => memberships.push(ms),
+            UserDocumentDUBP::Certification(cert) => {
+                let CertificationDocument::V10(cert) = *cert;
+                let issuer = cert.issuers()[0];
+                // At most one certification per issuer, and only if its quota allows it
+                if certifiers.insert(issuer) && check_cert_admission(bc, &issuer).is_ok() {
+                    certifications.push(TextDocumentFormat::Complete(cert));
+                }
+            }
+            UserDocumentDUBP::Revocation(revocation) => {
+                let RevocationDocument::V10(revocation) = *revocation;
+                revoked.push(TextDocumentFormat::Complete(revocation));
+            }
+            UserDocumentDUBP::Transaction(tx) => {
+                let TransactionDocument::V10(tx) = *tx;
+                transactions.push(tx);
+            }
+        }
+    }
+    let newcomers: HashSet<PubKey> = identities.iter().map(|idty| idty.issuers()[0]).collect();
+    let mut joiners = Vec::new();
+    let mut actives = Vec::new();
+    let mut leavers = Vec::new();
+    for ms in memberships {
+        let ms_issuer = ms.issuers()[0];
+        match ms.membership() {
+            MembershipType::In() => {
+                if is_member(bc, &ms_issuer) {
+                    actives.push(ms);
+                } else if newcomers.contains(&ms_issuer) || bc.wot_index.contains_key(&ms_issuer) {
+                    joiners.push(ms);
+                }
+            }
+            MembershipType::Out() => {
+                if is_member(bc, &ms_issuer) {
+                    leavers.push(ms);
+                }
+            }
+        }
+    }
+
+    // Monetary datas
+    // TODO: compute the members to exclude
+    let members_count = usize::from(previous.members_count) + joiners.len();
+    let monetary_mass = previous.monetary_mass
+        + dividend.map_or(0, |dividend| {
+            dividend as u64 * 10u64.pow(unit_base as u32) * members_count as u64
+        });
+
+    let mut block = BlockDocumentV10 {
+        version: previous.version,
+        nonce: 0,
+        number,
+        pow_min: UsizeSer32(pow_min),
+        time,
+        median_time,
+        members_count: UsizeSer32(members_count),
+        monetary_mass,
+        unit_base: UsizeSer32(unit_base),
+        issuers_count: UsizeSer32(issuers_frame.issuers_count),
+        issuers_frame: UsizeSer32(issuers_frame.issuers_frame),
+        issuers_frame_var: issuers_frame.issuers_frame_var,
+        currency: previous.currency.clone(),
+        issuers: vec![issuer],
+        signatures: vec![],
+        hash: None,
+        parameters: None,
+        previous_hash: Some(bc.current_blockstamp.hash.0),
+        previous_issuer: previous.issuers.first().cloned(),
+        inner_hash: None,
+        dividend: dividend.map(UsizeSer32),
+        identities,
+        joiners,
+        actives,
+        leavers,
+        revoked,
+        excluded: vec![],
+        certifications,
+        transactions,
+    };
+    block.generate_inner_hash();
+
+    Ok((block, personal_difficulty))
+}
@@ -0,0 +1,147 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Proof of work of the generated blocks, computed by a pool of worker threads.
+
+use crate::dubp::check::pow;
+use dubp_block_doc::block::{BlockDocumentTrait, BlockDocumentV10};
+use dubp_common_doc::Blockstamp;
+use dup_crypto::keys::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use threadpool::ThreadPool;
+
+/// Proof of work in progress
+pub struct Prover {
+    /// Blockstamp of the block on top of which the block is proved
+    pub previous: Blockstamp,
+    stop: Arc<AtomicBool>,
+    receiver: Receiver<BlockDocumentV10>,
+}
+
+impl Prover {
+    /// Start proving `block` with the given difficulty on all workers of the pool
+    pub fn start(
+        pool: &ThreadPool,
+        keypair: &KeyPairEnum,
+        block: BlockDocumentV10,
+        difficulty: usize,
+    ) -> Result<Prover, SignError> {
+        let previous = block.previous_blockstamp();
+        let stop = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = mpsc::channel();
+        let workers_count = pool.max_count() as u64;
+
+        for worker_index in 0..workers_count {
+            let signator = keypair.generate_signator()?;
+            let block = block.clone();
+            let stop = stop.clone();
+            let sender = sender.clone();
+            pool.execute(move || {
+                prove(
+                    block,
+                    &signator,
+                    difficulty,
+                    (worker_index, workers_count),
+                    &stop,
+                    &sender,
+                )
+            });
+        }
+
+        Ok(Prover {
+            previous,
+            stop,
+            receiver,
+        })
+    }
+    /// Get the proved block, if found
+    pub fn try_recv(&self) -> Option<BlockDocumentV10> {
+        self.receiver.try_recv().ok()
+    }
+    /// Stop all workers
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Drop for Prover {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Try the nonces `first_nonce + k * nonce_step` until finding one that satisfies the difficulty
+fn prove(
+    mut block: BlockDocumentV10,
+    signator: &SignatorEnum,
+    difficulty: usize,
+    (first_nonce, nonce_step): (u64, u64),
+    stop: &AtomicBool,
+    sender: &Sender<BlockDocumentV10>,
+) {
+    block.nonce = first_nonce;
+    while !stop.load(Ordering::Relaxed) {
+        block.sign(signator);
+        let hash = block.compute_hash();
+        if pow::verify_hash_pattern(hash.0, difficulty).is_ok() {
+            stop.store(true, Ordering::Relaxed);
+            block.hash = Some(hash);
+            if sender.send(block).is_err() {
+                debug!("Prover: the proved block is no longer expected.");
+            }
+            return;
+        }
+        block.nonce += nonce_step;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use dubp_common_doc::BlockHash;
+    use std::time::Duration;
+
+    #[test]
+    fn prove_block() {
+        let keypair = KeyPairEnum::Ed25519(ed25519::KeyPairFromSeed32Generator::generate(
+            Seed32::new([1u8; 32]),
+        ));
+        let mut block = dubp_blocks_tests_tools::mocks::gen_empty_timed_block_v10(
+            Blockstamp {
+                id: dubp_common_doc::BlockNumber(1),
+                hash: BlockHash(dup_crypto::hashs::Hash::default()),
+            },
+            0,
+            dup_crypto::hashs::Hash::default(),
+        );
+        block.issuers = vec![keypair.public_key()];
+        block.previous_issuer = Some(keypair.public_key());
+        block.generate_inner_hash();
+
+        let pool = ThreadPool::new(2);
+        let prover = Prover::start(&pool, &keypair, block, 4).expect("fail to start prover");
+        let proved_block = prover
+            .receiver
+            .recv_timeout(Duration::from_secs(10))
+            .expect("no proved block");
+
+        let hash = proved_block.hash.expect("proved block without hash");
+        assert_eq!(proved_block.compute_hash(), hash);
+        assert!(pow::verify_hash_pattern(hash.0, 4).is_ok());
+    }
+}
@@ -34,18 +34,30 @@
 #[macro_use]
 extern crate log;
 
+pub mod audit;
+pub mod backup;
+pub mod compaction;
 mod constants;
 pub mod dbex;
 mod dubp;
 mod dunp;
 mod events;
 mod fork;
+mod generation;
+mod mempool;
+mod metrics;
+mod recovery;
+pub mod reindex;
 mod requests;
+pub mod reserve_proof;
 mod responses;
+pub mod revert;
+pub mod snapshot;
 mod sync;
+mod wot_mempool;
 
 use std::collections::{HashMap, HashSet};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str;
 use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
@@ -57,13 +69,16 @@ use crate::dubp::*;
 use crate::fork::*;
 use dubp_block_doc::BlockDocument;
 use dubp_common_doc::traits::Document;
-use dubp_common_doc::Blockstamp;
+use dubp_common_doc::{BlockNumber, Blockstamp};
+use dubp_currency_params::feature_flags::FeatureFlags;
 use dubp_currency_params::{CurrencyName, CurrencyParameters};
 use dup_crypto::keys::*;
 use durs_bc_db_reader::blocks::fork_tree::ForkTree;
 use durs_bc_db_reader::BcDbRead;
+use durs_bc_db_writer::autosave::{AutoSaver, DirtyStores};
 use durs_bc_db_writer::*;
 use durs_common_tools::fatal_error;
+use durs_conf::{AutoSaveConf, BlocksRequestsConf, ForkResolutionConf, SandboxesConf, StorageMode};
 use durs_message::events::*;
 use durs_message::requests::*;
 use durs_message::responses::*;
@@ -79,12 +94,8 @@ use durs_wot::operations::distance::RustyDistanceCalculator;
 use durs_wot::WotId;
 use failure::Error;
 
-/// The blocks are requested by packet groups. This constant sets the block packet size.
-pub static CHUNK_SIZE: &u32 = &50;
 /// Necessary to instantiate the wot object before knowing the currency parameters
 pub static INFINITE_SIG_STOCK: &usize = &4_000_000_000;
-/// The blocks are requested by packet groups. This constant sets the number of packets per group.
-pub static MAX_BLOCKS_REQUEST: &u32 = &500;
 /// The distance calculator
 pub static DISTANCE_CALCULATOR: &RustyDistanceCalculator = &RustyDistanceCalculator {};
 
@@ -116,12 +127,30 @@ pub struct BlockchainModule {
     pub pending_block: Option<Box<BlockDocument>>,
     /// Memorization of fork whose application fails
     pub invalid_forks: HashSet<Blockstamp>,
+    /// Fork resolution rules
+    pub fork_resolution: ForkResolutionConf,
+    /// Requests of blocks to the other nodes
+    pub blocks_requests: BlocksRequestsConf,
+    /// Protocol experiments activated on the currency
+    pub feature_flags: FeatureFlags,
     /// pending network requests
     pub pending_network_requests: HashMap<ModuleReqId, OldNetworkRequest>,
     /// Last request blocks
     pub last_request_blocks: SystemTime,
     /// Last request fork blocks (=all blocks in fork window size)
     last_request_fork_blocks: SystemTime,
+    /// Block generator (only if the node has a member keypair)
+    block_generator: Option<generation::BlockGenerator>,
+    /// Pending transactions
+    tx_mempool: mempool::TxMemPool,
+    /// Pending identities, memberships and certifications
+    wot_mempool: wot_mempool::WotMemPool,
+    /// Time spent applying the last blocks
+    apply_metrics: metrics::ApplyMetrics,
+    /// Recent history of the key metrics
+    metrics_history: metrics_history::MetricsHistory,
+    /// Background saver of the databases (the databases are saved after each write without it)
+    autosaver: Option<AutoSaver>,
 }
 
 #[derive(Debug, Clone)]
@@ -197,6 +226,9 @@ impl BlockchainModule {
         let wot_index: HashMap<PubKey, WotId> =
             db.r(|db_r| durs_bc_db_reader::indexes::identities::get_wot_index(db_r))?;
 
+        let metrics_history =
+            metrics_history::MetricsHistory::new(&durs_conf::get_datas_path(profile_path.clone()));
+
         Ok(BlockchainModule {
             cautious_mode,
             router_sender,
@@ -211,9 +243,18 @@ impl BlockchainModule {
             wot_databases,
             pending_block: None,
             invalid_forks: HashSet::new(),
+            fork_resolution: ForkResolutionConf::default(),
+            blocks_requests: BlocksRequestsConf::default(),
+            feature_flags: FeatureFlags::default(),
             pending_network_requests: HashMap::new(),
             last_request_blocks: UNIX_EPOCH,
             last_request_fork_blocks: UNIX_EPOCH,
+            block_generator: None,
+            tx_mempool: mempool::TxMemPool::default(),
+            wot_mempool: wot_mempool::WotMemPool::default(),
+            apply_metrics: metrics::ApplyMetrics::default(),
+            metrics_history,
+            autosaver: None,
         })
     }
     /// Return module identifier
@@ -221,18 +262,40 @@ impl BlockchainModule {
         ModuleStaticName(MODULE_NAME)
     }
     /// Loading blockchain configuration
+    #[allow(clippy::too_many_arguments)]
     pub fn load_blockchain_conf(
         db: Db,
         router_sender: Sender<RouterThreadMessage<DursMsg>>,
         profile_path: PathBuf,
-        _keys: RequiredKeysContent,
+        keys: RequiredKeysContent,
         cautious_mode: bool,
+        fork_resolution: ForkResolutionConf,
+        blocks_requests: BlocksRequestsConf,
+        sandboxes: SandboxesConf,
+        feature_flags: FeatureFlags,
+        autosave: AutoSaveConf,
     ) -> BlockchainModule {
         // Get db path
         let dbs_path = durs_conf::get_blockchain_db_path(profile_path.clone());
 
+        // Recover databases if the previous run did not stop cleanly or a write was interrupted
+        if write_journal::WriteJournal::open(Some(&dbs_path)).needs_recovery() {
+            println!("Unclean shutdown detected, recovering databases...");
+            match recovery::recover(&db, &dbs_path) {
+                Ok(summary) => {
+                    println!("{}", summary);
+                    info!("{}", summary);
+                }
+                Err(e) => fatal_error!("Fail to recover databases: {}", e),
+            }
+        }
+
         // Open wot
         let wot_databases = WotsV10DBs::open(Some(&dbs_path));
+        wot_databases
+            .write_journal
+            .set_running_flag()
+            .unwrap_or_else(|e| fatal_error!("Fail to create running flag: {}", e));
 
         // Get currency parameters
         let (currency_name, currency_params) = if let Some((currency_name, currency_params)) =
@@ -247,7 +310,7 @@ impl BlockchainModule {
         };
 
         // Instanciate BlockchainModule
-        BlockchainModule::new(
+        let mut bc = BlockchainModule::new(
             cautious_mode,
             router_sender,
             profile_path,
@@ -256,12 +319,150 @@ impl BlockchainModule {
             db,
             wot_databases,
         )
-        .unwrap_or_else(|e| fatal_error!("Fail to instantiate BlockchainModule: {:?}", e))
+        .unwrap_or_else(|e| fatal_error!("Fail to instantiate BlockchainModule: {:?}", e));
+        bc.fork_resolution = fork_resolution;
+        bc.blocks_requests = blocks_requests;
+        bc.tx_mempool = mempool::TxMemPool::new(sandboxes);
+        bc.wot_mempool = wot_mempool::WotMemPool::new(sandboxes);
+        bc.feature_flags = feature_flags;
+        bc.autosaver = Some(
+            AutoSaver::start(&dbs_path, autosave)
+                .unwrap_or_else(|e| fatal_error!("Fail to start databases saver: {}", e)),
+        );
+
+        // Generate blocks with the member keypair
+        if let RequiredKeysContent::MemberKeyPair(Some(member_keypair)) = keys {
+            bc.block_generator = Some(generation::BlockGenerator::new(member_keypair));
+        }
+
+        bc
+    }
+    /// Write blockchain storage mode in database
+    pub fn set_storage_mode(db: &Db, storage_mode: StorageMode) -> Result<(), DbError> {
+        let light_storage = storage_mode == StorageMode::Light;
+        db.write(|mut w| {
+            let was_light_storage = current_metadata::set_light_storage(db, &mut w, light_storage)?;
+            if was_light_storage && !light_storage {
+                warn!("Leave light storage mode: old blocks remain without their transactions.");
+            }
+            Ok(WriteResp::from(w))
+        })
+    }
+    /// Enable or disable the transactions search index in database
+    pub fn set_tx_search_index(db: &Db, tx_search_index: bool) -> Result<(), DbError> {
+        db.write(|mut w| {
+            let was_enabled = current_metadata::set_tx_search_index(db, &mut w, tx_search_index)?;
+            if tx_search_index && !was_enabled {
+                info!("Enable transactions search index: only the next applied transactions will be indexed.");
+            }
+            Ok(WriteResp::from(w))
+        })
     }
     /// Databases explorer
     pub fn dbex(profile_path: PathBuf, csv: bool, req: &DbExQuery) {
         dbex::dbex(profile_path, csv, req);
     }
+    /// Revert the local blockchain to the target block
+    pub fn revert_to(
+        db: &Db,
+        profile_path: PathBuf,
+        target: BlockNumber,
+    ) -> Result<revert::RevertSummary, revert::RevertError> {
+        let dbs_path = durs_conf::get_blockchain_db_path(profile_path.clone());
+        let (_currency_name, currency_params) =
+            dubp_currency_params::db::get_currency_params(durs_conf::get_datas_path(profile_path))
+                .map_err(revert::RevertError::CurrencyParamsDbError)?
+                .ok_or(revert::RevertError::EmptyBlockchain)?;
+        revert::revert_to(db, &dbs_path, currency_params, target)
+    }
+    /// Audit the consistency of the blockchain database
+    pub fn audit_db(db: &Db) -> Result<Option<audit::AuditReport>, DbError> {
+        audit::audit_db(db)
+    }
+    /// Copy the blockchain database into the `backup_path` folder, the node can be running
+    pub fn backup_db(
+        db: &Db,
+        backup_path: &Path,
+    ) -> Result<backup::BackupSummary, backup::BackupError> {
+        backup::backup_db(db, backup_path)
+    }
+    /// Replace the blockchain database by a backup (the node must be stopped)
+    pub fn restore_db(
+        profile_path: PathBuf,
+        backup_path: &Path,
+    ) -> Result<backup::BackupSummary, backup::BackupError> {
+        backup::restore_db(
+            &durs_conf::get_blockchain_db_path(profile_path),
+            backup_path,
+        )
+    }
+    /// Migrate the blockchain database to the current version of its schema (the node must be stopped)
+    pub fn migrate_db(
+        db: &mut Db,
+        profile_path: PathBuf,
+        dry_run: bool,
+    ) -> Result<Vec<Migration>, DbError> {
+        durs_bc_db_writer::migrations::migrate_db(
+            db,
+            &durs_conf::get_blockchain_db_path(profile_path),
+            dry_run,
+        )
+    }
+    /// Compact the blockchain database
+    pub fn compact_db(
+        profile_path: PathBuf,
+    ) -> Result<compaction::CompactionSummary, compaction::CompactionError> {
+        compaction::compact_db(&durs_conf::get_blockchain_db_path(profile_path))
+    }
+    /// Rebuild a single derived index from the main blocks of the local blockchain
+    pub fn reindex(
+        db: &Db,
+        store: reindex::ReindexedStore,
+    ) -> Result<reindex::ReindexSummary, reindex::ReindexError> {
+        reindex::reindex(db, store)
+    }
+    /// Export a snapshot of the chain state of the local blockchain
+    pub fn export_snapshot(
+        db: &Db,
+        profile_path: PathBuf,
+        snapshot_path: &Path,
+    ) -> Result<snapshot::SnapshotSummary, snapshot::SnapshotError> {
+        snapshot::export_snapshot(
+            db,
+            &durs_conf::get_blockchain_db_path(profile_path.clone()),
+            durs_conf::get_datas_path(profile_path),
+            snapshot_path,
+        )
+    }
+    /// Import a snapshot of the chain state into an empty local blockchain
+    pub fn import_snapshot(
+        db: &Db,
+        profile_path: PathBuf,
+        snapshot_path: &Path,
+    ) -> Result<snapshot::SnapshotSummary, snapshot::SnapshotError> {
+        snapshot::import_snapshot(
+            db,
+            &durs_conf::get_blockchain_db_path(profile_path.clone()),
+            durs_conf::get_datas_path(profile_path),
+            snapshot_path,
+        )
+    }
+    /// Create the statement of the balances of `pubkeys` at the current block, signed with the member keypair
+    pub fn create_reserve_statement(
+        db: &Db,
+        member_keypair: &KeyPairEnum,
+        pubkeys: &[PubKey],
+        blockstamp: Option<Blockstamp>,
+    ) -> Result<reserve_proof::ReserveStatement, reserve_proof::ReserveProofError> {
+        reserve_proof::create_statement(db, member_keypair, pubkeys, blockstamp)
+    }
+    /// Verify a statement of balances against the local blockchain
+    pub fn verify_reserve_statement(
+        db: &Db,
+        statement: &reserve_proof::ReserveStatement,
+    ) -> Result<(), reserve_proof::ReserveProofError> {
+        reserve_proof::verify_statement(db, statement)
+    }
     /// Synchronize blockchain from local duniter json files
     pub fn local_sync<DC: DursConfTrait>(
         conf: &DC,
@@ -289,12 +490,26 @@ impl BlockchainModule {
             events::sent::send_event(self, &BlockchainEvent::CurrencyParameters(currency_params));
         }
 
-        if let Some(_sync_opts) = sync_opts {
-            // TODO ...
+        if let Some(sync_opts) = sync_opts {
+            // Apply the blocks downloaded by the sync network module
+            if let Err(err) = sync::network_sync(self, blockchain_receiver, sync_opts) {
+                println!("Fail to sync: {}", err);
+                error!("Fail to sync: {}", err);
+            }
         } else {
             // Start main loop
             self.main_loop(blockchain_receiver);
         }
+
+        // Clean shutdown
+        if let Some(ref mut autosaver) = self.autosaver {
+            if let Err(e) = autosaver.flush(&mut self.wot_databases, self.current_blockstamp) {
+                fatal_error!("Fail to save databases: {}", e);
+            }
+        }
+        if let Err(e) = self.wot_databases.write_journal.clear_running_flag() {
+            error!("Fail to remove running flag: {}", e);
+        }
     }
     /// Take blockchain database
     #[inline]
@@ -312,7 +527,74 @@ impl BlockchainModule {
             fatal_error!("Dev error: none bc db.")
         }
     }
+    /// Record the start of a write in databases
+    fn begin_write(&mut self) {
+        if let Some(ref mut autosaver) = self.autosaver {
+            autosaver.begin_write(
+                &mut self.wot_databases.write_journal,
+                self.current_blockstamp,
+            )
+        } else {
+            self.wot_databases
+                .write_journal
+                .begin(self.current_blockstamp)
+        }
+        .unwrap_or_else(|e| fatal_error!("Fail to write in write journal: {}", e));
+    }
+    /// Record the block being applied by the pending write
+    fn applying_write(&mut self, blockstamp: Blockstamp) {
+        self.wot_databases
+            .write_journal
+            .applying(blockstamp)
+            .unwrap_or_else(|e| fatal_error!("Fail to write in write journal: {}", e));
+    }
+    /// End a write in databases: the changed stores are saved by the autosaver, or saved
+    /// immediately without it
+    fn end_write(&mut self, dirty_stores: DirtyStores) {
+        if let Some(ref mut autosaver) = self.autosaver {
+            autosaver.end_write(dirty_stores);
+            return;
+        }
+        if dirty_stores.blockchain {
+            self.db()
+                .save()
+                .unwrap_or_else(|_| fatal_error!("DB corrupted, please reset data."));
+        }
+        if dirty_stores.wot {
+            self.wot_databases.save_dbs();
+        }
+        self.wot_databases
+            .write_journal
+            .commit(self.current_blockstamp)
+            .unwrap_or_else(|e| fatal_error!("Fail to write in write journal: {}", e));
+    }
+    /// Send the changed stores to the autosaver if their save is due
+    fn autosave_if_due(&mut self) {
+        if let Some(ref mut autosaver) = self.autosaver {
+            if let Err(e) = autosaver.save_if_due(&self.wot_databases, self.current_blockstamp) {
+                fatal_error!("Fail to save databases: {}", e);
+            }
+        }
+    }
 
+    /// Record the key metrics of the blockchain in the metrics history
+    fn record_metrics_history(&self) {
+        let now = durs_common_tools::fns::time::current_timestamp();
+        for (metric, value) in &[
+            (
+                "current_block_number",
+                u64::from(self.current_blockstamp.id.0),
+            ),
+            (
+                "pending_transactions",
+                self.tx_mempool.pending_txs().len() as u64,
+            ),
+        ] {
+            if let Err(err) = self.metrics_history.record(metric, now, *value) {
+                error!("Fail to write metrics history : {}", err);
+            }
+        }
+    }
     /// Start blockchain main loop
     pub fn main_loop(&mut self, blockchain_receiver: &Receiver<DursMsg>) {
         // Init main loop datas
@@ -326,6 +608,8 @@ impl BlockchainModule {
             requests::sent::request_next_main_blocks(self, now);
             // Request fork blocks
             requests::sent::request_fork_blocks(self, now);
+            // Generate next block
+            generation::generate(self, now);
 
             // Listen received messages
             match blockchain_receiver.recv_timeout(Duration::from_millis(2000)) {
@@ -377,7 +661,9 @@ impl BlockchainModule {
                     "BlockchainModule : current_blockstamp() = {:?}",
                     self.current_blockstamp
                 );
+                self.record_metrics_history();
             }
+            self.autosave_if_due();
         }
     }
 }
@@ -0,0 +1,488 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Sub-module managing the pending transactions (CurrencyPool role).
+
+use crate::dubp::check::local::tx_doc::local_verify_tx_doc_v10;
+use crate::dubp::check::tx_inputs;
+use crate::*;
+use dubp_block_doc::block::BlockDocumentV10;
+use dubp_common_doc::traits::Document;
+use dubp_indexes::sindex::{SourceUniqueIdV10, UniqueIdUTXOv10};
+use dubp_user_docs::documents::transaction::{
+    TransactionDocumentTrait, TransactionDocumentV10, TransactionInputV10, UTXOConditionsGroup,
+};
+use dup_crypto::hashs::Hash;
+use durs_bc_db_reader::BcDbInReadTx;
+use durs_conf::SandboxesConf;
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Reason why a transaction is not admitted in the mempool
+pub enum TxMemPoolError {
+    /// The transaction is already pending
+    AlreadyPending,
+    /// The transaction blockstamp is older than `tx_window`
+    Expired,
+    /// The input spends a source already spent by a pending transaction
+    DoubleSpend {
+        /// Index of the input
+        input_index: usize,
+        /// Hash of the pending transaction
+        pending_tx: Hash,
+    },
+    /// An issuer of the transaction has too many pending transactions
+    IssuerQuota(PubKey),
+}
+
+impl From<TxMemPoolError> for TxRejection {
+    fn from(e: TxMemPoolError) -> Self {
+        match e {
+            TxMemPoolError::AlreadyPending => TxRejection::AlreadyPending,
+            TxMemPoolError::Expired => TxRejection::Expired,
+            TxMemPoolError::DoubleSpend {
+                input_index,
+                pending_tx,
+            } => TxRejection::DoubleSpend {
+                input_index,
+                pending_tx,
+            },
+            TxMemPoolError::IssuerQuota(issuer) => TxRejection::IssuerQuota(issuer),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+/// Pending transaction
+struct PendingTx {
+    /// Transaction document
+    doc: TransactionDocumentV10,
+    /// Median time of the block referenced by the transaction blockstamp
+    blockstamp_time: u64,
+    /// Sources spent by the transaction
+    consumed_sources: Vec<SourceUniqueIdV10>,
+    /// Submission order of the transaction (the last submission, if it was submitted again)
+    submission: u64,
+}
+
+#[derive(Debug, Default)]
+/// Pending transactions, indexed by hash and by consumed source
+pub struct TxMemPool {
+    txs: HashMap<Hash, PendingTx>,
+    consumed_sources: HashMap<SourceUniqueIdV10, Hash>,
+    sandboxes: SandboxesConf,
+    submissions_count: u64,
+}
+
+/// Sources spent by a transaction
+fn consumed_sources(tx: &TransactionDocumentV10) -> Vec<SourceUniqueIdV10> {
+    tx.get_inputs()
+        .iter()
+        .map(|input| match *input {
+            TransactionInputV10::D(_, _, pubkey, block_id) => {
+                SourceUniqueIdV10::UD(pubkey, block_id)
+            }
+            TransactionInputV10::T(_, _, hash, tx_index) => {
+                SourceUniqueIdV10::UTXO(UniqueIdUTXOv10(hash, tx_index))
+            }
+        })
+        .collect()
+}
+
+/// Hash of a transaction
+pub(crate) fn tx_hash(tx: &TransactionDocumentV10) -> Hash {
+    tx.get_hash_opt().unwrap_or_else(|| tx.compute_hash())
+}
+
+impl TxMemPool {
+    /// Empty mempool whose size is limited by `sandboxes`
+    pub fn new(sandboxes: SandboxesConf) -> TxMemPool {
+        TxMemPool {
+            sandboxes,
+            ..TxMemPool::default()
+        }
+    }
+    /// Add a pending transaction, `blockstamp_time` is the median time of the block referenced
+    /// by its blockstamp.
+    /// A pending transaction spending the same sources is replaced only if it has the same issuers
+    /// and an older blockstamp. If the mempool is full, the least recently submitted transaction
+    /// is evicted. Returns the hashes of the replaced and evicted transactions.
+    pub fn add(
+        &mut self,
+        tx: TransactionDocumentV10,
+        blockstamp_time: u64,
+        current_median_time: u64,
+        tx_window: u64,
+    ) -> Result<Vec<Hash>, TxMemPoolError> {
+        let hash = tx_hash(&tx);
+        self.submissions_count += 1;
+        if let Some(pending_tx) = self.txs.get_mut(&hash) {
+            pending_tx.submission = self.submissions_count;
+            return Err(TxMemPoolError::AlreadyPending);
+        }
+        if blockstamp_time + tx_window < current_median_time {
+            return Err(TxMemPoolError::Expired);
+        }
+
+        let consumed_sources = consumed_sources(&tx);
+        let mut replaced_txs: Vec<Hash> = Vec::new();
+        for (input_index, source) in consumed_sources.iter().enumerate() {
+            if let Some(pending_hash) = self.consumed_sources.get(source) {
+                if !replaced_txs.contains(pending_hash) {
+                    let pending_tx = &self.txs[pending_hash];
+                    if pending_tx.doc.issuers() != tx.issuers()
+                        || pending_tx.doc.blockstamp().id >= tx.blockstamp().id
+                    {
+                        return Err(TxMemPoolError::DoubleSpend {
+                            input_index,
+                            pending_tx: *pending_hash,
+                        });
+                    }
+                    replaced_txs.push(*pending_hash);
+                }
+            }
+        }
+        for issuer in tx.issuers() {
+            let issuer_txs_count = self
+                .txs
+                .iter()
+                .filter(|(pending_hash, pending_tx)| {
+                    !replaced_txs.contains(*pending_hash)
+                        && pending_tx.doc.issuers().contains(issuer)
+                })
+                .count();
+            if issuer_txs_count >= self.sandboxes.max_txs_per_issuer {
+                return Err(TxMemPoolError::IssuerQuota(*issuer));
+            }
+        }
+        for replaced_hash in &replaced_txs {
+            self.remove(replaced_hash);
+        }
+        while !self.txs.is_empty() && self.txs.len() >= self.sandboxes.max_txs {
+            if let Some(evicted_hash) = self
+                .txs
+                .iter()
+                .min_by_key(|(_, pending_tx)| pending_tx.submission)
+                .map(|(hash, _)| *hash)
+            {
+                self.remove(&evicted_hash);
+                replaced_txs.push(evicted_hash);
+            }
+        }
+
+        for source in &consumed_sources {
+            self.consumed_sources.insert(*source, hash);
+        }
+        self.txs.insert(
+            hash,
+            PendingTx {
+                doc: tx,
+                blockstamp_time,
+                consumed_sources,
+                submission: self.submissions_count,
+            },
+        );
+        Ok(replaced_txs)
+    }
+    /// Remove a pending transaction
+    pub fn remove(&mut self, hash: &Hash) -> Option<TransactionDocumentV10> {
+        let pending_tx = self.txs.remove(hash)?;
+        for source in &pending_tx.consumed_sources {
+            self.consumed_sources.remove(source);
+        }
+        Some(pending_tx.doc)
+    }
+    /// Remove the transactions written in a new current block, those spending the same sources,
+    /// and those expired at the median time of the block
+    pub fn apply_block(&mut self, block: &BlockDocumentV10, tx_window: u64) {
+        for tx in &block.transactions {
+            self.remove(&tx_hash(tx));
+            for source in consumed_sources(tx) {
+                if let Some(pending_hash) = self.consumed_sources.get(&source).cloned() {
+                    self.remove(&pending_hash);
+                }
+            }
+        }
+        self.expire(block.median_time, tx_window);
+    }
+    /// Remove the transactions expired at `current_median_time`
+    pub fn expire(&mut self, current_median_time: u64, tx_window: u64) {
+        let expired_txs: Vec<Hash> = self
+            .txs
+            .iter()
+            .filter(|(_, pending_tx)| pending_tx.blockstamp_time + tx_window < current_median_time)
+            .map(|(hash, _)| *hash)
+            .collect();
+        for hash in expired_txs {
+            self.remove(&hash);
+        }
+    }
+    /// Number of pending transactions
+    pub fn txs_count(&self) -> usize {
+        self.txs.len()
+    }
+    /// Pending transactions, oldest blockstamp first
+    pub fn pending_txs(&self) -> Vec<TransactionDocumentV10> {
+        let mut pending_txs: Vec<&PendingTx> = self.txs.values().collect();
+        pending_txs.sort_by_key(|pending_tx| pending_tx.doc.blockstamp());
+        pending_txs
+            .into_iter()
+            .map(|pending_tx| pending_tx.doc.clone())
+            .collect()
+    }
+}
+
+/// Median time of the block referenced by `blockstamp` and median time of the current block,
+/// `None` if `blockstamp` isn't in the local blockchain
+pub fn blockstamp_and_current_times(
+    bc: &BlockchainModule,
+    blockstamp: Blockstamp,
+) -> Option<(u64, u64)> {
+    let current_block_number = bc.current_blockstamp.id;
+    let (blockstamp_header, current_median_time) = bc
+        .db()
+        .r(|db_r| {
+            Ok((
+                durs_bc_db_reader::blocks::header::get_block_header_in_local_blockchain(
+                    db_r,
+                    blockstamp.id,
+                )?,
+                durs_bc_db_reader::blocks::header::get_block_header_in_local_blockchain(
+                    db_r,
+                    current_block_number,
+                )?
+                .map(|header| header.common_time())
+                .unwrap_or(0),
+            ))
+        })
+        .unwrap_or_else(|e| fatal_error!("Fail to read blockchain DB: {:?}", e));
+
+    match blockstamp_header {
+        Some(ref header) if header.blockstamp() == blockstamp => {
+            Some((header.common_time(), current_median_time))
+        }
+        _ => None,
+    }
+}
+
+/// Conditions of the source spent by `input`, `None` if this source does not exist
+/// or is already consumed
+fn source_conditions<DB: BcDbInReadTx>(
+    db: &DB,
+    input: &TransactionInputV10,
+) -> Result<Option<UTXOConditionsGroup>, DbError> {
+    match *input {
+        TransactionInputV10::D(_, _, pubkey, block_number) => Ok(
+            if durs_bc_db_reader::indexes::sources::is_unconsumed_ud(db, &pubkey, block_number)? {
+                Some(tx_inputs::ud_conditions(pubkey))
+            } else {
+                None
+            },
+        ),
+        TransactionInputV10::T(amount, base, hash, tx_index) => Ok(
+            durs_bc_db_reader::indexes::sources::get_utxo_v10(db, UniqueIdUTXOv10(hash, tx_index))?
+                .filter(|utxo| utxo.amount == amount && utxo.base == base)
+                .map(|utxo| utxo.conditions.conditions),
+        ),
+    }
+}
+
+/// Submit a transaction to the mempool, returns its hash if it is admitted.
+/// The rejection reason is sent in a `DocumentRejected` event.
+pub fn receive_tx(
+    bc: &mut BlockchainModule,
+    tx: TransactionDocumentV10,
+) -> Result<Hash, TxRejection> {
+    let hash = tx_hash(&tx);
+    admit_tx(bc, tx).map_err(|rejection| {
+        events::sent::send_document_rejected(bc, hash, rejection.to_string());
+        rejection
+    })
+}
+
+fn admit_tx(bc: &mut BlockchainModule, tx: TransactionDocumentV10) -> Result<Hash, TxRejection> {
+    let tx_window = if let Some(currency_params) = bc.currency_params {
+        currency_params.tx_window
+    } else {
+        return Err(TxRejection::UnknownCurrency);
+    };
+    local_verify_tx_doc_v10(tx.version(), &tx)
+        .map_err(|e| TxRejection::InvalidDocument(format!("{:?}", e)))?;
+
+    let tx_blockstamp = tx.blockstamp();
+    let (blockstamp_time, current_median_time) = blockstamp_and_current_times(bc, tx_blockstamp)
+        .ok_or(TxRejection::UnknownBlockstamp(tx_blockstamp))?;
+    if blockstamp_time + tx.get_locktime() > current_median_time {
+        return Err(TxRejection::Locktime(blockstamp_time + tx.get_locktime()));
+    }
+
+    let db = bc.db();
+    tx_inputs::check_tx_inputs(&tx, current_median_time, |input| {
+        db.r(|db_r| source_conditions(db_r, input))
+            .unwrap_or_else(|e| fatal_error!("Fail to read blockchain DB: {:?}", e))
+    })?;
+
+    let hash = tx_hash(&tx);
+    let removed_txs = bc
+        .tx_mempool
+        .add(tx, blockstamp_time, current_median_time, tx_window)?;
+    for removed_hash in removed_txs {
+        debug!("Pending transaction {} replaced or evicted", removed_hash);
+    }
+    Ok(hash)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use dubp_common_doc::traits::DocumentBuilder;
+    use dubp_common_doc::{BlockHash, BlockNumber, Blockstamp};
+    use dubp_user_docs::documents::transaction::v10::TransactionInputUnlocksV10;
+    use dubp_user_docs::documents::transaction::*;
+    use dup_crypto::keys::*;
+    use std::str::FromStr;
+
+    fn gen_tx(issuer: char, block_number: u32, input: &str) -> TransactionDocumentV10 {
+        let issuer = dup_crypto_tests_tools::mocks::pubkey(issuer);
+        let builder = TransactionDocumentV10Builder {
+            currency: "test",
+            blockstamp: &Blockstamp {
+                id: BlockNumber(block_number),
+                hash: BlockHash(Hash::default()),
+            },
+            locktime: &0,
+            issuers: &[issuer],
+            inputs: &[TransactionInputV10::from_str(input).expect("invalid input")],
+            unlocks: &[TransactionInputUnlocksV10::from_str("0:SIG(0)").expect("invalid unlock")],
+            outputs: &[
+                TransactionOutputV10::from_str(&format!("10:0:SIG({})", issuer))
+                    .expect("invalid output"),
+            ],
+            comment: "",
+            hash: None,
+        };
+        let TransactionDocument::V10(tx) = TransactionDocumentBuilder::V10(builder)
+            .build_with_signature(vec![Sig::Ed25519(ed25519::Signature([0u8; 64]))]);
+        tx
+    }
+
+    #[test]
+    fn test_tx_mempool_double_spend_and_replacement() {
+        let mut mempool = TxMemPool::default();
+        let input_a = format!("10:0:D:{}:1", dup_crypto_tests_tools::mocks::pubkey('A'));
+
+        let tx1 = gen_tx('A', 10, &input_a);
+        let tx1_hash = tx_hash(&tx1);
+        assert_eq!(Ok(vec![]), mempool.add(tx1.clone(), 100, 100, 50));
+        assert_eq!(
+            Err(TxMemPoolError::AlreadyPending),
+            mempool.add(tx1, 100, 100, 50)
+        );
+
+        // Another issuer can't spend the same source
+        assert_eq!(
+            Err(TxMemPoolError::DoubleSpend {
+                input_index: 0,
+                pending_tx: tx1_hash
+            }),
+            mempool.add(gen_tx('B', 11, &input_a), 100, 100, 50)
+        );
+        // The same issuer can't replace its transaction with an older one
+        assert_eq!(
+            Err(TxMemPoolError::DoubleSpend {
+                input_index: 0,
+                pending_tx: tx1_hash
+            }),
+            mempool.add(gen_tx('A', 9, &input_a), 100, 100, 50)
+        );
+        // The same issuer can replace its transaction with a more recent one
+        let tx2 = gen_tx('A', 11, &input_a);
+        assert_eq!(Ok(vec![tx1_hash]), mempool.add(tx2.clone(), 100, 100, 50));
+        assert_eq!(vec![tx2], mempool.pending_txs());
+    }
+
+    #[test]
+    fn test_tx_mempool_quotas() {
+        let mut mempool = TxMemPool::new(SandboxesConf {
+            max_txs: 3,
+            max_txs_per_issuer: 2,
+            ..SandboxesConf::default()
+        });
+        let input = |issuer: char, block_number: u32| {
+            format!(
+                "10:0:D:{}:{}",
+                dup_crypto_tests_tools::mocks::pubkey(issuer),
+                block_number
+            )
+        };
+
+        let tx_a1 = gen_tx('A', 10, &input('A', 1));
+        let tx_a1_hash = tx_hash(&tx_a1);
+        let tx_a2 = gen_tx('A', 10, &input('A', 2));
+        let tx_a2_hash = tx_hash(&tx_a2);
+        assert_eq!(Ok(vec![]), mempool.add(tx_a1, 100, 100, 50));
+        assert_eq!(Ok(vec![]), mempool.add(tx_a2, 100, 100, 50));
+        assert_eq!(
+            Err(TxMemPoolError::IssuerQuota(
+                dup_crypto_tests_tools::mocks::pubkey('A')
+            )),
+            mempool.add(gen_tx('A', 10, &input('A', 3)), 100, 100, 50)
+        );
+
+        // The pool is full: the least recently submitted transaction is evicted
+        let tx_b1 = gen_tx('B', 10, &input('B', 1));
+        assert_eq!(Ok(vec![]), mempool.add(tx_b1.clone(), 100, 100, 50));
+        assert_eq!(
+            Ok(vec![tx_a1_hash]),
+            mempool.add(gen_tx('C', 10, &input('C', 1)), 100, 100, 50)
+        );
+        // Submitted again, tx_b1 becomes the most recently submitted
+        assert_eq!(
+            Err(TxMemPoolError::AlreadyPending),
+            mempool.add(tx_b1, 100, 100, 50)
+        );
+        assert_eq!(
+            Ok(vec![tx_a2_hash]),
+            mempool.add(gen_tx('D', 10, &input('D', 1)), 100, 100, 50)
+        );
+        assert_eq!(3, mempool.txs_count());
+    }
+
+    #[test]
+    fn test_tx_mempool_expiry() {
+        let mut mempool = TxMemPool::default();
+        let input_a = format!("10:0:D:{}:1", dup_crypto_tests_tools::mocks::pubkey('A'));
+        let input_b = format!("10:0:D:{}:1", dup_crypto_tests_tools::mocks::pubkey('B'));
+
+        assert_eq!(
+            Err(TxMemPoolError::Expired),
+            mempool.add(gen_tx('A', 10, &input_a), 100, 151, 50)
+        );
+        assert_eq!(
+            Ok(vec![]),
+            mempool.add(gen_tx('A', 10, &input_a), 100, 150, 50)
+        );
+        assert_eq!(
+            Ok(vec![]),
+            mempool.add(gen_tx('B', 20, &input_b), 120, 150, 50)
+        );
+
+        mempool.expire(160, 50);
+        assert_eq!(1, mempool.pending_txs().len());
+        mempool.expire(171, 50);
+        assert!(mempool.pending_txs().is_empty());
+    }
+}
@@ -0,0 +1,189 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Sub-module measuring the time spent in each stage of the application of the blocks
+//! received from the network, to explain why the application is slow.
+
+use crate::constants::*;
+use dubp_common_doc::Blockstamp;
+use durs_message::responses::{BlockApplyMetrics, BlockApplyStage, BlockApplyStageMetrics};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// All stages, in the order of their index
+static ALL_STAGES: &[BlockApplyStage; STAGES_COUNT] = &[
+    BlockApplyStage::PowAndHashs,
+    BlockApplyStage::LocalRules,
+    BlockApplyStage::GlobalRules,
+    BlockApplyStage::WotUpdate,
+    BlockApplyStage::BlockWrite,
+    BlockApplyStage::WotIndexesWrite,
+    BlockApplyStage::CurrencyIndexesWrite,
+];
+
+const STAGES_COUNT: usize = 7;
+
+#[derive(Clone, Copy, Debug)]
+/// Time spent in each stage by an applied block (in microseconds)
+struct BlockApplyRecord {
+    blockstamp: Blockstamp,
+    stages: [u64; STAGES_COUNT],
+}
+
+impl BlockApplyRecord {
+    fn total_micros(&self) -> u64 {
+        self.stages.iter().sum()
+    }
+}
+
+#[derive(Debug, Default)]
+/// Block apply metrics over the last applied blocks
+pub struct ApplyMetrics {
+    /// Timings of the block being applied
+    current: [u64; STAGES_COUNT],
+    /// Timings of the last applied blocks
+    window: VecDeque<BlockApplyRecord>,
+}
+
+impl ApplyMetrics {
+    /// Start measuring a new block (the timings of a block that was not applied are discarded)
+    pub fn begin_block(&mut self) {
+        self.current = [0; STAGES_COUNT];
+    }
+    /// Add time spent in a stage by the block being applied
+    pub fn add(&mut self, stage: BlockApplyStage, duration: Duration) {
+        self.current[stage as usize] += duration.as_micros() as u64;
+    }
+    /// The block being applied is now written in the local blockchain
+    pub fn end_block(&mut self, blockstamp: Blockstamp) {
+        let record = BlockApplyRecord {
+            blockstamp,
+            stages: self.current,
+        };
+        self.current = [0; STAGES_COUNT];
+
+        let total_millis = record.total_micros() / 1_000;
+        if total_millis >= *SLOW_BLOCK_APPLY_THRESHOLD_IN_MILLIS {
+            let (slowest_stage, slowest_stage_micros) = ALL_STAGES
+                .iter()
+                .zip(record.stages.iter())
+                .max_by_key(|(_, micros)| **micros)
+                .map(|(stage, micros)| (*stage, *micros))
+                .unwrap_or((BlockApplyStage::PowAndHashs, 0));
+            warn!(
+                "blockchain: slow apply of block {}: {} ms ({}: {} ms).",
+                blockstamp,
+                total_millis,
+                slowest_stage,
+                slowest_stage_micros / 1_000
+            );
+        }
+
+        if self.window.len() >= *APPLY_METRICS_WINDOW_IN_BLOCKS {
+            self.window.pop_front();
+        }
+        self.window.push_back(record);
+    }
+    /// Get the metrics of the last applied blocks
+    pub fn metrics(&self) -> BlockApplyMetrics {
+        let stages = ALL_STAGES
+            .iter()
+            .map(|stage| BlockApplyStageMetrics {
+                stage: *stage,
+                total_micros: self.window.iter().map(|r| r.stages[*stage as usize]).sum(),
+                max_micros: self
+                    .window
+                    .iter()
+                    .map(|r| r.stages[*stage as usize])
+                    .max()
+                    .unwrap_or(0),
+            })
+            .collect();
+
+        let mut histogram: Vec<(Option<u64>, usize)> = APPLY_METRICS_HISTOGRAM_BOUNDS_IN_MILLIS
+            .iter()
+            .map(|bound| (Some(*bound), 0))
+            .chain(std::iter::once((None, 0)))
+            .collect();
+        for record in &self.window {
+            let millis = record.total_micros() / 1_000;
+            let bucket = APPLY_METRICS_HISTOGRAM_BOUNDS_IN_MILLIS
+                .iter()
+                .position(|bound| millis < *bound)
+                .unwrap_or(APPLY_METRICS_HISTOGRAM_BOUNDS_IN_MILLIS.len());
+            histogram[bucket].1 += 1;
+        }
+
+        BlockApplyMetrics {
+            blocks_count: self.window.len(),
+            stages,
+            histogram,
+            slowest_block: self
+                .window
+                .iter()
+                .max_by_key(|r| r.total_micros())
+                .map(|r| (r.blockstamp, r.total_micros())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use dubp_common_doc::BlockNumber;
+
+    fn blockstamp(number: u32) -> Blockstamp {
+        Blockstamp {
+            id: BlockNumber(number),
+            ..Blockstamp::default()
+        }
+    }
+
+    #[test]
+    fn apply_metrics_window_and_histogram() {
+        let mut metrics = ApplyMetrics::default();
+
+        for number in 0..(*APPLY_METRICS_WINDOW_IN_BLOCKS as u32 + 2) {
+            metrics.begin_block();
+            metrics.add(BlockApplyStage::GlobalRules, Duration::from_millis(3));
+            metrics.add(BlockApplyStage::BlockWrite, Duration::from_millis(2));
+            metrics.end_block(blockstamp(number));
+        }
+        // A block that is not applied is not measured
+        metrics.begin_block();
+        metrics.add(BlockApplyStage::GlobalRules, Duration::from_secs(10));
+        metrics.begin_block();
+        metrics.add(BlockApplyStage::WotUpdate, Duration::from_millis(60));
+        metrics.end_block(blockstamp(1_000));
+
+        let metrics = metrics.metrics();
+        assert_eq!(*APPLY_METRICS_WINDOW_IN_BLOCKS, metrics.blocks_count);
+        assert_eq!(
+            BlockApplyStageMetrics {
+                stage: BlockApplyStage::GlobalRules,
+                total_micros: 3_000 * (*APPLY_METRICS_WINDOW_IN_BLOCKS as u64 - 1),
+                max_micros: 3_000,
+            },
+            metrics.stages[BlockApplyStage::GlobalRules as usize]
+        );
+        assert_eq!(
+            (Some(10), *APPLY_METRICS_WINDOW_IN_BLOCKS - 1),
+            metrics.histogram[0]
+        );
+        assert_eq!((Some(100), 1), metrics.histogram[2]);
+        assert_eq!(Some((blockstamp(1_000), 60_000)), metrics.slowest_block);
+    }
+}
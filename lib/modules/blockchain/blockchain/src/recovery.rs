@@ -0,0 +1,312 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Sub-module recovering the databases after an unclean shutdown.
+
+use crate::*;
+use dubp_common_doc::BlockNumber;
+use durs_bc_db_reader::blocks::header::get_block_header_in_local_blockchain;
+use durs_bc_db_reader::indexes::identities::{IdentityDb, IdentityStateDb};
+use durs_bc_db_reader::BcDbInReadTx;
+use durs_bc_db_writer::write_journal::{WriteJournal, WriteJournalEntry, WriteJournalState};
+use durs_wot::WebOfTrust;
+use failure::Fail;
+use std::fmt;
+use std::path::Path;
+
+#[derive(Debug, Fail)]
+/// Recovery error
+pub enum RecoveryError {
+    /// Database error
+    #[fail(display = "{}", _0)]
+    DbError(DbError),
+    /// The current block is not stored in the local blockchain
+    #[fail(
+        display = "current block {} is not in the local blockchain, you have to reset the data",
+        _0
+    )]
+    CurrentBlockNotFound(Blockstamp),
+}
+
+impl From<DbError> for RecoveryError {
+    fn from(e: DbError) -> Self {
+        RecoveryError::DbError(e)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Recovery of the wot database
+pub enum WotRecovery {
+    /// The wot database is consistent with the blockchain database
+    Consistent,
+    /// The interrupted write did not reach the blockchain database: the wot database is restored from its undo data
+    RolledBack,
+    /// The interrupted write reached the blockchain database: the wot database is rebuilt from the blockchain database
+    Replayed,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Summary of the recovery
+pub struct RecoverySummary {
+    /// Current blockstamp after recovery
+    pub current_blockstamp: Blockstamp,
+    /// Current blockstamp before the interrupted write (if any)
+    pub interrupted_write: Option<Blockstamp>,
+    /// Block being applied by the interrupted write (if known)
+    pub applying: Option<Blockstamp>,
+    /// Number of blocks stored after the current block and removed
+    pub truncated_blocks: u32,
+    /// Recovery of the wot database
+    pub wot_recovery: WotRecovery,
+}
+
+impl fmt::Display for RecoverySummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Recovery after unclean shutdown:")?;
+        writeln!(f, "  current block: {}", self.current_blockstamp)?;
+        if let Some(interrupted_write) = self.interrupted_write {
+            writeln!(f, "  interrupted write from block: {}", interrupted_write)?;
+        } else {
+            writeln!(f, "  interrupted write: none")?;
+        }
+        if let Some(applying) = self.applying {
+            writeln!(f, "  interrupted block application: {}", applying)?;
+        }
+        writeln!(f, "  truncated blocks: {}", self.truncated_blocks)?;
+        write!(
+            f,
+            "  wot database: {}",
+            match self.wot_recovery {
+                WotRecovery::Consistent => "consistent",
+                WotRecovery::RolledBack => "rolled back",
+                WotRecovery::Replayed => "replayed from blockchain database",
+            }
+        )
+    }
+}
+
+/// Recover the databases after an unclean shutdown (the wot databases must be opened after recovery)
+pub fn recover(db: &Db, dbs_path: &Path) -> Result<RecoverySummary, RecoveryError> {
+    let dbs_path = dbs_path.to_path_buf();
+    let write_journal = WriteJournal::open(Some(&dbs_path));
+    let (interrupted_write, applying) = match write_journal.read()? {
+        Some(WriteJournalEntry {
+            state: WriteJournalState::Pending,
+            blockstamp,
+            applying,
+        }) => (Some(blockstamp), applying),
+        _ => (None, None),
+    };
+
+    // Verify last block consistency
+    let current_blockstamp = db
+        .r(|db_r| durs_bc_db_reader::current_metadata::get_current_blockstamp(db_r))?
+        .unwrap_or_default();
+    let next_block_number = if current_blockstamp == Blockstamp::default() {
+        BlockNumber(0)
+    } else {
+        let current_header =
+            db.r(|db_r| get_block_header_in_local_blockchain(db_r, current_blockstamp.id))?;
+        if current_header.map(|header| header.blockstamp()) != Some(current_blockstamp) {
+            return Err(RecoveryError::CurrentBlockNotFound(current_blockstamp));
+        }
+        BlockNumber(current_blockstamp.id.0 + 1)
+    };
+
+    // Truncate blocks stored after the current block
+    let mut truncated_blocks = 0;
+    while db
+        .r(|db_r| {
+            get_block_header_in_local_blockchain(
+                db_r,
+                BlockNumber(next_block_number.0 + truncated_blocks),
+            )
+        })?
+        .is_some()
+    {
+        truncated_blocks += 1;
+    }
+    if truncated_blocks > 0 {
+        db.write(|mut w| {
+            for block_number in next_block_number.0..next_block_number.0 + truncated_blocks {
+                blocks::remove_block(db, &mut w, BlockNumber(block_number))?;
+            }
+            Ok(WriteResp::from(w))
+        })?;
+    }
+
+    // Roll back the wot database if the interrupted write did not reach the blockchain database,
+    // otherwise it will be replayed (a partially saved wot database is discarded)
+    let mut wot_recovery = WotRecovery::Consistent;
+    if let Some(interrupted_write) = interrupted_write {
+        if interrupted_write == current_blockstamp && write_journal.rollback_file_stores()? {
+            wot_recovery = WotRecovery::RolledBack;
+        } else {
+            let wot_db_path = dbs_path.join(WOT_DB_FILENAME);
+            if wot_db_path.exists() {
+                std::fs::remove_file(wot_db_path).map_err(DbError::FileSystemError)?;
+            }
+            wot_recovery = WotRecovery::Replayed;
+        }
+    }
+
+    // Re-open wot databases, and replay them if needed or if they do not match identities
+    let mut wot_databases = WotsV10DBs::open(Some(&dbs_path));
+    let wot_size = wot_databases
+        .wot_db
+        .read(WebOfTrust::size)
+        .map_err(DbError::from)?;
+    let identities_count = db
+        .r(|db_r| durs_bc_db_reader::current_metadata::get_greatest_wot_id_(db_r))?
+        .0;
+    if wot_recovery == WotRecovery::Replayed || wot_size != identities_count {
+        let wot = db.r(|db_r| rebuild_wot(db_r, identities_count, next_block_number))?;
+        wot_databases
+            .wot_db
+            .write(|wot_db| *wot_db = wot)
+            .map_err(DbError::from)?;
+        wot_databases.save_dbs();
+        wot_recovery = WotRecovery::Replayed;
+    }
+
+    db.save()?;
+    wot_databases.write_journal.commit(current_blockstamp)?;
+
+    Ok(RecoverySummary {
+        current_blockstamp,
+        interrupted_write,
+        applying,
+        truncated_blocks,
+        wot_recovery,
+    })
+}
+
+/// Rebuild the wot graph from the identities and the certifications stored in blockchain database
+fn rebuild_wot<DB: BcDbInReadTx>(
+    db: &DB,
+    identities_count: usize,
+    next_block_number: BlockNumber,
+) -> Result<WotDB, DbError> {
+    let mut wot = WotDB::default();
+    for wot_id in (0..identities_count).map(WotId) {
+        wot.add_node();
+        if let Some(IdentityDb {
+            state: IdentityStateDb::Member(_),
+            ..
+        }) = durs_bc_db_reader::indexes::identities::get_identity_by_wot_id(db, wot_id)?
+        {
+            wot.set_enabled(wot_id, true);
+        } else {
+            wot.set_enabled(wot_id, false);
+        }
+    }
+    let blocks_numbers: Vec<BlockNumber> = (0..next_block_number.0).map(BlockNumber).collect();
+    for (source, target) in
+        durs_bc_db_reader::indexes::certs::find_expire_certs(db, &blocks_numbers)?.keys()
+    {
+        wot.add_link(*source, *target);
+    }
+    Ok(wot)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use tempfile::tempdir;
+
+    fn add_nodes(wot_databases: &WotsV10DBs, count: usize) -> Result<(), DbError> {
+        wot_databases
+            .wot_db
+            .write(|wot_db| {
+                for _ in 0..count {
+                    wot_db.add_node();
+                }
+            })
+            .map_err(DbError::from)?;
+        wot_databases.save_dbs();
+        Ok(())
+    }
+
+    fn wot_size(dbs_path: &PathBuf) -> Result<usize, DbError> {
+        WotsV10DBs::open(Some(dbs_path))
+            .wot_db
+            .read(WebOfTrust::size)
+            .map_err(DbError::from)
+    }
+
+    #[test]
+    fn recover_interrupted_write() -> Result<(), RecoveryError> {
+        let tmp_dir = tempdir().map_err(DbError::FileSystemError)?;
+        let dbs_path = tmp_dir.path().to_owned();
+        let db = open_db(&dbs_path)?;
+        let applying = Blockstamp {
+            id: BlockNumber(0),
+            hash: dubp_common_doc::BlockHash(dup_crypto::hashs::Hash([1; 32])),
+        };
+
+        // Simulate a crash after a wot save, before the blockchain database write
+        let mut wot_databases = WotsV10DBs::open(Some(&dbs_path));
+        add_nodes(&wot_databases, 1)?;
+        wot_databases.write_journal.begin(Blockstamp::default())?;
+        wot_databases.write_journal.applying(applying)?;
+        add_nodes(&wot_databases, 2)?;
+        drop(wot_databases);
+
+        // The wot database is restored from its undo data, which has one node but no identity is
+        // stored: the wot database is then replayed
+        let summary = recover(&db, &dbs_path)?;
+        assert_eq!(
+            RecoverySummary {
+                current_blockstamp: Blockstamp::default(),
+                interrupted_write: Some(Blockstamp::default()),
+                applying: Some(applying),
+                truncated_blocks: 0,
+                wot_recovery: WotRecovery::Replayed,
+            },
+            summary
+        );
+        assert_eq!(0, wot_size(&dbs_path)?);
+        assert_eq!(
+            Some(WriteJournalState::Committed),
+            WriteJournal::open(Some(&dbs_path))
+                .read()?
+                .map(|entry| entry.state)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn recover_rolls_back_wot_database() -> Result<(), RecoveryError> {
+        let tmp_dir = tempdir().map_err(DbError::FileSystemError)?;
+        let dbs_path = tmp_dir.path().to_owned();
+        let db = open_db(&dbs_path)?;
+
+        // Simulate a crash after a wot save, before the blockchain database write
+        let mut wot_databases = WotsV10DBs::open(Some(&dbs_path));
+        wot_databases.write_journal.begin(Blockstamp::default())?;
+        add_nodes(&wot_databases, 2)?;
+        drop(wot_databases);
+
+        assert_eq!(
+            WotRecovery::RolledBack,
+            recover(&db, &dbs_path)?.wot_recovery
+        );
+        assert_eq!(0, wot_size(&dbs_path)?);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,341 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Sub-module rebuilding a single derived index from the main blocks, without a full resync.
+
+use crate::*;
+use dubp_block_doc::block::BlockDocumentV10;
+use dubp_common_doc::BlockNumber;
+use dubp_user_docs::documents::transaction::{
+    TransactionDocument, TransactionDocumentTrait, TxAmount, TxBase,
+};
+use durs_bc_db_reader::constants::*;
+use durs_bc_db_reader::indexes::sources::SourceAmount;
+use durs_bc_db_reader::DbReadable;
+use durs_common_tools::UsizeSer32;
+use failure::Fail;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Derived index that can be rebuilt
+pub enum ReindexedStore {
+    /// Sources: unconsumed transaction outputs and universal dividends
+    Balances,
+    /// Certifications by created block
+    Certs,
+    /// Memberships by created block
+    Ms,
+}
+
+impl ReindexedStore {
+    /// Names of the database stores of the index
+    fn stores_names(self) -> &'static [&'static str] {
+        match self {
+            ReindexedStore::Balances => &[UTXOS, DIVIDENDS],
+            ReindexedStore::Certs => &[CERTS_BY_CREATED_BLOCK],
+            ReindexedStore::Ms => &[MBS_BY_CREATED_BLOCK],
+        }
+    }
+}
+
+impl fmt::Display for ReindexedStore {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match *self {
+                ReindexedStore::Balances => "balances",
+                ReindexedStore::Certs => "certs",
+                ReindexedStore::Ms => "ms",
+            }
+        )
+    }
+}
+
+impl FromStr for ReindexedStore {
+    type Err = String;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        match source {
+            "balances" => Ok(ReindexedStore::Balances),
+            "certs" => Ok(ReindexedStore::Certs),
+            "ms" => Ok(ReindexedStore::Ms),
+            _ => Err(format!(
+                "unknown store '{}', expected balances, certs or ms",
+                source
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Fail)]
+/// Reindex error
+pub enum ReindexError {
+    /// Database error
+    #[fail(display = "{}", _0)]
+    DbError(DbError),
+    /// The local blockchain is empty
+    #[fail(display = "the local blockchain is empty")]
+    EmptyBlockchain,
+    /// The transactions of the old blocks are not stored (light storage mode)
+    #[fail(
+        display = "the transactions of the old blocks are not stored in light storage mode, you have to reset the data"
+    )]
+    LightStorage,
+}
+
+impl From<DbError> for ReindexError {
+    fn from(e: DbError) -> Self {
+        ReindexError::DbError(e)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Summary of the reindex
+pub struct ReindexSummary {
+    /// Rebuilt index
+    pub store: ReindexedStore,
+    /// Current blockstamp
+    pub current_blockstamp: Blockstamp,
+    /// Number of entries written in the index
+    pub entries_count: usize,
+}
+
+impl fmt::Display for ReindexSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Index {} rebuilt from the blocks #0 to {} ({} entries written).",
+            self.store, self.current_blockstamp, self.entries_count
+        )
+    }
+}
+
+/// Rebuild the index `store` from the main blocks of the local blockchain (the node must be stopped)
+pub fn reindex(db: &Db, store: ReindexedStore) -> Result<ReindexSummary, ReindexError> {
+    let current_blockstamp = db
+        .r(|db_r| durs_bc_db_reader::current_metadata::get_current_blockstamp(db_r))?
+        .ok_or(ReindexError::EmptyBlockchain)?;
+    if store == ReindexedStore::Balances
+        && db.r(|db_r| durs_bc_db_reader::current_metadata::is_light_storage(db_r))?
+    {
+        return Err(ReindexError::LightStorage);
+    }
+    let wot_index = db.r(|db_r| durs_bc_db_reader::indexes::identities::get_wot_index(db_r))?;
+
+    let entries_count = db.write(|mut w| {
+        clear_stores(db, &mut w, store, &wot_index, current_blockstamp.id)?;
+
+        let mut members = HashSet::new();
+        let mut entries_count = 0;
+        for block_number in 0..=current_blockstamp.id.0 {
+            let BlockDocument::V10(block) =
+                durs_bc_db_reader::blocks::get_block_in_local_blockchain(
+                    &BcDbRwWithWriter { db, w: &w },
+                    BlockNumber(block_number),
+                )?
+                .ok_or_else(|| DbError::WriteAbort {
+                    reason: format!("Missing block #{} in local blockchain.", block_number),
+                })?;
+            entries_count += match store {
+                ReindexedStore::Balances => index_block_sources(db, &mut w, &block, &mut members)?,
+                ReindexedStore::Certs => index_block_certs(db, &mut w, &block, &wot_index)?,
+                ReindexedStore::Ms => index_block_memberships(db, &mut w, &block, &wot_index)?,
+            };
+        }
+        Ok(WriteResp::new(w, entries_count))
+    })?;
+    db.save()?;
+
+    Ok(ReindexSummary {
+        store,
+        current_blockstamp,
+        entries_count,
+    })
+}
+
+/// Remove all the entries of the stores of the index
+fn clear_stores(
+    db: &Db,
+    w: &mut DbWriter,
+    store: ReindexedStore,
+    wot_index: &HashMap<PubKey, WotId>,
+    current_block_number: BlockNumber,
+) -> Result<(), DbError> {
+    for store_name in store.stores_names() {
+        if *store_name == UTXOS {
+            let store = db.get_store(store_name);
+            let keys = store
+                .iter_start(w.as_ref())?
+                .map(|entry| entry.map(|(k, _)| k.to_vec()))
+                .collect::<Result<Vec<Vec<u8>>, _>>()?;
+            for key in keys {
+                store.delete(w.as_mut(), &key)?;
+            }
+        } else if *store_name == DIVIDENDS {
+            let store = db.get_multi_store(store_name);
+            for pubkey in wot_index.keys() {
+                let pubkey_bytes = pubkey.to_bytes_vector();
+                if store.get_first(w.as_ref(), &pubkey_bytes)?.is_some() {
+                    store.delete_all(w.as_mut(), &pubkey_bytes)?;
+                }
+            }
+        } else {
+            let store = db.get_multi_int_store(store_name);
+            for block_number in 0..=current_block_number.0 {
+                if store.get_first(w.as_ref(), block_number)?.is_some() {
+                    store.delete_all(w.as_mut(), block_number)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn get_wot_id(wot_index: &HashMap<PubKey, WotId>, pubkey: &PubKey) -> Result<WotId, DbError> {
+    wot_index
+        .get(pubkey)
+        .copied()
+        .ok_or_else(|| DbError::WriteAbort {
+            reason: format!("Unknown identity {} in wot index.", pubkey),
+        })
+}
+
+/// Replay the universal dividend and the transactions of a block, `members` being updated
+/// with the membership changes of the block
+fn index_block_sources(
+    db: &Db,
+    w: &mut DbWriter,
+    block: &BlockDocumentV10,
+    members: &mut HashSet<PubKey>,
+) -> Result<usize, DbError> {
+    for joiner in &block.joiners {
+        members.insert(joiner.issuers()[0]);
+    }
+    for active in &block.actives {
+        members.insert(active.issuers()[0]);
+    }
+    for excluded in &block.excluded {
+        members.remove(excluded);
+    }
+    for revocation in &block.revoked {
+        members.remove(&revocation.to_compact_document().issuer);
+    }
+
+    let mut entries_count = 0;
+    if let Some(UsizeSer32(du_amount)) = block.dividend {
+        if du_amount > 0 {
+            let members_pubkeys: Vec<PubKey> = members.iter().copied().collect();
+            durs_bc_db_writer::indexes::dividends::create_du(
+                db,
+                w,
+                &SourceAmount(TxAmount(du_amount as isize), TxBase(block.unit_base.into())),
+                block.number,
+                &members_pubkeys,
+                false,
+            )?;
+            entries_count += members_pubkeys.len();
+        }
+    }
+    for tx in &block.transactions {
+        durs_bc_db_writer::indexes::transactions::apply_and_write_tx(
+            db,
+            w,
+            &TransactionDocument::V10(tx.clone()),
+            false,
+        )?;
+        entries_count += tx.get_outputs().as_ref().len();
+    }
+    Ok(entries_count)
+}
+
+/// Index the certifications of a block
+fn index_block_certs(
+    db: &Db,
+    w: &mut DbWriter,
+    block: &BlockDocumentV10,
+    wot_index: &HashMap<PubKey, WotId>,
+) -> Result<usize, DbError> {
+    for certification in &block.certifications {
+        let compact_cert = certification.to_compact_document();
+        durs_bc_db_writer::indexes::certs::index_certification(
+            db,
+            w,
+            get_wot_id(wot_index, &compact_cert.issuer)?,
+            get_wot_id(wot_index, &compact_cert.target)?,
+            compact_cert.block_number,
+        )?;
+    }
+    Ok(block.certifications.len())
+}
+
+/// Index the memberships of the joiners and the renewals of a block
+fn index_block_memberships(
+    db: &Db,
+    w: &mut DbWriter,
+    block: &BlockDocumentV10,
+    wot_index: &HashMap<PubKey, WotId>,
+) -> Result<usize, DbError> {
+    let newcomers: HashSet<PubKey> = block
+        .identities
+        .iter()
+        .map(|identity| identity.issuers()[0])
+        .collect();
+    let mut entries_count = 0;
+    for membership in block.joiners.iter().chain(
+        block
+            .actives
+            .iter()
+            .filter(|active| !newcomers.contains(&active.issuers()[0])),
+    ) {
+        durs_bc_db_writer::indexes::identities::index_membership(
+            db,
+            w,
+            get_wot_id(wot_index, &membership.issuers()[0])?,
+            membership.blockstamp().id,
+        )?;
+        entries_count += 1;
+    }
+    Ok(entries_count)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn reindexed_store_from_str() {
+        for store in &[
+            ReindexedStore::Balances,
+            ReindexedStore::Certs,
+            ReindexedStore::Ms,
+        ] {
+            assert_eq!(Ok(*store), ReindexedStore::from_str(&store.to_string()));
+        }
+        assert!(ReindexedStore::from_str("utxos").is_err());
+    }
+
+    #[test]
+    fn reindex_empty_blockchain() -> Result<(), DbError> {
+        let db = crate::tests::open_tmp_db()?;
+
+        match reindex(&db, ReindexedStore::Certs) {
+            Err(ReindexError::EmptyBlockchain) => Ok(()),
+            r => panic!("unexpected reindex result: {:?}", r),
+        }
+    }
+}
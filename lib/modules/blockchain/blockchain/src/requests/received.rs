@@ -15,14 +15,20 @@
 
 //! Sub-module managing the inter-modules requests received.
 
+use crate::constants::REPEATED_LOG_INTERVAL_IN_SECS;
 use crate::*;
 //use dubp_user_docs::documents::identity::IdentityDocument;
+use dubp_indexes::sindex::{SourceUniqueIdV10, UniqueIdUTXOv10};
+use dubp_user_docs::documents::transaction::TransactionDocument;
+use dubp_user_docs::documents::UserDocumentDUBP;
+use durs_bc_db_reader::indexes::sources::SourceAmount;
 use durs_bc_db_reader::BcDbRead;
+use durs_common_tools::log_once_per;
 use durs_message::requests::*;
 use durs_module::*;
 
 pub fn receive_req(
-    bc: &BlockchainModule,
+    bc: &mut BlockchainModule,
     req_from: ModuleStaticName,
     req_id: ModuleReqId,
     req_content: DursReqContent,
@@ -59,7 +65,11 @@ pub fn receive_req(
                             ),
                         );
                     } else {
-                        warn!("BlockchainModule : Req : fail to get current_block in bdd !");
+                        log_once_per!(
+                            *REPEATED_LOG_INTERVAL_IN_SECS,
+                            log::Level::Warn,
+                            "BlockchainModule : Req : fail to get current_block in bdd !"
+                        );
                     }
                 } else {
                     fatal_error!(
@@ -139,6 +149,65 @@ pub fn receive_req(
                     )
                 }
             }
+            BlockchainRequest::Metrics => responses::sent::send_req_response(
+                bc,
+                req_from,
+                req_id,
+                &BlockchainResponse::Metrics(bc.apply_metrics.metrics()),
+            ),
+            BlockchainRequest::UtxosOfPubkey(pubkey) => {
+                debug!(
+                    "BlockchainModule : receive BlockchainRequest::UtxosOfPubkey({})",
+                    pubkey
+                );
+                let sources = bc
+                    .db()
+                    .r(|db_r| {
+                        durs_bc_db_reader::indexes::sources::get_sources_of_pubkey(db_r, &pubkey)
+                    })
+                    .expect("Fatal error : get_sources_of_pubkey : Fail to read DB !")
+                    .into_iter()
+                    .map(|(source_id, SourceAmount(amount, base))| PubkeySource {
+                        id: match source_id {
+                            SourceUniqueIdV10::UTXO(UniqueIdUTXOv10(tx_hash, output_index)) => {
+                                PubkeySourceId::Utxo {
+                                    tx_hash,
+                                    output_index: output_index.0,
+                                }
+                            }
+                            SourceUniqueIdV10::UD(_, block_number) => {
+                                PubkeySourceId::Ud(block_number)
+                            }
+                        },
+                        amount,
+                        base,
+                    })
+                    .collect();
+                responses::sent::send_req_response(
+                    bc,
+                    req_from,
+                    req_id,
+                    &BlockchainResponse::UtxosOfPubkey(pubkey, sources),
+                );
+            }
+            BlockchainRequest::BalanceOfPubkey(pubkey) => {
+                debug!(
+                    "BlockchainModule : receive BlockchainRequest::BalanceOfPubkey({})",
+                    pubkey
+                );
+                let SourceAmount(amount, base) = bc
+                    .db()
+                    .r(|db_r| durs_bc_db_reader::indexes::sources::get_balances(db_r, &[pubkey]))
+                    .expect("Fatal error : get_balances : Fail to read DB !")
+                    .pop()
+                    .unwrap_or_default();
+                responses::sent::send_req_response(
+                    bc,
+                    req_from,
+                    req_id,
+                    &BlockchainResponse::BalanceOfPubkey(pubkey, amount, base),
+                );
+            }
             BlockchainRequest::UIDs(pubkeys) => {
                 responses::sent::send_req_response(
                     bc,
@@ -181,5 +250,112 @@ pub fn receive_req(
                   );
               }*/
         }
+    } else if let DursReqContent::MemPoolRequest(mempool_req) = req_content {
+        receive_mempool_req(bc, req_from, req_id, mempool_req);
+    }
+}
+
+fn receive_mempool_req(
+    bc: &mut BlockchainModule,
+    req_from: ModuleStaticName,
+    req_id: ModuleReqId,
+    mempool_req: MemPoolRequest,
+) {
+    match mempool_req {
+        MemPoolRequest::AllPendingIdentities(min_certs) => {
+            responses::sent::send_mempool_req_response(
+                bc,
+                req_from,
+                req_id,
+                MemPoolResponse::AllPendingIdentities(
+                    req_id,
+                    bc.wot_mempool.pending_identities(min_certs, true),
+                ),
+            )
+        }
+        MemPoolRequest::AllPendingIdentitiesWithoutCerts(min_certs) => {
+            responses::sent::send_mempool_req_response(
+                bc,
+                req_from,
+                req_id,
+                MemPoolResponse::AllPendingIdentitiesWithoutCerts(
+                    req_id,
+                    bc.wot_mempool.pending_identities(min_certs, false),
+                ),
+            )
+        }
+        MemPoolRequest::PendingWotDatasForPubkey(pubkey) => {
+            if let Some(pending_datas) = bc.wot_mempool.pending_wot_datas_for_pubkey(&pubkey) {
+                responses::sent::send_mempool_req_response(
+                    bc,
+                    req_from,
+                    req_id,
+                    MemPoolResponse::PendingWotDatasForPubkey(req_id, Box::new(pending_datas)),
+                )
+            } else {
+                debug!("No pending identity for {}", pubkey);
+            }
+        }
+        MemPoolRequest::DocumentsForNextBlock(_) => responses::sent::send_mempool_req_response(
+            bc,
+            req_from,
+            req_id,
+            MemPoolResponse::DocumentsForNextBlock(
+                req_id,
+                bc.wot_mempool
+                    .pending_documents()
+                    .into_iter()
+                    .chain(bc.tx_mempool.pending_txs().into_iter().map(|tx| {
+                        UserDocumentDUBP::Transaction(Box::new(TransactionDocument::V10(tx)))
+                    }))
+                    .collect(),
+            ),
+        ),
+        MemPoolRequest::PendingTransactions => responses::sent::send_mempool_req_response(
+            bc,
+            req_from,
+            req_id,
+            MemPoolResponse::PendingTransactions(
+                req_id,
+                bc.tx_mempool
+                    .pending_txs()
+                    .into_iter()
+                    .map(TransactionDocument::V10)
+                    .collect(),
+            ),
+        ),
+        MemPoolRequest::SubmitTransaction(tx) => {
+            let TransactionDocument::V10(tx) = *tx;
+            let result = mempool::receive_tx(bc, tx);
+            if let Err(ref rejection) = result {
+                debug!("Reject submitted transaction: {}", rejection);
+            } else {
+                events::sent::send_mempool_stats(bc);
+            }
+            responses::sent::send_mempool_req_response(
+                bc,
+                req_from,
+                req_id,
+                MemPoolResponse::TransactionSubmission(req_id, result),
+            )
+        }
+        MemPoolRequest::UidAvailability(uid) => responses::sent::send_mempool_req_response(
+            bc,
+            req_from,
+            req_id,
+            MemPoolResponse::IdentityAvailability(
+                req_id,
+                wot_mempool::check_idty_availability(bc, Some(&uid), None),
+            ),
+        ),
+        MemPoolRequest::PubkeyAvailability(pubkey) => responses::sent::send_mempool_req_response(
+            bc,
+            req_from,
+            req_id,
+            MemPoolResponse::IdentityAvailability(
+                req_id,
+                wot_mempool::check_idty_availability(bc, None, Some(&pubkey)),
+            ),
+        ),
     }
 }
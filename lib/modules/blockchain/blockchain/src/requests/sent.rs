@@ -91,7 +91,7 @@ pub fn request_next_main_blocks(bc: &mut BlockchainModule, now: SystemTime) {
         bc.last_request_blocks = now;
         // Request next main blocks
         let to = match bc.consensus.id.0 {
-            0 => (bc.current_blockstamp.id.0 + *MAX_BLOCKS_REQUEST),
+            0 => (bc.current_blockstamp.id.0 + bc.blocks_requests.max_blocks_request),
             _ => bc.consensus.id.0,
         };
         let new_pending_network_requests = dunp::queries::request_blocks_to(bc, BlockNumber(to));
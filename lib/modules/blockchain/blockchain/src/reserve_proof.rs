@@ -0,0 +1,351 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Sub-module producing and verifying proofs of reserve: statements of the balances of a set of
+//! public keys at a given block, signed by the member key of the node operator.
+//!
+//! The statement contains the state hash of the sources at this block, so that a third party
+//! can check it against its own node synchronized up to the same block.
+
+use crate::snapshot;
+use crate::*;
+use dubp_user_docs::documents::transaction::{TxAmount, TxBase};
+use dup_crypto::hashs::Hash;
+use durs_bc_db_reader::constants::*;
+use durs_bc_db_reader::indexes::sources::SourceAmount;
+use failure::Fail;
+use std::fmt;
+use std::str::FromStr;
+
+/// Version of the reserve statement format
+static RESERVE_STATEMENT_VERSION: &str = "1";
+
+#[derive(Debug, Fail)]
+/// Proof of reserve error
+pub enum ReserveProofError {
+    /// Database error
+    #[fail(display = "{}", _0)]
+    DbError(DbError),
+    /// The local blockchain is empty
+    #[fail(display = "the local blockchain is empty")]
+    EmptyBlockchain,
+    /// The local blockchain is not at the block of the statement
+    #[fail(
+        display = "the local blockchain is at block {}, not at block {}: synchronize it up to this block with the --end option",
+        local, expected
+    )]
+    BlockstampMismatch {
+        /// Current blockstamp of the local blockchain
+        local: Blockstamp,
+        /// Blockstamp of the statement
+        expected: Blockstamp,
+    },
+    /// The statement is not about the currency of the local blockchain
+    #[fail(display = "the statement is about the currency {}", _0)]
+    CurrencyMismatch(String),
+    /// Fail to sign the statement
+    #[fail(display = "fail to sign the statement: {:?}", _0)]
+    SignError(SignError),
+    /// The statement file is malformed
+    #[fail(display = "invalid statement: {}", _0)]
+    InvalidFormat(String),
+    /// The signature of the statement is invalid
+    #[fail(display = "the signature of the statement is invalid")]
+    InvalidSignature,
+    /// The state hash of the local blockchain differs from the state hash of the statement
+    #[fail(display = "the state hash of the local blockchain is {}", _0)]
+    StateHashMismatch(Hash),
+    /// The balance of a public key differs from the balance of the statement
+    #[fail(
+        display = "the balance of {} in the local blockchain is {}:{}",
+        _0, _1, _2
+    )]
+    BalanceMismatch(PubKey, isize, usize),
+}
+
+impl From<DbError> for ReserveProofError {
+    fn from(e: DbError) -> Self {
+        ReserveProofError::DbError(e)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// Statement of the balances of a set of public keys at a given block
+pub struct ReserveStatement {
+    /// Currency name
+    pub currency: String,
+    /// Block at which the balances are stated
+    pub blockstamp: Blockstamp,
+    /// Hash of the state of the sources at this block
+    pub state_hash: Hash,
+    /// Public key of the signer
+    pub issuer: PubKey,
+    /// Balances of the public keys
+    pub balances: Vec<(PubKey, SourceAmount)>,
+    /// Signature of the issuer
+    pub signature: Option<Sig>,
+}
+
+impl ReserveStatement {
+    /// Signed part of the statement
+    pub fn to_raw(&self) -> String {
+        let mut raw = format!(
+            "Version: {}\nType: ReserveStatement\nCurrency: {}\nBlockstamp: {}\nStateHash: {}\nIssuer: {}\nBalances:\n",
+            RESERVE_STATEMENT_VERSION,
+            self.currency,
+            self.blockstamp,
+            self.state_hash.to_hex(),
+            self.issuer,
+        );
+        for (pubkey, SourceAmount(TxAmount(amount), TxBase(base))) in &self.balances {
+            raw.push_str(&format!("{}:{}:{}\n", pubkey, amount, base));
+        }
+        raw
+    }
+    /// Sign the statement
+    pub fn sign(&mut self, keypair: &KeyPairEnum) -> Result<(), ReserveProofError> {
+        let signator = keypair
+            .generate_signator()
+            .map_err(ReserveProofError::SignError)?;
+        self.signature = Some(signator.sign(self.to_raw().as_bytes()));
+        Ok(())
+    }
+    /// Verify the signature of the statement
+    pub fn verify_signature(&self) -> Result<(), ReserveProofError> {
+        if let Some(ref signature) = self.signature {
+            self.issuer
+                .verify(self.to_raw().as_bytes(), signature)
+                .map_err(|_| ReserveProofError::InvalidSignature)
+        } else {
+            Err(ReserveProofError::InvalidSignature)
+        }
+    }
+}
+
+impl fmt::Display for ReserveStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_raw())?;
+        if let Some(ref signature) = self.signature {
+            writeln!(f, "{}", signature.to_base64())?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for ReserveStatement {
+    type Err = ReserveProofError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| ReserveProofError::InvalidFormat(reason.to_owned());
+        let mut lines = text.lines();
+        let mut field = |name: &str| -> Result<String, ReserveProofError> {
+            let line = lines.next().ok_or_else(|| invalid("unexpected end"))?;
+            let prefix = format!("{}: ", name);
+            if line.starts_with(&prefix) {
+                Ok(line[prefix.len()..].to_owned())
+            } else {
+                Err(invalid(&format!("expected field {}", name)))
+            }
+        };
+
+        if field("Version")? != RESERVE_STATEMENT_VERSION {
+            return Err(invalid("unsupported version"));
+        }
+        if field("Type")? != "ReserveStatement" {
+            return Err(invalid("not a reserve statement"));
+        }
+        let currency = field("Currency")?;
+        let blockstamp =
+            Blockstamp::from_string(&field("Blockstamp")?).map_err(|_| invalid("blockstamp"))?;
+        let state_hash = Hash::from_hex(&field("StateHash")?).map_err(|_| invalid("state hash"))?;
+        let issuer = PubKey::from_str(&field("Issuer")?).map_err(|_| invalid("issuer"))?;
+        if lines.next() != Some("Balances:") {
+            return Err(invalid("expected balances"));
+        }
+
+        let mut balances = Vec::new();
+        let mut signature = None;
+        for line in lines {
+            let parts: Vec<&str> = line.split(':').collect();
+            if let [pubkey, amount, base] = parts[..] {
+                if signature.is_some() {
+                    return Err(invalid("balance after the signature"));
+                }
+                balances.push((
+                    PubKey::from_str(pubkey).map_err(|_| invalid("balance public key"))?,
+                    SourceAmount(
+                        TxAmount(amount.parse().map_err(|_| invalid("balance amount"))?),
+                        TxBase(base.parse().map_err(|_| invalid("balance base"))?),
+                    ),
+                ));
+            } else if signature.is_none() {
+                signature = Some(Sig::Ed25519(
+                    ed25519::Signature::from_base64(line).map_err(|_| invalid("signature"))?,
+                ));
+            } else {
+                return Err(invalid("unexpected line after the signature"));
+            }
+        }
+
+        Ok(ReserveStatement {
+            currency,
+            blockstamp,
+            state_hash,
+            issuer,
+            balances,
+            signature,
+        })
+    }
+}
+
+/// Compute the state hash of the sources: unconsumed transaction outputs and universal dividends
+pub fn state_hash<DB: BcDbWithReader>(db: &DB) -> Result<Hash, DbError> {
+    let members_pubkeys = snapshot::members_pubkeys(db)?;
+    let stores = vec![
+        snapshot::export_store(db, UTXOS)?,
+        snapshot::export_store(db, UDS_HISTORY)?,
+        snapshot::export_multi_store(db, DIVIDENDS, &members_pubkeys)?,
+    ];
+    Ok(Hash::compute(
+        &bincode::serialize(&stores).map_err(DbError::from)?,
+    ))
+}
+
+/// Create the statement of the balances of `pubkeys` at the current block, signed with `keypair`
+///
+/// If `blockstamp` is given, the current block of the local blockchain must be this block.
+pub fn create_statement(
+    db: &Db,
+    keypair: &KeyPairEnum,
+    pubkeys: &[PubKey],
+    blockstamp: Option<Blockstamp>,
+) -> Result<ReserveStatement, ReserveProofError> {
+    let (currency, current_blockstamp, state_hash, balances) = db.r(|db_r| {
+        Ok((
+            durs_bc_db_reader::current_metadata::get_currency_name(db_r)?,
+            durs_bc_db_reader::current_metadata::get_current_blockstamp(db_r)?,
+            state_hash(db_r)?,
+            durs_bc_db_reader::indexes::sources::get_balances(db_r, pubkeys)?,
+        ))
+    })?;
+    let (currency, current_blockstamp) = match (currency, current_blockstamp) {
+        (Some(currency), Some(current_blockstamp)) => (currency, current_blockstamp),
+        _ => return Err(ReserveProofError::EmptyBlockchain),
+    };
+    if let Some(blockstamp) = blockstamp {
+        if blockstamp != current_blockstamp {
+            return Err(ReserveProofError::BlockstampMismatch {
+                local: current_blockstamp,
+                expected: blockstamp,
+            });
+        }
+    }
+
+    let mut statement = ReserveStatement {
+        currency: currency.0,
+        blockstamp: current_blockstamp,
+        state_hash,
+        issuer: keypair.public_key(),
+        balances: pubkeys.iter().copied().zip(balances).collect(),
+        signature: None,
+    };
+    statement.sign(keypair)?;
+    Ok(statement)
+}
+
+/// Verify a statement against the local blockchain, which must be at the block of the statement
+pub fn verify_statement(db: &Db, statement: &ReserveStatement) -> Result<(), ReserveProofError> {
+    statement.verify_signature()?;
+
+    let pubkeys: Vec<PubKey> = statement
+        .balances
+        .iter()
+        .map(|(pubkey, _)| *pubkey)
+        .collect();
+    let (currency, current_blockstamp, state_hash, balances) = db.r(|db_r| {
+        Ok((
+            durs_bc_db_reader::current_metadata::get_currency_name(db_r)?,
+            durs_bc_db_reader::current_metadata::get_current_blockstamp(db_r)?,
+            state_hash(db_r)?,
+            durs_bc_db_reader::indexes::sources::get_balances(db_r, &pubkeys)?,
+        ))
+    })?;
+    let (currency, current_blockstamp) = match (currency, current_blockstamp) {
+        (Some(currency), Some(current_blockstamp)) => (currency, current_blockstamp),
+        _ => return Err(ReserveProofError::EmptyBlockchain),
+    };
+
+    if currency.0 != statement.currency {
+        return Err(ReserveProofError::CurrencyMismatch(
+            statement.currency.clone(),
+        ));
+    }
+    if current_blockstamp != statement.blockstamp {
+        return Err(ReserveProofError::BlockstampMismatch {
+            local: current_blockstamp,
+            expected: statement.blockstamp,
+        });
+    }
+    if state_hash != statement.state_hash {
+        return Err(ReserveProofError::StateHashMismatch(state_hash));
+    }
+    for ((pubkey, stated_balance), balance) in statement.balances.iter().zip(balances) {
+        if *stated_balance != balance {
+            return Err(ReserveProofError::BalanceMismatch(
+                *pubkey,
+                (balance.0).0,
+                (balance.1).0,
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use dup_crypto_tests_tools::mocks::pubkey;
+
+    #[test]
+    fn statement_text_round_trip() -> Result<(), ReserveProofError> {
+        let keypair = KeyPairEnum::Ed25519(ed25519::KeyPairFromSeed32Generator::generate(
+            Seed32::new([3u8; 32]),
+        ));
+        let mut statement = ReserveStatement {
+            currency: "g1".to_owned(),
+            blockstamp: Blockstamp::default(),
+            state_hash: Hash::compute(b"state"),
+            issuer: keypair.public_key(),
+            balances: vec![
+                (pubkey('A'), SourceAmount(TxAmount(1_002), TxBase(0))),
+                (pubkey('B'), SourceAmount::default()),
+            ],
+            signature: None,
+        };
+        statement.sign(&keypair)?;
+        statement.verify_signature()?;
+
+        let parsed = ReserveStatement::from_str(&statement.to_string())?;
+        assert_eq!(statement, parsed);
+
+        let mut forged = parsed;
+        forged.balances[1].1 = SourceAmount(TxAmount(5_000), TxBase(0));
+        match forged.verify_signature() {
+            Err(ReserveProofError::InvalidSignature) => Ok(()),
+            r => panic!("unexpected verification result: {:?}", r),
+        }
+    }
+}
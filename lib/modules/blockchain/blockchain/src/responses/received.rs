@@ -22,7 +22,11 @@ pub fn receive_response(
     req_id: ModuleReqId,
     res_content: DursResContent,
 ) {
-    if let DursResContent::NetworkResponse(network_response) = res_content {
+    if let DursResContent::MemPoolResponse(MemPoolResponse::DocumentsForNextBlock(_, documents)) =
+        res_content
+    {
+        generation::receive_documents(bc, req_id, documents);
+    } else if let DursResContent::NetworkResponse(network_response) = res_content {
         debug!("BlockchainModule : receive NetworkResponse() !");
         if let Some(request) = bc.pending_network_requests.remove(&req_id) {
             match request {
@@ -33,8 +37,14 @@ pub fn receive_response(
                         }
                     }
                 }
-                OldNetworkRequest::GetBlocks(_, _, _) => {
+                OldNetworkRequest::GetBlocks(_, count, from) => {
                     if let NetworkResponse::Chunk(_, _, blocks) = network_response {
+                        // Request the next blocks while this chunk is applied
+                        dunp::queries::pipeline_main_blocks(
+                            bc,
+                            BlockNumber(from + count),
+                            blocks.len(),
+                        );
                         dunp::receiver::receive_blocks(bc, blocks);
                     }
                 }
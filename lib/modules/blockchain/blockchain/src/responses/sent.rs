@@ -33,3 +33,19 @@ pub fn send_req_response(
         }))
         .unwrap_or_else(|_| fatal_error!("Fail to send ReqRes to router"));
 }
+
+pub fn send_mempool_req_response(
+    bc: &BlockchainModule,
+    requester: ModuleStaticName,
+    req_id: ModuleReqId,
+    response: MemPoolResponse,
+) {
+    bc.router_sender
+        .send(RouterThreadMessage::ModuleMessage(DursMsg::Response {
+            res_from: BlockchainModule::name(),
+            res_to: requester,
+            req_id,
+            res_content: DursResContent::MemPoolResponse(response),
+        }))
+        .unwrap_or_else(|_| fatal_error!("Fail to send ReqRes to router"));
+}
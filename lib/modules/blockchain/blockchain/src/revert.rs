@@ -0,0 +1,237 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Sub-module reverting the local blockchain to a given block.
+
+use crate::dubp::apply::exec_currency_queries;
+use crate::fork::revert_block::{RevertValidBlockError, ValidBlockRevertReqs};
+use crate::*;
+use dubp_common_doc::BlockNumber;
+use durs_bc_db_reader::constants::{FORK_BLOCKS, LIGHT_STORAGE_TX_WINDOW, ORPHAN_BLOCKSTAMP};
+use durs_bc_db_writer::dal_transaction::DalTransaction;
+use failure::Fail;
+use std::fmt;
+use std::path::Path;
+
+#[derive(Debug, Fail)]
+/// Revert error
+pub enum RevertError {
+    /// Database error
+    #[fail(display = "{}", _0)]
+    DbError(DbError),
+    /// The local blockchain is empty
+    #[fail(display = "the local blockchain is empty")]
+    EmptyBlockchain,
+    /// The target block is not below the current block
+    #[fail(
+        display = "target block #{} is not below current block {}",
+        target, current
+    )]
+    TargetNotBelowCurrent {
+        /// Target block number
+        target: BlockNumber,
+        /// Current blockstamp
+        current: Blockstamp,
+    },
+    /// The transactions of the blocks to revert are no longer stored (light storage mode)
+    #[fail(
+        display = "the transactions of block #{} are no longer stored, you have to reset the data",
+        _0
+    )]
+    PrunedTransactions(BlockNumber),
+    /// Recovery error
+    #[fail(display = "{}", _0)]
+    RecoveryError(recovery::RecoveryError),
+    /// Fail to read currency parameters
+    #[fail(display = "Fail to read currency params DB: {}", _0)]
+    CurrencyParamsDbError(dubp_currency_params::db::CurrencyParamsDbError),
+}
+
+impl From<DbError> for RevertError {
+    fn from(e: DbError) -> Self {
+        RevertError::DbError(e)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Summary of the revert
+pub struct RevertSummary {
+    /// Current blockstamp before the revert
+    pub old_current_blockstamp: Blockstamp,
+    /// Current blockstamp after the revert
+    pub current_blockstamp: Blockstamp,
+}
+
+impl fmt::Display for RevertSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Blockchain reverted from {} to {} ({} blocks reverted).",
+            self.old_current_blockstamp,
+            self.current_blockstamp,
+            self.old_current_blockstamp.id.0 - self.current_blockstamp.id.0
+        )
+    }
+}
+
+/// Revert the local blockchain to the target block: unwind the indexes of each block above the target
+/// from the data stored with it, then rebuild the fork tree from the remaining blocks
+pub fn revert_to(
+    db: &Db,
+    dbs_path: &Path,
+    currency_params: CurrencyParameters,
+    target: BlockNumber,
+) -> Result<RevertSummary, RevertError> {
+    let dbs_path = dbs_path.to_path_buf();
+
+    // Recover databases if the previous run did not stop cleanly or a write was interrupted
+    if write_journal::WriteJournal::open(Some(&dbs_path)).needs_recovery() {
+        let summary = recovery::recover(db, &dbs_path).map_err(RevertError::RecoveryError)?;
+        info!("{}", summary);
+    }
+
+    let old_current_blockstamp = db
+        .r(|db_r| durs_bc_db_reader::current_metadata::get_current_blockstamp(db_r))?
+        .ok_or(RevertError::EmptyBlockchain)?;
+    if target >= old_current_blockstamp.id {
+        return Err(RevertError::TargetNotBelowCurrent {
+            target,
+            current: old_current_blockstamp,
+        });
+    }
+
+    // In light storage mode, transactions are only kept in the last blocks
+    if db.r(|db_r| durs_bc_db_reader::current_metadata::is_light_storage(db_r))? {
+        let tx_window = std::cmp::max(
+            *LIGHT_STORAGE_TX_WINDOW,
+            currency_params.fork_window_size as u32,
+        );
+        if old_current_blockstamp.id.0 - target.0 > tx_window {
+            return Err(RevertError::PrunedTransactions(BlockNumber(
+                old_current_blockstamp.id.0 - tx_window,
+            )));
+        }
+    }
+
+    let mut wot_index = db.r(|db_r| durs_bc_db_reader::indexes::identities::get_wot_index(db_r))?;
+    let mut wot_databases = WotsV10DBs::open(Some(&dbs_path));
+    let mut fork_tree = db.r(|db_r| durs_bc_db_reader::current_metadata::get_fork_tree(db_r))?;
+    let dal_tx = DalTransaction::begin(db, &mut wot_databases, old_current_blockstamp)?;
+
+    let current_blockstamp = dal_tx.write(|mut w| {
+        // Revert blocks
+        let mut current_blockstamp = old_current_blockstamp;
+        while current_blockstamp.id > target {
+            let dal_block = durs_bc_db_reader::blocks::get_db_block_in_local_blockchain(
+                &BcDbRwWithWriter { db, w: &w },
+                current_blockstamp.id,
+            )?
+            .filter(|dal_block| dal_block.expire_certs.is_some())
+            .ok_or_else(|| DbError::WriteAbort {
+                reason: format!("Missing revert datas of block {}.", current_blockstamp),
+            })?;
+            let blockstamp = dal_block.blockstamp();
+            debug!("try to revert block #{}", blockstamp);
+            let ValidBlockRevertReqs {
+                new_current_blockstamp,
+                block_query,
+                wot_queries,
+                currency_queries,
+            } = crate::fork::revert_block::revert_block(
+                dal_block,
+                &mut wot_index,
+                &dal_tx.wot_databases().wot_db,
+            )
+            .map_err(|e| match e {
+                RevertValidBlockError::DbError(e) => e,
+                e => DbError::WriteAbort {
+                    reason: format!("Fail to revert block {}: {:?}", blockstamp, e),
+                },
+            })?;
+            block_query.apply(
+                db,
+                &mut w,
+                &mut fork_tree,
+                currency_params.fork_window_size,
+                None,
+            )?;
+            for query in &wot_queries {
+                query.apply(db, &mut w, &blockstamp, &currency_params)?;
+            }
+            exec_currency_queries(db, &mut w, blockstamp.id, currency_queries)?;
+            current_blockstamp = new_current_blockstamp;
+        }
+
+        // Remove all fork and orphan blocks
+        for store_name in &[FORK_BLOCKS, ORPHAN_BLOCKSTAMP] {
+            let store = db.get_store(store_name);
+            let keys = store
+                .iter_start(w.as_ref())?
+                .map(|entry| entry.map(|(k, _)| k.to_vec()))
+                .collect::<Result<Vec<Vec<u8>>, _>>()?;
+            for key in keys {
+                store.delete(w.as_mut(), &key)?;
+            }
+        }
+
+        // Rebuild fork tree with the last blocks of the local blockchain
+        fork_tree = ForkTree::new(currency_params.fork_window_size);
+        let first_block_number =
+            (target.0 + 1).saturating_sub(currency_params.fork_window_size as u32);
+        for block_number in first_block_number..=target.0 {
+            if let Some(dal_block) = durs_bc_db_reader::blocks::get_db_block_in_local_blockchain(
+                &BcDbRwWithWriter { db, w: &w },
+                BlockNumber(block_number),
+            )? {
+                blocks::insert_new_head_block(db, &mut w, Some(&mut fork_tree), dal_block)?;
+            }
+        }
+        blocks::fork_tree::save_fork_tree(db, &mut w, &fork_tree)?;
+
+        Ok(WriteResp::new(w, current_blockstamp))
+    })?;
+
+    // Save databases
+    dal_tx.commit(current_blockstamp)?;
+
+    Ok(RevertSummary {
+        old_current_blockstamp,
+        current_blockstamp,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use dubp_currency_params::genesis_block_params::v10::BlockV10Parameters;
+    use tempfile::tempdir;
+
+    #[test]
+    fn revert_empty_blockchain() -> Result<(), DbError> {
+        let tmp_dir = tempdir().map_err(DbError::FileSystemError)?;
+        let dbs_path = tmp_dir.path().to_owned();
+        let db = open_db(&dbs_path)?;
+        let currency_params = CurrencyParameters::from((
+            &CurrencyName("test_currency".to_owned()),
+            BlockV10Parameters::default(),
+        ));
+
+        match revert_to(&db, &dbs_path, currency_params, BlockNumber(0)) {
+            Err(RevertError::EmptyBlockchain) => Ok(()),
+            r => panic!("unexpected revert result: {:?}", r),
+        }
+    }
+}
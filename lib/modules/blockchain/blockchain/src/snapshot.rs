@@ -0,0 +1,562 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Sub-module exporting and importing snapshots of the chain state.
+//! A snapshot contains the current indexes, the wot graph and the last blocks of the local
+//! blockchain, so that a new node can bootstrap from a trusted snapshot instead of replaying
+//! the whole chain.
+
+use crate::*;
+use dubp_common_doc::BlockNumber;
+use dubp_currency_params::db::CurrencyParamsDbError;
+use dubp_currency_params::genesis_block_params::GenesisBlockParams;
+use dup_crypto::hashs::Hash;
+use durs_bc_db_reader::constants::*;
+use durs_bc_db_reader::current_metadata::CurrentMetaDataKey;
+use durs_bc_db_reader::{BcDbWithReader, DbReadable, DbValue, KvFileDbStoreType};
+use durs_bc_db_writer::dal_transaction::DalTransaction;
+use durs_wot::data::WebOfTrust;
+use failure::Fail;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Version of the snapshot file format
+static SNAPSHOT_FORMAT_VERSION: &u32 = &1;
+
+/// Current metadata exported in the snapshot (the fork tree is rebuilt at import)
+static EXPORTED_METADATA: &[CurrentMetaDataKey] = &[
+    CurrentMetaDataKey::DbVersion,
+    CurrentMetaDataKey::CurrencyName,
+    CurrentMetaDataKey::CurrentBlockstamp,
+    CurrentMetaDataKey::CurrentBlockchainTime,
+    CurrentMetaDataKey::NextWotId,
+    CurrentMetaDataKey::CurrentUd,
+    CurrentMetaDataKey::LightStorage,
+];
+
+#[derive(Debug, Fail)]
+/// Snapshot error
+pub enum SnapshotError {
+    /// Database error
+    #[fail(display = "{}", _0)]
+    DbError(DbError),
+    /// The local blockchain is empty
+    #[fail(display = "the local blockchain is empty")]
+    EmptyBlockchain,
+    /// The local blockchain is not empty
+    #[fail(
+        display = "the local blockchain is not empty, you have to reset the data before importing a snapshot"
+    )]
+    NotEmptyBlockchain,
+    /// Fail to read or write the snapshot file
+    #[fail(display = "I/O error: {}", _0)]
+    IoError(std::io::Error),
+    /// The snapshot file can not be decoded
+    #[fail(display = "invalid snapshot file: {}", _0)]
+    InvalidFile(String),
+    /// The snapshot file was produced by an unsupported version
+    #[fail(display = "unsupported snapshot format version: {}", _0)]
+    UnsupportedVersion(u32),
+    /// The checksum of the snapshot does not match its content
+    #[fail(display = "corrupted snapshot: checksum mismatch")]
+    ChecksumMismatch,
+    /// Fail to read or write currency parameters
+    #[fail(display = "Fail to access currency params DB: {}", _0)]
+    CurrencyParamsDbError(CurrencyParamsDbError),
+}
+
+impl From<DbError> for SnapshotError {
+    fn from(e: DbError) -> Self {
+        SnapshotError::DbError(e)
+    }
+}
+
+impl From<CurrencyParamsDbError> for SnapshotError {
+    fn from(e: CurrencyParamsDbError) -> Self {
+        SnapshotError::CurrencyParamsDbError(e)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Summary of an export or an import
+pub struct SnapshotSummary {
+    /// Current blockstamp of the snapshot
+    pub current_blockstamp: Blockstamp,
+    /// Number of blocks contained in the snapshot
+    pub blocks_count: usize,
+    /// Checksum of the snapshot
+    pub checksum: Hash,
+}
+
+impl fmt::Display for SnapshotSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Snapshot of block {} ({} last blocks, checksum {}).",
+            self.current_blockstamp,
+            self.blocks_count,
+            self.checksum.to_hex()
+        )
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+/// Key of a store entry
+enum SnapshotKey {
+    Int(u32),
+    Bytes(Vec<u8>),
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+/// Value of a store entry
+enum SnapshotValue {
+    U64(u64),
+    Str(String),
+    Blob(Vec<u8>),
+}
+
+impl SnapshotValue {
+    fn from_db_value(value: DbValue) -> Result<Self, DbError> {
+        match value {
+            DbValue::U64(v) => Ok(SnapshotValue::U64(v)),
+            DbValue::Str(v) => Ok(SnapshotValue::Str(v.to_owned())),
+            DbValue::Blob(v) => Ok(SnapshotValue::Blob(v.to_vec())),
+            _ => Err(DbError::DBCorrupted),
+        }
+    }
+    fn to_db_value(&self) -> DbValue<'_> {
+        match *self {
+            SnapshotValue::U64(v) => DbValue::U64(v),
+            SnapshotValue::Str(ref v) => DbValue::Str(v),
+            SnapshotValue::Blob(ref v) => DbValue::Blob(v),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+/// Entries of a store
+pub(crate) struct StoreSnapshot {
+    name: String,
+    entries: Vec<(SnapshotKey, SnapshotValue)>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+/// Chain state
+struct ChainSnapshot {
+    currency_name: CurrencyName,
+    genesis_block_params: GenesisBlockParams,
+    current_blockstamp: Blockstamp,
+    blocks_count: usize,
+    stores: Vec<StoreSnapshot>,
+    wot: WotDB,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+/// Content of a snapshot file
+struct SnapshotFile {
+    format_version: u32,
+    checksum: Hash,
+    payload: Vec<u8>,
+}
+
+/// Export the chain state of the local blockchain in a snapshot file.
+///
+/// Indexes are exported entirely, but only the blocks of the last
+/// `max(LIGHT_STORAGE_TX_WINDOW, fork_window_size)` blocks are exported.
+pub fn export_snapshot(
+    db: &Db,
+    dbs_path: &Path,
+    datas_path: PathBuf,
+    snapshot_path: &Path,
+) -> Result<SnapshotSummary, SnapshotError> {
+    let (currency_name, genesis_block_params) =
+        dubp_currency_params::db::get_genesis_block_params(datas_path)?
+            .ok_or(SnapshotError::EmptyBlockchain)?;
+    let current_blockstamp = db
+        .r(|db_r| durs_bc_db_reader::current_metadata::get_current_blockstamp(db_r))?
+        .ok_or(SnapshotError::EmptyBlockchain)?;
+    let blocks_window = std::cmp::max(
+        *LIGHT_STORAGE_TX_WINDOW,
+        fork_window_size(&currency_name, genesis_block_params) as u32,
+    );
+
+    let wot = WotsV10DBs::open(Some(&dbs_path.to_path_buf()))
+        .wot_db
+        .read(Clone::clone)?;
+    let wot_size = wot.size();
+    let stores =
+        db.r(|db_r| export_stores(db_r, current_blockstamp.id, wot_size, blocks_window))?;
+
+    let snapshot = ChainSnapshot {
+        currency_name,
+        genesis_block_params,
+        current_blockstamp,
+        blocks_count: std::cmp::min(current_blockstamp.id.0 + 1, blocks_window) as usize,
+        stores,
+        wot,
+    };
+    let payload = bincode::serialize(&snapshot).map_err(DbError::from)?;
+    let summary = SnapshotSummary {
+        current_blockstamp,
+        blocks_count: snapshot.blocks_count,
+        checksum: Hash::compute(&payload),
+    };
+    let snapshot_file = SnapshotFile {
+        format_version: *SNAPSHOT_FORMAT_VERSION,
+        checksum: summary.checksum,
+        payload,
+    };
+    std::fs::write(
+        snapshot_path,
+        bincode::serialize(&snapshot_file).map_err(DbError::from)?,
+    )
+    .map_err(SnapshotError::IoError)?;
+
+    Ok(summary)
+}
+
+/// Import a snapshot file into an empty local blockchain
+pub fn import_snapshot(
+    db: &Db,
+    dbs_path: &Path,
+    datas_path: PathBuf,
+    snapshot_path: &Path,
+) -> Result<SnapshotSummary, SnapshotError> {
+    let (snapshot, checksum) = read_snapshot_file(snapshot_path)?;
+
+    if db
+        .r(|db_r| durs_bc_db_reader::current_metadata::get_current_blockstamp(db_r))?
+        .is_some()
+    {
+        return Err(SnapshotError::NotEmptyBlockchain);
+    }
+    let fork_window_size = fork_window_size(&snapshot.currency_name, snapshot.genesis_block_params);
+    let current_number = snapshot.current_blockstamp.id;
+
+    let mut wot_databases = WotsV10DBs::open(Some(&dbs_path.to_path_buf()));
+    let dal_tx = DalTransaction::begin(db, &mut wot_databases, Blockstamp::default())?;
+    dal_tx.write(|mut w| {
+        import_stores(db, &mut w, &snapshot.stores)?;
+
+        // Rebuild fork tree with the last blocks of the snapshot
+        let mut fork_tree = ForkTree::new(fork_window_size);
+        let first_block_number = (current_number.0 + 1).saturating_sub(fork_window_size as u32);
+        for block_number in first_block_number..=current_number.0 {
+            if let Some(dal_block) = durs_bc_db_reader::blocks::get_db_block_in_local_blockchain(
+                &BcDbRwWithWriter { db, w: &w },
+                BlockNumber(block_number),
+            )? {
+                blocks::insert_new_head_block(db, &mut w, Some(&mut fork_tree), dal_block)?;
+            }
+        }
+        blocks::fork_tree::save_fork_tree(db, &mut w, &fork_tree)?;
+
+        Ok(WriteResp::from(w))
+    })?;
+
+    let wot = snapshot.wot;
+    dal_tx
+        .wot_databases()
+        .wot_db
+        .write(|db_wot| *db_wot = wot)?;
+
+    // Save databases
+    dal_tx.commit(snapshot.current_blockstamp)?;
+    dubp_currency_params::db::write_currency_params(
+        datas_path,
+        snapshot.currency_name,
+        snapshot.genesis_block_params,
+    )?;
+
+    Ok(SnapshotSummary {
+        current_blockstamp: snapshot.current_blockstamp,
+        blocks_count: snapshot.blocks_count,
+        checksum,
+    })
+}
+
+fn fork_window_size(currency_name: &CurrencyName, params: GenesisBlockParams) -> usize {
+    match params {
+        GenesisBlockParams::V10(params) => {
+            CurrencyParameters::from((currency_name, params)).fork_window_size
+        }
+    }
+}
+
+/// Read a snapshot file and verify its checksum
+fn read_snapshot_file(snapshot_path: &Path) -> Result<(ChainSnapshot, Hash), SnapshotError> {
+    let bytes = std::fs::read(snapshot_path).map_err(SnapshotError::IoError)?;
+    let snapshot_file: SnapshotFile =
+        bincode::deserialize(&bytes).map_err(|e| SnapshotError::InvalidFile(format!("{}", e)))?;
+
+    if snapshot_file.format_version != *SNAPSHOT_FORMAT_VERSION {
+        return Err(SnapshotError::UnsupportedVersion(
+            snapshot_file.format_version,
+        ));
+    }
+    if Hash::compute(&snapshot_file.payload) != snapshot_file.checksum {
+        return Err(SnapshotError::ChecksumMismatch);
+    }
+    let snapshot: ChainSnapshot = bincode::deserialize(&snapshot_file.payload)
+        .map_err(|e| SnapshotError::InvalidFile(format!("{}", e)))?;
+    if let Some(store) = snapshot
+        .stores
+        .iter()
+        .find(|store| store.name == FORK_BLOCKS || store.name == ORPHAN_BLOCKSTAMP)
+    {
+        return Err(SnapshotError::InvalidFile(format!(
+            "unexpected store '{}'",
+            store.name
+        )));
+    }
+
+    Ok((snapshot, snapshot_file.checksum))
+}
+
+/// Export the entries of all stores, except fork and orphan blocks
+fn export_stores<DB: BcDbWithReader>(
+    db: &DB,
+    current_number: BlockNumber,
+    wot_size: usize,
+    blocks_window: u32,
+) -> Result<Vec<StoreSnapshot>, DbError> {
+    let all_blocks = 0..=current_number.0;
+    let last_blocks = (current_number.0 + 1).saturating_sub(blocks_window)..=current_number.0;
+    let members_pubkeys = members_pubkeys(db)?;
+
+    Ok(vec![
+        export_int_store(
+            db,
+            CURRENT_METADATA,
+            EXPORTED_METADATA.iter().map(|key| key.to_u32()),
+        )?,
+        export_int_store(db, MAIN_BLOCKS, last_blocks.clone())?,
+        export_int_store(db, CONSUMED_UTXOS, last_blocks)?,
+        export_int_store(db, IDENTITIES, 0..wot_size as u32)?,
+        export_multi_int_store(db, MBS_BY_CREATED_BLOCK, all_blocks.clone())?,
+        export_multi_int_store(db, CERTS_BY_CREATED_BLOCK, all_blocks)?,
+        export_store(db, WOT_ID_INDEX)?,
+        export_store(db, UDS_HISTORY)?,
+        export_store(db, UTXOS)?,
+        export_multi_store(db, DIVIDENDS, &members_pubkeys)?,
+    ])
+}
+
+/// Public keys of all identities (keys of the wot id index)
+pub(crate) fn members_pubkeys<DB: BcDbWithReader>(db: &DB) -> Result<Vec<Vec<u8>>, DbError> {
+    db.db()
+        .get_store(WOT_ID_INDEX)
+        .iter_start(db.r())?
+        .map(|entry| entry.map(|(k, _)| k.to_vec()).map_err(DbError::from))
+        .collect()
+}
+
+pub(crate) fn export_store<DB: BcDbWithReader>(
+    db: &DB,
+    store_name: &str,
+) -> Result<StoreSnapshot, DbError> {
+    let mut entries = Vec::new();
+    for entry in db.db().get_store(store_name).iter_start(db.r())? {
+        let (k, v_opt) = entry?;
+        if let Some(v) = v_opt {
+            entries.push((
+                SnapshotKey::Bytes(k.to_vec()),
+                SnapshotValue::from_db_value(v)?,
+            ));
+        }
+    }
+    Ok(StoreSnapshot {
+        name: store_name.to_owned(),
+        entries,
+    })
+}
+
+pub(crate) fn export_int_store<DB: BcDbWithReader, I: IntoIterator<Item = u32>>(
+    db: &DB,
+    store_name: &str,
+    keys: I,
+) -> Result<StoreSnapshot, DbError> {
+    let store = db.db().get_int_store(store_name);
+    let mut entries = Vec::new();
+    for k in keys {
+        if let Some(v) = store.get(db.r(), k)? {
+            entries.push((SnapshotKey::Int(k), SnapshotValue::from_db_value(v)?));
+        }
+    }
+    Ok(StoreSnapshot {
+        name: store_name.to_owned(),
+        entries,
+    })
+}
+
+pub(crate) fn export_multi_store<DB: BcDbWithReader>(
+    db: &DB,
+    store_name: &str,
+    keys: &[Vec<u8>],
+) -> Result<StoreSnapshot, DbError> {
+    let store = db.db().get_multi_store(store_name);
+    let mut entries = Vec::new();
+    for k in keys {
+        for entry in store.get(db.r(), k)? {
+            if let Some(v) = entry?.1 {
+                entries.push((
+                    SnapshotKey::Bytes(k.clone()),
+                    SnapshotValue::from_db_value(v)?,
+                ));
+            }
+        }
+    }
+    Ok(StoreSnapshot {
+        name: store_name.to_owned(),
+        entries,
+    })
+}
+
+pub(crate) fn export_multi_int_store<DB: BcDbWithReader, I: IntoIterator<Item = u32>>(
+    db: &DB,
+    store_name: &str,
+    keys: I,
+) -> Result<StoreSnapshot, DbError> {
+    let store = db.db().get_multi_int_store(store_name);
+    let mut entries = Vec::new();
+    for k in keys {
+        for entry in store.get(db.r(), k)? {
+            if let Some(v) = entry?.1 {
+                entries.push((SnapshotKey::Int(k), SnapshotValue::from_db_value(v)?));
+            }
+        }
+    }
+    Ok(StoreSnapshot {
+        name: store_name.to_owned(),
+        entries,
+    })
+}
+
+/// Write the entries of the given stores
+pub(crate) fn import_stores(
+    db: &Db,
+    w: &mut DbWriter<'_>,
+    stores: &[StoreSnapshot],
+) -> Result<(), DbError> {
+    let schema = durs_bc_db_reader::bc_db_schema();
+    for store in stores {
+        let store_type = *schema
+            .stores
+            .get(&store.name)
+            .ok_or_else(|| DbError::WriteAbort {
+                reason: format!("Unknown store '{}'.", store.name),
+            })?;
+        for (k, v) in &store.entries {
+            match (store_type, k) {
+                (KvFileDbStoreType::Single, SnapshotKey::Bytes(k)) => db
+                    .get_store(&store.name)
+                    .put(w.as_mut(), k, &v.to_db_value())?,
+                (KvFileDbStoreType::SingleIntKey, SnapshotKey::Int(k)) => db
+                    .get_int_store(&store.name)
+                    .put(w.as_mut(), *k, &v.to_db_value())?,
+                (KvFileDbStoreType::Multi, SnapshotKey::Bytes(k)) => db
+                    .get_multi_store(&store.name)
+                    .put(w.as_mut(), k, &v.to_db_value())?,
+                (KvFileDbStoreType::MultiIntKey, SnapshotKey::Int(k)) => db
+                    .get_multi_int_store(&store.name)
+                    .put(w.as_mut(), *k, &v.to_db_value())?,
+                _ => {
+                    return Err(DbError::WriteAbort {
+                        reason: format!("Invalid key type in store '{}'.", store.name),
+                    })
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn export_empty_blockchain() -> Result<(), DbError> {
+        let tmp_dir = tempdir().map_err(DbError::FileSystemError)?;
+        let dbs_path = tmp_dir.path().to_owned();
+        let db = open_db(&dbs_path)?;
+
+        match export_snapshot(
+            &db,
+            &dbs_path,
+            dbs_path.clone(),
+            &dbs_path.join("snapshot.bin"),
+        ) {
+            Err(SnapshotError::EmptyBlockchain) => Ok(()),
+            r => panic!("unexpected export result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn read_corrupted_snapshot() -> Result<(), DbError> {
+        let tmp_dir = tempdir().map_err(DbError::FileSystemError)?;
+        let snapshot_path = tmp_dir.path().join("snapshot.bin");
+        let snapshot_file = SnapshotFile {
+            format_version: *SNAPSHOT_FORMAT_VERSION,
+            checksum: Hash::default(),
+            payload: vec![1, 2, 3],
+        };
+        std::fs::write(&snapshot_path, bincode::serialize(&snapshot_file)?)
+            .map_err(DbError::FileSystemError)?;
+
+        match read_snapshot_file(&snapshot_path) {
+            Err(SnapshotError::ChecksumMismatch) => Ok(()),
+            r => panic!("unexpected read result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn stores_round_trip() -> Result<(), DbError> {
+        let tmp_dir = tempdir().map_err(DbError::FileSystemError)?;
+        let db = open_db(tmp_dir.path())?;
+        let pubkey = dup_crypto_tests_tools::mocks::pubkey('A').to_bytes_vector();
+        db.write(|mut w| {
+            db.get_store(WOT_ID_INDEX)
+                .put(w.as_mut(), &pubkey, &DbValue::U64(0))?;
+            db.get_int_store(IDENTITIES)
+                .put(w.as_mut(), 0, &DbValue::Blob(&[1, 2, 3]))?;
+            db.get_multi_store(DIVIDENDS)
+                .put(w.as_mut(), &pubkey, &DbValue::U64(3))?;
+            db.get_multi_int_store(CERTS_BY_CREATED_BLOCK)
+                .put(w.as_mut(), 2, &DbValue::U64(42))?;
+            Ok(WriteResp::from(w))
+        })?;
+        let stores = db.r(|db_r| export_stores(db_r, BlockNumber(3), 1, 2))?;
+
+        let other_tmp_dir = tempdir().map_err(DbError::FileSystemError)?;
+        let other_db = open_db(other_tmp_dir.path())?;
+        other_db.write(|mut w| {
+            import_stores(&other_db, &mut w, &stores)?;
+            Ok(WriteResp::from(w))
+        })?;
+
+        assert_eq!(
+            stores,
+            other_db.r(|db_r| export_stores(db_r, BlockNumber(3), 1, 2))?
+        );
+        assert!(stores
+            .iter()
+            .any(|store| store.name == DIVIDENDS && store.entries.len() == 1));
+        Ok(())
+    }
+}
@@ -16,6 +16,7 @@
 use crate::sync::*;
 use durs_bc_db_reader::BcDbRead;
 use pbr::ProgressBar;
+use std::sync::mpsc::TryRecvError;
 
 pub fn execute(
     pool: &ThreadPool,
@@ -24,6 +25,7 @@ pub fn execute(
     db: Db,
     target_blockstamp: Blockstamp,
     mut apply_pb: ProgressBar<std::io::Stdout>,
+    timings: Arc<ApplyTimings>,
 ) {
     // Launch blocks_worker thread
     pool.execute(move || {
@@ -49,24 +51,52 @@ pub fn execute(
                     Ok(SyncJobsMess::BlocksDBsWriteQuery(req)) => {
                         all_wait_duration += wait_begin.elapsed();
 
-                        // Apply db request
-                        db.write(|mut w| {
-                            req.apply(
-                                &db,
-                                &mut w,
-                                &mut fork_tree,
-                                fork_window_size,
-                                Some(target_blockstamp),
-                            )?;
-                            Ok(WriteResp::from(w))
-                        })
-                        .expect("Fatal error : Fail to apply BlocksDBsWriteQuery !");
+                        // The requests already received are written in the same transaction
+                        let mut reqs = vec![req];
+                        let mut end = false;
+                        while reqs.len() < *SYNC_BLOCKS_WRITE_BATCH_MAX {
+                            match recv.try_recv() {
+                                Ok(SyncJobsMess::BlocksDBsWriteQuery(req)) => reqs.push(req),
+                                Ok(SyncJobsMess::End) | Err(TryRecvError::Disconnected) => {
+                                    end = true;
+                                    break;
+                                }
+                                Err(TryRecvError::Empty) => break,
+                                Ok(msg) => fatal_error!(
+                                    "Dev error: block worker receive unexpected message: {:?}",
+                                    msg
+                                ),
+                            }
+                        }
+                        let reqs_count = reqs.len();
+
+                        // Apply db requests
+                        timings
+                            .measure(ApplyStage::IndexWrites, || {
+                                db.write(|mut w| {
+                                    for req in reqs {
+                                        req.apply(
+                                            &db,
+                                            &mut w,
+                                            &mut fork_tree,
+                                            fork_window_size,
+                                            Some(target_blockstamp),
+                                        )?;
+                                    }
+                                    Ok(WriteResp::from(w))
+                                })
+                            })
+                            .expect("Fatal error : Fail to apply BlocksDBsWriteQuery !");
 
-                        chunk_index += 1;
-                        if chunk_index == 250 {
-                            chunk_index = 0;
+                        chunk_index += reqs_count;
+                        while chunk_index >= 250 {
+                            chunk_index -= 250;
                             apply_pb.inc();
                         }
+                        if end {
+                            log::info!("Sync: block worker channel closed.");
+                            break;
+                        }
                         wait_begin = Instant::now();
                     }
                     Ok(SyncJobsMess::End) | Err(_) => {
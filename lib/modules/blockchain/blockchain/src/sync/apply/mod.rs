@@ -14,9 +14,11 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 pub mod blocks_worker;
+pub mod timings;
 pub mod txs_worker;
 pub mod wot_worker;
 
+use crate::constants::APPLY_TIMINGS_WINDOW_IN_BLOCKS;
 use crate::dubp;
 use crate::dubp::apply::apply_valid_block;
 use crate::dubp::apply::{ApplyValidBlockError, WriteBlockQueries};
@@ -36,8 +38,9 @@ use durs_wot::data::rusty::RustyWebOfTrust;
 use durs_wot::data::WotId;
 use std::collections::{HashMap, VecDeque};
 use std::path::PathBuf;
-use std::sync::mpsc;
+use std::sync::{mpsc, Arc};
 use std::time::{Duration, Instant};
+use timings::{ApplyStage, ApplyTimings, ApplyTimingsReport};
 use unwrap::unwrap;
 
 // récupérer les métadonnées entre deux utilisation
@@ -67,20 +70,42 @@ pub struct BlockApplicator {
     pub all_wait_duration: Duration,
     pub all_verif_block_hashs_duration: Duration,
     pub all_apply_valid_block_duration: Duration,
+    // stages timings
+    pub timings: Arc<ApplyTimings>,
+    pub timings_report: ApplyTimingsReport,
+    pub timings_window_blocks_count: u32,
+    pub timings_file_path: PathBuf,
 }
 
 impl BlockApplicator {
-    pub fn apply(&mut self, block_doc: BlockDocument) {
+    /// Check the stateless rules of a chunk of blocks in parallel,
+    /// then apply its blocks one by one
+    pub fn apply_chunk(&mut self, blocks: Vec<BlockDocument>) {
         self.all_wait_duration += self.wait_begin.elapsed();
 
-        // Verify block hashs
+        // Verify blocks hashs and signatures
         let verif_block_hashs_begin = Instant::now();
         if self.verif_inner_hash {
-            dubp::check::hashs::check_block_hashes(&block_doc)
-                .expect("Receive wrong block, please reset data and resync !");
+            if let Err(e) = dubp::check::chunk::verify_chunk(&blocks) {
+                fatal_error!(
+                    "Receive wrong block #{} ({}), please reset data and resync !",
+                    e.block_number,
+                    e.error
+                );
+            }
         }
-        self.all_verif_block_hashs_duration += verif_block_hashs_begin.elapsed();
+        let verif_block_hashs_duration = verif_block_hashs_begin.elapsed();
+        self.all_verif_block_hashs_duration += verif_block_hashs_duration;
+        self.timings
+            .add(ApplyStage::Rules, verif_block_hashs_duration);
 
+        // Index application depends on the previous blocks, so it stays serial
+        for block_doc in blocks {
+            self.apply(block_doc);
+        }
+        self.wait_begin = Instant::now();
+    }
+    fn apply(&mut self, block_doc: BlockDocument) {
         // Push block common_time in blocks_not_expiring
         self.blocks_not_expiring.push_back(block_doc.common_time());
         // Get blocks_expiring
@@ -132,7 +157,10 @@ impl BlockApplicator {
         if let Ok(WriteBlockQueries(block_req, wot_db_reqs, currency_db_reqs)) =
             apply_valid_block_result
         {
-            self.all_apply_valid_block_duration += apply_valid_block_begin.elapsed();
+            let apply_valid_block_duration = apply_valid_block_begin.elapsed();
+            self.all_apply_valid_block_duration += apply_valid_block_duration;
+            self.timings
+                .add(ApplyStage::IndexWrites, apply_valid_block_duration);
             self.current_blockstamp = blockstamp;
             debug!("Apply db requests...");
             // Send block request to blocks worker thread
@@ -181,6 +209,10 @@ impl BlockApplicator {
                     );
             }
             debug!("Success to apply block #{}", self.current_blockstamp.id.0);
+            self.timings_window_blocks_count += 1;
+            if self.timings_window_blocks_count >= *APPLY_TIMINGS_WINDOW_IN_BLOCKS {
+                self.flush_timings();
+            }
             if self.current_blockstamp.id.0 >= self.target_blockstamp.id.0 {
                 if self.current_blockstamp == self.target_blockstamp {
                     // Sync completed
@@ -195,6 +227,19 @@ impl BlockApplicator {
                 self.current_blockstamp.id.0 + 1
             )
         }
-        self.wait_begin = Instant::now();
+    }
+    /// Log and export the stages timings of the current window of blocks
+    pub fn flush_timings(&mut self) {
+        let window = self.timings.take();
+        info!(
+            "Apply timings of {} blocks (until #{}): {}",
+            self.timings_window_blocks_count, self.current_blockstamp.id.0, window
+        );
+        self.timings_report
+            .push_window(self.timings_window_blocks_count, window);
+        self.timings_window_blocks_count = 0;
+        if let Err(e) = self.timings_report.save(&self.timings_file_path) {
+            warn!("Fail to export apply timings: {}", e);
+        }
     }
 }
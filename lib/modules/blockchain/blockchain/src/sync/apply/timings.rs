@@ -0,0 +1,177 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Per-stage timings of the block apply pipeline.
+
+use serde::Serialize;
+use std::fs::File;
+use std::io::Write;
+use std::ops::AddAssign;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Stage of the block apply pipeline
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ApplyStage {
+    /// Parsing of the blocks json
+    Parse,
+    /// Verification of the blocks rules
+    Rules,
+    /// Update of the web of trust
+    WotUpdate,
+    /// Writing of the blocks and currency indexes
+    IndexWrites,
+    /// Flushing of the databases on disk
+    Fsync,
+}
+
+/// Per-stage timers, shared between the sync workers
+#[derive(Debug, Default)]
+pub struct ApplyTimings {
+    parse: AtomicU64,
+    rules: AtomicU64,
+    wot_update: AtomicU64,
+    index_writes: AtomicU64,
+    fsync: AtomicU64,
+}
+
+impl ApplyTimings {
+    fn timer(&self, stage: ApplyStage) -> &AtomicU64 {
+        match stage {
+            ApplyStage::Parse => &self.parse,
+            ApplyStage::Rules => &self.rules,
+            ApplyStage::WotUpdate => &self.wot_update,
+            ApplyStage::IndexWrites => &self.index_writes,
+            ApplyStage::Fsync => &self.fsync,
+        }
+    }
+    /// Add a duration to a stage
+    pub fn add(&self, stage: ApplyStage, duration: Duration) {
+        self.timer(stage)
+            .fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+    }
+    /// Execute `f` and add its duration to a stage
+    pub fn measure<R, F: FnOnce() -> R>(&self, stage: ApplyStage, f: F) -> R {
+        let begin = Instant::now();
+        let result = f();
+        self.add(stage, begin.elapsed());
+        result
+    }
+    /// Take the durations accumulated since the last call
+    pub fn take(&self) -> StagesMillis {
+        let take_millis = |stage| self.timer(stage).swap(0, Ordering::Relaxed) / 1_000;
+        StagesMillis {
+            parse: take_millis(ApplyStage::Parse),
+            rules: take_millis(ApplyStage::Rules),
+            wot_update: take_millis(ApplyStage::WotUpdate),
+            index_writes: take_millis(ApplyStage::IndexWrites),
+            fsync: take_millis(ApplyStage::Fsync),
+        }
+    }
+}
+
+/// Durations of each stage (in milliseconds)
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct StagesMillis {
+    pub parse: u64,
+    pub rules: u64,
+    pub wot_update: u64,
+    pub index_writes: u64,
+    pub fsync: u64,
+}
+
+impl AddAssign for StagesMillis {
+    fn add_assign(&mut self, other: StagesMillis) {
+        self.parse += other.parse;
+        self.rules += other.rules;
+        self.wot_update += other.wot_update;
+        self.index_writes += other.index_writes;
+        self.fsync += other.fsync;
+    }
+}
+
+impl std::fmt::Display for StagesMillis {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "parse={}ms rules={}ms wot_update={}ms index_writes={}ms fsync={}ms",
+            self.parse, self.rules, self.wot_update, self.index_writes, self.fsync
+        )
+    }
+}
+
+/// Block apply timings exported for operator telemetry
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct ApplyTimingsReport {
+    /// Number of blocks applied
+    pub blocks_count: u32,
+    /// Number of blocks in the last window
+    pub last_window_blocks_count: u32,
+    /// Stages durations of the last window
+    pub last_window: StagesMillis,
+    /// Stages durations since the beginning
+    pub total: StagesMillis,
+}
+
+impl ApplyTimingsReport {
+    /// Aggregate the durations of a new window of blocks
+    pub fn push_window(&mut self, blocks_count: u32, window: StagesMillis) {
+        self.blocks_count += blocks_count;
+        self.last_window_blocks_count = blocks_count;
+        self.last_window = window;
+        self.total += window;
+    }
+    /// Write report in json file
+    pub fn save(&self, file_path: &Path) -> std::io::Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        let mut file = File::create(file_path)?;
+        file.write_all(json.as_bytes())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aggregate_apply_timings() {
+        let timings = ApplyTimings::default();
+        timings.add(ApplyStage::Rules, Duration::from_millis(3));
+        timings.add(ApplyStage::Rules, Duration::from_millis(4));
+        timings.add(ApplyStage::Fsync, Duration::from_millis(10));
+        assert_eq!(
+            StagesMillis {
+                rules: 7,
+                fsync: 10,
+                ..StagesMillis::default()
+            },
+            timings.take()
+        );
+        assert_eq!(StagesMillis::default(), timings.take());
+
+        let mut report = ApplyTimingsReport::default();
+        let window = StagesMillis {
+            parse: 5,
+            ..StagesMillis::default()
+        };
+        report.push_window(1_000, window);
+        report.push_window(200, window);
+        assert_eq!(1_200, report.blocks_count);
+        assert_eq!(200, report.last_window_blocks_count);
+        assert_eq!(10, report.total.parse);
+    }
+}
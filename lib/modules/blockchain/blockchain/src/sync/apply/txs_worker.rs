@@ -20,6 +20,7 @@ pub fn execute(
     profile_path: PathBuf,
     sender_sync_thread: Sender<MessForSyncThread>,
     recv: Receiver<SyncJobsMess>,
+    timings: Arc<ApplyTimings>,
 ) {
     // Launch tx_worker thread
     pool.execute(move || {
@@ -38,11 +39,14 @@ pub fn execute(
         {
             all_wait_duration += wait_begin.elapsed();
             // Apply db request
-            db.write(|mut w| {
-                req.apply(&db, &mut w, None, in_fork_window)?;
-                Ok(WriteResp::from(w))
-            })
-            .expect("Fatal error : Fail to apply CurrencyDBsWriteQuery !");
+            timings
+                .measure(ApplyStage::IndexWrites, || {
+                    db.write(|mut w| {
+                        req.apply(&db, &mut w, None, in_fork_window)?;
+                        Ok(WriteResp::from(w))
+                    })
+                })
+                .expect("Fatal error : Fail to apply CurrencyDBsWriteQuery !");
             wait_begin = Instant::now();
         }
 
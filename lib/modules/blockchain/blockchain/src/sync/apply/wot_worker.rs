@@ -21,6 +21,7 @@ pub fn execute(
     profile_path: PathBuf,
     sender_sync_thread: Sender<MessForSyncThread>,
     recv: Receiver<SyncJobsMess>,
+    timings: Arc<ApplyTimings>,
 ) {
     // Launch wot_worker thread
     pool.execute(move || {
@@ -36,13 +37,16 @@ pub fn execute(
             all_wait_duration += wait_begin.elapsed();
             match mess {
                 SyncJobsMess::WotsDBsWriteQuery(blockstamp, currency_params, req) => {
-                    db.write(|mut w| {
-                        req.apply(&db, &mut w, &blockstamp, &currency_params.deref())?;
-                        Ok(WriteResp::from(w))
-                    })
-                    .unwrap_or_else(|_| {
-                        fatal_error!("Fail to apply WotsDBsWriteQuery ({})", blockstamp)
-                    });
+                    timings
+                        .measure(ApplyStage::WotUpdate, || {
+                            db.write(|mut w| {
+                                req.apply(&db, &mut w, &blockstamp, currency_params.deref())?;
+                                Ok(WriteResp::from(w))
+                            })
+                        })
+                        .unwrap_or_else(|_| {
+                            fatal_error!("Fail to apply WotsDBsWriteQuery ({})", blockstamp)
+                        });
                 }
                 SyncJobsMess::End => break,
                 _ => {}
@@ -36,6 +36,7 @@ pub fn json_reader_worker(
     sender_sync_thread: Sender<MessForSyncThread>,
     json_chunks_path: PathBuf,
     end: Option<u32>,
+    timings: Arc<ApplyTimings>,
 ) {
     // Lauch json reader thread
     pool.execute(move || {
@@ -69,7 +70,9 @@ pub fn json_reader_worker(
         }
 
         // Parse chunk file content
-        let blocks_result = parse_json_chunk(&chunk_file_content_result.expect("safe unwrap"));
+        let blocks_result = timings.measure(ApplyStage::Parse, || {
+            parse_json_chunk(&chunk_file_content_result.expect("safe unwrap"))
+        });
         let last_chunk_blocks = match blocks_result {
             Ok(blocks) => blocks,
             Err(e) => {
@@ -118,7 +121,9 @@ pub fn json_reader_worker(
             let chunks_numbers: Vec<_> = (begin_chunk_number..last_chunk_number).collect();
             let mut chunks_blocks: HashMap<usize, Vec<BlockDocument>> = chunks_numbers
                 .par_iter()
-                .map(|chunk_number| treat_once_json_chunk(&json_chunks_path, *chunk_number))
+                .map(|chunk_number| {
+                    treat_once_json_chunk(&json_chunks_path, *chunk_number, &timings)
+                })
                 .collect();
 
             // Send blocks
@@ -161,6 +166,7 @@ pub fn json_reader_worker(
 fn treat_once_json_chunk(
     json_chunks_path: &PathBuf,
     chunk_number: usize,
+    timings: &ApplyTimings,
 ) -> (usize, Vec<BlockDocument>) {
     // Open chunk file
     let chunk_file_content_result = open_json_chunk_file(json_chunks_path, chunk_number);
@@ -169,7 +175,9 @@ fn treat_once_json_chunk(
     }
 
     // Parse chunk file content
-    let blocks_result = parse_json_chunk(&chunk_file_content_result.expect("safe unwrap"));
+    let blocks_result = timings.measure(ApplyStage::Parse, || {
+        parse_json_chunk(&chunk_file_content_result.expect("safe unwrap"))
+    });
     let blocks = match blocks_result {
         Ok(blocks) => blocks,
         Err(e) => {
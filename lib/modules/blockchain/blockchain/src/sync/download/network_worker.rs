@@ -0,0 +1,236 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Relay the blocks downloaded by the sync network module to the sync thread.
+
+use crate::sync::*;
+use durs_message::events::BlockchainEvent;
+use durs_network::events::{NetworkEvent, SyncEvent};
+use std::collections::BTreeMap;
+
+/// Relay the received blocks to the sync thread in the order of their numbers
+struct BlocksRelay<'a> {
+    next_number: BlockNumber,
+    pending_blocks: BTreeMap<BlockNumber, BlockDocument>,
+    sender_sync_thread: &'a Sender<MessForSyncThread>,
+}
+
+impl<'a> BlocksRelay<'a> {
+    fn new(bc: &BlockchainModule, sender_sync_thread: &'a Sender<MessForSyncThread>) -> Self {
+        let next_number = if bc.current_blockstamp == Blockstamp::default() {
+            BlockNumber(0)
+        } else {
+            BlockNumber(bc.current_blockstamp.id.0 + 1)
+        };
+        BlocksRelay {
+            next_number,
+            pending_blocks: BTreeMap::new(),
+            sender_sync_thread,
+        }
+    }
+    /// Relay all the blocks that follow the last relayed block
+    fn push(&mut self, blocks: Vec<BlockDocument>) {
+        for block in blocks {
+            // Verify if the block number is within the expected interval
+            if block.number() >= self.next_number {
+                self.pending_blocks.insert(block.number(), block);
+            }
+        }
+        while let Some(block) = self.pending_blocks.remove(&self.next_number) {
+            self.next_number = BlockNumber(self.next_number.0 + 1);
+            self.sender_sync_thread
+                .send(MessForSyncThread::BlockDocument(block))
+                .expect("Fatal error : sync_thread unrechable !");
+        }
+    }
+}
+
+/// Request to the network the next missing blocks, the chunks being requested as soon as
+/// the previous ones are received while the blocks waiting to be relayed don't exceed
+/// the configured queue
+fn request_missing_blocks(
+    bc: &BlockchainModule,
+    relay: &BlocksRelay,
+    next_request_from: &mut BlockNumber,
+    target_number: BlockNumber,
+    pending_requests: &mut HashMap<ModuleReqId, OldNetworkRequest>,
+) {
+    let from = std::cmp::max(*next_request_from, relay.next_number);
+    *next_request_from = dunp::queries::fill_blocks_pipeline(
+        bc,
+        from,
+        target_number,
+        pending_requests,
+        relay.pending_blocks.len(),
+    );
+    if *next_request_from > from {
+        debug!(
+            "Sync: request blocks #{}-{} to the network...",
+            from,
+            next_request_from.0 - 1
+        );
+    }
+}
+
+/// Relay the sync events of the sync network module until the target block is received
+pub fn relay_network_blocks(
+    bc: &BlockchainModule,
+    blockchain_receiver: &Receiver<DursMsg>,
+    currency: CurrencyName,
+    sender_sync_thread: &Sender<MessForSyncThread>,
+) {
+    let mut relay = BlocksRelay::new(bc, sender_sync_thread);
+    let mut target_number = None;
+    let mut pending_requests = HashMap::new();
+    let mut next_request_from = relay.next_number;
+    let mut retries = 0;
+
+    loop {
+        let next_number_before = relay.next_number;
+        match blockchain_receiver.recv_timeout(Duration::from_secs(*NETWORK_SYNC_TIMEOUT_IN_SEC)) {
+            Ok(DursMsg::Request {
+                req_from,
+                req_id,
+                req_content,
+                ..
+            }) => requests::received::receive_req(bc, req_from, req_id, req_content),
+            Ok(DursMsg::Response {
+                req_id,
+                res_content: DursResContent::NetworkResponse(NetworkResponse::Chunk(_, _, blocks)),
+                ..
+            }) => {
+                if pending_requests.remove(&req_id).is_some() {
+                    relay.push(blocks);
+                    if let Some(target_number) = target_number {
+                        request_missing_blocks(
+                            bc,
+                            &relay,
+                            &mut next_request_from,
+                            target_number,
+                            &mut pending_requests,
+                        );
+                    }
+                }
+            }
+            Ok(DursMsg::Event {
+                event_content: DursEvent::NetworkEvent(NetworkEvent::SyncEvent(sync_event)),
+                ..
+            }) => match sync_event {
+                SyncEvent::ReceiveTargetBlockstamp(target_blockstamp) => {
+                    info!("Sync: target blockstamp = {}", target_blockstamp);
+                    sender_sync_thread
+                        .send(MessForSyncThread::Target(
+                            currency.clone(),
+                            target_blockstamp,
+                        ))
+                        .expect("Fatal error : sync_thread unrechable !");
+                    if bc.current_blockstamp != Blockstamp::default()
+                        && target_blockstamp.id <= bc.current_blockstamp.id
+                    {
+                        // Node is already synchronized
+                        break;
+                    }
+                    target_number = Some(target_blockstamp.id);
+                }
+                SyncEvent::ReceiveCorrectBlocksChunk { blocks, .. } => relay.push(blocks),
+                _ => {}
+            },
+            Ok(DursMsg::Stop) => break,
+            Ok(_) => {}
+            Err(RecvTimeoutError::Disconnected) => fatal_error!("Disconnected router !"),
+            Err(RecvTimeoutError::Timeout) => {
+                if let Some(target_number) = target_number {
+                    if retries < *NETWORK_SYNC_MAX_RETRIES {
+                        retries += 1;
+                        warn!("Sync: no blocks received from the sync network module.");
+                        // Request again all the blocks not received
+                        pending_requests.clear();
+                        next_request_from = relay.next_number;
+                        request_missing_blocks(
+                            bc,
+                            &relay,
+                            &mut next_request_from,
+                            target_number,
+                            &mut pending_requests,
+                        );
+                        continue;
+                    }
+                }
+                fatal_error!("Sync: no blocks received from the network !")
+            }
+        }
+
+        if let Some(target_number) = target_number {
+            if relay.next_number > next_number_before {
+                retries = 0;
+                // Emit sync progression at each new chunk
+                let chunk_size = bc.blocks_requests.chunk_size();
+                if relay.next_number.0 / chunk_size > next_number_before.0 / chunk_size
+                    || relay.next_number > target_number
+                {
+                    events::sent::send_event(
+                        bc,
+                        &BlockchainEvent::SyncProgress {
+                            current: BlockNumber(relay.next_number.0 - 1),
+                            target: target_number,
+                        },
+                    );
+                }
+            }
+            if relay.next_number > target_number {
+                break;
+            }
+        }
+    }
+
+    // The sync thread may have already stopped (node already synchronized)
+    let _ = sender_sync_thread.send(MessForSyncThread::DownloadFinish());
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn relay_blocks_in_order() {
+        let (sender, receiver) = mpsc::channel();
+        let mut relay = BlocksRelay {
+            next_number: BlockNumber(1),
+            pending_blocks: BTreeMap::new(),
+            sender_sync_thread: &sender,
+        };
+        let mut blocks = dubp_blocks_tests_tools::mocks::gen_empty_timed_blocks_v10(5, 300);
+        let last_blocks = blocks.split_off(3);
+
+        // Blocks #3 and #4 are buffered until block #2 is received
+        relay.push(last_blocks);
+        assert_eq!(BlockNumber(1), relay.next_number);
+        relay.push(blocks);
+        assert_eq!(BlockNumber(5), relay.next_number);
+
+        let relayed_numbers: Vec<BlockNumber> = receiver
+            .try_iter()
+            .filter_map(|mess| {
+                if let MessForSyncThread::BlockDocument(block) = mess {
+                    Some(block.number())
+                } else {
+                    None
+                }
+            })
+            .collect();
+        assert_eq!((1..5).map(BlockNumber).collect::<Vec<_>>(), relayed_numbers);
+    }
+}
@@ -17,6 +17,7 @@ mod apply;
 mod download;
 
 use crate::*;
+use apply::timings::{ApplyStage, ApplyTimings, ApplyTimingsReport};
 use apply::BlockApplicator;
 use dubp_block_doc::block::BlockDocumentTrait;
 use dubp_common_doc::Blockstamp;
@@ -26,11 +27,13 @@ use dup_crypto::keys::*;
 use durs_bc_db_reader::BcDbRead;
 use durs_bc_db_writer::writers::requests::*;
 use durs_common_tools::fatal_error;
+use durs_common_tools::fns::thread::{child_thread_name, spawn_child};
+use durs_network_documents::url::Url;
 use durs_wot::WotId;
 use failure::Fail;
 use pbr::ProgressBar;
 use std::collections::{HashMap, VecDeque};
-use std::sync::mpsc;
+use std::sync::{mpsc, Arc};
 use std::time::Instant;
 use std::{fs, thread};
 use threadpool::ThreadPool;
@@ -88,6 +91,9 @@ pub enum LocalSyncError {
         expected: CurrencyName,
         found: CurrencyName,
     },
+    /// The currency to synchronize is unknown
+    #[fail(display = "Unknown currency, please specify it with the --currency option.")]
+    UnknownCurrency,
 }
 
 /// Sync from local json files
@@ -125,18 +131,15 @@ pub fn local_sync<DC: DursConfTrait>(
     let (sender_sync_thread, recv_sync_thread) = mpsc::channel();
 
     // Create ThreadPool
-    let nb_cpus = num_cpus::get();
-    let nb_workers = if nb_cpus < *NB_SYNC_JOBS {
-        nb_cpus
-    } else {
-        *NB_SYNC_JOBS
-    };
-    let pool = ThreadPool::new(nb_workers);
+    let pool = sync_thread_pool();
 
     if !json_files_path.is_dir() {
         fatal_error!("json_files_path must be a directory");
     }
 
+    // Stages timings
+    let timings = Arc::new(ApplyTimings::default());
+
     // Lauch json reader worker
     download::json_reader_worker::json_reader_worker(
         &pool,
@@ -144,9 +147,111 @@ pub fn local_sync<DC: DursConfTrait>(
         sender_sync_thread.clone(),
         json_files_path,
         end,
+        timings.clone(),
     );
 
     // Get target blockstamp and target currency
+    let (currency, target_blockstamp) = recv_target(currency, &recv_sync_thread)?;
+
+    // Update DursConf
+    let mut conf = conf.clone();
+    conf.set_currency(currency.clone());
+
+    // Write new conf
+    let mut conf_path = profile_path.clone();
+    conf_path.push(durs_conf::constants::CONF_FILENAME);
+    durs_conf::file::write_conf_file(conf_path.as_path(), &conf).expect("Fail to write new conf !");
+
+    apply_blocks(
+        &pool,
+        profile_path,
+        currency,
+        target_blockstamp,
+        source,
+        unsafe_mode,
+        sender_sync_thread,
+        &recv_sync_thread,
+        timings,
+    )
+}
+
+/// Sync from the blocks chunks downloaded by the sync network module
+pub fn network_sync(
+    bc: &BlockchainModule,
+    blockchain_receiver: &Receiver<DursMsg>,
+    sync_opts: SyncOpt,
+) -> Result<(), LocalSyncError> {
+    let SyncOpt {
+        currency,
+        source,
+        unsafe_mode,
+        ..
+    } = sync_opts;
+
+    // Get currency
+    let currency = bc
+        .currency
+        .clone()
+        .or_else(|| currency.map(CurrencyName))
+        .ok_or(LocalSyncError::UnknownCurrency)?;
+
+    // Create sync_thread channels
+    let (sender_sync_thread, recv_sync_thread) = mpsc::channel();
+
+    // Create ThreadPool
+    let pool = sync_thread_pool();
+
+    // Launch apply thread
+    let profile_path = bc.profile_path.clone();
+    let currency_clone = currency.clone();
+    let sender_sync_thread_clone = sender_sync_thread.clone();
+    let apply_thread = spawn_child("sync_apply", move || {
+        let (currency, target_blockstamp) = recv_target(Some(&currency_clone), &recv_sync_thread)?;
+        apply_blocks(
+            &pool,
+            profile_path,
+            currency,
+            target_blockstamp,
+            source,
+            unsafe_mode,
+            sender_sync_thread_clone,
+            &recv_sync_thread,
+            Arc::new(ApplyTimings::default()),
+        )
+    });
+
+    // Relay the blocks received by the sync network module
+    download::network_worker::relay_network_blocks(
+        bc,
+        blockchain_receiver,
+        currency,
+        &sender_sync_thread,
+    );
+
+    apply_thread
+        .join()
+        .unwrap_or_else(|_| fatal_error!("Sync: apply thread panic !"))
+}
+
+/// Create sync jobs thread pool
+fn sync_thread_pool() -> ThreadPool {
+    let nb_cpus = num_cpus::get();
+    let nb_workers = if nb_cpus < *NB_SYNC_JOBS {
+        nb_cpus
+    } else {
+        *NB_SYNC_JOBS
+    };
+    threadpool::Builder::new()
+        .num_threads(nb_workers)
+        .thread_name(child_thread_name("sync_job"))
+        .build()
+}
+
+/// Receive target blockstamp and check the consistency between currency and target currency
+fn recv_target(
+    currency: Option<&CurrencyName>,
+    recv_sync_thread: &Receiver<MessForSyncThread>,
+) -> Result<(CurrencyName, Blockstamp), LocalSyncError> {
     let (target_currency, target_blockstamp) =
         if let Ok(MessForSyncThread::Target(target_currency, target_blockstamp)) =
             recv_sync_thread.recv()
@@ -156,32 +261,33 @@ pub fn local_sync<DC: DursConfTrait>(
             fatal_error!("Fatal error : no target blockstamp !");
         };
 
-    // Check the consistency between currency and target_currency
-    let currency = if let Some(currency) = currency {
-        if currency == &target_currency {
-            target_currency
-        } else {
+    if let Some(currency) = currency {
+        if currency != &target_currency {
             return Err(LocalSyncError::InvalidTargetCurrency {
                 expected: currency.clone(),
                 found: target_currency,
             });
         }
-    } else {
-        target_currency
-    };
-
-    // Update DursConf
-    let mut conf = conf.clone();
-    conf.set_currency(currency.clone());
+    }
+    Ok((target_currency, target_blockstamp))
+}
 
+/// Apply the blocks received from the download worker
+#[allow(clippy::too_many_arguments)]
+fn apply_blocks(
+    pool: &ThreadPool,
+    profile_path: PathBuf,
+    currency: CurrencyName,
+    target_blockstamp: Blockstamp,
+    source: Option<Url>,
+    unsafe_mode: bool,
+    sender_sync_thread: Sender<MessForSyncThread>,
+    recv_sync_thread: &Receiver<MessForSyncThread>,
+    timings: Arc<ApplyTimings>,
+) -> Result<(), LocalSyncError> {
     // Get databases path
     let db_path = durs_conf::get_blockchain_db_path(profile_path.clone());
 
-    // Write new conf
-    let mut conf_path = profile_path.clone();
-    conf_path.push(durs_conf::constants::CONF_FILENAME);
-    durs_conf::file::write_conf_file(conf_path.as_path(), &conf).expect("Fail to write new conf !");
-
     // Open database
     let db = open_db(&db_path.as_path()).map_err(|_| LocalSyncError::FailToOpenDB)?;
 
@@ -237,28 +343,31 @@ pub fn local_sync<DC: DursConfTrait>(
 
     // Launch blocks_worker thread
     apply::blocks_worker::execute(
-        &pool,
+        pool,
         sender_sync_thread.clone(),
         recv_blocks_thread,
         db,
         target_blockstamp,
         apply_pb,
+        timings.clone(),
     );
 
     // / Launch wot_worker thread
     apply::wot_worker::execute(
-        &pool,
+        pool,
         profile_path.clone(),
         sender_sync_thread.clone(),
         recv_wot_thread,
+        timings.clone(),
     );
 
     // Launch tx_worker thread
     apply::txs_worker::execute(
-        &pool,
+        pool,
         profile_path.clone(),
         sender_sync_thread,
         recv_tx_thread,
+        timings.clone(),
     );
 
     let main_job_begin = Instant::now();
@@ -289,10 +398,16 @@ pub fn local_sync<DC: DursConfTrait>(
         all_wait_duration: Duration::from_millis(0),
         all_verif_block_hashs_duration: Duration::from_millis(0),
         all_apply_valid_block_duration: Duration::from_millis(0),
+        timings,
+        timings_report: ApplyTimingsReport::default(),
+        timings_window_blocks_count: 0,
+        timings_file_path: durs_conf::get_datas_path(profile_path.clone())
+            .join(APPLY_TIMINGS_FILENAME),
     };
 
     // main loop
     let mut got_currency_params = false;
+    let mut chunk = Vec::with_capacity(*crate::constants::CHUNK_SIZE);
     while let Ok(MessForSyncThread::BlockDocument(block_doc)) = recv_sync_thread.recv() {
         // Get and write currency params
         if !got_currency_params {
@@ -328,7 +443,17 @@ pub fn local_sync<DC: DursConfTrait>(
             }
         }
 
-        block_applicator.apply(block_doc);
+        chunk.push(block_doc);
+        if chunk.len() >= *crate::constants::CHUNK_SIZE {
+            block_applicator.apply_chunk(std::mem::replace(
+                &mut chunk,
+                Vec::with_capacity(*crate::constants::CHUNK_SIZE),
+            ));
+        }
+    }
+    // Apply last chunk
+    if !chunk.is_empty() {
+        block_applicator.apply_chunk(chunk);
     }
 
     // Send end signal to workers threads
@@ -350,9 +475,10 @@ pub fn local_sync<DC: DursConfTrait>(
 
     // Save wot db
     block_applicator
-        .wot_databases
-        .wot_db
-        .save()
+        .timings
+        .measure(ApplyStage::Fsync, || {
+            block_applicator.wot_databases.wot_db.save()
+        })
         .expect("Fail to save wot db");
 
     let main_job_duration = main_job_begin.elapsed() - block_applicator.all_wait_duration;
@@ -395,8 +521,11 @@ pub fn local_sync<DC: DursConfTrait>(
 
     // Save blockchain DB
     if let Some(db) = db {
-        db.save()
+        block_applicator
+            .timings
+            .measure(ApplyStage::Fsync, || db.save())
             .unwrap_or_else(|_| fatal_error!("DB corrupted, please reset data."));
+        block_applicator.flush_timings();
     } else {
         fatal_error!("Dev error: sync workers didn't return the DB.")
     }
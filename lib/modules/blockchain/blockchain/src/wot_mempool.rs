@@ -0,0 +1,750 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Sub-module managing the pending identities, memberships and certifications (WotPool role).
+
+use crate::mempool::blockstamp_and_current_times;
+use crate::*;
+use dubp_block_doc::block::BlockDocumentV10;
+use dubp_common_doc::traits::text::{CompactTextDocument, TextDocument};
+use dubp_user_docs::documents::certification::{CertificationDocument, CertificationDocumentV10};
+use dubp_user_docs::documents::identity::{IdentityDocument, IdentityDocumentV10};
+use dubp_user_docs::documents::membership::{MembershipDocument, MembershipDocumentV10};
+use dubp_user_docs::documents::UserDocumentDUBP;
+use dup_crypto::hashs::Hash;
+use durs_conf::SandboxesConf;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Reason why a wot document is not admitted in the mempool
+pub enum WotMemPoolError {
+    /// The document is already pending
+    AlreadyPending,
+    /// The document blockstamp is older than its window
+    Expired,
+    /// The username or the public key of the identity is already used
+    Collision(IdtyCollision),
+    /// The issuer has too many pending documents of this kind
+    IssuerQuota(PubKey),
+}
+
+impl fmt::Display for WotMemPoolError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            WotMemPoolError::AlreadyPending => write!(f, "the document is already pending"),
+            WotMemPoolError::Expired => write!(f, "the document blockstamp is too old"),
+            WotMemPoolError::Collision(collision) => write!(f, "{}", collision),
+            WotMemPoolError::IssuerQuota(issuer) => write!(
+                f,
+                "the issuer {} has too many pending documents of this kind",
+                issuer
+            ),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Maximum age of the pending wot documents blockstamps (in seconds)
+pub struct WotWindows {
+    /// Identities window
+    pub idty_window: u64,
+    /// Memberships window
+    pub ms_window: u64,
+    /// Certifications window
+    pub sig_window: u64,
+}
+
+impl From<&CurrencyParameters> for WotWindows {
+    fn from(currency_params: &CurrencyParameters) -> Self {
+        WotWindows {
+            idty_window: currency_params.idty_window,
+            ms_window: currency_params.ms_window,
+            sig_window: currency_params.sig_window,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+/// Pending wot document
+struct PendingDoc<D> {
+    /// Document
+    doc: D,
+    /// Median time of the block referenced by the document blockstamp
+    blockstamp_time: u64,
+    /// Submission order of the document (the last submission, if it was submitted again)
+    submission: u64,
+}
+
+impl<D> PendingDoc<D> {
+    fn is_expired(&self, current_median_time: u64, window: u64) -> bool {
+        self.blockstamp_time + window < current_median_time
+    }
+}
+
+/// Evict the least recently submitted documents until there is room for a new one
+fn make_room<K: Clone + Eq + std::hash::Hash, D>(
+    docs: &mut HashMap<K, PendingDoc<D>>,
+    max_docs: usize,
+) {
+    while !docs.is_empty() && docs.len() >= max_docs {
+        if let Some(evicted_key) = docs
+            .iter()
+            .min_by_key(|(_, pending)| pending.submission)
+            .map(|(key, _)| key.clone())
+        {
+            docs.remove(&evicted_key);
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+/// Pending wot documents, identities and memberships are indexed by hash,
+/// certifications by (issuer, target)
+pub struct WotMemPool {
+    identities: HashMap<Hash, PendingDoc<IdentityDocumentV10>>,
+    memberships: HashMap<Hash, PendingDoc<MembershipDocumentV10>>,
+    certifications: HashMap<(PubKey, PubKey), PendingDoc<CertificationDocumentV10>>,
+    sandboxes: SandboxesConf,
+    submissions_count: u64,
+}
+
+/// Hash of the compact text of a document
+pub(crate) fn doc_hash<D: TextDocument>(doc: &D) -> Hash {
+    Hash::compute_str(&doc.to_compact_document().as_compact_text())
+}
+
+impl WotMemPool {
+    /// Empty mempool whose size is limited by `sandboxes`
+    pub fn new(sandboxes: SandboxesConf) -> WotMemPool {
+        WotMemPool {
+            sandboxes,
+            ..WotMemPool::default()
+        }
+    }
+    /// Add a pending identity, `blockstamp_time` is the median time of the block referenced
+    /// by its blockstamp.
+    /// If the identities pool is full, the least recently submitted identity is evicted.
+    pub fn add_identity(
+        &mut self,
+        idty: IdentityDocumentV10,
+        blockstamp_time: u64,
+        current_median_time: u64,
+        windows: WotWindows,
+    ) -> Result<(), WotMemPoolError> {
+        let hash = doc_hash(&idty);
+        self.submissions_count += 1;
+        if let Some(pending) = self.identities.get_mut(&hash) {
+            pending.submission = self.submissions_count;
+            return Err(WotMemPoolError::AlreadyPending);
+        }
+        if self.identities.values().any(|pending| {
+            pending.doc.issuers()[0] == idty.issuers()[0]
+                && pending.doc.username() == idty.username()
+        }) {
+            return Err(WotMemPoolError::AlreadyPending);
+        }
+        if self.is_uid_pending(idty.username()) {
+            return Err(WotMemPoolError::Collision(IdtyCollision::UidPending));
+        }
+        if self.is_pubkey_pending(&idty.issuers()[0]) {
+            return Err(WotMemPoolError::Collision(IdtyCollision::PubkeyPending));
+        }
+        let pending = PendingDoc {
+            doc: idty,
+            blockstamp_time,
+            submission: self.submissions_count,
+        };
+        if pending.is_expired(current_median_time, windows.idty_window) {
+            return Err(WotMemPoolError::Expired);
+        }
+        make_room(&mut self.identities, self.sandboxes.max_identities);
+        self.identities.insert(hash, pending);
+        Ok(())
+    }
+    /// Number of pending identities
+    pub fn identities_count(&self) -> usize {
+        self.identities.len()
+    }
+    /// Number of pending memberships
+    pub fn memberships_count(&self) -> usize {
+        self.memberships.len()
+    }
+    /// Number of pending certifications
+    pub fn certifications_count(&self) -> usize {
+        self.certifications.len()
+    }
+    /// Is the username used by a pending identity ?
+    pub fn is_uid_pending(&self, uid: &str) -> bool {
+        self.identities
+            .values()
+            .any(|pending| pending.doc.username() == uid)
+    }
+    /// Is the public key used by a pending identity ?
+    pub fn is_pubkey_pending(&self, pubkey: &PubKey) -> bool {
+        self.identities
+            .values()
+            .any(|pending| pending.doc.issuers()[0] == *pubkey)
+    }
+    /// Add a pending membership, `blockstamp_time` is the median time of the block referenced
+    /// by its blockstamp.
+    /// If the memberships pool is full, the least recently submitted membership is evicted.
+    pub fn add_membership(
+        &mut self,
+        membership: MembershipDocumentV10,
+        blockstamp_time: u64,
+        current_median_time: u64,
+        windows: WotWindows,
+    ) -> Result<(), WotMemPoolError> {
+        let hash = doc_hash(&membership);
+        self.submissions_count += 1;
+        if let Some(pending) = self.memberships.get_mut(&hash) {
+            pending.submission = self.submissions_count;
+            return Err(WotMemPoolError::AlreadyPending);
+        }
+        let issuer = membership.issuers()[0];
+        if self
+            .memberships
+            .values()
+            .filter(|pending| pending.doc.issuers()[0] == issuer)
+            .count()
+            >= self.sandboxes.max_memberships_per_issuer
+        {
+            return Err(WotMemPoolError::IssuerQuota(issuer));
+        }
+        let pending = PendingDoc {
+            doc: membership,
+            blockstamp_time,
+            submission: self.submissions_count,
+        };
+        if pending.is_expired(current_median_time, windows.ms_window) {
+            return Err(WotMemPoolError::Expired);
+        }
+        make_room(&mut self.memberships, self.sandboxes.max_memberships);
+        self.memberships.insert(hash, pending);
+        Ok(())
+    }
+    /// Add a pending certification, `blockstamp_time` is the median time of the block referenced
+    /// by its blockstamp.
+    /// Only one certification per issuer and target can be pending. If the certifications pool
+    /// is full, the least recently submitted certification is evicted.
+    pub fn add_certification(
+        &mut self,
+        cert: CertificationDocumentV10,
+        blockstamp_time: u64,
+        current_median_time: u64,
+        windows: WotWindows,
+    ) -> Result<(), WotMemPoolError> {
+        let key = (*cert.source(), *cert.target());
+        self.submissions_count += 1;
+        if let Some(pending) = self.certifications.get_mut(&key) {
+            pending.submission = self.submissions_count;
+            return Err(WotMemPoolError::AlreadyPending);
+        }
+        if self
+            .certifications
+            .keys()
+            .filter(|(issuer, _)| *issuer == key.0)
+            .count()
+            >= self.sandboxes.max_certifications_per_issuer
+        {
+            return Err(WotMemPoolError::IssuerQuota(key.0));
+        }
+        let pending = PendingDoc {
+            doc: cert,
+            blockstamp_time,
+            submission: self.submissions_count,
+        };
+        if pending.is_expired(current_median_time, windows.sig_window) {
+            return Err(WotMemPoolError::Expired);
+        }
+        make_room(&mut self.certifications, self.sandboxes.max_certifications);
+        self.certifications.insert(key, pending);
+        Ok(())
+    }
+    /// Remove the documents written in a new current block,
+    /// and those expired at the median time of the block
+    pub fn apply_block(&mut self, block: &BlockDocumentV10, windows: WotWindows) {
+        for idty in &block.identities {
+            self.identities.remove(&doc_hash(idty));
+        }
+        for membership in block
+            .joiners
+            .iter()
+            .chain(block.actives.iter())
+            .chain(block.leavers.iter())
+        {
+            self.memberships.remove(&doc_hash(membership));
+        }
+        for cert in &block.certifications {
+            let compact_cert = cert.to_compact_document();
+            self.certifications
+                .remove(&(compact_cert.issuer, compact_cert.target));
+        }
+        self.expire(block.median_time, windows);
+    }
+    /// Remove the documents expired at `current_median_time`
+    pub fn expire(&mut self, current_median_time: u64, windows: WotWindows) {
+        self.identities
+            .retain(|_, pending| !pending.is_expired(current_median_time, windows.idty_window));
+        self.memberships
+            .retain(|_, pending| !pending.is_expired(current_median_time, windows.ms_window));
+        self.certifications
+            .retain(|_, pending| !pending.is_expired(current_median_time, windows.sig_window));
+    }
+    /// All pending documents, oldest blockstamp first in each kind of document
+    pub fn pending_documents(&self) -> Vec<UserDocumentDUBP> {
+        let mut identities: Vec<&IdentityDocumentV10> = self
+            .identities
+            .values()
+            .map(|pending| &pending.doc)
+            .collect();
+        identities.sort_by_key(|idty| idty.blockstamp());
+        let mut memberships: Vec<&MembershipDocumentV10> = self
+            .memberships
+            .values()
+            .map(|pending| &pending.doc)
+            .collect();
+        memberships.sort_by_key(|membership| membership.blockstamp());
+        let mut certifications: Vec<&CertificationDocumentV10> = self
+            .certifications
+            .values()
+            .map(|pending| &pending.doc)
+            .collect();
+        certifications.sort_by_key(|cert| cert.blockstamp());
+
+        identities
+            .into_iter()
+            .map(|idty| UserDocumentDUBP::Identity(IdentityDocument::V10(idty.clone())))
+            .chain(memberships.into_iter().map(|membership| {
+                UserDocumentDUBP::Membership(MembershipDocument::V10(membership.clone()))
+            }))
+            .chain(certifications.into_iter().map(|cert| {
+                UserDocumentDUBP::Certification(Box::new(CertificationDocument::V10(cert.clone())))
+            }))
+            .collect()
+    }
+    /// Aggregated pending datas of an identity
+    fn pending_idty_datas(&self, idty: &IdentityDocumentV10, with_certs: bool) -> PendingIdtyDatas {
+        let pubkey = idty.issuers()[0];
+        let memberships = self
+            .memberships
+            .values()
+            .filter(|pending| {
+                pending.doc.issuers()[0] == pubkey
+                    && pending.doc.identity_username() == idty.username()
+            })
+            .map(|pending| MembershipDocument::V10(pending.doc.clone()))
+            .collect();
+        let certs: Vec<CertificationDocument> = self
+            .certifications
+            .values()
+            .filter(|pending| {
+                *pending.doc.target() == pubkey
+                    && pending.doc.identity_username() == idty.username()
+            })
+            .map(|pending| CertificationDocument::V10(pending.doc.clone()))
+            .collect();
+        PendingIdtyDatas {
+            idty: IdentityDocument::V10(idty.clone()),
+            memberships,
+            certs_count: certs.len(),
+            certs: if with_certs { certs } else { vec![] },
+            revocation: None,
+        }
+    }
+    /// Pending identities having at least `min_certs` pending certifications
+    pub fn pending_identities(
+        &self,
+        min_certs: usize,
+        with_certs: bool,
+    ) -> HashMap<Hash, PendingIdtyDatas> {
+        self.identities
+            .iter()
+            .map(|(hash, pending)| (*hash, self.pending_idty_datas(&pending.doc, with_certs)))
+            .filter(|(_, datas)| datas.certs_count >= min_certs)
+            .collect()
+    }
+    /// Pending datas of the identity of `pubkey`
+    pub fn pending_wot_datas_for_pubkey(&self, pubkey: &PubKey) -> Option<PendingIdtyDatas> {
+        self.identities
+            .values()
+            .find(|pending| pending.doc.issuers()[0] == *pubkey)
+            .map(|pending| self.pending_idty_datas(&pending.doc, true))
+    }
+}
+
+/// Times needed to admit a wot document in the mempool, `None` if the currency parameters
+/// or the document blockstamp are unknown
+fn admission_times(
+    bc: &BlockchainModule,
+    blockstamp: Blockstamp,
+) -> Option<(u64, u64, WotWindows)> {
+    let windows = WotWindows::from(bc.currency_params.as_ref()?);
+    let (blockstamp_time, current_median_time) = blockstamp_and_current_times(bc, blockstamp)?;
+    Some((blockstamp_time, current_median_time, windows))
+}
+
+/// Check that a username and a public key are used neither by an identity of the blockchain
+/// nor by a pending identity
+pub fn check_idty_availability(
+    bc: &BlockchainModule,
+    uid: Option<&str>,
+    pubkey: Option<&PubKey>,
+) -> Result<(), IdtyCollision> {
+    if let Some(pubkey) = pubkey {
+        if bc.wot_index.contains_key(pubkey) {
+            return Err(IdtyCollision::PubkeyInBlockchain);
+        }
+        if bc.wot_mempool.is_pubkey_pending(pubkey) {
+            return Err(IdtyCollision::PubkeyPending);
+        }
+    }
+    if let Some(uid) = uid {
+        if bc
+            .db()
+            .r(|db_r| durs_bc_db_reader::indexes::identities::get_wot_id_from_uid(db_r, uid))
+            .unwrap_or_else(|e| fatal_error!("Fail to read blockchain DB: {:?}", e))
+            .is_some()
+        {
+            return Err(IdtyCollision::UidInBlockchain);
+        }
+        if bc.wot_mempool.is_uid_pending(uid) {
+            return Err(IdtyCollision::UidPending);
+        }
+    }
+    Ok(())
+}
+
+/// Submit an identity received from the network to the mempool
+pub fn receive_identity(bc: &mut BlockchainModule, idty: IdentityDocumentV10) {
+    let blockstamp = idty.blockstamp();
+    let hash = doc_hash(&idty);
+    if let Err(collision) =
+        check_idty_availability(bc, Some(idty.username()), Some(&idty.issuers()[0]))
+    {
+        debug!("Reject identity: {}", collision);
+        events::sent::send_document_rejected(bc, hash, collision.to_string());
+        return;
+    }
+    if let Some((blockstamp_time, current_median_time, windows)) = admission_times(bc, blockstamp) {
+        if let Err(e) =
+            bc.wot_mempool
+                .add_identity(idty, blockstamp_time, current_median_time, windows)
+        {
+            debug!("Reject identity: {:?}", e);
+            events::sent::send_document_rejected(bc, hash, e.to_string());
+        }
+    } else {
+        debug!("Reject identity: unknown blockstamp {}", blockstamp);
+        events::sent::send_document_rejected(bc, hash, unknown_blockstamp_reason(blockstamp));
+    }
+}
+
+/// Rejection reason of a document whose blockstamp is not in the local blockchain
+fn unknown_blockstamp_reason(blockstamp: Blockstamp) -> String {
+    format!("unknown blockstamp {}", blockstamp)
+}
+
+/// Submit a membership received from the network to the mempool
+pub fn receive_membership(bc: &mut BlockchainModule, membership: MembershipDocumentV10) {
+    let blockstamp = membership.blockstamp();
+    let hash = doc_hash(&membership);
+    if let Some((blockstamp_time, current_median_time, windows)) = admission_times(bc, blockstamp) {
+        if let Err(e) =
+            bc.wot_mempool
+                .add_membership(membership, blockstamp_time, current_median_time, windows)
+        {
+            debug!("Reject membership: {:?}", e);
+            events::sent::send_document_rejected(bc, hash, e.to_string());
+        }
+    } else {
+        debug!("Reject membership: unknown blockstamp {}", blockstamp);
+        events::sent::send_document_rejected(bc, hash, unknown_blockstamp_reason(blockstamp));
+    }
+}
+
+/// Submit a certification received from the network to the mempool
+pub fn receive_certification(bc: &mut BlockchainModule, cert: CertificationDocumentV10) {
+    let blockstamp = cert.blockstamp();
+    let hash = doc_hash(&cert);
+    if let Some((blockstamp_time, current_median_time, windows)) = admission_times(bc, blockstamp) {
+        if let Err(e) =
+            bc.wot_mempool
+                .add_certification(cert, blockstamp_time, current_median_time, windows)
+        {
+            debug!("Reject certification: {:?}", e);
+            events::sent::send_document_rejected(bc, hash, e.to_string());
+        }
+    } else {
+        debug!("Reject certification: unknown blockstamp {}", blockstamp);
+        events::sent::send_document_rejected(bc, hash, unknown_blockstamp_reason(blockstamp));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use dubp_common_doc::traits::DocumentBuilder;
+    use dubp_common_doc::{BlockHash, BlockNumber};
+    use dubp_user_docs::documents::certification::v10::CertificationDocumentV10Builder;
+    use dubp_user_docs::documents::identity::v10::IdentityDocumentV10Builder;
+    use dubp_user_docs::documents::membership::v10::{
+        MembershipDocumentV10Builder, MembershipType,
+    };
+
+    const WINDOWS: WotWindows = WotWindows {
+        idty_window: 100,
+        ms_window: 50,
+        sig_window: 200,
+    };
+
+    fn blockstamp(block_number: u32) -> Blockstamp {
+        Blockstamp {
+            id: BlockNumber(block_number),
+            hash: BlockHash(Hash::default()),
+        }
+    }
+
+    fn sig() -> Sig {
+        Sig::Ed25519(ed25519::Signature([0u8; 64]))
+    }
+
+    fn gen_idty(issuer: char, uid: &str) -> IdentityDocumentV10 {
+        IdentityDocumentV10Builder {
+            currency: "test",
+            username: uid,
+            blockstamp: &blockstamp(1),
+            issuer: &dup_crypto_tests_tools::mocks::pubkey(issuer),
+        }
+        .build_with_signature(vec![sig()])
+    }
+
+    fn gen_membership(issuer: char, uid: &str, block_number: u32) -> MembershipDocumentV10 {
+        MembershipDocumentV10Builder {
+            currency: "test",
+            issuer: &dup_crypto_tests_tools::mocks::pubkey(issuer),
+            blockstamp: &blockstamp(block_number),
+            membership: MembershipType::In(),
+            identity_username: uid,
+            identity_blockstamp: &blockstamp(1),
+        }
+        .build_with_signature(vec![sig()])
+    }
+
+    fn gen_cert(issuer: char, target: char, uid: &str) -> CertificationDocumentV10 {
+        CertificationDocumentV10Builder {
+            currency: "test",
+            issuer: &dup_crypto_tests_tools::mocks::pubkey(issuer),
+            blockstamp: &blockstamp(2),
+            target: &dup_crypto_tests_tools::mocks::pubkey(target),
+            identity_username: uid,
+            identity_blockstamp: &blockstamp(1),
+            identity_sig: &sig(),
+        }
+        .build_with_signature(vec![sig()])
+    }
+
+    #[test]
+    fn test_wot_mempool_dedup() {
+        let mut mempool = WotMemPool::default();
+
+        assert_eq!(
+            Ok(()),
+            mempool.add_identity(gen_idty('A', "alice"), 100, 100, WINDOWS)
+        );
+        assert_eq!(
+            Err(WotMemPoolError::AlreadyPending),
+            mempool.add_identity(gen_idty('A', "alice"), 100, 100, WINDOWS)
+        );
+        assert_eq!(
+            Ok(()),
+            mempool.add_membership(gen_membership('A', "alice", 2), 100, 100, WINDOWS)
+        );
+        assert_eq!(
+            Err(WotMemPoolError::AlreadyPending),
+            mempool.add_membership(gen_membership('A', "alice", 2), 100, 100, WINDOWS)
+        );
+        assert_eq!(
+            Ok(()),
+            mempool.add_certification(gen_cert('B', 'A', "alice"), 100, 100, WINDOWS)
+        );
+        assert_eq!(
+            Err(WotMemPoolError::AlreadyPending),
+            mempool.add_certification(gen_cert('B', 'A', "alice"), 100, 100, WINDOWS)
+        );
+        assert_eq!(3, mempool.pending_documents().len());
+        assert_eq!(
+            (1, 1, 1),
+            (
+                mempool.identities_count(),
+                mempool.memberships_count(),
+                mempool.certifications_count()
+            )
+        );
+    }
+
+    #[test]
+    fn test_wot_mempool_quotas() {
+        let mut mempool = WotMemPool::new(SandboxesConf {
+            max_identities: 2,
+            max_certifications_per_issuer: 1,
+            ..SandboxesConf::default()
+        });
+
+        assert_eq!(
+            Ok(()),
+            mempool.add_certification(gen_cert('B', 'A', "alice"), 100, 100, WINDOWS)
+        );
+        assert_eq!(
+            Err(WotMemPoolError::IssuerQuota(
+                dup_crypto_tests_tools::mocks::pubkey('B')
+            )),
+            mempool.add_certification(gen_cert('B', 'C', "carol"), 100, 100, WINDOWS)
+        );
+
+        // The identities pool is full: the least recently submitted identity is evicted
+        let idty_a = gen_idty('A', "alice");
+        assert_eq!(
+            Ok(()),
+            mempool.add_identity(idty_a.clone(), 100, 100, WINDOWS)
+        );
+        assert_eq!(
+            Ok(()),
+            mempool.add_identity(gen_idty('C', "carol"), 100, 100, WINDOWS)
+        );
+        assert_eq!(
+            Err(WotMemPoolError::AlreadyPending),
+            mempool.add_identity(idty_a, 100, 100, WINDOWS)
+        );
+        assert_eq!(
+            Ok(()),
+            mempool.add_identity(gen_idty('D', "dave"), 100, 100, WINDOWS)
+        );
+        assert_eq!(2, mempool.identities_count());
+        assert!(mempool.is_uid_pending("alice"));
+        assert!(!mempool.is_uid_pending("carol"));
+    }
+
+    #[test]
+    fn test_wot_mempool_idty_collisions() {
+        let mut mempool = WotMemPool::default();
+
+        assert_eq!(
+            Ok(()),
+            mempool.add_identity(gen_idty('A', "alice"), 100, 100, WINDOWS)
+        );
+        assert_eq!(
+            Err(WotMemPoolError::Collision(IdtyCollision::UidPending)),
+            mempool.add_identity(gen_idty('B', "alice"), 100, 100, WINDOWS)
+        );
+        assert_eq!(
+            Err(WotMemPoolError::Collision(IdtyCollision::PubkeyPending)),
+            mempool.add_identity(gen_idty('A', "alice2"), 100, 100, WINDOWS)
+        );
+        assert!(mempool.is_uid_pending("alice"));
+        assert!(!mempool.is_uid_pending("bob"));
+        assert!(mempool.is_pubkey_pending(&dup_crypto_tests_tools::mocks::pubkey('A')));
+        assert!(!mempool.is_pubkey_pending(&dup_crypto_tests_tools::mocks::pubkey('B')));
+    }
+
+    #[test]
+    fn test_wot_mempool_windows() {
+        let mut mempool = WotMemPool::default();
+
+        assert_eq!(
+            Err(WotMemPoolError::Expired),
+            mempool.add_identity(gen_idty('A', "alice"), 100, 201, WINDOWS)
+        );
+        assert_eq!(
+            Ok(()),
+            mempool.add_identity(gen_idty('A', "alice"), 100, 200, WINDOWS)
+        );
+        assert_eq!(
+            Err(WotMemPoolError::Expired),
+            mempool.add_membership(gen_membership('A', "alice", 2), 100, 151, WINDOWS)
+        );
+        assert_eq!(
+            Ok(()),
+            mempool.add_membership(gen_membership('A', "alice", 3), 120, 151, WINDOWS)
+        );
+        assert_eq!(
+            Ok(()),
+            mempool.add_certification(gen_cert('B', 'A', "alice"), 100, 151, WINDOWS)
+        );
+
+        // The membership expires first, then the identity, then the certification
+        mempool.expire(171, WINDOWS);
+        assert_eq!(2, mempool.pending_documents().len());
+        mempool.expire(201, WINDOWS);
+        assert_eq!(1, mempool.pending_documents().len());
+        mempool.expire(301, WINDOWS);
+        assert!(mempool.pending_documents().is_empty());
+    }
+
+    #[test]
+    fn test_wot_mempool_pending_identities() {
+        let mut mempool = WotMemPool::default();
+        let idty_a = gen_idty('A', "alice");
+        let idty_a_hash = doc_hash(&idty_a);
+
+        assert_eq!(
+            Ok(()),
+            mempool.add_identity(idty_a.clone(), 100, 100, WINDOWS)
+        );
+        assert_eq!(
+            Ok(()),
+            mempool.add_identity(gen_idty('C', "carol"), 100, 100, WINDOWS)
+        );
+        let membership = gen_membership('A', "alice", 2);
+        assert_eq!(
+            Ok(()),
+            mempool.add_membership(membership.clone(), 100, 100, WINDOWS)
+        );
+        let cert_b = gen_cert('B', 'A', "alice");
+        assert_eq!(
+            Ok(()),
+            mempool.add_certification(cert_b.clone(), 100, 100, WINDOWS)
+        );
+
+        assert_eq!(2, mempool.pending_identities(0, true).len());
+
+        let pending_identities = mempool.pending_identities(1, true);
+        assert_eq!(1, pending_identities.len());
+        assert_eq!(
+            Some(&PendingIdtyDatas {
+                idty: IdentityDocument::V10(idty_a),
+                memberships: vec![MembershipDocument::V10(membership)],
+                certs_count: 1,
+                certs: vec![CertificationDocument::V10(cert_b)],
+                revocation: None,
+            }),
+            pending_identities.get(&idty_a_hash)
+        );
+
+        let without_certs = mempool.pending_identities(1, false);
+        assert_eq!(
+            Some(1),
+            without_certs.get(&idty_a_hash).map(|d| d.certs_count)
+        );
+        assert_eq!(
+            Some(true),
+            without_certs.get(&idty_a_hash).map(|d| d.certs.is_empty())
+        );
+        assert!(mempool
+            .pending_wot_datas_for_pubkey(&dup_crypto_tests_tools::mocks::pubkey('B'))
+            .is_none());
+    }
+}
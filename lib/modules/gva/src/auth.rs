@@ -0,0 +1,218 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Authentication of mutating graphql operations
+
+use actix_web::http::header::{HeaderMap, AUTHORIZATION};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+/// Credentials required to execute mutating graphql operations
+pub enum GvaAuthConf {
+    /// `Authorization: Bearer <token>`
+    Bearer {
+        /// Expected token
+        token: String,
+    },
+    /// `Authorization: Basic <base64(user:password)>`
+    Basic {
+        /// Expected user
+        user: String,
+        /// Expected password
+        password: String,
+    },
+}
+
+impl std::fmt::Display for GvaAuthConf {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        // Never display secrets
+        match self {
+            GvaAuthConf::Bearer { .. } => write!(f, "bearer"),
+            GvaAuthConf::Basic { ref user, .. } => write!(f, "basic (user: {})", user),
+        }
+    }
+}
+
+impl GvaAuthConf {
+    /// Parse basic auth credentials given as `user:password`
+    pub fn parse_basic(source: &str) -> Result<Self, String> {
+        let mut parts = source.splitn(2, ':');
+        match (parts.next(), parts.next()) {
+            (Some(user), Some(password)) if !user.is_empty() => Ok(GvaAuthConf::Basic {
+                user: user.to_owned(),
+                password: password.to_owned(),
+            }),
+            _ => Err(String::from("expected format user:password")),
+        }
+    }
+    /// Value expected in the `Authorization` header
+    pub(crate) fn expected_header_value(&self) -> String {
+        match self {
+            GvaAuthConf::Bearer { ref token } => format!("Bearer {}", token),
+            GvaAuthConf::Basic {
+                ref user,
+                ref password,
+            } => format!(
+                "Basic {}",
+                base64::encode(format!("{}:{}", user, password).as_bytes())
+            ),
+        }
+    }
+}
+
+/// Result of the authentication middleware, stored in request extensions
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct Authorized(pub bool);
+
+/// Check `Authorization` header against expected value
+pub(crate) fn check_headers(headers: &HeaderMap, expected_header_value: &str) -> bool {
+    if let Some(header_value) = headers.get(AUTHORIZATION) {
+        constant_time_eq(header_value.as_bytes(), expected_header_value.as_bytes())
+    } else {
+        false
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter()
+        .zip(b.iter())
+        .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+        == 0
+}
+
+/// Returns true if the graphql document contains a mutation operation.
+///
+/// Only the first keyword of each top-level definition is inspected,
+/// so it doesn't need the full graphql parser.
+pub(crate) fn is_mutation(query: &str) -> bool {
+    let mut chars = query.chars();
+    let mut braces_depth = 0usize;
+    let mut parens_depth = 0usize;
+    let mut expect_definition = true;
+    let mut word = String::new();
+
+    loop {
+        let c_opt = chars.next();
+        if let Some(c) = c_opt {
+            if c.is_alphanumeric() || c == '_' {
+                word.push(c);
+                continue;
+            }
+        }
+        if !word.is_empty() && braces_depth == 0 && parens_depth == 0 {
+            if expect_definition && word == "mutation" {
+                return true;
+            }
+            expect_definition = false;
+        }
+        word.clear();
+        match c_opt {
+            None => return false,
+            Some('{') => braces_depth += 1,
+            Some('}') => {
+                braces_depth = braces_depth.saturating_sub(1);
+                if braces_depth == 0 {
+                    expect_definition = true;
+                }
+            }
+            Some('(') => parens_depth += 1,
+            Some(')') => parens_depth = parens_depth.saturating_sub(1),
+            Some('#') => {
+                // Skip comment
+                for c in chars.by_ref() {
+                    if c == '\n' || c == '\r' {
+                        break;
+                    }
+                }
+            }
+            Some('"') => {
+                // Skip string
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => {
+                            chars.next();
+                        }
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            Some(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use actix_web::http::header::HeaderValue;
+
+    #[test]
+    fn test_is_mutation() {
+        assert!(!is_mutation("{ current { number } }"));
+        assert!(!is_mutation("query { current { number } }"));
+        assert!(!is_mutation(
+            "query mutation { mutation: current { number } }"
+        ));
+        assert!(!is_mutation(
+            "query Q($s: String = \"mutation {\") { block(number: 0) { hash } }"
+        ));
+        assert!(!is_mutation("# mutation { noop }\n{ apiVersion }"));
+        assert!(is_mutation("mutation { noop }"));
+        assert!(is_mutation("mutation Noop{noop}"));
+        assert!(is_mutation("query { apiVersion }\nmutation Noop { noop }"));
+    }
+
+    #[test]
+    fn test_parse_basic() {
+        assert_eq!(
+            Ok(GvaAuthConf::Basic {
+                user: "admin".to_owned(),
+                password: "pass:word".to_owned(),
+            }),
+            GvaAuthConf::parse_basic("admin:pass:word"),
+        );
+        assert!(GvaAuthConf::parse_basic("admin").is_err());
+        assert!(GvaAuthConf::parse_basic(":password").is_err());
+    }
+
+    #[test]
+    fn test_check_headers() {
+        let bearer = GvaAuthConf::Bearer {
+            token: "secret".to_owned(),
+        };
+        let basic = GvaAuthConf::Basic {
+            user: "admin".to_owned(),
+            password: "secret".to_owned(),
+        };
+        let mut headers = HeaderMap::new();
+        assert!(!check_headers(&headers, &bearer.expected_header_value()));
+
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer secret"));
+        assert!(check_headers(&headers, &bearer.expected_header_value()));
+        assert!(!check_headers(&headers, &basic.expected_header_value()));
+
+        headers.insert(
+            AUTHORIZATION,
+            HeaderValue::from_static("Basic YWRtaW46c2VjcmV0"),
+        );
+        assert!(check_headers(&headers, &basic.expected_header_value()));
+        assert!(!check_headers(&headers, &bearer.expected_header_value()));
+    }
+}
@@ -26,3 +26,8 @@ pub const MAX_PAGE_SIZE: i32 = 1_000;
 
 pub const BLOCK_INTERVAL_MIN_FROM: usize = 0;
 pub const BLOCK_INTERVAL_MAX_SIZE: usize = 500_000;
+
+pub const MODULE_RESPONSE_TIMEOUT_SECS: u64 = 10;
+
+/// Interval between two reloads of the module conf (to rebind the web server when its listen address changes)
+pub const CONF_RELOAD_INTERVAL_IN_SECS: u64 = 5;
@@ -16,24 +16,45 @@
 //! Context for graphql resolvers
 
 use crate::db::BcDbRo;
+use crate::known_endpoints::KnownGvaEndpoints;
+use crate::requester::ModuleRequester;
 use crate::schema::Schema;
+use dubp_currency_params::{CurrencyName, CurrencyParameters};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
 
 pub struct GlobalContext {
+    currency: Option<(CurrencyName, CurrencyParameters)>,
     db: &'static BcDbRo,
+    dbs_path: PathBuf,
+    known_gva_endpoints: Arc<RwLock<KnownGvaEndpoints>>,
+    network_map_file_path: PathBuf,
+    requester: Arc<ModuleRequester>,
     pub(crate) schema: Schema,
     software_name: &'static str,
     software_version: &'static str,
 }
 
 impl GlobalContext {
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
+        currency: Option<(CurrencyName, CurrencyParameters)>,
         db: &'static BcDbRo,
+        dbs_path: PathBuf,
+        known_gva_endpoints: Arc<RwLock<KnownGvaEndpoints>>,
+        network_map_file_path: PathBuf,
+        requester: Arc<ModuleRequester>,
         schema: Schema,
         software_name: &'static str,
         software_version: &'static str,
     ) -> Self {
         GlobalContext {
+            currency,
             db,
+            dbs_path,
+            known_gva_endpoints,
+            network_map_file_path,
+            requester,
             schema,
             software_name,
             software_version,
@@ -42,7 +63,12 @@ impl GlobalContext {
 }
 
 pub struct QueryContext {
+    currency: Option<(CurrencyName, CurrencyParameters)>,
     db: &'static BcDbRo,
+    dbs_path: PathBuf,
+    known_gva_endpoints: Arc<RwLock<KnownGvaEndpoints>>,
+    network_map_file_path: PathBuf,
+    requester: Arc<ModuleRequester>,
     software_name: &'static str,
     software_version: &'static str,
 }
@@ -52,7 +78,12 @@ impl juniper::Context for QueryContext {}
 impl From<&GlobalContext> for QueryContext {
     fn from(global_context: &GlobalContext) -> Self {
         QueryContext {
+            currency: global_context.currency.clone(),
             db: global_context.db,
+            dbs_path: global_context.dbs_path.clone(),
+            known_gva_endpoints: global_context.known_gva_endpoints.clone(),
+            network_map_file_path: global_context.network_map_file_path.clone(),
+            requester: global_context.requester.clone(),
             software_name: global_context.software_name,
             software_version: global_context.software_version,
         }
@@ -60,10 +91,38 @@ impl From<&GlobalContext> for QueryContext {
 }
 
 impl QueryContext {
+    pub(crate) fn get_currency_name(&self) -> Option<&CurrencyName> {
+        self.currency
+            .as_ref()
+            .map(|(currency_name, _currency_params)| currency_name)
+    }
+
+    pub(crate) fn get_currency_params(&self) -> Option<&CurrencyParameters> {
+        self.currency
+            .as_ref()
+            .map(|(_currency_name, currency_params)| currency_params)
+    }
+
     pub(crate) fn get_db(&self) -> &BcDbRo {
         &self.db
     }
 
+    pub(crate) fn get_dbs_path(&self) -> &Path {
+        &self.dbs_path
+    }
+
+    pub(crate) fn get_known_gva_endpoints(&self) -> &RwLock<KnownGvaEndpoints> {
+        &self.known_gva_endpoints
+    }
+
+    pub fn get_network_map_file_path(&self) -> &Path {
+        &self.network_map_file_path
+    }
+
+    pub(crate) fn get_requester(&self) -> &ModuleRequester {
+        &self.requester
+    }
+
     pub fn get_software_name(&self) -> &'static str {
         &self.software_name
     }
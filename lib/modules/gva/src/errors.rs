@@ -23,4 +23,13 @@ pub enum GvaError {
     /// Invalid host
     #[fail(display = "Invalid host")]
     InvalidHost,
+    /// A lock is poisoned
+    #[fail(display = "Poisoned lock")]
+    PoisonedLock,
+    /// The router thread is unreachable
+    #[fail(display = "The node is stopping")]
+    RouterUnreachable,
+    /// The requested module did not respond in time
+    #[fail(display = "The node did not respond in time")]
+    ModuleResponseTimeout,
 }
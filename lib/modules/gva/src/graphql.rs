@@ -16,17 +16,48 @@
 
 //! Module that execute graphql queries
 
+use crate::auth::{self, Authorized};
 use crate::context::{GlobalContext, QueryContext};
-use actix_web::{web, Result};
+use actix_web::http::header::WWW_AUTHENTICATE;
+use actix_web::{web, HttpMessage, HttpRequest, HttpResponse, Result};
 use juniper::http::GraphQLRequest;
+use juniper::InputValue;
 use std::sync::Arc;
 
+/// Raw graphql request, the query must be read before execution to detect mutations
+#[derive(Debug, Deserialize)]
+pub(crate) struct RawGraphQLRequest {
+    query: String,
+    #[serde(rename = "operationName")]
+    operation_name: Option<String>,
+    variables: Option<InputValue>,
+}
+
 pub(crate) async fn graphql(
+    req: HttpRequest,
     global_context: web::Data<Arc<GlobalContext>>,
-    data: web::Json<GraphQLRequest>,
-) -> Result<web::Json<serde_json::Value>> {
+    data: web::Json<RawGraphQLRequest>,
+) -> Result<HttpResponse> {
+    let RawGraphQLRequest {
+        query,
+        operation_name,
+        variables,
+    } = data.into_inner();
+
+    let authorized = req
+        .extensions()
+        .get::<Authorized>()
+        .map(|authorized| authorized.0)
+        .unwrap_or(false);
+    if !authorized && auth::is_mutation(&query) {
+        return Ok(HttpResponse::Unauthorized()
+            .header(WWW_AUTHENTICATE, "Bearer, Basic")
+            .finish());
+    }
+
     let query_context = QueryContext::from(global_context.get_ref().as_ref());
-    Ok(web::Json(serde_json::to_value(
-        data.execute(&global_context.schema, &query_context),
+    let request = GraphQLRequest::new(query, operation_name, variables);
+    Ok(HttpResponse::Ok().json(serde_json::to_value(
+        request.execute(&global_context.schema, &query_context),
     )?))
 }
@@ -0,0 +1,196 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! GVA endpoints of the other nodes, collected from the peer cards received by the node.
+//! They are filled by the module main loop and read by the resolvers.
+
+use durs_network_documents::network_endpoint::{EndpointEnum, EndpointV1, EndpointV2};
+use durs_network_documents::network_peer::PeerCard;
+use std::collections::HashMap;
+
+/// API name of the GVA endpoints
+pub static GVA_API_NAME: &str = "GVA";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// GVA endpoint declared by a peer card
+pub struct KnownGvaEndpoint {
+    /// Domain name or IP address
+    pub host: String,
+    /// Port number
+    pub port: u16,
+    /// Optional path
+    pub path: Option<String>,
+    /// Timestamp of the last peer card declaring this endpoint
+    pub last_seen: u64,
+    /// API version declared by the endpoint (unknown for endpoints v1)
+    pub declared_version: Option<u16>,
+}
+
+#[derive(Debug, Default)]
+/// GVA endpoints declared by the peer cards received by the node
+pub struct KnownGvaEndpoints(HashMap<(String, u16, Option<String>), KnownGvaEndpoint>);
+
+impl KnownGvaEndpoints {
+    /// Record the GVA endpoints of a peer card received at `now`
+    pub fn update(&mut self, peer_card: &PeerCard, now: u64) {
+        let endpoints: Vec<GvaEndpointFields> = match *peer_card {
+            PeerCard::V10(ref peer_card_v10) => peer_card_v10
+                .endpoints
+                .iter()
+                .filter_map(|endpoint| match *endpoint {
+                    EndpointEnum::V1(ref ep_v1) => gva_endpoint_v1(ep_v1),
+                    EndpointEnum::V2(ref ep_v2) => gva_endpoint_v2(ep_v2),
+                })
+                .collect(),
+            PeerCard::V11(ref peer_card_v11) => peer_card_v11
+                .endpoints
+                .iter()
+                .filter_map(gva_endpoint_v2)
+                .collect(),
+        };
+        for (host, port, path, declared_version) in endpoints {
+            self.0.insert(
+                (host.clone(), port, path.clone()),
+                KnownGvaEndpoint {
+                    host,
+                    port,
+                    path,
+                    last_seen: now,
+                    declared_version,
+                },
+            );
+        }
+    }
+    /// Known endpoints, the most recently seen first
+    pub fn endpoints(&self) -> Vec<KnownGvaEndpoint> {
+        let mut endpoints: Vec<KnownGvaEndpoint> = self.0.values().cloned().collect();
+        endpoints.sort_by(|e1, e2| {
+            e2.last_seen
+                .cmp(&e1.last_seen)
+                .then_with(|| e1.host.cmp(&e2.host))
+                .then_with(|| e1.port.cmp(&e2.port))
+        });
+        endpoints
+    }
+}
+
+/// Host, port, path and declared version of a GVA endpoint
+type GvaEndpointFields = (String, u16, Option<String>, Option<u16>);
+
+fn gva_endpoint_v1(ep_v1: &EndpointV1) -> Option<GvaEndpointFields> {
+    if ep_v1.api.0 != GVA_API_NAME {
+        return None;
+    }
+    Some((
+        ep_v1.host.clone(),
+        ep_v1.port as u16,
+        ep_v1.path.clone(),
+        None,
+    ))
+}
+
+fn gva_endpoint_v2(ep_v2: &EndpointV2) -> Option<GvaEndpointFields> {
+    if ep_v2.api.0 != GVA_API_NAME {
+        return None;
+    }
+    let host = if let Some(ref domain) = ep_v2.domain {
+        domain.clone()
+    } else if let Some(ip_v4) = ep_v2.ip_v4 {
+        ip_v4.to_string()
+    } else {
+        ep_v2.ip_v6?.to_string()
+    };
+    Some((
+        host,
+        ep_v2.port,
+        ep_v2.path.clone(),
+        Some(ep_v2.api_version),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dubp_common_doc::BlockNumber;
+    use dubp_currency_params::CurrencyName;
+    use durs_network_documents::network_endpoint::{
+        ApiFeatures, ApiName, EndpointV2NetworkFeatures,
+    };
+    use durs_network_documents::network_peer::PeerCardV11;
+    use durs_network_documents::NodeId;
+
+    fn endpoint(api: &str, domain: &str) -> EndpointV2 {
+        EndpointV2 {
+            api: ApiName(api.to_owned()),
+            api_version: 1,
+            network_features: EndpointV2NetworkFeatures(vec![1u8]),
+            api_features: ApiFeatures(vec![]),
+            domain: Some(domain.to_owned()),
+            ip_v4: None,
+            ip_v6: None,
+            port: 443,
+            path: Some("gva".to_owned()),
+        }
+    }
+
+    fn peer_card(endpoints: Vec<EndpointV2>) -> PeerCard {
+        PeerCard::V11(PeerCardV11 {
+            currency_name: CurrencyName("g1".to_owned()),
+            issuer: dup_crypto_tests_tools::mocks::pubkey('A'),
+            node_id: NodeId(1),
+            created_on: BlockNumber(50),
+            endpoints,
+            endpoints_str: vec![],
+            sig: None,
+        })
+    }
+
+    #[test]
+    fn test_known_gva_endpoints() {
+        let mut known_endpoints = KnownGvaEndpoints::default();
+
+        known_endpoints.update(
+            &peer_card(vec![
+                endpoint("WS2P", "g1.node1.org"),
+                endpoint(GVA_API_NAME, "g1.node1.org"),
+            ]),
+            100,
+        );
+        known_endpoints.update(
+            &peer_card(vec![endpoint(GVA_API_NAME, "g1.node2.org")]),
+            200,
+        );
+        // Seen again
+        known_endpoints.update(
+            &peer_card(vec![endpoint(GVA_API_NAME, "g1.node1.org")]),
+            300,
+        );
+
+        let expected_endpoint = |host: &str, last_seen| KnownGvaEndpoint {
+            host: host.to_owned(),
+            port: 443,
+            path: Some("gva".to_owned()),
+            last_seen,
+            declared_version: Some(1),
+        };
+        assert_eq!(
+            vec![
+                expected_endpoint("g1.node1.org", 300),
+                expected_endpoint("g1.node2.org", 200),
+            ],
+            known_endpoints.endpoints()
+        );
+    }
+}
@@ -22,6 +22,10 @@
 //!
 //! Graphiql web client is accessible at
 //! http://127.0.0.1:10901/graphiql
+//! unless `expose_playground` is disabled in module configuration.
+//!
+//! Mutating operations can be restricted to clients providing the credentials
+//! configured in `auth` (bearer token or basic auth).
 
 #![deny(
     clippy::option_unwrap_used,
@@ -45,15 +49,21 @@ extern crate structopt;
 
 extern crate juniper;
 
+mod auth;
 mod constants;
 mod context;
 mod db;
 mod errors;
 mod graphql;
+mod known_endpoints;
+mod requester;
 mod schema;
 mod webserver;
 
+pub use crate::auth::GvaAuthConf;
 use crate::errors::GvaError;
+use crate::known_endpoints::KnownGvaEndpoints;
+use crate::requester::ModuleRequester;
 use dubp_currency_params::CurrencyName;
 use durs_common_tools::fatal_error;
 use durs_common_tools::traits::merge::Merge;
@@ -68,10 +78,10 @@ use durs_module::{
 use durs_network::events::NetworkEvent;
 use durs_network_documents::host::Host;
 
+use durs_common_tools::fns::thread::spawn_child;
 use std::ops::Deref;
-use std::sync::mpsc;
-use std::thread;
-use std::time::{Duration, SystemTime};
+use std::sync::{mpsc, Arc, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 static MODULE_NAME: &str = "gva";
 
@@ -83,6 +93,8 @@ const DEFAULT_PORT: u16 = 10_901;
 pub struct GvaConf {
     host: String,
     port: u16,
+    expose_playground: bool,
+    auth: Option<GvaAuthConf>,
 }
 
 impl Default for GvaConf {
@@ -90,13 +102,26 @@ impl Default for GvaConf {
         GvaConf {
             host: DEFAULT_HOST.to_owned(),
             port: DEFAULT_PORT,
+            expose_playground: true,
+            auth: None,
         }
     }
 }
 
 impl std::fmt::Display for GvaConf {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
-        write!(f, "host: {}\nport: {}", self.host, self.port,)
+        write!(
+            f,
+            "host: {}\nport: {}\nexpose_playground: {}\nauth: {}",
+            self.host,
+            self.port,
+            self.expose_playground,
+            if let Some(ref auth) = self.auth {
+                auth.to_string()
+            } else {
+                "none".to_owned()
+            },
+        )
     }
 }
 
@@ -105,6 +130,8 @@ impl std::fmt::Display for GvaConf {
 pub struct GvaUserConf {
     host: Option<String>,
     port: Option<u16>,
+    expose_playground: Option<bool>,
+    auth: Option<GvaAuthConf>,
 }
 
 impl Merge for GvaUserConf {
@@ -112,6 +139,8 @@ impl Merge for GvaUserConf {
         GvaUserConf {
             host: self.host.or(other.host),
             port: self.port.or(other.port),
+            expose_playground: self.expose_playground.or(other.expose_playground),
+            auth: self.auth.or(other.auth),
         }
     }
 }
@@ -126,6 +155,15 @@ pub struct GvaOpt {
     #[structopt(long = "port")]
     /// Change GVA API port listen
     pub port: Option<u16>,
+    /// Serve (true) or not (false) the graphiql playground
+    #[structopt(long = "playground")]
+    pub expose_playground: Option<bool>,
+    /// Require this bearer token for mutating operations
+    #[structopt(long = "bearer-token", conflicts_with = "basic_auth")]
+    pub bearer_token: Option<String>,
+    /// Require these basic auth credentials (user:password) for mutating operations
+    #[structopt(long = "basic-auth", parse(try_from_str = GvaAuthConf::parse_basic))]
+    pub basic_auth: Option<GvaAuthConf>,
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -156,6 +194,9 @@ impl DursModule<DuRsConf, DursMsg> for GvaModule {
     fn ask_required_keys() -> RequiredKeys {
         RequiredKeys::None
     }
+    fn listen_addrs(module_conf: &Self::ModuleConf) -> Vec<(String, u16)> {
+        vec![(module_conf.host.clone(), module_conf.port)]
+    }
     fn have_subcommand() -> bool {
         false
     }
@@ -173,6 +214,12 @@ impl DursModule<DuRsConf, DursMsg> for GvaModule {
             if let Some(port) = module_user_conf.port {
                 conf.port = port;
             }
+            if let Some(expose_playground) = module_user_conf.expose_playground {
+                conf.expose_playground = expose_playground;
+            }
+            if let Some(ref auth) = module_user_conf.auth {
+                conf.auth = Some(auth.clone());
+            }
         }
 
         Ok((conf, module_user_conf))
@@ -187,6 +234,11 @@ impl DursModule<DuRsConf, DursMsg> for GvaModule {
         let new_gva_user_conf = GvaUserConf {
             host: subcommand_args.host.map(|h| h.to_string()),
             port: subcommand_args.port,
+            expose_playground: subcommand_args.expose_playground,
+            auth: subcommand_args
+                .bearer_token
+                .map(|token| GvaAuthConf::Bearer { token })
+                .or(subcommand_args.basic_auth),
         }
         .merge(module_user_conf.unwrap_or_default());
         match Self::generate_module_conf(
@@ -224,7 +276,10 @@ impl DursModule<DuRsConf, DursMsg> for GvaModule {
                 static_name: ModuleStaticName(MODULE_NAME),
                 sender: gva_sender, // Messages sent by the router will be received by your proxy thread
                 roles: vec![ModuleRole::UserInterface], // Roles assigned to your module
-                events_subscription: vec![ModuleEvent::NewValidBlock], // Events to which your module subscribes
+                events_subscription: vec![
+                    ModuleEvent::NewValidBlock,
+                    ModuleEvent::NewValidPeerFromNodeNetwork,
+                ], // Events to which your module subscribes
                 reserved_apis_parts: vec![],
                 endpoints: vec![],
             })
@@ -234,10 +289,34 @@ impl DursModule<DuRsConf, DursMsg> for GvaModule {
         // we indicate it in the debug level log, it can be helpful.
         debug!("Send gva module registration to router thread.");
 
+        // Requests sent by the resolvers, their responses are received by the main loop
+        let requester = Arc::new(ModuleRequester::new(router_sender.clone()));
+
+        // GVA endpoints of the other nodes, filled with the received peer cards
+        let known_gva_endpoints = Arc::new(RwLock::new(KnownGvaEndpoints::default()));
+
+        // The web server thread sends the handle of each started server,
+        // the main loop sends the new listen addresses when the conf changes
+        let (server_sender, server_receiver) = mpsc::channel();
+        let (rebind_sender, rebind_receiver) = mpsc::channel();
+        let mut web_server = None;
+        let mut current_conf = conf.clone();
+        let mut last_conf_reload = SystemTime::now();
+
         let smd: SoftwareMetaDatas<DuRsConf> = soft_meta_datas.clone();
         let router_sender_clone = router_sender.clone();
-        let _webserver_thread = thread::spawn(move || {
-            if let Err(e) = webserver::start_web_server(&smd, host, conf.port) {
+        let requester_clone = requester.clone();
+        let known_gva_endpoints_clone = known_gva_endpoints.clone();
+        let _webserver_thread = spawn_child("webserver", move || {
+            if let Err(e) = webserver::start_web_server(
+                &smd,
+                host,
+                &conf,
+                requester_clone,
+                known_gva_endpoints_clone,
+                server_sender,
+                rebind_receiver,
+            ) {
                 error!("GVA http web server error  : {}  ", e);
             } else {
                 info!("GVA http web server stop.")
@@ -268,7 +347,7 @@ impl DursModule<DuRsConf, DursMsg> for GvaModule {
                                 BlockchainEvent::StackUpValidBlock(ref _block) => {
                                     // Do something when the node has stacked a new block at its local blockchain
                                 }
-                                BlockchainEvent::RevertBlocks(ref _blocks) => {
+                                BlockchainEvent::RevertBlocks { .. } => {
                                     // Do something when the node has destacked blocks from its local blockchain (roll back)
                                 }
                                 _ => {} // Do nothing for events that don't concern this module.
@@ -276,8 +355,17 @@ impl DursModule<DuRsConf, DursMsg> for GvaModule {
                         }
                         DursEvent::NetworkEvent(ref network_event_box) => {
                             match *network_event_box.deref() {
-                                NetworkEvent::ReceivePeers(ref _peers) => {
-                                    // Do something when the node receive peers cards from network
+                                NetworkEvent::ReceivePeers(ref peers) => {
+                                    let now = SystemTime::now()
+                                        .duration_since(UNIX_EPOCH)
+                                        .map(|d| d.as_secs())
+                                        .unwrap_or_default();
+                                    if let Ok(mut known_gva_endpoints) = known_gva_endpoints.write()
+                                    {
+                                        for peer in peers {
+                                            known_gva_endpoints.update(peer, now);
+                                        }
+                                    }
                                 }
                                 NetworkEvent::ReceiveDocuments(ref _bc_documents) => {
                                     // Do something when the node receive blockchain documents from network
@@ -287,6 +375,11 @@ impl DursModule<DuRsConf, DursMsg> for GvaModule {
                         }
                         _ => {} // Do nothing for DursEvent variants that don't concern this module.
                     },
+                    DursMsg::Response {
+                        req_id,
+                        res_content,
+                        ..
+                    } => requester.receive_response(req_id, res_content),
                     _ => {} // Do nothing for DursMsgContent variants that don't concern this module.
                 },
                 Err(e) => match e {
@@ -299,8 +392,60 @@ impl DursModule<DuRsConf, DursMsg> for GvaModule {
                     }
                 },
             }
+            // Keep the handle of the running web server
+            while let Ok(server) = server_receiver.try_recv() {
+                web_server = Some(server);
+            }
+            // Reload conf
+            if let Ok(elapsed) = last_conf_reload.elapsed() {
+                if elapsed > Duration::from_secs(constants::CONF_RELOAD_INTERVAL_IN_SECS) {
+                    last_conf_reload = SystemTime::now();
+                    if let Some(ref server) = web_server {
+                        reload_conf(soft_meta_datas, &mut current_conf, server, &rebind_sender);
+                    }
+                }
+            }
         }
         // If we reach this point it means that the module has stopped correctly, so we return OK.
         Ok(())
     }
 }
+
+/// Reload the module conf, and rebind the web server if its listen addresses have changed
+fn reload_conf(
+    soft_meta_datas: &SoftwareMetaDatas<DuRsConf>,
+    current_conf: &mut GvaConf,
+    server: &actix_web::dev::Server,
+    rebind_sender: &mpsc::Sender<Vec<std::net::SocketAddr>>,
+) {
+    let new_conf = match durs_conf::modules_conf::reload_module_conf::<GvaModule>(
+        soft_meta_datas.profile_path.clone(),
+    ) {
+        Ok(new_conf) => new_conf,
+        Err(e) => {
+            warn!("GVA: fail to reload conf: {}", e);
+            return;
+        }
+    };
+    if new_conf.host == current_conf.host && new_conf.port == current_conf.port {
+        return;
+    }
+
+    let new_addrs = match Host::parse(&new_conf.host)
+        .map_err(|_| GvaError::InvalidHost.to_string())
+        .and_then(|host| webserver::listen_addrs(host, new_conf.port).map_err(|e| e.to_string()))
+    {
+        Ok(new_addrs) => new_addrs,
+        Err(e) => {
+            warn!("GVA: ignore new listen address: {}", e);
+            return;
+        }
+    };
+    info!(
+        "GVA: rebind web server from {}:{} to {}:{}...",
+        current_conf.host, current_conf.port, new_conf.host, new_conf.port
+    );
+    webserver::rebind_web_server(server, rebind_sender, new_addrs);
+    current_conf.host = new_conf.host;
+    current_conf.port = new_conf.port;
+}
@@ -0,0 +1,98 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Send requests to the other modules and wait for their responses.
+//! Resolvers run in the web server threads, responses are received by the module main loop.
+
+use crate::constants::MODULE_RESPONSE_TIMEOUT_SECS;
+use crate::errors::GvaError;
+use durs_message::requests::DursReqContent;
+use durs_message::responses::DursResContent;
+use durs_message::DursMsg;
+use durs_module::{ModuleReqId, ModuleRole, ModuleStaticName, RouterThreadMessage};
+use std::collections::HashMap;
+use std::sync::{mpsc, Mutex};
+use std::time::Duration;
+
+#[derive(Debug)]
+/// Requests sent to the other modules and waiting for a response
+pub struct ModuleRequester {
+    router_sender: Mutex<mpsc::Sender<RouterThreadMessage<DursMsg>>>,
+    next_req_id: Mutex<u32>,
+    pending_requests: Mutex<HashMap<ModuleReqId, mpsc::Sender<DursResContent>>>,
+}
+
+impl ModuleRequester {
+    pub(crate) fn new(router_sender: mpsc::Sender<RouterThreadMessage<DursMsg>>) -> Self {
+        ModuleRequester {
+            router_sender: Mutex::new(router_sender),
+            next_req_id: Mutex::new(0),
+            pending_requests: Mutex::new(HashMap::new()),
+        }
+    }
+    /// Send a request to the module holding the role `req_to` and wait for its response
+    pub(crate) fn request(
+        &self,
+        req_to: ModuleRole,
+        req_content: DursReqContent,
+    ) -> Result<DursResContent, GvaError> {
+        let req_id = {
+            let mut next_req_id = self
+                .next_req_id
+                .lock()
+                .map_err(|_| GvaError::PoisonedLock)?;
+            let req_id = ModuleReqId(*next_req_id);
+            *next_req_id = next_req_id.wrapping_add(1);
+            req_id
+        };
+        let (response_sender, response_receiver) = mpsc::channel();
+        self.pending_requests
+            .lock()
+            .map_err(|_| GvaError::PoisonedLock)?
+            .insert(req_id, response_sender);
+
+        let sent = self
+            .router_sender
+            .lock()
+            .map_err(|_| GvaError::PoisonedLock)?
+            .send(RouterThreadMessage::ModuleMessage(DursMsg::Request {
+                req_from: ModuleStaticName(crate::MODULE_NAME),
+                req_to,
+                req_id,
+                req_content,
+            }));
+        let response = if sent.is_ok() {
+            response_receiver
+                .recv_timeout(Duration::from_secs(MODULE_RESPONSE_TIMEOUT_SECS))
+                .map_err(|_| GvaError::ModuleResponseTimeout)
+        } else {
+            Err(GvaError::RouterUnreachable)
+        };
+
+        self.pending_requests
+            .lock()
+            .map_err(|_| GvaError::PoisonedLock)?
+            .remove(&req_id);
+        response
+    }
+    /// Give a response received by the module main loop to the waiting resolver
+    pub(crate) fn receive_response(&self, req_id: ModuleReqId, res_content: DursResContent) {
+        if let Ok(pending_requests) = self.pending_requests.lock() {
+            if let Some(response_sender) = pending_requests.get(&req_id) {
+                let _ = response_sender.send(res_content);
+            }
+        }
+    }
+}
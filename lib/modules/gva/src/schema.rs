@@ -17,18 +17,29 @@
 
 mod entities;
 pub mod inputs;
+mod mutations;
 mod queries;
 
 use self::entities::block::Block;
 use self::entities::blocks_page::BlocksPage;
+use self::entities::certification::Certification;
 use self::entities::current_ud::CurrentUd;
+use self::entities::distance_evaluation::DistanceEvaluation;
+use self::entities::gva_endpoint::GvaEndpoint;
+use self::entities::identity::Identity;
 use self::entities::node::{Node, Summary};
+use self::entities::tx_submission::{TxRejection, TxSubmission};
+use self::entities::txs_history::{TxHistoryEntry, TxsHistoryPage};
+use self::entities::ud_calendar::{NextUd, UdCalendar};
+use self::queries::certifications::CertsDirection;
 use crate::context::QueryContext;
+use dup_crypto::keys::PubKey;
 #[cfg(not(test))]
 use durs_bc_db_reader::{BcDbRoWithReader, DbReadable};
 use juniper::Executor;
-use juniper::FieldResult;
+use juniper::{FieldError, FieldResult};
 use juniper_from_schema::graphql_schema_from_file;
+use std::str::FromStr;
 
 // generate schema from schema file
 graphql_schema_from_file!("resources/schema.gql", context_type: QueryContext);
@@ -115,6 +126,124 @@ impl QueryFields for Query {
     ) -> FieldResult<Option<CurrentUd>> {
         exec_in_db_transaction!(current_ud(executor, trail))
     }
+    #[inline]
+    fn field_ud_calendar(
+        &self,
+        executor: &Executor<'_, QueryContext>,
+        trail: &QueryTrail<'_, UdCalendar, Walked>,
+    ) -> FieldResult<UdCalendar> {
+        let currency_params = executor.context().get_currency_params();
+        exec_in_db_transaction!(ud_calendar(executor, trail, currency_params))
+    }
+    #[inline]
+    fn field_network_map(
+        &self,
+        executor: &Executor<'_, QueryContext>,
+        format: NetworkMapFormat,
+    ) -> FieldResult<Option<String>> {
+        queries::network_map::execute(executor.context(), format)
+    }
+    #[inline]
+    fn field_is_uid_available(
+        &self,
+        executor: &Executor<'_, QueryContext>,
+        uid: String,
+    ) -> FieldResult<bool> {
+        queries::idty_availability::execute_uid(executor.context(), uid)
+    }
+    #[inline]
+    fn field_is_pubkey_available(
+        &self,
+        executor: &Executor<'_, QueryContext>,
+        pubkey: String,
+    ) -> FieldResult<bool> {
+        queries::idty_availability::execute_pubkey(executor.context(), &pubkey)
+    }
+    #[inline]
+    fn field_known_gva_endpoints(
+        &self,
+        executor: &Executor<'_, QueryContext>,
+        _trail: &QueryTrail<'_, GvaEndpoint, Walked>,
+    ) -> FieldResult<Vec<GvaEndpoint>> {
+        queries::known_gva_endpoints::execute(executor.context())
+    }
+    #[inline]
+    #[allow(clippy::too_many_arguments)]
+    fn field_transactions_history(
+        &self,
+        executor: &Executor<'_, QueryContext>,
+        trail: &QueryTrail<'_, TxsHistoryPage, Walked>,
+        pubkey: String,
+        direction: Option<TxDirection>,
+        from_block: Option<i32>,
+        to_block: Option<i32>,
+        first: i32,
+        after: Option<String>,
+    ) -> FieldResult<TxsHistoryPage> {
+        let (pubkey, filter) = queries::transactions_history::parse_args(
+            &pubkey, direction, from_block, to_block, first, after,
+        )?;
+        exec_in_db_transaction!(transactions_history(executor, trail, &pubkey, filter))
+    }
+    #[inline]
+    fn field_idty(
+        &self,
+        executor: &Executor<'_, QueryContext>,
+        trail: &QueryTrail<'_, Identity, Walked>,
+        uid_or_pubkey: String,
+    ) -> FieldResult<Option<Identity>> {
+        exec_in_db_transaction!(idty(executor, trail, &uid_or_pubkey))
+    }
+    #[inline]
+    fn field_certifiers_of(
+        &self,
+        executor: &Executor<'_, QueryContext>,
+        trail: &QueryTrail<'_, Certification, Walked>,
+        uid_or_pubkey: String,
+    ) -> FieldResult<Vec<Certification>> {
+        let currency_params = executor.context().get_currency_params();
+        exec_in_db_transaction!(certifications(
+            executor,
+            trail,
+            &uid_or_pubkey,
+            CertsDirection::Received,
+            currency_params
+        ))
+    }
+    #[inline]
+    fn field_certified_by(
+        &self,
+        executor: &Executor<'_, QueryContext>,
+        trail: &QueryTrail<'_, Certification, Walked>,
+        uid_or_pubkey: String,
+    ) -> FieldResult<Vec<Certification>> {
+        let currency_params = executor.context().get_currency_params();
+        exec_in_db_transaction!(certifications(
+            executor,
+            trail,
+            &uid_or_pubkey,
+            CertsDirection::Issued,
+            currency_params
+        ))
+    }
+    #[inline]
+    fn field_distance_evaluation(
+        &self,
+        executor: &Executor<'_, QueryContext>,
+        _trail: &QueryTrail<'_, DistanceEvaluation, Walked>,
+        pubkey: String,
+    ) -> FieldResult<Option<DistanceEvaluation>> {
+        let pubkey =
+            PubKey::from_str(&pubkey).map_err(|_| FieldError::from("Invalid public key"))?;
+        let wot = queries::distance_evaluation::load_wot(executor.context().get_dbs_path())?;
+        let currency_params = executor.context().get_currency_params();
+        exec_in_db_transaction!(distance_evaluation(
+            executor,
+            wot.as_ref(),
+            currency_params,
+            &pubkey
+        ))
+    }
 }
 
 pub struct Mutation;
@@ -123,6 +252,24 @@ impl MutationFields for Mutation {
     fn field_noop(&self, _executor: &Executor<'_, QueryContext>) -> FieldResult<&bool> {
         Ok(&true)
     }
+    #[inline]
+    fn field_submit_transaction(
+        &self,
+        executor: &Executor<'_, QueryContext>,
+        _trail: &QueryTrail<'_, TxSubmission, Walked>,
+        raw_document: String,
+    ) -> FieldResult<TxSubmission> {
+        mutations::submit_transaction::execute(executor.context(), &raw_document)
+    }
+    #[inline]
+    fn field_send_transaction(
+        &self,
+        executor: &Executor<'_, QueryContext>,
+        _trail: &QueryTrail<'_, TxSubmission, Walked>,
+        raw_tx: String,
+    ) -> FieldResult<TxSubmission> {
+        mutations::submit_transaction::execute(executor.context(), &raw_tx)
+    }
 }
 
 pub fn create_schema() -> Schema {
@@ -17,5 +17,12 @@
 
 pub mod block;
 pub mod blocks_page;
+pub mod certification;
 pub mod current_ud;
+pub mod distance_evaluation;
+pub mod gva_endpoint;
+pub mod identity;
 pub mod node;
+pub mod tx_submission;
+pub mod txs_history;
+pub mod ud_calendar;
@@ -0,0 +1,81 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// ! Module define graphql Certification type
+
+use super::identity::Identity;
+use crate::context::QueryContext;
+use chrono::NaiveDateTime;
+use dubp_common_doc::BlockNumber;
+use juniper::{Executor, FieldResult};
+
+pub struct Certification {
+    pub(crate) issuer: String,
+    pub(crate) issuer_uid: String,
+    pub(crate) receiver: String,
+    pub(crate) receiver_uid: String,
+    pub(crate) created_on: i32,
+    pub(crate) expires_on: Option<NaiveDateTime>,
+}
+
+impl Certification {
+    // Create the certification from `issuer` to `receiver`, expiring at `expires_on` (timestamp)
+    pub(crate) fn new(
+        issuer: &Identity,
+        receiver: &Identity,
+        created_on: BlockNumber,
+        expires_on: Option<u64>,
+    ) -> Certification {
+        Certification {
+            issuer: issuer.pubkey.clone(),
+            issuer_uid: issuer.uid.clone(),
+            receiver: receiver.pubkey.clone(),
+            receiver_uid: receiver.uid.clone(),
+            created_on: created_on.0 as i32,
+            expires_on: expires_on
+                .map(|expires_on| NaiveDateTime::from_timestamp(expires_on as i64, 0)),
+        }
+    }
+}
+
+impl super::super::CertificationFields for Certification {
+    #[inline]
+    fn field_issuer(&self, _executor: &Executor<'_, QueryContext>) -> FieldResult<&String> {
+        Ok(&self.issuer)
+    }
+    #[inline]
+    fn field_issuer_uid(&self, _executor: &Executor<'_, QueryContext>) -> FieldResult<&String> {
+        Ok(&self.issuer_uid)
+    }
+    #[inline]
+    fn field_receiver(&self, _executor: &Executor<'_, QueryContext>) -> FieldResult<&String> {
+        Ok(&self.receiver)
+    }
+    #[inline]
+    fn field_receiver_uid(&self, _executor: &Executor<'_, QueryContext>) -> FieldResult<&String> {
+        Ok(&self.receiver_uid)
+    }
+    #[inline]
+    fn field_created_on(&self, _executor: &Executor<'_, QueryContext>) -> FieldResult<&i32> {
+        Ok(&self.created_on)
+    }
+    #[inline]
+    fn field_expires_on(
+        &self,
+        _executor: &Executor<'_, QueryContext>,
+    ) -> FieldResult<&Option<NaiveDateTime>> {
+        Ok(&self.expires_on)
+    }
+}
@@ -16,6 +16,10 @@
 // ! Module define graphql BlockCurrent UD type
 use crate::context::QueryContext;
 use chrono::NaiveDateTime;
+use dubp_user_docs::amount::{
+    currency_symbol, format_amount, AmountFormat, AmountUnit, Separators,
+};
+use dubp_user_docs::documents::transaction::{TxAmount, TxBase};
 use durs_bc_db_reader::current_metadata::current_ud::CurrentUdDb;
 use juniper::{Executor, FieldResult};
 
@@ -42,6 +46,23 @@ impl CurrentUd {
     }
 }
 
+// Format an amount expressed in the given unit base with the currency symbol
+// (without symbol if the currency is still unknown)
+pub(crate) fn format_ud_amount(context: &QueryContext, amount: i32, base: i32) -> String {
+    let format = AmountFormat {
+        unit: AmountUnit::Currency(
+            context
+                .get_currency_name()
+                .map(|currency_name| currency_symbol(&currency_name.0))
+                .unwrap_or_default(),
+        ),
+        separators: Separators::ENGLISH,
+    };
+    format_amount(TxAmount(amount as isize), TxBase(base as usize), &format)
+        .trim_end()
+        .to_owned()
+}
+
 impl super::super::CurrentUdFields for CurrentUd {
     #[inline]
     fn field_amount(&self, _executor: &Executor<'_, QueryContext>) -> FieldResult<&i32> {
@@ -70,4 +91,8 @@ impl super::super::CurrentUdFields for CurrentUd {
     fn field_monetary_mass(&self, _executor: &Executor<'_, QueryContext>) -> FieldResult<&i32> {
         Ok(&self.monetary_mass)
     }
+    #[inline]
+    fn field_formatted_amount(&self, executor: &Executor<'_, QueryContext>) -> FieldResult<String> {
+        Ok(format_ud_amount(executor.context(), self.amount, self.base))
+    }
 }
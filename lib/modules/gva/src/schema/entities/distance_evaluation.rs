@@ -0,0 +1,70 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// ! Module define graphql DistanceEvaluation type
+
+use crate::context::QueryContext;
+use durs_wot::operations::distance::WotDistance;
+use juniper::{Executor, FieldResult};
+
+pub struct DistanceEvaluation {
+    pub(crate) sentries: i32,
+    pub(crate) success: i32,
+    pub(crate) success_at_border: i32,
+    pub(crate) reached: i32,
+    pub(crate) reached_at_border: i32,
+    pub(crate) outdistanced: bool,
+}
+
+impl DistanceEvaluation {
+    // Convert WotDistance (wot entity) into DistanceEvaluation (gva entity)
+    pub(crate) fn from_wot_distance(distance: WotDistance) -> DistanceEvaluation {
+        DistanceEvaluation {
+            sentries: distance.sentries as i32,
+            success: distance.success as i32,
+            success_at_border: distance.success_at_border as i32,
+            reached: distance.reached as i32,
+            reached_at_border: distance.reached_at_border as i32,
+            outdistanced: distance.outdistanced,
+        }
+    }
+}
+
+impl super::super::DistanceEvaluationFields for DistanceEvaluation {
+    #[inline]
+    fn field_sentries(&self, _executor: &Executor<'_, QueryContext>) -> FieldResult<&i32> {
+        Ok(&self.sentries)
+    }
+    #[inline]
+    fn field_success(&self, _executor: &Executor<'_, QueryContext>) -> FieldResult<&i32> {
+        Ok(&self.success)
+    }
+    #[inline]
+    fn field_success_at_border(&self, _executor: &Executor<'_, QueryContext>) -> FieldResult<&i32> {
+        Ok(&self.success_at_border)
+    }
+    #[inline]
+    fn field_reached(&self, _executor: &Executor<'_, QueryContext>) -> FieldResult<&i32> {
+        Ok(&self.reached)
+    }
+    #[inline]
+    fn field_reached_at_border(&self, _executor: &Executor<'_, QueryContext>) -> FieldResult<&i32> {
+        Ok(&self.reached_at_border)
+    }
+    #[inline]
+    fn field_outdistanced(&self, _executor: &Executor<'_, QueryContext>) -> FieldResult<&bool> {
+        Ok(&self.outdistanced)
+    }
+}
@@ -0,0 +1,70 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// ! Module define graphql GvaEndpoint type
+use crate::context::QueryContext;
+use crate::known_endpoints::KnownGvaEndpoint;
+use chrono::NaiveDateTime;
+use juniper::{Executor, FieldResult};
+
+pub struct GvaEndpoint {
+    pub host: String,
+    pub port: i32,
+    pub path: Option<String>,
+    pub last_seen: NaiveDateTime,
+    pub declared_version: Option<i32>,
+}
+
+impl GvaEndpoint {
+    // Convert KnownGvaEndpoint (collected from peer cards) into GvaEndpoint (gva entity)
+    pub(crate) fn from_known_endpoint(known_endpoint: KnownGvaEndpoint) -> GvaEndpoint {
+        GvaEndpoint {
+            host: known_endpoint.host,
+            port: i32::from(known_endpoint.port),
+            path: known_endpoint.path,
+            last_seen: NaiveDateTime::from_timestamp(known_endpoint.last_seen as i64, 0),
+            declared_version: known_endpoint.declared_version.map(i32::from),
+        }
+    }
+}
+
+impl super::super::GvaEndpointFields for GvaEndpoint {
+    #[inline]
+    fn field_host(&self, _executor: &Executor<'_, QueryContext>) -> FieldResult<&String> {
+        Ok(&self.host)
+    }
+    #[inline]
+    fn field_port(&self, _executor: &Executor<'_, QueryContext>) -> FieldResult<&i32> {
+        Ok(&self.port)
+    }
+    #[inline]
+    fn field_path(&self, _executor: &Executor<'_, QueryContext>) -> FieldResult<&Option<String>> {
+        Ok(&self.path)
+    }
+    #[inline]
+    fn field_last_seen(
+        &self,
+        _executor: &Executor<'_, QueryContext>,
+    ) -> FieldResult<&NaiveDateTime> {
+        Ok(&self.last_seen)
+    }
+    #[inline]
+    fn field_declared_version(
+        &self,
+        _executor: &Executor<'_, QueryContext>,
+    ) -> FieldResult<&Option<i32>> {
+        Ok(&self.declared_version)
+    }
+}
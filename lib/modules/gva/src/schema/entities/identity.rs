@@ -0,0 +1,91 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// ! Module define graphql Identity type
+
+use crate::context::QueryContext;
+use crate::schema::IdentityState;
+use dubp_common_doc::traits::Document;
+use durs_bc_db_reader::indexes::identities::{IdentityDb, IdentityStateDb};
+use juniper::{Executor, FieldResult};
+
+pub struct Identity {
+    pub(crate) pubkey: String,
+    pub(crate) uid: String,
+    pub(crate) hash: String,
+    pub(crate) state: IdentityState,
+    pub(crate) joined_on: String,
+    pub(crate) expired_on: Option<String>,
+    pub(crate) revoked_on: Option<String>,
+}
+
+impl Identity {
+    // Convert IdentityDb (db entity) into Identity (gva entity)
+    pub(crate) fn from_identity_db(idty_db: IdentityDb) -> Identity {
+        Identity {
+            pubkey: idty_db.idty_doc.issuers()[0].to_string(),
+            uid: idty_db.idty_doc.username().to_owned(),
+            hash: idty_db.hash,
+            state: match idty_db.state {
+                IdentityStateDb::Member(_) => IdentityState::Member,
+                IdentityStateDb::ExpireMember(_) => IdentityState::ExpiredMember,
+                IdentityStateDb::ExplicitRevoked(_) | IdentityStateDb::ExplicitExpireRevoked(_) => {
+                    IdentityState::Revoked
+                }
+                IdentityStateDb::ImplicitRevoked(_) => IdentityState::ImplicitRevoked,
+            },
+            joined_on: idty_db.joined_on.to_string(),
+            expired_on: idty_db.expired_on.map(|blockstamp| blockstamp.to_string()),
+            revoked_on: idty_db.revoked_on.map(|blockstamp| blockstamp.to_string()),
+        }
+    }
+}
+
+impl super::super::IdentityFields for Identity {
+    #[inline]
+    fn field_pubkey(&self, _executor: &Executor<'_, QueryContext>) -> FieldResult<&String> {
+        Ok(&self.pubkey)
+    }
+    #[inline]
+    fn field_uid(&self, _executor: &Executor<'_, QueryContext>) -> FieldResult<&String> {
+        Ok(&self.uid)
+    }
+    #[inline]
+    fn field_hash(&self, _executor: &Executor<'_, QueryContext>) -> FieldResult<&String> {
+        Ok(&self.hash)
+    }
+    #[inline]
+    fn field_state(&self, _executor: &Executor<'_, QueryContext>) -> FieldResult<&IdentityState> {
+        Ok(&self.state)
+    }
+    #[inline]
+    fn field_joined_on(&self, _executor: &Executor<'_, QueryContext>) -> FieldResult<&String> {
+        Ok(&self.joined_on)
+    }
+    #[inline]
+    fn field_expired_on(
+        &self,
+        _executor: &Executor<'_, QueryContext>,
+    ) -> FieldResult<&Option<String>> {
+        Ok(&self.expired_on)
+    }
+    #[inline]
+    fn field_revoked_on(
+        &self,
+        _executor: &Executor<'_, QueryContext>,
+    ) -> FieldResult<&Option<String>> {
+        Ok(&self.revoked_on)
+    }
+}
@@ -0,0 +1,163 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// ! Module define graphql TxSubmission type
+
+use crate::context::QueryContext;
+use crate::schema::TxRejectionReason;
+use dup_crypto::hashs::Hash;
+use durs_message::responses::TxRejection as TxRejectionMsg;
+use juniper::{Executor, FieldResult};
+use juniper_from_schema::{QueryTrail, Walked};
+
+pub struct TxSubmission {
+    pub hash: Option<String>,
+    pub rejection: Option<TxRejection>,
+}
+
+pub struct TxRejection {
+    pub reason: TxRejectionReason,
+    pub message: String,
+    pub input_index: Option<i32>,
+    pub output_index: Option<i32>,
+    pub pending_tx: Option<String>,
+}
+
+impl TxSubmission {
+    // Convert the mempool response into TxSubmission (gva entity)
+    pub(crate) fn from_result(result: Result<Hash, TxRejectionMsg>) -> TxSubmission {
+        match result {
+            Ok(hash) => TxSubmission {
+                hash: Some(hash.to_hex()),
+                rejection: None,
+            },
+            Err(rejection) => TxSubmission {
+                hash: None,
+                rejection: Some(TxRejection::from_rejection(&rejection)),
+            },
+        }
+    }
+}
+
+impl TxRejection {
+    // Convert TxRejection (message entity) into TxRejection (gva entity)
+    pub(crate) fn from_rejection(rejection: &TxRejectionMsg) -> TxRejection {
+        let (reason, input_index, output_index, pending_tx) = match *rejection {
+            TxRejectionMsg::UnknownCurrency => {
+                (TxRejectionReason::UnknownCurrency, None, None, None)
+            }
+            TxRejectionMsg::AlreadyPending => (TxRejectionReason::AlreadyPending, None, None, None),
+            TxRejectionMsg::InvalidDocument(_) => {
+                (TxRejectionReason::InvalidDocument, None, None, None)
+            }
+            TxRejectionMsg::UnknownBlockstamp(_) => {
+                (TxRejectionReason::UnknownBlockstamp, None, None, None)
+            }
+            TxRejectionMsg::Expired => (TxRejectionReason::Expired, None, None, None),
+            TxRejectionMsg::Locktime(_) => (TxRejectionReason::Locktime, None, None, None),
+            TxRejectionMsg::InvalidOutput { output_index } => (
+                TxRejectionReason::InvalidOutput,
+                None,
+                Some(output_index as i32),
+                None,
+            ),
+            TxRejectionMsg::UnknownSource { input_index } => (
+                TxRejectionReason::UnknownSource,
+                Some(input_index as i32),
+                None,
+                None,
+            ),
+            TxRejectionMsg::DoubleSpend {
+                input_index,
+                pending_tx,
+            } => (
+                TxRejectionReason::DoubleSpend,
+                Some(input_index as i32),
+                None,
+                Some(pending_tx.to_hex()),
+            ),
+            TxRejectionMsg::InvalidUnlock { input_index } => (
+                TxRejectionReason::InvalidUnlock,
+                Some(input_index as i32),
+                None,
+                None,
+            ),
+            TxRejectionMsg::LockedSource { input_index } => (
+                TxRejectionReason::LockedSource,
+                Some(input_index as i32),
+                None,
+                None,
+            ),
+            TxRejectionMsg::IssuerQuota(_) => (TxRejectionReason::IssuerQuota, None, None, None),
+        };
+        TxRejection {
+            reason,
+            message: rejection.to_string(),
+            input_index,
+            output_index,
+            pending_tx,
+        }
+    }
+}
+
+impl super::super::TxSubmissionFields for TxSubmission {
+    #[inline]
+    fn field_hash(&self, _executor: &Executor<'_, QueryContext>) -> FieldResult<&Option<String>> {
+        Ok(&self.hash)
+    }
+    #[inline]
+    fn field_rejection(
+        &self,
+        _executor: &Executor<'_, QueryContext>,
+        _trail: &QueryTrail<'_, TxRejection, Walked>,
+    ) -> FieldResult<&Option<TxRejection>> {
+        Ok(&self.rejection)
+    }
+}
+
+impl super::super::TxRejectionFields for TxRejection {
+    #[inline]
+    fn field_reason(
+        &self,
+        _executor: &Executor<'_, QueryContext>,
+    ) -> FieldResult<&TxRejectionReason> {
+        Ok(&self.reason)
+    }
+    #[inline]
+    fn field_message(&self, _executor: &Executor<'_, QueryContext>) -> FieldResult<&String> {
+        Ok(&self.message)
+    }
+    #[inline]
+    fn field_input_index(
+        &self,
+        _executor: &Executor<'_, QueryContext>,
+    ) -> FieldResult<&Option<i32>> {
+        Ok(&self.input_index)
+    }
+    #[inline]
+    fn field_output_index(
+        &self,
+        _executor: &Executor<'_, QueryContext>,
+    ) -> FieldResult<&Option<i32>> {
+        Ok(&self.output_index)
+    }
+    #[inline]
+    fn field_pending_tx(
+        &self,
+        _executor: &Executor<'_, QueryContext>,
+    ) -> FieldResult<&Option<String>> {
+        Ok(&self.pending_tx)
+    }
+}
@@ -0,0 +1,187 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// ! Module define graphql TxsHistoryPage and TxHistoryEntry types
+
+use crate::context::QueryContext;
+use crate::schema::TxDirection;
+use chrono::NaiveDateTime;
+use dubp_common_doc::BlockNumber;
+use durs_bc_db_reader::history::{
+    TxDirection as TxDirectionDb, TxHistoryCursor, TxHistoryEntry as TxHistoryEntryDb,
+    TxsHistoryPage as TxsHistoryPageDb,
+};
+use juniper::{Executor, FieldResult};
+use juniper_from_schema::{QueryTrail, Walked};
+
+pub struct TxsHistoryPage {
+    pub(crate) transactions: Vec<TxHistoryEntry>,
+    pub(crate) end_cursor: Option<String>,
+    pub(crate) has_next_page: bool,
+}
+
+pub struct TxHistoryEntry {
+    pub(crate) cursor: String,
+    pub(crate) hash: String,
+    pub(crate) block_number: i32,
+    pub(crate) blockchain_time: NaiveDateTime,
+    pub(crate) direction: TxDirection,
+    pub(crate) issuers: Vec<String>,
+    pub(crate) comment: String,
+    pub(crate) amount: i32,
+    pub(crate) base: i32,
+}
+
+// Format a cursor as "block_number:tx_index"
+pub(crate) fn format_cursor(cursor: TxHistoryCursor) -> String {
+    format!("{}:{}", cursor.block_number.0, cursor.tx_index)
+}
+
+// Parse a cursor formatted by `format_cursor`
+pub(crate) fn parse_cursor(cursor: &str) -> Option<TxHistoryCursor> {
+    let mut parts = cursor.splitn(2, ':');
+    let block_number = parts.next()?.parse().ok()?;
+    let tx_index = parts.next()?.parse().ok()?;
+    Some(TxHistoryCursor {
+        block_number: BlockNumber(block_number),
+        tx_index,
+    })
+}
+
+impl From<TxDirection> for TxDirectionDb {
+    fn from(direction: TxDirection) -> Self {
+        match direction {
+            TxDirection::Sent => TxDirectionDb::Sent,
+            TxDirection::Received => TxDirectionDb::Received,
+        }
+    }
+}
+
+impl TxsHistoryPage {
+    // Convert TxsHistoryPage (db entity) into TxsHistoryPage (gva entity)
+    pub(crate) fn from_txs_history_page_db(page_db: TxsHistoryPageDb) -> TxsHistoryPage {
+        TxsHistoryPage {
+            end_cursor: page_db.txs.last().map(|entry| format_cursor(entry.cursor)),
+            has_next_page: page_db.has_next_page,
+            transactions: page_db
+                .txs
+                .into_iter()
+                .map(TxHistoryEntry::from_tx_history_entry_db)
+                .collect(),
+        }
+    }
+}
+
+impl TxHistoryEntry {
+    // Convert TxHistoryEntry (db entity) into TxHistoryEntry (gva entity)
+    fn from_tx_history_entry_db(entry_db: TxHistoryEntryDb) -> TxHistoryEntry {
+        TxHistoryEntry {
+            cursor: format_cursor(entry_db.cursor),
+            hash: entry_db.hash.to_hex(),
+            block_number: entry_db.cursor.block_number.0 as i32,
+            blockchain_time: NaiveDateTime::from_timestamp(entry_db.blockchain_time as i64, 0),
+            direction: match entry_db.direction {
+                TxDirectionDb::Sent => TxDirection::Sent,
+                TxDirectionDb::Received => TxDirection::Received,
+            },
+            issuers: entry_db.issuers.iter().map(ToString::to_string).collect(),
+            comment: entry_db.comment,
+            amount: (entry_db.amount.0).0 as i32,
+            base: (entry_db.amount.1).0 as i32,
+        }
+    }
+}
+
+impl super::super::TxsHistoryPageFields for TxsHistoryPage {
+    #[inline]
+    fn field_transactions(
+        &self,
+        _executor: &Executor<'_, QueryContext>,
+        _trail: &QueryTrail<'_, TxHistoryEntry, Walked>,
+    ) -> FieldResult<&Vec<TxHistoryEntry>> {
+        Ok(&self.transactions)
+    }
+    #[inline]
+    fn field_end_cursor(
+        &self,
+        _executor: &Executor<'_, QueryContext>,
+    ) -> FieldResult<&Option<String>> {
+        Ok(&self.end_cursor)
+    }
+    #[inline]
+    fn field_has_next_page(&self, _executor: &Executor<'_, QueryContext>) -> FieldResult<&bool> {
+        Ok(&self.has_next_page)
+    }
+}
+
+impl super::super::TxHistoryEntryFields for TxHistoryEntry {
+    #[inline]
+    fn field_cursor(&self, _executor: &Executor<'_, QueryContext>) -> FieldResult<&String> {
+        Ok(&self.cursor)
+    }
+    #[inline]
+    fn field_hash(&self, _executor: &Executor<'_, QueryContext>) -> FieldResult<&String> {
+        Ok(&self.hash)
+    }
+    #[inline]
+    fn field_block_number(&self, _executor: &Executor<'_, QueryContext>) -> FieldResult<&i32> {
+        Ok(&self.block_number)
+    }
+    #[inline]
+    fn field_blockchain_time(
+        &self,
+        _executor: &Executor<'_, QueryContext>,
+    ) -> FieldResult<&NaiveDateTime> {
+        Ok(&self.blockchain_time)
+    }
+    #[inline]
+    fn field_direction(&self, _executor: &Executor<'_, QueryContext>) -> FieldResult<&TxDirection> {
+        Ok(&self.direction)
+    }
+    #[inline]
+    fn field_issuers(&self, _executor: &Executor<'_, QueryContext>) -> FieldResult<&Vec<String>> {
+        Ok(&self.issuers)
+    }
+    #[inline]
+    fn field_comment(&self, _executor: &Executor<'_, QueryContext>) -> FieldResult<&String> {
+        Ok(&self.comment)
+    }
+    #[inline]
+    fn field_amount(&self, _executor: &Executor<'_, QueryContext>) -> FieldResult<&i32> {
+        Ok(&self.amount)
+    }
+    #[inline]
+    fn field_base(&self, _executor: &Executor<'_, QueryContext>) -> FieldResult<&i32> {
+        Ok(&self.base)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_cursor() {
+        let cursor = TxHistoryCursor {
+            block_number: BlockNumber(42),
+            tx_index: 3,
+        };
+        assert_eq!("42:3", format_cursor(cursor));
+        assert_eq!(Some(cursor), parse_cursor("42:3"));
+        assert_eq!(None, parse_cursor("42"));
+        assert_eq!(None, parse_cursor("a:3"));
+    }
+}
@@ -0,0 +1,107 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// ! Module define graphql UdCalendar type
+use crate::context::QueryContext;
+use crate::schema::entities::current_ud::{format_ud_amount, CurrentUd};
+use chrono::NaiveDateTime;
+use durs_bc_db_reader::current_metadata::current_ud::NextUdProjection;
+use juniper::{Executor, FieldResult};
+use juniper_from_schema::{QueryTrail, Walked};
+
+pub struct UdCalendar {
+    pub past_uds: Vec<CurrentUd>,
+    pub next_ud: Option<NextUd>,
+}
+
+pub struct NextUd {
+    pub blockchain_time: NaiveDateTime,
+    pub estimated_amount: i32,
+    pub base: i32,
+    pub reevaluation: bool,
+    pub reevaluation_time: NaiveDateTime,
+}
+
+impl NextUd {
+    // Convert NextUdProjection (computed from db entity) into NextUd (gva entity)
+    pub(crate) fn from_projection(projection: NextUdProjection) -> NextUd {
+        NextUd {
+            blockchain_time: NaiveDateTime::from_timestamp(projection.time as i64, 0),
+            estimated_amount: projection.amount as i32,
+            base: projection.base as i32,
+            reevaluation: projection.reevaluation,
+            reevaluation_time: NaiveDateTime::from_timestamp(
+                projection.reevaluation_time as i64,
+                0,
+            ),
+        }
+    }
+}
+
+impl super::super::UdCalendarFields for UdCalendar {
+    #[inline]
+    fn field_past_uds(
+        &self,
+        _executor: &Executor<'_, QueryContext>,
+        _trail: &QueryTrail<'_, CurrentUd, Walked>,
+    ) -> FieldResult<&Vec<CurrentUd>> {
+        Ok(&self.past_uds)
+    }
+    #[inline]
+    fn field_next_ud(
+        &self,
+        _executor: &Executor<'_, QueryContext>,
+        _trail: &QueryTrail<'_, NextUd, Walked>,
+    ) -> FieldResult<&Option<NextUd>> {
+        Ok(&self.next_ud)
+    }
+}
+
+impl super::super::NextUdFields for NextUd {
+    #[inline]
+    fn field_blockchain_time(
+        &self,
+        _executor: &Executor<'_, QueryContext>,
+    ) -> FieldResult<&NaiveDateTime> {
+        Ok(&self.blockchain_time)
+    }
+    #[inline]
+    fn field_estimated_amount(&self, _executor: &Executor<'_, QueryContext>) -> FieldResult<&i32> {
+        Ok(&self.estimated_amount)
+    }
+    #[inline]
+    fn field_base(&self, _executor: &Executor<'_, QueryContext>) -> FieldResult<&i32> {
+        Ok(&self.base)
+    }
+    #[inline]
+    fn field_reevaluation(&self, _executor: &Executor<'_, QueryContext>) -> FieldResult<&bool> {
+        Ok(&self.reevaluation)
+    }
+    #[inline]
+    fn field_reevaluation_time(
+        &self,
+        _executor: &Executor<'_, QueryContext>,
+    ) -> FieldResult<&NaiveDateTime> {
+        Ok(&self.reevaluation_time)
+    }
+    #[inline]
+    fn field_formatted_amount(&self, executor: &Executor<'_, QueryContext>) -> FieldResult<String> {
+        Ok(format_ud_amount(
+            executor.context(),
+            self.estimated_amount,
+            self.base,
+        ))
+    }
+}
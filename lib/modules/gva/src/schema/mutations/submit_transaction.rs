@@ -0,0 +1,165 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// ! Module execute GraphQl schema submitTransaction mutation
+
+use crate::context::QueryContext;
+use crate::schema::entities::tx_submission::TxSubmission;
+use dubp_common_doc::parser::TextDocumentParser;
+use dubp_common_doc::traits::Document;
+use dubp_currency_params::CurrencyName;
+use dubp_user_docs::documents::transaction::{
+    TransactionDocument, TransactionDocumentParser, TransactionDocumentTrait,
+};
+use durs_message::requests::{DursReqContent, MemPoolRequest};
+use durs_message::responses::{DursResContent, MemPoolResponse, TxRejection};
+use durs_module::ModuleRole;
+use juniper::{FieldError, FieldResult};
+
+// Verifications of the transaction that don't need the blockchain,
+// done before bothering the blockchain module with the transaction
+fn local_verify_tx(
+    currency_name: Option<&CurrencyName>,
+    tx: &TransactionDocument,
+) -> Result<(), TxRejection> {
+    if let Some(currency_name) = currency_name {
+        if tx.currency() != currency_name.0 {
+            return Err(TxRejection::InvalidDocument(format!(
+                "the transaction is a transaction of currency {}, the node currency is {}",
+                tx.currency(),
+                currency_name
+            )));
+        }
+    }
+    let TransactionDocument::V10(ref tx_v10) = tx;
+    if tx_v10.get_inputs().is_empty() {
+        return Err(TxRejection::InvalidDocument(
+            "the transaction has no input".to_owned(),
+        ));
+    }
+    tx.verify_signatures()
+        .map_err(|e| TxRejection::InvalidDocument(format!("invalid signatures: {:?}", e)))
+}
+
+pub(crate) fn execute(context: &QueryContext, raw_document: &str) -> FieldResult<TxSubmission> {
+    let tx = match TransactionDocumentParser::parse(raw_document) {
+        Ok(tx) => tx,
+        Err(e) => {
+            return Ok(TxSubmission::from_result(Err(
+                TxRejection::InvalidDocument(e.to_string()),
+            )))
+        }
+    };
+    if let Err(rejection) = local_verify_tx(context.get_currency_name(), &tx) {
+        return Ok(TxSubmission::from_result(Err(rejection)));
+    }
+
+    // The mempool of the transactions is held by the blockchain module (CurrencyPool role)
+    match context.get_requester().request(
+        ModuleRole::CurrencyPool,
+        DursReqContent::MemPoolRequest(MemPoolRequest::SubmitTransaction(Box::new(tx))),
+    )? {
+        DursResContent::MemPoolResponse(MemPoolResponse::TransactionSubmission(_, result)) => {
+            Ok(TxSubmission::from_result(result))
+        }
+        _ => Err(FieldError::from("Unexpected response of the node")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::db::BcDbRo;
+    use crate::schema::queries::tests;
+    use dubp_common_doc::traits::text::TextDocument;
+    use dubp_user_docs_tests_tools::mocks::tx::first_g1_tx_doc;
+    use serde_json::json;
+
+    static mut DB_TEST_SEND_TX_INVALID: Option<BcDbRo> = None;
+    static mut DB_TEST_SUBMIT_TX_INVALID_SIG: Option<BcDbRo> = None;
+
+    // First g1 transaction with a comment that doesn't match its signature
+    fn tampered_tx_raw_document() -> String {
+        first_g1_tx_doc()
+            .as_text()
+            .replace("Comment: TEST", "Comment: TEST2")
+    }
+
+    #[test]
+    fn test_local_verify_tx() {
+        let tx = first_g1_tx_doc();
+        let g1 = CurrencyName("g1".to_owned());
+
+        assert_eq!(Ok(()), local_verify_tx(None, &tx));
+        assert_eq!(Ok(()), local_verify_tx(Some(&g1), &tx));
+        match local_verify_tx(Some(&CurrencyName("g1-test".to_owned())), &tx) {
+            Err(TxRejection::InvalidDocument(_)) => {}
+            r => panic!("unexpected verification result: {:?}", r),
+        }
+
+        let tampered_tx = TransactionDocumentParser::parse(&tampered_tx_raw_document())
+            .expect("Fail to parse tampered tx");
+        match local_verify_tx(Some(&g1), &tampered_tx) {
+            Err(TxRejection::InvalidDocument(_)) => {}
+            r => panic!("unexpected verification result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_graphql_submit_transaction_invalid_signature() {
+        let schema = tests::setup(BcDbRo::new(), unsafe { &mut DB_TEST_SUBMIT_TX_INVALID_SIG });
+
+        // The node is not requested
+        tests::test_gql_mutation(
+            schema,
+            &format!(
+                r#"mutation {{ submitTransaction(rawDocument: {}) {{ hash, rejection {{ reason }} }} }}"#,
+                serde_json::to_string(&tampered_tx_raw_document()).expect("Fail to quote tx"),
+            ),
+            json!({
+                "data": {
+                    "submitTransaction": {
+                        "hash": null,
+                        "rejection": {
+                            "reason": "INVALID_DOCUMENT"
+                        }
+                    }
+                }
+            }),
+        )
+    }
+
+    #[test]
+    fn test_graphql_send_transaction_invalid_document() {
+        let schema = tests::setup(BcDbRo::new(), unsafe { &mut DB_TEST_SEND_TX_INVALID });
+
+        tests::test_gql_mutation(
+            schema,
+            r#"mutation { sendTransaction(rawTx: "Version: 10") { hash, rejection { reason, inputIndex } } }"#,
+            json!({
+                "data": {
+                    "sendTransaction": {
+                        "hash": null,
+                        "rejection": {
+                            "reason": "INVALID_DOCUMENT",
+                            "inputIndex": null
+                        }
+                    }
+                }
+            }),
+        )
+    }
+}
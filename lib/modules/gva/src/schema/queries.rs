@@ -17,21 +17,35 @@
 
 pub mod block;
 pub mod blocks;
+pub mod certifications;
 pub mod current;
 pub mod current_ud;
+pub mod distance_evaluation;
+pub mod idty;
+pub mod idty_availability;
+pub mod known_gva_endpoints;
+pub mod network_map;
 pub mod node;
+pub mod transactions_history;
+pub mod ud_calendar;
 
 #[cfg(test)]
 mod tests {
 
+    use crate::auth::Authorized;
     use crate::context::GlobalContext;
     use crate::db::BcDbRo;
-    use crate::graphql::graphql;
+    use crate::graphql::{graphql, RawGraphQLRequest};
+    use crate::known_endpoints::KnownGvaEndpoints;
+    use crate::requester::ModuleRequester;
     use crate::schema::create_schema;
-    use actix_web::web;
+    use actix_web::{test, web, HttpMessage};
     use assert_json_diff::assert_json_eq;
-    use juniper::http::GraphQLRequest;
-    use std::sync::Arc;
+    use dubp_common_doc::{BlockNumber, Blockstamp};
+    use dup_crypto::keys::PubKey;
+    use durs_bc_db_reader::indexes::identities::{IdentityDb, IdentityStateDb};
+    use durs_wot::WotId;
+    use std::sync::{Arc, RwLock};
 
     pub(crate) fn setup(
         mock_db: BcDbRo,
@@ -42,25 +56,74 @@ mod tests {
 
         // Init global context
         web::Data::new(std::sync::Arc::new(GlobalContext::new(
+            None,
             db,
+            std::path::PathBuf::from("blockchain"),
+            Arc::new(RwLock::new(KnownGvaEndpoints::default())),
+            std::path::PathBuf::from("network_map.json"),
+            // No module answers the requests
+            Arc::new(ModuleRequester::new(std::sync::mpsc::channel().0)),
             create_schema(),
             "soft_name",
             "soft_version",
         )))
     }
 
+    pub(crate) fn gen_mock_idty_db(pubkey: PubKey, wot_id: WotId) -> IdentityDb {
+        IdentityDb {
+            hash: "".to_owned(),
+            state: IdentityStateDb::Member(vec![]),
+            joined_on: Blockstamp::default(),
+            expired_on: None,
+            revoked_on: None,
+            idty_doc: dubp_user_docs_tests_tools::mocks::identity::gen_mock_idty(
+                pubkey,
+                BlockNumber(0),
+            ),
+            wot_id,
+            ms_created_block_id: BlockNumber(0),
+            ms_chainable_on: vec![],
+            cert_chainable_on: vec![],
+        }
+    }
+
     pub(crate) fn test_gql_query(
         global_context: web::Data<Arc<GlobalContext>>,
         gql_query: &str,
         expected_response: serde_json::Value,
     ) {
+        exec_gql_request(global_context, gql_query, false, expected_response)
+    }
+
+    pub(crate) fn test_gql_mutation(
+        global_context: web::Data<Arc<GlobalContext>>,
+        gql_query: &str,
+        expected_response: serde_json::Value,
+    ) {
+        exec_gql_request(global_context, gql_query, true, expected_response)
+    }
+
+    fn exec_gql_request(
+        global_context: web::Data<Arc<GlobalContext>>,
+        gql_query: &str,
+        authorized: bool,
+        expected_response: serde_json::Value,
+    ) {
+        let req = test::TestRequest::default().to_http_request();
+        req.extensions_mut().insert(Authorized(authorized));
+        let raw_request: RawGraphQLRequest =
+            serde_json::from_value(serde_json::json!({ "query": gql_query }))
+                .expect("invalid graphql request");
         let resp = actix_rt::Runtime::new()
             .expect("fail to start async executor")
-            .block_on(graphql(
-                global_context,
-                web::Json(GraphQLRequest::new(gql_query.to_owned(), None, None)),
-            ))
+            .block_on(graphql(req, global_context, web::Json(raw_request)))
             .expect("async executor crashed");
-        assert_json_eq!(expected_response, resp.0)
+        let body = match resp.body().as_ref() {
+            Some(actix_web::body::Body::Bytes(bytes)) => {
+                serde_json::from_slice(bytes).expect("invalid json response")
+            }
+            _ => panic!("unexpected response body"),
+        };
+        assert_json_eq!(expected_response, body)
     }
 }
@@ -0,0 +1,171 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// ! Module execute GraphQl schema certifiersOf and certifiedBy queries
+
+use super::idty::find_identity;
+use crate::schema::entities::certification::Certification;
+use crate::schema::entities::identity::Identity;
+use dubp_block_doc::block::BlockDocumentTrait;
+use dubp_common_doc::BlockNumber;
+use dubp_currency_params::CurrencyParameters;
+use durs_bc_db_reader::{BcDbInReadTx, DbError};
+use juniper_from_schema::{QueryTrail, Walked};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Certifications issued or received by an identity
+pub(crate) enum CertsDirection {
+    Issued,
+    Received,
+}
+
+pub(crate) fn execute<DB: BcDbInReadTx>(
+    db: &DB,
+    _trail: &QueryTrail<'_, Certification, Walked>,
+    uid_or_pubkey: &str,
+    direction: CertsDirection,
+    currency_params: Option<&CurrencyParameters>,
+) -> Result<Vec<Certification>, DbError> {
+    let idty_db = if let Some(idty_db) = find_identity(db, uid_or_pubkey)? {
+        idty_db
+    } else {
+        return Ok(vec![]);
+    };
+    let current_block_number = if let Some(current_blockstamp) = db.get_current_blockstamp()? {
+        current_blockstamp.id
+    } else {
+        return Ok(vec![]);
+    };
+
+    let wot_id = idty_db.wot_id;
+    let certs: Vec<_> = db
+        .get_member_certs(wot_id, current_block_number)?
+        .into_iter()
+        .filter(|(_, source, target)| match direction {
+            CertsDirection::Issued => *source == wot_id,
+            CertsDirection::Received => *target == wot_id,
+        })
+        .collect();
+
+    // A certification expires sig_validity after the time of the block it references
+    let expire_times: HashMap<BlockNumber, u64> = if let Some(currency_params) = currency_params {
+        let mut created_blocks: Vec<BlockNumber> =
+            certs.iter().map(|(created_on, _, _)| *created_on).collect();
+        created_blocks.dedup();
+        db.get_db_blocks_in_local_blockchain(created_blocks)?
+            .into_iter()
+            .map(|block_db| {
+                (
+                    block_db.block.number(),
+                    block_db.block.common_time() + currency_params.sig_validity,
+                )
+            })
+            .collect()
+    } else {
+        HashMap::new()
+    };
+
+    let mut identities = HashMap::new();
+    identities.insert(wot_id, Identity::from_identity_db(idty_db));
+    let mut certifications = Vec::with_capacity(certs.len());
+    for (created_on, source, target) in certs {
+        let other = if source == wot_id { target } else { source };
+        if let Entry::Vacant(entry) = identities.entry(other) {
+            let other_idty_db = db
+                .get_identity_by_wot_id(other)?
+                .ok_or(DbError::DBCorrupted)?;
+            entry.insert(Identity::from_identity_db(other_idty_db));
+        }
+        certifications.push(Certification::new(
+            &identities[&source],
+            &identities[&target],
+            created_on,
+            expire_times.get(&created_on).copied(),
+        ));
+    }
+    Ok(certifications)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::BcDbRo;
+    use crate::schema::queries::tests;
+    use dubp_common_doc::{BlockHash, BlockNumber, Blockstamp};
+    use dup_crypto::hashs::Hash;
+    use dup_crypto_tests_tools::mocks::pubkey;
+    use durs_wot::WotId;
+    use mockall::predicate::eq;
+    use serde_json::json;
+
+    static mut DB_TEST_CERTIFIERS_OF: Option<BcDbRo> = None;
+
+    #[test]
+    fn test_graphql_certifiers_of() {
+        let mut mock_db = BcDbRo::new();
+
+        mock_db
+            .expect_get_identity_by_pubkey()
+            .with(eq(pubkey('A')))
+            .times(1)
+            .returning(|_| Ok(Some(tests::gen_mock_idty_db(pubkey('A'), WotId(0)))));
+        mock_db
+            .expect_get_current_blockstamp()
+            .times(1)
+            .returning(|| {
+                Ok(Some(Blockstamp {
+                    id: BlockNumber(10),
+                    hash: BlockHash(Hash::default()),
+                }))
+            });
+        mock_db
+            .expect_get_member_certs()
+            .with(eq(WotId(0)), eq(BlockNumber(10)))
+            .times(1)
+            .returning(|_, _| {
+                Ok(vec![
+                    (BlockNumber(1), WotId(1), WotId(0)),
+                    (BlockNumber(2), WotId(0), WotId(2)),
+                ])
+            });
+        mock_db
+            .expect_get_identity_by_wot_id()
+            .with(eq(WotId(1)))
+            .times(1)
+            .returning(|_| Ok(Some(tests::gen_mock_idty_db(pubkey('B'), WotId(1)))));
+
+        let schema = tests::setup(mock_db, unsafe { &mut DB_TEST_CERTIFIERS_OF });
+
+        // Currency parameters are unknown: no expiration time
+        tests::test_gql_query(
+            schema,
+            &format!(
+                r#"{{ certifiersOf(uidOrPubkey: "{}") {{ issuer, receiver, createdOn, expiresOn }} }}"#,
+                pubkey('A')
+            ),
+            json!({
+                "data": {
+                    "certifiersOf": [{
+                        "issuer": pubkey('B').to_string(),
+                        "receiver": pubkey('A').to_string(),
+                        "createdOn": 1,
+                        "expiresOn": null
+                    }]
+                }
+            }),
+        )
+    }
+}
@@ -0,0 +1,142 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// ! Module execute GraphQl schema distanceEvaluation query
+
+use crate::schema::entities::distance_evaluation::DistanceEvaluation;
+use dubp_currency_params::CurrencyParameters;
+use dup_crypto::keys::PubKey;
+use durs_bc_db_reader::constants::WOT_DB_FILENAME;
+use durs_bc_db_reader::tools::get_sentry_requirement;
+use durs_bc_db_reader::{BcDbInReadTx, DbError};
+use durs_dbs_tools::open_free_struct_file_db;
+use durs_wot::data::rusty::RustyWebOfTrust;
+use durs_wot::data::WebOfTrust;
+use durs_wot::operations::distance::{
+    DistanceCalculator, RustyDistanceCalculator, WotDistanceParameters,
+};
+use std::path::Path;
+
+// Load the wot last saved by the blockchain module (None before the first synchronization)
+pub(crate) fn load_wot(dbs_path: &Path) -> Result<Option<RustyWebOfTrust>, DbError> {
+    if dbs_path.join(WOT_DB_FILENAME).is_file() {
+        Ok(Some(
+            open_free_struct_file_db::<RustyWebOfTrust>(&dbs_path.to_path_buf(), WOT_DB_FILENAME)?
+                .read(Clone::clone)?,
+        ))
+    } else {
+        Ok(None)
+    }
+}
+
+pub(crate) fn execute<DB: BcDbInReadTx>(
+    db: &DB,
+    wot: Option<&RustyWebOfTrust>,
+    currency_params: Option<&CurrencyParameters>,
+    pubkey: &PubKey,
+) -> Result<Option<DistanceEvaluation>, DbError> {
+    let (wot, currency_params) = match (wot, currency_params) {
+        (Some(wot), Some(currency_params)) => (wot, currency_params),
+        _ => return Ok(None),
+    };
+    let wot_id = if let Some(wot_id) = db.get_wot_id(pubkey)? {
+        wot_id
+    } else {
+        return Ok(None);
+    };
+
+    let step_max = currency_params.step_max as u32;
+    Ok(RustyDistanceCalculator
+        .compute_distance(
+            wot,
+            WotDistanceParameters {
+                node: wot_id,
+                sentry_requirement: get_sentry_requirement(wot.get_enabled().len(), step_max),
+                step_max,
+                x_percent: currency_params.x_percent,
+            },
+        )
+        .map(DistanceEvaluation::from_wot_distance))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::db::BcDbRo;
+    use dubp_currency_params::genesis_block_params::v10::BlockV10Parameters;
+    use dubp_currency_params::CurrencyName;
+    use dup_crypto_tests_tools::mocks::pubkey;
+    use durs_wot::WotId;
+    use mockall::predicate::eq;
+
+    #[test]
+    fn test_distance_evaluation() -> Result<(), DbError> {
+        // A, B and C certify each other, A certifies D, E is not certified
+        let mut wot = RustyWebOfTrust::new(100);
+        for _ in 0..5 {
+            wot.add_node();
+        }
+        for (source, target) in &[(0, 1), (0, 2), (1, 0), (1, 2), (2, 0), (2, 1), (0, 3)] {
+            wot.add_link(WotId(*source), WotId(*target));
+        }
+        let currency_params = CurrencyParameters::from((
+            &CurrencyName("g1".to_owned()),
+            BlockV10Parameters::default(),
+        ));
+
+        let mut mock_db = BcDbRo::new();
+        mock_db
+            .expect_get_wot_id()
+            .with(eq(pubkey('D')))
+            .returning(|_| Ok(Some(WotId(3))));
+        mock_db
+            .expect_get_wot_id()
+            .with(eq(pubkey('E')))
+            .returning(|_| Ok(Some(WotId(4))));
+        mock_db
+            .expect_get_wot_id()
+            .with(eq(pubkey('F')))
+            .returning(|_| Ok(None));
+
+        let distance = execute(&mock_db, Some(&wot), Some(&currency_params), &pubkey('D'))?
+            .expect("D must have a distance evaluation");
+        assert_eq!(
+            (3, 3, 3, false),
+            (
+                distance.sentries,
+                distance.success,
+                distance.reached,
+                distance.outdistanced
+            )
+        );
+        let distance = execute(&mock_db, Some(&wot), Some(&currency_params), &pubkey('E'))?
+            .expect("E must have a distance evaluation");
+        assert_eq!(
+            (3, 0, 0, true),
+            (
+                distance.sentries,
+                distance.success,
+                distance.reached,
+                distance.outdistanced
+            )
+        );
+        // No identity or unknown currency parameters
+        assert!(execute(&mock_db, Some(&wot), Some(&currency_params), &pubkey('F'))?.is_none());
+        assert!(execute(&mock_db, Some(&wot), None, &pubkey('D'))?.is_none());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,112 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// ! Module execute GraphQl schema idty query
+
+use crate::schema::entities::identity::Identity;
+use dup_crypto::keys::PubKey;
+use durs_bc_db_reader::indexes::identities::IdentityDb;
+use durs_bc_db_reader::{BcDbInReadTx, DbError};
+use juniper_from_schema::{QueryTrail, Walked};
+use std::str::FromStr;
+
+// Find the identity of the blockchain with the username or public key `uid_or_pubkey`
+pub(crate) fn find_identity<DB: BcDbInReadTx>(
+    db: &DB,
+    uid_or_pubkey: &str,
+) -> Result<Option<IdentityDb>, DbError> {
+    if let Ok(pubkey) = PubKey::from_str(uid_or_pubkey) {
+        if let Some(idty_db) = db.get_identity_by_pubkey(&pubkey)? {
+            return Ok(Some(idty_db));
+        }
+    }
+    db.get_identity_by_uid(uid_or_pubkey)
+}
+
+pub(crate) fn execute<DB: BcDbInReadTx>(
+    db: &DB,
+    _trail: &QueryTrail<'_, Identity, Walked>,
+    uid_or_pubkey: &str,
+) -> Result<Option<Identity>, DbError> {
+    Ok(find_identity(db, uid_or_pubkey)?.map(Identity::from_identity_db))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::BcDbRo;
+    use crate::schema::queries::tests;
+    use dup_crypto_tests_tools::mocks::pubkey;
+    use durs_wot::WotId;
+    use mockall::predicate::eq;
+    use serde_json::json;
+
+    static mut DB_TEST_IDTY_BY_PUBKEY: Option<BcDbRo> = None;
+    static mut DB_TEST_IDTY_BY_UID: Option<BcDbRo> = None;
+
+    #[test]
+    fn test_graphql_idty_by_pubkey() {
+        let mut mock_db = BcDbRo::new();
+
+        mock_db
+            .expect_get_identity_by_pubkey()
+            .with(eq(pubkey('A')))
+            .times(1)
+            .returning(|_| Ok(Some(tests::gen_mock_idty_db(pubkey('A'), WotId(0)))));
+
+        let schema = tests::setup(mock_db, unsafe { &mut DB_TEST_IDTY_BY_PUBKEY });
+
+        tests::test_gql_query(
+            schema,
+            &format!(
+                r#"{{ idty(uidOrPubkey: "{}") {{ pubkey, state, joinedOn, revokedOn }} }}"#,
+                pubkey('A')
+            ),
+            json!({
+                "data": {
+                    "idty": {
+                        "pubkey": pubkey('A').to_string(),
+                        "state": "MEMBER",
+                        "joinedOn": "0-0000000000000000000000000000000000000000000000000000000000000000",
+                        "revokedOn": null
+                    }
+                }
+            }),
+        )
+    }
+
+    #[test]
+    fn test_graphql_idty_by_uid() {
+        let mut mock_db = BcDbRo::new();
+
+        // "toto" is not a public key
+        mock_db.expect_get_identity_by_pubkey().times(0);
+        mock_db
+            .expect_get_identity_by_uid()
+            .times(1)
+            .returning(|_| Ok(None));
+
+        let schema = tests::setup(mock_db, unsafe { &mut DB_TEST_IDTY_BY_UID });
+
+        tests::test_gql_query(
+            schema,
+            r#"{ idty(uidOrPubkey: "toto") { pubkey } }"#,
+            json!({
+                "data": {
+                    "idty": null
+                }
+            }),
+        )
+    }
+}
@@ -0,0 +1,77 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// ! Module execute GraphQl schema isUidAvailable and isPubkeyAvailable queries
+
+use crate::context::QueryContext;
+use dup_crypto::keys::PubKey;
+use durs_message::requests::{DursReqContent, MemPoolRequest};
+use durs_message::responses::{DursResContent, MemPoolResponse};
+use durs_module::ModuleRole;
+use juniper::{FieldError, FieldResult};
+use std::str::FromStr;
+
+pub(crate) fn execute_uid(context: &QueryContext, uid: String) -> FieldResult<bool> {
+    request_availability(context, MemPoolRequest::UidAvailability(uid))
+}
+
+pub(crate) fn execute_pubkey(context: &QueryContext, pubkey: &str) -> FieldResult<bool> {
+    let pubkey = PubKey::from_str(pubkey).map_err(|_| FieldError::from("Invalid public key"))?;
+    request_availability(context, MemPoolRequest::PubkeyAvailability(pubkey))
+}
+
+fn request_availability(context: &QueryContext, req: MemPoolRequest) -> FieldResult<bool> {
+    match context
+        .get_requester()
+        .request(ModuleRole::WotPool, DursReqContent::MemPoolRequest(req))?
+    {
+        DursResContent::MemPoolResponse(MemPoolResponse::IdentityAvailability(_, result)) => {
+            Ok(result.is_ok())
+        }
+        _ => Err(FieldError::from("Unexpected response of the node")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::BcDbRo;
+    use crate::schema::queries::tests;
+    use serde_json::json;
+
+    static mut DB_TEST_PUBKEY_AVAILABILITY_INVALID: Option<BcDbRo> = None;
+
+    #[test]
+    fn test_graphql_is_pubkey_available_invalid_pubkey() {
+        let schema = tests::setup(BcDbRo::new(), unsafe {
+            &mut DB_TEST_PUBKEY_AVAILABILITY_INVALID
+        });
+
+        tests::test_gql_query(
+            schema,
+            r#"{ isPubkeyAvailable(pubkey: "not a pubkey") }"#,
+            json!({
+                "data": null,
+                "errors": [{
+                    "message": "Invalid public key",
+                    "locations": [{
+                        "line": 1,
+                        "column": 3,
+                    }],
+                    "path": ["isPubkeyAvailable"]
+                }]
+            }),
+        )
+    }
+}
@@ -0,0 +1,56 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// ! Module execute GraphQl schema knownGvaEndpoints query
+
+use crate::context::QueryContext;
+use crate::errors::GvaError;
+use crate::schema::entities::gva_endpoint::GvaEndpoint;
+use juniper::FieldResult;
+
+pub(crate) fn execute(context: &QueryContext) -> FieldResult<Vec<GvaEndpoint>> {
+    Ok(context
+        .get_known_gva_endpoints()
+        .read()
+        .map_err(|_| GvaError::PoisonedLock)?
+        .endpoints()
+        .into_iter()
+        .map(GvaEndpoint::from_known_endpoint)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::BcDbRo;
+    use crate::schema::queries::tests;
+    use serde_json::json;
+
+    static mut DB_TEST_KNOWN_GVA_ENDPOINTS: Option<BcDbRo> = None;
+
+    #[test]
+    fn test_graphql_known_gva_endpoints_empty() {
+        let schema = tests::setup(BcDbRo::new(), unsafe { &mut DB_TEST_KNOWN_GVA_ENDPOINTS });
+
+        tests::test_gql_query(
+            schema,
+            "{ knownGvaEndpoints { host, port, path, lastSeen, declaredVersion } }",
+            json!({
+                "data": {
+                    "knownGvaEndpoints": []
+                }
+            }),
+        )
+    }
+}
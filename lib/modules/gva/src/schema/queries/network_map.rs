@@ -0,0 +1,61 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// ! Module execute GraphQl schema networkMap query
+
+use crate::context::QueryContext;
+use crate::schema::NetworkMapFormat;
+use durs_network::map::NetworkMap;
+use juniper::FieldResult;
+
+pub(crate) fn execute(
+    context: &QueryContext,
+    format: NetworkMapFormat,
+) -> FieldResult<Option<String>> {
+    let network_map_file_path = context.get_network_map_file_path();
+    if !network_map_file_path.exists() {
+        return Ok(None);
+    }
+    let network_map = NetworkMap::load(network_map_file_path)?;
+
+    Ok(Some(match format {
+        NetworkMapFormat::Json => serde_json::to_string(&network_map)?,
+        NetworkMapFormat::Dot => network_map.to_dot(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::BcDbRo;
+    use crate::schema::queries::tests;
+    use serde_json::json;
+
+    static mut DB_TEST_NETWORK_MAP: Option<BcDbRo> = None;
+
+    #[test]
+    fn test_graphql_network_map_unknown() {
+        let schema = tests::setup(BcDbRo::new(), unsafe { &mut DB_TEST_NETWORK_MAP });
+
+        tests::test_gql_query(
+            schema,
+            "{ networkMap(format: DOT) }",
+            json!({
+                "data": {
+                    "networkMap": null
+                }
+            }),
+        )
+    }
+}
@@ -0,0 +1,181 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// ! Module execute GraphQl schema transactionsHistory query
+
+use crate::constants::{MAX_PAGE_SIZE, MIN_PAGE_SIZE};
+use crate::schema::entities::txs_history::{parse_cursor, TxsHistoryPage};
+use crate::schema::TxDirection;
+use dubp_common_doc::BlockNumber;
+use dup_crypto::keys::PubKey;
+use durs_bc_db_reader::history::TxsHistoryFilter;
+use durs_bc_db_reader::{BcDbInReadTx, DbError};
+use juniper::FieldError;
+use juniper_from_schema::{QueryTrail, Walked};
+use std::str::FromStr;
+
+// Check and convert the arguments of the query
+pub(crate) fn parse_args(
+    pubkey: &str,
+    direction: Option<TxDirection>,
+    from_block: Option<i32>,
+    to_block: Option<i32>,
+    first: i32,
+    after: Option<String>,
+) -> Result<(PubKey, TxsHistoryFilter), FieldError> {
+    let pubkey = PubKey::from_str(pubkey).map_err(|_| FieldError::from("Invalid public key"))?;
+    let after = if let Some(ref after) = after {
+        Some(parse_cursor(after).ok_or_else(|| FieldError::from("Invalid cursor"))?)
+    } else {
+        None
+    };
+    Ok((
+        pubkey,
+        TxsHistoryFilter {
+            direction: direction.map(Into::into),
+            from: BlockNumber(std::cmp::max(from_block.unwrap_or(0), 0) as u32),
+            to: to_block.map(|to| BlockNumber(std::cmp::max(to, 0) as u32)),
+            first: std::cmp::min(std::cmp::max(first, MIN_PAGE_SIZE), MAX_PAGE_SIZE) as usize,
+            after,
+        },
+    ))
+}
+
+pub(crate) fn execute<DB: BcDbInReadTx>(
+    db: &DB,
+    _trail: &QueryTrail<'_, TxsHistoryPage, Walked>,
+    pubkey: &PubKey,
+    filter: TxsHistoryFilter,
+) -> Result<TxsHistoryPage, DbError> {
+    Ok(TxsHistoryPage::from_txs_history_page_db(
+        db.get_txs_history(pubkey, filter)?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::BcDbRo;
+    use crate::schema::queries::tests;
+    use dubp_common_doc::BlockNumber;
+    use dubp_user_docs::documents::transaction::{TxAmount, TxBase};
+    use dup_crypto::hashs::Hash;
+    use dup_crypto_tests_tools::mocks::pubkey;
+    use durs_bc_db_reader::history::{
+        TxDirection, TxHistoryCursor, TxHistoryEntry, TxsHistoryFilter, TxsHistoryPage,
+    };
+    use durs_bc_db_reader::indexes::sources::SourceAmount;
+    use mockall::predicate::eq;
+    use serde_json::json;
+
+    static mut DB_TEST_TXS_HISTORY_1: Option<BcDbRo> = None;
+    static mut DB_TEST_TXS_HISTORY_INVALID_CURSOR: Option<BcDbRo> = None;
+
+    #[test]
+    fn test_graphql_transactions_history() {
+        let mut mock_db = BcDbRo::new();
+
+        mock_db
+            .expect_get_txs_history()
+            .with(
+                eq(pubkey('A')),
+                eq(TxsHistoryFilter {
+                    direction: Some(TxDirection::Received),
+                    from: BlockNumber(0),
+                    to: None,
+                    first: 1,
+                    after: Some(TxHistoryCursor {
+                        block_number: BlockNumber(3),
+                        tx_index: 0,
+                    }),
+                }),
+            )
+            .times(1)
+            .returning(|_, _| {
+                Ok(TxsHistoryPage {
+                    txs: vec![TxHistoryEntry {
+                        cursor: TxHistoryCursor {
+                            block_number: BlockNumber(5),
+                            tx_index: 1,
+                        },
+                        blockchain_time: 1_488_987_127,
+                        hash: Hash([0u8; 32]),
+                        direction: TxDirection::Received,
+                        issuers: vec![pubkey('B')],
+                        comment: "pizza".to_owned(),
+                        amount: SourceAmount(TxAmount(1_000), TxBase(0)),
+                    }],
+                    has_next_page: true,
+                })
+            });
+
+        let schema = tests::setup(mock_db, unsafe { &mut DB_TEST_TXS_HISTORY_1 });
+
+        tests::test_gql_query(
+            schema,
+            &format!(
+                r#"{{ transactionsHistory(pubkey: "{}", direction: RECEIVED, first: 1, after: "3:0") {{
+                    transactions {{ cursor, blockNumber, blockchainTime, direction, issuers, comment, amount, base }},
+                    endCursor,
+                    hasNextPage
+                }} }}"#,
+                pubkey('A')
+            ),
+            json!({
+                "data": {
+                    "transactionsHistory": {
+                        "transactions": [{
+                            "cursor": "5:1",
+                            "blockNumber": 5,
+                            "blockchainTime": 1_488_987_127.0,
+                            "direction": "RECEIVED",
+                            "issuers": [pubkey('B').to_string()],
+                            "comment": "pizza",
+                            "amount": 1_000,
+                            "base": 0
+                        }],
+                        "endCursor": "5:1",
+                        "hasNextPage": true
+                    }
+                }
+            }),
+        )
+    }
+
+    #[test]
+    fn test_graphql_transactions_history_invalid_cursor() {
+        let schema = tests::setup(BcDbRo::new(), unsafe {
+            &mut DB_TEST_TXS_HISTORY_INVALID_CURSOR
+        });
+
+        tests::test_gql_query(
+            schema,
+            &format!(
+                r#"{{ transactionsHistory(pubkey: "{}", after: "not a cursor") {{ hasNextPage }} }}"#,
+                pubkey('A')
+            ),
+            json!({
+                "data": null,
+                "errors": [{
+                    "message": "Invalid cursor",
+                    "locations": [{
+                        "line": 1,
+                        "column": 3,
+                    }],
+                    "path": ["transactionsHistory"]
+                }]
+            }),
+        )
+    }
+}
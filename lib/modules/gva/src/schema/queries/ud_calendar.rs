@@ -0,0 +1,85 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// ! Module execute GraphQl schema udCalendar query
+use crate::schema::entities::current_ud::CurrentUd;
+use crate::schema::entities::ud_calendar::{NextUd, UdCalendar};
+use dubp_currency_params::CurrencyParameters;
+use durs_bc_db_reader::{BcDbInReadTx, DbError};
+use juniper_from_schema::{QueryTrail, Walked};
+
+pub(crate) fn execute<DB: BcDbInReadTx>(
+    db: &DB,
+    _trail: &QueryTrail<'_, UdCalendar, Walked>,
+    currency_params: Option<&CurrencyParameters>,
+) -> Result<UdCalendar, DbError> {
+    let uds = db.get_uds_history()?;
+    let next_ud = match (uds.last(), currency_params) {
+        (Some(last_ud), Some(currency_params)) => Some(NextUd::from_projection(
+            last_ud.project_next_ud(currency_params),
+        )),
+        _ => None,
+    };
+    Ok(UdCalendar {
+        past_uds: uds.into_iter().map(CurrentUd::from_current_du_db).collect(),
+        next_ud,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::BcDbRo;
+    use crate::schema::queries::tests;
+    use dubp_common_doc::BlockNumber;
+    use durs_bc_db_reader::current_metadata::current_ud::CurrentUdDb;
+    use serde_json::json;
+
+    static mut DB_TEST_UD_CALENDAR_1: Option<BcDbRo> = None;
+
+    #[test]
+    fn test_graphql_ud_calendar() {
+        let mut mock_db = BcDbRo::new();
+
+        mock_db.expect_get_uds_history().times(1).returning(|| {
+            Ok(vec![CurrentUdDb {
+                amount: 1_000,
+                base: 0,
+                block_number: BlockNumber(1),
+                common_time: 1_488_987_127,
+                members_count: 59,
+                monetary_mass: 59_000,
+            }])
+        });
+
+        let schema = tests::setup(mock_db, unsafe { &mut DB_TEST_UD_CALENDAR_1 });
+
+        // Currency parameters are unknown: no projection
+        tests::test_gql_query(
+            schema,
+            "{ udCalendar { pastUds { amount, blockNumber }, nextUd { estimatedAmount } } }",
+            json!({
+                "data": {
+                    "udCalendar": {
+                        "pastUds": [{
+                            "amount": 1_000,
+                            "blockNumber": 1
+                        }],
+                        "nextUd": null
+                    }
+                }
+            }),
+        )
+    }
+}
@@ -14,12 +14,17 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 // web server implementaion based on actix-web
 
+use crate::auth::{self, Authorized};
 use crate::context::GlobalContext;
 use crate::db::BcDbRo;
 use crate::graphql::graphql;
+use crate::known_endpoints::KnownGvaEndpoints;
+use crate::requester::ModuleRequester;
 use crate::schema::create_schema;
+use crate::GvaConf;
 use actix_cors::Cors;
-use actix_web::{middleware, web, App, HttpResponse, HttpServer};
+use actix_web::dev::{Server, Service};
+use actix_web::{middleware, web, App, HttpMessage, HttpResponse, HttpServer};
 #[cfg(not(test))]
 use durs_common_tools::fatal_error;
 use durs_conf::DuRsConf;
@@ -28,6 +33,7 @@ use durs_network_documents::host::Host;
 use durs_network_documents::url::Url;
 use juniper::http::graphiql::graphiql_source;
 use std::net::SocketAddr;
+use std::sync::mpsc;
 
 /// Database readonly handler (access to database)
 static mut DB_RO_HANDLER: Option<BcDbRo> = None;
@@ -39,16 +45,40 @@ async fn graphiql() -> HttpResponse {
         .body(html)
 }
 
+/// Listen addresses of the web server
+pub fn listen_addrs(host: Host, port: u16) -> std::io::Result<Vec<SocketAddr>> {
+    Url::from_host_port_path(host, port, None).to_listenable_addr("http")
+}
+
+/// Rebind the web server to new listen addresses.
+/// The running server stops accepting connections and drains its in-flight requests,
+/// then the web server thread binds the new addresses.
+pub fn rebind_web_server(
+    server: &Server,
+    rebind_sender: &mpsc::Sender<Vec<SocketAddr>>,
+    new_addrs: Vec<SocketAddr>,
+) {
+    if rebind_sender.send(new_addrs).is_ok() {
+        // The stop command is sent immediately, the returned future only waits for its completion
+        let _ = server.stop(true);
+    } else {
+        warn!("GVA: fail to rebind web server: web server thread stopped.");
+    }
+}
+
 pub fn start_web_server(
     soft_meta_datas: &SoftwareMetaDatas<DuRsConf>,
     host: Host,
-    port: u16,
+    conf: &GvaConf,
+    requester: std::sync::Arc<ModuleRequester>,
+    known_gva_endpoints: std::sync::Arc<std::sync::RwLock<KnownGvaEndpoints>>,
+    server_sender: mpsc::Sender<Server>,
+    rebind_receiver: mpsc::Receiver<Vec<SocketAddr>>,
 ) -> std::io::Result<()> {
     info!("GVA web server start...");
 
     // Define listen addrs
-    let addrs: Vec<SocketAddr> =
-        Url::from_host_port_path(host, port, None).to_listenable_addr("http")?;
+    let mut addrs: Vec<SocketAddr> = listen_addrs(host, conf.port)?;
 
     // Get DB
     #[cfg(not(test))]
@@ -66,30 +96,98 @@ pub fn start_web_server(
     // Give a static lifetime to the DB
     let db = durs_common_tools::fns::r#static::to_static_ref(db, unsafe { &mut DB_RO_HANDLER });
 
+    // Get currency name and parameters (unknown before the first synchronization)
+    let currency = dubp_currency_params::db::get_currency_params(durs_conf::get_datas_path(
+        soft_meta_datas.profile_path.clone(),
+    ))
+    .unwrap_or_else(|e| {
+        warn!("GVA: fail to read currency parameters: {}", e);
+        None
+    });
+
     // Create global context
     let global_context = std::sync::Arc::new(GlobalContext::new(
+        currency,
         db,
+        durs_conf::get_blockchain_db_path(soft_meta_datas.profile_path.clone()),
+        known_gva_endpoints,
+        durs_conf::get_datas_path(soft_meta_datas.profile_path.clone())
+            .join(durs_network::map::NETWORK_MAP_FILENAME),
+        requester,
         create_schema(),
         soft_meta_datas.soft_name,
         soft_meta_datas.soft_version,
     ));
 
-    // Start http server
-    actix_rt::System::new("gva").block_on(
-        HttpServer::new(move || {
-            App::new()
-                .data(global_context.clone())
-                .wrap(
-                    Cors::new()
-                        .expose_headers(vec!["Content-Length", "Content-Range"])
-                        .send_wildcard()
-                        .finish(),
-                )
-                .wrap(middleware::Logger::default())
-                .service(web::resource("/graphql").route(web::post().to(graphql)))
-                .service(web::resource("/graphiql").route(web::get().to(graphiql)))
-        })
-        .bind(&addrs[..])?
-        .run(),
-    )
+    // Value of the Authorization header required for mutating operations
+    let expected_auth_header: Option<std::sync::Arc<String>> = conf
+        .auth
+        .as_ref()
+        .map(|auth| std::sync::Arc::new(auth.expected_header_value()));
+    if !conf.expose_playground {
+        info!("GVA: graphiql playground disabled.");
+    }
+    let expose_playground = conf.expose_playground;
+
+    let app_factory = move || {
+        let expected_auth_header = expected_auth_header.clone();
+        let app = App::new()
+            .data(global_context.clone())
+            .wrap_fn(move |req, srv| {
+                // Flag the request, mutating operations are rejected by the graphql handler
+                // if it is not authorized.
+                let authorized = if let Some(ref expected) = expected_auth_header {
+                    auth::check_headers(req.headers(), expected)
+                } else {
+                    true
+                };
+                req.extensions_mut().insert(Authorized(authorized));
+                srv.call(req)
+            })
+            .wrap(
+                Cors::new()
+                    .expose_headers(vec!["Content-Length", "Content-Range"])
+                    .send_wildcard()
+                    .finish(),
+            )
+            .wrap(middleware::Logger::default())
+            .service(web::resource("/graphql").route(web::post().to(graphql)));
+        if expose_playground {
+            app.service(web::resource("/graphiql").route(web::get().to(graphiql)))
+        } else {
+            app
+        }
+    };
+
+    // Start http server, then start it again each time it is rebound
+    let mut system = actix_rt::System::new("gva");
+    let mut previous_addrs: Option<Vec<SocketAddr>> = None;
+    loop {
+        let http_server = match HttpServer::new(app_factory.clone()).bind(&addrs[..]) {
+            Ok(http_server) => http_server,
+            Err(e) => {
+                if let Some(previous_addrs) = previous_addrs.take() {
+                    // Keep listening on the previous addresses
+                    error!("GVA: fail to bind {:?}: {}", addrs, e);
+                    addrs = previous_addrs;
+                    continue;
+                } else {
+                    return Err(e);
+                }
+            }
+        };
+        info!("GVA: listen on {:?}.", addrs);
+        previous_addrs = Some(addrs.clone());
+
+        let server = http_server.run();
+        let _ = server_sender.send(server.clone());
+        system.block_on(server)?;
+
+        // The server is stopped, start it again if it is rebound
+        if let Ok(new_addrs) = rebind_receiver.try_recv() {
+            addrs = new_addrs;
+        } else {
+            break Ok(());
+        }
+    }
 }
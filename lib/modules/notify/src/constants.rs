@@ -0,0 +1,40 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Notify module constants
+
+/// Module name
+pub static MODULE_NAME: &str = "notify";
+
+/// Default maximum number of delivery attempts of a payload
+pub static DEFAULT_MAX_ATTEMPTS: &u32 = &5;
+
+/// Default delay before the first retry of a delivery (in seconds)
+pub static DEFAULT_RETRY_DELAY: &u64 = &10;
+
+/// Maximum duration of a delivery attempt (in seconds)
+pub static DELIVERY_TIMEOUT_IN_SECS: &u64 = &10;
+
+/// Name of the file storing the undeliverable payloads (in the module datas folder)
+pub static DEAD_LETTERS_FILENAME: &str = "notify_dead_letters.jsonl";
+
+/// HTTP header containing the type of the notified event
+pub static EVENT_HEADER: &str = "X-Durs-Event";
+
+/// HTTP header containing the HMAC-SHA256 signature of the payload
+pub static SIGNATURE_HEADER: &str = "X-Durs-Signature";
+
+/// User agent of the webhooks requests
+pub static USER_AGENT: &str = "dunitrust-notify";
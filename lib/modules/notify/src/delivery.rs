@@ -0,0 +1,173 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Delivery of the webhooks, with retry/backoff and dead letters.
+
+use crate::payloads::Payload;
+use crate::webhook;
+use crate::NotifyConf;
+use durs_common_tools::fns::thread::spawn_child;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, SystemTime};
+use url::Url;
+
+/// Interval between two checks of the pending deliveries
+static DELIVERY_TICK_IN_MS: &u64 = &500;
+
+#[derive(Debug, Clone, PartialEq)]
+/// Message received by the delivery thread
+pub enum DeliveryMsg {
+    /// Payload to deliver to all webhooks
+    Payload(Payload),
+    /// Stop the delivery thread
+    Stop,
+}
+
+#[derive(Debug, Clone)]
+/// Payload waiting to be delivered to one webhook
+struct Delivery {
+    url: Url,
+    event: &'static str,
+    body: String,
+    attempts: u32,
+    next_attempt: SystemTime,
+    last_error: String,
+}
+
+/// Delay before the next attempt of a delivery
+fn backoff_delay(retry_delay: u64, attempts: u32) -> Duration {
+    let factor = 2u64.saturating_pow(attempts.saturating_sub(1));
+    Duration::from_secs(retry_delay.saturating_mul(factor))
+}
+
+/// Append an undeliverable payload to the dead letters file
+fn write_dead_letter(dead_letters_path: &PathBuf, delivery: &Delivery) {
+    let payload: serde_json::Value =
+        serde_json::from_str(&delivery.body).unwrap_or(serde_json::Value::Null);
+    let line = json!({
+        "url": delivery.url.as_str(),
+        "event": delivery.event,
+        "attempts": delivery.attempts,
+        "lastError": delivery.last_error,
+        "payload": payload,
+    });
+    let result = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dead_letters_path)
+        .and_then(|mut file| writeln!(file, "{}", line));
+    if let Err(e) = result {
+        error!(
+            "NOTIFY: fail to write dead letter in '{}': {}",
+            dead_letters_path.display(),
+            e
+        );
+    }
+}
+
+/// Try to deliver a payload
+fn attempt(secret: &str, delivery: &mut Delivery) -> bool {
+    delivery.attempts += 1;
+    let signature = webhook::sign(secret, delivery.body.as_bytes());
+    match webhook::post(
+        &delivery.url,
+        delivery.event,
+        &signature,
+        delivery.body.as_bytes(),
+    ) {
+        Ok(status) if (200..300).contains(&status) => {
+            debug!("NOTIFY: {} delivered to {}", delivery.event, delivery.url);
+            return true;
+        }
+        Ok(status) => delivery.last_error = format!("HTTP status {}", status),
+        Err(e) => delivery.last_error = e.to_string(),
+    }
+    warn!(
+        "NOTIFY: fail to deliver {} to {} (attempt {}): {}",
+        delivery.event, delivery.url, delivery.attempts, delivery.last_error
+    );
+    false
+}
+
+/// Start the delivery thread
+pub fn start_delivery_thread(
+    conf: NotifyConf,
+    dead_letters_path: PathBuf,
+) -> (mpsc::Sender<DeliveryMsg>, thread::JoinHandle<()>) {
+    let (sender, receiver) = mpsc::channel();
+    let handle = spawn_child("delivery", move || {
+        let mut pending: Vec<Delivery> = Vec::new();
+        loop {
+            match receiver.recv_timeout(Duration::from_millis(*DELIVERY_TICK_IN_MS)) {
+                Ok(DeliveryMsg::Payload(payload)) => {
+                    let body = payload.body.to_string();
+                    for url in &conf.webhooks {
+                        pending.push(Delivery {
+                            url: url.clone(),
+                            event: payload.event,
+                            body: body.clone(),
+                            attempts: 0,
+                            next_attempt: SystemTime::now(),
+                            last_error: String::new(),
+                        });
+                    }
+                }
+                Ok(DeliveryMsg::Stop) | Err(mpsc::RecvTimeoutError::Disconnected) => break,
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+            }
+
+            let now = SystemTime::now();
+            let mut still_pending = Vec::with_capacity(pending.len());
+            for mut delivery in pending.drain(..) {
+                if delivery.next_attempt > now {
+                    still_pending.push(delivery);
+                } else if !attempt(&conf.secret, &mut delivery) {
+                    if delivery.attempts >= conf.max_attempts {
+                        write_dead_letter(&dead_letters_path, &delivery);
+                    } else {
+                        delivery.next_attempt =
+                            now + backoff_delay(conf.retry_delay, delivery.attempts);
+                        still_pending.push(delivery);
+                    }
+                }
+            }
+            pending = still_pending;
+        }
+
+        // Keep a trace of the payloads not delivered before stopping
+        for delivery in &pending {
+            write_dead_letter(&dead_letters_path, delivery);
+        }
+    });
+    (sender, handle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_delay() {
+        assert_eq!(Duration::from_secs(10), backoff_delay(10, 1));
+        assert_eq!(Duration::from_secs(20), backoff_delay(10, 2));
+        assert_eq!(Duration::from_secs(80), backoff_delay(10, 4));
+        // Saturates instead of overflowing
+        assert!(backoff_delay(10, 100) >= backoff_delay(10, 64));
+    }
+}
@@ -0,0 +1,353 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Notify module for the Dunitrust project.
+//!
+//! POSTs webhook payloads signed with HMAC-SHA256 for new blocks, for the activity
+//! of watched accounts and for the messages of the operators of directly connected nodes,
+//! for integrations that can't use the client APIs.
+
+#![deny(
+    clippy::option_unwrap_used,
+    clippy::result_unwrap_used,
+    missing_docs,
+    missing_debug_implementations,
+    missing_copy_implementations,
+    trivial_casts,
+    unsafe_code,
+    unstable_features,
+    unused_import_braces,
+    unused_qualifications
+)]
+
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate serde_derive;
+#[macro_use]
+extern crate serde_json;
+#[macro_use]
+extern crate structopt;
+
+pub mod constants;
+mod delivery;
+pub mod payloads;
+pub mod webhook;
+
+use crate::delivery::DeliveryMsg;
+use dubp_currency_params::CurrencyName;
+use dup_crypto::keys::PubKey;
+use durs_common_tools::fatal_error;
+use durs_common_tools::traits::merge::Merge;
+use durs_conf::DuRsConf;
+use durs_message::events::*;
+use durs_message::*;
+use durs_module::*;
+use durs_network::events::NetworkEvent;
+use std::collections::HashSet;
+use std::ops::Deref;
+use std::str::FromStr;
+use std::sync::mpsc;
+use url::Url;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Notify module configuration
+pub struct NotifyConf {
+    /// Urls of the webhooks
+    pub webhooks: Vec<Url>,
+    /// Secret used to sign the payloads
+    pub secret: String,
+    /// Accounts whose activity is notified
+    pub watched_accounts: HashSet<PubKey>,
+    /// Maximum number of delivery attempts of a payload
+    pub max_attempts: u32,
+    /// Delay before the first retry of a delivery (in seconds)
+    pub retry_delay: u64,
+}
+
+impl Default for NotifyConf {
+    fn default() -> Self {
+        NotifyConf {
+            webhooks: vec![],
+            secret: String::new(),
+            watched_accounts: HashSet::new(),
+            max_attempts: *constants::DEFAULT_MAX_ATTEMPTS,
+            retry_delay: *constants::DEFAULT_RETRY_DELAY,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// Notify module user configuration
+pub struct NotifyUserConf {
+    /// Urls of the webhooks
+    pub webhooks: Option<Vec<String>>,
+    /// Secret used to sign the payloads
+    pub secret: Option<String>,
+    /// Accounts whose activity is notified
+    pub watched_accounts: Option<Vec<String>>,
+    /// Maximum number of delivery attempts of a payload
+    pub max_attempts: Option<u32>,
+    /// Delay before the first retry of a delivery (in seconds)
+    pub retry_delay: Option<u64>,
+}
+
+impl Merge for NotifyUserConf {
+    fn merge(self, other: Self) -> Self {
+        NotifyUserConf {
+            webhooks: self.webhooks.or(other.webhooks),
+            secret: self.secret.or(other.secret),
+            watched_accounts: self.watched_accounts.or(other.watched_accounts),
+            max_attempts: self.max_attempts.or(other.max_attempts),
+            retry_delay: self.retry_delay.or(other.retry_delay),
+        }
+    }
+}
+
+#[derive(StructOpt, Debug, Copy, Clone)]
+#[structopt(name = "notify", setting(structopt::clap::AppSettings::ColoredHelp))]
+/// Notify subcommand options
+pub struct NotifyOpt {}
+
+/// Parse the url of a webhook
+fn parse_webhook_url(url: &str) -> Result<Url, ModuleConfError> {
+    let url = Url::parse(url).map_err(|e| ModuleConfError::InvalidField {
+        field_name: stringify!(webhooks),
+        cause: format!("'{}' is not a valid url: {}", url, e),
+    })?;
+    if url.scheme() != "http" {
+        return Err(ModuleConfError::InvalidField {
+            field_name: stringify!(webhooks),
+            cause: format!(
+                "scheme '{}' is not supported, use a local TLS proxy for https webhooks",
+                url.scheme()
+            ),
+        });
+    }
+    Ok(url)
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+/// Notify module
+pub struct NotifyModule {}
+
+impl DursModule<DuRsConf, DursMsg> for NotifyModule {
+    type ModuleUserConf = NotifyUserConf;
+    type ModuleConf = NotifyConf;
+    type ModuleOpt = NotifyOpt;
+
+    fn name() -> ModuleStaticName {
+        ModuleStaticName(constants::MODULE_NAME)
+    }
+    fn priority() -> ModulePriority {
+        ModulePriority::Optional
+    }
+    fn ask_required_keys() -> RequiredKeys {
+        RequiredKeys::None
+    }
+    fn have_subcommand() -> bool {
+        false
+    }
+    fn generate_module_conf(
+        _currency_name: Option<&CurrencyName>,
+        _global_conf: &<DuRsConf as DursConfTrait>::GlobalConf,
+        module_user_conf: Option<Self::ModuleUserConf>,
+    ) -> Result<(Self::ModuleConf, Option<Self::ModuleUserConf>), ModuleConfError> {
+        let mut conf = NotifyConf::default();
+
+        if let Some(ref module_user_conf) = module_user_conf {
+            if let Some(ref webhooks) = module_user_conf.webhooks {
+                conf.webhooks = webhooks
+                    .iter()
+                    .map(|url| parse_webhook_url(url))
+                    .collect::<Result<Vec<Url>, ModuleConfError>>()?;
+            }
+            if let Some(ref secret) = module_user_conf.secret {
+                conf.secret = secret.to_owned();
+            }
+            if !conf.webhooks.is_empty() && conf.secret.is_empty() {
+                return Err(ModuleConfError::InvalidField {
+                    field_name: stringify!(secret),
+                    cause: "a secret is required to sign the webhooks".to_owned(),
+                });
+            }
+            if let Some(ref watched_accounts) = module_user_conf.watched_accounts {
+                conf.watched_accounts = watched_accounts
+                    .iter()
+                    .enumerate()
+                    .map(|(i, p)| {
+                        PubKey::from_str(p).map_err(|e| ModuleConfError::InvalidField {
+                            field_name: stringify!(watched_accounts),
+                            cause: format!("pubkey n°{} is invalid: {}", i, e),
+                        })
+                    })
+                    .collect::<Result<HashSet<PubKey>, ModuleConfError>>()?;
+            }
+            if module_user_conf.max_attempts == Some(0) {
+                return Err(ModuleConfError::InvalidField {
+                    field_name: stringify!(max_attempts),
+                    cause: "must be greater than 0".to_owned(),
+                });
+            }
+            if let Some(max_attempts) = module_user_conf.max_attempts {
+                conf.max_attempts = max_attempts;
+            }
+            if let Some(retry_delay) = module_user_conf.retry_delay {
+                conf.retry_delay = retry_delay;
+            }
+        }
+
+        Ok((conf, module_user_conf))
+    }
+    fn exec_subcommand(
+        _soft_meta_datas: &SoftwareMetaDatas<DuRsConf>,
+        _keys: RequiredKeysContent,
+        _module_conf: Self::ModuleConf,
+        module_user_conf: Option<Self::ModuleUserConf>,
+        _subcommand_args: Self::ModuleOpt,
+    ) -> Option<Self::ModuleUserConf> {
+        module_user_conf
+    }
+    fn start(
+        soft_meta_datas: &SoftwareMetaDatas<DuRsConf>,
+        _keys: RequiredKeysContent,
+        conf: Self::ModuleConf,
+        router_sender: mpsc::Sender<RouterThreadMessage<DursMsg>>,
+    ) -> Result<(), failure::Error> {
+        // Create module channel
+        let (module_sender, module_receiver): (mpsc::Sender<DursMsg>, mpsc::Receiver<DursMsg>) =
+            mpsc::channel();
+
+        // Registration with the router
+        if router_sender
+            .send(RouterThreadMessage::ModuleRegistration {
+                static_name: ModuleStaticName(constants::MODULE_NAME),
+                sender: module_sender,
+                roles: vec![ModuleRole::UserInterface],
+                events_subscription: vec![
+                    ModuleEvent::NewValidBlock,
+                    ModuleEvent::NewOperatorMessage,
+                ],
+                reserved_apis_parts: vec![],
+                endpoints: vec![],
+            })
+            .is_err()
+        {
+            fatal_error!("NOTIFY module fail to send registration to router !")
+        }
+
+        if conf.webhooks.is_empty() {
+            info!("NOTIFY: no webhook configured.");
+        }
+
+        // Start delivery thread
+        let dead_letters_path = durs_conf::get_datas_path(soft_meta_datas.profile_path.clone())
+            .join(constants::DEAD_LETTERS_FILENAME);
+        let watched_accounts = conf.watched_accounts.clone();
+        let have_webhooks = !conf.webhooks.is_empty();
+        let (delivery_sender, delivery_handle) =
+            delivery::start_delivery_thread(conf, dead_letters_path);
+
+        while let Ok(msg) = module_receiver.recv() {
+            match msg {
+                DursMsg::Stop => break,
+                DursMsg::Event {
+                    event_content:
+                        DursEvent::NetworkEvent(NetworkEvent::ReceiveOperatorMessage(from, ref text)),
+                    ..
+                } => {
+                    info!("NOTIFY: operator message from {}: {}", from, text);
+                    if have_webhooks
+                        && delivery_sender
+                            .send(DeliveryMsg::Payload(payloads::operator_message_payload(
+                                from, text,
+                            )))
+                            .is_err()
+                    {
+                        fatal_error!("NOTIFY: delivery thread unexpectedly disconnected !");
+                    }
+                }
+                DursMsg::Event {
+                    event_content: DursEvent::BlockchainEvent(ref blockchain_event),
+                    ..
+                } if have_webhooks => {
+                    if let BlockchainEvent::StackUpValidBlock(ref block) = *blockchain_event.deref()
+                    {
+                        let mut payloads = vec![payloads::block_payload(block)];
+                        payloads.append(&mut payloads::accounts_activity_payloads(
+                            block,
+                            &watched_accounts,
+                        ));
+                        for payload in payloads {
+                            if delivery_sender.send(DeliveryMsg::Payload(payload)).is_err() {
+                                fatal_error!("NOTIFY: delivery thread unexpectedly disconnected !");
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let _ = delivery_sender.send(DeliveryMsg::Stop);
+        let _ = delivery_handle.join();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user_conf(webhooks: &[&str], secret: Option<&str>) -> NotifyUserConf {
+        NotifyUserConf {
+            webhooks: Some(webhooks.iter().map(|url| (*url).to_owned()).collect()),
+            secret: secret.map(ToOwned::to_owned),
+            ..NotifyUserConf::default()
+        }
+    }
+
+    #[test]
+    fn test_generate_module_conf() -> Result<(), ModuleConfError> {
+        let global_conf = DuRsConf::default().get_global_conf();
+
+        let (conf, _) = NotifyModule::generate_module_conf(None, &global_conf, None)?;
+        assert_eq!(NotifyConf::default(), conf);
+
+        let (conf, _) = NotifyModule::generate_module_conf(
+            None,
+            &global_conf,
+            Some(user_conf(&["http://localhost:8080/hooks"], Some("secret"))),
+        )?;
+        assert_eq!(1, conf.webhooks.len());
+        assert_eq!("secret", conf.secret);
+
+        // https and missing secret are rejected
+        assert!(NotifyModule::generate_module_conf(
+            None,
+            &global_conf,
+            Some(user_conf(&["https://example.org/hooks"], Some("secret"))),
+        )
+        .is_err());
+        assert!(NotifyModule::generate_module_conf(
+            None,
+            &global_conf,
+            Some(user_conf(&["http://localhost:8080/hooks"], None)),
+        )
+        .is_err());
+
+        Ok(())
+    }
+}
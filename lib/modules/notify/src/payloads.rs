@@ -0,0 +1,165 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Payloads of the webhooks.
+
+use dubp_block_doc::block::BlockDocumentTrait;
+use dubp_block_doc::BlockDocument;
+use dubp_common_doc::traits::Document;
+use dubp_user_docs::documents::transaction::v10::TransactionDocumentV10;
+use dubp_user_docs::documents::transaction::{
+    TransactionDocumentTrait, TransactionOutputCondition, UTXOConditionsGroup,
+};
+use dup_crypto::keys::PubKey;
+use durs_network_documents::NodeFullId;
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, PartialEq)]
+/// Payload of a webhook
+pub struct Payload {
+    /// Type of the notified event
+    pub event: &'static str,
+    /// JSON body
+    pub body: serde_json::Value,
+}
+
+/// Payload notifying a new block
+pub fn block_payload(block: &BlockDocument) -> Payload {
+    let BlockDocument::V10(block_v10) = block;
+    Payload {
+        event: "block",
+        body: json!({
+            "event": "block",
+            "currency": block_v10.currency.to_string(),
+            "number": block.number().0,
+            "hash": block.hash().map(|hash| hash.to_string()),
+            "medianTime": block_v10.median_time,
+            "issuer": block_v10.issuers.first().map(ToString::to_string),
+            "membersCount": block_v10.members_count.0,
+            "transactionsCount": block_v10.transactions.len(),
+        }),
+    }
+}
+
+/// Payload notifying a message from the operator of a directly connected node
+pub fn operator_message_payload(from: NodeFullId, text: &str) -> Payload {
+    Payload {
+        event: "operator_message",
+        body: json!({
+            "event": "operator_message",
+            "node": from.to_string(),
+            "text": text,
+        }),
+    }
+}
+
+/// Payloads notifying the transactions of a block concerning the watched accounts
+pub fn accounts_activity_payloads(
+    block: &BlockDocument,
+    watched_accounts: &HashSet<PubKey>,
+) -> Vec<Payload> {
+    let BlockDocument::V10(block_v10) = block;
+    let mut payloads = Vec::new();
+    for tx in &block_v10.transactions {
+        for account in watched_accounts {
+            let sent = tx.issuers().contains(account);
+            let received_amount = received_amount(tx, account);
+            if sent || received_amount > 0 {
+                payloads.push(Payload {
+                    event: "account_activity",
+                    body: json!({
+                        "event": "account_activity",
+                        "currency": block_v10.currency.to_string(),
+                        "account": account.to_string(),
+                        "blockNumber": block.number().0,
+                        "blockHash": block.hash().map(|hash| hash.to_string()),
+                        "medianTime": block_v10.median_time,
+                        "transactionHash": tx.get_hash_opt().unwrap_or_else(|| tx.compute_hash()).to_string(),
+                        "sent": sent,
+                        "receivedAmount": received_amount,
+                    }),
+                });
+            }
+        }
+    }
+    payloads
+}
+
+/// Amount of the outputs of the transaction that the account alone can spend (in currency cents)
+fn received_amount(tx: &TransactionDocumentV10, account: &PubKey) -> isize {
+    tx.get_outputs()
+        .iter()
+        .filter(|output| only_sig(&output.conditions.conditions) == Some(*account))
+        .map(|output| output.amount.0 * 10isize.pow(output.base.0 as u32))
+        .sum()
+}
+
+fn only_sig(conditions: &UTXOConditionsGroup) -> Option<PubKey> {
+    match *conditions {
+        UTXOConditionsGroup::Single(TransactionOutputCondition::Sig(pubkey)) => Some(pubkey),
+        UTXOConditionsGroup::Brackets(ref conditions) => only_sig(conditions),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dubp_blocks_tests_tools::mocks::gen_mock_normal_block_v10;
+    use durs_network_documents::NodeId;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_block_payload() {
+        let block = BlockDocument::V10(gen_mock_normal_block_v10());
+        let payload = block_payload(&block);
+        assert_eq!("block", payload.event);
+        assert_eq!(json!(107_984), payload.body["number"]);
+        assert_eq!(json!(2), payload.body["transactionsCount"]);
+    }
+
+    #[test]
+    fn test_operator_message_payload() {
+        let payload = operator_message_payload(
+            NodeFullId(NodeId(1), PubKey::default()),
+            "maintenance at 20:00",
+        );
+        assert_eq!("operator_message", payload.event);
+        assert_eq!(json!("maintenance at 20:00"), payload.body["text"]);
+    }
+
+    #[test]
+    fn test_accounts_activity_payloads() {
+        let block = BlockDocument::V10(gen_mock_normal_block_v10());
+        let issuer = PubKey::from_str("8dkCwvAqSczUjKsoVMDPVbQ3i6bBQeBQYawL87kqTSQ3")
+            .expect("invalid pubkey");
+        let recipient = PubKey::from_str("78ZwwgpgdH5uLZLbThUQH7LKwPgjMunYfLiCfUCySkM8")
+            .expect("invalid pubkey");
+
+        assert!(accounts_activity_payloads(&block, &HashSet::new()).is_empty());
+
+        // Both transactions of the block are issued by the same account
+        let payloads = accounts_activity_payloads(&block, &[issuer].iter().copied().collect());
+        assert_eq!(2, payloads.len());
+        assert_eq!(json!(true), payloads[0].body["sent"]);
+        assert_eq!(json!(0), payloads[0].body["receivedAmount"]);
+
+        let payloads = accounts_activity_payloads(&block, &[recipient].iter().copied().collect());
+        assert_eq!(1, payloads.len());
+        assert_eq!(json!(false), payloads[0].body["sent"]);
+        assert_eq!(json!(1002), payloads[0].body["receivedAmount"]);
+        assert_eq!(json!(recipient.to_string()), payloads[0].body["account"]);
+    }
+}
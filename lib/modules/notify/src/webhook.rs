@@ -0,0 +1,112 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Signature and HTTP delivery of the webhooks.
+
+use crate::constants;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+use url::Url;
+
+/// Sign a webhook body with HMAC-SHA256
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_varkey(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.input(body);
+    format!("sha256={}", hex::encode(mac.result().code()))
+}
+
+/// POST a webhook body to an url, returns the HTTP status code of the response
+pub fn post(url: &Url, event: &str, signature: &str, body: &[u8]) -> io::Result<u16> {
+    let host = url
+        .host_str()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "url without host"))?;
+    let port = url.port_or_known_default().unwrap_or(80);
+    let timeout = Duration::from_secs(*constants::DELIVERY_TIMEOUT_IN_SECS);
+
+    let addr = (host, port)
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "unresolvable host"))?;
+    let mut stream = TcpStream::connect_timeout(&addr, timeout)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+
+    let path = match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_owned(),
+    };
+    write!(
+        stream,
+        "POST {path} HTTP/1.1\r\nHost: {host}:{port}\r\nUser-Agent: {user_agent}\r\n\
+         Content-Type: application/json\r\nContent-Length: {length}\r\n\
+         {event_header}: {event}\r\n{signature_header}: {signature}\r\n\
+         Connection: close\r\n\r\n",
+        path = path,
+        host = host,
+        port = port,
+        user_agent = constants::USER_AGENT,
+        length = body.len(),
+        event_header = constants::EVENT_HEADER,
+        event = event,
+        signature_header = constants::SIGNATURE_HEADER,
+        signature = signature,
+    )?;
+    stream.write_all(body)?;
+    stream.flush()?;
+
+    let mut status_line = String::new();
+    BufReader::new(stream).read_line(&mut status_line)?;
+    parse_status_line(&status_line)
+}
+
+fn parse_status_line(status_line: &str) -> io::Result<u16> {
+    let mut parts = status_line.split_whitespace();
+    match (parts.next(), parts.next().map(str::parse)) {
+        (Some(version), Some(Ok(status))) if version.starts_with("HTTP/") => Ok(status),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid HTTP status line: '{}'", status_line.trim_end()),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign() {
+        // RFC 4231, test case 2
+        assert_eq!(
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+            sign("Jefe", b"what do ya want for nothing?")
+        );
+    }
+
+    #[test]
+    fn test_parse_status_line() {
+        assert_eq!(
+            200,
+            parse_status_line("HTTP/1.1 200 OK\r\n").expect("valid")
+        );
+        assert_eq!(204, parse_status_line("HTTP/1.0 204").expect("valid"));
+        assert!(parse_status_line("").is_err());
+        assert!(parse_status_line("SSH-2.0-OpenSSH").is_err());
+    }
+}
@@ -38,6 +38,7 @@ extern crate structopt;
 
 use dubp_currency_params::CurrencyName;
 use durs_common_tools::fatal_error;
+use durs_common_tools::fns::thread::spawn_child;
 use durs_common_tools::traits::merge::Merge;
 use durs_conf::DuRsConf;
 use durs_message::events::*;
@@ -46,7 +47,6 @@ use durs_module::*;
 use durs_network::events::NetworkEvent;
 use std::ops::Deref;
 use std::sync::mpsc;
-use std::thread;
 use std::time::{Duration, SystemTime};
 
 /// Name of your module
@@ -196,7 +196,7 @@ impl DursModule<DuRsConf, DursMsg> for SkeletonModule {
         // Launch a proxy thread that transform DursMsgContent() to SkeleonMsg::DursMsgContent(DursMsgContent())
         let router_sender_clone = router_sender.clone();
         let skeleton_sender_clone = skeleton_sender;
-        thread::spawn(move || {
+        spawn_child("proxy", move || {
             // Send skeleton module registration to router thread
             router_sender_clone
                 .send(RouterThreadMessage::ModuleRegistration {
@@ -278,7 +278,7 @@ impl DursModule<DuRsConf, DursMsg> for SkeletonModule {
                                         BlockchainEvent::StackUpValidBlock(ref _block) => {
                                             // Do something when the node has stacked a new block at its local blockchain
                                         }
-                                        BlockchainEvent::RevertBlocks(ref _blocks) => {
+                                        BlockchainEvent::RevertBlocks { .. } => {
                                             // Do something when the node has destacked blocks from its local blockchain (roll back)
                                         }
                                         _ => {} // Do nothing for events that don't concern your module.
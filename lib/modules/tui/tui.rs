@@ -32,6 +32,7 @@
 
 use dubp_currency_params::CurrencyName;
 use durs_common_tools::fatal_error;
+use durs_common_tools::fns::thread::spawn_child;
 use durs_common_tools::traits::merge::Merge;
 use durs_conf::DuRsConf;
 use durs_message::events::*;
@@ -113,6 +114,24 @@ pub struct TuiModuleDatas {
     pub connections_status: HashMap<NodeFullId, Connection>,
     /// Number of connections in `Established` status
     pub established_conns_count: usize,
+    /// Recent history of the key metrics of the node
+    pub metrics_history: metrics_history::MetricsHistory,
+}
+
+/// Characters of the sparklines, from the lowest to the highest value
+static SPARKLINE_CHARS: &[char] = &['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Duration of the metrics history displayed (in seconds)
+static DISPLAYED_HISTORY_IN_SECS: &u64 = &86_400;
+
+/// Draw the last `width` values in a line of characters of height proportional to the value
+fn sparkline(values: &[u64], width: usize) -> String {
+    let values = &values[values.len().saturating_sub(width)..];
+    let max = values.iter().copied().max().unwrap_or(0).max(1);
+    values
+        .iter()
+        .map(|value| SPARKLINE_CHARS[(value * (SPARKLINE_CHARS.len() as u64 - 1) / max) as usize])
+        .collect()
 }
 
 impl TuiModuleDatas {
@@ -155,7 +174,7 @@ impl TuiModuleDatas {
         // Prepare HEADs screen
         let mut heads = heads_cache.values().collect::<Vec<&NetworkHead>>();
         heads.sort_unstable_by(|a, b| b.cmp(a));
-        let heads_window_size = h as isize - 8 - out_established_conns.len() as isize;
+        let heads_window_size = h as isize - 9 - out_established_conns.len() as isize;
         let heads_index_max = if heads_window_size > 0 && heads.len() > heads_window_size as usize {
             heads.len() - heads_window_size as usize
         } else {
@@ -233,6 +252,25 @@ impl TuiModuleDatas {
             out_disconnected_conns_count,
         )?;
 
+        // Draw history of established connections
+        line += 1;
+        let since = durs_common_tools::fns::time::current_timestamp()
+            .saturating_sub(*DISPLAYED_HISTORY_IN_SECS);
+        let history: Vec<u64> = self
+            .metrics_history
+            .read("established_connections", since)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(_, value)| value)
+            .collect();
+        write!(
+            stdout,
+            "{}{}established connections (24h) : {}",
+            cursor::Goto(2, line),
+            color::Fg(color::Rgb(128, 128, 128)),
+            sparkline(&history, (w as usize).saturating_sub(36)),
+        )?;
+
         // Draw separated line
         line += 1;
         let mut separated_line = String::with_capacity(w as usize);
@@ -389,7 +427,7 @@ impl DursModule<DuRsConf, DursMsg> for TuiModule {
         Ok((TuiConf {}, None))
     }
     fn start(
-        _soft_meta_datas: &SoftwareMetaDatas<DuRsConf>,
+        soft_meta_datas: &SoftwareMetaDatas<DuRsConf>,
         _keys: RequiredKeysContent,
         _conf: Self::ModuleConf,
         router_sender: Sender<RouterThreadMessage<DursMsg>>,
@@ -403,6 +441,9 @@ impl DursModule<DuRsConf, DursMsg> for TuiModule {
             heads_index: 0,
             connections_status: HashMap::new(),
             established_conns_count: 0,
+            metrics_history: metrics_history::MetricsHistory::new(&durs_conf::get_datas_path(
+                soft_meta_datas.profile_path.clone(),
+            )),
         };
 
         // Create tui main thread channel
@@ -413,7 +454,7 @@ impl DursModule<DuRsConf, DursMsg> for TuiModule {
 
         // Launch a proxy thread that transform DursMsg() to TuiMess::DursMsg(DursMsg())
         let tui_sender_clone = tui_sender.clone();
-        thread::spawn(move || {
+        spawn_child("proxy", move || {
             // Send proxy sender to main
             router_sender
                 .send(RouterThreadMessage::ModuleRegistration {
@@ -476,7 +517,7 @@ impl DursModule<DuRsConf, DursMsg> for TuiModule {
         ));
 
         // Launch stdin thread
-        let _stdin_thread = thread::spawn(move || {
+        let _stdin_thread = spawn_child("stdin", move || {
             // Get the standard input stream.
             let stdin = std::io::stdin();
             // Get stdin events
@@ -514,7 +555,7 @@ impl DursModule<DuRsConf, DursMsg> for TuiModule {
                         } => match *event_content {
                             DursEvent::BlockchainEvent(ref dal_event) => match *dal_event.deref() {
                                 BlockchainEvent::StackUpValidBlock(ref _block) => {}
-                                BlockchainEvent::RevertBlocks(ref _blocks) => {}
+                                BlockchainEvent::RevertBlocks { .. } => {}
                                 _ => {}
                             },
                             DursEvent::NetworkEvent(ref network_event_box) => {
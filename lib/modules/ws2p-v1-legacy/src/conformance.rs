@@ -0,0 +1,193 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Sub-module scoring the protocol conformance of peers and weighting the HEAD consensus.
+
+use crate::constants::*;
+use dubp_common_doc::Blockstamp;
+use durs_network::NetworkConsensusError;
+use durs_network_documents::network_head::NetworkHead;
+use durs_network_documents::NodeFullId;
+use std::collections::HashMap;
+
+/// Maximum weight of a peer (perfectly conform peer)
+pub static MAX_PEER_WEIGHT: &u64 = &1_000;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+/// Protocol conformance counters of a peer
+pub struct ConformanceScore {
+    /// Number of HEADs received with a valid signature
+    pub valid_sigs: u32,
+    /// Number of HEADs received with an invalid signature
+    pub invalid_sigs: u32,
+    /// Number of requests answered in time
+    pub answered_requests: u32,
+    /// Number of requests that timed out
+    pub timeout_requests: u32,
+    /// Number of flood violations
+    pub flood_violations: u32,
+}
+
+impl ConformanceScore {
+    /// Weight of the peer, between 1 and `MAX_PEER_WEIGHT`
+    pub fn weight(&self) -> u64 {
+        let good = 1 + u64::from(self.valid_sigs) + u64::from(self.answered_requests);
+        let bad = u64::from(self.invalid_sigs) * *WS2P_INVALID_SIG_PENALTY
+            + u64::from(self.timeout_requests) * *WS2P_REQUEST_TIMEOUT_PENALTY
+            + u64::from(self.flood_violations) * *WS2P_FLOOD_PENALTY;
+        std::cmp::max(1, *MAX_PEER_WEIGHT * good / (good + bad))
+    }
+}
+
+/// Compute the network consensus from the HEADs, each HEAD being weighted by the conformance score of its issuer
+pub fn weighted_consensus(
+    heads_cache: &HashMap<NodeFullId, NetworkHead>,
+    scores: &HashMap<NodeFullId, ConformanceScore>,
+) -> Result<Blockstamp, NetworkConsensusError> {
+    if heads_cache.len() < *WS2P_CONSENSUS_MIN_HEADS {
+        return Err(NetworkConsensusError::InsufficientData(heads_cache.len()));
+    }
+
+    let mut total_weight = 0;
+    let mut weights_by_blockstamp: HashMap<Blockstamp, u64> = HashMap::new();
+    for (node_full_id, head) in heads_cache {
+        let weight = scores
+            .get(node_full_id)
+            .map(ConformanceScore::weight)
+            .unwrap_or(*MAX_PEER_WEIGHT);
+        total_weight += weight;
+        *weights_by_blockstamp.entry(head.blockstamp()).or_insert(0) += weight;
+    }
+
+    let (best_blockstamp, best_weight) = weights_by_blockstamp
+        .into_iter()
+        .max_by(|(b1, w1), (b2, w2)| w1.cmp(w2).then(b1.id.cmp(&b2.id)))
+        .ok_or(NetworkConsensusError::InsufficientData(0))?;
+
+    if best_weight * 100 >= total_weight * *WS2P_CONSENSUS_MIN_SHARE_PERCENT {
+        Ok(best_blockstamp)
+    } else {
+        Err(NetworkConsensusError::Fork())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::heads::generate_my_head;
+    use dubp_common_doc::{BlockHash, BlockNumber};
+    use dup_crypto::hashs::Hash;
+    use dup_crypto::keys::*;
+    use durs_network_documents::NodeId;
+
+    fn head(seed: u8, blockstamp: Blockstamp) -> (NodeFullId, NetworkHead) {
+        let keypair = ed25519::KeyPairFromSeed32Generator::generate(Seed32::new([seed; 32]));
+        let signator =
+            SignatorEnum::Ed25519(keypair.generate_signator().expect("fail to gen signator"));
+        let head = generate_my_head(
+            &signator,
+            NodeId(0),
+            "dunitrust",
+            "0.3.0",
+            &blockstamp,
+            None,
+        );
+        (head.node_full_id(), head)
+    }
+
+    fn blockstamp(id: u32, hash_byte: u8) -> Blockstamp {
+        Blockstamp {
+            id: BlockNumber(id),
+            hash: BlockHash(Hash([hash_byte; 32])),
+        }
+    }
+
+    #[test]
+    fn conformance_score_weight() {
+        assert_eq!(*MAX_PEER_WEIGHT, ConformanceScore::default().weight());
+
+        let honest = ConformanceScore {
+            valid_sigs: 50,
+            answered_requests: 10,
+            timeout_requests: 1,
+            ..ConformanceScore::default()
+        };
+        let flooder = ConformanceScore {
+            valid_sigs: 50,
+            flood_violations: 10,
+            ..ConformanceScore::default()
+        };
+        assert!(honest.weight() > flooder.weight());
+        assert!(flooder.weight() >= 1);
+    }
+
+    #[test]
+    fn weighted_consensus_ignore_misbehaving_peers() {
+        let main_blockstamp = blockstamp(42, 1);
+        let fork_blockstamp = blockstamp(42, 2);
+
+        let mut heads_cache = HashMap::new();
+        let mut scores = HashMap::new();
+        for seed in 0..2 {
+            let (node_full_id, head) = head(seed, main_blockstamp);
+            heads_cache.insert(node_full_id, head);
+        }
+        for seed in 2..5 {
+            let (node_full_id, head) = head(seed, fork_blockstamp);
+            heads_cache.insert(node_full_id, head);
+            scores.insert(
+                node_full_id,
+                ConformanceScore {
+                    invalid_sigs: 20,
+                    flood_violations: 5,
+                    ..ConformanceScore::default()
+                },
+            );
+        }
+
+        // Without scores, the misbehaving peers are the majority
+        assert_eq!(
+            Ok(fork_blockstamp),
+            weighted_consensus(&heads_cache, &HashMap::new())
+        );
+        // With scores, they can't skew the consensus anymore
+        assert_eq!(
+            Ok(main_blockstamp),
+            weighted_consensus(&heads_cache, &scores)
+        );
+    }
+
+    #[test]
+    fn weighted_consensus_errors() {
+        let mut heads_cache = HashMap::new();
+        for seed in 0..2 {
+            let (node_full_id, head) = head(seed, blockstamp(42, seed));
+            heads_cache.insert(node_full_id, head);
+        }
+        assert_eq!(
+            Err(NetworkConsensusError::InsufficientData(2)),
+            weighted_consensus(&heads_cache, &HashMap::new())
+        );
+
+        for seed in 2..4 {
+            let (node_full_id, head) = head(seed, blockstamp(42, seed));
+            heads_cache.insert(node_full_id, head);
+        }
+        assert_eq!(
+            Err(NetworkConsensusError::Fork()),
+            weighted_consensus(&heads_cache, &HashMap::new())
+        );
+    }
+}
@@ -0,0 +1,80 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Sub-module counting connection metrics (handshake failures, response latency of peers).
+
+use durs_network_documents::NodeFullId;
+use std::collections::HashMap;
+use std::time::Duration;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+/// Response times of a peer
+pub struct ResponseTimes {
+    /// Number of responses received
+    pub count: u64,
+    /// Sum of the response times (in milliseconds)
+    pub total_ms: u64,
+}
+
+impl ResponseTimes {
+    /// Average response time (in milliseconds)
+    pub fn average_ms(&self) -> Option<u64> {
+        self.total_ms.checked_div(self.count)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+/// Connection counters of the WS2Pv1 module
+pub struct WS2Pv1Metrics {
+    /// Number of failed handshakes
+    pub handshake_failures: u64,
+    /// Response times of each peer
+    pub response_times: HashMap<NodeFullId, ResponseTimes>,
+}
+
+impl WS2Pv1Metrics {
+    /// Record the response time of a peer
+    pub fn record_response_time(&mut self, node_full_id: NodeFullId, response_time: Duration) {
+        let response_times = self.response_times.entry(node_full_id).or_default();
+        response_times.count += 1;
+        response_times.total_ms += response_time.as_millis() as u64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dup_crypto::keys::PubKey;
+    use durs_network_documents::NodeId;
+
+    #[test]
+    fn average_response_time() {
+        let node_full_id = NodeFullId(NodeId(1), PubKey::default());
+        let mut metrics = WS2Pv1Metrics::default();
+        assert_eq!(
+            None,
+            metrics
+                .response_times
+                .get(&node_full_id)
+                .and_then(ResponseTimes::average_ms)
+        );
+        metrics.record_response_time(node_full_id, Duration::from_millis(100));
+        metrics.record_response_time(node_full_id, Duration::from_millis(300));
+        assert_eq!(
+            Some(200),
+            metrics.response_times[&node_full_id].average_ms()
+        );
+    }
+}
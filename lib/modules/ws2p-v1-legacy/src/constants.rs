@@ -33,12 +33,51 @@ pub static WS2P_OUTCOMING_INTERVAL: &u64 = &300;
 /// Default outgoing connection quota
 pub static WS2P_DEFAULT_OUTCOMING_QUOTA: &usize = &10;
 
+/// Default incoming connection quota
+pub static WS2P_DEFAULT_INCOMING_QUOTA: &usize = &20;
+
+/// Default host on which to listen for incoming connections
+pub static WS2P_DEFAULT_HOST: &str = "0.0.0.0";
+
+/// Default maximum step of the HEADs forwarded to other connections
+pub static WS2P_DEFAULT_HEADS_MAX_STEP: &u32 = &3;
+
+/// Default maximum number of HEAD messages forwarded to a connection per minute
+pub static WS2P_DEFAULT_HEADS_FORWARDS_PER_MIN: &u32 = &30;
+
+/// Default maximum number of outgoing connections handshakes in progress at the same time
+pub static WS2P_DEFAULT_MAX_PARALLEL_HANDSHAKES: &usize = &10;
+
+/// Default interval between two keep-alive pings (in seconds)
+pub static WS2P_DEFAULT_KEEP_ALIVE_INTERVAL: &u64 = &30;
+
+/// Default number of consecutive missed pongs from which a connection is stale
+pub static WS2P_DEFAULT_KEEP_ALIVE_MAX_MISSED_PONGS: &u32 = &3;
+
+/// Default interval between two checks of the public IP (in seconds)
+pub static WS2P_DEFAULT_PUBLIC_IP_CHECK_INTERVAL: &u64 = &600;
+
+/// Interval between two re-emissions of the self peer card (in seconds)
+pub static WS2P_SELF_PEER_REEMISSION_INTERVAL_IN_SECS: &u64 = &3_600;
+
+/// Lifetime requested for the port mapping of the WS2P server (in seconds)
+pub static WS2P_PORT_MAPPING_LIFETIME_IN_SECS: &u32 = &3_600;
+
+/// Interval between two attempts to map the port of the WS2P server after a failure (in seconds)
+pub static WS2P_PORT_MAPPING_RETRY_INTERVAL_IN_SECS: &u64 = &600;
+
 /// Maximum duration of a connection negotiation
 pub static WS2P_NEGOTIATION_TIMEOUT: &u64 = &15;
 
 /// Maximum waiting time for a response to a request
 pub static WS2P_V1_REQUESTS_TIMEOUT_IN_SECS: &u64 = &30;
 
+/// Number of times a timed out request is re-issued to another peer
+pub static WS2P_V1_REQUESTS_MAX_RETRIES: &usize = &2;
+
+/// Minimal interval between two logs of the same repetitive warning (in seconds)
+pub static WS2P_REPEATED_LOG_INTERVAL_IN_SECS: &u64 = &60;
+
 /// Maximum duration of inactivity of a connection (the connection will be closed after this delay)
 pub static WS2P_EXPIRE_TIMEOUT: &u64 = &120;
 
@@ -51,8 +90,57 @@ pub static WS2P_SPAM_LIMIT: &usize = &6;
 /// Rest time in a situation of proven spam
 pub static WS2P_SPAM_SLEEP_TIME_IN_SEC: &u64 = &100;
 
+/// Weight penalty of a HEAD received with an invalid signature
+pub static WS2P_INVALID_SIG_PENALTY: &u64 = &10;
+
+/// Weight penalty of a request that timed out
+pub static WS2P_REQUEST_TIMEOUT_PENALTY: &u64 = &2;
+
+/// Weight penalty of a flood violation
+pub static WS2P_FLOOD_PENALTY: &u64 = &20;
+
+/// Minimum number of HEADs needed to determine the network consensus
+pub static WS2P_CONSENSUS_MIN_HEADS: &usize = &3;
+
+/// Minimum share of the total weight (in percent) that the consensus blockstamp must reach
+pub static WS2P_CONSENSUS_MIN_SHARE_PERCENT: &u64 = &50;
+
+/// Number of blocks requested at once during synchronization
+pub static WS2P_SYNC_CHUNK_SIZE: &u32 = &250;
+
+/// Maximum number of blocks sent in response to a getBlocks request.
+/// The requester asks for the following blocks from the last one received.
+pub static WS2P_V1_MAX_BLOCKS_PER_RESPONSE: &u32 = &500;
+
+/// Time to live of a cached response to a blocks request
+pub static WS2P_V1_RESPONSE_CACHE_TTL_IN_SECS: &u64 = &10;
+
+/// Maximum number of cached responses to blocks requests
+pub static WS2P_V1_RESPONSE_CACHE_MAX_ENTRIES: &usize = &32;
+
 /// Duration between 2 endpoints saving
 pub static DURATION_BETWEEN_2_ENDPOINTS_SAVING: &u64 = &180;
 
 /// Duration between 2 requests from the pool of the wot data
 pub static PENDING_IDENTITIES_REQUEST_INTERVAL: &u64 = &40;
+
+/// Minimum number of certifications of the pending identities requested to the other nodes
+pub static PENDING_IDENTITIES_MIN_CERT: &usize = &5;
+
+/// Maximum score given by the uptime (share of established outgoing connections)
+pub static WS2P_SCORE_UPTIME_WEIGHT: &u64 = &400;
+
+/// Maximum score given by the freshness of the last HEAD received
+pub static WS2P_SCORE_HEAD_FRESHNESS_WEIGHT: &u64 = &300;
+
+/// Maximum score given by the response latency
+pub static WS2P_SCORE_LATENCY_WEIGHT: &u64 = &300;
+
+/// Score bonus of endpoints whose issuer is a prefered pubkey
+pub static WS2P_SCORE_PREFERED_BONUS: &u64 = &1_000;
+
+/// Age (in seconds) from which a HEAD no longer gives any freshness score
+pub static WS2P_SCORE_HEAD_MAX_AGE_IN_SECS: &u64 = &3_600;
+
+/// Latency (in milliseconds) from which a node no longer gives any latency score
+pub static WS2P_SCORE_MAX_LATENCY_IN_MS: &u64 = &10_000;
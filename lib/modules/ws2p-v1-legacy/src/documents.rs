@@ -0,0 +1,39 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Broadcast of documents generated or received by the local node.
+
+use crate::serializers::document::document_message;
+use crate::*;
+use dubp_block_doc::DocumentDUBP;
+use dubp_common_doc::traits::ToStringObject;
+
+/// Push document to all established connections
+pub fn send_document_to_all(ws2p_module: &WS2Pv1Module, document: &DocumentDUBP) {
+    let message = if let Some(message) = document_message(document.to_string_object()) {
+        message.to_string()
+    } else {
+        debug!("WS2P: this document type is not broadcast by WS2Pv1.");
+        return;
+    };
+    for (node_full_id, websocket) in ws2p_module.connections.websockets() {
+        if !ws2p_module.connections.is_established(node_full_id) {
+            continue;
+        }
+        if let Err(e) = websocket.0.send(Message::text(message.clone())) {
+            debug!("WS2P: fail to send document to {}: {}", node_full_id, e);
+        }
+    }
+}
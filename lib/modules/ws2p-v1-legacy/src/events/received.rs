@@ -17,25 +17,35 @@
 
 use crate::serializers::IntoWS2Pv1Json;
 use crate::*;
+use dubp_block_doc::DocumentDUBP;
 use dubp_common_doc::traits::Document;
-use durs_message::events::DursEvent;
+use durs_message::events::{DursEvent, MemPoolEvent};
 use durs_module::*;
 use std::ops::Deref;
 
 pub fn receive_event(
     ws2p_module: &mut WS2Pv1Module,
-    _event_type: ModuleEvent,
+    event_type: ModuleEvent,
     event_content: &DursEvent,
 ) {
-    if let DursEvent::BlockchainEvent(ref bc_event) = *event_content {
-        match *bc_event.deref() {
+    match *event_content {
+        DursEvent::BlockchainEvent(ref bc_event) => match *bc_event.deref() {
             BlockchainEvent::StackUpValidBlock(ref block) => {
+                ws2p_module.responses_cache.invalidate();
+                if event_type == ModuleEvent::NewValidBlockFromSelf {
+                    // Block issued by the local node: push it to all peers
+                    documents::send_document_to_all(
+                        ws2p_module,
+                        &DocumentDUBP::Block(block.clone()),
+                    );
+                    return;
+                }
                 ws2p_module.current_blockstamp = block.deref().blockstamp();
                 debug!(
                     "WS2Pv1Module : current_blockstamp = {}",
                     ws2p_module.current_blockstamp
                 );
-                ws2p_module.my_head = Some(heads::generate_my_head(
+                ws2p_module.heads.set_my_head(heads::generate_my_head(
                     &ws2p_module.my_signator,
                     ws2p_module.node_id,
                     ws2p_module.soft_name,
@@ -43,16 +53,17 @@ pub fn receive_event(
                     &ws2p_module.current_blockstamp,
                     None,
                 ));
+                let my_head = unwrap!(ws2p_module.heads.my_head()).clone();
                 super::sent::send_network_event(
                     ws2p_module,
-                    NetworkEvent::ReceiveHeads(vec![unwrap!(ws2p_module.my_head.clone())]),
+                    NetworkEvent::ReceiveHeads(vec![my_head.clone()]),
                 );
                 // Send my head to all connections
-                let my_json_head = unwrap!(ws2p_module.my_head.clone()).into_ws2p_v1_json();
+                let my_json_head = my_head.into_ws2p_v1_json();
                 trace!("Send my HEAD: {:#?}", my_json_head);
                 let _results: Result<(), ws::Error> = ws2p_module
-                    .websockets
-                    .iter_mut()
+                    .connections
+                    .websockets()
                     .map(|ws| {
                         (ws.1).0.send(Message::text(
                             json!({
@@ -66,8 +77,16 @@ pub fn receive_event(
                     })
                     .collect();
             }
-            BlockchainEvent::RevertBlocks(ref _blocks) => {}
+            BlockchainEvent::RevertBlocks { .. } => ws2p_module.responses_cache.invalidate(),
             _ => {}
+        },
+        DursEvent::MemPoolEvent(MemPoolEvent::StoreNewDocInPool(ref user_doc)) => {
+            // New transaction or wot document in the local mempool
+            documents::send_document_to_all(
+                ws2p_module,
+                &DocumentDUBP::UserDocument(user_doc.deref().clone()),
+            );
         }
+        _ => {}
     }
 }
@@ -49,6 +49,7 @@ pub fn send_network_event(ws2p_module: &mut WS2Pv1Module, event: NetworkEvent) {
         NetworkEvent::ReceiveHeads(_) => ModuleEvent::NewValidHeadFromNetwork,
         NetworkEvent::ReceivePeers(_) => ModuleEvent::NewValidPeerFromNodeNetwork,
         NetworkEvent::SyncEvent(_) => ModuleEvent::SyncEvent,
+        NetworkEvent::ReceiveOperatorMessage(..) => ModuleEvent::NewOperatorMessage,
     };
     ws2p_module
         .router_sender
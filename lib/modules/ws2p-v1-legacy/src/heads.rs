@@ -13,9 +13,11 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+use crate::serializers::IntoWS2Pv1Json;
 use crate::*;
 use dubp_common_doc::Blockstamp;
 use durs_network_documents::network_head_v2::*;
+use std::time::Instant;
 
 pub fn generate_my_head(
     network_signator: &SignatorEnum,
@@ -58,3 +60,206 @@ pub fn generate_my_head(
         uid: my_uid,
     }))
 }
+
+/// Limit the number of HEAD messages forwarded to a connection
+#[derive(Clone, Copy, Debug)]
+pub struct HeadsForwardLimiter {
+    window_begin: Instant,
+    count: u32,
+}
+
+impl Default for HeadsForwardLimiter {
+    fn default() -> Self {
+        HeadsForwardLimiter {
+            window_begin: Instant::now(),
+            count: 0,
+        }
+    }
+}
+
+impl HeadsForwardLimiter {
+    /// Returns `true` if a HEAD message can be forwarded now
+    pub fn allow(&mut self, now: Instant, max_per_min: u32) -> bool {
+        if now.duration_since(self.window_begin) >= Duration::from_secs(60) {
+            self.window_begin = now;
+            self.count = 0;
+        }
+        if self.count < max_per_min {
+            self.count += 1;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// HEADs known by the module
+#[derive(Debug, Default)]
+pub struct HeadsState {
+    /// HEAD of the local node
+    my_head: Option<NetworkHead>,
+    /// Last HEAD of each remote node
+    heads_cache: HashMap<NodeFullId, NetworkHead>,
+    /// Limiters of the HEADs forwarded to each connection
+    forward_limiters: HashMap<NodeFullId, HeadsForwardLimiter>,
+    /// Uid of the members whose HEAD was received
+    uids_cache: HashMap<PubKey, String>,
+}
+
+impl HeadsState {
+    /// HEAD of the local node
+    pub fn my_head(&self) -> Option<&NetworkHead> {
+        self.my_head.as_ref()
+    }
+    /// Replace the HEAD of the local node
+    pub fn set_my_head(&mut self, my_head: NetworkHead) {
+        self.my_head = Some(my_head);
+    }
+    /// Last HEAD of each remote node
+    pub fn heads(&self) -> &HashMap<NodeFullId, NetworkHead> {
+        &self.heads_cache
+    }
+    /// Apply a HEAD received from the network, returns `true` if it is new.
+    /// HEADs of the local node are ignored.
+    pub fn apply(&mut self, head: &NetworkHead) -> bool {
+        if let Some(ref my_head) = self.my_head {
+            if head.node_full_id() == my_head.node_full_id() {
+                return false;
+            }
+        }
+        head.apply(&mut self.heads_cache)
+    }
+    /// Uid of a member
+    pub fn uid(&self, pubkey: &PubKey) -> Option<&String> {
+        self.uids_cache.get(pubkey)
+    }
+    /// Update the uids of the members and of their HEADs
+    pub fn update_uids(&mut self, uids: &HashMap<PubKey, Option<String>>) {
+        for head in self.heads_cache.values_mut() {
+            if let Some(uid_option) = uids.get(&head.pubkey()) {
+                if let Some(ref uid) = *uid_option {
+                    head.set_uid(uid);
+                    self.uids_cache.insert(head.pubkey(), uid.to_string());
+                } else {
+                    self.uids_cache.remove(&head.pubkey());
+                }
+            }
+        }
+    }
+    /// Returns `true` if a HEAD message can be forwarded now to this connection
+    pub fn allow_forward(
+        &mut self,
+        node_full_id: NodeFullId,
+        now: Instant,
+        max_per_min: u32,
+    ) -> bool {
+        self.forward_limiters
+            .entry(node_full_id)
+            .or_default()
+            .allow(now, max_per_min)
+    }
+    /// Forget the forward limiter of a closed connection
+    pub fn remove_connection(&mut self, node_full_id: &NodeFullId) {
+        self.forward_limiters.remove(node_full_id);
+    }
+}
+
+/// Forward new valid HEADs to all other established connections (except the sender)
+pub fn forward_heads(ws2p_module: &mut WS2Pv1Module, from: NodeFullId, heads: &[NetworkHead]) {
+    if !ws2p_module.conf.heads_gossip {
+        return;
+    }
+    let json_heads: Vec<serde_json::Value> = heads
+        .iter()
+        .filter(|head| head.step() < ws2p_module.conf.heads_max_step)
+        .cloned()
+        .map(IntoWS2Pv1Json::into_ws2p_v1_json)
+        .collect();
+    if json_heads.is_empty() {
+        return;
+    }
+    let message = json!({
+        "name": "HEAD",
+        "body": {
+            "heads": json_heads
+        }
+    })
+    .to_string();
+
+    let now = Instant::now();
+    for (node_full_id, websocket) in ws2p_module.connections.websockets() {
+        if *node_full_id == from || !ws2p_module.connections.is_established(node_full_id) {
+            continue;
+        }
+        if !ws2p_module.heads.allow_forward(
+            *node_full_id,
+            now,
+            ws2p_module.conf.heads_forwards_per_min,
+        ) {
+            trace!(
+                "WS2P: HEADs forward rate limit reached for {}",
+                node_full_id
+            );
+            continue;
+        }
+        if let Err(e) = websocket.0.send(Message::text(message.clone())) {
+            debug!("WS2P: fail to forward HEADs to {}: {}", node_full_id, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use maplit::hashmap;
+
+    #[test]
+    fn heads_forward_limiter() {
+        let begin = Instant::now();
+        let mut limiter = HeadsForwardLimiter {
+            window_begin: begin,
+            count: 0,
+        };
+        assert!(limiter.allow(begin, 2));
+        assert!(limiter.allow(begin + Duration::from_secs(10), 2));
+        assert!(!limiter.allow(begin + Duration::from_secs(20), 2));
+        // New window
+        assert!(limiter.allow(begin + Duration::from_secs(60), 2));
+    }
+
+    #[test]
+    fn heads_state() {
+        let signator = |seed: u8| {
+            let keypair = ed25519::KeyPairFromSeed32Generator::generate(Seed32::new([seed; 32]));
+            SignatorEnum::Ed25519(keypair.generate_signator().expect("fail to gen signator"))
+        };
+        let head = |seed: u8, block_number: u32| {
+            generate_my_head(
+                &signator(seed),
+                NodeId(0),
+                "dunitrust",
+                "0.3.0",
+                &Blockstamp {
+                    id: dubp_common_doc::BlockNumber(block_number),
+                    hash: dubp_common_doc::BlockHash(dup_crypto::hashs::Hash([0; 32])),
+                },
+                None,
+            )
+        };
+        let mut heads_state = HeadsState::default();
+        heads_state.set_my_head(head(1, 10));
+
+        // The HEADs of the local node are ignored
+        assert!(!heads_state.apply(&head(1, 11)));
+        assert!(heads_state.apply(&head(2, 10)));
+        assert!(!heads_state.apply(&head(2, 9)));
+        assert!(heads_state.apply(&head(2, 11)));
+        assert_eq!(1, heads_state.heads().len());
+
+        let pubkey = head(2, 0).pubkey();
+        heads_state.update_uids(&hashmap![pubkey => Some("elois".to_owned())]);
+        assert_eq!(Some(&"elois".to_owned()), heads_state.uid(&pubkey));
+        heads_state.update_uids(&hashmap![pubkey => None]);
+        assert_eq!(None, heads_state.uid(&pubkey));
+    }
+}
@@ -0,0 +1,98 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! WS2Pv1 module translations.
+
+use durs_module::i18n::Catalog;
+
+/// French translations of WS2Pv1 subcommands messages
+pub static FR_CATALOG: Catalog = &[
+    // ban/unban
+    ("ban list", "liste des bannis"),
+    ("greylist", "liste grise"),
+    (
+        "Pubkey '{}' successfully added to the {}.",
+        "Clé '{}' ajoutée avec succès à la {}.",
+    ),
+    (
+        "Pubkey '{}' successfully unbanned.",
+        "Clé '{}' débannie avec succès.",
+    ),
+    (
+        "Pubkey '{}' is not banned.",
+        "La clé '{}' n'est pas bannie.",
+    ),
+    (
+        "Fail to write ban list: {}",
+        "Échec de l'écriture de la liste des bannis : {}",
+    ),
+    (
+        "Fail to read ban list: {}",
+        "Échec de la lecture de la liste des bannis : {}",
+    ),
+    // endpoints
+    (
+        "Fail to read endpoints: {}",
+        "Échec de la lecture des points d'accès : {}",
+    ),
+    (
+        "{} endpoints successfully imported.",
+        "{} points d'accès importés avec succès.",
+    ),
+    (
+        "Fail to import endpoints: {}",
+        "Échec de l'import des points d'accès : {}",
+    ),
+    ("{} known endpoints:", "{} points d'accès connus :"),
+    (
+        "{} (state: {}, last check: {})",
+        "{} (état : {}, dernière vérification : {})",
+    ),
+    // prefered
+    (
+        "Pubkey '{}' successfully added to the list of preferred keys.",
+        "Clé '{}' ajoutée avec succès à la liste des clés préférées.",
+    ),
+    (
+        "Pubkey '{}' successfully removed from the list of preferred keys.",
+        "Clé '{}' retirée avec succès de la liste des clés préférées.",
+    ),
+    (
+        "Line n°{} is invalid: {}",
+        "La ligne n°{} est invalide : {}",
+    ),
+    (
+        "Fail to read line n°{}: {}",
+        "Échec de la lecture de la ligne n°{} : {}",
+    ),
+    (
+        "Fail to open file: {}",
+        "Échec de l'ouverture du fichier : {}",
+    ),
+    (
+        "Error: file does not exist!",
+        "Erreur : le fichier n'existe pas !",
+    ),
+    (
+        "All preferred keys removed!",
+        "Toutes les clés préférées ont été retirées !",
+    ),
+    ("{} preferred keys:", "{} clés préférées :"),
+    // status
+    (
+        "No connection metrics available (the node must be running): {}",
+        "Aucune métrique de connexion disponible (le nœud doit être lancé) : {}",
+    ),
+];
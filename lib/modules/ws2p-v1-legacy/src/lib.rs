@@ -37,27 +37,46 @@ extern crate serde_json;
 extern crate structopt;
 
 mod ack_message;
+mod conformance;
 mod connect_message;
+mod connections_metrics;
 pub mod constants;
+mod documents;
 mod events;
 mod heads;
+mod i18n;
 mod ok_message;
+mod port_mapping;
+mod request_tracker;
 mod requests;
+mod response_cache;
 mod responses;
+mod self_peer;
 pub mod serializers;
+pub mod state_machine;
 mod subcommands;
+mod sync;
 pub mod ws2p_db;
 pub mod ws_connections;
 
 use crate::ack_message::WS2PAckMessageV1;
+use crate::conformance::ConformanceScore;
 use crate::connect_message::WS2PConnectMessageV1;
+use crate::connections_metrics::{ResponseTimes, WS2Pv1Metrics};
 use crate::constants::*;
 use crate::ok_message::WS2POkMessageV1;
+use crate::request_tracker::RequestTracker;
 use crate::requests::sent::send_dal_request;
+use crate::response_cache::ResponseCache;
+use crate::state_machine::Ws2pStateMachine;
 use crate::subcommands::WS2PSubCommands;
-use crate::ws2p_db::DbEndpoint;
+use crate::ws2p_db::{BanList, DbEndpoint, DbEndpoints, EndpointStats};
+use crate::ws_connections::handshakes::HandshakesPool;
+use crate::ws_connections::keep_alive::KeepAlive;
 use crate::ws_connections::messages::WS2Pv1Msg;
+use crate::ws_connections::pool::ConnectionsPool;
 use crate::ws_connections::requests::{WS2Pv1ReqBody, WS2Pv1ReqFullId, WS2Pv1ReqId, WS2Pv1Request};
+use crate::ws_connections::server::IncomingConnection;
 use crate::ws_connections::states::WS2PConnectionState;
 use crate::ws_connections::*;
 use dubp_block_doc::BlockDocument;
@@ -65,13 +84,16 @@ use dubp_common_doc::Blockstamp;
 use dubp_currency_params::CurrencyName;
 use dubp_user_docs::documents::UserDocumentDUBP;
 use dup_crypto::keys::*;
-use durs_common_tools::fatal_error;
+use durs_common_tools::fns::thread::spawn_child;
+use durs_common_tools::timer::StdMonotonicClock;
 use durs_common_tools::traits::merge::Merge;
+use durs_common_tools::{fatal_error, log_once_per};
 use durs_conf::DuRsConf;
 use durs_message::events::*;
 use durs_message::requests::*;
 use durs_message::responses::*;
 use durs_message::*;
+use durs_module::i18n::{Catalog, Locale};
 use durs_module::*;
 use durs_network::cli::sync::SyncOpt;
 use durs_network::events::*;
@@ -80,17 +102,19 @@ use durs_network::*;
 use durs_network_documents::network_endpoint::*;
 use durs_network_documents::network_head::*;
 use durs_network_documents::*;
+use durs_ws2p_pool::WS2PPool;
 use failure::Fail;
 use maplit::hashset;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::net::Ipv4Addr;
 use std::ops::Deref;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::mpsc;
 use std::thread;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant};
 use unwrap::unwrap;
 use ws::{CloseCode, Message};
 
@@ -110,22 +134,124 @@ pub fn ssl() -> bool {
 pub struct WS2PUserConf {
     /// Limit of outcoming connections
     pub outcoming_quota: Option<usize>,
+    /// Limit of incoming connections
+    pub incoming_quota: Option<usize>,
+    /// Maximum number of outgoing connections handshakes in progress at the same time
+    pub max_parallel_handshakes: Option<usize>,
+    /// Host on which to listen for incoming connections
+    pub host: Option<String>,
+    /// Port on which to listen for incoming connections (incoming connections are accepted only if this field is set)
+    pub port: Option<u16>,
+    /// Public host declared in the peer card (default to `host`)
+    pub public_host: Option<String>,
+    /// Public port declared in the peer card (default to `port`)
+    pub public_port: Option<u16>,
+    /// Public path declared in the peer card
+    pub public_path: Option<String>,
+    /// SOCKS5 proxy through which to open outgoing connections (`host:port`)
+    pub proxy: Option<String>,
+    /// Never open outgoing connections without the proxy
+    pub only_proxy: Option<bool>,
     /// List of prefered public keys
     pub prefered_pubkeys: Option<HashSet<String>>,
+    /// SHA-256 fingerprints (in hexadecimal) of the TLS certificates expected from prefered public keys
+    pub pinned_certificates: Option<HashMap<String, String>>,
     /// Default WS2P endpoints provides by configuration file
     pub sync_endpoints: Option<Vec<EndpointV1>>,
+    /// Forward the HEADs received to the other connections
+    pub heads_gossip: Option<bool>,
+    /// Maximum step of the HEADs forwarded
+    pub heads_max_step: Option<u32>,
+    /// Maximum number of HEAD messages forwarded to a connection per minute
+    pub heads_forwards_per_min: Option<u32>,
+    /// Interval between two keep-alive pings (in seconds, 0 disables the keep-alive)
+    pub keep_alive_interval: Option<u64>,
+    /// Number of consecutive missed pongs from which a connection is stale
+    pub keep_alive_max_missed_pongs: Option<u32>,
+    /// Shell command printing the public IP of the node (the endpoints declared with another IP are updated)
+    pub public_ip_command: Option<String>,
+    /// Interval between two checks of the public IP (in seconds)
+    pub public_ip_check_interval: Option<u64>,
+    /// Map the port of the WS2P server on the gateway with NAT-PMP or UPnP
+    pub port_mapping: Option<bool>,
+    /// IPv4 of the NAT-PMP gateway (default to the gateway of the default route)
+    pub nat_pmp_gateway: Option<String>,
 }
 
 impl Merge for WS2PUserConf {
     fn merge(self, other: Self) -> Self {
         WS2PUserConf {
             outcoming_quota: self.outcoming_quota.or(other.outcoming_quota),
+            incoming_quota: self.incoming_quota.or(other.incoming_quota),
+            max_parallel_handshakes: self
+                .max_parallel_handshakes
+                .or(other.max_parallel_handshakes),
+            host: self.host.or(other.host),
+            port: self.port.or(other.port),
+            public_host: self.public_host.or(other.public_host),
+            public_port: self.public_port.or(other.public_port),
+            public_path: self.public_path.or(other.public_path),
+            proxy: self.proxy.or(other.proxy),
+            only_proxy: self.only_proxy.or(other.only_proxy),
             prefered_pubkeys: self.prefered_pubkeys.or(other.prefered_pubkeys),
+            pinned_certificates: self.pinned_certificates.or(other.pinned_certificates),
             sync_endpoints: self.sync_endpoints.or(other.sync_endpoints),
+            heads_gossip: self.heads_gossip.or(other.heads_gossip),
+            heads_max_step: self.heads_max_step.or(other.heads_max_step),
+            heads_forwards_per_min: self.heads_forwards_per_min.or(other.heads_forwards_per_min),
+            keep_alive_interval: self.keep_alive_interval.or(other.keep_alive_interval),
+            keep_alive_max_missed_pongs: self
+                .keep_alive_max_missed_pongs
+                .or(other.keep_alive_max_missed_pongs),
+            public_ip_command: self.public_ip_command.or(other.public_ip_command),
+            public_ip_check_interval: self
+                .public_ip_check_interval
+                .or(other.public_ip_check_interval),
+            port_mapping: self.port_mapping.or(other.port_mapping),
+            nat_pmp_gateway: self.nat_pmp_gateway.or(other.nat_pmp_gateway),
         }
     }
 }
 
+#[derive(Debug, Clone, Deserialize)]
+/// WS2P sync endpoint declared in currency defaults
+struct WS2PDefaultSyncEndpoint {
+    /// Endpoint in raw format
+    raw_endpoint: String,
+    /// Public key of the node declaring this endpoint
+    issuer: String,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+/// WS2P defaults declared for a currency
+struct WS2PCurrencyDefaults {
+    /// Default WS2P sync endpoints
+    #[serde(default)]
+    sync_endpoints: Vec<WS2PDefaultSyncEndpoint>,
+}
+
+impl WS2PCurrencyDefaults {
+    fn sync_endpoints(&self) -> Result<Vec<EndpointV1>, ModuleConfError> {
+        self.sync_endpoints
+            .iter()
+            .enumerate()
+            .map(|(i, ep)| {
+                let issuer =
+                    PubKey::from_str(&ep.issuer).map_err(|e| ModuleConfError::InvalidField {
+                        field_name: stringify!(sync_endpoints),
+                        cause: format!("issuer of endpoint n°{} is invalid: {}", i, e),
+                    })?;
+                EndpointV1::parse_from_raw(&ep.raw_endpoint, issuer, 0, 0).map_err(|e| {
+                    ModuleConfError::InvalidField {
+                        field_name: stringify!(sync_endpoints),
+                        cause: format!("endpoint n°{} is invalid: {}", i, e),
+                    }
+                })
+            })
+            .collect()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 /// WS2P Configuration
 pub struct WS2PConf {
@@ -133,10 +259,156 @@ pub struct WS2PConf {
     pub currency: Option<CurrencyName>,
     /// Limit of outcoming connections
     pub outcoming_quota: usize,
+    /// Limit of incoming connections
+    pub incoming_quota: usize,
+    /// Maximum number of outgoing connections handshakes in progress at the same time
+    pub max_parallel_handshakes: usize,
     /// List of prefered public keys
     pub prefered_pubkeys: HashSet<PubKey>,
+    /// SHA-256 fingerprints of the TLS certificates expected from prefered public keys
+    pub pinned_certificates: HashMap<PubKey, [u8; 32]>,
+    /// Incoming connections configuration (None if the node does not accept incoming connections)
+    pub server: Option<WS2PServerConf>,
+    /// SOCKS5 proxy through which to open outgoing connections (`host:port`)
+    pub proxy: Option<String>,
+    /// Never open outgoing connections without the proxy
+    pub only_proxy: bool,
     /// Default WS2P endpoints provides by configuration file
     pub sync_endpoints: Vec<EndpointV1>,
+    /// Forward the HEADs received to the other connections
+    pub heads_gossip: bool,
+    /// Maximum step of the HEADs forwarded
+    pub heads_max_step: u32,
+    /// Maximum number of HEAD messages forwarded to a connection per minute
+    pub heads_forwards_per_min: u32,
+    /// Interval between two keep-alive pings (in seconds, 0 disables the keep-alive)
+    pub keep_alive_interval: u64,
+    /// Number of consecutive missed pongs from which a connection is stale
+    pub keep_alive_max_missed_pongs: u32,
+    /// Shell command printing the public IP of the node (the endpoints declared with another IP are updated)
+    pub public_ip_command: Option<String>,
+    /// Interval between two checks of the public IP (in seconds)
+    pub public_ip_check_interval: u64,
+    /// Map the port of the WS2P server on the gateway with NAT-PMP or UPnP
+    pub port_mapping: bool,
+    /// IPv4 of the NAT-PMP gateway (default to the gateway of the default route)
+    pub nat_pmp_gateway: Option<Ipv4Addr>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Route of an outgoing connection
+pub enum WS2POutgoingRoute {
+    /// Direct connection
+    Direct,
+    /// Connection through the SOCKS5 proxy at this address
+    Proxy(String),
+    /// The endpoint is unreachable with the current configuration
+    Unreachable,
+}
+
+impl WS2PConf {
+    /// Get keep-alive state of a new connection
+    pub fn keep_alive(&self) -> KeepAlive {
+        KeepAlive::new(self.keep_alive_interval, self.keep_alive_max_missed_pongs)
+    }
+    /// Get the route of an outgoing connection to an endpoint
+    ///
+    /// TLS endpoints cannot be reached through the proxy, and .onion endpoints can only be reached
+    /// through the proxy. Endpoints whose certificate is pinned can only be reached with TLS.
+    pub fn outgoing_route(&self, ep: &EndpointV1, ssl: bool) -> WS2POutgoingRoute {
+        let tls = ep.port == 443;
+        if (tls && !ssl) || (!tls && self.pinned_certificates.contains_key(&ep.issuer)) {
+            return WS2POutgoingRoute::Unreachable;
+        }
+        match self.proxy {
+            Some(ref proxy) if !tls => WS2POutgoingRoute::Proxy(proxy.clone()),
+            Some(_) if self.only_proxy => WS2POutgoingRoute::Unreachable,
+            _ if ep.host.ends_with(".onion") => WS2POutgoingRoute::Unreachable,
+            _ => WS2POutgoingRoute::Direct,
+        }
+    }
+}
+
+/// Parse a SHA-256 certificate fingerprint in hexadecimal (bytes may be separated by colons)
+fn parse_cert_fingerprint(fingerprint: &str) -> Option<[u8; 32]> {
+    let hex: String = fingerprint.chars().filter(|c| *c != ':').collect();
+    if hex.len() != 64 || !hex.is_ascii() {
+        return None;
+    }
+    let mut bytes = [0u8; 32];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).ok()?;
+    }
+    Some(bytes)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// WS2P incoming connections configuration
+pub struct WS2PServerConf {
+    /// Host on which to listen for incoming connections
+    pub host: String,
+    /// Port on which to listen for incoming connections
+    pub port: u16,
+    /// Public endpoint declared in the peer card (in raw format)
+    pub public_endpoint: Option<String>,
+}
+
+impl WS2PServerConf {
+    fn from_user_conf(
+        node_id: NodeId,
+        module_user_conf: &WS2PUserConf,
+    ) -> Result<Option<WS2PServerConf>, ModuleConfError> {
+        let port = if let Some(port) = module_user_conf.port {
+            port
+        } else {
+            return Ok(None);
+        };
+        let public_endpoint = if let Some(public_host) = module_user_conf
+            .public_host
+            .as_ref()
+            .or(module_user_conf.host.as_ref())
+        {
+            let raw_endpoint = format!(
+                "{} {} {} {}{}",
+                WS2P_API,
+                node_id,
+                public_host,
+                module_user_conf.public_port.unwrap_or(port),
+                module_user_conf
+                    .public_path
+                    .as_ref()
+                    .map(|path| format!(" {}", path))
+                    .unwrap_or_default(),
+            );
+            if let Err(e) = EndpointV1::parse_from_raw(&raw_endpoint, PubKey::default(), 0, 0) {
+                return Err(ModuleConfError::InvalidField {
+                    field_name: stringify!(public_host),
+                    cause: format!("invalid public endpoint '{}': {}", raw_endpoint, e),
+                });
+            }
+            Some(raw_endpoint)
+        } else {
+            None
+        };
+
+        Ok(Some(WS2PServerConf {
+            host: module_user_conf
+                .host
+                .clone()
+                .unwrap_or_else(|| WS2P_DEFAULT_HOST.to_owned()),
+            port,
+            public_endpoint,
+        }))
+    }
+    /// Get public endpoint of the local node
+    pub fn public_endpoint(&self, issuer: PubKey) -> Option<EndpointV1> {
+        self.public_endpoint.as_ref().map(|raw_endpoint| {
+            unwrap!(
+                EndpointV1::parse_from_raw(raw_endpoint, issuer, 0, 0),
+                "public endpoint is checked when generating conf"
+            )
+        })
+    }
 }
 
 impl Default for WS2PConf {
@@ -144,65 +416,23 @@ impl Default for WS2PConf {
         WS2PConf {
             currency: None,
             outcoming_quota: *WS2P_DEFAULT_OUTCOMING_QUOTA,
+            incoming_quota: *WS2P_DEFAULT_INCOMING_QUOTA,
+            max_parallel_handshakes: *WS2P_DEFAULT_MAX_PARALLEL_HANDSHAKES,
             prefered_pubkeys: HashSet::new(),
-            sync_endpoints: vec![
-                unwrap!(EndpointV1::parse_from_raw(
-                    "WS2P e66254bf 91.121.157.13 20901",
-                    PubKey::Ed25519(unwrap!(ed25519::PublicKey::from_base58(
-                        "8iVdpXqFLCxGyPqgVx5YbFSkmWKkceXveRd2yvBKeARL",
-                    )),),
-                    0,
-                    0,
-                )),
-                unwrap!(EndpointV1::parse_from_raw(
-                    "WS2P c1c39a0a ts.g1.librelois.fr 443 /ws2p",
-                    PubKey::Ed25519(unwrap!(ed25519::PublicKey::from_base58(
-                        "D9D2zaJoWYWveii1JRYLVK3J4Z7ZH3QczoKrnQeiM6mx",
-                    )),),
-                    0,
-                    0,
-                )),
-                unwrap!(EndpointV1::parse_from_raw(
-                    "WS2P fb17fcd4 g1.duniter.fr 443 /ws2p",
-                    PubKey::Ed25519(unwrap!(ed25519::PublicKey::from_base58(
-                        "38MEAZN68Pz1DTvT3tqgxx4yQP6snJCQhPqEFxbDk4aE",
-                    ))),
-                    0,
-                    0,
-                )),
-                unwrap!(EndpointV1::parse_from_raw(
-                    "WS2P 9407e0ac monit.g1.nordstrom.duniter.org 443 /ws2p",
-                    PubKey::Ed25519(unwrap!(ed25519::PublicKey::from_base58(
-                        "74RBUM4VkhZU4PLJcf8ok9snKjXTX6aP52PdGcCM1meA",
-                    ))),
-                    0,
-                    0,
-                )),
-                unwrap!(EndpointV1::parse_from_raw(
-                    "WS2P beb7012c g1.monnaielibreoccitanie.org 443 /ws2p",
-                    PubKey::Ed25519(unwrap!(ed25519::PublicKey::from_base58(
-                        "RD11hyG5HY9MGAp4ui3KoPYWHBMEBCyzSPT81Em4cCL",
-                    ))),
-                    0,
-                    0,
-                )),
-                unwrap!(EndpointV1::parse_from_raw(
-                    "WS2P 90e9b12 duniter.g1.1000i100.fr 443 /ws2p",
-                    PubKey::Ed25519(unwrap!(ed25519::PublicKey::from_base58(
-                        "2sZF6j2PkxBDNAqUde7Dgo5x3crkerZpQ4rBqqJGn8QT",
-                    ))),
-                    0,
-                    0,
-                )),
-                unwrap!(EndpointV1::parse_from_raw(
-                    "WS2P dff60418 duniter.normandie-libre.fr 443 /ws2p",
-                    PubKey::Ed25519(unwrap!(ed25519::PublicKey::from_base58(
-                        "8t6Di3pLxxoTEfjXHjF49pNpjSTXuGEQ6BpkT75CkNb2",
-                    ))),
-                    0,
-                    0,
-                )),
-            ],
+            pinned_certificates: HashMap::new(),
+            server: None,
+            proxy: None,
+            only_proxy: false,
+            sync_endpoints: vec![],
+            heads_gossip: true,
+            heads_max_step: *WS2P_DEFAULT_HEADS_MAX_STEP,
+            heads_forwards_per_min: *WS2P_DEFAULT_HEADS_FORWARDS_PER_MIN,
+            keep_alive_interval: *WS2P_DEFAULT_KEEP_ALIVE_INTERVAL,
+            keep_alive_max_missed_pongs: *WS2P_DEFAULT_KEEP_ALIVE_MAX_MISSED_PONGS,
+            public_ip_command: None,
+            public_ip_check_interval: *WS2P_DEFAULT_PUBLIC_IP_CHECK_INTERVAL,
+            port_mapping: false,
+            nat_pmp_gateway: None,
         }
     }
 }
@@ -215,6 +445,8 @@ pub enum WS2PSignal {
     ConnectionEstablished(NodeFullId),
     Empty,
     Heads(NodeFullId, Vec<NetworkHead>),
+    /// The connection with `NodeFullId` is stale (too many keep-alive pings without pong).
+    KeepAliveTimeout(NodeFullId),
     NegociationTimeout(NodeFullId),
     NoConnection,
     PeerCard(NodeFullId, serde_json::Value, Vec<EndpointV1>),
@@ -235,12 +467,6 @@ pub enum WS2PSignal {
     WSError(NodeFullId),
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
-pub enum NetworkConsensusError {
-    InsufficientData(usize),
-    Fork,
-}
-
 #[derive(Debug)]
 pub enum SendRequestError {
     RequestTypeMustNotBeTransmitted(),
@@ -249,29 +475,35 @@ pub enum SendRequestError {
 
 #[derive(Debug)]
 pub struct WS2Pv1Module {
+    pub ban_list: BanList,
     pub conf: WS2PConf,
-    pub count_dal_requests: u32,
+    pub conformance_scores: HashMap<NodeFullId, ConformanceScore>,
+    pub connections: ConnectionsPool,
     pub current_blockstamp: Blockstamp,
     pub ep_file_path: PathBuf,
-    pub heads_cache: HashMap<NodeFullId, NetworkHead>,
+    pub handshakes: HandshakesPool,
+    pub heads: heads::HeadsState,
     pub key_pair: KeyPairEnum,
     pub main_thread_channel: (
         mpsc::Sender<WS2PThreadSignal>,
         mpsc::Receiver<WS2PThreadSignal>,
     ),
-    pub my_head: Option<NetworkHead>,
+    pub metrics: WS2Pv1Metrics,
+    pub metrics_history: metrics_history::MetricsHistory,
     pub my_signator: SignatorEnum,
-    pub next_receiver: usize,
+    pub pool: WS2PPool,
+    pub network_map_file_path: PathBuf,
+    pub network_metrics_file_path: PathBuf,
+    pub port_mapping_renewal: Option<Instant>,
+    pub requests: RequestTracker,
+    pub responses_cache: ResponseCache,
     pub node_id: NodeId,
-    pub pending_received_requests: HashMap<ModuleReqId, WS2Pv1ReqFullId>,
-    pub requests_awaiting_response: HashMap<WS2Pv1ReqId, WS2Pv1PendingReqInfos>,
     pub router_sender: mpsc::Sender<RouterThreadMessage<DursMsg>>,
+    pub self_peer: self_peer::SelfPeer,
+    pub server_sender: Option<WsSender>,
     pub soft_name: &'static str,
     pub soft_version: &'static str,
     pub ssl: bool,
-    pub websockets: HashMap<NodeFullId, WsSender>,
-    pub ws2p_endpoints: HashMap<NodeFullId, DbEndpoint>,
-    pub uids_cache: HashMap<PubKey, String>,
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -279,7 +511,8 @@ pub struct WS2Pv1PendingReqInfos {
     requester_module: ModuleReqFullId,
     req_body: WS2Pv1ReqBody,
     recipient_node: NodeFullId,
-    timestamp: SystemTime,
+    timestamp: Instant,
+    retries: usize,
 }
 
 impl WS2Pv1Module {
@@ -297,26 +530,109 @@ impl WS2Pv1Module {
         };
 
         WS2Pv1Module {
+            ban_list: BanList::default(),
             router_sender,
             key_pair,
             current_blockstamp: Blockstamp::default(),
             conf,
+            conformance_scores: HashMap::new(),
+            connections: ConnectionsPool::default(),
             ep_file_path,
+            network_map_file_path: durs_conf::get_datas_path(soft_meta_datas.profile_path.clone())
+                .join(map::NETWORK_MAP_FILENAME),
+            network_metrics_file_path: durs_conf::get_datas_path(
+                soft_meta_datas.profile_path.clone(),
+            )
+            .join(metrics::NETWORK_METRICS_FILENAME),
             soft_name: soft_meta_datas.soft_name,
             soft_version: soft_meta_datas.soft_version,
             ssl: ssl(),
             node_id: NodeId(soft_meta_datas.conf.my_node_id()),
             main_thread_channel: mpsc::channel(),
-            next_receiver: 0,
-            pending_received_requests: HashMap::new(),
-            ws2p_endpoints: HashMap::new(),
-            websockets: HashMap::new(),
-            requests_awaiting_response: HashMap::new(),
-            heads_cache: HashMap::new(),
-            my_head: None,
+            port_mapping_renewal: None,
+            requests: RequestTracker::default(),
+            responses_cache: ResponseCache::default(),
+            handshakes: HandshakesPool::default(),
+            heads: heads::HeadsState::default(),
+            self_peer: self_peer::SelfPeer::default(),
+            server_sender: None,
+            metrics: WS2Pv1Metrics::default(),
+            metrics_history: metrics_history::MetricsHistory::new(&durs_conf::get_datas_path(
+                soft_meta_datas.profile_path.clone(),
+            )),
             my_signator,
-            uids_cache: HashMap::new(),
-            count_dal_requests: 0,
+            pool: WS2PPool::shared(),
+        }
+    }
+    /// Build the network map from known HEADs and endpoints
+    pub fn network_map(&self) -> map::NetworkMap {
+        let mut network_map = map::NetworkMap::default();
+        for head in self.heads.heads().values().chain(self.heads.my_head()) {
+            network_map.add_head(head);
+        }
+        let my_node_full_id = NodeFullId(self.node_id, self.key_pair.public_key());
+        for (node_full_id, DbEndpoint { ep, state, .. }) in self.connections.endpoints().iter() {
+            network_map.add_endpoint(*node_full_id, &ep.raw_endpoint);
+            if *state == WS2PConnectionState::Established {
+                network_map.add_connection(my_node_full_id, *node_full_id);
+            }
+        }
+        for node_full_id in self.connections.incoming_nodes() {
+            network_map.add_connection(my_node_full_id, *node_full_id);
+        }
+        network_map
+    }
+    /// Save the network map in its file
+    pub fn save_network_map(&self) {
+        if let Err(err) = self.network_map().save(&self.network_map_file_path) {
+            error!("WS2P1: Fail to write network map : {}", err);
+        }
+    }
+    /// Get connection metrics
+    pub fn network_metrics(&self) -> metrics::NetworkMetrics {
+        let established_connections = self.connections.count_established();
+        let mut network_metrics = metrics::NetworkMetrics {
+            established_connections,
+            handshake_failures: self.metrics.handshake_failures,
+            requests_in_flight: self.requests.count_awaiting(),
+            peers: Default::default(),
+        };
+        for (node_full_id, score) in &self.conformance_scores {
+            network_metrics.peers.insert(
+                node_full_id.to_string(),
+                metrics::PeerMetrics {
+                    uid: self.heads.uid(&node_full_id.1).cloned(),
+                    answered_requests: u64::from(score.answered_requests),
+                    average_latency_ms: self
+                        .metrics
+                        .response_times
+                        .get(node_full_id)
+                        .and_then(ResponseTimes::average_ms),
+                },
+            );
+        }
+        network_metrics
+    }
+    /// Save the connection metrics in their file and in the metrics history
+    pub fn save_network_metrics(&self) {
+        let network_metrics = self.network_metrics();
+        if let Err(err) = network_metrics.save(&self.network_metrics_file_path) {
+            error!("WS2P1: Fail to write network metrics : {}", err);
+        }
+        let now = durs_common_tools::fns::time::current_timestamp();
+        for (metric, value) in &[
+            (
+                "established_connections",
+                network_metrics.established_connections as u64,
+            ),
+            (
+                "requests_in_flight",
+                network_metrics.requests_in_flight as u64,
+            ),
+        ] {
+            if let Err(err) = self.metrics_history.record(metric, now, *value) {
+                error!("WS2P1: Fail to write metrics history : {}", err);
+            }
         }
     }
 }
@@ -324,6 +640,10 @@ impl WS2Pv1Module {
 #[derive(Debug)]
 pub enum WS2PThreadSignal {
     DursMsg(Box<DursMsg>),
+    /// Result of the port mapping of the WS2P server
+    PortMapping(Result<port_mapping::PortMapping, String>),
+    /// Public IP detected by the external check
+    PublicIp(std::net::IpAddr),
     WS2Pv1Msg(WS2Pv1Msg),
 }
 
@@ -378,15 +698,13 @@ impl ApiModule<DuRsConf, DursMsg> for WS2Pv1Module {
 
 impl NetworkModule<DuRsConf, DursMsg> for WS2Pv1Module {
     fn sync(
-        _soft_meta_datas: &SoftwareMetaDatas<DuRsConf>,
-        _keys: RequiredKeysContent,
-        _conf: WS2PConf,
-        _main_sender: mpsc::Sender<RouterThreadMessage<DursMsg>>,
-        _sync_params: SyncOpt,
+        soft_meta_datas: &SoftwareMetaDatas<DuRsConf>,
+        keys: RequiredKeysContent,
+        conf: WS2PConf,
+        main_sender: mpsc::Sender<RouterThreadMessage<DursMsg>>,
+        sync_params: SyncOpt,
     ) -> Result<(), SyncError> {
-        println!("Downlaod blockchain from network...");
-        println!("Error : not yet implemented !");
-        Ok(())
+        sync::sync(soft_meta_datas, keys, conf, main_sender, sync_params)
     }
 }
 
@@ -411,6 +729,8 @@ macro_rules! fields_overload {
 enum WS2Pv1Error {
     #[fail(display = "WS2Pv1Module fatal error at load_conf() : keys != NetworkKeyPair")]
     UnexpectedKeys,
+    #[fail(display = "WS2Pv1Module fail to listen on {}:{} : {}", _0, _1, _2)]
+    FailToListen(String, u16, String),
 }
 
 impl DursModule<DuRsConf, DursMsg> for WS2Pv1Module {
@@ -427,31 +747,75 @@ impl DursModule<DuRsConf, DursMsg> for WS2Pv1Module {
     fn ask_required_keys() -> RequiredKeys {
         RequiredKeys::NetworkKeyPair
     }
+    fn listen_addrs(module_conf: &Self::ModuleConf) -> Vec<(String, u16)> {
+        module_conf
+            .server
+            .iter()
+            .map(|server_conf| (server_conf.host.clone(), server_conf.port))
+            .collect()
+    }
     fn have_subcommand() -> bool {
         true
     }
+    fn i18n_catalogs() -> Vec<(Locale, Catalog)> {
+        vec![(Locale::Fr, i18n::FR_CATALOG)]
+    }
 
     fn generate_module_conf(
         currency_name: Option<&CurrencyName>,
-        _global_conf: &<DuRsConf as DursConfTrait>::GlobalConf,
+        global_conf: &<DuRsConf as DursConfTrait>::GlobalConf,
         module_user_conf: Option<Self::ModuleUserConf>,
     ) -> Result<(Self::ModuleConf, Option<Self::ModuleUserConf>), ModuleConfError> {
         let mut conf = WS2PConf::default();
         conf.currency = currency_name.cloned();
 
-        if currency_name.is_some() && unwrap!(currency_name) == &CurrencyName("g1-test".to_owned())
+        let defaults_currency = currency_name
+            .cloned()
+            .unwrap_or_else(|| CurrencyName(durs_conf::constants::DEFAULT_CURRENCY.to_owned()));
+        if let Some(currency_defaults) = durs_conf::currencies::get_module_currency_defaults::<
+            WS2PCurrencyDefaults,
+        >(&defaults_currency, Self::name())?
         {
-            conf.sync_endpoints = vec![unwrap!(EndpointV1::parse_from_raw(
-                "WS2P 17ae4dd9 ts.gt.elo.tf 80 ws2p",
-                PubKey::Ed25519(unwrap!(ed25519::PublicKey::from_base58(
-                    "42jMJtb8chXrpHMAMcreVdyPJK7LtWjEeRqkPw4eSEVp",
-                )),),
-                0,
-                0,
-            ))];
+            conf.sync_endpoints = currency_defaults.sync_endpoints()?;
         }
 
         if let Some(module_user_conf) = module_user_conf.clone() {
+            conf.server = WS2PServerConf::from_user_conf(
+                NodeId(global_conf.my_node_id()),
+                &module_user_conf,
+            )?;
+            if let Some(ref proxy) = module_user_conf.proxy {
+                if !proxy::is_valid_proxy_addr(proxy) {
+                    return Err(ModuleConfError::InvalidField {
+                        field_name: stringify!(proxy),
+                        cause: format!("'{}' is not in the format host:port", proxy),
+                    });
+                }
+            } else if module_user_conf.only_proxy.unwrap_or(false) {
+                return Err(ModuleConfError::InvalidField {
+                    field_name: stringify!(only_proxy),
+                    cause: "only_proxy requires a proxy".to_owned(),
+                });
+            }
+            conf.proxy = module_user_conf.proxy.clone();
+            conf.public_ip_command = module_user_conf.public_ip_command.clone();
+            if module_user_conf.max_parallel_handshakes == Some(0) {
+                return Err(ModuleConfError::InvalidField {
+                    field_name: stringify!(max_parallel_handshakes),
+                    cause: "must be greater than 0".to_owned(),
+                });
+            }
+            if let Some(ref nat_pmp_gateway) = module_user_conf.nat_pmp_gateway {
+                conf.nat_pmp_gateway =
+                    Some(
+                        nat_pmp_gateway
+                            .parse()
+                            .map_err(|_| ModuleConfError::InvalidField {
+                                field_name: stringify!(nat_pmp_gateway),
+                                cause: format!("'{}' is not an IPv4", nat_pmp_gateway),
+                            })?,
+                    );
+            }
             /*if let Some(outcoming_quota) = module_user_conf.outcoming_quota {
                 conf.outcoming_quota = outcoming_quota;
             }
@@ -470,12 +834,44 @@ impl DursModule<DuRsConf, DursMsg> for WS2Pv1Module {
                     })
                     .collect::<Result<HashSet<PubKey>, ModuleConfError>>()?;
             }
+            if let Some(ref pinned_certificates) = module_user_conf.pinned_certificates {
+                for (pubkey, fingerprint) in pinned_certificates {
+                    let pubkey =
+                        PubKey::from_str(pubkey).map_err(|e| ModuleConfError::InvalidField {
+                            field_name: stringify!(pinned_certificates),
+                            cause: format!("pubkey '{}' is invalid: {}", pubkey, e),
+                        })?;
+                    if !conf.prefered_pubkeys.contains(&pubkey) {
+                        return Err(ModuleConfError::InvalidField {
+                            field_name: stringify!(pinned_certificates),
+                            cause: format!("pubkey '{}' is not a prefered pubkey", pubkey),
+                        });
+                    }
+                    let fingerprint = parse_cert_fingerprint(fingerprint).ok_or_else(|| {
+                        ModuleConfError::InvalidField {
+                            field_name: stringify!(pinned_certificates),
+                            cause: format!("'{}' is not a SHA-256 fingerprint", fingerprint),
+                        }
+                    })?;
+                    conf.pinned_certificates.insert(pubkey, fingerprint);
+                }
+            }
             fields_overload!(
                 conf;
                 module_user_conf;
                 [
                     outcoming_quota,
-                    sync_endpoints
+                    incoming_quota,
+                    max_parallel_handshakes,
+                    only_proxy,
+                    sync_endpoints,
+                    heads_gossip,
+                    heads_max_step,
+                    heads_forwards_per_min,
+                    keep_alive_interval,
+                    keep_alive_max_missed_pongs,
+                    public_ip_check_interval,
+                    port_mapping
                 ]
             )
         }
@@ -483,7 +879,7 @@ impl DursModule<DuRsConf, DursMsg> for WS2Pv1Module {
         Ok((conf, module_user_conf))
     }
     fn exec_subcommand(
-        _soft_meta_datas: &SoftwareMetaDatas<DuRsConf>,
+        soft_meta_datas: &SoftwareMetaDatas<DuRsConf>,
         _keys: RequiredKeysContent,
         _module_conf: Self::ModuleConf,
         module_user_conf: Option<Self::ModuleUserConf>,
@@ -492,7 +888,44 @@ impl DursModule<DuRsConf, DursMsg> for WS2Pv1Module {
         match opts.subcommand {
             WS2PSubCommands::Prefered {
                 subcommand: prefered_subcommand,
-            } => prefered_subcommand.execute(module_user_conf),
+            } => prefered_subcommand.execute(&soft_meta_datas.i18n, module_user_conf),
+            WS2PSubCommands::Status => {
+                let network_metrics_file_path =
+                    durs_conf::get_datas_path(soft_meta_datas.profile_path.clone())
+                        .join(metrics::NETWORK_METRICS_FILENAME);
+                match metrics::NetworkMetrics::load(&network_metrics_file_path) {
+                    Ok(network_metrics) => print!("{}", network_metrics),
+                    Err(e) => println!(
+                        "{}",
+                        soft_meta_datas.i18n.tr_args(
+                            "No connection metrics available (the node must be running): {}",
+                            &[&e]
+                        )
+                    ),
+                }
+                module_user_conf
+            }
+            WS2PSubCommands::Ban(ban_opt) => {
+                ban_opt.execute(
+                    &soft_meta_datas.i18n,
+                    &get_ban_list_file_path(soft_meta_datas),
+                );
+                module_user_conf
+            }
+            WS2PSubCommands::Unban(unban_opt) => {
+                unban_opt.execute(
+                    &soft_meta_datas.i18n,
+                    &get_ban_list_file_path(soft_meta_datas),
+                );
+                module_user_conf
+            }
+            WS2PSubCommands::Endpoints {
+                subcommand: endpoints_subcommand,
+            } => {
+                endpoints_subcommand
+                    .execute(&soft_meta_datas.i18n, &get_ep_file_path(soft_meta_datas));
+                module_user_conf
+            }
         }
     }
     fn start(
@@ -502,7 +935,7 @@ impl DursModule<DuRsConf, DursMsg> for WS2Pv1Module {
         router_sender: mpsc::Sender<RouterThreadMessage<DursMsg>>,
     ) -> Result<(), failure::Error> {
         // Get start time
-        let start_time = SystemTime::now();
+        let start_time = Instant::now();
 
         // Get key_pair
         let key_pair = if let RequiredKeysContent::NetworkKeyPair(key_pair) = keys {
@@ -512,7 +945,7 @@ impl DursModule<DuRsConf, DursMsg> for WS2Pv1Module {
         };
 
         // load conf
-        let mut ws2p_endpoints = HashMap::new();
+        let mut ws2p_endpoints = DbEndpoints::default();
         for ep in &conf.sync_endpoints {
             info!("Load sync endpoint {}", ep.raw_endpoint);
             let node_full_id = ep
@@ -524,17 +957,13 @@ impl DursModule<DuRsConf, DursMsg> for WS2Pv1Module {
                     ep: ep.clone(),
                     state: WS2PConnectionState::Close,
                     last_check: 0,
+                    stats: EndpointStats::default(),
                 },
             );
         }
 
         // Get endpoints file path
-        let mut ep_file_path = durs_conf::get_datas_path(soft_meta_datas.profile_path.clone());
-        ep_file_path.push("ws2pv1");
-        if !ep_file_path.exists() {
-            fs::create_dir(ep_file_path.as_path()).expect("Impossible to create ws2pv1 dir !");
-        }
-        ep_file_path.push("endpoints.bin");
+        let ep_file_path = get_ep_file_path(soft_meta_datas);
 
         // Define WS2Pv1Module
         let mut ws2p_module = WS2Pv1Module::new(
@@ -544,373 +973,166 @@ impl DursModule<DuRsConf, DursMsg> for WS2Pv1Module {
             key_pair,
             router_sender.clone(),
         );
-        ws2p_module.ws2p_endpoints = ws2p_endpoints;
+        ws2p_module.connections = ConnectionsPool::new(ws2p_endpoints);
 
         // Create ws2p main thread channel
         let ws2p_sender_clone = ws2p_module.main_thread_channel.0.clone();
 
         // Get ws2p endpoints in file
         debug!("WS2P SSL={}", ssl());
-        let count;
-        match ws2p_db::get_endpoints(&ep_file_path) {
-            Ok(ws2p_enpoints) => {
-                let ws2p_enpoints = ws2p_enpoints
-                    .into_iter()
-                    .filter(|(_, dal_ep)| cfg!(feature = "ssl") || dal_ep.ep.port != 443)
-                    .map(|(node_full_id, mut dal_ep)| {
-                        if dal_ep.state == WS2PConnectionState::Established {
-                            dal_ep.state = WS2PConnectionState::Close;
-                        }
-                        (node_full_id, dal_ep)
-                    })
-                    .collect::<Vec<(NodeFullId, DbEndpoint)>>();
-                count = ws2p_enpoints.len();
-                ws2p_module.ws2p_endpoints.extend(ws2p_enpoints);
-            }
-            Err(err) => fatal_error!("WS2Pv1: fail to load endpoints from DB: {:?}", err),
+        if !ssl() && !ws2p_module.conf.pinned_certificates.is_empty() {
+            warn!(
+                "WS2P: pinned certificates require the ssl feature, pinned nodes are unreachable !"
+            );
         }
-        info!("Load {} endpoints from DB !", count);
-
-        // Create proxy channel
-        let (proxy_sender, proxy_receiver): (mpsc::Sender<DursMsg>, mpsc::Receiver<DursMsg>) =
-            mpsc::channel();
-        let proxy_sender_clone = proxy_sender;
-
-        // Launch a proxy thread that transform DursMsg to WS2PThreadSignal(DursMsg)
-        thread::spawn(move || {
-            // Send proxy sender to main
-            router_sender
-                .send(RouterThreadMessage::ModuleRegistration {
-                    static_name: WS2Pv1Module::name(),
-                    sender: proxy_sender_clone,
-                    roles: vec![ModuleRole::InterNodesNetwork],
-                    events_subscription: vec![
-                        ModuleEvent::CurrencyParameters,
-                        ModuleEvent::NewValidBlock,
-                        ModuleEvent::NewWotDocInPool,
-                        ModuleEvent::NewTxinPool,
-                    ],
-                    reserved_apis_parts: vec![ApiPart {
-                        name: ApiName(WS2P_API.to_owned()),
-                        versions: hashset![ApiVersion(1)],
-                    }],
-                    endpoints: vec![],
-                })
-                .expect("Fatal error : ws2p1 module fail to send is sender channel !");
-            debug!("Send ws2p1 sender to main thread.");
-            loop {
-                match proxy_receiver.recv() {
-                    Ok(message) => {
-                        let stop = if let DursMsg::Stop = message {
-                            true
-                        } else {
-                            false
-                        };
-                        ws2p_sender_clone
-                            .send(WS2PThreadSignal::DursMsg(Box::new(message)))
-                            .expect(
-                                "Fatal error : fail to relay DursMsgContent to ws2p main thread !",
-                            );
-                        if stop {
-                            break;
-                        };
-                    }
-                    Err(e) => fatal_error!(format!("{}", e)),
-                }
+        let ws2p_enpoints = get_endpoints_from_db(&ep_file_path);
+        info!("Load {} endpoints from DB !", ws2p_enpoints.len());
+        ws2p_module.connections.extend_endpoints(ws2p_enpoints);
+
+        // Get ban list
+        ws2p_module.ban_list = match BanList::load(&get_ban_list_file_path(soft_meta_datas)) {
+            Ok(ban_list) => ban_list,
+            Err(err) => fatal_error!("WS2Pv1: fail to load ban list: {:?}", err),
+        };
+
+        // Listen incoming connections
+        let mut endpoints = Vec::new();
+        if let Some(ref server_conf) = ws2p_module.conf.server {
+            ws2p_module.server_sender = Some(
+                server::listen_incoming_connections(
+                    &server_conf.host,
+                    server_conf.port,
+                    &ws2p_sender_clone,
+                    &ws2p_module
+                        .conf
+                        .currency
+                        .clone()
+                        .expect("WS2PError : No currency !")
+                        .0,
+                    &ws2p_module.key_pair,
+                    ws2p_module.conf.keep_alive(),
+                )
+                .map_err(|e| {
+                    WS2Pv1Error::FailToListen(
+                        server_conf.host.clone(),
+                        server_conf.port,
+                        e.to_string(),
+                    )
+                })?,
+            );
+            if let Some(ep) = server_conf.public_endpoint(ws2p_module.key_pair.public_key()) {
+                info!("WS2P: declare public endpoint {}", ep.raw_endpoint);
+                endpoints.push(EndpointEnum::V1(ep));
             }
-        });
+            if ws2p_module.conf.port_mapping {
+                port_mapping::start_port_mapping(&ws2p_module);
+            }
+        }
+
+        // Register ws2p module in router
+        register_in_router(
+            router_sender,
+            ws2p_sender_clone,
+            vec![
+                ModuleEvent::CurrencyParameters,
+                ModuleEvent::NewValidBlock,
+                ModuleEvent::NewValidBlockFromSelf,
+                ModuleEvent::NewWotDocInPool,
+                ModuleEvent::NewTxinPool,
+            ],
+            endpoints,
+        );
 
         // Request current blockstamp
         send_dal_request(&mut ws2p_module, &BlockchainRequest::CurrentBlockstamp());
 
         // Start
         connect_to_know_endpoints(&mut ws2p_module);
-        ws2p_module.main_loop(start_time);
+        Ws2pStateMachine::new(ws2p_module, StdMonotonicClock, start_time).run();
 
         Ok(())
     }
 }
 
-impl WS2Pv1Module {
-    fn main_loop(mut self, start_time: SystemTime) {
-        // Initialize variables
-        let mut last_ws2p_connecting_wave = SystemTime::now();
-        let mut last_ws2p_state_print = SystemTime::now();
-        let mut last_ws2p_endpoints_write = SystemTime::now();
-        let mut endpoints_to_update_status: HashMap<NodeFullId, SystemTime> = HashMap::new();
-        let mut last_identities_request = UNIX_EPOCH;
+/// Get endpoints file path
+fn get_ep_file_path(soft_meta_datas: &SoftwareMetaDatas<DuRsConf>) -> PathBuf {
+    let mut ep_file_path = durs_conf::get_datas_path(soft_meta_datas.profile_path.clone());
+    ep_file_path.push("ws2pv1");
+    if !ep_file_path.exists() {
+        fs::create_dir(ep_file_path.as_path()).expect("Impossible to create ws2pv1 dir !");
+    }
+    ep_file_path.push("endpoints.bin");
+    ep_file_path
+}
 
-        loop {
-            match self
-                .main_thread_channel
-                .1
-                .recv_timeout(Duration::from_millis(200))
-            {
-                Ok(message) => match message {
-                    WS2PThreadSignal::DursMsg(durs_mesage) => {
-                        match durs_mesage.deref() {
-                            DursMsg::Stop => {
-                                // Close all connections
-                                for ws in self.websockets.values() {
-                                    let _ = ws.0.close(CloseCode::Normal);
-                                }
-                                // Break main loop
-                                break;
-                            }
-                            DursMsg::Request {
-                                ref req_content, ..
-                            } => requests::received::receive_req(&mut self, req_content),
-                            DursMsg::Event {
-                                ref event_type,
-                                ref event_content,
-                                ..
-                            } => events::received::receive_event(
-                                &mut self,
-                                *event_type,
-                                event_content,
-                            ),
-                            DursMsg::Response {
-                                req_id,
-                                res_content,
-                                ..
-                            } => {
-                                responses::received::receive_response(
-                                    &mut self,
-                                    *req_id,
-                                    res_content,
-                                );
-                            }
-                            _ => {} // Others DursMsg variants
-                        }
-                    }
-                    WS2PThreadSignal::WS2Pv1Msg(msg) => {
-                        match crate::ws_connections::messages::ws2p_recv_message_pretreatment(
-                            &mut self, msg,
-                        ) {
-                            WS2PSignal::NoConnection => {
-                                warn!("WS2PSignal::NoConnection");
-                            }
-                            WS2PSignal::ConnectionEstablished(ws2p_full_id) => {
-                                let module_req_id =
-                                    ModuleReqId(self.requests_awaiting_response.len() as u32);
-                                let module_id = WS2Pv1Module::name();
-                                debug!("WS2P: send req to: ({:?})", ws2p_full_id);
-                                let _current_request_result =
-                                    ws_connections::requests::sent::send_request_to_specific_node(
-                                        &mut self,
-                                        ModuleReqFullId(module_id, module_req_id),
-                                        &ws2p_full_id,
-                                        &WS2Pv1Request {
-                                            id: WS2Pv1ReqId::random(),
-                                            body: WS2Pv1ReqBody::GetCurrent,
-                                        },
-                                    );
-                                if self.uids_cache.get(&ws2p_full_id.1).is_none() {
-                                    send_dal_request(
-                                        &mut self,
-                                        &BlockchainRequest::UIDs(vec![ws2p_full_id.1]),
-                                    );
-                                }
-                                let event = NetworkEvent::ConnectionStateChange(
-                                    ws2p_full_id,
-                                    WS2PConnectionState::Established as u32,
-                                    self.uids_cache.get(&ws2p_full_id.1).cloned(),
-                                    self.ws2p_endpoints[&ws2p_full_id]
-                                        .ep
-                                        .get_url(false, false)
-                                        .expect("Endpoint unreachable !"),
-                                );
-                                events::sent::send_network_event(&mut self, event);
-                            }
-                            WS2PSignal::WSError(ws2p_full_id) => {
-                                endpoints_to_update_status.insert(ws2p_full_id, SystemTime::now());
-                                close_connection(
-                                    &mut self,
-                                    &ws2p_full_id,
-                                    WS2PCloseConnectionReason::WsError,
-                                );
-                                let event = NetworkEvent::ConnectionStateChange(
-                                    ws2p_full_id,
-                                    WS2PConnectionState::WSError as u32,
-                                    self.uids_cache.get(&ws2p_full_id.1).cloned(),
-                                    self.ws2p_endpoints[&ws2p_full_id]
-                                        .ep
-                                        .get_url(false, false)
-                                        .expect("Endpoint unreachable !"),
-                                );
-                                events::sent::send_network_event(&mut self, event);
-                            }
-                            WS2PSignal::NegociationTimeout(ws2p_full_id) => {
-                                endpoints_to_update_status.insert(ws2p_full_id, SystemTime::now());
-                                let event = NetworkEvent::ConnectionStateChange(
-                                    ws2p_full_id,
-                                    WS2PConnectionState::Denial as u32,
-                                    self.uids_cache.get(&ws2p_full_id.1).cloned(),
-                                    self.ws2p_endpoints[&ws2p_full_id]
-                                        .ep
-                                        .get_url(false, false)
-                                        .expect("Endpoint unreachable !"),
-                                );
-                                events::sent::send_network_event(&mut self, event);
-                            }
-                            WS2PSignal::Timeout(ws2p_full_id) => {
-                                endpoints_to_update_status.insert(ws2p_full_id, SystemTime::now());
-                                let event = NetworkEvent::ConnectionStateChange(
-                                    ws2p_full_id,
-                                    WS2PConnectionState::Close as u32,
-                                    self.uids_cache.get(&ws2p_full_id.1).cloned(),
-                                    self.ws2p_endpoints[&ws2p_full_id]
-                                        .ep
-                                        .get_url(false, false)
-                                        .expect("Endpoint unreachable !"),
-                                );
-                                events::sent::send_network_event(&mut self, event);
-                            }
-                            WS2PSignal::PeerCard(_ws2p_full_id, _peer_card, ws2p_endpoints) => {
-                                //trace!("WS2PSignal::PeerCard({})", ws2p_full_id);
-                                //self.send_network_event(NetworkEvent::ReceivePeers(_));
-                                for ep in ws2p_endpoints {
-                                    match self.ws2p_endpoints.get(
-                                        &ep.node_full_id()
-                                            .expect("WS2P: Fail to get ep.node_full_id() !"),
-                                    ) {
-                                        Some(_) => {}
-                                        None => {
-                                            if let Some(_api) =
-                                                ws2p_db::string_to_api(&ep.api.0.clone())
-                                            {
-                                                endpoints_to_update_status.insert(
-                                                    ep.node_full_id().expect(
-                                                        "WS2P: Fail to get ep.node_full_id() !",
-                                                    ),
-                                                    SystemTime::now(),
-                                                );
-                                            }
-                                            if cfg!(feature = "ssl") || ep.port != 443 {
-                                                connect_to(&mut self, &ep);
-                                            }
-                                        }
-                                    };
-                                }
-                            }
-                            WS2PSignal::Heads(ws2p_full_id, heads) => {
-                                trace!("WS2PSignal::Heads({}, {:?})", ws2p_full_id, heads.len());
-                                send_dal_request(
-                                    &mut self,
-                                    &BlockchainRequest::UIDs(
-                                        heads.iter().map(NetworkHead::pubkey).collect(),
-                                    ),
-                                );
-                                let event = NetworkEvent::ReceiveHeads(
-                                    heads
-                                        .iter()
-                                        .map(|head| {
-                                            let mut new_head = head.clone();
-                                            if let Some(uid) = self.uids_cache.get(&head.pubkey()) {
-                                                new_head.set_uid(uid);
-                                            }
-                                            new_head
-                                        })
-                                        .collect(),
-                                );
-                                events::sent::send_network_event(&mut self, event);
-                            }
-                            WS2PSignal::Blocks(ws2p_full_id, blocks) => {
-                                trace!("WS2PSignal::Blocks({})", ws2p_full_id);
-                                events::sent::send_network_event(
-                                    &mut self,
-                                    NetworkEvent::ReceiveBlocks(blocks),
-                                );
-                            }
-                            WS2PSignal::UserDocuments(ws2p_full_id, user_documents) => {
-                                trace!("WS2PSignal::UserDocuments({})", ws2p_full_id);
-                                events::sent::send_network_event(
-                                    &mut self,
-                                    NetworkEvent::ReceiveDocuments(user_documents),
-                                );
-                            }
-                            WS2PSignal::Request { from, req_id, body } => {
-                                ws_connections::requests::received::receive_ws2p_v1_request(
-                                    &mut self, from, req_id, body,
-                                );
-                            }
-                            WS2PSignal::ReqResponse(
-                                module_req_full_id,
-                                ws2p_req_body,
-                                recipient_full_id,
-                                response,
-                            ) => ws_connections::responses::received::receive_response(
-                                &mut self,
-                                module_req_full_id,
-                                ws2p_req_body,
-                                recipient_full_id,
-                                response,
-                            ),
-                            WS2PSignal::Empty => {}
-                        }
-                    }
-                },
-                Err(e) => match e {
-                    mpsc::RecvTimeoutError::Disconnected => {
-                        fatal_error!("Disconnected ws2p module !");
-                    }
-                    mpsc::RecvTimeoutError::Timeout => {}
-                },
-            }
-            if unwrap!(SystemTime::now().duration_since(last_ws2p_endpoints_write))
-                > Duration::new(*DURATION_BETWEEN_2_ENDPOINTS_SAVING, 0)
-            {
-                last_ws2p_endpoints_write = SystemTime::now();
-                if let Err(err) = ws2p_db::write_endpoints(&self.ep_file_path, &self.ws2p_endpoints)
-                {
-                    fatal_error!("WS2P1: Fail to write endpoints in DB : {:?}", err);
-                }
-            }
-            if unwrap!(SystemTime::now().duration_since(last_ws2p_state_print))
-                > Duration::new(*WS2P_GENERAL_STATE_INTERVAL, 0)
-            {
-                last_ws2p_state_print = SystemTime::now();
-                let mut connected_nodes = Vec::new();
-                for (k, DbEndpoint { state, .. }) in self.ws2p_endpoints.clone() {
-                    if let WS2PConnectionState::Established = state {
-                        connected_nodes.push(k);
-                    }
-                }
-                // Print current_blockstamp
-                info!(
-                    "WS2Pv1Module : current_blockstamp() = {:?}",
-                    self.current_blockstamp
-                );
-                // New WS2P connection wave
-                if connected_nodes.len() < self.conf.clone().outcoming_quota
-                    && (unwrap!(SystemTime::now().duration_since(last_ws2p_connecting_wave))
-                        > Duration::new(*WS2P_OUTCOMING_INTERVAL, 0)
-                        || (unwrap!(SystemTime::now().duration_since(last_ws2p_connecting_wave))
-                            > Duration::new(*WS2P_OUTCOMING_INTERVAL_AT_STARTUP, 0)
-                            && unwrap!(SystemTime::now().duration_since(start_time))
-                                < Duration::new(*WS2P_OUTCOMING_INTERVAL, 0)))
-                {
-                    last_ws2p_connecting_wave = SystemTime::now();
-                    info!("Connected to know endpoints...");
-                    connect_to_know_endpoints(&mut self);
+/// Get ban list file path (alongside endpoints file)
+fn get_ban_list_file_path(soft_meta_datas: &SoftwareMetaDatas<DuRsConf>) -> PathBuf {
+    get_ep_file_path(soft_meta_datas).with_file_name(ws2p_db::BAN_LIST_FILENAME)
+}
+
+/// Get reachable endpoints saved in DB
+fn get_endpoints_from_db(ep_file_path: &Path) -> Vec<(NodeFullId, DbEndpoint)> {
+    match ws2p_db::get_endpoints(ep_file_path) {
+        Ok(ws2p_enpoints) => ws2p_enpoints
+            .into_iter()
+            .filter(|(_, dal_ep)| cfg!(feature = "ssl") || dal_ep.ep.port != 443)
+            .map(|(node_full_id, mut dal_ep)| {
+                if dal_ep.state == WS2PConnectionState::Established {
+                    dal_ep.state = WS2PConnectionState::Close;
                 }
-                // Request pending_identities from network
-                if unwrap!(SystemTime::now().duration_since(last_identities_request))
-                    > Duration::new(*PENDING_IDENTITIES_REQUEST_INTERVAL, 0)
-                    && unwrap!(SystemTime::now().duration_since(start_time)) > Duration::new(10, 0)
-                {
-                    /*info!("get pending_identities from all connections...");
-                    let _blocks_request_result = self.send_request_to_all_connections(
-                        &OldNetworkRequest::GetRequirementsPending(ModuleReqId(0 as u32), 5),
-                    );*/
-                    last_identities_request = SystemTime::now();
+                (node_full_id, dal_ep)
+            })
+            .collect(),
+        Err(err) => fatal_error!("WS2Pv1: fail to load endpoints from DB: {:?}", err),
+    }
+}
+
+/// Register ws2p module in router and launch a proxy thread that transform DursMsg to WS2PThreadSignal(DursMsg)
+fn register_in_router(
+    router_sender: mpsc::Sender<RouterThreadMessage<DursMsg>>,
+    ws2p_sender: mpsc::Sender<WS2PThreadSignal>,
+    events_subscription: Vec<ModuleEvent>,
+    endpoints: Vec<EndpointEnum>,
+) {
+    // Create proxy channel
+    let (proxy_sender, proxy_receiver): (mpsc::Sender<DursMsg>, mpsc::Receiver<DursMsg>) =
+        mpsc::channel();
+
+    spawn_child("proxy", move || {
+        // Send proxy sender to main
+        router_sender
+            .send(RouterThreadMessage::ModuleRegistration {
+                static_name: WS2Pv1Module::name(),
+                sender: proxy_sender,
+                roles: vec![ModuleRole::InterNodesNetwork],
+                events_subscription,
+                reserved_apis_parts: vec![ApiPart {
+                    name: ApiName(WS2P_API.to_owned()),
+                    versions: hashset![ApiVersion(1)],
+                }],
+                endpoints,
+            })
+            .expect("Fatal error : ws2p1 module fail to send is sender channel !");
+        debug!("Send ws2p1 sender to main thread.");
+        loop {
+            match proxy_receiver.recv() {
+                Ok(message) => {
+                    let stop = if let DursMsg::Stop = message {
+                        true
+                    } else {
+                        false
+                    };
+                    ws2p_sender
+                        .send(WS2PThreadSignal::DursMsg(Box::new(message)))
+                        .expect("Fatal error : fail to relay DursMsgContent to ws2p main thread !");
+                    if stop {
+                        break;
+                    };
                 }
-                // ..
-                // Request current blockstamp
-                send_dal_request(&mut self, &BlockchainRequest::CurrentBlockstamp());
+                Err(e) => fatal_error!(format!("{}", e)),
             }
         }
-    }
+    });
 }
 
 #[cfg(test)]
@@ -918,156 +1140,429 @@ mod tests {
     use super::*;
     use crate::ws_connections::requests::sent::network_request_to_json;
     use crate::ws_connections::requests::*;
+    use crate::ws_connections::responses::{WS2Pv1ReqRes, WS2Pv1ReqResBody};
     use dubp_block_doc::block::{BlockDocument, BlockDocumentTrait};
     use dubp_block_doc::parser::parse_json_block_from_serde_value;
     use dubp_common_doc::BlockNumber;
 
     #[test]
-    fn test_parse_json_block() {
-        let json_block = json!({
-            "fork": false,
+    fn test_generate_module_conf_with_currency_defaults() -> Result<(), ModuleConfError> {
+        let global_conf = DuRsConf::default().get_global_conf();
+
+        let (conf, _) = WS2Pv1Module::generate_module_conf(
+            Some(&CurrencyName("g1-test".to_owned())),
+            &global_conf,
+            None,
+        )?;
+        assert_eq!(1, conf.sync_endpoints.len());
+        assert_eq!(
+            "WS2P 17ae4dd9 ts.gt.elo.tf 80 ws2p",
+            conf.sync_endpoints[0].raw_endpoint
+        );
+
+        let (conf, _) = WS2Pv1Module::generate_module_conf(
+            Some(&CurrencyName("unknown".to_owned())),
+            &global_conf,
+            None,
+        )?;
+        assert!(conf.sync_endpoints.is_empty());
+
+        let (conf, _) = WS2Pv1Module::generate_module_conf(None, &global_conf, None)?;
+        assert_eq!(7, conf.sync_endpoints.len());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_module_conf_with_server() -> Result<(), ModuleConfError> {
+        let global_conf = DuRsConf::default().get_global_conf();
+        let node_id = NodeId(global_conf.my_node_id());
+
+        let (conf, _) = WS2Pv1Module::generate_module_conf(
+            None,
+            &global_conf,
+            Some(WS2PUserConf {
+                port: Some(20901),
+                ..WS2PUserConf::default()
+            }),
+        )?;
+        assert_eq!(
+            Some(WS2PServerConf {
+                host: WS2P_DEFAULT_HOST.to_owned(),
+                port: 20901,
+                public_endpoint: None,
+            }),
+            conf.server
+        );
+
+        let (conf, _) = WS2Pv1Module::generate_module_conf(
+            None,
+            &global_conf,
+            Some(WS2PUserConf {
+                incoming_quota: Some(5),
+                host: Some("127.0.0.1".to_owned()),
+                port: Some(20901),
+                public_host: Some("g1.durs.info".to_owned()),
+                public_port: Some(443),
+                public_path: Some("ws2p".to_owned()),
+                ..WS2PUserConf::default()
+            }),
+        )?;
+        assert_eq!(5, conf.incoming_quota);
+        let server_conf = conf.server.expect("server conf must be defined");
+        assert_eq!("127.0.0.1", server_conf.host);
+        let public_endpoint = server_conf
+            .public_endpoint(PubKey::default())
+            .expect("public endpoint must be defined");
+        assert_eq!(
+            format!("WS2P {} g1.durs.info 443 ws2p", node_id),
+            public_endpoint.raw_endpoint
+        );
+        assert_eq!(Some(node_id), public_endpoint.node_id);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_module_conf_with_keep_alive() -> Result<(), ModuleConfError> {
+        let global_conf = DuRsConf::default().get_global_conf();
+
+        let (conf, _) = WS2Pv1Module::generate_module_conf(None, &global_conf, None)?;
+        assert_eq!(
+            KeepAlive::new(
+                *WS2P_DEFAULT_KEEP_ALIVE_INTERVAL,
+                *WS2P_DEFAULT_KEEP_ALIVE_MAX_MISSED_PONGS
+            ),
+            conf.keep_alive()
+        );
+
+        let (conf, _) = WS2Pv1Module::generate_module_conf(
+            None,
+            &global_conf,
+            Some(WS2PUserConf {
+                keep_alive_interval: Some(0),
+                keep_alive_max_missed_pongs: Some(5),
+                ..WS2PUserConf::default()
+            }),
+        )?;
+        assert_eq!(KeepAlive::new(0, 5), conf.keep_alive());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_module_conf_with_port_mapping() -> Result<(), ModuleConfError> {
+        let global_conf = DuRsConf::default().get_global_conf();
+
+        assert!(WS2Pv1Module::generate_module_conf(
+            None,
+            &global_conf,
+            Some(WS2PUserConf {
+                nat_pmp_gateway: Some("box.local".to_owned()),
+                ..WS2PUserConf::default()
+            }),
+        )
+        .is_err());
+
+        let (conf, _) = WS2Pv1Module::generate_module_conf(
+            None,
+            &global_conf,
+            Some(WS2PUserConf {
+                port_mapping: Some(true),
+                nat_pmp_gateway: Some("192.168.1.1".to_owned()),
+                ..WS2PUserConf::default()
+            }),
+        )?;
+        assert!(conf.port_mapping);
+        assert_eq!(Some(Ipv4Addr::new(192, 168, 1, 1)), conf.nat_pmp_gateway);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_module_conf_with_proxy() -> Result<(), ModuleConfError> {
+        let global_conf = DuRsConf::default().get_global_conf();
+
+        assert!(WS2Pv1Module::generate_module_conf(
+            None,
+            &global_conf,
+            Some(WS2PUserConf {
+                only_proxy: Some(true),
+                ..WS2PUserConf::default()
+            }),
+        )
+        .is_err());
+        assert!(WS2Pv1Module::generate_module_conf(
+            None,
+            &global_conf,
+            Some(WS2PUserConf {
+                proxy: Some("127.0.0.1".to_owned()),
+                ..WS2PUserConf::default()
+            }),
+        )
+        .is_err());
+
+        let (conf, _) = WS2Pv1Module::generate_module_conf(
+            None,
+            &global_conf,
+            Some(WS2PUserConf {
+                proxy: Some("127.0.0.1:9050".to_owned()),
+                only_proxy: Some(true),
+                ..WS2PUserConf::default()
+            }),
+        )?;
+        let endpoint = |raw_endpoint: &str| {
+            EndpointV1::parse_from_raw(raw_endpoint, PubKey::default(), 0, 0)
+                .expect("invalid endpoint")
+        };
+        let proxy_route = WS2POutgoingRoute::Proxy("127.0.0.1:9050".to_owned());
+        assert_eq!(
+            proxy_route,
+            conf.outgoing_route(&endpoint("WS2P 11111111 g1.durs.info 20901"), false)
+        );
+        assert_eq!(
+            proxy_route,
+            conf.outgoing_route(
+                &endpoint("WS2P 11111111 abcdefghijklmnop.onion 20901"),
+                false
+            )
+        );
+        assert_eq!(
+            WS2POutgoingRoute::Unreachable,
+            conf.outgoing_route(&endpoint("WS2P 11111111 g1.durs.info 443 ws2p"), true)
+        );
+
+        let conf = WS2PConf::default();
+        assert_eq!(
+            WS2POutgoingRoute::Direct,
+            conf.outgoing_route(&endpoint("WS2P 11111111 g1.durs.info 20901"), false)
+        );
+        assert_eq!(
+            WS2POutgoingRoute::Unreachable,
+            conf.outgoing_route(
+                &endpoint("WS2P 11111111 abcdefghijklmnop.onion 20901"),
+                false
+            )
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_module_conf_with_pinned_certificates() -> Result<(), ModuleConfError> {
+        let global_conf = DuRsConf::default().get_global_conf();
+        let pubkey = "8dkCwvAqSczUjKsoVMDPVbQ3i6bBQeBQYawL87kqTSQ3";
+        let fingerprint = "AB:CD:EF:01:23:45:67:89:ab:cd:ef:01:23:45:67:89:\
+                           AB:CD:EF:01:23:45:67:89:ab:cd:ef:01:23:45:67:89";
+        let user_conf = |prefered: bool, fingerprint: &str| WS2PUserConf {
+            prefered_pubkeys: if prefered {
+                Some(hashset![pubkey.to_owned()])
+            } else {
+                None
+            },
+            pinned_certificates: Some(
+                vec![(pubkey.to_owned(), fingerprint.to_owned())]
+                    .into_iter()
+                    .collect(),
+            ),
+            ..WS2PUserConf::default()
+        };
+
+        // The pubkey must be prefered and the fingerprint must be a SHA-256 digest
+        assert!(WS2Pv1Module::generate_module_conf(
+            None,
+            &global_conf,
+            Some(user_conf(false, fingerprint))
+        )
+        .is_err());
+        assert!(WS2Pv1Module::generate_module_conf(
+            None,
+            &global_conf,
+            Some(user_conf(true, "ABCD"))
+        )
+        .is_err());
+
+        let (conf, _) = WS2Pv1Module::generate_module_conf(
+            None,
+            &global_conf,
+            Some(user_conf(true, fingerprint)),
+        )?;
+        let pubkey = PubKey::from_str(pubkey).expect("invalid pubkey");
+        let mut expected_fingerprint = [0u8; 32];
+        for (i, byte) in expected_fingerprint.iter_mut().enumerate() {
+            *byte = [0xab, 0xcd, 0xef, 0x01, 0x23, 0x45, 0x67, 0x89][i % 8];
+        }
+        assert_eq!(
+            Some(&expected_fingerprint),
+            conf.pinned_certificates.get(&pubkey)
+        );
+
+        // A pinned endpoint is only reachable with TLS
+        let endpoint = |raw_endpoint: &str| {
+            EndpointV1::parse_from_raw(raw_endpoint, pubkey, 0, 0).expect("invalid endpoint")
+        };
+        assert_eq!(
+            WS2POutgoingRoute::Unreachable,
+            conf.outgoing_route(&endpoint("WS2P 11111111 g1.durs.info 20901"), true)
+        );
+        assert_eq!(
+            WS2POutgoingRoute::Direct,
+            conf.outgoing_route(&endpoint("WS2P 11111111 g1.durs.info 443 ws2p"), true)
+        );
+
+        Ok(())
+    }
+
+    fn json_block() -> serde_json::Value {
+        json!({
+        "fork": false,
+        "version": 10,
+        "nonce": 10_500_000_059_239_u64,
+        "number": 109_966,
+        "powMin": 88,
+        "time": 1_523_300_656,
+        "medianTime": 1_523_295_259,
+        "membersCount": 933,
+        "monetaryMass": 146_881_563,
+        "unitbase": 0,
+        "issuersCount": 44,
+        "issuersFrame": 221,
+        "issuersFrameVar": 0,
+        "currency": "g1",
+        "issuer": "GRBPV3Y7PQnB9LaZhSGuS3BqBJbSHyibzYq65kTh1nQ4",
+        "signature": "GCg2Lti3TdxWlhA8JF8pRI+dRQ0XZVtcC4BqO/COTpjTQFdWG6qmUNVvdeYCtR/lu1JQe3N/IhrbyV6L/6I+Cg==",
+        "hash": "000000EF5B2AA849F4C3AF3D35E1284EA1F34A9F617EA806CE8371619023DC74",
+        "parameters": "",
+        "previousHash": "000004C00602F8A27AE078DE6351C0DDA1EA0974A78D2BEFA7DFBE7B7C3146FD",
+        "previousIssuer": "5SwfQubSat5SunNafCsunEGTY93nVM4kLSsuprNqQb6S",
+        "inner_hash": "61F02B1A6AE2E4B9A1FD66CE673258B4B21C0076795571EE3C9DC440DD06C46C",
+        "dividend": null,
+        "identities": [],
+        "joiners": [],
+        "actives": [],
+        "leavers": [],
+        "revoked": [],
+        "excluded": [],
+        "certifications": [
+            "Hm5qjaNuHogNRdGZ4vgnLA9DMZVUu5YWzVup5mubuxCc:8AmdBsimcLziXaCS4AcVUfPx7rkjeic7482dLbBkuZw6:109964:yHKBGMeuxyIqFb295gVNK6neRC+U0tmsX1Zed3TLjS3ZZHYYycE1piLcYsTKll4ifNVp6rm+hd/CLdHYB+29CA==",
+            "BncjgJeFpGsMCCsUfzNLEexjsbuX3V2mg9P67ov2LkwK:DyBUBNpzpfvjtwYYSaVMM6ST6t2DNg3NCE9CU9bRQFhF:105864:cJEGW9WxJwlMA2+4LNAK4YieyseUy1WIkFh1YLYD+JJtJEoCSnIQRXzhiAoRpGaj0bRz8sTpwI6PRkuVoDJJDQ=="
+        ],
+        "transactions": [
+            {
             "version": 10,
-            "nonce": 10_500_000_059_239 as u64,
-            "number": 109_966,
-            "powMin": 88,
-            "time": 1_523_300_656,
-            "medianTime": 1_523_295_259,
-            "membersCount": 933,
-            "monetaryMass": 146_881_563,
-            "unitbase": 0,
-            "issuersCount": 44,
-            "issuersFrame": 221,
-            "issuersFrameVar": 0,
             "currency": "g1",
-            "issuer": "GRBPV3Y7PQnB9LaZhSGuS3BqBJbSHyibzYq65kTh1nQ4",
-            "signature": "GCg2Lti3TdxWlhA8JF8pRI+dRQ0XZVtcC4BqO/COTpjTQFdWG6qmUNVvdeYCtR/lu1JQe3N/IhrbyV6L/6I+Cg==",
-            "hash": "000000EF5B2AA849F4C3AF3D35E1284EA1F34A9F617EA806CE8371619023DC74",
-            "parameters": "",
-            "previousHash": "000004C00602F8A27AE078DE6351C0DDA1EA0974A78D2BEFA7DFBE7B7C3146FD",
-            "previousIssuer": "5SwfQubSat5SunNafCsunEGTY93nVM4kLSsuprNqQb6S",
-            "inner_hash": "61F02B1A6AE2E4B9A1FD66CE673258B4B21C0076795571EE3C9DC440DD06C46C",
-            "dividend": null,
-            "identities": [],
-            "joiners": [],
-            "actives": [],
-            "leavers": [],
-            "revoked": [],
-            "excluded": [],
-            "certifications": [
-                "Hm5qjaNuHogNRdGZ4vgnLA9DMZVUu5YWzVup5mubuxCc:8AmdBsimcLziXaCS4AcVUfPx7rkjeic7482dLbBkuZw6:109964:yHKBGMeuxyIqFb295gVNK6neRC+U0tmsX1Zed3TLjS3ZZHYYycE1piLcYsTKll4ifNVp6rm+hd/CLdHYB+29CA==",
-                "BncjgJeFpGsMCCsUfzNLEexjsbuX3V2mg9P67ov2LkwK:DyBUBNpzpfvjtwYYSaVMM6ST6t2DNg3NCE9CU9bRQFhF:105864:cJEGW9WxJwlMA2+4LNAK4YieyseUy1WIkFh1YLYD+JJtJEoCSnIQRXzhiAoRpGaj0bRz8sTpwI6PRkuVoDJJDQ=="
+            "locktime": 0,
+            "hash": "80FE1E83DC4D0B722CA5F8363EFC6A3E29071032EBB71C1E0DF8D4FEA589C698",
+            "blockstamp": "109964-00000168105D4A8A8BC8C0DC70033F45ABE472782C75A7F2074D0F4D4A3B7B2B",
+            "blockstampTime": 0,
+            "issuers": [
+                "6PiqcuUWhyiBF3Lgcht8c1yfk6gMfQzcUc46CqrJfeLT"
             ],
-            "transactions": [
-                {
-                "version": 10,
-                "currency": "g1",
-                "locktime": 0,
-                "hash": "80FE1E83DC4D0B722CA5F8363EFC6A3E29071032EBB71C1E0DF8D4FEA589C698",
-                "blockstamp": "109964-00000168105D4A8A8BC8C0DC70033F45ABE472782C75A7F2074D0F4D4A3B7B2B",
-                "blockstampTime": 0,
-                "issuers": [
-                    "6PiqcuUWhyiBF3Lgcht8c1yfk6gMfQzcUc46CqrJfeLT"
-                ],
-                "inputs": [
-                    "1001:0:D:6PiqcuUWhyiBF3Lgcht8c1yfk6gMfQzcUc46CqrJfeLT:98284",
-                    "1001:0:D:6PiqcuUWhyiBF3Lgcht8c1yfk6gMfQzcUc46CqrJfeLT:98519",
-                    "1001:0:D:6PiqcuUWhyiBF3Lgcht8c1yfk6gMfQzcUc46CqrJfeLT:98779",
-                    "1001:0:D:6PiqcuUWhyiBF3Lgcht8c1yfk6gMfQzcUc46CqrJfeLT:99054",
-                    "1001:0:D:6PiqcuUWhyiBF3Lgcht8c1yfk6gMfQzcUc46CqrJfeLT:99326",
-                    "1001:0:D:6PiqcuUWhyiBF3Lgcht8c1yfk6gMfQzcUc46CqrJfeLT:99599",
-                    "1001:0:D:6PiqcuUWhyiBF3Lgcht8c1yfk6gMfQzcUc46CqrJfeLT:99884",
-                    "1001:0:D:6PiqcuUWhyiBF3Lgcht8c1yfk6gMfQzcUc46CqrJfeLT:100174",
-                    "1001:0:D:6PiqcuUWhyiBF3Lgcht8c1yfk6gMfQzcUc46CqrJfeLT:100469",
-                    "1001:0:D:6PiqcuUWhyiBF3Lgcht8c1yfk6gMfQzcUc46CqrJfeLT:100746",
-                    "1001:0:D:6PiqcuUWhyiBF3Lgcht8c1yfk6gMfQzcUc46CqrJfeLT:101036",
-                    "1001:0:D:6PiqcuUWhyiBF3Lgcht8c1yfk6gMfQzcUc46CqrJfeLT:101327"
-                ],
-                "outputs": [
-                    "12000:0:SIG(HmH5beJqKGMeotcQUrSW7Wo5tKvAksHmfYXfiSQ9EbWz)",
-                    "12:0:SIG(6PiqcuUWhyiBF3Lgcht8c1yfk6gMfQzcUc46CqrJfeLT)"
-                ],
-                "unlocks": [
-                    "0:SIG(0)",
-                    "1:SIG(0)",
-                    "2:SIG(0)",
-                    "3:SIG(0)",
-                    "4:SIG(0)",
-                    "5:SIG(0)",
-                    "6:SIG(0)",
-                    "7:SIG(0)",
-                    "8:SIG(0)",
-                    "9:SIG(0)",
-                    "10:SIG(0)",
-                    "11:SIG(0)"
-                ],
-                "signatures": [
-                    "MZxoKxYgwufh/s5mwLCsYEZXtIsP1hEKCyAzLipJsvCbR9xj7wXUw0C/ahwvZfBtR7+QVPIfLmwYEol1JcHjDw=="
-                ],
-                "comment": "Adhesion 2018"
-                },
-                {
-                "version": 10,
-                "currency": "g1",
-                "locktime": 0,
-                "hash": "B80507412B35BD5EB437AE0D3EB97E60E3A4974F5CDEA1AF7E2127C0E943481F",
-                "blockstamp": "109964-00000168105D4A8A8BC8C0DC70033F45ABE472782C75A7F2074D0F4D4A3B7B2B",
-                "blockstampTime": 0,
-                "issuers": [
-                    "8gundJEbfm73Kx3jjw8YivJyz8qD2igjf6baCBLFCxPU"
-                ],
-                "inputs": [
-                    "1001:0:D:8gundJEbfm73Kx3jjw8YivJyz8qD2igjf6baCBLFCxPU:91560",
-                    "1001:0:D:8gundJEbfm73Kx3jjw8YivJyz8qD2igjf6baCBLFCxPU:91850",
-                    "1001:0:D:8gundJEbfm73Kx3jjw8YivJyz8qD2igjf6baCBLFCxPU:92111",
-                    "1001:0:D:8gundJEbfm73Kx3jjw8YivJyz8qD2igjf6baCBLFCxPU:92385",
-                    "1001:0:D:8gundJEbfm73Kx3jjw8YivJyz8qD2igjf6baCBLFCxPU:92635"
-                ],
-                "outputs": [
-                    "5000:0:SIG(BzHnbec1Gov7dLSt1EzJS7vikoQCECeuvZs4wamZAcT1)",
-                    "5:0:SIG(8gundJEbfm73Kx3jjw8YivJyz8qD2igjf6baCBLFCxPU)"
-                ],
-                "unlocks": [
-                    "0:SIG(0)",
-                    "1:SIG(0)",
-                    "2:SIG(0)",
-                    "3:SIG(0)",
-                    "4:SIG(0)"
-                ],
-                "signatures": [
-                    "A+ukwRvLWs1gZQ0KAqAnknEgmRQHdrnOvNuBx/WZqje17BAPrVxSxKpqwU6MiajU+ppigsYp6Bu0FdPf/tGnCQ=="
-                ],
-                "comment": ""
-                },
-                {
-                "version": 10,
-                "currency": "g1",
-                "locktime": 0,
-                "hash": "D8970E6629C0381A78534EEDD86803E9215A7EC4C494BAEA79EB19425F9B4D31",
-                "blockstamp": "109964-00000168105D4A8A8BC8C0DC70033F45ABE472782C75A7F2074D0F4D4A3B7B2B",
-                "blockstampTime": 0,
-                "issuers": [
-                    "FnSXE7QyBfs4ozoYAt5NEewWhHEPorf38cNXu3kX9xsg"
-                ],
-                "inputs": [
-                    "1000:0:D:FnSXE7QyBfs4ozoYAt5NEewWhHEPorf38cNXu3kX9xsg:36597",
-                    "1000:0:D:FnSXE7QyBfs4ozoYAt5NEewWhHEPorf38cNXu3kX9xsg:36880",
-                    "1000:0:D:FnSXE7QyBfs4ozoYAt5NEewWhHEPorf38cNXu3kX9xsg:37082"
-                ],
-                "outputs": [
-                    "3000:0:SIG(BBC8Rnh4CWN1wBrPLevK7GRFFVDVw7Lu24YNMUmhqoHU)"
-                ],
-                "unlocks": [
-                    "0:SIG(0)",
-                    "1:SIG(0)",
-                    "2:SIG(0)"
-                ],
-                "signatures": [
-                    "OpiF/oQfIigOeAtsteukU0w9FPSELE+BVTxhmsQ8bEeYGlwovG2VF8ZFiJkLLPi6vFuKgwzULJfjNGd97twZCw=="
-                ],
-                "comment": "1 billet pour une seance.pour un chouette film"
-                }
+            "inputs": [
+                "1001:0:D:6PiqcuUWhyiBF3Lgcht8c1yfk6gMfQzcUc46CqrJfeLT:98284",
+                "1001:0:D:6PiqcuUWhyiBF3Lgcht8c1yfk6gMfQzcUc46CqrJfeLT:98519",
+                "1001:0:D:6PiqcuUWhyiBF3Lgcht8c1yfk6gMfQzcUc46CqrJfeLT:98779",
+                "1001:0:D:6PiqcuUWhyiBF3Lgcht8c1yfk6gMfQzcUc46CqrJfeLT:99054",
+                "1001:0:D:6PiqcuUWhyiBF3Lgcht8c1yfk6gMfQzcUc46CqrJfeLT:99326",
+                "1001:0:D:6PiqcuUWhyiBF3Lgcht8c1yfk6gMfQzcUc46CqrJfeLT:99599",
+                "1001:0:D:6PiqcuUWhyiBF3Lgcht8c1yfk6gMfQzcUc46CqrJfeLT:99884",
+                "1001:0:D:6PiqcuUWhyiBF3Lgcht8c1yfk6gMfQzcUc46CqrJfeLT:100174",
+                "1001:0:D:6PiqcuUWhyiBF3Lgcht8c1yfk6gMfQzcUc46CqrJfeLT:100469",
+                "1001:0:D:6PiqcuUWhyiBF3Lgcht8c1yfk6gMfQzcUc46CqrJfeLT:100746",
+                "1001:0:D:6PiqcuUWhyiBF3Lgcht8c1yfk6gMfQzcUc46CqrJfeLT:101036",
+                "1001:0:D:6PiqcuUWhyiBF3Lgcht8c1yfk6gMfQzcUc46CqrJfeLT:101327"
             ],
-        });
+            "outputs": [
+                "12000:0:SIG(HmH5beJqKGMeotcQUrSW7Wo5tKvAksHmfYXfiSQ9EbWz)",
+                "12:0:SIG(6PiqcuUWhyiBF3Lgcht8c1yfk6gMfQzcUc46CqrJfeLT)"
+            ],
+            "unlocks": [
+                "0:SIG(0)",
+                "1:SIG(0)",
+                "2:SIG(0)",
+                "3:SIG(0)",
+                "4:SIG(0)",
+                "5:SIG(0)",
+                "6:SIG(0)",
+                "7:SIG(0)",
+                "8:SIG(0)",
+                "9:SIG(0)",
+                "10:SIG(0)",
+                "11:SIG(0)"
+            ],
+            "signatures": [
+                "MZxoKxYgwufh/s5mwLCsYEZXtIsP1hEKCyAzLipJsvCbR9xj7wXUw0C/ahwvZfBtR7+QVPIfLmwYEol1JcHjDw=="
+            ],
+            "comment": "Adhesion 2018"
+            },
+            {
+            "version": 10,
+            "currency": "g1",
+            "locktime": 0,
+            "hash": "B80507412B35BD5EB437AE0D3EB97E60E3A4974F5CDEA1AF7E2127C0E943481F",
+            "blockstamp": "109964-00000168105D4A8A8BC8C0DC70033F45ABE472782C75A7F2074D0F4D4A3B7B2B",
+            "blockstampTime": 0,
+            "issuers": [
+                "8gundJEbfm73Kx3jjw8YivJyz8qD2igjf6baCBLFCxPU"
+            ],
+            "inputs": [
+                "1001:0:D:8gundJEbfm73Kx3jjw8YivJyz8qD2igjf6baCBLFCxPU:91560",
+                "1001:0:D:8gundJEbfm73Kx3jjw8YivJyz8qD2igjf6baCBLFCxPU:91850",
+                "1001:0:D:8gundJEbfm73Kx3jjw8YivJyz8qD2igjf6baCBLFCxPU:92111",
+                "1001:0:D:8gundJEbfm73Kx3jjw8YivJyz8qD2igjf6baCBLFCxPU:92385",
+                "1001:0:D:8gundJEbfm73Kx3jjw8YivJyz8qD2igjf6baCBLFCxPU:92635"
+            ],
+            "outputs": [
+                "5000:0:SIG(BzHnbec1Gov7dLSt1EzJS7vikoQCECeuvZs4wamZAcT1)",
+                "5:0:SIG(8gundJEbfm73Kx3jjw8YivJyz8qD2igjf6baCBLFCxPU)"
+            ],
+            "unlocks": [
+                "0:SIG(0)",
+                "1:SIG(0)",
+                "2:SIG(0)",
+                "3:SIG(0)",
+                "4:SIG(0)"
+            ],
+            "signatures": [
+                "A+ukwRvLWs1gZQ0KAqAnknEgmRQHdrnOvNuBx/WZqje17BAPrVxSxKpqwU6MiajU+ppigsYp6Bu0FdPf/tGnCQ=="
+            ],
+            "comment": ""
+            },
+            {
+            "version": 10,
+            "currency": "g1",
+            "locktime": 0,
+            "hash": "D8970E6629C0381A78534EEDD86803E9215A7EC4C494BAEA79EB19425F9B4D31",
+            "blockstamp": "109964-00000168105D4A8A8BC8C0DC70033F45ABE472782C75A7F2074D0F4D4A3B7B2B",
+            "blockstampTime": 0,
+            "issuers": [
+                "FnSXE7QyBfs4ozoYAt5NEewWhHEPorf38cNXu3kX9xsg"
+            ],
+            "inputs": [
+                "1000:0:D:FnSXE7QyBfs4ozoYAt5NEewWhHEPorf38cNXu3kX9xsg:36597",
+                "1000:0:D:FnSXE7QyBfs4ozoYAt5NEewWhHEPorf38cNXu3kX9xsg:36880",
+                "1000:0:D:FnSXE7QyBfs4ozoYAt5NEewWhHEPorf38cNXu3kX9xsg:37082"
+            ],
+            "outputs": [
+                "3000:0:SIG(BBC8Rnh4CWN1wBrPLevK7GRFFVDVw7Lu24YNMUmhqoHU)"
+            ],
+            "unlocks": [
+                "0:SIG(0)",
+                "1:SIG(0)",
+                "2:SIG(0)"
+            ],
+            "signatures": [
+                "OpiF/oQfIigOeAtsteukU0w9FPSELE+BVTxhmsQ8bEeYGlwovG2VF8ZFiJkLLPi6vFuKgwzULJfjNGd97twZCw=="
+            ],
+            "comment": "1 billet pour une seance.pour un chouette film"
+            }
+        ],
+        })
+    }
+
+    #[test]
+    fn test_parse_json_block() {
+        let json_block = json_block();
         let block: BlockDocument = parse_json_block_from_serde_value(&json_block)
             .expect("Fail to parse test json block !");
         assert_eq!(
@@ -1088,6 +1583,35 @@ mod tests {
         );
     }
 
+    #[test]
+    fn ws2p_get_blocks_response() {
+        let block: BlockDocument = parse_json_block_from_serde_value(&json_block())
+            .expect("Fail to parse test json block !");
+        let req_id = WS2Pv1ReqId::from_str("fbcf0bfa-7e18-40cc-b300-5c797d27518e")
+            .expect("fail to parse req_id");
+        let response = WS2Pv1ReqRes {
+            req_id,
+            body: WS2Pv1ReqResBody::GetBlocks(vec![block.clone(), block]),
+        };
+        let json_response: serde_json::Value = response.clone().into();
+        let raw_response = response.into_raw_json();
+        assert_eq!(
+            json_response,
+            serde_json::from_str::<serde_json::Value>(&raw_response)
+                .expect("Fail to parse raw response !")
+        );
+        assert_eq!(Some(2), json_response["body"].as_array().map(Vec::len));
+
+        let empty_response = WS2Pv1ReqRes {
+            req_id,
+            body: WS2Pv1ReqResBody::GetBlocks(vec![]),
+        };
+        assert_eq!(
+            "{\"resId\":\"fbcf0bfa-7e18-40cc-b300-5c797d27518e\",\"body\":[]}",
+            empty_response.into_raw_json()
+        );
+    }
+
     #[test]
     fn ws2p_requests() {
         let req_id_str = "fbcf0bfa-7e18-40cc-b300-5c797d27518e";
@@ -0,0 +1,193 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Port mapping of the WS2P server on the gateway of the local network.
+//!
+//! NAT-PMP is tried first, then UPnP IGD. The port mapping is renewed before the end of its
+//! lifetime, and the external IP given by the gateway is declared in the self peer card.
+
+mod nat_pmp;
+mod upnp;
+
+use crate::constants::*;
+use crate::*;
+use durs_common_tools::fns::thread::spawn_child;
+use std::net::{IpAddr, Ipv4Addr, SocketAddrV4};
+
+/// File describing the IPv4 routing table (Linux)
+static ROUTE_TABLE_FILE: &str = "/proc/net/route";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// Port mapping granted by the gateway
+pub struct PortMapping {
+    /// Protocol used to map the port
+    pub protocol: &'static str,
+    /// External IP of the gateway
+    pub external_ip: Option<IpAddr>,
+    /// Port requested on the gateway (declared in the peer card)
+    pub requested_port: u16,
+    /// Port mapped on the gateway
+    pub external_port: u16,
+    /// Lifetime of the port mapping in seconds (0 for a permanent port mapping)
+    pub lifetime: u32,
+}
+
+/// Map port with NAT-PMP, then with UPnP IGD
+fn map_port(
+    nat_pmp_gateway: Option<Ipv4Addr>,
+    internal_port: u16,
+    external_port: u16,
+) -> Result<PortMapping, String> {
+    let nat_pmp_error = match nat_pmp_gateway.or_else(default_gateway) {
+        Some(gateway) => {
+            let gateway = SocketAddrV4::new(gateway, nat_pmp::NAT_PMP_PORT);
+            match nat_pmp::map_tcp_port(
+                gateway,
+                internal_port,
+                external_port,
+                *WS2P_PORT_MAPPING_LIFETIME_IN_SECS,
+            ) {
+                Ok((mapped_port, lifetime)) => {
+                    return Ok(PortMapping {
+                        protocol: "NAT-PMP",
+                        external_ip: nat_pmp::external_address(gateway).ok().map(IpAddr::V4),
+                        requested_port: external_port,
+                        external_port: mapped_port,
+                        lifetime,
+                    })
+                }
+                Err(e) => e.to_string(),
+            }
+        }
+        None => "unknown gateway".to_owned(),
+    };
+    let upnp_result = upnp::Gateway::discover().and_then(|gateway| {
+        let lifetime = gateway.map_tcp_port(
+            gateway.local_ip()?,
+            internal_port,
+            external_port,
+            *WS2P_PORT_MAPPING_LIFETIME_IN_SECS,
+        )?;
+        Ok(PortMapping {
+            protocol: "UPnP",
+            external_ip: gateway.external_ip().ok(),
+            requested_port: external_port,
+            external_port,
+            lifetime,
+        })
+    });
+    upnp_result.map_err(|upnp_error| format!("NAT-PMP: {}, UPnP: {}", nat_pmp_error, upnp_error))
+}
+
+/// Get the gateway of the default route
+fn default_gateway() -> Option<Ipv4Addr> {
+    fs::read_to_string(ROUTE_TABLE_FILE)
+        .ok()
+        .and_then(|route_table| parse_default_gateway(&route_table))
+}
+
+fn parse_default_gateway(route_table: &str) -> Option<Ipv4Addr> {
+    route_table.lines().skip(1).find_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() > 2 && fields[1] == "00000000" {
+            // The gateway is written in hexadecimal in the host byte order
+            u32::from_str_radix(fields[2], 16)
+                .ok()
+                .map(|gateway| Ipv4Addr::from(u32::from_be(gateway)))
+                .filter(|gateway| !gateway.is_unspecified())
+        } else {
+            None
+        }
+    })
+}
+
+/// Map the port of the WS2P server in a dedicated thread
+pub fn start_port_mapping(ws2p_module: &WS2Pv1Module) {
+    let server_conf = if let Some(ref server_conf) = ws2p_module.conf.server {
+        server_conf
+    } else {
+        return;
+    };
+    let internal_port = server_conf.port;
+    let external_port = server_conf
+        .public_endpoint(ws2p_module.key_pair.public_key())
+        .map(|ep| ep.port as u16)
+        .unwrap_or(internal_port);
+    let nat_pmp_gateway = ws2p_module.conf.nat_pmp_gateway;
+    let sender = ws2p_module.main_thread_channel.0.clone();
+    spawn_child("port_mapping", move || {
+        let _ = sender.send(WS2PThreadSignal::PortMapping(map_port(
+            nat_pmp_gateway,
+            internal_port,
+            external_port,
+        )));
+    });
+}
+
+/// Receive the result of the port mapping
+pub fn receive_port_mapping(
+    ws2p_module: &mut WS2Pv1Module,
+    port_mapping: Result<PortMapping, String>,
+) {
+    match port_mapping {
+        Ok(port_mapping) => {
+            info!(
+                "WS2P: port {} of the gateway is mapped with {} (lifetime: {}s).",
+                port_mapping.external_port, port_mapping.protocol, port_mapping.lifetime
+            );
+            ws2p_module.port_mapping_renewal = if port_mapping.lifetime > 0 {
+                Some(Instant::now() + Duration::from_secs(u64::from(port_mapping.lifetime) / 2))
+            } else {
+                None
+            };
+            self_peer::receive_public_port(
+                ws2p_module,
+                port_mapping.requested_port,
+                port_mapping.external_port,
+            );
+            if let Some(external_ip) = port_mapping.external_ip {
+                self_peer::receive_public_ip(ws2p_module, external_ip);
+            }
+        }
+        Err(e) => {
+            warn!(
+                "WS2P: fail to map port with NAT-PMP or UPnP ({}). Incoming connections are possible only if the port is forwarded manually to this node.",
+                e
+            );
+            ws2p_module.port_mapping_renewal = Some(
+                Instant::now() + Duration::from_secs(*WS2P_PORT_MAPPING_RETRY_INTERVAL_IN_SECS),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_default_gateway() {
+        let route_table =
+            "Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT
+eth0\t00000000\t0101A8C0\t0003\t0\t0\t100\t00000000\t0\t0\t0
+eth0\t0001A8C0\t00000000\t0001\t0\t0\t100\t00FFFFFF\t0\t0\t0
+";
+        assert_eq!(
+            Some(Ipv4Addr::new(192, 168, 1, 1)),
+            parse_default_gateway(route_table)
+        );
+        assert_eq!(None, parse_default_gateway("Iface\tDestination\tGateway\n"));
+    }
+}
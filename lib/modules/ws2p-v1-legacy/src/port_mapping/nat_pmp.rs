@@ -0,0 +1,153 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! NAT-PMP client (RFC 6886).
+
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket};
+use std::time::Duration;
+
+/// NAT-PMP port of the gateway
+pub const NAT_PMP_PORT: u16 = 5351;
+
+const NAT_PMP_VERSION: u8 = 0;
+const OP_EXTERNAL_ADDRESS: u8 = 0;
+const OP_MAP_TCP: u8 = 2;
+const OP_RESPONSE: u8 = 128;
+const RESULT_SUCCESS: u16 = 0;
+/// Number of attempts (the timeout is doubled at each attempt)
+const MAX_ATTEMPTS: u32 = 4;
+const INITIAL_TIMEOUT_IN_MILLIS: u64 = 250;
+
+/// Get the external IP address of the gateway
+pub fn external_address(gateway: SocketAddrV4) -> io::Result<Ipv4Addr> {
+    let response = request(gateway, &[NAT_PMP_VERSION, OP_EXTERNAL_ADDRESS], 12)?;
+    Ok(Ipv4Addr::new(
+        response[8],
+        response[9],
+        response[10],
+        response[11],
+    ))
+}
+
+/// Map a TCP port of the gateway to a local port.
+/// Returns the external port and the lifetime (in seconds) granted by the gateway.
+pub fn map_tcp_port(
+    gateway: SocketAddrV4,
+    internal_port: u16,
+    external_port: u16,
+    lifetime: u32,
+) -> io::Result<(u16, u32)> {
+    let mut request_bytes = vec![NAT_PMP_VERSION, OP_MAP_TCP, 0, 0];
+    request_bytes.extend_from_slice(&internal_port.to_be_bytes());
+    request_bytes.extend_from_slice(&external_port.to_be_bytes());
+    request_bytes.extend_from_slice(&lifetime.to_be_bytes());
+    let response = request(gateway, &request_bytes, 16)?;
+    Ok((
+        u16::from_be_bytes([response[10], response[11]]),
+        u32::from_be_bytes([response[12], response[13], response[14], response[15]]),
+    ))
+}
+
+/// Send request to the gateway and check the response
+fn request(gateway: SocketAddrV4, request: &[u8], response_len: usize) -> io::Result<Vec<u8>> {
+    let socket = UdpSocket::bind("0.0.0.0:0")?;
+    socket.connect(SocketAddr::V4(gateway))?;
+    let mut response = vec![0u8; response_len];
+    let mut timeout = Duration::from_millis(INITIAL_TIMEOUT_IN_MILLIS);
+    for _ in 0..MAX_ATTEMPTS {
+        socket.send(request)?;
+        socket.set_read_timeout(Some(timeout))?;
+        match socket.recv(&mut response) {
+            Ok(len) if len >= response_len => return check_response(request[1], response),
+            Ok(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "truncated NAT-PMP response",
+                ))
+            }
+            Err(ref e)
+                if e.kind() == io::ErrorKind::WouldBlock || e.kind() == io::ErrorKind::TimedOut =>
+            {
+                timeout *= 2
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::TimedOut,
+        "no response from NAT-PMP gateway",
+    ))
+}
+
+fn check_response(op: u8, response: Vec<u8>) -> io::Result<Vec<u8>> {
+    if response[0] != NAT_PMP_VERSION || response[1] != OP_RESPONSE + op {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "invalid NAT-PMP response",
+        ));
+    }
+    let result_code = u16::from_be_bytes([response[2], response[3]]);
+    if result_code != RESULT_SUCCESS {
+        return Err(io::Error::new(
+            io::ErrorKind::ConnectionRefused,
+            format!(
+                "NAT-PMP gateway refuses request (result code {})",
+                result_code
+            ),
+        ));
+    }
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_nat_pmp_requests() -> io::Result<()> {
+        // Fake NAT-PMP gateway
+        let gateway_socket = UdpSocket::bind("127.0.0.1:0")?;
+        let gateway = match gateway_socket.local_addr()? {
+            SocketAddr::V4(gateway) => gateway,
+            SocketAddr::V6(_) => panic!("gateway must be IPv4"),
+        };
+        let gateway_thread = thread::spawn(move || -> io::Result<Vec<u8>> {
+            let mut request = [0u8; 12];
+            let (_, client) = gateway_socket.recv_from(&mut request)?;
+            gateway_socket.send_to(&[0, 128, 0, 0, 0, 0, 0, 1, 84, 16, 72, 210], client)?;
+            let (len, client) = gateway_socket.recv_from(&mut request)?;
+            gateway_socket.send_to(
+                &[
+                    0, 130, 0, 0, 0, 0, 0, 2, 0x51, 0xa5, 0x51, 0xa6, 0, 0, 0x0e, 0x10,
+                ],
+                client,
+            )?;
+            Ok(request[..len].to_vec())
+        });
+
+        assert_eq!(Ipv4Addr::new(84, 16, 72, 210), external_address(gateway)?);
+        assert_eq!((20902, 3600), map_tcp_port(gateway, 20901, 20901, 7200)?);
+
+        let map_request = gateway_thread.join().expect("gateway thread panic")?;
+        assert_eq!(
+            vec![0, 2, 0, 0, 0x51, 0xa5, 0x51, 0xa5, 0, 0, 0x1c, 0x20],
+            map_request
+        );
+
+        Ok(())
+    }
+}
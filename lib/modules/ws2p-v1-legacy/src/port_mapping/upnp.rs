@@ -0,0 +1,289 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! UPnP IGD client: discover the Internet Gateway Device and map a TCP port.
+
+use std::io::{self, Read, Write};
+use std::net::{IpAddr, SocketAddr, TcpStream, UdpSocket};
+use std::time::Duration;
+use url::Url;
+
+const SSDP_ADDR: &str = "239.255.255.250:1900";
+const SSDP_SEARCH: &str = "M-SEARCH * HTTP/1.1\r\n\
+                           HOST: 239.255.255.250:1900\r\n\
+                           MAN: \"ssdp:discover\"\r\n\
+                           MX: 2\r\n\
+                           ST: urn:schemas-upnp-org:device:InternetGatewayDevice:1\r\n\r\n";
+const SSDP_TIMEOUT_IN_SECS: u64 = 3;
+const HTTP_TIMEOUT_IN_SECS: u64 = 5;
+/// WAN connection services able to map ports
+const WAN_SERVICES: &[&str] = &[
+    "urn:schemas-upnp-org:service:WANIPConnection:1",
+    "urn:schemas-upnp-org:service:WANIPConnection:2",
+    "urn:schemas-upnp-org:service:WANPPPConnection:1",
+];
+/// UPnP error returned by gateways that only support permanent port mappings
+const ONLY_PERMANENT_LEASES_SUPPORTED: &str = "725";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// WAN connection service of the gateway
+pub struct Gateway {
+    /// Control URL of the WAN connection service
+    control_url: Url,
+    /// Type of the WAN connection service
+    service_type: &'static str,
+}
+
+impl Gateway {
+    /// Discover the gateway on the local network
+    pub fn discover() -> io::Result<Gateway> {
+        let socket = UdpSocket::bind("0.0.0.0:0")?;
+        socket.set_read_timeout(Some(Duration::from_secs(SSDP_TIMEOUT_IN_SECS)))?;
+        socket.send_to(SSDP_SEARCH.as_bytes(), SSDP_ADDR)?;
+        let mut buffer = [0u8; 2048];
+        let (len, _) = socket.recv_from(&mut buffer)?;
+        let location = ssdp_location(&String::from_utf8_lossy(&buffer[..len]))
+            .ok_or_else(|| invalid_data("SSDP response without location"))?;
+        let location = Url::parse(&location).map_err(|e| invalid_data(e.to_string()))?;
+        let (_, description) = http_request(&location, "GET", &[], "")?;
+        let (service_type, control_url) = wan_service(&description)
+            .ok_or_else(|| invalid_data("the gateway has no WAN connection service"))?;
+        Ok(Gateway {
+            control_url: location
+                .join(&control_url)
+                .map_err(|e| invalid_data(e.to_string()))?,
+            service_type,
+        })
+    }
+    /// Local IP address used to reach the gateway
+    pub fn local_ip(&self) -> io::Result<IpAddr> {
+        Ok(TcpStream::connect_timeout(
+            &socket_addr(&self.control_url)?,
+            Duration::from_secs(HTTP_TIMEOUT_IN_SECS),
+        )?
+        .local_addr()?
+        .ip())
+    }
+    /// Get the external IP address of the gateway
+    pub fn external_ip(&self) -> io::Result<IpAddr> {
+        let response = self.soap_request("GetExternalIPAddress", &[])?;
+        xml_value(&response, "NewExternalIPAddress")
+            .and_then(|ip| ip.parse().ok())
+            .ok_or_else(|| invalid_data("invalid external IP address"))
+    }
+    /// Map a TCP port of the gateway to a port of the local IP.
+    /// Returns the lifetime of the port mapping (0 for a permanent port mapping).
+    pub fn map_tcp_port(
+        &self,
+        local_ip: IpAddr,
+        internal_port: u16,
+        external_port: u16,
+        lifetime: u32,
+    ) -> io::Result<u32> {
+        let internal_port = internal_port.to_string();
+        let external_port = external_port.to_string();
+        let local_ip = local_ip.to_string();
+        let add_port_mapping = |lifetime: &str| {
+            self.soap_request(
+                "AddPortMapping",
+                &[
+                    ("NewRemoteHost", ""),
+                    ("NewExternalPort", &external_port),
+                    ("NewProtocol", "TCP"),
+                    ("NewInternalPort", &internal_port),
+                    ("NewInternalClient", &local_ip),
+                    ("NewEnabled", "1"),
+                    ("NewPortMappingDescription", "durs ws2p"),
+                    ("NewLeaseDuration", lifetime),
+                ],
+            )
+        };
+        match add_port_mapping(&lifetime.to_string()) {
+            Ok(_) => Ok(lifetime),
+            Err(ref e) if e.to_string().contains(ONLY_PERMANENT_LEASES_SUPPORTED) => {
+                add_port_mapping("0").map(|_| 0)
+            }
+            Err(e) => Err(e),
+        }
+    }
+    fn soap_request(&self, action: &str, args: &[(&str, &str)]) -> io::Result<String> {
+        let body = format!(
+            "<?xml version=\"1.0\"?>\r\n\
+             <s:Envelope xmlns:s=\"http://schemas.xmlsoap.org/soap/envelope/\" \
+             s:encodingStyle=\"http://schemas.xmlsoap.org/soap/encoding/\">\
+             <s:Body><u:{action} xmlns:u=\"{service}\">{args}</u:{action}></s:Body></s:Envelope>",
+            action = action,
+            service = self.service_type,
+            args = args
+                .iter()
+                .map(|(name, value)| format!("<{0}>{1}</{0}>", name, value))
+                .collect::<String>(),
+        );
+        let soap_action = format!("\"{}#{}\"", self.service_type, action);
+        let (status, response) = http_request(
+            &self.control_url,
+            "POST",
+            &[
+                ("Content-Type", "text/xml; charset=\"utf-8\""),
+                ("SOAPAction", &soap_action),
+            ],
+            &body,
+        )?;
+        if status == 200 {
+            Ok(response)
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::ConnectionRefused,
+                format!(
+                    "UPnP action {} fail (HTTP status {}, error code {})",
+                    action,
+                    status,
+                    xml_value(&response, "errorCode").unwrap_or_default()
+                ),
+            ))
+        }
+    }
+}
+
+fn invalid_data<E: Into<Box<dyn std::error::Error + Send + Sync>>>(error: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+fn socket_addr(url: &Url) -> io::Result<SocketAddr> {
+    url.socket_addrs(|| Some(80))?
+        .into_iter()
+        .next()
+        .ok_or_else(|| invalid_data("unresolvable URL"))
+}
+
+/// Send HTTP/1.0 request, returns the status and the body of the response
+fn http_request(
+    url: &Url,
+    method: &str,
+    headers: &[(&str, &str)],
+    body: &str,
+) -> io::Result<(u16, String)> {
+    let addr = socket_addr(url)?;
+    let mut stream = TcpStream::connect_timeout(&addr, Duration::from_secs(HTTP_TIMEOUT_IN_SECS))?;
+    stream.set_read_timeout(Some(Duration::from_secs(HTTP_TIMEOUT_IN_SECS)))?;
+    let mut request = format!(
+        "{} {} HTTP/1.0\r\nHost: {}\r\nContent-Length: {}\r\n",
+        method,
+        &url[url::Position::BeforePath..],
+        addr,
+        body.len()
+    );
+    for (name, value) in headers {
+        request.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    request.push_str("\r\n");
+    request.push_str(body);
+    stream.write_all(request.as_bytes())?;
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+    let status = response
+        .split(' ')
+        .nth(1)
+        .and_then(|status| status.parse().ok())
+        .ok_or_else(|| invalid_data("invalid HTTP response"))?;
+    let body = response
+        .find("\r\n\r\n")
+        .map(|pos| response[pos + 4..].to_owned())
+        .unwrap_or_default();
+    Ok((status, body))
+}
+
+/// Get the location of the gateway description in a SSDP response
+fn ssdp_location(response: &str) -> Option<String> {
+    response.lines().find_map(|line| {
+        let mut parts = line.splitn(2, ':');
+        match (parts.next(), parts.next()) {
+            (Some(name), Some(value)) if name.trim().eq_ignore_ascii_case("location") => {
+                Some(value.trim().to_owned())
+            }
+            _ => None,
+        }
+    })
+}
+
+/// Get the type and the control URL of the WAN connection service in a gateway description
+fn wan_service(description: &str) -> Option<(&'static str, String)> {
+    WAN_SERVICES.iter().find_map(|service_type| {
+        let service_pos =
+            description.find(&format!("<serviceType>{}</serviceType>", service_type))?;
+        let service = &description[service_pos..];
+        let service = &service[..service.find("</service>").unwrap_or(service.len())];
+        xml_value(service, "controlURL").map(|control_url| (*service_type, control_url))
+    })
+}
+
+/// Get the value of the first element with this name
+fn xml_value(xml: &str, name: &str) -> Option<String> {
+    let start = xml.find(&format!("<{}>", name))? + name.len() + 2;
+    let end = start + xml[start..].find(&format!("</{}>", name))?;
+    Some(xml[start..end].trim().to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ssdp_location() {
+        assert_eq!(
+            Some("http://192.168.1.1:49000/igddesc.xml".to_owned()),
+            ssdp_location(
+                "HTTP/1.1 200 OK\r\nCACHE-CONTROL: max-age=120\r\n\
+                 Location: http://192.168.1.1:49000/igddesc.xml\r\n\
+                 ST: urn:schemas-upnp-org:device:InternetGatewayDevice:1\r\n\r\n"
+            )
+        );
+        assert_eq!(None, ssdp_location("HTTP/1.1 200 OK\r\n\r\n"));
+    }
+
+    #[test]
+    fn test_wan_service() {
+        let description = "<root><device><serviceList>\
+            <service><serviceType>urn:schemas-upnp-org:service:Layer3Forwarding:1</serviceType>\
+            <controlURL>/upnp/control/Layer3Forwarding</controlURL></service>\
+            <service><serviceType>urn:schemas-upnp-org:service:WANIPConnection:1</serviceType>\
+            <controlURL>/upnp/control/WANIPConn1</controlURL></service>\
+            </serviceList></device></root>";
+        assert_eq!(
+            Some((
+                "urn:schemas-upnp-org:service:WANIPConnection:1",
+                "/upnp/control/WANIPConn1".to_owned()
+            )),
+            wan_service(description)
+        );
+        assert_eq!(None, wan_service("<root></root>"));
+    }
+
+    #[test]
+    fn test_xml_value() {
+        assert_eq!(
+            Some("84.16.72.210".to_owned()),
+            xml_value(
+                "<u:GetExternalIPAddressResponse><NewExternalIPAddress>84.16.72.210</NewExternalIPAddress></u:GetExternalIPAddressResponse>",
+                "NewExternalIPAddress"
+            )
+        );
+        assert_eq!(
+            None,
+            xml_value("<errorCode>725</errorCode>", "NewExternalIPAddress")
+        );
+    }
+}
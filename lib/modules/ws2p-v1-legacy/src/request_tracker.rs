@@ -0,0 +1,167 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Track the requests sent and received by the module.
+
+use crate::ws_connections::requests::{WS2Pv1ReqFullId, WS2Pv1ReqId};
+use crate::WS2Pv1PendingReqInfos;
+use durs_common_tools::timer::elapsed_since;
+use durs_module::ModuleReqId;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Requests in progress
+#[derive(Debug, Default)]
+pub struct RequestTracker {
+    /// Counter of the requests sent to the blockchain module
+    count_dal_requests: u32,
+    /// Requests sent to other nodes and awaiting their response
+    awaiting_response: HashMap<WS2Pv1ReqId, WS2Pv1PendingReqInfos>,
+    /// Requests received from other nodes and forwarded to the blockchain module
+    pending_received: HashMap<ModuleReqId, WS2Pv1ReqFullId>,
+    /// Index of the next node to which send a request (among established connections)
+    next_receiver: usize,
+}
+
+impl RequestTracker {
+    /// Generate the id of a new request to the blockchain module
+    pub fn next_dal_req_id(&mut self) -> ModuleReqId {
+        self.count_dal_requests += 1;
+        if self.count_dal_requests == u32::MAX {
+            self.count_dal_requests = 0;
+        }
+        ModuleReqId(self.count_dal_requests)
+    }
+    /// Choose the receiver of the next request among `receivers_count` nodes (round robin)
+    pub fn next_receiver(&mut self, receivers_count: usize) -> Option<usize> {
+        if receivers_count == 0 {
+            return None;
+        }
+        if self.next_receiver >= receivers_count {
+            self.next_receiver = 0;
+        }
+        let receiver = self.next_receiver;
+        self.next_receiver += 1;
+        Some(receiver)
+    }
+    /// Track a request sent to another node
+    pub fn track_sent(&mut self, req_id: WS2Pv1ReqId, pending_req_infos: WS2Pv1PendingReqInfos) {
+        self.awaiting_response.insert(req_id, pending_req_infos);
+    }
+    /// Is this request still awaiting its response ?
+    pub fn is_awaiting(&self, req_id: &WS2Pv1ReqId) -> bool {
+        self.awaiting_response.contains_key(req_id)
+    }
+    /// Number of requests awaiting their response
+    pub fn count_awaiting(&self) -> usize {
+        self.awaiting_response.len()
+    }
+    /// Stop tracking a request whose response is received
+    pub fn take_sent(&mut self, req_id: &WS2Pv1ReqId) -> Option<WS2Pv1PendingReqInfos> {
+        self.awaiting_response.remove(req_id)
+    }
+    /// Stop tracking the requests sent for longer than `timeout`
+    pub fn take_timed_out(
+        &mut self,
+        now: Instant,
+        timeout: Duration,
+    ) -> Vec<(WS2Pv1ReqId, WS2Pv1PendingReqInfos)> {
+        let timed_out: Vec<WS2Pv1ReqId> = self
+            .awaiting_response
+            .iter()
+            .filter(|(_, pending_req_infos)| {
+                elapsed_since(now, pending_req_infos.timestamp) > timeout
+            })
+            .map(|(req_id, _)| *req_id)
+            .collect();
+        timed_out
+            .into_iter()
+            .filter_map(|req_id| {
+                self.awaiting_response
+                    .remove(&req_id)
+                    .map(|pending_req_infos| (req_id, pending_req_infos))
+            })
+            .collect()
+    }
+    /// Track a request received from another node
+    pub fn track_received(&mut self, module_req_id: ModuleReqId, req_full_id: WS2Pv1ReqFullId) {
+        self.pending_received.insert(module_req_id, req_full_id);
+    }
+    /// Stop tracking a received request whose response is ready
+    pub fn take_received(&mut self, module_req_id: &ModuleReqId) -> Option<WS2Pv1ReqFullId> {
+        self.pending_received.remove(module_req_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ws_connections::requests::WS2Pv1ReqBody;
+    use dup_crypto::keys::PubKey;
+    use durs_module::{ModuleReqFullId, ModuleStaticName};
+    use durs_network_documents::{NodeFullId, NodeId};
+
+    fn pending_req_infos(timestamp: Instant) -> WS2Pv1PendingReqInfos {
+        WS2Pv1PendingReqInfos {
+            requester_module: ModuleReqFullId(ModuleStaticName("test"), ModuleReqId(0)),
+            req_body: WS2Pv1ReqBody::GetCurrent,
+            recipient_node: NodeFullId(NodeId(1), PubKey::default()),
+            timestamp,
+            retries: 0,
+        }
+    }
+
+    #[test]
+    fn dal_req_ids() {
+        let mut tracker = RequestTracker::default();
+        assert_eq!(ModuleReqId(1), tracker.next_dal_req_id());
+        assert_eq!(ModuleReqId(2), tracker.next_dal_req_id());
+        tracker.count_dal_requests = u32::MAX - 1;
+        assert_eq!(ModuleReqId(0), tracker.next_dal_req_id());
+    }
+
+    #[test]
+    fn round_robin_receivers() {
+        let mut tracker = RequestTracker::default();
+        assert_eq!(None, tracker.next_receiver(0));
+        assert_eq!(Some(0), tracker.next_receiver(2));
+        assert_eq!(Some(1), tracker.next_receiver(2));
+        assert_eq!(Some(0), tracker.next_receiver(2));
+        // The number of receivers decreased
+        assert_eq!(Some(1), tracker.next_receiver(3));
+        assert_eq!(Some(0), tracker.next_receiver(1));
+    }
+
+    #[test]
+    fn sent_requests() {
+        let now = Instant::now() + Duration::from_secs(60);
+        let timeout = Duration::from_secs(30);
+        let old_req_id = WS2Pv1ReqId::random();
+        let new_req_id = WS2Pv1ReqId::random();
+        let mut tracker = RequestTracker::default();
+        tracker.track_sent(old_req_id, pending_req_infos(now - Duration::from_secs(60)));
+        tracker.track_sent(new_req_id, pending_req_infos(now));
+        assert_eq!(2, tracker.count_awaiting());
+
+        let timed_out = tracker.take_timed_out(now, timeout);
+        assert_eq!(1, timed_out.len());
+        assert_eq!(old_req_id, timed_out[0].0);
+        assert!(!tracker.is_awaiting(&old_req_id));
+
+        assert!(tracker.take_sent(&new_req_id).is_some());
+        assert!(tracker.take_sent(&new_req_id).is_none());
+        assert_eq!(0, tracker.count_awaiting());
+    }
+}
@@ -15,40 +15,35 @@
 
 //! Sub-module managing the inter-modules requests received.
 
+use crate::constants::WS2P_REPEATED_LOG_INTERVAL_IN_SECS;
 use crate::ws2p_db::DbEndpoint;
 use crate::ws_connections::requests::{WS2Pv1ReqBody, WS2Pv1ReqId, WS2Pv1Request};
 use crate::ws_connections::states::WS2PConnectionState;
 use crate::WS2Pv1Module;
 use dubp_common_doc::BlockNumber;
+use durs_common_tools::log_once_per;
 use durs_message::requests::DursReqContent;
-use durs_network::requests::OldNetworkRequest;
+use durs_module::{DursModule, ModuleReqFullId};
+use durs_network::requests::{NetworkResponse, OldNetworkRequest};
+use durs_network_documents::NodeFullId;
 
 pub fn receive_req(ws2p_module: &mut WS2Pv1Module, req_content: &DursReqContent) {
     if let DursReqContent::OldNetworkRequest(ref old_net_request) = *req_content {
         match *old_net_request {
             OldNetworkRequest::GetBlocks(ref module_req_full_id, ref count, ref from) => {
-                let mut receiver_index = 0;
-                let mut real_receiver = None;
-                for (ws2p_full_id, DbEndpoint { state, .. }) in ws2p_module.ws2p_endpoints.clone() {
-                    if let WS2PConnectionState::Established = state {
-                        if receiver_index == ws2p_module.next_receiver {
-                            real_receiver = Some(ws2p_full_id);
-                            break;
-                        }
-                        receiver_index += 1;
-                    }
-                }
-                if real_receiver.is_none() {
-                    ws2p_module.next_receiver = 0;
-                    for (ws2p_full_id, DbEndpoint { state, .. }) in &ws2p_module.ws2p_endpoints {
-                        if let WS2PConnectionState::Established = *state {
-                            real_receiver = Some(*ws2p_full_id);
-                            break;
-                        }
-                    }
-                } else {
-                    ws2p_module.next_receiver += 1;
-                }
+                let established_nodes: Vec<NodeFullId> = ws2p_module
+                    .connections
+                    .endpoints()
+                    .iter()
+                    .filter(|(_, DbEndpoint { state, .. })| {
+                        *state == WS2PConnectionState::Established
+                    })
+                    .map(|(ws2p_full_id, _)| *ws2p_full_id)
+                    .collect();
+                let real_receiver = ws2p_module
+                    .requests
+                    .next_receiver(established_nodes.len())
+                    .map(|receiver_index| established_nodes[receiver_index]);
                 if let Some(real_receiver) = real_receiver {
                     debug!("WS2P: send req to: ({:?})", real_receiver);
                     let _blocks_request_result =
@@ -65,9 +60,41 @@ pub fn receive_req(ws2p_module: &mut WS2Pv1Module, req_content: &DursReqContent)
                             },
                         );
                 } else {
-                    warn!("WS2P: not found peer to send request !");
+                    log_once_per!(
+                        *WS2P_REPEATED_LOG_INTERVAL_IN_SECS,
+                        log::Level::Warn,
+                        "WS2P: not found peer to send request !"
+                    );
                 }
             }
+            OldNetworkRequest::GetConsensus(ref module_req_full_id) => {
+                let consensus = crate::conformance::weighted_consensus(
+                    ws2p_module.heads.heads(),
+                    &ws2p_module.conformance_scores,
+                );
+                debug!("WS2P: weighted network consensus: {:?}", consensus);
+                crate::responses::sent::send_network_req_response(
+                    ws2p_module,
+                    module_req_full_id.0,
+                    module_req_full_id.1,
+                    NetworkResponse::Consensus(
+                        ModuleReqFullId(WS2Pv1Module::name(), module_req_full_id.1),
+                        consensus,
+                    ),
+                );
+            }
+            OldNetworkRequest::GetMetrics(ref module_req_full_id) => {
+                let network_metrics = ws2p_module.network_metrics();
+                crate::responses::sent::send_network_req_response(
+                    ws2p_module,
+                    module_req_full_id.0,
+                    module_req_full_id.1,
+                    NetworkResponse::Metrics(
+                        ModuleReqFullId(WS2Pv1Module::name(), module_req_full_id.1),
+                        Box::new(network_metrics),
+                    ),
+                );
+            }
             OldNetworkRequest::GetEndpoints(ref _request) => {}
             _ => {}
         }
@@ -16,17 +16,12 @@
 //! Sub-module managing the inter-modules requests sent.
 
 use crate::WS2Pv1Module;
-use durs_message::requests::{BlockchainRequest, DursReqContent};
+use durs_message::requests::{BlockchainRequest, DursReqContent, MemPoolRequest};
 use durs_message::*;
 use durs_module::{DursModule, ModuleReqId, ModuleRole, RouterThreadMessage};
 
 pub fn send_dal_request(ws2p_module: &mut WS2Pv1Module, req: &BlockchainRequest) -> ModuleReqId {
-    ws2p_module.count_dal_requests += 1;
-    if ws2p_module.count_dal_requests == std::u32::MAX {
-        ws2p_module.count_dal_requests = 0;
-    }
-
-    let req_id = ModuleReqId(ws2p_module.count_dal_requests);
+    let req_id = ws2p_module.requests.next_dal_req_id();
 
     ws2p_module
         .router_sender
@@ -40,3 +35,19 @@ pub fn send_dal_request(ws2p_module: &mut WS2Pv1Module, req: &BlockchainRequest)
 
     req_id
 }
+
+pub fn send_mempool_request(ws2p_module: &mut WS2Pv1Module, req: MemPoolRequest) -> ModuleReqId {
+    let req_id = ws2p_module.requests.next_dal_req_id();
+
+    ws2p_module
+        .router_sender
+        .send(RouterThreadMessage::ModuleMessage(DursMsg::Request {
+            req_from: WS2Pv1Module::name(),
+            req_to: ModuleRole::WotPool,
+            req_id,
+            req_content: DursReqContent::MemPoolRequest(req),
+        }))
+        .expect("Fail to send message to router !");
+
+    req_id
+}
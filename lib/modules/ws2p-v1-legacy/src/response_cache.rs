@@ -0,0 +1,193 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Cache the responses to the blocks requests received from other nodes.
+
+use crate::constants::*;
+use crate::ws_connections::requests::WS2Pv1ReqBody;
+use durs_common_tools::timer::elapsed_since;
+use durs_module::ModuleReqId;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Cached response
+#[derive(Debug)]
+struct CachedResponse {
+    /// Response body serialized in JSON
+    raw_body: Arc<str>,
+    /// Time at which the response was cached
+    cached_at: Instant,
+}
+
+/// Responses to the requests that hit the blockchain database, keyed by request body.
+/// The whole cache is invalidated when the current block changes.
+#[derive(Debug)]
+pub struct ResponseCache {
+    /// Cached responses
+    responses: HashMap<WS2Pv1ReqBody, CachedResponse>,
+    /// Requests forwarded to the blockchain module whose response will be cached
+    pending: HashMap<ModuleReqId, WS2Pv1ReqBody>,
+    /// Time to live of a cached response
+    ttl: Duration,
+    /// Maximum number of cached responses
+    max_entries: usize,
+}
+
+impl Default for ResponseCache {
+    fn default() -> Self {
+        ResponseCache::new(
+            Duration::from_secs(*WS2P_V1_RESPONSE_CACHE_TTL_IN_SECS),
+            *WS2P_V1_RESPONSE_CACHE_MAX_ENTRIES,
+        )
+    }
+}
+
+impl ResponseCache {
+    /// Create an empty cache
+    pub fn new(ttl: Duration, max_entries: usize) -> Self {
+        ResponseCache {
+            responses: HashMap::new(),
+            pending: HashMap::new(),
+            ttl,
+            max_entries,
+        }
+    }
+    /// Only the blocks requests are cached, pending wot datas change at any time
+    fn is_cacheable(req_body: &WS2Pv1ReqBody) -> bool {
+        match *req_body {
+            WS2Pv1ReqBody::GetCurrent
+            | WS2Pv1ReqBody::GetBlock { .. }
+            | WS2Pv1ReqBody::GetBlocks { .. } => true,
+            WS2Pv1ReqBody::GetRequirementsPending { .. } => false,
+        }
+    }
+    /// Get the cached response to a request, if it isn't expired
+    pub fn get(&self, req_body: &WS2Pv1ReqBody, now: Instant) -> Option<Arc<str>> {
+        self.responses
+            .get(req_body)
+            .filter(|cached| elapsed_since(now, cached.cached_at) <= self.ttl)
+            .map(|cached| cached.raw_body.clone())
+    }
+    /// Remember the body of a request forwarded to the blockchain module
+    pub fn expect(&mut self, module_req_id: ModuleReqId, req_body: WS2Pv1ReqBody) {
+        if Self::is_cacheable(&req_body) {
+            self.pending.insert(module_req_id, req_body);
+        }
+    }
+    /// Cache the response to a request forwarded to the blockchain module
+    pub fn store(&mut self, module_req_id: &ModuleReqId, raw_body: Arc<str>, now: Instant) {
+        if let Some(req_body) = self.pending.remove(module_req_id) {
+            if self.responses.len() >= self.max_entries {
+                self.evict(now);
+            }
+            self.responses.insert(
+                req_body,
+                CachedResponse {
+                    raw_body,
+                    cached_at: now,
+                },
+            );
+        }
+    }
+    /// Remove the expired responses, or the oldest one if none is expired
+    fn evict(&mut self, now: Instant) {
+        let ttl = self.ttl;
+        self.responses
+            .retain(|_, cached| elapsed_since(now, cached.cached_at) <= ttl);
+        if self.responses.len() >= self.max_entries {
+            let oldest = self
+                .responses
+                .iter()
+                .min_by_key(|(_, cached)| cached.cached_at)
+                .map(|(req_body, _)| *req_body);
+            if let Some(oldest) = oldest {
+                self.responses.remove(&oldest);
+            }
+        }
+    }
+    /// Forget all responses, including those of the requests in progress
+    /// that may have been computed with the previous current block
+    pub fn invalidate(&mut self) {
+        self.responses.clear();
+        self.pending.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dubp_common_doc::BlockNumber;
+
+    fn get_block(number: u32) -> WS2Pv1ReqBody {
+        WS2Pv1ReqBody::GetBlock {
+            number: BlockNumber(number),
+        }
+    }
+
+    #[test]
+    fn cache_ttl_and_invalidation() {
+        let now = Instant::now();
+        let mut cache = ResponseCache::new(Duration::from_secs(10), 8);
+
+        cache.expect(ModuleReqId(1), WS2Pv1ReqBody::GetCurrent);
+        assert_eq!(None, cache.get(&WS2Pv1ReqBody::GetCurrent, now));
+        cache.store(&ModuleReqId(1), Arc::from("{}"), now);
+        assert_eq!(
+            Some(Arc::from("{}")),
+            cache.get(&WS2Pv1ReqBody::GetCurrent, now + Duration::from_secs(10))
+        );
+        assert_eq!(
+            None,
+            cache.get(&WS2Pv1ReqBody::GetCurrent, now + Duration::from_secs(11))
+        );
+
+        // A response computed before the invalidation is not cached
+        cache.expect(ModuleReqId(2), get_block(1));
+        cache.invalidate();
+        assert_eq!(None, cache.get(&WS2Pv1ReqBody::GetCurrent, now));
+        cache.store(&ModuleReqId(2), Arc::from("{}"), now);
+        assert_eq!(None, cache.get(&get_block(1), now));
+    }
+
+    #[test]
+    fn cache_ignores_wot_requests() {
+        let now = Instant::now();
+        let mut cache = ResponseCache::new(Duration::from_secs(10), 8);
+        let req_body = WS2Pv1ReqBody::GetRequirementsPending { min_cert: 5 };
+
+        cache.expect(ModuleReqId(1), req_body);
+        cache.store(&ModuleReqId(1), Arc::from("{}"), now);
+        assert_eq!(None, cache.get(&req_body, now));
+    }
+
+    #[test]
+    fn cache_evicts_oldest_response() {
+        let now = Instant::now();
+        let mut cache = ResponseCache::new(Duration::from_secs(10), 2);
+
+        for number in 0..3 {
+            cache.expect(ModuleReqId(number), get_block(number));
+            cache.store(
+                &ModuleReqId(number),
+                Arc::from("{}"),
+                now + Duration::from_secs(u64::from(number)),
+            );
+        }
+        assert_eq!(None, cache.get(&get_block(0), now));
+        assert!(cache.get(&get_block(1), now).is_some());
+        assert!(cache.get(&get_block(2), now).is_some());
+    }
+}
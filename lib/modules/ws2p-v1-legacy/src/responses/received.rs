@@ -15,8 +15,26 @@
 
 //! Sub-module managing the inter-modules responses received.
 
-use crate::ws_connections::responses::{WS2Pv1ReqRes, WS2Pv1ReqResBody};
+use crate::ws_connections::responses::{
+    raw_json_response, WS2Pv1IdentityRequirementsPending, WS2Pv1ReqResBody,
+};
 use crate::*;
+use std::sync::Arc;
+
+/// Send the response to a request received from another node, and cache it if it's cacheable
+fn send_ws2p_response(ws2p_module: &mut WS2Pv1Module, req_id: ModuleReqId, body: WS2Pv1ReqResBody) {
+    if let Some(ws2p_req_full_id) = ws2p_module.requests.take_received(&req_id) {
+        let raw_body: Arc<str> = Arc::from(body.into_raw_json());
+        ws2p_module
+            .responses_cache
+            .store(&req_id, raw_body.clone(), Instant::now());
+        ws_connections::responses::sent::send_raw_response(
+            ws2p_module,
+            ws2p_req_full_id.from,
+            raw_json_response(ws2p_req_full_id.req_id, &raw_body),
+        );
+    }
+}
 
 pub fn receive_response(
     ws2p_module: &mut WS2Pv1Module,
@@ -31,8 +49,8 @@ pub fn receive_response(
                     ws2p_module.current_blockstamp
                 );
                 ws2p_module.current_blockstamp = *current_blockstamp_;
-                if ws2p_module.my_head.is_none() {
-                    ws2p_module.my_head = Some(heads::generate_my_head(
+                if ws2p_module.heads.my_head().is_none() {
+                    ws2p_module.heads.set_my_head(heads::generate_my_head(
                         &ws2p_module.my_signator,
                         ws2p_module.node_id,
                         ws2p_module.soft_name,
@@ -41,30 +59,23 @@ pub fn receive_response(
                         None,
                     ));
                 }
-                let event = NetworkEvent::ReceiveHeads(vec![unwrap!(ws2p_module.my_head.clone())]);
+                let event =
+                    NetworkEvent::ReceiveHeads(vec![unwrap!(ws2p_module.heads.my_head()).clone()]);
                 events::sent::send_network_event(ws2p_module, event);
+                self_peer::update_self_peer(ws2p_module);
             }
             BlockchainResponse::UIDs(ref uids) => {
                 // Add uids to heads
-                for head in ws2p_module.heads_cache.values_mut() {
-                    if let Some(uid_option) = uids.get(&head.pubkey()) {
-                        if let Some(ref uid) = *uid_option {
-                            head.set_uid(uid);
-                            ws2p_module
-                                .uids_cache
-                                .insert(head.pubkey(), uid.to_string());
-                        } else {
-                            ws2p_module.uids_cache.remove(&head.pubkey());
-                        }
-                    }
-                }
+                ws2p_module.heads.update_uids(uids);
                 // Resent heads to other modules
-                let event =
-                    NetworkEvent::ReceiveHeads(ws2p_module.heads_cache.values().cloned().collect());
+                let event = NetworkEvent::ReceiveHeads(
+                    ws2p_module.heads.heads().values().cloned().collect(),
+                );
                 events::sent::send_network_event(ws2p_module, event);
                 // Resent to other modules connections that match receive uids
                 let events = ws2p_module
-                    .ws2p_endpoints
+                    .connections
+                    .endpoints()
                     .iter()
                     .filter_map(|(node_full_id, DbEndpoint { ep, state, .. })| {
                         if let Some(uid_option) = uids.get(&node_full_id.1) {
@@ -82,20 +93,42 @@ pub fn receive_response(
                 events::sent::send_network_events(ws2p_module, events);
             }
             BlockchainResponse::CurrentBlock(ref block_box, _blockstamp) => {
-                if let Some(ws2p_req_full_id) =
-                    ws2p_module.pending_received_requests.remove(&req_id)
-                {
-                    ws_connections::responses::sent::send_response(
-                        ws2p_module,
-                        ws2p_req_full_id.from,
-                        WS2Pv1ReqRes {
-                            req_id: ws2p_req_full_id.req_id,
-                            body: WS2Pv1ReqResBody::GetCurrent(block_box.deref().clone()),
-                        },
-                    )
-                }
+                send_ws2p_response(
+                    ws2p_module,
+                    req_id,
+                    WS2Pv1ReqResBody::GetCurrent(block_box.deref().clone()),
+                );
+            }
+            BlockchainResponse::BlockByNumber(ref block_box) => {
+                send_ws2p_response(
+                    ws2p_module,
+                    req_id,
+                    WS2Pv1ReqResBody::GetBlock(block_box.deref().clone()),
+                );
+            }
+            BlockchainResponse::Chunk(ref blocks) => {
+                send_ws2p_response(
+                    ws2p_module,
+                    req_id,
+                    WS2Pv1ReqResBody::GetBlocks(blocks.clone()),
+                );
             }
             _ => {} // Others BlockchainResponse variants
         }
+    } else if let DursResContent::MemPoolResponse(MemPoolResponse::AllPendingIdentities(
+        _,
+        ref pending_identities,
+    )) = *res_content
+    {
+        send_ws2p_response(
+            ws2p_module,
+            req_id,
+            WS2Pv1ReqResBody::GetRequirementsPending {
+                identities: pending_identities
+                    .values()
+                    .map(WS2Pv1IdentityRequirementsPending::from)
+                    .collect(),
+            },
+        );
     }
 }
@@ -0,0 +1,329 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Peer card of the local node.
+//!
+//! The peer card is generated from the endpoints declared by all the modules of the local node.
+//! It is re-signed with the current blockstamp and broadcast each time these endpoints change,
+//! in particular when the public IP detected by the external check changes.
+//! It is also periodically re-signed with the current blockstamp and re-emitted.
+
+use crate::serializers::peer::peer_message;
+use crate::*;
+use dup_crypto::keys::text_signable::TextSignable;
+use durs_common_tools::fns::thread::spawn_child;
+use durs_network_documents::network_peer::{PeerCard, PeerCardV10};
+use std::net::IpAddr;
+use std::process::Command;
+
+#[derive(Debug, Clone, Default)]
+/// Peer card of the local node
+pub struct SelfPeer {
+    /// Endpoints declared by the modules of the local node (unknown until the router sends them)
+    modules_endpoints: Option<Vec<EndpointEnum>>,
+    /// Public IP detected by the external check
+    public_ip: Option<IpAddr>,
+    /// Port declared by the WS2P server and port mapped on the gateway instead
+    public_port: Option<(u16, u16)>,
+    /// Current signed peer card
+    peer_card: Option<PeerCardV10>,
+}
+
+impl SelfPeer {
+    /// Current signed peer card
+    pub fn peer_card(&self) -> Option<&PeerCardV10> {
+        self.peer_card.as_ref()
+    }
+    /// Endpoints to declare in the peer card
+    fn endpoints(&self) -> Option<Vec<EndpointEnum>> {
+        self.modules_endpoints.as_ref().map(|endpoints| {
+            endpoints
+                .iter()
+                .map(|ep| {
+                    let ep = match self.public_ip {
+                        Some(public_ip) => with_public_ip(ep, public_ip),
+                        None => ep.clone(),
+                    };
+                    match self.public_port {
+                        Some((declared_port, mapped_port)) => {
+                            with_public_port(&ep, declared_port, mapped_port)
+                        }
+                        None => ep,
+                    }
+                })
+                .collect()
+        })
+    }
+}
+
+/// Replace the host of the endpoint by the public IP if the host is an IP of the same family
+fn with_public_ip(ep: &EndpointEnum, public_ip: IpAddr) -> EndpointEnum {
+    if let EndpointEnum::V1(ep_v1) = ep {
+        match ep_v1.host.parse::<IpAddr>() {
+            Ok(host_ip) if host_ip != public_ip && host_ip.is_ipv4() == public_ip.is_ipv4() => {
+                let raw_endpoint = ep_v1
+                    .raw_endpoint
+                    .split(' ')
+                    .map(|field| {
+                        if field == ep_v1.host {
+                            public_ip.to_string()
+                        } else {
+                            field.to_owned()
+                        }
+                    })
+                    .collect::<Vec<String>>()
+                    .join(" ");
+                if let Ok(new_ep_v1) = EndpointV1::parse_from_raw(
+                    &raw_endpoint,
+                    ep_v1.issuer,
+                    ep_v1.status,
+                    ep_v1.last_check,
+                ) {
+                    return EndpointEnum::V1(new_ep_v1);
+                }
+            }
+            _ => {}
+        }
+    }
+    ep.clone()
+}
+
+/// Replace the port of the WS2P endpoint declared with the port of the WS2P server by the mapped port
+fn with_public_port(ep: &EndpointEnum, declared_port: u16, mapped_port: u16) -> EndpointEnum {
+    if let EndpointEnum::V1(ep_v1) = ep {
+        if ep_v1.api.0 == WS2P_API && ep_v1.port == usize::from(declared_port) {
+            let declared_port = declared_port.to_string();
+            let raw_endpoint = ep_v1
+                .raw_endpoint
+                .split(' ')
+                .map(|field| {
+                    if field == declared_port {
+                        mapped_port.to_string()
+                    } else {
+                        field.to_owned()
+                    }
+                })
+                .collect::<Vec<String>>()
+                .join(" ");
+            if let Ok(new_ep_v1) = EndpointV1::parse_from_raw(
+                &raw_endpoint,
+                ep_v1.issuer,
+                ep_v1.status,
+                ep_v1.last_check,
+            ) {
+                return EndpointEnum::V1(new_ep_v1);
+            }
+        }
+    }
+    ep.clone()
+}
+
+/// Receive the endpoints declared by the modules of the local node
+pub fn receive_modules_endpoints(ws2p_module: &mut WS2Pv1Module, endpoints: &[EndpointEnum]) {
+    ws2p_module.self_peer.modules_endpoints = Some(endpoints.to_vec());
+    update_self_peer(ws2p_module);
+}
+
+/// Receive the public IP detected by the external check
+pub fn receive_public_ip(ws2p_module: &mut WS2Pv1Module, public_ip: IpAddr) {
+    if ws2p_module.self_peer.public_ip != Some(public_ip) {
+        info!("WS2P: public IP is {}", public_ip);
+        ws2p_module.self_peer.public_ip = Some(public_ip);
+        update_self_peer(ws2p_module);
+    }
+}
+
+/// Receive the port mapped on the gateway for the port declared by the WS2P server
+pub fn receive_public_port(ws2p_module: &mut WS2Pv1Module, declared_port: u16, mapped_port: u16) {
+    let public_port = if declared_port == mapped_port {
+        None
+    } else {
+        Some((declared_port, mapped_port))
+    };
+    if ws2p_module.self_peer.public_port != public_port {
+        if public_port.is_some() {
+            info!(
+                "WS2P: public port {} is replaced by mapped port {}",
+                declared_port, mapped_port
+            );
+        }
+        ws2p_module.self_peer.public_port = public_port;
+        update_self_peer(ws2p_module);
+    }
+}
+
+/// Re-sign the peer card and broadcast it if the endpoints have changed
+pub fn update_self_peer(ws2p_module: &mut WS2Pv1Module) {
+    let endpoints = if let Some(endpoints) = ws2p_module.self_peer.endpoints() {
+        endpoints
+    } else {
+        return;
+    };
+    let currency_name = if let Some(ref currency_name) = ws2p_module.conf.currency {
+        currency_name.clone()
+    } else {
+        return;
+    };
+    // The blockstamp of the peer card must be known by the other nodes
+    if ws2p_module.current_blockstamp == Blockstamp::default() {
+        return;
+    }
+    if let Some(ref peer_card) = ws2p_module.self_peer.peer_card {
+        if peer_card.endpoints == endpoints {
+            return;
+        }
+    }
+    sign_and_send_self_peer(ws2p_module, currency_name, endpoints);
+}
+
+/// Re-sign the peer card with the current blockstamp and re-emit it
+pub fn renew_self_peer(ws2p_module: &mut WS2Pv1Module) {
+    let (currency_name, endpoints) = match ws2p_module.self_peer.peer_card {
+        Some(ref peer_card) if peer_card.blockstamp != ws2p_module.current_blockstamp => {
+            (peer_card.currency_name.clone(), peer_card.endpoints.clone())
+        }
+        Some(_) => {
+            send_self_peer_to_all(ws2p_module);
+            return;
+        }
+        None => return,
+    };
+    sign_and_send_self_peer(ws2p_module, currency_name, endpoints);
+}
+
+fn sign_and_send_self_peer(
+    ws2p_module: &mut WS2Pv1Module,
+    currency_name: CurrencyName,
+    endpoints: Vec<EndpointEnum>,
+) {
+    let mut peer_card = PeerCardV10 {
+        currency_name,
+        blockstamp: ws2p_module.current_blockstamp,
+        issuer: ws2p_module.key_pair.public_key(),
+        endpoints,
+        sig: None,
+    };
+    if let Err(e) = peer_card.sign(&ws2p_module.my_signator) {
+        error!("WS2P: fail to sign self peer card: {:?}", e);
+        return;
+    }
+    info!(
+        "WS2P: new self peer card at blockstamp {}",
+        peer_card.blockstamp
+    );
+    ws2p_module.self_peer.peer_card = Some(peer_card.clone());
+
+    send_self_peer_to_all(ws2p_module);
+    events::sent::send_network_event(
+        ws2p_module,
+        NetworkEvent::NewSelfPeer(PeerCard::V10(peer_card)),
+    );
+}
+
+/// Push self peer card to an established connection
+pub fn send_self_peer(ws2p_module: &WS2Pv1Module, node_full_id: &NodeFullId) {
+    if let Some(peer_card) = ws2p_module.self_peer.peer_card() {
+        if let Some(websocket) = ws2p_module.connections.websocket(node_full_id) {
+            let message = peer_message(peer_card.clone()).to_string();
+            if let Err(e) = websocket.0.send(Message::text(message)) {
+                debug!("WS2P: fail to send self peer to {}: {}", node_full_id, e);
+            }
+        }
+    }
+}
+
+/// Push self peer card to all established connections
+fn send_self_peer_to_all(ws2p_module: &WS2Pv1Module) {
+    for node_full_id in ws2p_module.connections.connected_nodes() {
+        if ws2p_module.connections.is_established(node_full_id) {
+            send_self_peer(ws2p_module, node_full_id);
+        }
+    }
+}
+
+/// Run the external check of the public IP in a dedicated thread
+pub fn check_public_ip(ws2p_module: &WS2Pv1Module) {
+    let command = if let Some(ref command) = ws2p_module.conf.public_ip_command {
+        command.clone()
+    } else {
+        return;
+    };
+    let sender = ws2p_module.main_thread_channel.0.clone();
+    spawn_child("public_ip", move || {
+        match Command::new("sh").arg("-c").arg(&command).output() {
+            Ok(output) if output.status.success() => {
+                let stdout = String::from_utf8_lossy(&output.stdout);
+                match stdout.trim().parse::<IpAddr>() {
+                    Ok(public_ip) => {
+                        let _ = sender.send(WS2PThreadSignal::PublicIp(public_ip));
+                    }
+                    Err(_) => warn!(
+                        "WS2P: public IP check returned an invalid IP: '{}'",
+                        stdout.trim()
+                    ),
+                }
+            }
+            Ok(output) => warn!(
+                "WS2P: public IP check failed ({}): {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+            Err(e) => warn!("WS2P: fail to run public IP check: {}", e),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn endpoint(raw_endpoint: &str) -> EndpointEnum {
+        EndpointEnum::V1(unwrap!(EndpointV1::parse_from_raw(
+            raw_endpoint,
+            PubKey::default(),
+            0,
+            0
+        )))
+    }
+
+    #[test]
+    fn test_self_peer_endpoints_with_public_ip() {
+        let mut self_peer = SelfPeer::default();
+        assert_eq!(None, self_peer.endpoints());
+
+        self_peer.modules_endpoints = Some(vec![
+            endpoint("WS2P 11111111 84.16.72.210 20901"),
+            endpoint("WS2P 11111111 g1.durs.info 443 ws2p"),
+        ]);
+        self_peer.public_ip = Some(unwrap!("84.16.72.211".parse()));
+        assert_eq!(
+            Some(vec![
+                endpoint("WS2P 11111111 84.16.72.211 20901"),
+                endpoint("WS2P 11111111 g1.durs.info 443 ws2p"),
+            ]),
+            self_peer.endpoints()
+        );
+
+        // An IPv6 address does not replace an IPv4 one
+        self_peer.public_ip = Some(unwrap!("2001:db8::1".parse()));
+        assert_eq!(
+            Some(vec![
+                endpoint("WS2P 11111111 84.16.72.210 20901"),
+                endpoint("WS2P 11111111 g1.durs.info 443 ws2p"),
+            ]),
+            self_peer.endpoints()
+        );
+    }
+}
@@ -0,0 +1,131 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Sub-module that serialize documents into WS2Pv1 push messages
+
+use super::IntoWS2Pv1Json;
+use dubp_block_doc::DocumentDUBPStr;
+use dubp_user_docs::documents::certification::CertificationDocumentStringified;
+use dubp_user_docs::documents::identity::IdentityDocumentStringified;
+use dubp_user_docs::documents::membership::MembershipDocumentStringified;
+use dubp_user_docs::documents::UserDocumentDUBPStr;
+
+/// Serialize document into a WS2Pv1 push message.
+/// Returns `None` for documents that WS2Pv1 does not push (revocations).
+pub fn document_message(document: DocumentDUBPStr) -> Option<serde_json::Value> {
+    let (name, field, json_doc) = match document {
+        DocumentDUBPStr::Block(block) => ("BLOCK", "block", block.into_ws2p_v1_json()),
+        DocumentDUBPStr::UserDocument(user_doc) => match user_doc {
+            UserDocumentDUBPStr::Transaction(tx) => {
+                ("TRANSACTION", "transaction", tx.into_ws2p_v1_json())
+            }
+            UserDocumentDUBPStr::Identity(idty) => ("IDENTITY", "identity", identity_json(idty)),
+            UserDocumentDUBPStr::Membership(ms) => {
+                ("MEMBERSHIP", "membership", membership_json(ms))
+            }
+            UserDocumentDUBPStr::Certification(cert) => {
+                ("CERTIFICATION", "certification", certification_json(*cert))
+            }
+            UserDocumentDUBPStr::Revocation(_) => return None,
+        },
+    };
+    Some(json!({
+        "body": {
+            "name": name,
+            field: json_doc
+        }
+    }))
+}
+
+fn identity_json(idty: IdentityDocumentStringified) -> serde_json::Value {
+    match idty {
+        IdentityDocumentStringified::V10(idty) => json!({
+            "version": 10,
+            "currency": idty.currency,
+            "pubkey": idty.issuer,
+            "uid": idty.username,
+            "buid": idty.blockstamp,
+            "sig": idty.signature,
+        }),
+    }
+}
+
+fn membership_json(ms: MembershipDocumentStringified) -> serde_json::Value {
+    match ms {
+        MembershipDocumentStringified::V10(ms) => json!({
+            "version": 10,
+            "currency": ms.currency,
+            "issuer": ms.issuer,
+            "membership": ms.membership,
+            "userid": ms.username,
+            "certts": ms.identity_blockstamp,
+            "block": ms.blockstamp,
+            "signature": ms.signature,
+        }),
+    }
+}
+
+fn certification_json(cert: CertificationDocumentStringified) -> serde_json::Value {
+    match cert {
+        CertificationDocumentStringified::V10(cert) => json!({
+            "version": 10,
+            "currency": cert.currency,
+            "issuer": cert.issuer,
+            "idty_issuer": cert.target,
+            "idty_uid": cert.identity_username,
+            "idty_buid": cert.identity_blockstamp,
+            "idty_sig": cert.identity_sig,
+            "buid": cert.blockstamp,
+            "sig": cert.signature,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dubp_user_docs::documents::identity::IdentityDocumentV10Stringified;
+
+    #[test]
+    fn identity_push_message() {
+        let idty = IdentityDocumentV10Stringified {
+            currency: "g1".to_owned(),
+            username: "tic".to_owned(),
+            blockstamp: "0-E3B0C44298FC1C149AFBF4C8996FB92427AE41E4649B934CA495991B7852B855"
+                .to_owned(),
+            issuer: "DNann1Lh55eZMEDXeYt59bzHbA3NJR46DeQYCS2qQdLV".to_owned(),
+            signature: "sig".to_owned(),
+        };
+        let message = document_message(DocumentDUBPStr::UserDocument(
+            UserDocumentDUBPStr::Identity(IdentityDocumentStringified::V10(idty)),
+        ));
+        assert_eq!(
+            Some(json!({
+                "body": {
+                    "name": "IDENTITY",
+                    "identity": {
+                        "version": 10,
+                        "currency": "g1",
+                        "pubkey": "DNann1Lh55eZMEDXeYt59bzHbA3NJR46DeQYCS2qQdLV",
+                        "uid": "tic",
+                        "buid": "0-E3B0C44298FC1C149AFBF4C8996FB92427AE41E4649B934CA495991B7852B855",
+                        "sig": "sig",
+                    }
+                }
+            })),
+            message
+        );
+    }
+}
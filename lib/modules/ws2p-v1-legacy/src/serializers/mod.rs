@@ -17,9 +17,11 @@
 
 pub mod block;
 pub mod certification;
+pub mod document;
 pub mod head;
 pub mod identity;
 pub mod membership;
+pub mod peer;
 pub mod revoked;
 pub mod transaction;
 
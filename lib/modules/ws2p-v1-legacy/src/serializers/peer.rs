@@ -0,0 +1,46 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Sub-module that serialize peer card into WS2Pv1 json format
+
+use super::IntoWS2Pv1Json;
+use durs_network_documents::network_endpoint::EndpointEnum;
+use durs_network_documents::network_peer::PeerCardV10;
+
+impl IntoWS2Pv1Json for PeerCardV10 {
+    fn into_ws2p_v1_json(self) -> serde_json::Value {
+        json!({
+            "version": 10,
+            "currency": self.currency_name.0,
+            "pubkey": self.issuer.to_string(),
+            "block": self.blockstamp.to_string(),
+            "endpoints": self.endpoints.iter().map(|ep| match ep {
+                EndpointEnum::V1(ep_v1) => ep_v1.raw_endpoint.clone(),
+                EndpointEnum::V2(ep_v2) => ep_v2.to_string(),
+            }).collect::<Vec<String>>(),
+            "signature": self.sig.map(|sig| sig.to_string()).unwrap_or_default(),
+        })
+    }
+}
+
+/// Serialize peer card into a WS2Pv1 push message
+pub fn peer_message(peer: PeerCardV10) -> serde_json::Value {
+    json!({
+        "body": {
+            "name": "PEER",
+            "peer": peer.into_ws2p_v1_json()
+        }
+    })
+}
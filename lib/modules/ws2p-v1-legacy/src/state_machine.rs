@@ -0,0 +1,565 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Event-driven state machine running the WS2Pv1 module.
+
+use crate::*;
+use durs_common_tools::timer::{
+    elapsed_since, LastOccurrence, MonotonicClock, StdMonotonicClock, Timer,
+};
+use std::time::{Duration, Instant};
+
+/// Periodic action of the module
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimerAction {
+    /// Write known endpoints, network map and network metrics
+    SaveEndpoints,
+    /// Check the public IP of the node
+    CheckPublicIp,
+    /// Re-sign and re-emit the self peer card
+    RenewSelfPeer,
+    /// Print the general state and request the current blockstamp
+    PrintState,
+    /// Try to connect to known endpoints
+    ConnectionWave,
+    /// Request pending identities to all connections
+    RequestPendingIdentities,
+}
+
+/// Module state needed to know which periodic actions are due
+#[derive(Clone, Copy, Debug)]
+pub struct TimersContext {
+    /// The outgoing connections quota is reached
+    pub outgoing_quota_reached: bool,
+    /// Interval between 2 public IP checks (in seconds), if enabled
+    pub public_ip_check_interval: Option<u64>,
+}
+
+/// Timers of the periodic actions of the module
+#[derive(Clone, Copy, Debug)]
+pub struct Ws2pTimers {
+    start_time: Instant,
+    connecting_wave: LastOccurrence,
+    state_print: Timer,
+    endpoints_write: Timer,
+    identities_request: LastOccurrence,
+    public_ip_check: LastOccurrence,
+    self_peer_emission: Timer,
+}
+
+impl Ws2pTimers {
+    /// Instantiate timers of a module started at `start_time`
+    pub fn new(start_time: Instant, now: Instant) -> Self {
+        Ws2pTimers {
+            start_time,
+            connecting_wave: LastOccurrence::at(now),
+            state_print: Timer::new(now, Duration::from_secs(*WS2P_GENERAL_STATE_INTERVAL)),
+            endpoints_write: Timer::new(
+                now,
+                Duration::from_secs(*DURATION_BETWEEN_2_ENDPOINTS_SAVING),
+            ),
+            identities_request: LastOccurrence::never(),
+            public_ip_check: LastOccurrence::never(),
+            self_peer_emission: Timer::new(
+                now,
+                Duration::from_secs(*WS2P_SELF_PEER_REEMISSION_INTERVAL_IN_SECS),
+            ),
+        }
+    }
+    /// Get the periodic actions due at `now` and reset their timers
+    pub fn due_actions(&mut self, now: Instant, context: TimersContext) -> Vec<TimerAction> {
+        let mut actions = Vec::new();
+        if self.endpoints_write.reset_if_expired(now) {
+            actions.push(TimerAction::SaveEndpoints);
+        }
+        if let Some(public_ip_check_interval) = context.public_ip_check_interval {
+            if self
+                .public_ip_check
+                .is_older_than(now, Duration::from_secs(public_ip_check_interval))
+            {
+                self.public_ip_check.record(now);
+                actions.push(TimerAction::CheckPublicIp);
+            }
+        }
+        if self.self_peer_emission.reset_if_expired(now) {
+            actions.push(TimerAction::RenewSelfPeer);
+        }
+        if self.state_print.reset_if_expired(now) {
+            actions.push(TimerAction::PrintState);
+            let since_start = elapsed_since(now, self.start_time);
+            // New WS2P connection wave
+            if !context.outgoing_quota_reached
+                && (self
+                    .connecting_wave
+                    .is_older_than(now, Duration::from_secs(*WS2P_OUTCOMING_INTERVAL))
+                    || (self.connecting_wave.is_older_than(
+                        now,
+                        Duration::from_secs(*WS2P_OUTCOMING_INTERVAL_AT_STARTUP),
+                    ) && since_start < Duration::from_secs(*WS2P_OUTCOMING_INTERVAL)))
+            {
+                self.connecting_wave.record(now);
+                actions.push(TimerAction::ConnectionWave);
+            }
+            // Request pending_identities from network
+            if self.identities_request.is_older_than(
+                now,
+                Duration::from_secs(*PENDING_IDENTITIES_REQUEST_INTERVAL),
+            ) && since_start > Duration::from_secs(10)
+            {
+                self.identities_request.record(now);
+                actions.push(TimerAction::RequestPendingIdentities);
+            }
+        }
+        actions
+    }
+}
+
+/// State of the main loop
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Ws2pState {
+    /// The module handles messages
+    Running,
+    /// The module received the stop order
+    Stopped,
+}
+
+/// Event-driven state machine of the WS2Pv1 module
+#[derive(Debug)]
+pub struct Ws2pStateMachine<C: MonotonicClock = StdMonotonicClock> {
+    module: WS2Pv1Module,
+    clock: C,
+    timers: Ws2pTimers,
+    state: Ws2pState,
+    endpoints_to_update_status: HashMap<NodeFullId, Instant>,
+}
+
+impl<C: MonotonicClock> Ws2pStateMachine<C> {
+    /// Instantiate the state machine of a module started at `start_time`
+    pub fn new(module: WS2Pv1Module, clock: C, start_time: Instant) -> Self {
+        let timers = Ws2pTimers::new(start_time, clock.now());
+        Ws2pStateMachine {
+            module,
+            clock,
+            timers,
+            state: Ws2pState::Running,
+            endpoints_to_update_status: HashMap::new(),
+        }
+    }
+    /// Current state
+    pub fn state(&self) -> Ws2pState {
+        self.state
+    }
+    /// Handle signals of the module channel until the stop order
+    pub fn run(mut self) {
+        while self.state == Ws2pState::Running {
+            match self
+                .module
+                .main_thread_channel
+                .1
+                .recv_timeout(Duration::from_millis(200))
+            {
+                Ok(signal) => self.on_signal(signal),
+                Err(mpsc::RecvTimeoutError::Disconnected) => {
+                    fatal_error!("Disconnected ws2p module !");
+                }
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+            }
+            if self.state == Ws2pState::Running {
+                self.tick();
+            }
+        }
+    }
+    /// Handle a signal of the module channel
+    pub fn on_signal(&mut self, signal: WS2PThreadSignal) {
+        match signal {
+            WS2PThreadSignal::DursMsg(durs_msg) => self.on_durs_msg(&durs_msg),
+            WS2PThreadSignal::PortMapping(result) => {
+                port_mapping::receive_port_mapping(&mut self.module, result)
+            }
+            WS2PThreadSignal::PublicIp(public_ip) => {
+                self_peer::receive_public_ip(&mut self.module, public_ip)
+            }
+            WS2PThreadSignal::WS2Pv1Msg(msg) => self.on_ws2p_msg(msg),
+        }
+    }
+    /// Handle a message from another durs module
+    pub fn on_durs_msg(&mut self, durs_msg: &DursMsg) {
+        match *durs_msg {
+            DursMsg::Stop => self.stop(),
+            DursMsg::ModulesEndpoints(ref endpoints) => {
+                self_peer::receive_modules_endpoints(&mut self.module, endpoints)
+            }
+            DursMsg::Request {
+                ref req_content, ..
+            } => requests::received::receive_req(&mut self.module, req_content),
+            DursMsg::Event {
+                ref event_type,
+                ref event_content,
+                ..
+            } => events::received::receive_event(&mut self.module, *event_type, event_content),
+            DursMsg::Response {
+                req_id,
+                ref res_content,
+                ..
+            } => responses::received::receive_response(&mut self.module, req_id, res_content),
+            _ => {} // Others DursMsg variants
+        }
+    }
+    /// Handle a message from another node
+    pub fn on_ws2p_msg(&mut self, msg: WS2Pv1Msg) {
+        let signal = messages::ws2p_recv_message_pretreatment(&mut self.module, msg);
+        self.on_ws2p_signal(signal);
+    }
+    /// Execute the periodic actions due now
+    pub fn tick(&mut self) {
+        let now = self.clock.now();
+        let context = TimersContext {
+            outgoing_quota_reached: self.module.connections.count_established_outgoing()
+                >= self.module.conf.outcoming_quota,
+            public_ip_check_interval: self
+                .module
+                .conf
+                .public_ip_command
+                .as_ref()
+                .map(|_| self.module.conf.public_ip_check_interval),
+        };
+        for action in self.timers.due_actions(now, context) {
+            self.execute(action);
+        }
+        if let Some(port_mapping_renewal) = self.module.port_mapping_renewal {
+            if now > port_mapping_renewal {
+                self.module.port_mapping_renewal = None;
+                port_mapping::start_port_mapping(&self.module);
+            }
+        }
+    }
+    fn execute(&mut self, action: TimerAction) {
+        match action {
+            TimerAction::SaveEndpoints => {
+                if let Err(err) = self
+                    .module
+                    .connections
+                    .save_endpoints(&self.module.ep_file_path)
+                {
+                    fatal_error!("WS2P1: Fail to write endpoints in DB : {:?}", err);
+                }
+                self.module.save_network_map();
+                self.module.save_network_metrics();
+            }
+            TimerAction::CheckPublicIp => self_peer::check_public_ip(&self.module),
+            TimerAction::RenewSelfPeer => self_peer::renew_self_peer(&mut self.module),
+            TimerAction::PrintState => {
+                info!(
+                    "WS2Pv1Module : current_blockstamp() = {:?}",
+                    self.module.current_blockstamp
+                );
+                debug!(
+                    "WS2P: {} handshakes in progress, {} queued",
+                    self.module.handshakes.in_progress(),
+                    self.module.handshakes.queued()
+                );
+                // Request current blockstamp
+                send_dal_request(&mut self.module, &BlockchainRequest::CurrentBlockstamp());
+            }
+            TimerAction::ConnectionWave => {
+                info!("Connected to know endpoints...");
+                connect_to_know_endpoints(&mut self.module);
+            }
+            TimerAction::RequestPendingIdentities => {
+                info!("get pending_identities from all connections...");
+                ws_connections::requests::sent::send_request_to_all_connections(
+                    &mut self.module,
+                    ModuleReqFullId(WS2Pv1Module::name(), ModuleReqId(0)),
+                    WS2Pv1ReqBody::GetRequirementsPending {
+                        min_cert: *PENDING_IDENTITIES_MIN_CERT,
+                    },
+                );
+            }
+        }
+    }
+    fn stop(&mut self) {
+        // Close all connections
+        for (_, ws) in self.module.connections.websockets() {
+            let _ = ws.0.close(CloseCode::Normal);
+        }
+        // Stop listening incoming connections
+        if let Some(ref server_sender) = self.module.server_sender {
+            let _ = server_sender.0.shutdown();
+        }
+        // Flush modified endpoints
+        if let Err(err) = self
+            .module
+            .connections
+            .save_endpoints(&self.module.ep_file_path)
+        {
+            error!("WS2P1: Fail to write endpoints in DB : {:?}", err);
+        }
+        self.module.save_network_map();
+        self.module.save_network_metrics();
+        self.state = Ws2pState::Stopped;
+    }
+    fn on_ws2p_signal(&mut self, signal: WS2PSignal) {
+        match signal {
+            WS2PSignal::NoConnection => {
+                log_once_per!(
+                    *WS2P_REPEATED_LOG_INTERVAL_IN_SECS,
+                    log::Level::Warn,
+                    "WS2PSignal::NoConnection"
+                );
+            }
+            WS2PSignal::ConnectionEstablished(ws2p_full_id) => {
+                let module_req_id = ModuleReqId(self.module.requests.count_awaiting() as u32);
+                let module_id = WS2Pv1Module::name();
+                debug!("WS2P: send req to: ({:?})", ws2p_full_id);
+                let _current_request_result =
+                    ws_connections::requests::sent::send_request_to_specific_node(
+                        &mut self.module,
+                        ModuleReqFullId(module_id, module_req_id),
+                        &ws2p_full_id,
+                        &WS2Pv1Request {
+                            id: WS2Pv1ReqId::random(),
+                            body: WS2Pv1ReqBody::GetCurrent,
+                        },
+                    );
+                if self.module.heads.uid(&ws2p_full_id.1).is_none() {
+                    send_dal_request(
+                        &mut self.module,
+                        &BlockchainRequest::UIDs(vec![ws2p_full_id.1]),
+                    );
+                }
+                self_peer::send_self_peer(&self.module, &ws2p_full_id);
+                let event = NetworkEvent::ConnectionStateChange(
+                    ws2p_full_id,
+                    WS2PConnectionState::Established as u32,
+                    self.module.heads.uid(&ws2p_full_id.1).cloned(),
+                    self.module.connections.connection_url(&ws2p_full_id),
+                );
+                events::sent::send_network_event(&mut self.module, event);
+            }
+            WS2PSignal::WSError(ws2p_full_id) => {
+                self.endpoints_to_update_status
+                    .insert(ws2p_full_id, self.clock.now());
+                close_connection(
+                    &mut self.module,
+                    &ws2p_full_id,
+                    WS2PCloseConnectionReason::WsError,
+                );
+                let event = NetworkEvent::ConnectionStateChange(
+                    ws2p_full_id,
+                    WS2PConnectionState::WSError as u32,
+                    self.module.heads.uid(&ws2p_full_id.1).cloned(),
+                    self.module.connections.connection_url(&ws2p_full_id),
+                );
+                events::sent::send_network_event(&mut self.module, event);
+            }
+            WS2PSignal::NegociationTimeout(ws2p_full_id) => {
+                self.endpoints_to_update_status
+                    .insert(ws2p_full_id, self.clock.now());
+                let event = NetworkEvent::ConnectionStateChange(
+                    ws2p_full_id,
+                    WS2PConnectionState::Denial as u32,
+                    self.module.heads.uid(&ws2p_full_id.1).cloned(),
+                    self.module.connections.connection_url(&ws2p_full_id),
+                );
+                events::sent::send_network_event(&mut self.module, event);
+            }
+            WS2PSignal::Timeout(ws2p_full_id) | WS2PSignal::KeepAliveTimeout(ws2p_full_id) => {
+                self.endpoints_to_update_status
+                    .insert(ws2p_full_id, self.clock.now());
+                let event = NetworkEvent::ConnectionStateChange(
+                    ws2p_full_id,
+                    WS2PConnectionState::Close as u32,
+                    self.module.heads.uid(&ws2p_full_id.1).cloned(),
+                    self.module.connections.connection_url(&ws2p_full_id),
+                );
+                events::sent::send_network_event(&mut self.module, event);
+            }
+            WS2PSignal::PeerCard(_ws2p_full_id, _peer_card, ws2p_endpoints) => {
+                //trace!("WS2PSignal::PeerCard({})", ws2p_full_id);
+                //self.send_network_event(NetworkEvent::ReceivePeers(_));
+                for ep in ws2p_endpoints {
+                    match self.module.connections.endpoint(
+                        &ep.node_full_id()
+                            .expect("WS2P: Fail to get ep.node_full_id() !"),
+                    ) {
+                        Some(_) => {}
+                        None => {
+                            if let Some(_api) = ws2p_db::string_to_api(&ep.api.0.clone()) {
+                                self.endpoints_to_update_status.insert(
+                                    ep.node_full_id()
+                                        .expect("WS2P: Fail to get ep.node_full_id() !"),
+                                    self.clock.now(),
+                                );
+                            }
+                            if cfg!(feature = "ssl") || ep.port != 443 {
+                                connect_to(&mut self.module, &ep);
+                            }
+                        }
+                    };
+                }
+            }
+            WS2PSignal::Heads(ws2p_full_id, heads) => {
+                trace!("WS2PSignal::Heads({}, {:?})", ws2p_full_id, heads.len());
+                heads::forward_heads(&mut self.module, ws2p_full_id, &heads);
+                send_dal_request(
+                    &mut self.module,
+                    &BlockchainRequest::UIDs(heads.iter().map(NetworkHead::pubkey).collect()),
+                );
+                let event = NetworkEvent::ReceiveHeads(
+                    heads
+                        .iter()
+                        .map(|head| {
+                            let mut new_head = head.clone();
+                            if let Some(uid) = self.module.heads.uid(&head.pubkey()) {
+                                new_head.set_uid(uid);
+                            }
+                            new_head
+                        })
+                        .collect(),
+                );
+                events::sent::send_network_event(&mut self.module, event);
+            }
+            WS2PSignal::Blocks(ws2p_full_id, blocks) => {
+                trace!("WS2PSignal::Blocks({})", ws2p_full_id);
+                events::sent::send_network_event(
+                    &mut self.module,
+                    NetworkEvent::ReceiveBlocks(blocks),
+                );
+            }
+            WS2PSignal::UserDocuments(ws2p_full_id, user_documents) => {
+                trace!("WS2PSignal::UserDocuments({})", ws2p_full_id);
+                events::sent::send_network_event(
+                    &mut self.module,
+                    NetworkEvent::ReceiveDocuments(user_documents),
+                );
+            }
+            WS2PSignal::Request { from, req_id, body } => {
+                ws_connections::requests::received::receive_ws2p_v1_request(
+                    &mut self.module,
+                    from,
+                    req_id,
+                    body,
+                );
+            }
+            WS2PSignal::ReqResponse(
+                module_req_full_id,
+                ws2p_req_body,
+                recipient_full_id,
+                response,
+            ) => ws_connections::responses::received::receive_response(
+                &mut self.module,
+                module_req_full_id,
+                ws2p_req_body,
+                recipient_full_id,
+                response,
+            ),
+            WS2PSignal::Empty => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(start_time: Instant, secs: u64) -> Instant {
+        start_time + Duration::from_secs(secs)
+    }
+
+    fn context(outgoing_quota_reached: bool) -> TimersContext {
+        TimersContext {
+            outgoing_quota_reached,
+            public_ip_check_interval: None,
+        }
+    }
+
+    fn connection_wave(timers: &mut Ws2pTimers, now: Instant, quota_reached: bool) -> bool {
+        timers
+            .due_actions(now, context(quota_reached))
+            .contains(&TimerAction::ConnectionWave)
+    }
+
+    #[test]
+    fn connection_wave_timer() {
+        let start_time = Instant::now();
+        let mut timers = Ws2pTimers::new(start_time, start_time);
+
+        // Waves are only checked with the state print
+        assert_eq!(
+            vec![TimerAction::PrintState],
+            timers
+                .due_actions(at(start_time, 16), context(false))
+                .into_iter()
+                .filter(|action| *action != TimerAction::RequestPendingIdentities)
+                .collect::<Vec<_>>()
+        );
+        assert!(!connection_wave(&mut timers, at(start_time, 20), false));
+        // Start-up interval
+        assert!(connection_wave(&mut timers, at(start_time, 76), false));
+        assert!(!connection_wave(&mut timers, at(start_time, 130), false));
+        assert!(connection_wave(&mut timers, at(start_time, 160), false));
+        // After the start-up phase
+        assert!(!connection_wave(&mut timers, at(start_time, 320), false));
+        assert!(!connection_wave(&mut timers, at(start_time, 440), false));
+        // Outgoing quota reached
+        assert!(!connection_wave(&mut timers, at(start_time, 470), true));
+        assert!(connection_wave(&mut timers, at(start_time, 490), false));
+    }
+
+    #[test]
+    fn endpoints_saving_timer() {
+        let start_time = Instant::now();
+        let mut timers = Ws2pTimers::new(start_time, start_time);
+        let save_endpoints = |timers: &mut Ws2pTimers, secs: u64| {
+            timers
+                .due_actions(at(start_time, secs), context(true))
+                .contains(&TimerAction::SaveEndpoints)
+        };
+
+        assert!(!save_endpoints(&mut timers, 100));
+        assert!(!save_endpoints(&mut timers, 180));
+        assert!(save_endpoints(&mut timers, 181));
+        assert!(!save_endpoints(&mut timers, 200));
+        assert!(save_endpoints(&mut timers, 362));
+        // Time source going backward
+        assert!(!save_endpoints(&mut timers, 0));
+        // Long suspend: only one saving
+        assert!(save_endpoints(&mut timers, 86_400));
+        assert!(!save_endpoints(&mut timers, 86_401));
+    }
+
+    #[test]
+    fn public_ip_check_timer() {
+        let start_time = Instant::now();
+        let mut timers = Ws2pTimers::new(start_time, start_time);
+        let public_ip_context = TimersContext {
+            outgoing_quota_reached: true,
+            public_ip_check_interval: Some(60),
+        };
+
+        assert!(!timers
+            .due_actions(at(start_time, 1), context(true))
+            .contains(&TimerAction::CheckPublicIp));
+        assert!(timers
+            .due_actions(at(start_time, 2), public_ip_context)
+            .contains(&TimerAction::CheckPublicIp));
+        assert!(!timers
+            .due_actions(at(start_time, 60), public_ip_context)
+            .contains(&TimerAction::CheckPublicIp));
+        assert!(timers
+            .due_actions(at(start_time, 63), public_ip_context)
+            .contains(&TimerAction::CheckPublicIp));
+    }
+}
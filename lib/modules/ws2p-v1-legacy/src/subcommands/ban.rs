@@ -0,0 +1,95 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! WS2P1 module subcommands ban and unban
+
+use crate::ws2p_db::{BanLevel, BanList};
+use dup_crypto::keys::PubKey;
+use durs_module::i18n::I18n;
+use std::path::Path;
+
+#[derive(Clone, Debug, StructOpt)]
+/// Ban pubkeys
+pub struct BanOpt {
+    /// Greylist instead of ban (only connect to these peers when no other endpoint is available)
+    #[structopt(long = "grey")]
+    pub grey: bool,
+    /// Public keys to ban
+    pub public_keys: Vec<PubKey>,
+}
+
+#[derive(Clone, Debug, StructOpt)]
+/// Unban pubkeys
+pub struct UnbanOpt {
+    /// Public keys to unban
+    pub public_keys: Vec<PubKey>,
+}
+
+impl BanOpt {
+    pub fn execute(self, i18n: &I18n, ban_list_file_path: &Path) {
+        let (level, list_name) = if self.grey {
+            (BanLevel::Greylisted, "greylist")
+        } else {
+            (BanLevel::Banned, "ban list")
+        };
+        update_ban_list(i18n, ban_list_file_path, |ban_list| {
+            for pubkey in self.public_keys {
+                ban_list.ban(pubkey, level);
+                println!(
+                    "{}",
+                    i18n.tr_args(
+                        "Pubkey '{}' successfully added to the {}.",
+                        &[&pubkey, &i18n.tr(list_name)]
+                    )
+                );
+            }
+        });
+    }
+}
+
+impl UnbanOpt {
+    pub fn execute(self, i18n: &I18n, ban_list_file_path: &Path) {
+        update_ban_list(i18n, ban_list_file_path, |ban_list| {
+            for pubkey in self.public_keys {
+                if ban_list.unban(&pubkey) {
+                    println!(
+                        "{}",
+                        i18n.tr_args("Pubkey '{}' successfully unbanned.", &[&pubkey])
+                    );
+                } else {
+                    println!("{}", i18n.tr_args("Pubkey '{}' is not banned.", &[&pubkey]));
+                }
+            }
+        });
+    }
+}
+
+fn update_ban_list<F: FnOnce(&mut BanList)>(i18n: &I18n, ban_list_file_path: &Path, f: F) {
+    match BanList::load(ban_list_file_path) {
+        Ok(mut ban_list) => {
+            f(&mut ban_list);
+            if let Err(e) = ban_list.save(ban_list_file_path) {
+                println!(
+                    "{}",
+                    i18n.tr_args("Fail to write ban list: {}", &[&format!("{:?}", e)])
+                );
+            }
+        }
+        Err(e) => println!(
+            "{}",
+            i18n.tr_args("Fail to read ban list: {}", &[&format!("{:?}", e)])
+        ),
+    }
+}
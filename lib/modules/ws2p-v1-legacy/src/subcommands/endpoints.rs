@@ -0,0 +1,107 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! WS2P1 module subcommand endpoints
+
+use crate::ws2p_db::JsonEndpoints;
+use durs_module::i18n::I18n;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Debug, StructOpt)]
+/// Ws2p1 endpoints subcommands
+pub enum Ws2pEndpointsSubCommands {
+    /// Export known endpoints
+    #[structopt(name = "export", setting(structopt::clap::AppSettings::ColoredHelp))]
+    Export {
+        /// Export in JSON format (the only format that can be imported)
+        #[structopt(long = "json")]
+        json: bool,
+    },
+    /// Import endpoints from a JSON file produced by the export subcommand
+    #[structopt(name = "import", setting(structopt::clap::AppSettings::ColoredHelp))]
+    Import {
+        /// File path
+        #[structopt(parse(from_os_str))]
+        file_path: PathBuf,
+    },
+}
+
+impl Ws2pEndpointsSubCommands {
+    pub fn execute(self, i18n: &I18n, ep_file_path: &Path) {
+        match self {
+            Ws2pEndpointsSubCommands::Export { json } => {
+                match JsonEndpoints::export(ep_file_path) {
+                    Ok(json_endpoints) => {
+                        if json {
+                            println!("{}", json_endpoints);
+                        } else {
+                            print_endpoints(i18n, &json_endpoints);
+                        }
+                    }
+                    Err(e) => println!(
+                        "{}",
+                        i18n.tr_args("Fail to read endpoints: {}", &[&format!("{:?}", e)])
+                    ),
+                }
+            }
+            Ws2pEndpointsSubCommands::Import { file_path } => {
+                match fs::read_to_string(file_path.as_path()) {
+                    Ok(json_endpoints) => {
+                        match JsonEndpoints::import(ep_file_path, &json_endpoints) {
+                            Ok(imported_count) => println!(
+                                "{}",
+                                i18n.tr_args(
+                                    "{} endpoints successfully imported.",
+                                    &[&imported_count]
+                                )
+                            ),
+                            Err(e) => println!(
+                                "{}",
+                                i18n.tr_args(
+                                    "Fail to import endpoints: {}",
+                                    &[&format!("{:?}", e)]
+                                )
+                            ),
+                        }
+                    }
+                    Err(e) => println!("{}", i18n.tr_args("Fail to open file: {}", &[&e])),
+                }
+            }
+        }
+    }
+}
+
+fn print_endpoints(i18n: &I18n, json_endpoints: &str) {
+    if let Ok(json_endpoints) = serde_json::from_str::<JsonEndpoints>(json_endpoints) {
+        println!(
+            "{}",
+            i18n.tr_args("{} known endpoints:", &[&json_endpoints.endpoints.len()])
+        );
+        for db_endpoint in json_endpoints.endpoints {
+            println!(
+                "{}",
+                i18n.tr_args(
+                    "{} (state: {}, last check: {})",
+                    &[
+                        &db_endpoint.ep.raw_endpoint,
+                        &format!("{:?}", db_endpoint.state),
+                        &db_endpoint.last_check
+                    ]
+                )
+            );
+        }
+    }
+}
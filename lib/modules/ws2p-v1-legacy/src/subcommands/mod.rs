@@ -15,8 +15,12 @@
 
 //! WS2P1 module subcommands
 
+pub mod ban;
+pub mod endpoints;
 pub mod prefered;
 
+use ban::{BanOpt, UnbanOpt};
+use endpoints::Ws2pEndpointsSubCommands;
 use prefered::Ws2pPreferedSubCommands;
 
 #[derive(Clone, Debug, StructOpt)]
@@ -28,4 +32,19 @@ pub enum WS2PSubCommands {
         #[structopt(subcommand)]
         subcommand: Ws2pPreferedSubCommands,
     },
+    /// Show connection metrics of the running node
+    #[structopt(name = "status", setting(structopt::clap::AppSettings::ColoredHelp))]
+    Status,
+    /// Ban or greylist pubkeys
+    #[structopt(name = "ban", setting(structopt::clap::AppSettings::ColoredHelp))]
+    Ban(BanOpt),
+    /// Remove pubkeys from the ban list and the greylist
+    #[structopt(name = "unban", setting(structopt::clap::AppSettings::ColoredHelp))]
+    Unban(UnbanOpt),
+    /// Export or import known endpoints
+    #[structopt(name = "endpoints", setting(structopt::clap::AppSettings::ColoredHelp))]
+    Endpoints {
+        #[structopt(subcommand)]
+        subcommand: Ws2pEndpointsSubCommands,
+    },
 }
@@ -16,6 +16,7 @@
 //! WS2P1 module subcommand prefered
 
 use dup_crypto::keys::PubKey;
+use durs_module::i18n::I18n;
 use std::collections::HashSet;
 use std::fs;
 use std::io::BufRead;
@@ -55,6 +56,7 @@ pub enum Ws2pPreferedSubCommands {
 impl Ws2pPreferedSubCommands {
     pub fn execute(
         self,
+        i18n: &I18n,
         module_user_conf: Option<crate::WS2PUserConf>,
     ) -> Option<crate::WS2PUserConf> {
         {
@@ -72,8 +74,11 @@ impl Ws2pPreferedSubCommands {
                     for pubkey in public_keys {
                         prefered_pubkeys.insert(pubkey.to_string());
                         println!(
-                            "Pubkey '{}' successfully added to the list of preferred keys.",
-                            pubkey
+                            "{}",
+                            i18n.tr_args(
+                                "Pubkey '{}' successfully added to the list of preferred keys.",
+                                &[&pubkey]
+                            )
                         );
                     }
                     let mut new_user_conf = module_user_conf.unwrap_or_default();
@@ -91,16 +96,31 @@ impl Ws2pPreferedSubCommands {
                                             Ok(pubkey) => {
                                                 new_prefered_pubkeys.insert(pubkey.to_string());
                                                 println!(
-                                                            "Pubkey '{}' successfully added to the list of preferred keys.",
-                                                            pubkey
-                                                        );
+                                                    "{}",
+                                                    i18n.tr_args(
+                                                        "Pubkey '{}' successfully added to the list of preferred keys.",
+                                                        &[&pubkey]
+                                                    )
+                                                );
                                             }
                                             Err(e) => {
-                                                println!("Line n°{} is invalid: {}", i + 1, e);
+                                                println!(
+                                                    "{}",
+                                                    i18n.tr_args(
+                                                        "Line n°{} is invalid: {}",
+                                                        &[&(i + 1), &e]
+                                                    )
+                                                );
                                             }
                                         },
                                         Err(e) => {
-                                            println!("Fail to read line n°{}: {}", i + 1, e);
+                                            println!(
+                                                "{}",
+                                                i18n.tr_args(
+                                                    "Fail to read line n°{}: {}",
+                                                    &[&(i + 1), &e]
+                                                )
+                                            );
                                             return module_user_conf;
                                         }
                                     }
@@ -116,19 +136,19 @@ impl Ws2pPreferedSubCommands {
                                 Some(new_user_conf)
                             }
                             Err(e) => {
-                                println!("Fail to open file: {}", e);
+                                println!("{}", i18n.tr_args("Fail to open file: {}", &[&e]));
                                 module_user_conf
                             }
                         }
                     } else {
-                        println!("Error: file note exist !");
+                        println!("{}", i18n.tr("Error: file does not exist!"));
                         module_user_conf
                     }
                 }
                 Ws2pPreferedSubCommands::Clear => {
                     if let Some(mut module_user_conf) = module_user_conf {
                         module_user_conf.prefered_pubkeys = None;
-                        println!("All preferred keys removed !");
+                        println!("{}", i18n.tr("All preferred keys removed!"));
                         Some(module_user_conf)
                     } else {
                         module_user_conf
@@ -138,8 +158,11 @@ impl Ws2pPreferedSubCommands {
                     for pubkey in public_keys {
                         prefered_pubkeys.remove(&pubkey.to_string());
                         println!(
-                            "Pubkey '{}' successfully removed from the list of preferred keys",
-                            pubkey
+                            "{}",
+                            i18n.tr_args(
+                                "Pubkey '{}' successfully removed from the list of preferred keys.",
+                                &[&pubkey]
+                            )
                         );
                     }
                     let mut new_user_conf = module_user_conf.unwrap_or_default();
@@ -147,7 +170,10 @@ impl Ws2pPreferedSubCommands {
                     Some(new_user_conf)
                 }
                 Ws2pPreferedSubCommands::Show => {
-                    println!("{} preferred keys: ", prefered_pubkeys.len());
+                    println!(
+                        "{}",
+                        i18n.tr_args("{} preferred keys:", &[&prefered_pubkeys.len()])
+                    );
                     for pubkey in &prefered_pubkeys {
                         println!("{}", pubkey);
                     }
@@ -0,0 +1,471 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Sub-module downloading the blockchain from WS2Pv1 sync endpoints.
+
+use crate::ws_connections::messages::{check_timeout_requests, ws2p_recv_message_pretreatment};
+use crate::ws_connections::requests::sent::send_request_to_specific_node;
+use crate::*;
+use dubp_block_doc::block::BlockDocumentTrait;
+use dubp_block_doc::parser::parse_json_block_from_serde_value;
+use dubp_common_doc::traits::Document;
+use dubp_common_doc::BlockNumber;
+use dup_crypto::hashs::Hash;
+use durs_network_documents::url::Url;
+
+#[derive(Debug, Default)]
+/// Synchronization progress
+struct WS2Pv1SyncState {
+    /// Nodes from which the blocks can be downloaded
+    candidates: HashSet<NodeFullId>,
+    /// Node from which the blocks are downloaded
+    sync_node: Option<NodeFullId>,
+    /// Last block number to download (sync option)
+    end: Option<u32>,
+    /// Local current blockstamp
+    local_current: Option<Blockstamp>,
+    /// Target blockstamp
+    target: Option<Blockstamp>,
+    /// Next expected block number and hash of its previous block
+    next: Option<(BlockNumber, Option<Hash>)>,
+    /// Pending request
+    pending_req: Option<WS2Pv1ReqId>,
+    /// All blocks are downloaded
+    finished: bool,
+}
+
+/// Download the blockchain from the sync endpoints matching the source, and send it to the blockchain module
+pub fn sync(
+    soft_meta_datas: &SoftwareMetaDatas<DuRsConf>,
+    keys: RequiredKeysContent,
+    conf: WS2PConf,
+    router_sender: mpsc::Sender<RouterThreadMessage<DursMsg>>,
+    sync_opts: SyncOpt,
+) -> Result<(), SyncError> {
+    println!("Download blockchain from network...");
+
+    // Get key_pair
+    let key_pair = if let RequiredKeysContent::NetworkKeyPair(key_pair) = keys {
+        key_pair
+    } else {
+        fatal_error!("WS2Pv1: unexpected keys !");
+    };
+
+    // Define WS2Pv1Module
+    let ep_file_path = get_ep_file_path(soft_meta_datas);
+    let mut ws2p_module = WS2Pv1Module::new(
+        soft_meta_datas,
+        conf,
+        ep_file_path.clone(),
+        key_pair,
+        router_sender.clone(),
+    );
+
+    // Select sync endpoints
+    let source = sync_opts
+        .source
+        .as_ref()
+        .and_then(Url::host)
+        .unwrap_or_default();
+    let sync_endpoints = select_sync_endpoints(
+        &ws2p_module.conf.sync_endpoints,
+        get_endpoints_from_db(&ep_file_path),
+        sync_opts.source.as_ref(),
+    );
+    if sync_endpoints.is_empty() {
+        return Err(SyncError::InvalidSource { source });
+    }
+    let mut sync_state = WS2Pv1SyncState {
+        end: sync_opts.end,
+        ..WS2Pv1SyncState::default()
+    };
+    for ep in sync_endpoints {
+        let node_full_id = ep
+            .node_full_id()
+            .expect("WS2P: Fail to get ep.node_full_id() !");
+        info!("WS2Pv1: sync endpoint {}", ep.raw_endpoint);
+        sync_state.candidates.insert(node_full_id);
+        ws2p_module.connections.insert_endpoint(
+            node_full_id,
+            DbEndpoint {
+                ep,
+                state: WS2PConnectionState::NeverTry,
+                last_check: 0,
+                stats: EndpointStats::default(),
+            },
+        );
+    }
+
+    // Register ws2p module in router
+    register_in_router(
+        router_sender,
+        ws2p_module.main_thread_channel.0.clone(),
+        vec![],
+        vec![],
+    );
+
+    // Request local current blockstamp
+    send_dal_request(&mut ws2p_module, &BlockchainRequest::CurrentBlockstamp());
+
+    // Connect to sync endpoints
+    for node_full_id in sync_state.candidates.clone() {
+        connect_to_without_checking_quotas(&mut ws2p_module, node_full_id);
+    }
+
+    while !sync_state.finished {
+        match ws2p_module
+            .main_thread_channel
+            .1
+            .recv_timeout(Duration::from_millis(200))
+        {
+            Ok(WS2PThreadSignal::DursMsg(durs_msg)) => match *durs_msg {
+                DursMsg::Stop => break,
+                DursMsg::Response {
+                    res_content:
+                        DursResContent::BlockchainResponse(BlockchainResponse::CurrentBlockstamp(
+                            current_blockstamp,
+                        )),
+                    ..
+                } => {
+                    sync_state.local_current = Some(current_blockstamp);
+                }
+                _ => {}
+            },
+            Ok(WS2PThreadSignal::PortMapping(_)) | Ok(WS2PThreadSignal::PublicIp(_)) => {}
+            Ok(WS2PThreadSignal::WS2Pv1Msg(msg)) => {
+                match ws2p_recv_message_pretreatment(&mut ws2p_module, msg) {
+                    WS2PSignal::ConnectionEstablished(node_full_id)
+                        if sync_state.sync_node.is_none()
+                            && sync_state.candidates.contains(&node_full_id) =>
+                    {
+                        info!("WS2Pv1: sync from {}", node_full_id);
+                        sync_state.sync_node = Some(node_full_id);
+                    }
+                    WS2PSignal::WSError(node_full_id)
+                    | WS2PSignal::NegociationTimeout(node_full_id)
+                    | WS2PSignal::Timeout(node_full_id)
+                    | WS2PSignal::KeepAliveTimeout(node_full_id) => {
+                        sync_state.remove_candidate(&mut ws2p_module, node_full_id);
+                    }
+                    WS2PSignal::ReqResponse(_, req_body, node_full_id, response)
+                        if Some(node_full_id) == sync_state.sync_node =>
+                    {
+                        sync_state.receive_response(
+                            &mut ws2p_module,
+                            req_body,
+                            node_full_id,
+                            &response,
+                        );
+                    }
+                    _ => {}
+                }
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                fatal_error!("Disconnected ws2p module !");
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                check_timeout_requests(&mut ws2p_module);
+            }
+        }
+        if sync_state.candidates.is_empty() {
+            return Err(SyncError::UnreachableSource { source });
+        }
+        sync_state.send_next_request(&mut ws2p_module);
+    }
+
+    // Close all connections
+    for (_, ws) in ws2p_module.connections.websockets() {
+        let _ = ws.0.close(CloseCode::Normal);
+    }
+
+    Ok(())
+}
+
+/// Select sync endpoints (from conf and DB) matching the source
+fn select_sync_endpoints(
+    conf_sync_endpoints: &[EndpointV1],
+    db_endpoints: Vec<(NodeFullId, DbEndpoint)>,
+    source: Option<&Url>,
+) -> Vec<EndpointV1> {
+    let source_host = source.and_then(Url::host);
+    let source_port = source.and_then(Url::port);
+
+    let mut node_full_ids = HashSet::new();
+    conf_sync_endpoints
+        .iter()
+        .cloned()
+        .chain(db_endpoints.into_iter().map(|(_, db_ep)| db_ep.ep))
+        .filter(|ep| {
+            source_host.is_none()
+                || (source_host.as_ref() == Some(&ep.host)
+                    && source_port.map_or(ep.port, usize::from) == ep.port)
+        })
+        .filter(|ep| {
+            ep.node_full_id()
+                .map(|node_full_id| node_full_ids.insert(node_full_id))
+                .unwrap_or(false)
+        })
+        .collect()
+}
+
+/// Check that the chunk blocks follow each other from the expected block
+fn check_chunk_continuity(
+    first_number: BlockNumber,
+    previous_hash: Option<Hash>,
+    blocks: &[BlockDocument],
+) -> bool {
+    let mut expected = (first_number, previous_hash);
+    for block in blocks {
+        if block.number() != expected.0 || block.previous_hash() != expected.1 {
+            return false;
+        }
+        expected = (
+            BlockNumber(block.number().0 + 1),
+            block.hash().map(|block_hash| block_hash.0),
+        );
+    }
+    !blocks.is_empty()
+}
+
+impl WS2Pv1SyncState {
+    /// Send a request to the sync node
+    fn send_request(&mut self, ws2p_module: &mut WS2Pv1Module, body: WS2Pv1ReqBody) {
+        if let Some(sync_node) = self.sync_node {
+            let request = WS2Pv1Request {
+                id: WS2Pv1ReqId::random(),
+                body,
+            };
+            if let Err(e) = send_request_to_specific_node(
+                ws2p_module,
+                ModuleReqFullId(WS2Pv1Module::name(), ModuleReqId(0)),
+                &sync_node,
+                &request,
+            ) {
+                warn!("WS2Pv1: fail to send sync request: {}", e);
+            }
+            self.pending_req = Some(request.id);
+        }
+    }
+    /// Send the next request needed by the synchronization, if there is no pending request
+    fn send_next_request(&mut self, ws2p_module: &mut WS2Pv1Module) {
+        if let Some(pending_req) = self.pending_req {
+            if ws2p_module.requests.is_awaiting(&pending_req) {
+                return;
+            }
+        }
+        self.pending_req = None;
+        if self.sync_node.is_none() {
+            return;
+        }
+
+        if let Some(target) = self.target {
+            if let Some((next_number, _)) = self.next {
+                let count = std::cmp::min(*WS2P_SYNC_CHUNK_SIZE, target.id.0 + 1 - next_number.0);
+                self.send_request(
+                    ws2p_module,
+                    WS2Pv1ReqBody::GetBlocks {
+                        count,
+                        from_number: next_number,
+                    },
+                );
+            } else if let Some(local_current) = self.local_current {
+                if local_current == Blockstamp::default() {
+                    self.next = Some((BlockNumber(0), None));
+                } else if target.id > local_current.id {
+                    self.next = Some((
+                        BlockNumber(local_current.id.0 + 1),
+                        Some(local_current.hash.0),
+                    ));
+                } else {
+                    println!("Your durs node is already synchronized.");
+                    self.finished = true;
+                }
+            }
+        } else if let Some(end) = self.end {
+            self.send_request(
+                ws2p_module,
+                WS2Pv1ReqBody::GetBlock {
+                    number: BlockNumber(end),
+                },
+            );
+        } else {
+            self.send_request(ws2p_module, WS2Pv1ReqBody::GetCurrent);
+        }
+    }
+    /// Treat a response of the sync node
+    fn receive_response(
+        &mut self,
+        ws2p_module: &mut WS2Pv1Module,
+        req_body: WS2Pv1ReqBody,
+        node_full_id: NodeFullId,
+        response: &serde_json::Value,
+    ) {
+        self.pending_req = None;
+        match req_body {
+            WS2Pv1ReqBody::GetCurrent | WS2Pv1ReqBody::GetBlock { .. } => {
+                match parse_json_block_from_serde_value(response) {
+                    Ok(block) => {
+                        let target = block.blockstamp();
+                        info!("WS2Pv1: sync target blockstamp: {}", target);
+                        self.target = Some(target);
+                        events::sent::send_network_event(
+                            ws2p_module,
+                            NetworkEvent::SyncEvent(SyncEvent::ReceiveTargetBlockstamp(target)),
+                        );
+                    }
+                    Err(e) => {
+                        warn!("WS2Pv1: receive invalid target block: {}.", e);
+                        // The end block may not exist yet, fall back on the current block
+                        self.end = None;
+                    }
+                }
+            }
+            WS2Pv1ReqBody::GetBlocks { from_number, .. } => {
+                let (next_number, previous_hash) = match self.next {
+                    Some(next) if next.0 == from_number => next,
+                    _ => return,
+                };
+                let blocks = response
+                    .as_array()
+                    .map(|json_blocks| {
+                        json_blocks
+                            .iter()
+                            .map(parse_json_block_from_serde_value)
+                            .collect::<Result<Vec<BlockDocument>, _>>()
+                    })
+                    .unwrap_or_else(|| Ok(vec![]));
+                let target = self.target.unwrap_or_default();
+                match blocks {
+                    Ok(blocks) if check_chunk_continuity(next_number, previous_hash, &blocks) => {
+                        let last_block = &blocks[blocks.len() - 1];
+                        if last_block.number() >= target.id && last_block.blockstamp() != target {
+                            warn!("WS2Pv1: sync chunk not match target blockstamp !");
+                            self.remove_candidate(ws2p_module, node_full_id);
+                            return;
+                        }
+                        self.next = Some((
+                            BlockNumber(last_block.number().0 + 1),
+                            last_block.hash().map(|block_hash| block_hash.0),
+                        ));
+                        self.finished = last_block.number() >= target.id;
+                        debug!(
+                            "WS2Pv1: receive sync chunk from #{} to #{}",
+                            from_number,
+                            last_block.number()
+                        );
+                        events::sent::send_network_event(
+                            ws2p_module,
+                            NetworkEvent::SyncEvent(SyncEvent::ReceiveCorrectBlocksChunk {
+                                blocks,
+                                raw_blocks: None,
+                            }),
+                        );
+                    }
+                    _ => {
+                        warn!("WS2Pv1: receive invalid sync chunk from #{} !", from_number);
+                        self.remove_candidate(ws2p_module, node_full_id);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    /// Stop downloading blocks from a node and switch to another established sync node
+    fn remove_candidate(&mut self, ws2p_module: &mut WS2Pv1Module, node_full_id: NodeFullId) {
+        if !self.candidates.remove(&node_full_id) {
+            return;
+        }
+        close_connection(
+            ws2p_module,
+            &node_full_id,
+            WS2PCloseConnectionReason::Unknow,
+        );
+        if self.sync_node == Some(node_full_id) {
+            self.pending_req = None;
+            self.sync_node = self
+                .candidates
+                .iter()
+                .find(|candidate| {
+                    ws2p_module.connections.endpoint_state(candidate)
+                        == Some(WS2PConnectionState::Established)
+                })
+                .cloned();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn endpoint(raw_endpoint: &str, pubkey: &str) -> EndpointV1 {
+        EndpointV1::parse_from_raw(
+            raw_endpoint,
+            PubKey::from_str(pubkey).expect("invalid pubkey"),
+            0,
+            0,
+        )
+        .expect("invalid endpoint")
+    }
+
+    #[test]
+    fn select_sync_endpoints_by_source() {
+        let ep1 = endpoint(
+            "WS2P 11111111 g1.duniter.org 443 /ws2p",
+            "D9D2zaJoWYWveii1JRYLVK3J4Z7ZH3QczoKrnQeiM6mx",
+        );
+        let ep2 = endpoint(
+            "WS2P 22222222 g1.duniter.fr 80 /ws2p",
+            "2ny7YAdmzReQxAayyJZsyVYwYhVyax2thKcGknmQy5nQ",
+        );
+        let db_endpoints = vec![
+            (
+                unwrap!(ep1.node_full_id()),
+                DbEndpoint {
+                    ep: ep1.clone(),
+                    state: WS2PConnectionState::Close,
+                    last_check: 0,
+                    stats: EndpointStats::default(),
+                },
+            ),
+            (
+                unwrap!(ep2.node_full_id()),
+                DbEndpoint {
+                    ep: ep2.clone(),
+                    state: WS2PConnectionState::Close,
+                    last_check: 0,
+                    stats: EndpointStats::default(),
+                },
+            ),
+        ];
+
+        let source = Url::from_str("g1.duniter.fr").expect("invalid url");
+        assert_eq!(
+            vec![ep2.clone()],
+            select_sync_endpoints(
+                std::slice::from_ref(&ep1),
+                db_endpoints.clone(),
+                Some(&source)
+            )
+        );
+        let source = Url::from_str("g1.duniter.fr:443").expect("invalid url");
+        assert!(select_sync_endpoints(&[], db_endpoints.clone(), Some(&source)).is_empty());
+        assert_eq!(
+            vec![ep1.clone(), ep2],
+            select_sync_endpoints(&[ep1], db_endpoints, None)
+        );
+    }
+}
@@ -15,12 +15,15 @@
 
 //! Manage WS2Pv1 storage.
 
+use crate::constants::*;
 use crate::ws_connections::states::WS2PConnectionState;
+use dup_crypto::keys::PubKey;
 use durs_network_documents::network_endpoint::EndpointV1;
 use durs_network_documents::NodeFullId;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
+use std::ops::Deref;
 use std::path::Path;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -49,6 +52,9 @@ pub fn string_to_api(api: &str) -> Option<EndpointApi> {
 pub enum Ws2pPeersDbError {
     IoErr(std::io::Error),
     SerdeErr(bincode::Error),
+    JsonErr(serde_json::Error),
+    /// Endpoints store written by a more recent version
+    UnknownVersion(u32),
 }
 
 impl From<std::io::Error> for Ws2pPeersDbError {
@@ -63,11 +69,97 @@ impl From<bincode::Error> for Ws2pPeersDbError {
     }
 }
 
+impl From<serde_json::Error> for Ws2pPeersDbError {
+    fn from(e: serde_json::Error) -> Self {
+        Ws2pPeersDbError::JsonErr(e)
+    }
+}
+
+/// Header written at the beginning of the endpoints store (followed by the version)
+static ENDPOINTS_DB_MAGIC: &[u8] = b"DWS2PEPS";
+
+/// Current version of the endpoints store schema
+pub static ENDPOINTS_DB_VERSION: &u32 = &2;
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct DbEndpoint {
     pub ep: EndpointV1,
     pub state: WS2PConnectionState,
     pub last_check: u64,
+    pub stats: EndpointStats,
+}
+
+/// Endpoint stored by previous versions (without statistics)
+#[derive(Deserialize)]
+struct LegacyDbEndpoint {
+    ep: EndpointV1,
+    state: WS2PConnectionState,
+    last_check: u64,
+}
+
+impl From<LegacyDbEndpoint> for DbEndpoint {
+    fn from(legacy: LegacyDbEndpoint) -> Self {
+        DbEndpoint {
+            ep: legacy.ep,
+            state: legacy.state,
+            last_check: legacy.last_check,
+            stats: EndpointStats::default(),
+        }
+    }
+}
+
+/// Statistics used to score an endpoint
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct EndpointStats {
+    /// Number of outgoing connection attempts
+    pub connection_attempts: u32,
+    /// Number of connections established
+    pub established_connections: u32,
+    /// Timestamp of the last HEAD received from this node (0 if never)
+    pub last_head_time: u64,
+    /// Smoothed response latency (in milliseconds)
+    pub latency_ms: Option<u64>,
+}
+
+impl EndpointStats {
+    /// Take a new response latency into account
+    pub fn record_latency(&mut self, latency_ms: u64) {
+        self.latency_ms = Some(match self.latency_ms {
+            Some(previous_latency_ms) => (3 * previous_latency_ms + latency_ms) / 4,
+            None => latency_ms,
+        });
+    }
+    /// Score of the endpoint (the higher, the better)
+    pub fn score(&self, now: u64, prefered: bool) -> u64 {
+        // Never tried endpoints get half of the uptime score to give them a chance
+        let uptime_score = if self.connection_attempts == 0 {
+            *WS2P_SCORE_UPTIME_WEIGHT / 2
+        } else {
+            let established = u64::from(self.established_connections.min(self.connection_attempts));
+            *WS2P_SCORE_UPTIME_WEIGHT * established / u64::from(self.connection_attempts)
+        };
+        let head_score = if self.last_head_time == 0 {
+            0
+        } else {
+            let head_age = now.saturating_sub(self.last_head_time);
+            *WS2P_SCORE_HEAD_FRESHNESS_WEIGHT
+                * WS2P_SCORE_HEAD_MAX_AGE_IN_SECS.saturating_sub(head_age)
+                / *WS2P_SCORE_HEAD_MAX_AGE_IN_SECS
+        };
+        let latency_score = match self.latency_ms {
+            Some(latency_ms) => {
+                *WS2P_SCORE_LATENCY_WEIGHT * WS2P_SCORE_MAX_LATENCY_IN_MS.saturating_sub(latency_ms)
+                    / *WS2P_SCORE_MAX_LATENCY_IN_MS
+            }
+            None => *WS2P_SCORE_LATENCY_WEIGHT / 2,
+        };
+        let prefered_bonus = if prefered {
+            *WS2P_SCORE_PREFERED_BONUS
+        } else {
+            0
+        };
+        uptime_score + head_score + latency_score + prefered_bonus
+    }
 }
 
 pub fn get_endpoints(
@@ -77,8 +169,21 @@ pub fn get_endpoints(
         let bin_endpoints = durs_common_tools::fns::bin_file::read_bin_file(file_path)?;
         if bin_endpoints.is_empty() {
             Ok(HashMap::new())
+        } else if bin_endpoints.starts_with(ENDPOINTS_DB_MAGIC) {
+            let bin_endpoints = &bin_endpoints[ENDPOINTS_DB_MAGIC.len()..];
+            let version: u32 = bincode::deserialize(bin_endpoints)?;
+            if version != *ENDPOINTS_DB_VERSION {
+                return Err(Ws2pPeersDbError::UnknownVersion(version));
+            }
+            Ok(bincode::deserialize(&bin_endpoints[4..])?)
         } else {
-            Ok(bincode::deserialize(&bin_endpoints[..])?)
+            let endpoints = read_legacy_endpoints(&bin_endpoints)?;
+            write_endpoints(file_path, &endpoints)?;
+            info!(
+                "WS2Pv1: endpoints store migrated to version {}.",
+                ENDPOINTS_DB_VERSION
+            );
+            Ok(endpoints)
         }
     } else {
         File::create(file_path)?;
@@ -86,12 +191,328 @@ pub fn get_endpoints(
     }
 }
 
+/// Read endpoints store written by a previous version (without header)
+fn read_legacy_endpoints(
+    bin_endpoints: &[u8],
+) -> Result<HashMap<NodeFullId, DbEndpoint>, Ws2pPeersDbError> {
+    match bincode::deserialize(bin_endpoints) {
+        Ok(endpoints) => Ok(endpoints),
+        Err(e) => {
+            // Endpoints without statistics
+            let legacy_endpoints: HashMap<NodeFullId, LegacyDbEndpoint> =
+                bincode::deserialize(bin_endpoints).map_err(|_| e)?;
+            Ok(legacy_endpoints
+                .into_iter()
+                .map(|(node_full_id, legacy)| (node_full_id, legacy.into()))
+                .collect())
+        }
+    }
+}
+
 pub fn write_endpoints<S: std::hash::BuildHasher>(
     file_path: &Path,
     endpoints: &HashMap<NodeFullId, DbEndpoint, S>,
 ) -> Result<(), Ws2pPeersDbError> {
-    let bin_endpoints = bincode::serialize(&endpoints)?;
+    let mut bin_endpoints = ENDPOINTS_DB_MAGIC.to_vec();
+    bin_endpoints.extend(bincode::serialize(ENDPOINTS_DB_VERSION)?);
+    bin_endpoints.extend(bincode::serialize(&endpoints)?);
     durs_common_tools::fns::bin_file::write_bin_file(file_path, &bin_endpoints)?;
 
     Ok(())
 }
+
+/// Endpoints in JSON format (used to export and import the endpoints store)
+#[derive(Debug, Deserialize, Serialize)]
+pub struct JsonEndpoints {
+    /// Version of the endpoints store schema
+    pub version: u32,
+    /// Known endpoints
+    pub endpoints: Vec<DbEndpoint>,
+}
+
+impl JsonEndpoints {
+    /// Export endpoints store
+    pub fn export(file_path: &Path) -> Result<String, Ws2pPeersDbError> {
+        let mut endpoints: Vec<DbEndpoint> = get_endpoints(file_path)?.values().cloned().collect();
+        endpoints.sort_by(|a, b| a.ep.raw_endpoint.cmp(&b.ep.raw_endpoint));
+        Ok(serde_json::to_string_pretty(&JsonEndpoints {
+            version: *ENDPOINTS_DB_VERSION,
+            endpoints,
+        })?)
+    }
+    /// Import endpoints in store (endpoints already known are replaced).
+    /// Returns the number of imported endpoints.
+    pub fn import(file_path: &Path, json_endpoints: &str) -> Result<usize, Ws2pPeersDbError> {
+        let json_endpoints: JsonEndpoints = serde_json::from_str(json_endpoints)?;
+        if json_endpoints.version > *ENDPOINTS_DB_VERSION {
+            return Err(Ws2pPeersDbError::UnknownVersion(json_endpoints.version));
+        }
+        let mut endpoints = get_endpoints(file_path)?;
+        let mut imported_count = 0;
+        for db_endpoint in json_endpoints.endpoints {
+            if let Some(node_full_id) = db_endpoint.ep.node_full_id() {
+                endpoints.insert(node_full_id, db_endpoint);
+                imported_count += 1;
+            }
+        }
+        write_endpoints(file_path, &endpoints)?;
+        Ok(imported_count)
+    }
+}
+
+/// Known endpoints, with tracking of the entries modified since the last save
+#[derive(Debug, Default)]
+pub struct DbEndpoints {
+    endpoints: HashMap<NodeFullId, DbEndpoint>,
+    dirty: HashSet<NodeFullId>,
+}
+
+impl Deref for DbEndpoints {
+    type Target = HashMap<NodeFullId, DbEndpoint>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.endpoints
+    }
+}
+
+impl DbEndpoints {
+    /// Get mutable endpoint (the entry is considered modified)
+    pub fn get_mut(&mut self, node_full_id: &NodeFullId) -> Option<&mut DbEndpoint> {
+        let endpoint = self.endpoints.get_mut(node_full_id);
+        if endpoint.is_some() {
+            self.dirty.insert(*node_full_id);
+        }
+        endpoint
+    }
+    /// Insert or replace an endpoint
+    pub fn insert(&mut self, node_full_id: NodeFullId, endpoint: DbEndpoint) {
+        self.dirty.insert(node_full_id);
+        self.endpoints.insert(node_full_id, endpoint);
+    }
+    /// Insert endpoint only if there is not already an endpoint for this node
+    pub fn insert_if_absent(&mut self, node_full_id: NodeFullId, endpoint: DbEndpoint) {
+        if !self.endpoints.contains_key(&node_full_id) {
+            self.insert(node_full_id, endpoint);
+        }
+    }
+    /// Insert or replace several endpoints
+    pub fn extend<I: IntoIterator<Item = (NodeFullId, DbEndpoint)>>(&mut self, endpoints: I) {
+        for (node_full_id, endpoint) in endpoints {
+            self.insert(node_full_id, endpoint);
+        }
+    }
+    /// Number of entries modified since the last save
+    pub fn dirty_count(&self) -> usize {
+        self.dirty.len()
+    }
+    /// Write endpoints in file if at least one entry was modified since the last save.
+    /// Returns `true` if the file has been written.
+    pub fn save(&mut self, file_path: &Path) -> Result<bool, Ws2pPeersDbError> {
+        if self.dirty.is_empty() {
+            return Ok(false);
+        }
+        write_endpoints(file_path, &self.endpoints)?;
+        debug!("WS2Pv1: {} modified endpoints saved.", self.dirty.len());
+        self.dirty.clear();
+        Ok(true)
+    }
+}
+
+/// Name of the file where the ban list is stored (alongside the endpoints file)
+pub static BAN_LIST_FILENAME: &str = "ban_list.bin";
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum BanLevel {
+    /// Only connect to this peer when no other endpoint is available
+    Greylisted,
+    /// Never connect to this peer and ignore its HEADs
+    Banned,
+}
+
+/// Pubkeys banned or greylisted by the node operator
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct BanList(HashMap<PubKey, BanLevel>);
+
+impl BanList {
+    /// Load ban list from file (empty list if the file does not exist)
+    pub fn load(file_path: &Path) -> Result<BanList, Ws2pPeersDbError> {
+        if file_path.exists() {
+            let bin_ban_list = durs_common_tools::fns::bin_file::read_bin_file(file_path)?;
+            if !bin_ban_list.is_empty() {
+                return Ok(bincode::deserialize(&bin_ban_list[..])?);
+            }
+        }
+        Ok(BanList::default())
+    }
+    /// Write ban list in file
+    pub fn save(&self, file_path: &Path) -> Result<(), Ws2pPeersDbError> {
+        let bin_ban_list = bincode::serialize(self)?;
+        durs_common_tools::fns::bin_file::write_bin_file(file_path, &bin_ban_list)?;
+        Ok(())
+    }
+    /// Ban or greylist a pubkey
+    pub fn ban(&mut self, pubkey: PubKey, level: BanLevel) {
+        self.0.insert(pubkey, level);
+    }
+    /// Remove a pubkey from the ban list. Returns `true` if the pubkey was listed.
+    pub fn unban(&mut self, pubkey: &PubKey) -> bool {
+        self.0.remove(pubkey).is_some()
+    }
+    /// Get ban level of a pubkey
+    pub fn level(&self, pubkey: &PubKey) -> Option<BanLevel> {
+        self.0.get(pubkey).copied()
+    }
+    /// Is this pubkey banned ?
+    pub fn is_banned(&self, pubkey: &PubKey) -> bool {
+        self.level(pubkey) == Some(BanLevel::Banned)
+    }
+    /// Is this pubkey greylisted ?
+    pub fn is_greylisted(&self, pubkey: &PubKey) -> bool {
+        self.level(pubkey) == Some(BanLevel::Greylisted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dup_crypto::keys::*;
+    use unwrap::unwrap;
+
+    fn endpoint() -> EndpointV1 {
+        unwrap!(EndpointV1::parse_from_raw(
+            "WS2P e66254bf 91.121.157.13 20901",
+            PubKey::Ed25519(unwrap!(ed25519::PublicKey::from_base58(
+                "8iVdpXqFLCxGyPqgVx5YbFSkmWKkceXveRd2yvBKeARL",
+            ))),
+            0,
+            0,
+        ))
+    }
+
+    #[test]
+    fn save_endpoints_only_when_modified() -> Result<(), Ws2pPeersDbError> {
+        let tmp_dir = tempfile::tempdir()?;
+        let file_path = tmp_dir.path().join("endpoints.bin");
+
+        let ep = endpoint();
+        let node_full_id = unwrap!(ep.node_full_id());
+        let mut endpoints = DbEndpoints::default();
+        endpoints.insert_if_absent(
+            node_full_id,
+            DbEndpoint {
+                ep,
+                state: WS2PConnectionState::NeverTry,
+                last_check: 0,
+                stats: EndpointStats::default(),
+            },
+        );
+        assert_eq!(endpoints.dirty_count(), 1);
+        assert!(endpoints.save(&file_path)?);
+        assert_eq!(endpoints.dirty_count(), 0);
+
+        // Nothing changed: skip writing
+        assert!(!endpoints.save(&file_path)?);
+
+        unwrap!(endpoints.get_mut(&node_full_id)).state = WS2PConnectionState::Close;
+        assert!(endpoints.save(&file_path)?);
+
+        let saved_endpoints = get_endpoints(&file_path)?;
+        assert_eq!(
+            saved_endpoints[&node_full_id].state,
+            WS2PConnectionState::Close
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn migrate_legacy_endpoints_and_export_import() -> Result<(), Ws2pPeersDbError> {
+        let tmp_dir = tempfile::tempdir()?;
+        let file_path = tmp_dir.path().join("endpoints.bin");
+
+        let ep = endpoint();
+        let node_full_id = unwrap!(ep.node_full_id());
+        let mut legacy_endpoints = HashMap::new();
+        legacy_endpoints.insert(
+            node_full_id,
+            DbEndpoint {
+                ep,
+                state: WS2PConnectionState::Denial,
+                last_check: 42,
+                stats: EndpointStats::default(),
+            },
+        );
+        durs_common_tools::fns::bin_file::write_bin_file(
+            &file_path,
+            &bincode::serialize(&legacy_endpoints)?,
+        )?;
+
+        // Legacy store is migrated on load
+        assert_eq!(1, get_endpoints(&file_path)?.len());
+        let bin_endpoints = durs_common_tools::fns::bin_file::read_bin_file(&file_path)?;
+        assert!(bin_endpoints.starts_with(ENDPOINTS_DB_MAGIC));
+
+        let json_endpoints = JsonEndpoints::export(&file_path)?;
+        let other_file_path = tmp_dir.path().join("other_endpoints.bin");
+        assert_eq!(1, JsonEndpoints::import(&other_file_path, &json_endpoints)?);
+        let imported_endpoints = get_endpoints(&other_file_path)?;
+        assert_eq!(42, imported_endpoints[&node_full_id].last_check);
+        assert_eq!(
+            WS2PConnectionState::Denial,
+            imported_endpoints[&node_full_id].state
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn endpoint_score() {
+        let never_tried = EndpointStats::default();
+        let reliable = EndpointStats {
+            connection_attempts: 4,
+            established_connections: 4,
+            last_head_time: 1_000,
+            latency_ms: Some(100),
+        };
+        let unreliable = EndpointStats {
+            connection_attempts: 4,
+            established_connections: 1,
+            last_head_time: 0,
+            latency_ms: Some(9_000),
+        };
+        let now = 1_060;
+        assert!(reliable.score(now, false) > never_tried.score(now, false));
+        assert!(never_tried.score(now, false) > unreliable.score(now, false));
+        assert!(unreliable.score(now, true) > reliable.score(now, false));
+
+        let mut stats = EndpointStats::default();
+        stats.record_latency(400);
+        assert_eq!(Some(400), stats.latency_ms);
+        stats.record_latency(800);
+        assert_eq!(Some(500), stats.latency_ms);
+    }
+
+    #[test]
+    fn save_and_load_ban_list() -> Result<(), Ws2pPeersDbError> {
+        let tmp_dir = tempfile::tempdir()?;
+        let file_path = tmp_dir.path().join(BAN_LIST_FILENAME);
+        assert_eq!(BanList::default(), BanList::load(&file_path)?);
+
+        let pubkey = endpoint().issuer;
+        let mut ban_list = BanList::default();
+        ban_list.ban(pubkey, BanLevel::Greylisted);
+        assert!(ban_list.is_greylisted(&pubkey));
+        assert!(!ban_list.is_banned(&pubkey));
+        ban_list.ban(pubkey, BanLevel::Banned);
+        assert!(ban_list.is_banned(&pubkey));
+        ban_list.save(&file_path)?;
+
+        let mut ban_list = BanList::load(&file_path)?;
+        assert_eq!(Some(BanLevel::Banned), ban_list.level(&pubkey));
+        assert!(ban_list.unban(&pubkey));
+        assert!(!ban_list.unban(&pubkey));
+        assert_eq!(None, ban_list.level(&pubkey));
+
+        Ok(())
+    }
+}
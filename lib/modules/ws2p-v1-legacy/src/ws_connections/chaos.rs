@@ -0,0 +1,124 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Fault injection in WS2Pv1 connections, to exercise the resilience of the node.
+//!
+//! Only compiled with the `chaos` feature, and enabled at runtime by setting the environment
+//! variable `DURS_WS2P_CHAOS_RATE` to the percentage of incoming messages to disrupt.
+//! Each disrupted message randomly causes a disconnection, a delay or a JSON corruption.
+
+use dup_crypto::rand;
+use once_cell::sync::Lazy;
+use std::time::Duration;
+use ws::Message;
+
+/// Environment variable defining the percentage of disrupted messages
+pub static CHAOS_RATE_ENV_VAR: &str = "DURS_WS2P_CHAOS_RATE";
+
+/// Maximum delay of a delayed message (in milliseconds)
+pub static CHAOS_MAX_DELAY_IN_MILLI_SECS: &u32 = &5_000;
+
+/// Percentage of disrupted messages (None if fault injection is disabled)
+static CHAOS_RATE: Lazy<Option<u32>> = Lazy::new(|| {
+    let rate = std::env::var(CHAOS_RATE_ENV_VAR)
+        .ok()?
+        .parse::<u32>()
+        .ok()
+        .filter(|rate| *rate > 0)?;
+    warn!("WS2P: fault injection enabled on {}% of messages !", rate);
+    Some(std::cmp::min(rate, 100))
+});
+
+#[derive(Debug, Clone, PartialEq)]
+/// Fate of an incoming message
+pub enum ChaosOutcome {
+    /// Process the message
+    Forward(Message),
+    /// Close the connection
+    Disconnect,
+}
+
+/// Randomly disrupt an incoming message
+pub fn inject(msg: Message) -> ChaosOutcome {
+    if let Some(rate) = *CHAOS_RATE {
+        disrupt(msg, rate)
+    } else {
+        ChaosOutcome::Forward(msg)
+    }
+}
+
+fn disrupt(msg: Message, rate: u32) -> ChaosOutcome {
+    if random_below(100) >= rate {
+        return ChaosOutcome::Forward(msg);
+    }
+    match random_below(3) {
+        0 => {
+            debug!("WS2P: chaos: disconnect.");
+            ChaosOutcome::Disconnect
+        }
+        1 => {
+            let delay = random_below(*CHAOS_MAX_DELAY_IN_MILLI_SECS);
+            debug!("WS2P: chaos: delay message of {} ms.", delay);
+            std::thread::sleep(Duration::from_millis(u64::from(delay)));
+            ChaosOutcome::Forward(msg)
+        }
+        _ => {
+            debug!("WS2P: chaos: corrupt message.");
+            ChaosOutcome::Forward(corrupt(msg))
+        }
+    }
+}
+
+/// Truncate a text message, so that its JSON is no longer valid
+fn corrupt(msg: Message) -> Message {
+    if let Message::Text(text) = msg {
+        let mut text_bytes = text.into_bytes();
+        text_bytes.truncate(random_below(text_bytes.len() as u32) as usize);
+        Message::Text(String::from_utf8_lossy(&text_bytes).into_owned())
+    } else {
+        msg
+    }
+}
+
+fn random_below(max: u32) -> u32 {
+    if max == 0 {
+        0
+    } else {
+        rand::gen_u32().expect("unspecified rand error") % max
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn disrupt_messages() {
+        let msg = Message::Text(String::from(r#"{"auth":"CONNECT"}"#));
+
+        // No message is disrupted with a null rate
+        for _ in 0..100 {
+            assert_eq!(ChaosOutcome::Forward(msg.clone()), disrupt(msg.clone(), 0));
+        }
+
+        // A corrupted message is no longer a valid json
+        if let Message::Text(corrupted_text) = corrupt(msg) {
+            assert!(serde_json::from_str::<serde_json::Value>(&corrupted_text).is_err());
+        } else {
+            panic!("corrupt() must return a text message");
+        }
+    }
+}
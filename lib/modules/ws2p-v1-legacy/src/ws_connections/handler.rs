@@ -15,17 +15,23 @@
 
 //! WS2P connections handler.
 
+use super::keep_alive::{KeepAlive, KEEP_ALIVE};
 use super::messages::*;
 use super::meta_datas::WS2PConnectionMetaDatas;
 use super::states::WS2PConnectionState;
 use crate::constants::*;
 use crate::*;
 use dup_crypto::keys::*;
-use durs_common_tools::fatal_error;
+use durs_common_tools::timer::elapsed_since;
+use durs_common_tools::{fatal_error, log_rate_limited};
+#[cfg(feature = "ssl")]
+use openssl::hash::MessageDigest;
+#[cfg(feature = "ssl")]
+use openssl::ssl::{SslConnector, SslMethod, SslStream, SslVerifyMode};
 use std::sync::mpsc;
 #[allow(deprecated)]
 use ws::util::{Timeout, Token};
-use ws::{CloseCode, Frame, Handler, Handshake, Message, Sender};
+use ws::{CloseCode, Frame, Handler, Handshake, Message, Request, Sender};
 
 const CONNECT: Token = Token(1);
 const EXPIRE: Token = Token(2);
@@ -41,21 +47,44 @@ pub struct Client {
     currency: String,
     connect_message: Message,
     conn_meta_datas: WS2PConnectionMetaDatas,
-    last_mess_time: SystemTime,
+    proxied_host: Option<String>,
+    keep_alive: KeepAlive,
+    last_mess_time: Instant,
     signator: SignatorEnum,
     spam_interval: bool,
     spam_counter: usize,
     timeout: Option<Timeout>,
+    #[cfg(feature = "ssl")]
+    pinned_certificate: Option<[u8; 32]>,
 }
 
+#[cfg_attr(not(feature = "ssl"), allow(unused_variables))]
 pub fn connect_to_ws2p_endpoint(
     endpoint: &EndpointV1,
     conductor_sender: &mpsc::Sender<WS2PThreadSignal>,
     currency: &str,
     keypair: &KeyPairEnum,
+    route: &WS2POutgoingRoute,
+    keep_alive: KeepAlive,
+    pinned_certificate: Option<[u8; 32]>,
 ) -> ws::Result<()> {
     // Get endpoint url
-    let ws_url = endpoint.get_url(true, false).expect("Endpoint unreachable");
+    let (ws_url, proxied_host) = if let WS2POutgoingRoute::Proxy(ref proxy) = route {
+        let relay_addr = proxy::open_relay(proxy, &endpoint.host, endpoint.port as u16)?;
+        (
+            format!(
+                "ws://{}/{}",
+                relay_addr,
+                endpoint.path.clone().unwrap_or_default()
+            ),
+            Some(format!("{}:{}", endpoint.host, endpoint.port)),
+        )
+    } else {
+        (
+            endpoint.get_url(true, false).expect("Endpoint unreachable"),
+            None,
+        )
+    };
 
     // Create WS2PConnectionMetaDatass
     let mut conn_meta_datas = WS2PConnectionMetaDatas::new(
@@ -90,11 +119,15 @@ pub fn connect_to_ws2p_endpoint(
             currency: String::from(currency),
             connect_message,
             conn_meta_datas: conn_meta_datas.clone(),
-            last_mess_time: SystemTime::now(),
+            proxied_host: proxied_host.clone(),
+            keep_alive,
+            last_mess_time: Instant::now(),
             signator,
             spam_interval: false,
             spam_counter: 0,
             timeout: None,
+            #[cfg(feature = "ssl")]
+            pinned_certificate,
         }
     })
 }
@@ -102,6 +135,62 @@ pub fn connect_to_ws2p_endpoint(
 // We implement the Handler trait for Client so that we can get more
 // fine-grained control of the connection.
 impl Handler for Client {
+    // Through the proxy relay, the request must declare the endpoint host instead of the relay address
+    fn build_request(&mut self, url: &::url::Url) -> ws::Result<Request> {
+        let mut request = Request::from_url(url)?;
+        if let Some(ref proxied_host) = self.proxied_host {
+            for (name, value) in request.headers_mut().iter_mut() {
+                if name.eq_ignore_ascii_case("Host") {
+                    *value = proxied_host.as_bytes().to_vec();
+                }
+            }
+        }
+        Ok(request)
+    }
+    // When the certificate of the remote node is pinned, the certificate presented must match it
+    #[cfg(feature = "ssl")]
+    fn upgrade_ssl_client(
+        &mut self,
+        stream: ws::util::TcpStream,
+        url: &::url::Url,
+    ) -> ws::Result<SslStream<ws::util::TcpStream>> {
+        let domain = url.domain().ok_or_else(|| {
+            ws::Error::new(
+                ws::ErrorKind::Protocol,
+                format!("Unable to parse domain from {}. Needed for SSL.", url),
+            )
+        })?;
+        let mut builder = SslConnector::builder(SslMethod::tls()).map_err(|e| {
+            ws::Error::new(
+                ws::ErrorKind::Internal,
+                format!("Failed to upgrade client to SSL: {}", e),
+            )
+        })?;
+        if let Some(pinned_certificate) = self.pinned_certificate {
+            let remote_pubkey = self.conn_meta_datas.remote_pubkey;
+            builder.set_verify_callback(SslVerifyMode::PEER, move |_, x509_ctx| {
+                // The certificate chain is trusted through the pin of the peer certificate
+                if x509_ctx.error_depth() > 0 {
+                    return true;
+                }
+                let matches = x509_ctx
+                    .current_cert()
+                    .and_then(|cert| cert.digest(MessageDigest::sha256()).ok())
+                    .map_or(false, |digest| digest[..] == pinned_certificate[..]);
+                if !matches {
+                    warn!(
+                        "WS2P: certificate of {:?} does not match its pinned fingerprint !",
+                        remote_pubkey
+                    );
+                }
+                matches
+            });
+        }
+        builder
+            .build()
+            .connect(domain, stream)
+            .map_err(ws::Error::from)
+    }
     // `on_open` will be called only after the WebSocket handshake is successful
     // so at this point we know that the connection is ready to send/receive messages.
     // We ignore the `Handshake` for now, but you could also use this method to setup
@@ -132,8 +221,8 @@ impl Handler for Client {
     // and returns a `Result<()>`.
     fn on_message(&mut self, msg: Message) -> ws::Result<()> {
         // Spam ?
-        if unwrap!(SystemTime::now().duration_since(self.last_mess_time))
-            > Duration::new(*WS2P_SPAM_INTERVAL_IN_MILLI_SECS, 0)
+        if elapsed_since(Instant::now(), self.last_mess_time)
+            < Duration::from_millis(*WS2P_SPAM_INTERVAL_IN_MILLI_SECS)
         {
             if self.spam_interval {
                 self.spam_counter += 1;
@@ -147,11 +236,27 @@ impl Handler for Client {
         }
         // Spam ?
         if self.spam_counter >= *WS2P_SPAM_LIMIT {
+            if self.spam_counter == *WS2P_SPAM_LIMIT {
+                // Report flood violation to WS2PConductor
+                let _ = self
+                    .conductor_sender
+                    .send(WS2PThreadSignal::WS2Pv1Msg(WS2Pv1Msg {
+                        from: self.conn_meta_datas.node_full_id(),
+                        payload: WS2Pv1MsgPayload::Spam,
+                    }));
+            }
             thread::sleep(Duration::from_millis(*WS2P_SPAM_SLEEP_TIME_IN_SEC));
-            self.last_mess_time = SystemTime::now();
+            self.last_mess_time = Instant::now();
             return Ok(());
         }
-        self.last_mess_time = SystemTime::now();
+        self.last_mess_time = Instant::now();
+
+        // Fault injection
+        #[cfg(feature = "chaos")]
+        let msg = match chaos::inject(msg) {
+            chaos::ChaosOutcome::Forward(msg) => msg,
+            chaos::ChaosOutcome::Disconnect => return self.ws.close(CloseCode::Away),
+        };
 
         // Parse and check incoming message
         if msg.is_text() {
@@ -159,8 +264,16 @@ impl Handler for Client {
                 .into_text()
                 .expect("WS2P: Fail to convert message payload to String !");
             trace!("WS2P: receive mess: {}", s);
-            let json_message: serde_json::Value = serde_json::from_str(&s)
-                .expect("WS2P: Fail to convert string message ton json value !");
+            let json_message: serde_json::Value = match serde_json::from_str(&s) {
+                Ok(json_message) => json_message,
+                Err(_) => {
+                    warn!(
+                        "WS2P: receive invalid json message from {}",
+                        self.conn_meta_datas.node_full_id()
+                    );
+                    return Ok(());
+                }
+            };
             let result = self
                 .conductor_sender
                 .send(WS2PThreadSignal::WS2Pv1Msg(WS2Pv1Msg {
@@ -175,6 +288,11 @@ impl Handler for Client {
                 info!("Close ws2p connection because ws2p main thread is unrechable !");
                 self.ws.close(CloseCode::Normal)?;
             }
+            if self.conn_meta_datas.state == WS2PConnectionState::Established {
+                if let Some(delay) = self.keep_alive.start() {
+                    self.ws.timeout(delay, KEEP_ALIVE)?;
+                }
+            }
         }
         Ok(())
     }
@@ -202,6 +320,20 @@ impl Handler for Client {
                     }));
                 self.ws.close(CloseCode::Away)
             }
+            KEEP_ALIVE => {
+                if self.keep_alive.on_timeout() {
+                    let _result =
+                        self.conductor_sender
+                            .send(WS2PThreadSignal::WS2Pv1Msg(WS2Pv1Msg {
+                                from: self.conn_meta_datas.node_full_id(),
+                                payload: WS2Pv1MsgPayload::KeepAliveTimeout,
+                            }));
+                    self.ws.close(CloseCode::Away)
+                } else {
+                    self.ws.ping(Vec::new())?;
+                    self.ws.timeout(self.keep_alive.delay(), KEEP_ALIVE)
+                }
+            }
             _ => Ok(()),
         }
     }
@@ -218,6 +350,7 @@ impl Handler for Client {
     fn on_frame(&mut self, frame: Frame) -> ws::Result<Option<Frame>> {
         // some activity has occurred, let's reset the expiration timeout
         self.ws.timeout(WS2P_EXPIRE_TIMEOUT * 1_000, EXPIRE)?;
+        self.keep_alive.on_frame(&frame);
         Ok(Some(frame))
     }
     fn on_close(&mut self, code: CloseCode, reason: &str) {
@@ -229,7 +362,13 @@ impl Handler for Client {
         match code {
             CloseCode::Normal => info!("The remote server close the connection."),
             CloseCode::Away => info!("The remote server is leaving."),
-            _ => warn!("The remote server encountered an error: {}", reason),
+            _ => log_rate_limited!(
+                self.conn_meta_datas.node_full_id(),
+                *WS2P_REPEATED_LOG_INTERVAL_IN_SECS,
+                log::Level::Warn,
+                "The remote server encountered an error: {}",
+                reason
+            ),
         }
         let _result = self
             .conductor_sender
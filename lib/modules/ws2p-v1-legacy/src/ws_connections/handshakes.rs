@@ -0,0 +1,97 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Bounded pool of outgoing connections handshakes.
+//!
+//! Each outgoing connection is opened in its own thread, but only a limited number of handshakes
+//! are in progress at the same time: the other connections wait in a queue. A slot is released
+//! when the connection thread reports the end of the handshake through the WS2P main channel.
+
+use durs_network_documents::NodeFullId;
+use std::collections::{HashSet, VecDeque};
+
+#[derive(Debug, Clone, Default)]
+/// Outgoing connections handshakes
+pub struct HandshakesPool {
+    /// Connections waiting for a free slot
+    queue: VecDeque<NodeFullId>,
+    /// Connections whose handshake is in progress
+    in_progress: HashSet<NodeFullId>,
+}
+
+impl HandshakesPool {
+    /// Queue a connection (returns `false` if it is already queued or in progress)
+    pub fn push(&mut self, node_full_id: NodeFullId) -> bool {
+        if self.in_progress.contains(&node_full_id) || self.queue.contains(&node_full_id) {
+            false
+        } else {
+            self.queue.push_back(node_full_id);
+            true
+        }
+    }
+    /// Take the next queued connection if there is a free slot
+    pub fn next(&mut self, max_parallel_handshakes: usize) -> Option<NodeFullId> {
+        if self.in_progress.len() >= max_parallel_handshakes {
+            return None;
+        }
+        let node_full_id = self.queue.pop_front()?;
+        self.in_progress.insert(node_full_id);
+        Some(node_full_id)
+    }
+    /// Release the slot of a connection (returns `false` if its handshake was not in progress)
+    pub fn release(&mut self, node_full_id: &NodeFullId) -> bool {
+        self.in_progress.remove(node_full_id)
+    }
+    /// Number of handshakes in progress
+    pub fn in_progress(&self) -> usize {
+        self.in_progress.len()
+    }
+    /// Number of connections waiting for a free slot
+    pub fn queued(&self) -> usize {
+        self.queue.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dup_crypto::keys::PubKey;
+    use durs_network_documents::NodeId;
+
+    fn node_full_id(node_id: u32) -> NodeFullId {
+        NodeFullId(NodeId(node_id), PubKey::default())
+    }
+
+    #[test]
+    fn test_handshakes_pool() {
+        let mut pool = HandshakesPool::default();
+        assert!(pool.push(node_full_id(1)));
+        assert!(pool.push(node_full_id(2)));
+        assert!(pool.push(node_full_id(3)));
+        assert!(!pool.push(node_full_id(1)));
+
+        assert_eq!(Some(node_full_id(1)), pool.next(2));
+        assert_eq!(Some(node_full_id(2)), pool.next(2));
+        assert_eq!(None, pool.next(2));
+        assert!(!pool.push(node_full_id(2)));
+        assert_eq!((2, 1), (pool.in_progress(), pool.queued()));
+
+        assert!(pool.release(&node_full_id(1)));
+        assert!(!pool.release(&node_full_id(1)));
+        assert_eq!(Some(node_full_id(3)), pool.next(2));
+        assert_eq!(None, pool.next(2));
+        assert_eq!((2, 0), (pool.in_progress(), pool.queued()));
+    }
+}
@@ -0,0 +1,104 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Websocket keep-alive: ping the remote node at regular intervals and detect stale connections.
+//!
+//! The keep-alive is started only once the WS2P negotiation is over, so the remote node is never
+//! pinged before it has accepted the connection.
+
+use ws::util::Token;
+use ws::{Frame, OpCode};
+
+/// Token of the keep-alive timer
+pub const KEEP_ALIVE: Token = Token(3);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// Keep-alive state of a connection
+pub struct KeepAlive {
+    /// Interval between two pings (in seconds, 0 disables the keep-alive)
+    interval: u64,
+    /// Number of consecutive missed pongs from which the connection is stale
+    max_missed_pongs: u32,
+    /// Number of consecutive pings without pong
+    missed_pongs: u32,
+    /// The keep-alive timer is running
+    started: bool,
+}
+
+impl KeepAlive {
+    /// Create keep-alive state
+    pub fn new(interval: u64, max_missed_pongs: u32) -> Self {
+        KeepAlive {
+            interval,
+            max_missed_pongs,
+            missed_pongs: 0,
+            started: false,
+        }
+    }
+    /// Start the keep-alive.
+    /// Returns the delay of the keep-alive timer to schedule (in milliseconds), or `None` if the
+    /// keep-alive is disabled or already started.
+    pub fn start(&mut self) -> Option<u64> {
+        if self.interval == 0 || self.started {
+            return None;
+        }
+        self.started = true;
+        Some(self.delay())
+    }
+    /// Delay of the keep-alive timer (in milliseconds)
+    pub fn delay(&self) -> u64 {
+        self.interval * 1_000
+    }
+    /// Handle the keep-alive timer.
+    /// Returns `true` if the connection is stale, otherwise the remote node must be pinged again.
+    pub fn on_timeout(&mut self) -> bool {
+        if self.missed_pongs >= self.max_missed_pongs {
+            true
+        } else {
+            self.missed_pongs += 1;
+            false
+        }
+    }
+    /// Handle an incoming frame
+    pub fn on_frame(&mut self, frame: &Frame) {
+        if frame.opcode() == OpCode::Pong {
+            self.missed_pongs = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_start() {
+        assert_eq!(None, KeepAlive::new(0, 3).start());
+        let mut keep_alive = KeepAlive::new(30, 3);
+        assert_eq!(Some(30_000), keep_alive.start());
+        assert_eq!(None, keep_alive.start());
+    }
+
+    #[test]
+    fn test_missed_pongs() {
+        let mut keep_alive = KeepAlive::new(30, 2);
+        assert!(!keep_alive.on_timeout());
+        assert!(!keep_alive.on_timeout());
+        keep_alive.on_frame(&Frame::ping(Vec::new()));
+        assert!(keep_alive.on_timeout());
+        keep_alive.on_frame(&Frame::pong(Vec::new()));
+        assert!(!keep_alive.on_timeout());
+    }
+}
@@ -16,10 +16,15 @@
 //! Define ws2p connections messages.
 
 use super::*;
-use crate::ws_connections::requests::WS2Pv1ReqBody;
+use crate::ws_connections::requests::sent::send_request;
+use crate::ws_connections::requests::{WS2Pv1ReqBody, WS2Pv1ReqId, WS2Pv1Request};
+use crate::ws_connections::server::IncomingConnection;
 use dubp_block_doc::DocumentDUBP;
+use durs_common_tools::log_rate_limited;
+use durs_common_tools::timer::elapsed_since;
 use durs_network_documents::NodeFullId;
-use ws::Message;
+use durs_ws2p_pool::{ConnectionDirection, PoolError, WS2PApi};
+use ws::{CloseCode, Message};
 
 #[derive(Debug)]
 /// WS2Pv1 Message
@@ -37,6 +42,8 @@ pub enum WS2Pv1MsgPayload {
     TryToSendConnectMess,
     FailSendConnectMess,
     WebsocketOk(WsSender),
+    IncomingConnection(WsSender, IncomingConnection),
+    IncomingClose(u32),
     NegociationTimeout,
     ValidConnectMessage(String, WS2PConnectionState),
     ValidAckMessage(String, WS2PConnectionState),
@@ -53,7 +60,11 @@ pub enum WS2Pv1MsgPayload {
     WrongFormatMessage,
     UnknowMessage,
     Timeout,
+    KeepAliveTimeout,
+    Spam,
     Close,
+    /// The thread of an outgoing connection has ended
+    ConnectionThreadEnd,
 }
 
 pub fn generate_connect_message(
@@ -78,6 +89,29 @@ pub fn ws2p_recv_message_pretreatment(
     ws2p_module: &mut WS2Pv1Module,
     message: WS2Pv1Msg,
 ) -> WS2PSignal {
+    // Connection whose handshake is over (successfully or not)
+    let handshake_end = match message.payload {
+        WS2Pv1MsgPayload::WrongUrl
+        | WS2Pv1MsgPayload::FailOpenWS
+        | WS2Pv1MsgPayload::FailToSplitWS
+        | WS2Pv1MsgPayload::FailSendConnectMess
+        | WS2Pv1MsgPayload::NegociationTimeout
+        | WS2Pv1MsgPayload::Timeout
+        | WS2Pv1MsgPayload::KeepAliveTimeout
+        | WS2Pv1MsgPayload::Close
+        | WS2Pv1MsgPayload::ConnectionThreadEnd => Some(message.from),
+        _ => None,
+    };
+    let signal = recv_message_pretreatment(ws2p_module, message);
+    if let WS2PSignal::ConnectionEstablished(ws2p_full_id) = signal {
+        end_handshake(ws2p_module, &ws2p_full_id);
+    } else if let Some(ws2p_full_id) = handshake_end {
+        end_handshake(ws2p_module, &ws2p_full_id);
+    }
+    signal
+}
+
+fn recv_message_pretreatment(ws2p_module: &mut WS2Pv1Module, message: WS2Pv1Msg) -> WS2PSignal {
     check_timeout_requests(ws2p_module);
 
     let ws2p_full_id = message.from;
@@ -85,63 +119,104 @@ pub fn ws2p_recv_message_pretreatment(
         WS2Pv1MsgPayload::WrongUrl
         | WS2Pv1MsgPayload::FailOpenWS
         | WS2Pv1MsgPayload::FailToSplitWS => {
-            let dal_ep = ws2p_module
-                .ws2p_endpoints
-                .get_mut(&ws2p_full_id)
-                .expect("WS2P: Fail to get mut ep !");
-            dal_ep.state = WS2PConnectionState::WSError;
-            dal_ep.last_check = durs_common_tools::fns::time::current_timestamp();
+            ws2p_module
+                .connections
+                .set_endpoint_checked_state(&ws2p_full_id, WS2PConnectionState::WSError);
             return WS2PSignal::WSError(ws2p_full_id);
         }
         WS2Pv1MsgPayload::TryToSendConnectMess => {
             ws2p_module
-                .ws2p_endpoints
-                .get_mut(&ws2p_full_id)
-                .expect("WS2P: Fail to get mut ep !")
-                .state = WS2PConnectionState::TryToSendConnectMess;
+                .connections
+                .set_endpoint_state(&ws2p_full_id, WS2PConnectionState::TryToSendConnectMess);
         }
         WS2Pv1MsgPayload::FailSendConnectMess => {
-            let dal_ep = ws2p_module
-                .ws2p_endpoints
-                .get_mut(&ws2p_full_id)
-                .expect("WS2P: Fail to get mut ep !");
-            dal_ep.state = WS2PConnectionState::Unreachable;
-            dal_ep.last_check = durs_common_tools::fns::time::current_timestamp();
+            ws2p_module
+                .connections
+                .set_endpoint_checked_state(&ws2p_full_id, WS2PConnectionState::Unreachable);
         }
         WS2Pv1MsgPayload::WebsocketOk(sender) => {
-            ws2p_module.websockets.insert(ws2p_full_id, sender);
+            ws2p_module
+                .connections
+                .add_outgoing_connection(ws2p_full_id, sender);
+        }
+        WS2Pv1MsgPayload::IncomingConnection(sender, incoming_connection) => {
+            if ws2p_module.connections.is_connected(&ws2p_full_id) {
+                debug!(
+                    "WS2P: refuse incoming connection from {}: already connected.",
+                    incoming_connection.remote_addr
+                );
+                let _ = sender.0.close(CloseCode::Policy);
+            } else if ws2p_module.pool.free_rooms(
+                ConnectionDirection::Incoming,
+                ws2p_module.conf.incoming_quota,
+            ) == 0
+            {
+                debug!(
+                    "WS2P: refuse incoming connection from {}: incoming quota reached.",
+                    incoming_connection.remote_addr
+                );
+                let _ = sender.0.close(CloseCode::Again);
+            } else if let Err(PoolError::AlreadyConnected(api)) =
+                ws2p_module
+                    .pool
+                    .register(WS2PApi::V1, ws2p_full_id, ConnectionDirection::Incoming)
+            {
+                debug!(
+                    "WS2P: refuse incoming connection from {}: already connected with {:?}.",
+                    incoming_connection.remote_addr, api
+                );
+                let _ = sender.0.close(CloseCode::Policy);
+            } else {
+                info!(
+                    "WS2P: accept incoming connection from {} ({}).",
+                    incoming_connection.remote_addr, ws2p_full_id.1
+                );
+                ws2p_module.connections.add_incoming_connection(
+                    ws2p_full_id,
+                    sender,
+                    incoming_connection,
+                );
+                return WS2PSignal::ConnectionEstablished(ws2p_full_id);
+            }
+        }
+        WS2Pv1MsgPayload::IncomingClose(connection_id) => {
+            // Ignore the closing of a refused connection
+            if ws2p_module
+                .connections
+                .incoming_connection(&ws2p_full_id)
+                .map(|incoming_connection| incoming_connection.connection_id)
+                == Some(connection_id)
+            {
+                close_connection(
+                    ws2p_module,
+                    &ws2p_full_id,
+                    WS2PCloseConnectionReason::Unknow,
+                );
+            }
         }
         WS2Pv1MsgPayload::ValidConnectMessage(response, new_con_state) => {
             ws2p_module
-                .ws2p_endpoints
-                .get_mut(&ws2p_full_id)
-                .expect("WS2P: Fail to get mut ep !")
-                .state = new_con_state;
+                .connections
+                .set_endpoint_state(&ws2p_full_id, new_con_state);
             debug!("Send: {:#?}", response);
-            if let Some(websocket) = ws2p_module.websockets.get_mut(&ws2p_full_id) {
+            if let Some(websocket) = ws2p_module.connections.websocket(&ws2p_full_id) {
                 if websocket.0.send(Message::text(response)).is_err() {
                     return WS2PSignal::WSError(ws2p_full_id);
                 }
             } else {
                 // Connection closed by remote peer
-                let dal_ep = ws2p_module
-                    .ws2p_endpoints
-                    .get_mut(&ws2p_full_id)
-                    .expect("WS2P: Fail to get mut ep !");
-                dal_ep.state = WS2PConnectionState::Close;
-                dal_ep.last_check = durs_common_tools::fns::time::current_timestamp();
+                ws2p_module
+                    .connections
+                    .set_endpoint_checked_state(&ws2p_full_id, WS2PConnectionState::Close);
             }
         }
         WS2Pv1MsgPayload::ValidAckMessage(response, new_con_state) => {
             ws2p_module
-                .ws2p_endpoints
-                .get_mut(&ws2p_full_id)
-                .expect("WS2P: Fail to get mut ep !")
-                .state = new_con_state;
-            if let WS2PConnectionState::AckMessOk = ws2p_module.ws2p_endpoints[&ws2p_full_id].state
-            {
+                .connections
+                .set_endpoint_state(&ws2p_full_id, new_con_state);
+            if let WS2PConnectionState::AckMessOk = new_con_state {
                 debug!("Send: {:#?}", response);
-                if let Some(websocket) = ws2p_module.websockets.get_mut(&ws2p_full_id) {
+                if let Some(websocket) = ws2p_module.connections.websocket(&ws2p_full_id) {
                     if websocket.0.send(Message::text(response)).is_err() {
                         return WS2PSignal::WSError(ws2p_full_id);
                     }
@@ -152,14 +227,31 @@ pub fn ws2p_recv_message_pretreatment(
         }
         WS2Pv1MsgPayload::ValidOk(new_con_state) => {
             ws2p_module
-                .ws2p_endpoints
-                .get_mut(&ws2p_full_id)
-                .expect("WS2P: Fail to get mut ep !")
-                .state = new_con_state;
+                .connections
+                .set_endpoint_state(&ws2p_full_id, new_con_state);
             let mut close_conn = false;
-            let signal = match ws2p_module.ws2p_endpoints[&ws2p_full_id].state {
+            let signal = match new_con_state {
                 WS2PConnectionState::OkMessOkWaitingAckMess => WS2PSignal::Empty,
-                WS2PConnectionState::Established => WS2PSignal::ConnectionEstablished(ws2p_full_id),
+                WS2PConnectionState::Established => {
+                    if let Err(PoolError::AlreadyConnected(api)) = ws2p_module.pool.register(
+                        WS2PApi::V1,
+                        ws2p_full_id,
+                        ConnectionDirection::Outgoing,
+                    ) {
+                        debug!(
+                            "WS2P: close connection with {}: already connected with {:?}.",
+                            ws2p_full_id, api
+                        );
+                        close_conn = true;
+                        WS2PSignal::Empty
+                    } else {
+                        if let Some(endpoint) = ws2p_module.connections.endpoint_mut(&ws2p_full_id)
+                        {
+                            endpoint.stats.established_connections += 1;
+                        }
+                        WS2PSignal::ConnectionEstablished(ws2p_full_id)
+                    }
+                }
                 _ => {
                     close_conn = true;
                     WS2PSignal::Empty
@@ -189,16 +281,26 @@ pub fn ws2p_recv_message_pretreatment(
             let mut applied_heads = Vec::with_capacity(heads.len());
             for head in heads {
                 if let Ok(head) = NetworkHead::from_json_value(&head) {
-                    if head.verify()
-                        && (ws2p_module.my_head.is_none()
-                            || head.node_full_id()
-                                != ws2p_module
-                                    .my_head
-                                    .clone()
-                                    .expect("WS2P: Fail to clone my_head")
-                                    .node_full_id())
-                        && head.apply(&mut ws2p_module.heads_cache)
+                    let valid_sig = head.verify();
+                    let score = ws2p_module
+                        .conformance_scores
+                        .entry(ws2p_full_id)
+                        .or_default();
+                    if valid_sig {
+                        score.valid_sigs += 1;
+                    } else {
+                        score.invalid_sigs += 1;
+                    }
+                    if valid_sig
+                        && !ws2p_module.ban_list.is_banned(&head.pubkey())
+                        && ws2p_module.heads.apply(&head)
                     {
+                        if let Some(endpoint) =
+                            ws2p_module.connections.endpoint_mut(&head.node_full_id())
+                        {
+                            endpoint.stats.last_head_time =
+                                durs_common_tools::fns::time::current_timestamp();
+                        }
                         applied_heads.push(head);
                     }
                 }
@@ -218,9 +320,24 @@ pub fn ws2p_recv_message_pretreatment(
                 ref requester_module,
                 ref req_body,
                 ref recipient_node,
+                timestamp,
                 ..
-            }) = ws2p_module.requests_awaiting_response.remove(&ws2p_req_id)
+            }) = ws2p_module.requests.take_sent(&ws2p_req_id)
             {
+                let response_time = elapsed_since(Instant::now(), timestamp);
+                ws2p_module
+                    .metrics
+                    .record_response_time(*recipient_node, response_time);
+                if let Some(endpoint) = ws2p_module.connections.endpoint_mut(recipient_node) {
+                    endpoint
+                        .stats
+                        .record_latency(response_time.as_millis() as u64);
+                }
+                ws2p_module
+                    .conformance_scores
+                    .entry(*recipient_node)
+                    .or_default()
+                    .answered_requests += 1;
                 return WS2PSignal::ReqResponse(
                     *requester_module,
                     *req_body,
@@ -230,29 +347,19 @@ pub fn ws2p_recv_message_pretreatment(
             }
         }
         WS2Pv1MsgPayload::NegociationTimeout => {
-            match ws2p_module.ws2p_endpoints[&ws2p_full_id].state {
-                WS2PConnectionState::AckMessOk | WS2PConnectionState::ConnectMessOk => {
-                    ws2p_module
-                        .ws2p_endpoints
-                        .get_mut(&ws2p_full_id)
-                        .expect("WS2P: Fail to get mut ep !")
-                        .state = WS2PConnectionState::Denial
-                }
-                WS2PConnectionState::WaitingConnectMess => {
+            ws2p_module.metrics.handshake_failures += 1;
+            match ws2p_module.connections.endpoint_state(&ws2p_full_id) {
+                Some(WS2PConnectionState::AckMessOk) | Some(WS2PConnectionState::ConnectMessOk) => {
                     ws2p_module
-                        .ws2p_endpoints
-                        .get_mut(&ws2p_full_id)
-                        .expect("WS2P: Fail to get mut ep !")
-                        .state = WS2PConnectionState::NoResponse
-                }
-                _ => {
-                    let dal_ep = ws2p_module
-                        .ws2p_endpoints
-                        .get_mut(&ws2p_full_id)
-                        .expect("WS2P: Fail to get mut ep !");
-                    dal_ep.state = WS2PConnectionState::Unreachable;
-                    dal_ep.last_check = durs_common_tools::fns::time::current_timestamp();
+                        .connections
+                        .set_endpoint_state(&ws2p_full_id, WS2PConnectionState::Denial)
                 }
+                Some(WS2PConnectionState::WaitingConnectMess) => ws2p_module
+                    .connections
+                    .set_endpoint_state(&ws2p_full_id, WS2PConnectionState::NoResponse),
+                _ => ws2p_module
+                    .connections
+                    .set_endpoint_checked_state(&ws2p_full_id, WS2PConnectionState::Unreachable),
             }
             close_connection(
                 ws2p_module,
@@ -269,42 +376,128 @@ pub fn ws2p_recv_message_pretreatment(
             );
             return WS2PSignal::Timeout(ws2p_full_id);
         }
+        WS2Pv1MsgPayload::KeepAliveTimeout => {
+            close_connection(
+                ws2p_module,
+                &ws2p_full_id,
+                WS2PCloseConnectionReason::KeepAliveTimeout,
+            );
+            return WS2PSignal::KeepAliveTimeout(ws2p_full_id);
+        }
         WS2Pv1MsgPayload::UnknowMessage => {}
-        WS2Pv1MsgPayload::WrongFormatMessage => warn!(
+        WS2Pv1MsgPayload::WrongFormatMessage => log_rate_limited!(
+            ws2p_full_id.1,
+            *WS2P_REPEATED_LOG_INTERVAL_IN_SECS,
+            log::Level::Warn,
             "WS2P : Receive Wrong Format Message from {}.",
             &ws2p_full_id.1
         ),
-        WS2Pv1MsgPayload::InvalidMessage => return WS2PSignal::Empty,
-        WS2Pv1MsgPayload::Close => close_connection(
-            ws2p_module,
-            &ws2p_full_id,
-            WS2PCloseConnectionReason::AuthMessInvalidSig,
-        ),
+        WS2Pv1MsgPayload::InvalidMessage | WS2Pv1MsgPayload::ConnectionThreadEnd => {
+            return WS2PSignal::Empty
+        }
+        WS2Pv1MsgPayload::Spam => {
+            log_rate_limited!(
+                ws2p_full_id.1,
+                *WS2P_REPEATED_LOG_INTERVAL_IN_SECS,
+                log::Level::Warn,
+                "WS2P : {} is flooding us.",
+                &ws2p_full_id.1
+            );
+            ws2p_module
+                .conformance_scores
+                .entry(ws2p_full_id)
+                .or_default()
+                .flood_violations += 1;
+        }
+        WS2Pv1MsgPayload::Close => {
+            // Connection closed by the remote node before the end of the handshake
+            if let Some(state) = ws2p_module.connections.endpoint_state(&ws2p_full_id) {
+                if state != WS2PConnectionState::Established {
+                    ws2p_module.metrics.handshake_failures += 1;
+                }
+            }
+            close_connection(
+                ws2p_module,
+                &ws2p_full_id,
+                WS2PCloseConnectionReason::AuthMessInvalidSig,
+            )
+        }
     }
-    let connections_count = ws2p_module.websockets.len();
-    if connections_count == 0 {
+    if ws2p_module.connections.count_connections() == 0 {
         return WS2PSignal::NoConnection;
     }
     WS2PSignal::Empty
 }
 
-fn check_timeout_requests(ws2p_module: &mut WS2Pv1Module) {
-    // Detect timeout requests
-    let mut requests_timeout = Vec::new();
+pub fn check_timeout_requests(ws2p_module: &mut WS2Pv1Module) {
+    let requests_timeout = ws2p_module.requests.take_timed_out(
+        Instant::now(),
+        Duration::from_secs(*WS2P_V1_REQUESTS_TIMEOUT_IN_SECS),
+    );
+    for (ws2p_req_id, pending_req_infos) in requests_timeout {
+        log_rate_limited!(
+            pending_req_infos.recipient_node,
+            *WS2P_REPEATED_LOG_INTERVAL_IN_SECS,
+            log::Level::Warn,
+            "request timeout : {:?} (sent to {:?})",
+            pending_req_infos.req_body,
+            pending_req_infos.recipient_node
+        );
+        ws2p_module
+            .conformance_scores
+            .entry(pending_req_infos.recipient_node)
+            .or_default()
+            .timeout_requests += 1;
+        retry_timeout_request(ws2p_module, ws2p_req_id, pending_req_infos);
+    }
+}
 
-    for (ws2p_req_id, pending_req_infos) in ws2p_module.requests_awaiting_response.iter() {
-        if unwrap!(SystemTime::now().duration_since(pending_req_infos.timestamp))
-            > Duration::from_secs(*WS2P_V1_REQUESTS_TIMEOUT_IN_SECS)
-        {
-            requests_timeout.push(*ws2p_req_id);
-            warn!(
-                "request timeout : {:?} (sent to {:?})",
-                pending_req_infos.req_body, pending_req_infos.recipient_node
+/// Re-issue a timed out request to another established connection,
+/// or notify the requester module once all retries are exhausted
+fn retry_timeout_request(
+    ws2p_module: &mut WS2Pv1Module,
+    ws2p_req_id: WS2Pv1ReqId,
+    pending_req_infos: WS2Pv1PendingReqInfos,
+) {
+    if pending_req_infos.retries < *WS2P_V1_REQUESTS_MAX_RETRIES {
+        let other_connections: HashSet<&NodeFullId> = ws2p_module
+            .connections
+            .connected_nodes()
+            .filter(|node_full_id| **node_full_id != pending_req_infos.recipient_node)
+            .collect();
+        if !other_connections.is_empty() {
+            let node_full_id = get_random_connection(other_connections);
+            debug!(
+                "WS2P: re-issue request {:?} to {} (retry {}/{})",
+                pending_req_infos.req_body,
+                node_full_id,
+                pending_req_infos.retries + 1,
+                *WS2P_V1_REQUESTS_MAX_RETRIES
             );
+            let request = WS2Pv1Request {
+                id: ws2p_req_id,
+                body: pending_req_infos.req_body,
+            };
+            match send_request(
+                ws2p_module,
+                pending_req_infos.requester_module,
+                &node_full_id,
+                &request,
+                pending_req_infos.retries + 1,
+            ) {
+                Ok(()) => return,
+                Err(e) => warn!("WS2P: fail to re-issue request: {}", e),
+            }
         }
     }
-    // Delete timeout requests
-    for ws2p_req_id in requests_timeout {
-        let _request_option = ws2p_module.requests_awaiting_response.remove(&ws2p_req_id);
+    // Requests of the WS2Pv1 module itself need no notification
+    let ModuleReqFullId(requester, module_req_id) = pending_req_infos.requester_module;
+    if requester != WS2Pv1Module::name() {
+        crate::responses::sent::send_network_req_response(
+            ws2p_module,
+            requester,
+            module_req_id,
+            NetworkResponse::RequestTimeout(ModuleReqFullId(WS2Pv1Module::name(), module_req_id)),
+        );
     }
 }
@@ -70,11 +70,15 @@ impl WS2PConnectionMetaDatas {
                     "CONNECT" => {
                         let message = WS2PConnectMessageV1::parse(msg, currency.to_string())
                             .expect("Failed to parsing CONNECT Message !");
-                        if message.verify() && message.pubkey == unwrap!(self.remote_pubkey) {
+                        // The remote public key is unknown for incoming connections
+                        if message.verify()
+                            && self.remote_pubkey.unwrap_or(message.pubkey) == message.pubkey
+                        {
                             match self.state {
                                 WS2PConnectionState::WaitingConnectMess => {
                                     debug!("CONNECT sig is valid.");
                                     self.state = WS2PConnectionState::ConnectMessOk;
+                                    self.remote_pubkey = Some(message.pubkey);
                                     self.remote_challenge = message.challenge;
                                     let mut response = WS2PAckMessageV1 {
                                         currency: currency.to_string(),
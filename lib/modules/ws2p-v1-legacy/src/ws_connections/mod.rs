@@ -15,19 +15,28 @@
 
 //! Manage websockets connections.
 
+#[cfg(feature = "chaos")]
+pub mod chaos;
 pub mod handler;
+pub mod handshakes;
+pub mod keep_alive;
 pub mod messages;
 mod meta_datas;
+pub mod pool;
+pub mod proxy;
 pub mod requests;
 pub mod responses;
+pub mod server;
 pub mod states;
 
 use crate::*;
 use dup_crypto::keys::*;
 use dup_crypto::rand;
+use durs_common_tools::fns::thread::spawn_child;
 use durs_network_documents::network_endpoint::EndpointV1;
+use durs_ws2p_pool::{ConnectionDirection, WS2PApi};
+use messages::WS2Pv1MsgPayload;
 use states::WS2PConnectionState;
-use std::cmp::Ordering;
 use std::collections::HashSet;
 #[allow(deprecated)]
 use ws::Sender;
@@ -44,6 +53,7 @@ impl ::std::fmt::Debug for WsSender {
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum WS2PCloseConnectionReason {
     AuthMessInvalidSig,
+    KeepAliveTimeout,
     NegociationTimeout,
     Timeout,
     WsError,
@@ -52,97 +62,185 @@ pub enum WS2PCloseConnectionReason {
 
 pub fn connect_to_know_endpoints(ws2p_module: &mut WS2Pv1Module) {
     info!("WS2P: connect to know endpoints...");
-    let mut count_established_connections = 0;
-    let mut pubkeys = HashSet::new();
+    let now = durs_common_tools::fns::time::current_timestamp();
     let mut reachable_endpoints = Vec::new();
     let mut unreachable_endpoints = Vec::new();
-    for (_ws2p_full_id, DbEndpoint { ep, state, .. }) in ws2p_module.ws2p_endpoints.clone() {
-        if ep.issuer == ws2p_module.key_pair.public_key() || !pubkeys.contains(&ep.issuer) {
-            match state {
-                WS2PConnectionState::Established => count_established_connections += 1,
-                WS2PConnectionState::NeverTry
-                | WS2PConnectionState::Close
-                | WS2PConnectionState::Denial => {
-                    pubkeys.insert(ep.issuer);
-                    if ws2p_module.ssl || ep.port != 443 {
-                        reachable_endpoints.push(ep);
-                    }
-                }
-                _ => {
-                    pubkeys.insert(ep.issuer);
-                    unreachable_endpoints.push(ep);
+    let mut greylisted_endpoints = Vec::new();
+    for (
+        ws2p_full_id,
+        DbEndpoint {
+            ep, state, stats, ..
+        },
+    ) in ws2p_module.connections.endpoints().iter()
+    {
+        if ws2p_module.ban_list.is_banned(&ep.issuer) {
+            continue;
+        }
+        if state == &WS2PConnectionState::Established
+            || ws2p_module
+                .pool
+                .is_connected_with_other_api(WS2PApi::V1, ws2p_full_id)
+        {
+            continue;
+        }
+        let score = stats.score(now, ws2p_module.conf.prefered_pubkeys.contains(&ep.issuer));
+        let reachable =
+            ws2p_module.conf.outgoing_route(ep, ws2p_module.ssl) != WS2POutgoingRoute::Unreachable;
+        if ws2p_module.ban_list.is_greylisted(&ep.issuer) {
+            // Greylisted peers are only tried when no other endpoint is available
+            if reachable {
+                greylisted_endpoints.push((score, ep));
+            }
+            continue;
+        }
+        match state {
+            WS2PConnectionState::NeverTry
+            | WS2PConnectionState::Close
+            | WS2PConnectionState::Denial => {
+                if reachable {
+                    reachable_endpoints.push((score, ep));
                 }
             }
+            _ => unreachable_endpoints.push((score, ep)),
         }
     }
-    if !ws2p_module.conf.prefered_pubkeys.is_empty() {
-        reachable_endpoints.sort_unstable_by(|ep1, ep2| {
-            if ws2p_module.conf.prefered_pubkeys.contains(&ep1.issuer) {
-                if ws2p_module.conf.prefered_pubkeys.contains(&ep2.issuer) {
-                    Ordering::Equal
-                } else {
-                    Ordering::Greater
-                }
-            } else {
-                Ordering::Less
+    // Best scores first, then keep only the best endpoint of each pubkey
+    let my_pubkey = ws2p_module.key_pair.public_key();
+    let mut pubkeys = HashSet::new();
+    let mut candidates = Vec::new();
+    for endpoints in &mut [
+        &mut reachable_endpoints,
+        &mut unreachable_endpoints,
+        &mut greylisted_endpoints,
+    ] {
+        endpoints.sort_by(|(score1, _), (score2, _)| score2.cmp(score1));
+        for (_score, ep) in endpoints.drain(..) {
+            if ep.issuer == my_pubkey || pubkeys.insert(ep.issuer) {
+                candidates.push(unwrap!(ep.node_full_id()));
             }
-        });
+        }
     }
-    let mut free_outcoming_rooms =
-        ws2p_module.conf.clone().outcoming_quota - count_established_connections;
-    while free_outcoming_rooms > 0 {
-        let ep = if !reachable_endpoints.is_empty() {
-            reachable_endpoints
-                .pop()
-                .expect("WS2P: Fail to pop() reachable_endpoints !")
-        } else if !unreachable_endpoints.is_empty() {
-            unreachable_endpoints
-                .pop()
-                .expect("WS2P: Fail to pop() unreachable_endpoints !")
-        } else {
-            break;
-        };
-        connect_to_without_checking_quotas(ws2p_module, unwrap!(ep.node_full_id()));
-        free_outcoming_rooms -= 1;
+    // The outgoing quota is shared with the other WS2P modules
+    let free_outcoming_rooms = ws2p_module.pool.free_rooms(
+        ConnectionDirection::Outgoing,
+        ws2p_module.conf.outcoming_quota,
+    );
+    for node_full_id in candidates.into_iter().take(free_outcoming_rooms) {
+        connect_to_without_checking_quotas(ws2p_module, node_full_id);
     }
 }
 
 pub fn connect_to(ws2p_module: &mut WS2Pv1Module, ep: &EndpointV1) {
+    if ws2p_module.ban_list.is_banned(&ep.issuer) {
+        debug!("WS2P: ignore endpoint of banned pubkey {}", ep.issuer);
+        return;
+    }
     // Add endpoint to endpoints list (if there isn't already)
     let node_full_id = ep
         .node_full_id()
         .expect("WS2P: Fail to get ep.node_full_id() !");
-    ws2p_module
-        .ws2p_endpoints
-        .entry(node_full_id)
-        .or_insert(DbEndpoint {
+    ws2p_module.connections.insert_endpoint_if_absent(
+        node_full_id,
+        DbEndpoint {
             ep: ep.clone(),
             state: WS2PConnectionState::NeverTry,
             last_check: 0,
-        });
-    let count_established_connections = count_established_connections(&ws2p_module);
-    if ws2p_module.conf.outcoming_quota > count_established_connections {
+            stats: EndpointStats::default(),
+        },
+    );
+    if ws2p_module.pool.free_rooms(
+        ConnectionDirection::Outgoing,
+        ws2p_module.conf.outcoming_quota,
+    ) > 0
+        && !ws2p_module
+            .pool
+            .is_connected_with_other_api(WS2PApi::V1, &node_full_id)
+    {
         connect_to_without_checking_quotas(ws2p_module, node_full_id);
     }
 }
 
+/// Queue an outgoing connection, it is opened as soon as a handshake slot is free
 pub fn connect_to_without_checking_quotas(
     ws2p_module: &mut WS2Pv1Module,
     node_full_id: NodeFullId,
 ) {
-    let endpoint = unwrap!(ws2p_module.ws2p_endpoints.get(&node_full_id));
+    if ws2p_module.handshakes.push(node_full_id) {
+        start_queued_handshakes(ws2p_module);
+    }
+}
+
+/// Open the queued outgoing connections while there are free handshake slots
+pub fn start_queued_handshakes(ws2p_module: &mut WS2Pv1Module) {
+    while let Some(node_full_id) = ws2p_module
+        .handshakes
+        .next(ws2p_module.conf.max_parallel_handshakes)
+    {
+        if !open_connection(ws2p_module, node_full_id) {
+            ws2p_module.handshakes.release(&node_full_id);
+        }
+    }
+}
+
+/// Release the handshake slot of an outgoing connection
+pub fn end_handshake(ws2p_module: &mut WS2Pv1Module, node_full_id: &NodeFullId) {
+    if ws2p_module.handshakes.release(node_full_id) {
+        start_queued_handshakes(ws2p_module);
+    }
+}
+
+/// Open an outgoing connection in a dedicated thread (returns `false` if the endpoint is unreachable)
+fn open_connection(ws2p_module: &mut WS2Pv1Module, node_full_id: NodeFullId) -> bool {
+    let endpoint = unwrap!(ws2p_module.connections.endpoint_mut(&node_full_id));
+    endpoint.stats.connection_attempts += 1;
+    let route = ws2p_module
+        .conf
+        .outgoing_route(&endpoint.ep, ws2p_module.ssl);
+    if route == WS2POutgoingRoute::Unreachable {
+        debug!(
+            "WS2P: endpoint {} is unreachable.",
+            endpoint.ep.raw_endpoint
+        );
+        endpoint.state = WS2PConnectionState::Unreachable;
+        endpoint.last_check = durs_common_tools::fns::time::current_timestamp();
+        return false;
+    }
     let endpoint_copy = endpoint.ep.clone();
     let conductor_sender_copy = ws2p_module.main_thread_channel.0.clone();
     let currency_copy = ws2p_module.conf.currency.clone();
     let key_pair_copy = ws2p_module.key_pair.clone();
-    thread::spawn(move || {
-        let _result = crate::ws_connections::handler::connect_to_ws2p_endpoint(
+    let keep_alive = ws2p_module.conf.keep_alive();
+    let pinned_certificate = ws2p_module
+        .conf
+        .pinned_certificates
+        .get(&endpoint_copy.issuer)
+        .copied();
+    spawn_child(&format!("conn::{}", endpoint_copy.issuer), move || {
+        let payload = match handler::connect_to_ws2p_endpoint(
             &endpoint_copy,
             &conductor_sender_copy,
             &currency_copy.expect("WS2PError : No currency !").0,
             &key_pair_copy,
-        );
+            &route,
+            keep_alive,
+            pinned_certificate,
+        ) {
+            Ok(()) => WS2Pv1MsgPayload::ConnectionThreadEnd,
+            Err(e) => {
+                debug!(
+                    "WS2P: fail to open connection to {}: {}",
+                    endpoint_copy.raw_endpoint, e
+                );
+                WS2Pv1MsgPayload::FailOpenWS
+            }
+        };
+        // Report the end of the connection thread to release its handshake slot
+        let _ = conductor_sender_copy.send(WS2PThreadSignal::WS2Pv1Msg(WS2Pv1Msg {
+            from: node_full_id,
+            payload,
+        }));
     });
+    true
 }
 
 pub fn close_connection(
@@ -153,19 +251,20 @@ pub fn close_connection(
     match reason {
         WS2PCloseConnectionReason::NegociationTimeout => {}
         WS2PCloseConnectionReason::AuthMessInvalidSig
+        | WS2PCloseConnectionReason::KeepAliveTimeout
         | WS2PCloseConnectionReason::Timeout
         | WS2PCloseConnectionReason::WsError
         | WS2PCloseConnectionReason::Unknow => {
-            if let Some(dal_ep) = ws2p_module.ws2p_endpoints.get_mut(ws2p_full_id) {
-                dal_ep.state = WS2PConnectionState::Close;
-                dal_ep.last_check = durs_common_tools::fns::time::current_timestamp();
-            }
+            ws2p_module
+                .connections
+                .set_endpoint_checked_state(ws2p_full_id, WS2PConnectionState::Close);
         }
     }
-    if let Some(websocket) = ws2p_module.websockets.get(&ws2p_full_id) {
+    ws2p_module.pool.unregister(WS2PApi::V1, ws2p_full_id);
+    if let Some(websocket) = ws2p_module.connections.remove_connection(ws2p_full_id) {
         let _result = websocket.0.close(ws::CloseCode::Normal);
     }
-    let _result = ws2p_module.websockets.remove(ws2p_full_id);
+    ws2p_module.heads.remove_connection(ws2p_full_id);
 }
 
 pub fn get_random_connection<S: ::std::hash::BuildHasher>(
@@ -185,13 +284,3 @@ pub fn get_random_connection<S: ::std::hash::BuildHasher>(
     }
     last_node_full_id.expect("ws2p connections set must be not empty !")
 }
-
-pub fn count_established_connections(ws2p_module: &WS2Pv1Module) -> usize {
-    let mut count_established_connections = 0;
-    for DbEndpoint { state, .. } in ws2p_module.ws2p_endpoints.values() {
-        if let WS2PConnectionState::Established = state {
-            count_established_connections += 1;
-        }
-    }
-    count_established_connections
-}
@@ -0,0 +1,276 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Pool of the known endpoints and of the opened connections.
+
+use super::server::IncomingConnection;
+use super::states::WS2PConnectionState;
+use super::WsSender;
+use crate::ws2p_db::{DbEndpoint, DbEndpoints, Ws2pPeersDbError};
+use durs_network_documents::NodeFullId;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Known endpoints and opened connections.
+///
+/// A node has a websocket as long as its connection is open, and an incoming connection
+/// entry only if this connection was opened by the remote node.
+#[derive(Debug, Default)]
+pub struct ConnectionsPool {
+    endpoints: DbEndpoints,
+    websockets: HashMap<NodeFullId, WsSender>,
+    incoming_connections: HashMap<NodeFullId, IncomingConnection>,
+}
+
+impl ConnectionsPool {
+    /// Create a pool with already known endpoints
+    pub fn new(endpoints: DbEndpoints) -> Self {
+        ConnectionsPool {
+            endpoints,
+            ..ConnectionsPool::default()
+        }
+    }
+    /// Known endpoints
+    pub fn endpoints(&self) -> &DbEndpoints {
+        &self.endpoints
+    }
+    /// Get known endpoint of a node
+    pub fn endpoint(&self, node_full_id: &NodeFullId) -> Option<&DbEndpoint> {
+        self.endpoints.get(node_full_id)
+    }
+    /// Get mutable known endpoint of a node
+    pub fn endpoint_mut(&mut self, node_full_id: &NodeFullId) -> Option<&mut DbEndpoint> {
+        self.endpoints.get_mut(node_full_id)
+    }
+    /// Get the connection state of a known endpoint
+    pub fn endpoint_state(&self, node_full_id: &NodeFullId) -> Option<WS2PConnectionState> {
+        self.endpoints
+            .get(node_full_id)
+            .map(|endpoint| endpoint.state)
+    }
+    /// Change the connection state of a known endpoint
+    pub fn set_endpoint_state(&mut self, node_full_id: &NodeFullId, state: WS2PConnectionState) {
+        if let Some(endpoint) = self.endpoints.get_mut(node_full_id) {
+            endpoint.state = state;
+        }
+    }
+    /// Change the connection state of a known endpoint and update the time of its last check
+    pub fn set_endpoint_checked_state(
+        &mut self,
+        node_full_id: &NodeFullId,
+        state: WS2PConnectionState,
+    ) {
+        if let Some(endpoint) = self.endpoints.get_mut(node_full_id) {
+            endpoint.state = state;
+            endpoint.last_check = durs_common_tools::fns::time::current_timestamp();
+        }
+    }
+    /// Insert or replace an endpoint
+    pub fn insert_endpoint(&mut self, node_full_id: NodeFullId, endpoint: DbEndpoint) {
+        self.endpoints.insert(node_full_id, endpoint);
+    }
+    /// Insert an endpoint only if the node has no known endpoint
+    pub fn insert_endpoint_if_absent(&mut self, node_full_id: NodeFullId, endpoint: DbEndpoint) {
+        self.endpoints.insert_if_absent(node_full_id, endpoint);
+    }
+    /// Insert or replace several endpoints
+    pub fn extend_endpoints<I: IntoIterator<Item = (NodeFullId, DbEndpoint)>>(
+        &mut self,
+        endpoints: I,
+    ) {
+        self.endpoints.extend(endpoints);
+    }
+    /// Write the known endpoints in file if at least one of them was modified
+    pub fn save_endpoints(&mut self, file_path: &Path) -> Result<bool, Ws2pPeersDbError> {
+        self.endpoints.save(file_path)
+    }
+    /// Number of established outgoing connections
+    pub fn count_established_outgoing(&self) -> usize {
+        self.endpoints
+            .values()
+            .filter(|DbEndpoint { state, .. }| *state == WS2PConnectionState::Established)
+            .count()
+    }
+    /// Number of established connections (outgoing and incoming)
+    pub fn count_established(&self) -> usize {
+        self.count_established_outgoing() + self.incoming_connections.len()
+    }
+    /// Is the connection with this node established ?
+    pub fn is_established(&self, node_full_id: &NodeFullId) -> bool {
+        self.incoming_connections.contains_key(node_full_id)
+            || self.endpoint_state(node_full_id) == Some(WS2PConnectionState::Established)
+    }
+    /// Get the url of a connection (the remote address for incoming connections)
+    pub fn connection_url(&self, node_full_id: &NodeFullId) -> String {
+        if let Some(DbEndpoint { ep, .. }) = self.endpoints.get(node_full_id) {
+            ep.get_url(false, false).expect("Endpoint unreachable !")
+        } else if let Some(incoming_connection) = self.incoming_connections.get(node_full_id) {
+            incoming_connection.remote_addr.clone()
+        } else {
+            String::new()
+        }
+    }
+    /// Get the websocket of an opened connection
+    pub fn websocket(&self, node_full_id: &NodeFullId) -> Option<&WsSender> {
+        self.websockets.get(node_full_id)
+    }
+    /// Websockets of all opened connections
+    pub fn websockets(&self) -> impl Iterator<Item = (&NodeFullId, &WsSender)> {
+        self.websockets.iter()
+    }
+    /// Nodes with an opened connection
+    pub fn connected_nodes(&self) -> impl Iterator<Item = &NodeFullId> {
+        self.websockets.keys()
+    }
+    /// Is there an opened connection with this node ?
+    pub fn is_connected(&self, node_full_id: &NodeFullId) -> bool {
+        self.websockets.contains_key(node_full_id)
+    }
+    /// Number of opened connections
+    pub fn count_connections(&self) -> usize {
+        self.websockets.len()
+    }
+    /// Register the websocket of an outgoing connection
+    pub fn add_outgoing_connection(&mut self, node_full_id: NodeFullId, websocket: WsSender) {
+        self.websockets.insert(node_full_id, websocket);
+    }
+    /// Register an accepted incoming connection
+    pub fn add_incoming_connection(
+        &mut self,
+        node_full_id: NodeFullId,
+        websocket: WsSender,
+        incoming_connection: IncomingConnection,
+    ) {
+        self.websockets.insert(node_full_id, websocket);
+        self.incoming_connections
+            .insert(node_full_id, incoming_connection);
+    }
+    /// Get an accepted incoming connection
+    pub fn incoming_connection(&self, node_full_id: &NodeFullId) -> Option<&IncomingConnection> {
+        self.incoming_connections.get(node_full_id)
+    }
+    /// Nodes connected with an incoming connection
+    pub fn incoming_nodes(&self) -> impl Iterator<Item = &NodeFullId> {
+        self.incoming_connections.keys()
+    }
+    /// Number of accepted incoming connections
+    pub fn count_incoming(&self) -> usize {
+        self.incoming_connections.len()
+    }
+    /// Forget the connection with a node, returns its websocket
+    pub fn remove_connection(&mut self, node_full_id: &NodeFullId) -> Option<WsSender> {
+        self.incoming_connections.remove(node_full_id);
+        self.websockets.remove(node_full_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ws2p_db::EndpointStats;
+    use dup_crypto::keys::PubKey;
+    use durs_network_documents::network_endpoint::EndpointV1;
+
+    fn endpoint(raw_endpoint: &str, state: WS2PConnectionState) -> (NodeFullId, DbEndpoint) {
+        let ep = EndpointV1::parse_from_raw(raw_endpoint, PubKey::default(), 0, 0)
+            .expect("invalid endpoint");
+        (
+            ep.node_full_id().expect("endpoint without node id"),
+            DbEndpoint {
+                ep,
+                state,
+                last_check: 0,
+                stats: EndpointStats::default(),
+            },
+        )
+    }
+
+    struct NoopHandler;
+
+    impl ws::Handler for NoopHandler {}
+
+    fn websocket() -> WsSender {
+        WsSender(
+            ws::WebSocket::new(|_| NoopHandler)
+                .expect("fail to create websocket")
+                .broadcaster(),
+        )
+    }
+
+    #[test]
+    fn endpoints_states() {
+        let (node1, endpoint1) = endpoint(
+            "WS2P 11111111 g1.durs.info 20901",
+            WS2PConnectionState::NeverTry,
+        );
+        let (node2, endpoint2) = endpoint(
+            "WS2P 22222222 g1.durs.info 20902",
+            WS2PConnectionState::Established,
+        );
+        let mut pool = ConnectionsPool::default();
+        pool.extend_endpoints(vec![(node1, endpoint1.clone()), (node2, endpoint2)]);
+        assert_eq!(2, pool.endpoints().len());
+        assert_eq!(1, pool.count_established_outgoing());
+        assert!(pool.is_established(&node2));
+        assert!(!pool.is_established(&node1));
+
+        // An endpoint already known is not replaced
+        pool.set_endpoint_state(&node1, WS2PConnectionState::Denial);
+        pool.insert_endpoint_if_absent(node1, endpoint1);
+        assert_eq!(
+            Some(WS2PConnectionState::Denial),
+            pool.endpoint_state(&node1)
+        );
+
+        pool.set_endpoint_checked_state(&node2, WS2PConnectionState::Close);
+        assert_eq!(0, pool.count_established());
+        assert!(pool.endpoint(&node2).expect("unknown endpoint").last_check > 0);
+        assert_eq!("g1.durs.info:20902/", pool.connection_url(&node2));
+    }
+
+    #[test]
+    fn connections() {
+        let (node1, _) = endpoint(
+            "WS2P 11111111 g1.durs.info 20901",
+            WS2PConnectionState::NeverTry,
+        );
+        let (node2, _) = endpoint(
+            "WS2P 22222222 g1.durs.info 20902",
+            WS2PConnectionState::NeverTry,
+        );
+        let mut pool = ConnectionsPool::default();
+        pool.add_outgoing_connection(node1, websocket());
+        pool.add_incoming_connection(
+            node2,
+            websocket(),
+            IncomingConnection {
+                connection_id: 1,
+                remote_addr: "127.0.0.1:54321".to_owned(),
+            },
+        );
+        assert_eq!(2, pool.count_connections());
+        assert_eq!(1, pool.count_incoming());
+        assert!(pool.is_established(&node2));
+        assert_eq!("127.0.0.1:54321", pool.connection_url(&node2));
+
+        // Removing a connection forgets its incoming connection too
+        assert!(pool.remove_connection(&node2).is_some());
+        assert!(!pool.is_connected(&node2));
+        assert!(pool.incoming_connection(&node2).is_none());
+        assert_eq!(0, pool.count_established());
+        assert!(pool.remove_connection(&node2).is_none());
+        assert_eq!(vec![&node1], pool.connected_nodes().collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,211 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Route outgoing connections through a SOCKS5 proxy.
+//!
+//! The websocket client cannot connect through a proxy, so each proxied connection goes through a
+//! local relay: the client connects to the relay, which opens the SOCKS5 tunnel to the endpoint
+//! and forwards bytes in both directions.
+
+use durs_common_tools::fns::thread::spawn_child;
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+
+const SOCKS5_VERSION: u8 = 5;
+const SOCKS5_NO_AUTH: u8 = 0;
+const SOCKS5_CMD_CONNECT: u8 = 1;
+const SOCKS5_ATYP_IPV4: u8 = 1;
+const SOCKS5_ATYP_DOMAIN: u8 = 3;
+const SOCKS5_ATYP_IPV6: u8 = 4;
+const SOCKS5_REPLY_SUCCEEDED: u8 = 0;
+
+/// Check that the proxy address is in the `host:port` format
+pub fn is_valid_proxy_addr(proxy: &str) -> bool {
+    let mut parts = proxy.rsplitn(2, ':');
+    let port = parts.next().map(str::parse::<u16>);
+    let host = parts.next().unwrap_or("");
+    !host.is_empty() && port.map(|port| port.is_ok()).unwrap_or(false)
+}
+
+/// Open a tunnel to `host:port` through the SOCKS5 proxy (the proxy resolves the host name)
+pub fn socks5_connect(proxy: &str, host: &str, port: u16) -> io::Result<TcpStream> {
+    if host.is_empty() || host.len() > 255 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "invalid host name",
+        ));
+    }
+    let mut stream = TcpStream::connect(proxy)?;
+
+    // Greeting: no authentication
+    stream.write_all(&[SOCKS5_VERSION, 1, SOCKS5_NO_AUTH])?;
+    let mut greeting_reply = [0u8; 2];
+    stream.read_exact(&mut greeting_reply)?;
+    if greeting_reply != [SOCKS5_VERSION, SOCKS5_NO_AUTH] {
+        return Err(io::Error::new(
+            io::ErrorKind::ConnectionRefused,
+            "SOCKS5 proxy refuses connection without authentication",
+        ));
+    }
+
+    // Connect request
+    let mut request = vec![
+        SOCKS5_VERSION,
+        SOCKS5_CMD_CONNECT,
+        0,
+        SOCKS5_ATYP_DOMAIN,
+        host.len() as u8,
+    ];
+    request.extend_from_slice(host.as_bytes());
+    request.extend_from_slice(&port.to_be_bytes());
+    stream.write_all(&request)?;
+
+    // Connect reply
+    let mut reply = [0u8; 4];
+    stream.read_exact(&mut reply)?;
+    if reply[0] != SOCKS5_VERSION || reply[1] != SOCKS5_REPLY_SUCCEEDED {
+        return Err(io::Error::new(
+            io::ErrorKind::ConnectionRefused,
+            format!("SOCKS5 proxy fail to connect (reply code {})", reply[1]),
+        ));
+    }
+    let bound_addr_len = match reply[3] {
+        SOCKS5_ATYP_IPV4 => 4,
+        SOCKS5_ATYP_IPV6 => 16,
+        SOCKS5_ATYP_DOMAIN => {
+            let mut len = [0u8; 1];
+            stream.read_exact(&mut len)?;
+            usize::from(len[0])
+        }
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "invalid SOCKS5 reply",
+            ))
+        }
+    };
+    // Skip bound address and port
+    let mut bound_addr = vec![0u8; bound_addr_len + 2];
+    stream.read_exact(&mut bound_addr)?;
+
+    Ok(stream)
+}
+
+/// Open a local relay forwarding its first connection to `host:port` through the SOCKS5 proxy
+pub fn open_relay(proxy: &str, host: &str, port: u16) -> io::Result<SocketAddr> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let relay_addr = listener.local_addr()?;
+    let proxy = proxy.to_owned();
+    let host = host.to_owned();
+
+    spawn_child("relay", move || {
+        let result = listener.accept().and_then(|(client_stream, _)| {
+            let remote_stream = socks5_connect(&proxy, &host, port)?;
+            forward(client_stream, remote_stream)
+        });
+        if let Err(e) = result {
+            debug!("WS2P: proxy relay to {}:{} fail: {}", host, port, e);
+        }
+    });
+
+    Ok(relay_addr)
+}
+
+/// Forward bytes in both directions until one side closes the connection
+fn forward(client_stream: TcpStream, remote_stream: TcpStream) -> io::Result<()> {
+    let mut client_reader = client_stream.try_clone()?;
+    let mut remote_writer = remote_stream.try_clone()?;
+    let upstream = spawn_child("upstream", move || {
+        let _ = io::copy(&mut client_reader, &mut remote_writer);
+        let _ = remote_writer.shutdown(Shutdown::Write);
+    });
+
+    let mut remote_reader = remote_stream;
+    let mut client_writer = client_stream;
+    let _ = io::copy(&mut remote_reader, &mut client_writer);
+    let _ = client_writer.shutdown(Shutdown::Both);
+    let _ = upstream.join();
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_is_valid_proxy_addr() {
+        assert!(is_valid_proxy_addr("127.0.0.1:9050"));
+        assert!(is_valid_proxy_addr("localhost:9050"));
+        assert!(!is_valid_proxy_addr("localhost"));
+        assert!(!is_valid_proxy_addr(":9050"));
+        assert!(!is_valid_proxy_addr("localhost:proxy"));
+    }
+
+    #[test]
+    fn test_relay_through_socks5_proxy() -> io::Result<()> {
+        // Fake SOCKS5 proxy answering "pong" to "ping"
+        let proxy_listener = TcpListener::bind("127.0.0.1:0")?;
+        let proxy_addr = proxy_listener.local_addr()?;
+        let proxy_thread = thread::spawn(move || -> io::Result<Vec<u8>> {
+            let (mut stream, _) = proxy_listener.accept()?;
+            let mut greeting = [0u8; 3];
+            stream.read_exact(&mut greeting)?;
+            stream.write_all(&[SOCKS5_VERSION, SOCKS5_NO_AUTH])?;
+            let mut request = vec![0u8; 5 + "example.onion".len() + 2];
+            stream.read_exact(&mut request)?;
+            stream.write_all(&[
+                SOCKS5_VERSION,
+                SOCKS5_REPLY_SUCCEEDED,
+                0,
+                SOCKS5_ATYP_IPV4,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+            ])?;
+            let mut ping = [0u8; 4];
+            stream.read_exact(&mut ping)?;
+            stream.write_all(b"pong")?;
+            Ok(request)
+        });
+
+        let relay_addr = open_relay(&proxy_addr.to_string(), "example.onion", 20901)?;
+        let mut client = TcpStream::connect(relay_addr)?;
+        client.write_all(b"ping")?;
+        let mut pong = [0u8; 4];
+        client.read_exact(&mut pong)?;
+        assert_eq!(b"pong", &pong);
+
+        let request = proxy_thread.join().expect("proxy thread panic")?;
+        assert_eq!(
+            &[
+                SOCKS5_VERSION,
+                SOCKS5_CMD_CONNECT,
+                0,
+                SOCKS5_ATYP_DOMAIN,
+                13
+            ],
+            &request[..5]
+        );
+        assert_eq!(b"example.onion", &request[5..18]);
+        assert_eq!(&20901u16.to_be_bytes(), &request[18..]);
+
+        Ok(())
+    }
+}
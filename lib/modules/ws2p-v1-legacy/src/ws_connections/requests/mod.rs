@@ -59,7 +59,7 @@ pub struct WS2Pv1Request {
     pub body: WS2Pv1ReqBody,
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
 /// WS2Pv1 requets body
 pub enum WS2Pv1ReqBody {
     /// get current block
@@ -15,12 +15,15 @@
 
 //! Sub-module managing the WS2Pv1 requests received.
 
-use crate::requests::sent::send_dal_request;
+use crate::constants::WS2P_V1_MAX_BLOCKS_PER_RESPONSE;
+use crate::requests::sent::{send_dal_request, send_mempool_request};
 use crate::ws_connections::requests::{WS2Pv1ReqBody, WS2Pv1ReqFullId, WS2Pv1ReqId};
-use crate::ws_connections::responses::{WS2Pv1ReqRes, WS2Pv1ReqResBody};
+use crate::ws_connections::responses::raw_json_response;
+use crate::ws_connections::responses::sent::send_raw_response;
 use crate::WS2Pv1Module;
-use durs_message::requests::BlockchainRequest;
+use durs_message::requests::{BlockchainRequest, MemPoolRequest};
 use durs_network_documents::NodeFullId;
+use std::time::Instant;
 
 pub fn receive_ws2p_v1_request(
     ws2p_module: &mut WS2Pv1Module,
@@ -28,44 +31,39 @@ pub fn receive_ws2p_v1_request(
     ws2p_req_id: WS2Pv1ReqId,
     req_boby: WS2Pv1ReqBody,
 ) {
-    let module_req_id_opt = match req_boby {
-        WS2Pv1ReqBody::GetCurrent => Some(send_dal_request(
-            ws2p_module,
-            &BlockchainRequest::CurrentBlock,
-        )),
-        WS2Pv1ReqBody::GetBlock { number } => Some(send_dal_request(
+    if let Some(raw_body) = ws2p_module.responses_cache.get(&req_boby, Instant::now()) {
+        send_raw_response(ws2p_module, from, raw_json_response(ws2p_req_id, &raw_body));
+        return;
+    }
+
+    let module_req_id = match req_boby {
+        WS2Pv1ReqBody::GetCurrent => {
+            send_dal_request(ws2p_module, &BlockchainRequest::CurrentBlock)
+        }
+        WS2Pv1ReqBody::GetBlock { number } => send_dal_request(
             ws2p_module,
             &BlockchainRequest::BlockByNumber {
                 block_number: number,
             },
-        )),
-        WS2Pv1ReqBody::GetBlocks { from_number, count } => Some(send_dal_request(
+        ),
+        WS2Pv1ReqBody::GetBlocks { from_number, count } => send_dal_request(
             ws2p_module,
             &BlockchainRequest::Chunk {
                 first_block_number: from_number,
-                count,
+                count: std::cmp::min(count, *WS2P_V1_MAX_BLOCKS_PER_RESPONSE),
             },
-        )),
-        WS2Pv1ReqBody::GetRequirementsPending { .. } => {
-            crate::ws_connections::responses::sent::send_response(
-                ws2p_module,
-                from,
-                WS2Pv1ReqRes {
-                    req_id: ws2p_req_id,
-                    body: WS2Pv1ReqResBody::GetRequirementsPending { identities: vec![] },
-                },
-            );
-            None
+        ),
+        WS2Pv1ReqBody::GetRequirementsPending { min_cert } => {
+            send_mempool_request(ws2p_module, MemPoolRequest::AllPendingIdentities(min_cert))
         }
     };
 
-    if let Some(module_req_id) = module_req_id_opt {
-        ws2p_module.pending_received_requests.insert(
-            module_req_id,
-            WS2Pv1ReqFullId {
-                from,
-                req_id: ws2p_req_id,
-            },
-        );
-    }
+    ws2p_module.responses_cache.expect(module_req_id, req_boby);
+    ws2p_module.requests.track_received(
+        module_req_id,
+        WS2Pv1ReqFullId {
+            from,
+            req_id: ws2p_req_id,
+        },
+    );
 }
@@ -15,11 +15,11 @@
 
 //! Sub-module managing the WS2Pv1 requests sent.
 
-use super::{WS2Pv1ReqBody, WS2Pv1Request};
+use super::{WS2Pv1ReqBody, WS2Pv1ReqId, WS2Pv1Request};
 use crate::{WS2Pv1Module, WS2Pv1PendingReqInfos};
 use durs_module::ModuleReqFullId;
 use durs_network_documents::NodeFullId;
-use std::time::SystemTime;
+use std::time::Instant;
 use ws::Message;
 
 pub fn send_request_to_specific_node(
@@ -28,17 +28,35 @@ pub fn send_request_to_specific_node(
     ws2p_full_id: &NodeFullId,
     ws2p_request: &WS2Pv1Request,
 ) -> ws::Result<()> {
-    if let Some(ws) = ws2p_module.websockets.get_mut(ws2p_full_id) {
+    send_request(
+        ws2p_module,
+        module_req_full_id,
+        ws2p_full_id,
+        ws2p_request,
+        0,
+    )
+}
+
+/// Send a request already issued `retries` times to other nodes
+pub fn send_request(
+    ws2p_module: &mut WS2Pv1Module,
+    module_req_full_id: ModuleReqFullId,
+    ws2p_full_id: &NodeFullId,
+    ws2p_request: &WS2Pv1Request,
+    retries: usize,
+) -> ws::Result<()> {
+    if let Some(ws) = ws2p_module.connections.websocket(ws2p_full_id) {
         let json_req = network_request_to_json(ws2p_request).to_string();
         debug!("send request {} to {}", json_req, ws2p_full_id);
         ws.0.send(Message::text(json_req))?;
-        ws2p_module.requests_awaiting_response.insert(
+        ws2p_module.requests.track_sent(
             ws2p_request.id,
             WS2Pv1PendingReqInfos {
                 req_body: ws2p_request.body,
                 requester_module: module_req_full_id,
                 recipient_node: *ws2p_full_id,
-                timestamp: SystemTime::now(),
+                timestamp: Instant::now(),
+                retries,
             },
         );
     } else {
@@ -47,6 +65,31 @@ pub fn send_request_to_specific_node(
     Ok(())
 }
 
+/// Send a request to all established connections
+pub fn send_request_to_all_connections(
+    ws2p_module: &mut WS2Pv1Module,
+    module_req_full_id: ModuleReqFullId,
+    req_body: WS2Pv1ReqBody,
+) {
+    let established_connections: Vec<NodeFullId> = ws2p_module
+        .connections
+        .connected_nodes()
+        .filter(|node_full_id| ws2p_module.connections.is_established(node_full_id))
+        .copied()
+        .collect();
+    for node_full_id in established_connections {
+        let request = WS2Pv1Request {
+            id: WS2Pv1ReqId::random(),
+            body: req_body,
+        };
+        if let Err(e) =
+            send_request_to_specific_node(ws2p_module, module_req_full_id, &node_full_id, &request)
+        {
+            debug!("WS2P: fail to send request to {}: {}", node_full_id, e);
+        }
+    }
+}
+
 pub fn network_request_to_json(request: &WS2Pv1Request) -> serde_json::Value {
     let (request_type, request_params) = match request.body {
         WS2Pv1ReqBody::GetCurrent => ("CURRENT", json!({})),
@@ -21,8 +21,11 @@ pub mod sent;
 use crate::serializers::IntoWS2Pv1Json;
 use crate::ws_connections::requests::WS2Pv1ReqId;
 use dubp_block_doc::BlockDocument;
-use dubp_common_doc::traits::ToStringObject;
-use dup_crypto::keys::PubKey;
+use dubp_common_doc::traits::{Document, ToStringObject};
+use dubp_user_docs::documents::certification::{CertificationDocument, CertificationDocumentV10};
+use dubp_user_docs::documents::identity::{IdentityDocument, IdentityDocumentV10};
+use dubp_user_docs::documents::membership::{MembershipDocument, MembershipDocumentV10};
+use durs_message::responses::PendingIdtyDatas;
 
 /// WS2Pv1 request response
 #[derive(Clone, Debug)]
@@ -33,6 +36,22 @@ pub struct WS2Pv1ReqRes {
     pub body: WS2Pv1ReqResBody,
 }
 
+impl WS2Pv1ReqRes {
+    /// Serialize the response in JSON.
+    pub fn into_raw_json(self) -> String {
+        raw_json_response(self.req_id, &self.body.into_raw_json())
+    }
+}
+
+/// Wrap the JSON body of a response with the id of the request
+pub fn raw_json_response(req_id: WS2Pv1ReqId, raw_body: &str) -> String {
+    format!(
+        "{{\"resId\":\"{}\",\"body\":{}}}",
+        req_id.to_hyphenated_string(),
+        raw_body
+    )
+}
+
 impl Into<serde_json::Value> for WS2Pv1ReqRes {
     fn into(self) -> serde_json::Value {
         let mut map = serde_json::map::Map::with_capacity(2);
@@ -60,6 +79,28 @@ pub enum WS2Pv1ReqResBody {
     },
 }
 
+impl WS2Pv1ReqResBody {
+    /// Serialize the body in JSON.
+    /// The blocks of a getBlocks response are serialized one by one, without building the JSON
+    /// tree of the whole chunk.
+    pub fn into_raw_json(self) -> String {
+        if let WS2Pv1ReqResBody::GetBlocks(ref blocks) = self {
+            let mut raw = String::from("[");
+            for (i, block) in blocks.iter().enumerate() {
+                if i > 0 {
+                    raw.push(',');
+                }
+                raw.push_str(&block.to_string_object().into_ws2p_v1_json().to_string());
+            }
+            raw.push(']');
+            raw
+        } else {
+            let json_body: serde_json::Value = self.into();
+            json_body.to_string()
+        }
+    }
+}
+
 impl Into<serde_json::Value> for WS2Pv1ReqResBody {
     fn into(self) -> serde_json::Value {
         match self {
@@ -76,9 +117,12 @@ impl Into<serde_json::Value> for WS2Pv1ReqResBody {
                     .map(IntoWS2Pv1Json::into_ws2p_v1_json)
                     .collect(),
             ),
-            WS2Pv1ReqResBody::GetRequirementsPending { .. } => {
+            WS2Pv1ReqResBody::GetRequirementsPending { identities } => {
                 let mut map = serde_json::map::Map::with_capacity(1);
-                map.insert("identities".to_owned(), serde_json::Value::Array(vec![]));
+                map.insert(
+                    "identities".to_owned(),
+                    serde_json::Value::Array(identities.into_iter().map(Into::into).collect()),
+                );
                 serde_json::Value::Object(map)
             }
         }
@@ -88,23 +132,77 @@ impl Into<serde_json::Value> for WS2Pv1ReqResBody {
 /// WS2Pv1 Identity requirements pending
 #[derive(Clone, Debug)]
 pub struct WS2Pv1IdentityRequirementsPending {
-    pub certifications: Vec<WS2pv1CertificationPending>,
-    pub expired: bool,
-    pub is_sentry: bool,
-    pub membership_expires_in: u64,
-    pub membership_pending_expires_in: u64,
-    // Some fields missing ...
+    /// Pending identity
+    pub idty: IdentityDocumentV10,
+    /// Pending memberships of the identity
+    pub pending_memberships: Vec<MembershipDocumentV10>,
+    /// Pending certifications received by the identity
+    pub pending_certs: Vec<CertificationDocumentV10>,
+    /// Revocation signature (None if identity has not been revoked)
+    pub revocation_sig: Option<String>,
+}
+
+impl From<&PendingIdtyDatas> for WS2Pv1IdentityRequirementsPending {
+    fn from(pending_idty_datas: &PendingIdtyDatas) -> Self {
+        let IdentityDocument::V10(ref idty) = pending_idty_datas.idty;
+        WS2Pv1IdentityRequirementsPending {
+            idty: idty.clone(),
+            pending_memberships: pending_idty_datas
+                .memberships
+                .iter()
+                .map(|MembershipDocument::V10(membership)| membership.clone())
+                .collect(),
+            pending_certs: pending_idty_datas
+                .certs
+                .iter()
+                .map(|CertificationDocument::V10(cert)| cert.clone())
+                .collect(),
+            revocation_sig: pending_idty_datas
+                .revocation
+                .as_ref()
+                .map(|revocation| revocation.signatures()[0].to_string()),
+        }
+    }
 }
 
-/// WS2Pv1 Certification pending
-#[derive(Copy, Clone, Debug)]
-pub struct WS2pv1CertificationPending {
-    /// Expires in
-    pub expires_in: u64,
-    /// From
-    pub from: PubKey,
-    /// Timestamp
-    pub timestamp: u64,
-    /// To
-    pub to: PubKey,
+impl Into<serde_json::Value> for WS2Pv1IdentityRequirementsPending {
+    fn into(self) -> serde_json::Value {
+        let idty = self.idty.to_string_object();
+        json!({
+            "pubkey": idty.issuer,
+            "uid": idty.username,
+            "sig": idty.signature,
+            "meta": {
+                "timestamp": idty.blockstamp,
+            },
+            "revoked": self.revocation_sig.is_some(),
+            "revocation_sig": self.revocation_sig,
+            "expired": false,
+            "certifications": [],
+            "pendingCerts": self.pending_certs.iter().map(|cert| {
+                let blockstamp = cert.blockstamp();
+                let cert = cert.to_string_object();
+                json!({
+                    "from": cert.issuer,
+                    "to": cert.target,
+                    "sig": cert.signature,
+                    "block_number": blockstamp.id.0,
+                    "block_hash": blockstamp.hash.to_string(),
+                })
+            }).collect::<Vec<serde_json::Value>>(),
+            "pendingMemberships": self.pending_memberships.iter().map(|membership| {
+                let blockstamp = membership.blockstamp();
+                let membership = membership.to_string_object();
+                json!({
+                    "membership": membership.membership,
+                    "issuer": membership.issuer,
+                    "signature": membership.signature,
+                    "blockNumber": blockstamp.id.0,
+                    "blockHash": blockstamp.hash.to_string(),
+                    "userid": membership.username,
+                    "certts": membership.identity_blockstamp,
+                })
+            }).collect::<Vec<serde_json::Value>>(),
+        })
+    }
 }
@@ -17,10 +17,91 @@
 
 use crate::*;
 use dubp_block_doc::parser::parse_json_block_from_serde_value;
+use dubp_user_docs::documents::identity::IdentityDocument;
+use dubp_user_docs::documents::membership::v10::MembershipType;
+use dubp_user_docs::documents::membership::MembershipDocument;
+use dubp_user_docs::parsers::identities::parse_compact_identities;
+use dubp_user_docs::parsers::memberships::parse_compact_memberships;
 use durs_module::ModuleReqFullId;
 use durs_network::requests::*;
 use durs_network_documents::NodeFullId;
 
+/// Parse the identities and memberships of a response to the request getRequirementsPending
+fn parse_requirements_pending(
+    currency: &str,
+    response: &serde_json::Value,
+) -> Vec<UserDocumentDUBP> {
+    let identities = if let Some(identities) = response.get("identities").and_then(|v| v.as_array())
+    {
+        identities
+    } else {
+        warn!("WS2Pv1: receive invalid requirements pending: no identities array.");
+        return vec![];
+    };
+    let mut documents = Vec::new();
+    for identity in identities {
+        match parse_pending_identity(currency, identity) {
+            Some(identity_doc) => documents.push(UserDocumentDUBP::Identity(identity_doc)),
+            None => warn!("WS2Pv1: receive invalid pending identity: {}", identity),
+        }
+        let memberships = identity
+            .get("pendingMemberships")
+            .and_then(|v| v.as_array())
+            .map(Vec::as_slice)
+            .unwrap_or(&[]);
+        for membership in memberships {
+            match parse_pending_membership(currency, membership) {
+                Some(membership_doc) => {
+                    documents.push(UserDocumentDUBP::Membership(membership_doc))
+                }
+                None => warn!("WS2Pv1: receive invalid pending membership: {}", membership),
+            }
+        }
+    }
+    documents
+}
+
+fn parse_pending_identity(
+    currency: &str,
+    identity: &serde_json::Value,
+) -> Option<IdentityDocument> {
+    let compact_identity = format!(
+        "{}:{}:{}:{}",
+        identity.get("pubkey")?.as_str()?,
+        identity.get("sig")?.as_str()?,
+        identity.get("meta")?.get("timestamp")?.as_str()?,
+        identity.get("uid")?.as_str()?,
+    );
+    parse_compact_identities(currency, vec![&compact_identity])
+        .ok()?
+        .pop()
+        .map(IdentityDocument::V10)
+}
+
+fn parse_pending_membership(
+    currency: &str,
+    membership: &serde_json::Value,
+) -> Option<MembershipDocument> {
+    let membership_type = match membership.get("membership")?.as_str()? {
+        "IN" => MembershipType::In(),
+        "OUT" => MembershipType::Out(),
+        _ => return None,
+    };
+    let compact_membership = format!(
+        "{}:{}:{}-{}:{}:{}",
+        membership.get("issuer")?.as_str()?,
+        membership.get("signature")?.as_str()?,
+        membership.get("blockNumber")?.as_u64()?,
+        membership.get("blockHash")?.as_str()?,
+        membership.get("certts")?.as_str()?,
+        membership.get("userid")?.as_str()?,
+    );
+    parse_compact_memberships(currency, membership_type, &[&compact_membership])
+        .ok()?
+        .pop()
+        .map(MembershipDocument::V10)
+}
+
 pub fn receive_response(
     ws2p_module: &mut WS2Pv1Module,
     module_req_full_id: ModuleReqFullId,
@@ -73,18 +154,132 @@ pub fn receive_response(
             }
         }
         WS2Pv1ReqBody::GetRequirementsPending { min_cert } => {
+            let currency = if let Some(ref currency) = ws2p_module.conf.currency {
+                currency.to_string()
+            } else {
+                return;
+            };
+            let documents = parse_requirements_pending(&currency, &response);
             info!(
-                "WS2PSignal::ReceiveRequirementsPending({}, {})",
-                module_req_full_id.0, min_cert
+                "WS2PSignal::ReceiveRequirementsPending({}, {}, {} documents)",
+                module_req_full_id.0,
+                min_cert,
+                documents.len()
+            );
+            if module_req_full_id.0 != WS2Pv1Module::name() {
+                responses::sent::send_network_req_response(
+                    ws2p_module,
+                    module_req_full_id.0,
+                    module_req_full_id.1,
+                    NetworkResponse::PendingDocuments(
+                        ModuleReqFullId(WS2Pv1Module::name(), module_req_full_id.1),
+                        documents.clone(),
+                    ),
+                );
+            }
+            events::sent::send_network_event(
+                ws2p_module,
+                NetworkEvent::ReceiveDocuments(documents),
             );
-            debug!("----------------------------------------");
-            debug!("-      BEGIN IDENTITIES PENDING        -");
-            debug!("----------------------------------------");
-            debug!("{:#?}", response);
-            debug!("----------------------------------------");
-            debug!("-       END IDENTITIES PENDING         -");
-            debug!("----------------------------------------");
         }
         _ => {}
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ws_connections::responses::{WS2Pv1IdentityRequirementsPending, WS2Pv1ReqResBody};
+    use dubp_common_doc::traits::Document;
+    use durs_message::responses::PendingIdtyDatas;
+
+    #[test]
+    fn test_parse_requirements_pending() {
+        let response = json!({
+            "identities": [{
+                "pubkey": "DNann1Lh55eZMEDXeYt59bzHbA3NJR46DeQYCS2qQdLV",
+                "uid": "tic",
+                "sig": "mmFepRsiOjILKnCvEvN3IZScLOfg8+e0JPAl5VkiuTLZRGJKgKhPy8nQlCKbeg0jefQm/2HJ78e/Sj+NMqYLCw==",
+                "meta": {
+                    "timestamp": "0-E3B0C44298FC1C149AFBF4C8996FB92427AE41E4649B934CA495991B7852B855"
+                },
+                "certifications": [],
+                "pendingMemberships": [{
+                    "membership": "IN",
+                    "issuer": "DNann1Lh55eZMEDXeYt59bzHbA3NJR46DeQYCS2qQdLV",
+                    "blockNumber": 0,
+                    "blockHash": "E3B0C44298FC1C149AFBF4C8996FB92427AE41E4649B934CA495991B7852B855",
+                    "userid": "tic",
+                    "certts": "0-E3B0C44298FC1C149AFBF4C8996FB92427AE41E4649B934CA495991B7852B855",
+                    "signature": "cUgoc8AI+Tae/AZmRfTnW+xq3XFtmYoUi2LXlmXr8/7LaXiUccQb8+Ds1nZoBp/8+t031HMwqAUpVIqww2FGCg=="
+                }, {
+                    "membership": "UNKNOWN"
+                }],
+                "isSentry": false,
+                "expired": false,
+                "membershipExpiresIn": 0,
+                "membershipPendingExpiresIn": 0
+            }]
+        });
+
+        let documents = parse_requirements_pending("g1", &response);
+        assert_eq!(2, documents.len());
+        if let UserDocumentDUBP::Identity(IdentityDocument::V10(ref identity)) = documents[0] {
+            assert_eq!("tic", identity.username());
+            assert_eq!("g1", identity.currency());
+        } else {
+            panic!("expected an identity document");
+        }
+        if let UserDocumentDUBP::Membership(MembershipDocument::V10(ref membership)) = documents[1]
+        {
+            assert_eq!(MembershipType::In(), membership.membership());
+            assert_eq!("tic", membership.identity_username());
+        } else {
+            panic!("expected a membership document");
+        }
+
+        assert!(parse_requirements_pending("g1", &json!({})).is_empty());
+    }
+    #[test]
+    fn test_requirements_pending_round_trip() {
+        let response = json!({
+            "identities": [{
+                "pubkey": "DNann1Lh55eZMEDXeYt59bzHbA3NJR46DeQYCS2qQdLV",
+                "uid": "tic",
+                "sig": "mmFepRsiOjILKnCvEvN3IZScLOfg8+e0JPAl5VkiuTLZRGJKgKhPy8nQlCKbeg0jefQm/2HJ78e/Sj+NMqYLCw==",
+                "meta": {
+                    "timestamp": "0-E3B0C44298FC1C149AFBF4C8996FB92427AE41E4649B934CA495991B7852B855"
+                },
+                "pendingMemberships": [{
+                    "membership": "IN",
+                    "issuer": "DNann1Lh55eZMEDXeYt59bzHbA3NJR46DeQYCS2qQdLV",
+                    "blockNumber": 0,
+                    "blockHash": "E3B0C44298FC1C149AFBF4C8996FB92427AE41E4649B934CA495991B7852B855",
+                    "userid": "tic",
+                    "certts": "0-E3B0C44298FC1C149AFBF4C8996FB92427AE41E4649B934CA495991B7852B855",
+                    "signature": "cUgoc8AI+Tae/AZmRfTnW+xq3XFtmYoUi2LXlmXr8/7LaXiUccQb8+Ds1nZoBp/8+t031HMwqAUpVIqww2FGCg=="
+                }]
+            }]
+        });
+        let documents = parse_requirements_pending("g1", &response);
+        let (idty, membership) = match (&documents[0], &documents[1]) {
+            (UserDocumentDUBP::Identity(idty), UserDocumentDUBP::Membership(membership)) => {
+                (idty.clone(), membership.clone())
+            }
+            _ => panic!("expected an identity and a membership"),
+        };
+        let pending_idty_datas = PendingIdtyDatas {
+            idty,
+            memberships: vec![membership],
+            certs_count: 0,
+            certs: vec![],
+            revocation: None,
+        };
+
+        let serialized: serde_json::Value = WS2Pv1ReqResBody::GetRequirementsPending {
+            identities: vec![WS2Pv1IdentityRequirementsPending::from(&pending_idty_datas)],
+        }
+        .into();
+        assert_eq!(documents, parse_requirements_pending("g1", &serialized));
+    }
+}
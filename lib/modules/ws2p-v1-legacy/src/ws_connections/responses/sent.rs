@@ -25,13 +25,17 @@ pub fn send_response(
     ws2p_req_from: NodeFullId,
     response: WS2Pv1ReqRes,
 ) {
-    if let Some(ws_sender) = ws2p_module.websockets.get(&ws2p_req_from) {
-        let json_response: serde_json::Value = response.into();
-        if ws_sender
-            .0
-            .send(Message::text(json_response.to_string()))
-            .is_err()
-        {
+    send_raw_response(ws2p_module, ws2p_req_from, response.into_raw_json())
+}
+
+/// Send a response already serialized in JSON
+pub fn send_raw_response(
+    ws2p_module: &mut WS2Pv1Module,
+    ws2p_req_from: NodeFullId,
+    raw_response: String,
+) {
+    if let Some(ws_sender) = ws2p_module.connections.websocket(&ws2p_req_from) {
+        if ws_sender.0.send(Message::text(raw_response)).is_err() {
             let _ = ws_sender
                 .0
                 .close_with_reason(CloseCode::Error, "Fail to send request response !");
@@ -0,0 +1,313 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! WS2P incoming connections handler.
+
+use super::keep_alive::{KeepAlive, KEEP_ALIVE};
+use super::messages::*;
+use super::meta_datas::WS2PConnectionMetaDatas;
+use super::states::WS2PConnectionState;
+use crate::constants::*;
+use crate::*;
+use dup_crypto::keys::*;
+use durs_common_tools::fatal_error;
+use durs_common_tools::fns::thread::spawn_child;
+use durs_common_tools::timer::elapsed_since;
+use std::sync::mpsc;
+#[allow(deprecated)]
+use ws::util::{Timeout, Token};
+use ws::{CloseCode, Frame, Handler, Handshake, Message, Sender};
+
+const CONNECT: Token = Token(1);
+const EXPIRE: Token = Token(2);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// Incoming connection accepted by the local node
+pub struct IncomingConnection {
+    /// Websocket connection id
+    pub connection_id: u32,
+    /// Remote address
+    pub remote_addr: String,
+}
+
+/// Handler of an incoming connection.
+/// The negotiation is done in the websocket thread, the main thread is notified only once the
+/// connection is established.
+#[allow(deprecated)]
+#[derive(Debug)]
+pub struct Server {
+    ws: Sender,
+    conductor_sender: mpsc::Sender<WS2PThreadSignal>,
+    currency: String,
+    connect_message: Message,
+    conn_meta_datas: WS2PConnectionMetaDatas,
+    remote_addr: String,
+    keep_alive: KeepAlive,
+    last_mess_time: Instant,
+    signator: SignatorEnum,
+    spam_interval: bool,
+    spam_counter: usize,
+    timeout: Option<Timeout>,
+}
+
+/// Listen for incoming connections in a dedicated thread.
+/// Returns a sender that allows to shutdown the listener.
+pub fn listen_incoming_connections(
+    host: &str,
+    port: u16,
+    conductor_sender: &mpsc::Sender<WS2PThreadSignal>,
+    currency: &str,
+    keypair: &KeyPairEnum,
+    keep_alive: KeepAlive,
+) -> ws::Result<WsSender> {
+    let conductor_sender = conductor_sender.clone();
+    let currency = currency.to_owned();
+    let keypair = keypair.clone();
+
+    let ws = ws::Builder::new()
+        .build(move |ws| {
+            // Generate signator
+            let signator = if let Ok(signator) = keypair.generate_signator() {
+                signator
+            } else {
+                fatal_error!("Your key pair is corrupted, please recreate it !");
+            };
+
+            // The remote node is identified by its public key only, its node id is unknown
+            let mut conn_meta_datas = WS2PConnectionMetaDatas::new(format!(
+                "{}{}",
+                uuid::Uuid::new_v4(),
+                uuid::Uuid::new_v4()
+            ));
+            conn_meta_datas.remote_uuid = Some(NodeId::default());
+
+            // Generate connect message
+            let connect_message =
+                generate_connect_message(&currency, &signator, conn_meta_datas.challenge.clone());
+
+            Server {
+                ws,
+                conductor_sender: conductor_sender.clone(),
+                currency: currency.clone(),
+                connect_message,
+                conn_meta_datas,
+                remote_addr: String::new(),
+                keep_alive,
+                last_mess_time: Instant::now(),
+                signator,
+                spam_interval: false,
+                spam_counter: 0,
+                timeout: None,
+            }
+        })?
+        .bind((host, port))?;
+    let broadcaster = ws.broadcaster();
+
+    info!("WS2P: listen incoming connections on {}:{}", host, port);
+    spawn_child("server", move || {
+        if let Err(e) = ws.run() {
+            error!("WS2P: incoming connections listener stopped: {}", e);
+        }
+    });
+
+    Ok(WsSender(broadcaster))
+}
+
+impl Server {
+    fn established(&self) -> bool {
+        self.conn_meta_datas.state == WS2PConnectionState::Established
+    }
+    fn send_to_conductor(&self, payload: WS2Pv1MsgPayload) -> ws::Result<()> {
+        let result = self
+            .conductor_sender
+            .send(WS2PThreadSignal::WS2Pv1Msg(WS2Pv1Msg {
+                from: self.conn_meta_datas.node_full_id(),
+                payload,
+            }));
+        // If WS2PConductor is unrechable, close connection.
+        if result.is_err() {
+            debug!("Close ws2p connection because ws2p main thread is unrechable !");
+            self.ws.close(CloseCode::Normal)
+        } else {
+            Ok(())
+        }
+    }
+    fn negotiate(&mut self, json_message: &serde_json::Value) -> ws::Result<()> {
+        // The remote public key is known only after a valid CONNECT message
+        let auth = json_message.get("auth").and_then(serde_json::Value::as_str);
+        if auth.is_none()
+            || (self.conn_meta_datas.remote_pubkey.is_none() && auth != Some("CONNECT"))
+        {
+            debug!("WS2P: ignore message received before the end of negotiation.");
+            return Ok(());
+        }
+
+        match self.conn_meta_datas.parse_and_check_incoming_message(
+            &self.currency,
+            &self.signator,
+            json_message,
+        ) {
+            WS2Pv1MsgPayload::ValidConnectMessage(response, _) => {
+                self.ws.send(Message::text(response))
+            }
+            WS2Pv1MsgPayload::ValidAckMessage(response, WS2PConnectionState::AckMessOk) => {
+                self.ws.send(Message::text(response))
+            }
+            WS2Pv1MsgPayload::ValidAckMessage(_, WS2PConnectionState::Established)
+            | WS2Pv1MsgPayload::ValidOk(WS2PConnectionState::Established) => self
+                .send_to_conductor(WS2Pv1MsgPayload::IncomingConnection(
+                    WsSender(self.ws.clone()),
+                    IncomingConnection {
+                        connection_id: self.ws.connection_id(),
+                        remote_addr: self.remote_addr.clone(),
+                    },
+                )),
+            WS2Pv1MsgPayload::InvalidMessage => self.ws.close(CloseCode::Policy),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Handler for Server {
+    fn on_open(&mut self, shake: Handshake) -> ws::Result<()> {
+        self.remote_addr = shake.remote_addr()?.unwrap_or_default();
+        debug!("WS2P: incoming connection from {}", self.remote_addr);
+        // Define timeouts
+        self.ws.timeout(WS2P_NEGOTIATION_TIMEOUT * 1_000, CONNECT)?;
+        self.ws.timeout(WS2P_EXPIRE_TIMEOUT * 1_000, EXPIRE)?;
+        // Send CONNECT Message
+        self.ws.send(self.connect_message.clone())
+    }
+    fn on_message(&mut self, msg: Message) -> ws::Result<()> {
+        // Spam ?
+        if elapsed_since(Instant::now(), self.last_mess_time)
+            < Duration::from_millis(*WS2P_SPAM_INTERVAL_IN_MILLI_SECS)
+        {
+            if self.spam_interval {
+                self.spam_counter += 1;
+            } else {
+                self.spam_interval = true;
+                self.spam_counter = 2;
+            }
+        } else {
+            self.spam_interval = false;
+            self.spam_counter = 0;
+        }
+        if self.spam_counter >= *WS2P_SPAM_LIMIT {
+            if self.spam_counter == *WS2P_SPAM_LIMIT && self.established() {
+                // Report flood violation to WS2PConductor
+                self.send_to_conductor(WS2Pv1MsgPayload::Spam)?;
+            }
+            thread::sleep(Duration::from_millis(*WS2P_SPAM_SLEEP_TIME_IN_SEC));
+            self.last_mess_time = Instant::now();
+            return Ok(());
+        }
+        self.last_mess_time = Instant::now();
+
+        // Fault injection
+        #[cfg(feature = "chaos")]
+        let msg = match chaos::inject(msg) {
+            chaos::ChaosOutcome::Forward(msg) => msg,
+            chaos::ChaosOutcome::Disconnect => return self.ws.close(CloseCode::Away),
+        };
+
+        // Parse and check incoming message
+        if msg.is_text() {
+            let s: String = msg.into_text()?;
+            trace!("WS2P: receive mess: {}", s);
+            let json_message: serde_json::Value = match serde_json::from_str(&s) {
+                Ok(json_message) => json_message,
+                Err(_) => {
+                    warn!(
+                        "WS2P: receive invalid json message from {}",
+                        self.remote_addr
+                    );
+                    return Ok(());
+                }
+            };
+            if self.established() {
+                let payload = self.conn_meta_datas.parse_and_check_incoming_message(
+                    &self.currency,
+                    &self.signator,
+                    &json_message,
+                );
+                self.send_to_conductor(payload)?;
+            } else {
+                self.negotiate(&json_message)?;
+                if self.established() {
+                    if let Some(delay) = self.keep_alive.start() {
+                        self.ws.timeout(delay, KEEP_ALIVE)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+    fn on_timeout(&mut self, event: Token) -> ws::Result<()> {
+        match event {
+            CONNECT => {
+                if self.established() {
+                    Ok(())
+                } else {
+                    debug!("WS2P: negotiation timeout with {}", self.remote_addr);
+                    self.ws.close(CloseCode::Away)
+                }
+            }
+            EXPIRE => {
+                if self.established() {
+                    self.send_to_conductor(WS2Pv1MsgPayload::Timeout)?;
+                }
+                self.ws.close(CloseCode::Away)
+            }
+            KEEP_ALIVE => {
+                if self.keep_alive.on_timeout() {
+                    debug!("WS2P: keep-alive timeout with {}", self.remote_addr);
+                    self.send_to_conductor(WS2Pv1MsgPayload::KeepAliveTimeout)?;
+                    self.ws.close(CloseCode::Away)
+                } else {
+                    self.ws.ping(Vec::new())?;
+                    self.ws.timeout(self.keep_alive.delay(), KEEP_ALIVE)
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+    #[allow(deprecated)]
+    fn on_new_timeout(&mut self, event: Token, timeout: Timeout) -> ws::Result<()> {
+        if event == EXPIRE {
+            if let Some(t) = self.timeout.take() {
+                self.ws.cancel(t)?;
+            }
+            self.timeout = Some(timeout)
+        }
+        Ok(())
+    }
+    fn on_frame(&mut self, frame: Frame) -> ws::Result<Option<Frame>> {
+        // some activity has occurred, let's reset the expiration timeout
+        self.ws.timeout(WS2P_EXPIRE_TIMEOUT * 1_000, EXPIRE)?;
+        self.keep_alive.on_frame(&frame);
+        Ok(Some(frame))
+    }
+    fn on_close(&mut self, code: CloseCode, reason: &str) {
+        debug!(
+            "WS2P: incoming connection from {} closed ({:?}): {}",
+            self.remote_addr, code, reason
+        );
+        if self.established() {
+            let _ =
+                self.send_to_conductor(WS2Pv1MsgPayload::IncomingClose(self.ws.connection_id()));
+        }
+    }
+}
@@ -0,0 +1,28 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! WS2P light-client module constants
+
+/// Module name
+pub static MODULE_NAME: &str = "ws2p-client";
+
+/// Default host on which to listen for clients
+pub static DEFAULT_HOST: &str = "localhost";
+
+/// Default port on which to listen for clients
+pub static DEFAULT_PORT: &u16 = &20_903;
+
+/// Default maximum number of connected clients
+pub static DEFAULT_MAX_CLIENTS: &usize = &100;
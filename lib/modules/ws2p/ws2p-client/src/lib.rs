@@ -0,0 +1,409 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! WebSocketToPeer light-client API for the Dunitrust project.
+//!
+//! Lets light clients (like mobile wallets) subscribe to new blocks and HEADs concerning
+//! given public keys, and request block proofs, without polling an HTTP API.
+
+#![deny(
+    clippy::option_unwrap_used,
+    clippy::result_unwrap_used,
+    missing_docs,
+    missing_debug_implementations,
+    missing_copy_implementations,
+    trivial_casts,
+    unsafe_code,
+    unstable_features,
+    unused_import_braces,
+    unused_qualifications
+)]
+
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate serde_derive;
+#[macro_use]
+extern crate structopt;
+
+pub mod constants;
+pub mod protocol;
+pub mod server;
+
+use crate::protocol::*;
+use crate::server::{ClientEvent, ClientId};
+use dubp_common_doc::BlockNumber;
+use dubp_currency_params::CurrencyName;
+use durs_common_tools::fatal_error;
+use durs_common_tools::fns::thread::spawn_child;
+use durs_common_tools::traits::merge::Merge;
+use durs_conf::DuRsConf;
+use durs_message::events::*;
+use durs_message::requests::*;
+use durs_message::responses::*;
+use durs_message::*;
+use durs_module::*;
+use durs_network::events::NetworkEvent;
+use failure::Fail;
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::sync::mpsc;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// WS2P light-client module configuration
+pub struct WS2PClientConf {
+    /// Host on which to listen for clients
+    pub host: String,
+    /// Port on which to listen for clients
+    pub port: u16,
+    /// Maximum number of connected clients
+    pub max_clients: usize,
+}
+
+impl Default for WS2PClientConf {
+    fn default() -> Self {
+        WS2PClientConf {
+            host: constants::DEFAULT_HOST.to_owned(),
+            port: *constants::DEFAULT_PORT,
+            max_clients: *constants::DEFAULT_MAX_CLIENTS,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// WS2P light-client module user configuration
+pub struct WS2PClientUserConf {
+    /// Host on which to listen for clients
+    pub host: Option<String>,
+    /// Port on which to listen for clients
+    pub port: Option<u16>,
+    /// Maximum number of connected clients
+    pub max_clients: Option<usize>,
+}
+
+impl Merge for WS2PClientUserConf {
+    fn merge(self, other: Self) -> Self {
+        WS2PClientUserConf {
+            host: self.host.or(other.host),
+            port: self.port.or(other.port),
+            max_clients: self.max_clients.or(other.max_clients),
+        }
+    }
+}
+
+#[derive(Debug, Fail)]
+/// WS2P light-client module error
+pub enum WS2PClientError {
+    /// Fail to listen clients
+    #[fail(display = "Fail to listen clients on {}:{}: {}", _0, _1, _2)]
+    FailToListen(String, u16, String),
+}
+
+#[derive(StructOpt, Debug, Copy, Clone)]
+#[structopt(
+    name = "ws2p-client",
+    setting(structopt::clap::AppSettings::ColoredHelp)
+)]
+/// WS2P light-client subcommand options
+pub struct WS2PClientOpt {}
+
+#[derive(Debug)]
+/// Message received by the main thread of the module
+pub enum WS2PClientMsg {
+    /// Message from another module
+    DursMsg(Box<DursMsg>),
+    /// Event on a client connection
+    ClientEvent(ClientEvent),
+}
+
+#[derive(Debug)]
+/// Connected client
+struct Client {
+    ws: ws::Sender,
+    subscription: Subscription,
+}
+
+#[derive(Debug)]
+/// Data of the running module
+struct WS2PClientModuleDatas {
+    clients: HashMap<ClientId, Client>,
+    count_requests: u32,
+    pending_proofs: HashMap<ModuleReqId, (ClientId, u32)>,
+    router_sender: mpsc::Sender<RouterThreadMessage<DursMsg>>,
+}
+
+impl WS2PClientModuleDatas {
+    fn send_to_client(&mut self, client_id: ClientId, msg: &ServerMsg) {
+        if let Some(client) = self.clients.get(&client_id) {
+            if let Err(e) = server::send_to_client(&client.ws, msg) {
+                debug!("WS2P-CLIENT: fail to send message to client: {}", e);
+                self.clients.remove(&client_id);
+            }
+        }
+    }
+    fn broadcast<F>(&mut self, msg: &ServerMsg, filter: F)
+    where
+        F: Fn(&Subscription) -> bool,
+    {
+        let recipients: Vec<ClientId> = self
+            .clients
+            .iter()
+            .filter(|(_, client)| filter(&client.subscription))
+            .map(|(client_id, _)| *client_id)
+            .collect();
+        for client_id in recipients {
+            self.send_to_client(client_id, msg);
+        }
+    }
+    fn request_block_proof(&mut self, client_id: ClientId, client_req_id: u32, number: u32) {
+        self.count_requests = self.count_requests.wrapping_add(1);
+        let req_id = ModuleReqId(self.count_requests);
+        self.pending_proofs
+            .insert(req_id, (client_id, client_req_id));
+        if self
+            .router_sender
+            .send(RouterThreadMessage::ModuleMessage(DursMsg::Request {
+                req_from: WS2PClientModule::name(),
+                req_to: ModuleRole::BlockchainDatas,
+                req_id,
+                req_content: DursReqContent::BlockchainRequest(BlockchainRequest::BlockByNumber {
+                    block_number: BlockNumber(number),
+                }),
+            }))
+            .is_err()
+        {
+            fatal_error!("WS2P-CLIENT: fail to send message to router !");
+        }
+    }
+    fn handle_client_event(&mut self, event: ClientEvent) {
+        match event {
+            ClientEvent::Open(client_id, ws) => {
+                self.clients.insert(
+                    client_id,
+                    Client {
+                        ws,
+                        subscription: Subscription::default(),
+                    },
+                );
+            }
+            ClientEvent::Close(client_id) => {
+                self.clients.remove(&client_id);
+                self.pending_proofs
+                    .retain(|_, (proof_client_id, _)| *proof_client_id != client_id);
+            }
+            ClientEvent::Message(client_id, msg) => match msg {
+                ClientMsg::Subscribe(subscribe_msg) => match subscribe_msg.into_subscription() {
+                    Ok(subscription) => {
+                        if let Some(client) = self.clients.get_mut(&client_id) {
+                            client.subscription = subscription;
+                        }
+                    }
+                    Err(message) => self.send_to_client(
+                        client_id,
+                        &ServerMsg::Error {
+                            req_id: None,
+                            message,
+                        },
+                    ),
+                },
+                ClientMsg::Unsubscribe => {
+                    if let Some(client) = self.clients.get_mut(&client_id) {
+                        client.subscription = Subscription::default();
+                    }
+                }
+                ClientMsg::GetBlockProof { req_id, number } => {
+                    self.request_block_proof(client_id, req_id, number)
+                }
+            },
+        }
+    }
+    fn handle_durs_msg(&mut self, durs_msg: &DursMsg) {
+        match durs_msg {
+            DursMsg::Event {
+                ref event_content, ..
+            } => match *event_content {
+                DursEvent::BlockchainEvent(ref blockchain_event) => {
+                    if let BlockchainEvent::StackUpValidBlock(ref block) = *blockchain_event.deref()
+                    {
+                        let msg = ServerMsg::Block {
+                            block: block_to_json(block),
+                        };
+                        self.broadcast(&msg, |subscription| subscription.wants_block(block));
+                    }
+                }
+                DursEvent::NetworkEvent(NetworkEvent::ReceiveHeads(ref heads)) => {
+                    for head in heads {
+                        let msg = ServerMsg::Head {
+                            head: head_summary(head),
+                        };
+                        self.broadcast(&msg, |subscription| subscription.wants_head(head));
+                    }
+                }
+                _ => {}
+            },
+            DursMsg::Response {
+                req_id,
+                res_content: DursResContent::BlockchainResponse(ref response),
+                ..
+            } => {
+                if let Some((client_id, client_req_id)) = self.pending_proofs.remove(req_id) {
+                    let msg = if let BlockchainResponse::BlockByNumber(ref block) = *response {
+                        ServerMsg::BlockProof {
+                            req_id: client_req_id,
+                            proof: block_proof(block),
+                        }
+                    } else {
+                        ServerMsg::Error {
+                            req_id: Some(client_req_id),
+                            message: "Block not found".to_owned(),
+                        }
+                    };
+                    self.send_to_client(client_id, &msg);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+/// WS2P light-client module
+pub struct WS2PClientModule {}
+
+impl DursModule<DuRsConf, DursMsg> for WS2PClientModule {
+    type ModuleUserConf = WS2PClientUserConf;
+    type ModuleConf = WS2PClientConf;
+    type ModuleOpt = WS2PClientOpt;
+
+    fn name() -> ModuleStaticName {
+        ModuleStaticName(constants::MODULE_NAME)
+    }
+    fn priority() -> ModulePriority {
+        ModulePriority::Optional
+    }
+    fn ask_required_keys() -> RequiredKeys {
+        RequiredKeys::None
+    }
+    fn listen_addrs(module_conf: &Self::ModuleConf) -> Vec<(String, u16)> {
+        vec![(module_conf.host.clone(), module_conf.port)]
+    }
+    fn have_subcommand() -> bool {
+        false
+    }
+    fn generate_module_conf(
+        _currency_name: Option<&CurrencyName>,
+        _global_conf: &<DuRsConf as DursConfTrait>::GlobalConf,
+        module_user_conf: Option<Self::ModuleUserConf>,
+    ) -> Result<(Self::ModuleConf, Option<Self::ModuleUserConf>), ModuleConfError> {
+        let mut conf = WS2PClientConf::default();
+
+        if let Some(ref module_user_conf) = module_user_conf {
+            if let Some(ref host) = module_user_conf.host {
+                conf.host = host.to_owned();
+            }
+            if let Some(port) = module_user_conf.port {
+                conf.port = port;
+            }
+            if let Some(max_clients) = module_user_conf.max_clients {
+                conf.max_clients = max_clients;
+            }
+        }
+
+        Ok((conf, module_user_conf))
+    }
+    fn exec_subcommand(
+        _soft_meta_datas: &SoftwareMetaDatas<DuRsConf>,
+        _keys: RequiredKeysContent,
+        _module_conf: Self::ModuleConf,
+        module_user_conf: Option<Self::ModuleUserConf>,
+        _subcommand_args: Self::ModuleOpt,
+    ) -> Option<Self::ModuleUserConf> {
+        module_user_conf
+    }
+    fn start(
+        _soft_meta_datas: &SoftwareMetaDatas<DuRsConf>,
+        _keys: RequiredKeysContent,
+        conf: Self::ModuleConf,
+        router_sender: mpsc::Sender<RouterThreadMessage<DursMsg>>,
+    ) -> Result<(), failure::Error> {
+        // Create module main thread channel
+        let (module_sender, module_receiver) = mpsc::channel();
+
+        // Listen clients
+        let listener =
+            server::listen_clients(&conf.host, conf.port, conf.max_clients, &module_sender)
+                .map_err(|e| {
+                    WS2PClientError::FailToListen(conf.host.clone(), conf.port, e.to_string())
+                })?;
+
+        // Create proxy channel
+        let (proxy_sender, proxy_receiver): (mpsc::Sender<DursMsg>, mpsc::Receiver<DursMsg>) =
+            mpsc::channel();
+
+        // Registration with the router
+        if router_sender
+            .send(RouterThreadMessage::ModuleRegistration {
+                static_name: ModuleStaticName(constants::MODULE_NAME),
+                sender: proxy_sender,
+                roles: vec![ModuleRole::ClientsNetwork],
+                events_subscription: vec![
+                    ModuleEvent::NewValidBlock,
+                    ModuleEvent::NewValidHeadFromNetwork,
+                ],
+                reserved_apis_parts: vec![],
+                endpoints: vec![],
+            })
+            .is_err()
+        {
+            fatal_error!("WS2P-CLIENT module fail to send registration to router !")
+        }
+
+        // Relay messages of other modules to the main thread
+        spawn_child("proxy", move || {
+            while let Ok(msg) = proxy_receiver.recv() {
+                let stop = msg == DursMsg::Stop;
+                if module_sender
+                    .send(WS2PClientMsg::DursMsg(Box::new(msg)))
+                    .is_err()
+                    || stop
+                {
+                    break;
+                }
+            }
+        });
+
+        let mut datas = WS2PClientModuleDatas {
+            clients: HashMap::new(),
+            count_requests: 0,
+            pending_proofs: HashMap::new(),
+            router_sender,
+        };
+
+        while let Ok(msg) = module_receiver.recv() {
+            match msg {
+                WS2PClientMsg::DursMsg(durs_msg) => {
+                    if let DursMsg::Stop = *durs_msg {
+                        break;
+                    }
+                    datas.handle_durs_msg(&durs_msg);
+                }
+                WS2PClientMsg::ClientEvent(event) => datas.handle_client_event(event),
+            }
+        }
+
+        let _ = listener.shutdown();
+        Ok(())
+    }
+}
@@ -0,0 +1,304 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Light-client protocol: JSON messages exchanged between the node and its clients.
+//!
+//! Clients subscribe to new blocks and/or HEADs, optionally filtered by public keys,
+//! and can request the proof of a block (its signed part, issuer and signature).
+
+use dubp_block_doc::block::{BlockDocument, BlockDocumentTrait};
+use dubp_common_doc::traits::{Document, ToStringObject};
+use dup_crypto::keys::*;
+use durs_network_documents::network_head::NetworkHead;
+use std::collections::HashSet;
+use std::str::FromStr;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+/// Subscription requested by a client
+pub struct SubscribeMsg {
+    /// Receive new valid blocks
+    #[serde(default)]
+    pub blocks: bool,
+    /// Receive new valid HEADs
+    #[serde(default)]
+    pub heads: bool,
+    /// Only receive blocks and HEADs concerning these public keys (all if empty)
+    #[serde(default)]
+    pub pubkeys: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+/// Message sent by a client
+pub enum ClientMsg {
+    /// Replace the current subscription
+    Subscribe(SubscribeMsg),
+    /// Cancel the current subscription
+    Unsubscribe,
+    /// Request the proof of a block
+    #[serde(rename_all = "camelCase")]
+    GetBlockProof {
+        /// Request id, chosen by the client
+        req_id: u32,
+        /// Block number
+        number: u32,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+/// Proof of a block: a client knowing the issuer key can check the signature of the signed string
+pub struct BlockProof {
+    /// Block number
+    pub number: u32,
+    /// Block hash
+    pub hash: String,
+    /// Hash of the previous block
+    pub previous_hash: Option<String>,
+    /// Block issuer
+    pub issuer: String,
+    /// Issuer signature of the signed string
+    pub signature: String,
+    /// Signed part of the block
+    pub signed_string: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+/// Summary of a HEAD
+pub struct HeadSummary {
+    /// Node public key
+    pub pubkey: String,
+    /// Node member uid
+    pub uid: Option<String>,
+    /// Current blockstamp of the node
+    pub blockstamp: String,
+    /// Software name
+    pub software: String,
+    /// Software version
+    pub soft_version: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+/// Message sent to a client
+pub enum ServerMsg {
+    /// New valid block
+    Block {
+        /// Block document
+        block: serde_json::Value,
+    },
+    /// New valid HEAD
+    Head {
+        /// HEAD summary
+        head: HeadSummary,
+    },
+    /// Requested block proof
+    #[serde(rename_all = "camelCase")]
+    BlockProof {
+        /// Request id, chosen by the client
+        req_id: u32,
+        /// Block proof
+        proof: BlockProof,
+    },
+    /// Error
+    #[serde(rename_all = "camelCase")]
+    Error {
+        /// Request id (if the error concerns a request)
+        req_id: Option<u32>,
+        /// Error message
+        message: String,
+    },
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+/// Current subscription of a client
+pub struct Subscription {
+    /// Receive new valid blocks
+    pub blocks: bool,
+    /// Receive new valid HEADs
+    pub heads: bool,
+    /// Only receive blocks and HEADs concerning these public keys (all if empty)
+    pub pubkeys: HashSet<PubKey>,
+}
+
+impl SubscribeMsg {
+    /// Parse subscription public keys
+    pub fn into_subscription(self) -> Result<Subscription, String> {
+        let pubkeys = self
+            .pubkeys
+            .iter()
+            .map(|pubkey| {
+                PubKey::from_str(pubkey).map_err(|e| format!("Invalid pubkey '{}': {}", pubkey, e))
+            })
+            .collect::<Result<HashSet<PubKey>, String>>()?;
+        Ok(Subscription {
+            blocks: self.blocks,
+            heads: self.heads,
+            pubkeys,
+        })
+    }
+}
+
+impl Subscription {
+    /// Does the client want to receive this block ?
+    pub fn wants_block(&self, block: &BlockDocument) -> bool {
+        self.blocks
+            && (self.pubkeys.is_empty()
+                || block_pubkeys(block)
+                    .iter()
+                    .any(|pubkey| self.pubkeys.contains(pubkey)))
+    }
+    /// Does the client want to receive this HEAD ?
+    pub fn wants_head(&self, head: &NetworkHead) -> bool {
+        self.heads && (self.pubkeys.is_empty() || self.pubkeys.contains(&head.pubkey()))
+    }
+}
+
+/// Public keys concerned by a block (issuer and issuers of the documents it contains)
+pub fn block_pubkeys(block: &BlockDocument) -> HashSet<PubKey> {
+    let BlockDocument::V10(block) = block;
+    let mut pubkeys: HashSet<PubKey> = block.issuers.iter().copied().collect();
+    for idty in &block.identities {
+        pubkeys.extend(idty.issuers());
+    }
+    for membership in block
+        .joiners
+        .iter()
+        .chain(block.actives.iter())
+        .chain(block.leavers.iter())
+    {
+        pubkeys.extend(membership.issuers());
+    }
+    for revocation in &block.revoked {
+        pubkeys.insert(revocation.to_compact_document().issuer);
+    }
+    pubkeys.extend(&block.excluded);
+    for cert in &block.certifications {
+        let cert = cert.to_compact_document();
+        pubkeys.insert(cert.issuer);
+        pubkeys.insert(cert.target);
+    }
+    for tx in &block.transactions {
+        pubkeys.extend(tx.issuers());
+    }
+    pubkeys
+}
+
+/// Serialize a block for clients
+pub fn block_to_json(block: &BlockDocument) -> serde_json::Value {
+    serde_json::to_value(block.to_string_object()).unwrap_or(serde_json::Value::Null)
+}
+
+/// Generate the proof of a block
+pub fn block_proof(block: &BlockDocument) -> BlockProof {
+    let BlockDocument::V10(block_v10) = block;
+    BlockProof {
+        number: block.number().0,
+        hash: block.hash().unwrap_or_default().to_string(),
+        previous_hash: block.previous_hash().map(|hash| hash.to_hex()),
+        issuer: block_v10
+            .issuers
+            .first()
+            .map(ToString::to_string)
+            .unwrap_or_default(),
+        signature: block_v10
+            .signatures
+            .first()
+            .map(ToString::to_string)
+            .unwrap_or_default(),
+        signed_string: block.compute_will_signed_string(),
+    }
+}
+
+/// Summarize a HEAD for clients
+pub fn head_summary(head: &NetworkHead) -> HeadSummary {
+    HeadSummary {
+        pubkey: head.pubkey().to_string(),
+        uid: head.uid(),
+        blockstamp: head.blockstamp().to_string(),
+        software: head.software(),
+        soft_version: head.soft_version(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_client_messages() {
+        assert_eq!(
+            ClientMsg::Subscribe(SubscribeMsg {
+                blocks: true,
+                heads: false,
+                pubkeys: vec!["D9D2zaJoWYWveii1JRYLVK3J4Z7ZH3QczoKrnQeiM6mx".to_owned()],
+            }),
+            serde_json::from_str::<ClientMsg>(
+                r#"{"type":"subscribe","blocks":true,"pubkeys":["D9D2zaJoWYWveii1JRYLVK3J4Z7ZH3QczoKrnQeiM6mx"]}"#
+            )
+            .expect("Fail to parse subscribe message")
+        );
+        assert_eq!(
+            ClientMsg::Unsubscribe,
+            serde_json::from_str::<ClientMsg>(r#"{"type":"unsubscribe"}"#)
+                .expect("Fail to parse unsubscribe message")
+        );
+        assert_eq!(
+            ClientMsg::GetBlockProof {
+                req_id: 3,
+                number: 42
+            },
+            serde_json::from_str::<ClientMsg>(r#"{"type":"getBlockProof","reqId":3,"number":42}"#)
+                .expect("Fail to parse getBlockProof message")
+        );
+        assert!(SubscribeMsg {
+            blocks: true,
+            heads: true,
+            pubkeys: vec!["invalid".to_owned()],
+        }
+        .into_subscription()
+        .is_err());
+    }
+
+    #[test]
+    fn filter_blocks_by_pubkey() {
+        let block = BlockDocument::V10(dubp_blocks_tests_tools::mocks::gen_mock_normal_block_v10());
+        let BlockDocument::V10(ref block_v10) = block;
+        let issuer = block_v10.issuers[0];
+
+        let mut subscription = Subscription::default();
+        assert!(!subscription.wants_block(&block));
+        subscription.blocks = true;
+        assert!(subscription.wants_block(&block));
+        subscription.pubkeys.insert(
+            PubKey::from_str("2ny7YAdmzReQxAayyJZsyVYwYhVyax2thKcGknmQy5nQ")
+                .expect("invalid pubkey"),
+        );
+        assert!(!subscription.wants_block(&block));
+        subscription.pubkeys.insert(issuer);
+        assert!(subscription.wants_block(&block));
+
+        let proof = block_proof(&block);
+        assert_eq!(issuer.to_string(), proof.issuer);
+        assert_eq!(block_v10.number.0, proof.number);
+        assert_eq!(
+            "blockProof",
+            serde_json::to_value(ServerMsg::BlockProof { req_id: 1, proof })
+                .expect("Fail to serialize block proof")["type"]
+        );
+    }
+}
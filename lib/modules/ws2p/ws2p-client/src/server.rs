@@ -0,0 +1,124 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Listen for light-client connections.
+
+use crate::protocol::{ClientMsg, ServerMsg};
+use crate::WS2PClientMsg;
+use durs_common_tools::fns::thread::spawn_child;
+use std::sync::mpsc;
+use ws::{CloseCode, Handler, Handshake, Message, Sender};
+
+/// Identifier of a client connection
+pub type ClientId = u32;
+
+#[derive(Debug)]
+/// Event on a client connection
+pub enum ClientEvent {
+    /// New client connection
+    Open(ClientId, Sender),
+    /// Message received from a client
+    Message(ClientId, ClientMsg),
+    /// Closed client connection
+    Close(ClientId),
+}
+
+struct ClientHandler {
+    ws: Sender,
+    module_sender: mpsc::Sender<WS2PClientMsg>,
+}
+
+impl ClientHandler {
+    fn send_event(&self, event: ClientEvent) -> ws::Result<()> {
+        if self
+            .module_sender
+            .send(WS2PClientMsg::ClientEvent(event))
+            .is_err()
+        {
+            self.ws.shutdown()
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Handler for ClientHandler {
+    fn on_open(&mut self, _shake: Handshake) -> ws::Result<()> {
+        self.send_event(ClientEvent::Open(self.ws.connection_id(), self.ws.clone()))
+    }
+    fn on_message(&mut self, msg: Message) -> ws::Result<()> {
+        let parse_result = msg
+            .as_text()
+            .map_err(|e| e.to_string())
+            .and_then(|text| serde_json::from_str::<ClientMsg>(text).map_err(|e| e.to_string()));
+        match parse_result {
+            Ok(client_msg) => {
+                self.send_event(ClientEvent::Message(self.ws.connection_id(), client_msg))
+            }
+            Err(e) => send_to_client(
+                &self.ws,
+                &ServerMsg::Error {
+                    req_id: None,
+                    message: format!("Invalid message: {}", e),
+                },
+            ),
+        }
+    }
+    fn on_close(&mut self, _code: CloseCode, _reason: &str) {
+        let _ = self.send_event(ClientEvent::Close(self.ws.connection_id()));
+    }
+}
+
+/// Send a message to a client
+pub fn send_to_client(ws: &Sender, msg: &ServerMsg) -> ws::Result<()> {
+    match serde_json::to_string(msg) {
+        Ok(json) => ws.send(Message::text(json)),
+        Err(e) => {
+            warn!("WS2P-CLIENT: fail to serialize message: {}", e);
+            Ok(())
+        }
+    }
+}
+
+/// Listen for clients connections in a dedicated thread.
+/// Returns a sender that allows to shutdown the listener.
+pub fn listen_clients(
+    host: &str,
+    port: u16,
+    max_clients: usize,
+    module_sender: &mpsc::Sender<WS2PClientMsg>,
+) -> ws::Result<Sender> {
+    let module_sender = module_sender.clone();
+    let ws = ws::Builder::new()
+        .with_settings(ws::Settings {
+            max_connections: max_clients,
+            ..ws::Settings::default()
+        })
+        .build(move |ws| ClientHandler {
+            ws,
+            module_sender: module_sender.clone(),
+        })?
+        .bind((host, port))?;
+    let broadcaster = ws.broadcaster();
+
+    info!("WS2P-CLIENT: listen clients on {}:{}", host, port);
+    spawn_child("server", move || {
+        if let Err(e) = ws.run() {
+            error!("WS2P-CLIENT: clients listener stopped: {}", e);
+        }
+    });
+
+    Ok(broadcaster)
+}
@@ -0,0 +1,152 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Framing of binary WS2P messages, with optional compression.
+//!
+//! Once the connection is established and the ZIP feature negotiated, each binary message
+//! starts with a flag byte indicating the compression of the rest of the message.
+
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use std::io::{Read, Write};
+
+/// Size from which a message is compressed (smaller messages are not worth it)
+pub static WS2P_COMPRESSION_THRESHOLD: &usize = &512;
+
+/// Maximum size of a decompressed message
+pub static WS2P_MAX_DECOMPRESSED_MSG_SIZE: &u64 = &67_108_864;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Compression of a framed message (value of its flag byte)
+pub enum WS2PCompression {
+    /// Uncompressed message
+    None = 0,
+    /// Message compressed with zlib
+    Zlib = 1,
+}
+
+/// Error when reading a framed message
+#[derive(Debug)]
+pub enum WS2PFramingError {
+    /// Empty message
+    EmptyMessage,
+    /// Unknown compression flag
+    UnknownFlag(u8),
+    /// Fail to decompress message
+    DecompressionError(std::io::Error),
+    /// Decompressed message exceeds WS2P_MAX_DECOMPRESSED_MSG_SIZE
+    TooLargeMessage,
+}
+
+impl From<std::io::Error> for WS2PFramingError {
+    fn from(e: std::io::Error) -> Self {
+        WS2PFramingError::DecompressionError(e)
+    }
+}
+
+/// Frame a binary message, compress it if it reduces its size
+pub fn frame_bin_message(bin_msg: &[u8]) -> Vec<u8> {
+    if bin_msg.len() >= *WS2P_COMPRESSION_THRESHOLD {
+        if let Some(compressed_msg) = zlib_compress(bin_msg) {
+            if compressed_msg.len() < bin_msg.len() {
+                let mut framed_msg = Vec::with_capacity(compressed_msg.len() + 1);
+                framed_msg.push(WS2PCompression::Zlib as u8);
+                framed_msg.extend(compressed_msg);
+                return framed_msg;
+            }
+        }
+    }
+    let mut framed_msg = Vec::with_capacity(bin_msg.len() + 1);
+    framed_msg.push(WS2PCompression::None as u8);
+    framed_msg.extend_from_slice(bin_msg);
+    framed_msg
+}
+
+/// Get the binary message contained in a framed message
+pub fn unframe_bin_message(framed_msg: &[u8]) -> Result<Vec<u8>, WS2PFramingError> {
+    let (flag, bin_msg) = if let Some((flag, bin_msg)) = framed_msg.split_first() {
+        (*flag, bin_msg)
+    } else {
+        return Err(WS2PFramingError::EmptyMessage);
+    };
+    if flag == WS2PCompression::None as u8 {
+        Ok(bin_msg.to_vec())
+    } else if flag == WS2PCompression::Zlib as u8 {
+        let mut decompressed_msg = Vec::new();
+        ZlibDecoder::new(bin_msg)
+            .take(*WS2P_MAX_DECOMPRESSED_MSG_SIZE + 1)
+            .read_to_end(&mut decompressed_msg)?;
+        if decompressed_msg.len() as u64 > *WS2P_MAX_DECOMPRESSED_MSG_SIZE {
+            Err(WS2PFramingError::TooLargeMessage)
+        } else {
+            Ok(decompressed_msg)
+        }
+    } else {
+        Err(WS2PFramingError::UnknownFlag(flag))
+    }
+}
+
+fn zlib_compress(bin_msg: &[u8]) -> Option<Vec<u8>> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(bin_msg).ok()?;
+    encoder.finish().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v2::payload_container::WS2Pv2MessagePayload;
+    use crate::v2::WS2Pv2Message;
+    use dubp_blocks_tests_tools::mocks::gen_empty_timed_blocks_v10;
+    use dubp_currency_params::CurrencyName;
+    use dup_crypto::keys::*;
+    use durs_network_documents::NodeId;
+    use unwrap::unwrap;
+
+    #[test]
+    fn test_frame_small_message() {
+        let bin_msg = vec![1u8, 2, 3];
+        let framed_msg = frame_bin_message(&bin_msg);
+        assert_eq!(vec![0u8, 1, 2, 3], framed_msg);
+        assert_eq!(bin_msg, unwrap!(unframe_bin_message(&framed_msg)));
+    }
+
+    #[test]
+    fn test_unframe_invalid_message() {
+        assert!(unframe_bin_message(&[]).is_err());
+        assert!(unframe_bin_message(&[2u8, 1, 2, 3]).is_err());
+        assert!(unframe_bin_message(&[1u8, 1, 2, 3]).is_err());
+    }
+
+    #[test]
+    fn test_frame_sync_chunk_reduces_bandwidth() {
+        // A chunk of blocks as sent during synchronization
+        let seed = Seed32::new([1u8; 32]);
+        let key_pair = KeyPairEnum::Ed25519(ed25519::KeyPairFromSeed32Generator::generate(seed));
+        let signator = unwrap!(key_pair.generate_signator());
+        let (_, bin_msg) = unwrap!(WS2Pv2Message::encapsulate_payload(
+            CurrencyName("g1".to_owned()),
+            NodeId(1),
+            &signator,
+            WS2Pv2MessagePayload::Blocks(gen_empty_timed_blocks_v10(250, 300)),
+        ));
+
+        let framed_msg = frame_bin_message(&bin_msg);
+        assert_eq!(WS2PCompression::Zlib as u8, framed_msg[0]);
+        assert!(framed_msg.len() * 2 < bin_msg.len());
+        assert_eq!(bin_msg, unwrap!(unframe_bin_message(&framed_msg)));
+    }
+}
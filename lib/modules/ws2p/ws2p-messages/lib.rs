@@ -36,6 +36,8 @@ extern crate pretty_assertions;*/
 #[macro_use]
 extern crate serde_derive;
 
+/// Compression of binary messages
+pub mod compression;
 /// WS2Pv2 Messages
 pub mod v2;
 
@@ -61,6 +63,8 @@ pub enum WS2PMessage {
 pub enum WS2PMessageError {
     /// Error at deserialization
     DeserError(bincode::Error),
+    /// Invalid framing
+    FramingError(compression::WS2PFramingError),
     /// Invalid hash
     InvalidHash,
     /// Invalid signature
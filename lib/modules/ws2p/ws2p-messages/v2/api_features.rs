@@ -39,6 +39,41 @@ impl WS2PFeatures {
     pub fn abf(self) -> bool {
         self.0[0] | 0b1111_1011 == 255u8
     }
+    /// Check flag ZIP
+    pub fn zip(self) -> bool {
+        self.0[0] | 0b1111_0111 == 255u8
+    }
+    /// Enable or disable flag ZIP
+    pub fn with_zip(mut self, zip: bool) -> WS2PFeatures {
+        if zip {
+            self.0[0] |= 0b0000_1000;
+        } else {
+            self.0[0] &= 0b1111_0111;
+        }
+        self
+    }
+    /// Check flag OPM
+    pub fn opm(self) -> bool {
+        self.0[0] | 0b1110_1111 == 255u8
+    }
+    /// Enable or disable flag OPM
+    pub fn with_opm(mut self, opm: bool) -> WS2PFeatures {
+        if opm {
+            self.0[0] |= 0b0001_0000;
+        } else {
+            self.0[0] &= 0b1110_1111;
+        }
+        self
+    }
+    /// Enable or disable flag LOW
+    pub fn with_low(mut self, low: bool) -> WS2PFeatures {
+        if low {
+            self.0[0] |= 0b0000_0010;
+        } else {
+            self.0[0] &= 0b1111_1101;
+        }
+        self
+    }
     /// Check features compatibility
     pub fn check_features_compatibility(
         self,
@@ -49,17 +84,69 @@ impl WS2PFeatures {
         if self.def() && !remote_features.def() {
             merged_features.0[0] &= 0b1111_1110;
         }
-        if self.low() && !remote_features.low() {
-            merged_features.0[0] &= 0b1111_1101;
-        }
         if self.abf() && !remote_features.abf() {
             merged_features.0[0] &= 0b1111_1011;
         }
-        // Check incompatiblities
-        if remote_features.low() && !self.low() {
-            Err(())
-        } else {
-            Ok(merged_features)
+        if self.zip() && !remote_features.zip() {
+            merged_features.0[0] &= 0b1111_0111;
         }
+        if self.opm() && !remote_features.opm() {
+            merged_features.0[0] &= 0b1110_1111;
+        }
+        // A node in low bandwidth mode only receives what it explicitly asks for,
+        // so flag LOW is honored as soon as one of the two nodes requests it.
+        Ok(merged_features.with_low(self.low() || remote_features.low()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ws2p_features_low() {
+        let default_features = WS2PFeatures([5u8, 0, 0, 0]);
+        let low_features = default_features.with_low(true);
+        assert_eq!(WS2PFeatures([7u8, 0, 0, 0]), low_features);
+        assert!(low_features.low());
+        assert!(!default_features.low());
+        assert_eq!(default_features, low_features.with_low(false));
+    }
+
+    #[test]
+    fn test_ws2p_features_compatibility() {
+        let default_features = WS2PFeatures([5u8, 0, 0, 0]);
+        let low_features = WS2PFeatures([3u8, 0, 0, 0]);
+        assert_eq!(
+            Ok(default_features),
+            default_features.check_features_compatibility(default_features)
+        );
+        // LOW is honored whichever node requests it
+        assert_eq!(
+            Ok(WS2PFeatures([3u8, 0, 0, 0])),
+            default_features.check_features_compatibility(low_features)
+        );
+        assert_eq!(
+            Ok(WS2PFeatures([3u8, 0, 0, 0])),
+            low_features.check_features_compatibility(default_features)
+        );
+        // ZIP is used only if both nodes support it
+        let zip_features = default_features.with_zip(true);
+        assert!(zip_features.zip());
+        assert_eq!(
+            Ok(zip_features),
+            zip_features.check_features_compatibility(zip_features)
+        );
+        assert_eq!(
+            Ok(default_features),
+            zip_features.check_features_compatibility(default_features)
+        );
+        // OPM is used only if both nodes accept operator messages
+        let opm_features = default_features.with_opm(true);
+        assert!(opm_features.opm());
+        assert_eq!(
+            Ok(default_features),
+            opm_features.check_features_compatibility(default_features)
+        );
     }
 }
@@ -19,6 +19,8 @@ pub mod api_features;
 pub mod connect;
 /// WS2P v2 OK Message
 pub mod ok;
+/// WS2P v2 OPERATOR_MSG Message
+pub mod operator_msg;
 /// Message Payload container
 pub mod payload_container;
 /// WS2Pv2 requests responses messages
@@ -0,0 +1,42 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+/// Maximum size of the text of an operator message (in bytes)
+pub static WS2P_OPERATOR_MSG_MAX_LEN: &usize = &280;
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+/// WS2Pv2OperatorMsg
+pub struct WS2Pv2OperatorMsg {
+    /// Counter of the messages sent on this connection (used as nonce, must be strictly increasing)
+    pub counter: u64,
+    /// Text encrypted with the operator messages key of the connection
+    pub encrypted_text: Vec<u8>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use super::*;
+    use crate::tests::*;
+
+    #[test]
+    fn test_ws2p_message_operator_msg() {
+        test_ws2p_message(WS2Pv2MessagePayload::OperatorKey([7u8; 32]));
+        test_ws2p_message(WS2Pv2MessagePayload::OperatorMsg(WS2Pv2OperatorMsg {
+            counter: 1,
+            encrypted_text: vec![1u8, 2, 3],
+        }));
+    }
+}
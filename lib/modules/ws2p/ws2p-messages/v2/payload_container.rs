@@ -15,9 +15,11 @@
 
 use super::connect::WS2Pv2ConnectMsg;
 use super::ok::WS2Pv2OkMsg;
+use super::operator_msg::WS2Pv2OperatorMsg;
 use super::req_responses::WS2Pv2ReqRes;
 use super::requests::WS2Pv2Request;
 use super::secret_flags::WS2Pv2SecretFlagsMsg;
+use dubp_block_doc::block::BlockDocumentV10;
 use dubp_block_doc::BlockDocument;
 use dubp_user_docs::documents::certification::CertificationDocument;
 use dubp_user_docs::documents::identity::IdentityDocument;
@@ -70,4 +72,72 @@ pub enum WS2Pv2MessagePayload {
     PendingRevocations(Vec<RevocationDocumentV10>),
     /// PENDING_TXS Message
     PendingTxs(Vec<TransactionDocument>),
+    /// OPERATOR_KEY Message (ephemeral X25519 public key of the operator messages session)
+    OperatorKey([u8; 32]),
+    /// OPERATOR_MSG Message
+    OperatorMsg(WS2Pv2OperatorMsg),
+}
+
+impl WS2Pv2MessagePayload {
+    /// Version of the payload that can be sent unsolicited to a node in low bandwidth mode
+    /// (feature LOW): blocks are reduced to their headers and pending documents are not sent.
+    /// Explicitly requested datas (REQUEST_RESPONSE) are never reduced.
+    pub fn low_bandwidth_version(self) -> Option<WS2Pv2MessagePayload> {
+        match self {
+            WS2Pv2MessagePayload::Blocks(blocks) => Some(WS2Pv2MessagePayload::Blocks(
+                blocks.into_iter().map(block_header).collect(),
+            )),
+            WS2Pv2MessagePayload::PendingIdentities(_)
+            | WS2Pv2MessagePayload::PendingMemberships(_)
+            | WS2Pv2MessagePayload::PendingCerts(_)
+            | WS2Pv2MessagePayload::PendingRevocations(_)
+            | WS2Pv2MessagePayload::PendingTxs(_) => None,
+            payload => Some(payload),
+        }
+    }
+}
+
+/// Remove the body of a block (documents and transactions)
+fn block_header(block: BlockDocument) -> BlockDocument {
+    match block {
+        BlockDocument::V10(block) => BlockDocument::V10(BlockDocumentV10 {
+            identities: vec![],
+            joiners: vec![],
+            actives: vec![],
+            leavers: vec![],
+            revoked: vec![],
+            excluded: vec![],
+            certifications: vec![],
+            transactions: vec![],
+            ..block
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dubp_blocks_tests_tools::mocks::gen_empty_timed_blocks_v10;
+    use dup_crypto::keys::PubKey;
+
+    #[test]
+    fn test_low_bandwidth_version() {
+        let header = gen_empty_timed_blocks_v10(1, 0).remove(0);
+        let mut block = header.clone();
+        let BlockDocument::V10(ref mut block_v10) = block;
+        block_v10.excluded.push(PubKey::default());
+
+        assert_eq!(
+            Some(WS2Pv2MessagePayload::Blocks(vec![header])),
+            WS2Pv2MessagePayload::Blocks(vec![block.clone()]).low_bandwidth_version()
+        );
+        assert_eq!(
+            Some(WS2Pv2MessagePayload::Heads3(vec![])),
+            WS2Pv2MessagePayload::Heads3(vec![]).low_bandwidth_version()
+        );
+        assert_eq!(
+            None,
+            WS2Pv2MessagePayload::PendingTxs(vec![]).low_bandwidth_version()
+        );
+    }
 }
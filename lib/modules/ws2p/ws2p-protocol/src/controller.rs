@@ -24,9 +24,11 @@ use self::meta_datas::WS2PControllerMetaDatas;
 use crate::connection_state::WS2PConnectionState;
 use crate::constants;
 use crate::orchestrator::OrchestratorMsg;
-use crate::websocket::{WebsocketAction, WebsocketIncomingEvent};
+use crate::websocket::{WebsocketAction, WebsocketIncomingEvent, WebsocketMessage};
 use durs_module::ModuleMessage;
 use durs_network_documents::NodeFullId;
+use durs_ws2p_messages::compression::frame_bin_message;
+use durs_ws2p_messages::v2::api_features::WS2PFeatures;
 use durs_ws2p_messages::v2::connect::WS2Pv2ConnectType;
 use durs_ws2p_messages::WS2PMessage;
 use failure::Fail;
@@ -74,6 +76,8 @@ pub enum WS2PControllerEvent {
         conn_type: WS2Pv2ConnectType,
         /// Remote node full id
         remote_full_id: NodeFullId,
+        /// Features negotiated with the remote node
+        features: WS2PFeatures,
     },
     /// Connection state change
     StateChange {
@@ -189,11 +193,21 @@ impl<M: ModuleMessage> WS2PController<M> {
         })
     }
 
-    /// Get orchestrator sender
+    /// Frame binary messages to be sent if the connection uses framed messages (feature ZIP)
+    pub fn frame_outgoing_msg(&self, msg: WebsocketMessage) -> WebsocketMessage {
+        match msg {
+            WebsocketMessage::Bin(bin_msg) if self.meta_datas.framed_messages() => {
+                WebsocketMessage::Bin(frame_bin_message(&bin_msg))
+            }
+            msg => msg,
+        }
+    }
+
+    /// Get the websocket actions ordered by the orchestrator
     pub fn get_pending_ws_actions(&self) -> Vec<WebsocketActionOrder> {
         let mut ws_actions = Vec::new();
 
-        while let Ok(ws_action) = self.receiver.recv() {
+        while let Ok(ws_action) = self.receiver.try_recv() {
             ws_actions.push(ws_action);
         }
 
@@ -92,6 +92,14 @@ impl WS2PControllerMetaDatas {
             state: WS2PConnectionState::TryToOpenWS,
         }
     }
+    /// Return true if binary messages are framed (connection established with feature ZIP)
+    pub fn framed_messages(&self) -> bool {
+        if let Some(features) = self.features {
+            self.state == WS2PConnectionState::Established && features.zip()
+        } else {
+            false
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -28,8 +28,9 @@ use crate::websocket::{WebsocketAction, WebsocketMessage};
 use durs_common_tools::fatal_error;
 use durs_module::ModuleMessage;
 use durs_network_documents::NodeFullId;
+use durs_ws2p_messages::compression::unframe_bin_message;
 use durs_ws2p_messages::v2::payload_container::WS2Pv2MessagePayload;
-use durs_ws2p_messages::WS2PMessage;
+use durs_ws2p_messages::{WS2PMessage, WS2PMessageError};
 use std::ops::Deref;
 use std::thread;
 use std::time::{Duration, Instant};
@@ -66,7 +67,14 @@ pub fn process<M: ModuleMessage>(
 
     if let WebsocketMessage::Bin(bin_msg) = msg {
         log::debug!("Receive new bin message there is not a spam !");
-        match WS2PMessage::parse_and_check_bin_message(&bin_msg) {
+        let parse_result = if controller.meta_datas.framed_messages() {
+            unframe_bin_message(&bin_msg)
+                .map_err(WS2PMessageError::FramingError)
+                .and_then(|bin_msg| WS2PMessage::parse_and_check_bin_message(&bin_msg))
+        } else {
+            WS2PMessage::parse_and_check_bin_message(&bin_msg)
+        };
+        match parse_result {
             Ok(valid_msg) => match valid_msg {
                 WS2PMessage::V2(ref msg_v2) => {
                     match msg_v2.payload {
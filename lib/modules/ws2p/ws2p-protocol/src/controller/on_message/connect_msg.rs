@@ -86,7 +86,10 @@ pub fn process_ws2p_v2p_connect_msg<M: ModuleMessage>(
     {
         Ok(merged_features) => controller.meta_datas.features = Some(merged_features),
         Err(_) => {
-            super::close_with_reason("Unsupported features !", WS2PConnectionState::Denial);
+            return Ok(super::close_with_reason(
+                "Unsupported features !",
+                WS2PConnectionState::Denial,
+            ));
         }
     }
 
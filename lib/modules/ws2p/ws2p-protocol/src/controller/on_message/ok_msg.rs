@@ -48,6 +48,11 @@ pub fn process_ws2p_v2p_ok_msg<M: ModuleMessage>(
                 } else {
                     fatal_error!("remote_node must be valued in process_ws2p_v2p_ok_msg() !")
                 },
+                features: if let Some(features) = controller.meta_datas.features {
+                    features
+                } else {
+                    fatal_error!("features must be valued in process_ws2p_v2p_ok_msg() !")
+                },
             })?;
             Ok(None)
         }
@@ -21,6 +21,21 @@ pub static WS2P_DEFAULT_OUTCOMING_QUOTA: &usize = &10;
 /*pub static WS2P_OUTCOMING_INTERVAL_AT_STARTUP: &u64 = &75;
 pub static WS2P_OUTCOMING_INTERVAL: &u64 = &300;*/
 pub static WS2P_RECV_SERVICE_FREQ_IN_MS: &u64 = &1_000;
+
+/// Number of blocks requested at once during synchronization
+pub static WS2P_SYNC_CHUNK_SIZE: &u16 = &250;
+/// Maximum number of chunks downloaded in advance during synchronization
+pub static WS2P_SYNC_MAX_BUFFERED_CHUNKS: &usize = &20;
+/// Delay after which a peer that has not answered a sync request is abandoned
+pub static WS2P_SYNC_REQUEST_TIMEOUT_IN_SECS: &u64 = &30;
+
+/// Delay after which the probe of a remote endpoint is abandoned
+pub static WS2P_PING_TIMEOUT_IN_SECS: &u64 = &15;
+
+/// Maximum number of operator messages sent or received on a connection per rate window
+pub static WS2P_OPERATOR_MSGS_RATE_LIMIT: &usize = &5;
+/// Duration of the operator messages rate window
+pub static WS2P_OPERATOR_MSGS_RATE_WINDOW_IN_SECS: &u64 = &60;
 /*
 pub static WS2P_REQUEST_TIMEOUT: &u64 = &30_000;
 pub static DURATION_BEFORE_RECORDING_ENDPOINT: &u64 = &180;
@@ -48,7 +48,7 @@ impl Ws2pConnectionHandler {
                 fatal_error!("Could not generate a new connection in the context of a controller.")
             }
             WebsocketAction::SendMessage { msg } => {
-                let ws_msg = match msg {
+                let ws_msg = match self.controller.frame_outgoing_msg(msg) {
                     WebsocketMessage::Bin(bin_msg) => Message::binary(bin_msg),
                     WebsocketMessage::Str(str_msg) => Message::text(str_msg),
                 };
@@ -177,6 +177,10 @@ impl Handler for Ws2pConnectionHandler {
     }
     fn on_timeout(&mut self, _event: Token) -> ws::Result<()> {
         self.ws.0.timeout(1_000, RECV_SERVICE)?;
+        // Execute orders received from the orchestrator
+        for ws_action_order in self.controller.get_pending_ws_actions() {
+            self.exec_ws_action(ws_action_order)?;
+        }
         if let Some(ws_action_order) = self.controller.check_timeouts() {
             self.exec_ws_action(ws_action_order)
         } else {
@@ -186,24 +190,28 @@ impl Handler for Ws2pConnectionHandler {
     /*fn on_frame(&mut self, frame: Frame) -> ws::Result<Option<Frame>> {
         Ok(Some(frame))
     }*/
-    fn on_close(&mut self, _code: CloseCode, _reason: &str) {
-        // The WebSocket protocol allows for a utf8 reason for the closing state after the
-        // close code. WS-RS will attempt to interpret this data as a utf8 description of the
-        // reason for closing the connection. I many cases, `reason` will be an empty string.
-        // So, you may not normally want to display `reason` to the user,
-        // but let's assume that we know that `reason` is human-readable.
-        /*match code {
-            CloseCode::Normal => info!("The remote server close the connection."),
-            CloseCode::Away => info!("The remote server is leaving."),
-            _ => warn!("The remote server encountered an error: {}", reason),
-        }
-        let _result = self
-            .conductor_sender
-            .send(WS2PThreadSignal::WS2PConnectionMessage(
-                WS2PConnectionMessage(
-                    self.conn_datas.node_full_id(),
-                    WS2PConnectionMessagePayload::Close,
-                ),
-            ));*/
+    fn on_close(&mut self, code: CloseCode, reason: &str) {
+        let _ = self.controller.process(WebsocketIncomingEvent::OnClose {
+            close_code: code.into(),
+            reason: if reason.is_empty() {
+                None
+            } else {
+                Some(reason.to_owned())
+            },
+        });
+    }
+    fn on_error(&mut self, err: ws::Error) {
+        warn!(
+            "Websocket error with '{}' : {}",
+            if let Some(remote_addr) = self.remote_addr_opt {
+                remote_addr.to_string()
+            } else {
+                "unknown addr".to_string()
+            },
+            err
+        );
+        let _ = self
+            .controller
+            .update_conn_state(WS2PConnectionState::WSError);
     }
 }
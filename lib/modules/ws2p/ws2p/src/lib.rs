@@ -39,19 +39,35 @@ mod constants;
 pub mod controllers;
 mod errors;
 mod generate_peer;
+pub mod operator_msgs;
+pub mod ping;
 pub mod services;
+mod sync;
 
 use crate::errors::WS2PError;
+use crate::services::outgoing::{send_network_event, WS2POutgoingOrchestrator};
+use crate::services::WS2PServiceMsg;
 use dubp_currency_params::CurrencyName;
 use durs_common_tools::fatal_error;
+use durs_common_tools::fns::thread::spawn_child;
 use durs_common_tools::traits::merge::Merge;
 use durs_conf::DuRsConf;
+use durs_message::events::{BlockchainEvent, DursEvent};
+use durs_message::requests::DursReqContent;
 use durs_message::DursMsg;
 use durs_module::*;
 use durs_network::cli::sync::SyncOpt;
+use durs_network::requests::NetworkRequest;
 use durs_network::*;
 use durs_network_documents::network_endpoint::*;
+use durs_network_documents::NodeId;
+use durs_ws2p_messages::v2::api_features::WS2PFeatures;
+use durs_ws2p_messages::v2::payload_container::WS2Pv2MessagePayload;
+use durs_ws2p_pool::endpoints::POOL_ENDPOINTS_FILENAME;
+use durs_ws2p_pool::WS2PPool;
+use durs_ws2p_protocol::MySelfWs2pNode;
 use maplit::hashset;
+use std::ops::Deref;
 use std::sync::mpsc;
 use unwrap::unwrap;
 
@@ -62,6 +78,12 @@ pub struct WS2PConf {
     pub outcoming_quota: usize,
     /// Default WS2P endpoints provides by configuration file
     pub sync_endpoints: Vec<EndpointEnum>,
+    /// Low bandwidth mode (feature LOW): only receive block headers and HEADs unless explicitly asked
+    pub low_bandwidth: bool,
+    /// Compress messages with the nodes that support it (feature ZIP)
+    pub compression: bool,
+    /// Exchange encrypted operator messages with the nodes that accept them (feature OPM)
+    pub operator_msgs: bool,
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -71,6 +93,12 @@ pub struct WS2PUserConf {
     pub outcoming_quota: Option<usize>,
     /// Default WS2P endpoints provides by configuration file
     pub sync_endpoints: Option<Vec<EndpointEnum>>,
+    /// Low bandwidth mode (feature LOW): only receive block headers and HEADs unless explicitly asked
+    pub low_bandwidth: Option<bool>,
+    /// Compress messages with the nodes that support it (feature ZIP)
+    pub compression: Option<bool>,
+    /// Exchange encrypted operator messages with the nodes that accept them (feature OPM)
+    pub operator_msgs: Option<bool>,
 }
 
 impl Merge for WS2PUserConf {
@@ -78,6 +106,9 @@ impl Merge for WS2PUserConf {
         WS2PUserConf {
             outcoming_quota: self.outcoming_quota.or(other.outcoming_quota),
             sync_endpoints: self.sync_endpoints.or(other.sync_endpoints),
+            low_bandwidth: self.low_bandwidth.or(other.low_bandwidth),
+            compression: self.compression.or(other.compression),
+            operator_msgs: self.operator_msgs.or(other.operator_msgs),
         }
     }
 }
@@ -88,16 +119,29 @@ impl Default for WS2PConf {
             outcoming_quota: *constants::WS2P_DEFAULT_OUTCOMING_QUOTA,
             sync_endpoints: vec![
                 unwrap!(EndpointV2::parse_from_raw(
-                    "WS2P 2 g1.dunitrust.org 443 ws2p"
+                    "WS2P V2 g1.dunitrust.org 443 ws2p"
                 )),
                 unwrap!(EndpointV2::parse_from_raw(
-                    "WS2P 2 rs.g1.librelois.fr 443 ws2p"
+                    "WS2P V2 rs.g1.librelois.fr 443 ws2p"
                 )),
             ],
+            low_bandwidth: false,
+            compression: true,
+            operator_msgs: false,
         }
     }
 }
 
+impl WS2PConf {
+    /// Features advertised by the local node
+    pub fn my_features(&self) -> WS2PFeatures {
+        WS2PFeatures([5u8, 0, 0, 0])
+            .with_low(self.low_bandwidth)
+            .with_zip(self.compression)
+            .with_opm(self.operator_msgs)
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 /// WS2Pv2 Module
 pub struct WS2PModule {}
@@ -126,6 +170,8 @@ impl ApiModule<DuRsConf, DursMsg> for WS2PModule {
                 "DEF" => api_features[0] += 1u8,
                 "LOW" => api_features[0] += 2u8,
                 "ABF" => api_features[0] += 4u8,
+                "ZIP" => api_features[0] += 8u8,
+                "OPM" => api_features[0] += 16u8,
                 _ => {
                     debug!(
                         "parse_raw_api_features() = UnknowApiFeature({})",
@@ -143,20 +189,32 @@ impl ApiModule<DuRsConf, DursMsg> for WS2PModule {
 
 impl NetworkModule<DuRsConf, DursMsg> for WS2PModule {
     fn sync(
-        _soft_meta_datas: &SoftwareMetaDatas<DuRsConf>,
-        _keys: RequiredKeysContent,
-        _conf: WS2PConf,
-        _main_sender: mpsc::Sender<RouterThreadMessage<DursMsg>>,
-        _sync_params: SyncOpt,
+        soft_meta_datas: &SoftwareMetaDatas<DuRsConf>,
+        keys: RequiredKeysContent,
+        conf: WS2PConf,
+        main_sender: mpsc::Sender<RouterThreadMessage<DursMsg>>,
+        sync_params: SyncOpt,
     ) -> Result<(), SyncError> {
-        unimplemented!()
+        sync::sync(soft_meta_datas, keys, conf, main_sender, sync_params)
     }
 }
 
-#[derive(StructOpt, Debug, Copy, Clone)]
+#[derive(StructOpt, Debug, Clone)]
 #[structopt(name = "ws2p", setting(structopt::clap::AppSettings::ColoredHelp))]
 /// WS2P subcommand options
-pub struct WS2POpt {}
+pub struct WS2POpt {
+    /// WS2P subcommands
+    #[structopt(subcommand)]
+    pub subcommand: WS2PSubCommands,
+}
+
+#[derive(StructOpt, Debug, Clone)]
+/// WS2P subcommands
+pub enum WS2PSubCommands {
+    /// Probe a remote WS2Pv2 endpoint (handshake, latency, HEAD)
+    #[structopt(name = "ping", setting(structopt::clap::AppSettings::ColoredHelp))]
+    Ping(ping::PingOpt),
+}
 
 impl DursModule<DuRsConf, DursMsg> for WS2PModule {
     type ModuleUserConf = WS2PUserConf;
@@ -189,64 +247,195 @@ impl DursModule<DuRsConf, DursMsg> for WS2PModule {
             if let Some(sync_endpoints) = module_user_conf.sync_endpoints {
                 conf.sync_endpoints = sync_endpoints;
             }
+            if let Some(low_bandwidth) = module_user_conf.low_bandwidth {
+                conf.low_bandwidth = low_bandwidth;
+            }
+            if let Some(compression) = module_user_conf.compression {
+                conf.compression = compression;
+            }
+            if let Some(operator_msgs) = module_user_conf.operator_msgs {
+                conf.operator_msgs = operator_msgs;
+            }
         }
 
         Ok((conf, module_user_conf))
     }
     fn exec_subcommand(
-        _soft_meta_datas: &SoftwareMetaDatas<DuRsConf>,
-        _keys: RequiredKeysContent,
-        _module_conf: Self::ModuleConf,
-        _module_user_conf: Option<Self::ModuleUserConf>,
-        _subcommand_args: WS2POpt,
+        soft_meta_datas: &SoftwareMetaDatas<DuRsConf>,
+        keys: RequiredKeysContent,
+        module_conf: Self::ModuleConf,
+        module_user_conf: Option<Self::ModuleUserConf>,
+        opts: WS2POpt,
     ) -> Option<Self::ModuleUserConf> {
-        println!("Succesfully exec ws2p subcommand !");
-        None
+        match opts.subcommand {
+            WS2PSubCommands::Ping(ping_opt) => {
+                let key_pair = if let RequiredKeysContent::NetworkKeyPair(key_pair) = keys {
+                    key_pair
+                } else {
+                    fatal_error!("WS2P: network keypair unavailable !");
+                };
+                let endpoint = match EndpointV2::parse_from_raw(&ping_opt.endpoint) {
+                    Ok(endpoint) => endpoint,
+                    Err(e) => {
+                        println!("Invalid endpoint '{}': {}", ping_opt.endpoint, e);
+                        return module_user_conf;
+                    }
+                };
+                match ping::ping(
+                    soft_meta_datas.conf.get_currency(),
+                    &module_conf,
+                    MySelfWs2pNode {
+                        my_node_id: NodeId(soft_meta_datas.conf.my_node_id()),
+                        my_key_pair: key_pair,
+                        my_features: module_conf.my_features(),
+                    },
+                    endpoint,
+                ) {
+                    Ok(report) => print!("{}", report),
+                    Err(e) => println!("Fail to ping {}: {}", ping_opt.endpoint, e),
+                }
+            }
+        }
+        module_user_conf
     }
     fn start(
-        _soft_meta_datas: &SoftwareMetaDatas<DuRsConf>,
+        soft_meta_datas: &SoftwareMetaDatas<DuRsConf>,
         keys: RequiredKeysContent,
-        _conf: WS2PConf,
+        conf: WS2PConf,
         router_sender: mpsc::Sender<RouterThreadMessage<DursMsg>>,
     ) -> Result<(), failure::Error> {
         // Get key_pair
-        let _key_pair = if let RequiredKeysContent::NetworkKeyPair(key_pair) = keys {
+        let key_pair = if let RequiredKeysContent::NetworkKeyPair(key_pair) = keys {
             key_pair
         } else {
             return Err(WS2PError::UnexpectedKeys.into());
         };
 
-        // Create module channel
-        let (module_sender, module_receiver) = mpsc::channel();
+        // Instantiate outgoing connections orchestrator
+        let mut orchestrator = WS2POutgoingOrchestrator::new(
+            soft_meta_datas.conf.get_currency(),
+            &conf,
+            MySelfWs2pNode {
+                my_node_id: NodeId(soft_meta_datas.conf.my_node_id()),
+                my_key_pair: key_pair,
+                my_features: conf.my_features(),
+            },
+            WS2PPool::shared(),
+        );
+
+        // Endpoints of the nodes already connected during a previous run
+        let known_endpoints_file_path =
+            durs_conf::get_datas_path(soft_meta_datas.profile_path.clone())
+                .join(POOL_ENDPOINTS_FILENAME);
+        orchestrator.load_known_endpoints(&known_endpoints_file_path);
 
         // Registration with the rooter
-        if router_sender
-            .send(RouterThreadMessage::ModuleRegistration {
-                static_name: ModuleStaticName(constants::MODULE_NAME),
-                sender: module_sender,
-                roles: vec![ModuleRole::InterNodesNetwork],
-                events_subscription: vec![
-                    ModuleEvent::NewValidBlock,
-                    ModuleEvent::NewWotDocInPool,
-                    ModuleEvent::NewTxinPool,
-                ],
-                reserved_apis_parts: vec![ApiPart {
-                    name: ApiName(constants::API_NAME.to_owned()),
-                    versions: hashset![ApiVersion(2)],
-                }],
-                endpoints: vec![],
-            })
-            .is_err()
-        {
-            fatal_error!("WS2P module fail to send registration to router !")
+        register_in_router(&router_sender, orchestrator.sender.clone());
+
+        // Connect to sync endpoints
+        orchestrator.connect_to_endpoints();
+
+        loop {
+            match orchestrator.receiver.recv() {
+                Ok(WS2PServiceMsg::DursMsg(durs_msg)) => match *durs_msg {
+                    DursMsg::Stop => {
+                        orchestrator.close_all_connections();
+                        break;
+                    }
+                    DursMsg::Event {
+                        event_type: ModuleEvent::NewValidBlockFromSelf,
+                        event_content: DursEvent::BlockchainEvent(ref bc_event),
+                        ..
+                    } => {
+                        // Block issued by the local node: push it to all peers
+                        if let BlockchainEvent::StackUpValidBlock(ref block) = **bc_event {
+                            orchestrator.send_payload_to_all(&WS2Pv2MessagePayload::Blocks(vec![
+                                block.deref().clone(),
+                            ]));
+                        }
+                    }
+                    DursMsg::Request {
+                        req_content:
+                            DursReqContent::NetworkRequest(NetworkRequest::SendOperatorMessage {
+                                to,
+                                ref text,
+                            }),
+                        ..
+                    } => {
+                        let recipients_count = orchestrator.send_operator_message(to, text);
+                        info!(
+                            "WS2P: operator message sent to {} node(s).",
+                            recipients_count
+                        );
+                    }
+                    _ => {}
+                },
+                Ok(WS2PServiceMsg::OutgoingController { conn_id, msg }) => {
+                    if let Some(event) = orchestrator.process_controller_msg(conn_id, *msg) {
+                        send_network_event(&router_sender, event);
+                    }
+                    if let Err(e) = orchestrator
+                        .known_endpoints
+                        .save(&known_endpoints_file_path)
+                    {
+                        warn!("WS2P: fail to save known endpoints: {:?}", e);
+                    }
+                }
+                Ok(WS2PServiceMsg::OutgoingClosed { conn_id }) => {
+                    if let Some(connection) = orchestrator.remove_connection(conn_id) {
+                        debug!("WS2P: connection with {} ended.", connection.endpoint.raw());
+                    }
+                }
+                Err(_) => fatal_error!("Disconnected ws2p module !"),
+            }
         }
 
+        Ok(())
+    }
+}
+
+/// Register the module in the router and relay the messages of the router to the services
+fn register_in_router(
+    router_sender: &mpsc::Sender<RouterThreadMessage<DursMsg>>,
+    service_sender: mpsc::Sender<WS2PServiceMsg>,
+) {
+    // Create module channel
+    let (module_sender, module_receiver) = mpsc::channel();
+
+    if router_sender
+        .send(RouterThreadMessage::ModuleRegistration {
+            static_name: ModuleStaticName(constants::MODULE_NAME),
+            sender: module_sender,
+            roles: vec![ModuleRole::InterNodesNetwork],
+            events_subscription: vec![
+                ModuleEvent::NewValidBlock,
+                ModuleEvent::NewValidBlockFromSelf,
+                ModuleEvent::NewWotDocInPool,
+                ModuleEvent::NewTxinPool,
+            ],
+            reserved_apis_parts: vec![ApiPart {
+                name: ApiName(constants::API_NAME.to_owned()),
+                versions: hashset![ApiVersion(2)],
+            }],
+            endpoints: vec![],
+        })
+        .is_err()
+    {
+        fatal_error!("WS2P module fail to send registration to router !")
+    }
+
+    spawn_child("proxy", move || {
         while let Ok(msg) = module_receiver.recv() {
             if let DursMsg::Stop = msg {
+                let _ = service_sender.send(WS2PServiceMsg::DursMsg(Box::new(msg)));
+                break;
+            }
+            if service_sender
+                .send(WS2PServiceMsg::DursMsg(Box::new(msg)))
+                .is_err()
+            {
                 break;
             }
         }
-
-        Ok(())
-    }
+    });
 }
@@ -0,0 +1,288 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Operator messages: short messages between the operators of directly connected nodes.
+//!
+//! When the OPM feature is negotiated, each node sends an ephemeral X25519 public key in an
+//! OPERATOR_KEY message (signed with its node key like any WS2P message). The shared secret
+//! gives one ChaCha20-Poly1305 key per direction, used to encrypt the OPERATOR_MSG messages.
+
+use crate::constants;
+use durs_ws2p_messages::v2::operator_msg::{WS2Pv2OperatorMsg, WS2P_OPERATOR_MSG_MAX_LEN};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN};
+use ring::agreement::{agree_ephemeral, EphemeralPrivateKey, UnparsedPublicKey, X25519};
+use ring::hkdf::{Prk, Salt, HKDF_SHA256};
+use ring::rand::SystemRandom;
+use std::time::{Duration, Instant};
+
+/// Context of the keys derivation
+static OPERATOR_MSGS_KDF_CONTEXT: &[u8] = b"WS2P_OPERATOR_MSGS";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// Operator message error
+pub enum OperatorMsgError {
+    /// Fail to generate the session key
+    KeyGenerationError,
+    /// The keys of the session are not yet known
+    NoSessionKeys,
+    /// Invalid or already received remote key
+    InvalidRemoteKey,
+    /// Text too long
+    TooLongText,
+    /// Too many messages in the rate window
+    RateLimitExceeded,
+    /// Replayed or reordered message
+    InvalidCounter,
+    /// Fail to decrypt the message
+    DecryptionError,
+    /// Decrypted text is not valid utf8
+    InvalidText,
+}
+
+#[derive(Debug, Copy, Clone)]
+/// Limit the number of operator messages per rate window
+struct RateLimiter {
+    window_start: Instant,
+    count: usize,
+}
+
+impl RateLimiter {
+    fn new() -> Self {
+        RateLimiter {
+            window_start: Instant::now(),
+            count: 0,
+        }
+    }
+    fn try_acquire(&mut self) -> bool {
+        if self.window_start.elapsed()
+            >= Duration::from_secs(*constants::WS2P_OPERATOR_MSGS_RATE_WINDOW_IN_SECS)
+        {
+            self.window_start = Instant::now();
+            self.count = 0;
+        }
+        if self.count < *constants::WS2P_OPERATOR_MSGS_RATE_LIMIT {
+            self.count += 1;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Operator messages session of a connection
+pub struct OperatorMsgsSession {
+    private_key: Option<EphemeralPrivateKey>,
+    public_key: [u8; 32],
+    keys: Option<(LessSafeKey, LessSafeKey)>,
+    sent_count: u64,
+    last_recv_counter: u64,
+    sending_limiter: RateLimiter,
+    receiving_limiter: RateLimiter,
+}
+
+impl std::fmt::Debug for OperatorMsgsSession {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "OperatorMsgsSession {{ public_key: {:?}, established: {} }}",
+            self.public_key,
+            self.keys.is_some()
+        )
+    }
+}
+
+impl OperatorMsgsSession {
+    /// Create a session with a new ephemeral key
+    pub fn new() -> Result<Self, OperatorMsgError> {
+        let private_key = EphemeralPrivateKey::generate(&X25519, &SystemRandom::new())
+            .map_err(|_| OperatorMsgError::KeyGenerationError)?;
+        let mut public_key = [0u8; 32];
+        public_key.copy_from_slice(
+            private_key
+                .compute_public_key()
+                .map_err(|_| OperatorMsgError::KeyGenerationError)?
+                .as_ref(),
+        );
+        Ok(OperatorMsgsSession {
+            private_key: Some(private_key),
+            public_key,
+            keys: None,
+            sent_count: 0,
+            last_recv_counter: 0,
+            sending_limiter: RateLimiter::new(),
+            receiving_limiter: RateLimiter::new(),
+        })
+    }
+    /// Local ephemeral public key, to send to the remote node
+    pub fn public_key(&self) -> [u8; 32] {
+        self.public_key
+    }
+    /// Return true if the session keys are known
+    pub fn is_established(&self) -> bool {
+        self.keys.is_some()
+    }
+    /// Compute the session keys from the ephemeral public key of the remote node
+    pub fn set_remote_key(&mut self, remote_key: &[u8; 32]) -> Result<(), OperatorMsgError> {
+        let private_key = self
+            .private_key
+            .take()
+            .ok_or(OperatorMsgError::InvalidRemoteKey)?;
+        let public_key = self.public_key;
+        let keys = agree_ephemeral(
+            private_key,
+            &UnparsedPublicKey::new(&X25519, remote_key),
+            OperatorMsgError::InvalidRemoteKey,
+            |shared_secret| {
+                let prk = Salt::new(HKDF_SHA256, OPERATOR_MSGS_KDF_CONTEXT).extract(shared_secret);
+                Ok((
+                    derive_key(&prk, &public_key, remote_key)?,
+                    derive_key(&prk, remote_key, &public_key)?,
+                ))
+            },
+        )?;
+        self.keys = Some(keys);
+        Ok(())
+    }
+    /// Encrypt an operator message
+    pub fn encrypt(&mut self, text: &str) -> Result<WS2Pv2OperatorMsg, OperatorMsgError> {
+        if text.len() > *WS2P_OPERATOR_MSG_MAX_LEN {
+            return Err(OperatorMsgError::TooLongText);
+        }
+        let sending_key = if let Some((ref sending_key, _)) = self.keys {
+            sending_key
+        } else {
+            return Err(OperatorMsgError::NoSessionKeys);
+        };
+        if !self.sending_limiter.try_acquire() {
+            return Err(OperatorMsgError::RateLimitExceeded);
+        }
+        self.sent_count += 1;
+        let mut encrypted_text = text.as_bytes().to_vec();
+        sending_key
+            .seal_in_place_append_tag(nonce(self.sent_count), Aad::empty(), &mut encrypted_text)
+            .map_err(|_| OperatorMsgError::KeyGenerationError)?;
+        Ok(WS2Pv2OperatorMsg {
+            counter: self.sent_count,
+            encrypted_text,
+        })
+    }
+    /// Decrypt an operator message
+    pub fn decrypt(&mut self, msg: &WS2Pv2OperatorMsg) -> Result<String, OperatorMsgError> {
+        let receiving_key = if let Some((_, ref receiving_key)) = self.keys {
+            receiving_key
+        } else {
+            return Err(OperatorMsgError::NoSessionKeys);
+        };
+        if msg.counter <= self.last_recv_counter {
+            return Err(OperatorMsgError::InvalidCounter);
+        }
+        if !self.receiving_limiter.try_acquire() {
+            return Err(OperatorMsgError::RateLimitExceeded);
+        }
+        let mut encrypted_text = msg.encrypted_text.clone();
+        let text = receiving_key
+            .open_in_place(nonce(msg.counter), Aad::empty(), &mut encrypted_text)
+            .map_err(|_| OperatorMsgError::DecryptionError)?;
+        if text.len() > *WS2P_OPERATOR_MSG_MAX_LEN {
+            return Err(OperatorMsgError::TooLongText);
+        }
+        let text = String::from_utf8(text.to_vec()).map_err(|_| OperatorMsgError::InvalidText)?;
+        self.last_recv_counter = msg.counter;
+        Ok(text)
+    }
+}
+
+/// Derive the key of the messages sent by the owner of `from_key` to the owner of `to_key`
+fn derive_key(prk: &Prk, from_key: &[u8], to_key: &[u8]) -> Result<LessSafeKey, OperatorMsgError> {
+    let info = [from_key, to_key];
+    let okm = prk
+        .expand(&info, &CHACHA20_POLY1305)
+        .map_err(|_| OperatorMsgError::KeyGenerationError)?;
+    Ok(LessSafeKey::new(UnboundKey::from(okm)))
+}
+
+/// Nonce of the message number `counter`
+fn nonce(counter: u64) -> Nonce {
+    let mut nonce = [0u8; NONCE_LEN];
+    nonce[NONCE_LEN - 8..].copy_from_slice(&counter.to_be_bytes());
+    Nonce::assume_unique_for_key(nonce)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use unwrap::unwrap;
+
+    fn established_sessions() -> (OperatorMsgsSession, OperatorMsgsSession) {
+        let mut session_a = unwrap!(OperatorMsgsSession::new());
+        let mut session_b = unwrap!(OperatorMsgsSession::new());
+        let (key_a, key_b) = (session_a.public_key(), session_b.public_key());
+        unwrap!(session_a.set_remote_key(&key_b));
+        unwrap!(session_b.set_remote_key(&key_a));
+        (session_a, session_b)
+    }
+
+    #[test]
+    fn exchange_operator_msgs() {
+        let (mut session_a, mut session_b) = established_sessions();
+
+        let msg = unwrap!(session_a.encrypt("maintenance at 20:00"));
+        assert_ne!(b"maintenance at 20:00".to_vec(), msg.encrypted_text);
+        // A node can't decrypt its own messages
+        assert_eq!(
+            Err(OperatorMsgError::DecryptionError),
+            session_a.decrypt(&msg)
+        );
+        assert_eq!(
+            Ok("maintenance at 20:00".to_owned()),
+            session_b.decrypt(&msg)
+        );
+        // Replayed message
+        assert_eq!(
+            Err(OperatorMsgError::InvalidCounter),
+            session_b.decrypt(&msg)
+        );
+        let msg = unwrap!(session_b.encrypt("ok"));
+        assert_eq!(Ok("ok".to_owned()), session_a.decrypt(&msg));
+        // The remote key can be set only once
+        let key = session_b.public_key();
+        assert_eq!(
+            Err(OperatorMsgError::InvalidRemoteKey),
+            session_a.set_remote_key(&key)
+        );
+    }
+
+    #[test]
+    fn operator_msgs_limits() {
+        let mut session = unwrap!(OperatorMsgsSession::new());
+        assert_eq!(
+            Err(OperatorMsgError::NoSessionKeys),
+            session.encrypt("hello")
+        );
+
+        let (mut session_a, _) = established_sessions();
+        assert_eq!(
+            Err(OperatorMsgError::TooLongText),
+            session_a.encrypt(&"a".repeat(*WS2P_OPERATOR_MSG_MAX_LEN + 1))
+        );
+        for _ in 0..*constants::WS2P_OPERATOR_MSGS_RATE_LIMIT {
+            assert!(session_a.encrypt("hello").is_ok());
+        }
+        assert_eq!(
+            Err(OperatorMsgError::RateLimitExceeded),
+            session_a.encrypt("hello")
+        );
+    }
+}
@@ -0,0 +1,307 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Sub-command probing a remote WS2Pv2 endpoint.
+
+use crate::constants;
+use crate::services::outgoing::WS2POutgoingOrchestrator;
+use crate::services::WS2PServiceMsg;
+use crate::*;
+use dubp_common_doc::Blockstamp;
+use durs_network_documents::NodeFullId;
+use durs_ws2p_messages::v2::req_responses::WS2Pv2ReqResBody;
+use durs_ws2p_messages::v2::requests::{WS2Pv2Request, WS2Pv2RequestBody};
+use durs_ws2p_messages::WS2PMessage;
+use durs_ws2p_protocol::connection_state::WS2PConnectionState;
+use durs_ws2p_protocol::controller::WS2PControllerEvent;
+use durs_ws2p_protocol::orchestrator::OrchestratorMsg;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Identifier of the CURRENT request sent to the probed node
+static PING_REQUEST_ID: &u32 = &1;
+
+#[derive(StructOpt, Debug, Clone)]
+/// Ping subcommand options
+pub struct PingOpt {
+    /// Raw WS2Pv2 endpoint (for example "WS2P V2 g1.dunitrust.org 443 ws2p")
+    pub endpoint: String,
+}
+
+/// Probe error
+#[derive(Debug, Clone, PartialEq)]
+pub enum PingError {
+    /// Invalid or unreachable endpoint
+    InvalidEndpoint,
+    /// The connection was closed before the end of the probe
+    ConnectionClosed(WS2PConnectionState),
+    /// The probe did not end in time (datas received until then)
+    Timeout(Box<PingReport>),
+}
+
+impl fmt::Display for PingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PingError::InvalidEndpoint => write!(f, "Invalid endpoint"),
+            PingError::ConnectionClosed(state) => {
+                write!(f, "Connection closed (last state: {:?})", state)
+            }
+            PingError::Timeout(ref report) => write!(f, "Timeout\n{}", report),
+        }
+    }
+}
+
+/// Datas collected on the probed node
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PingReport {
+    /// Node full id
+    pub remote_full_id: Option<NodeFullId>,
+    /// Negotiated features
+    pub features: Option<WS2PFeatures>,
+    /// Duration of the handshake
+    pub handshake_duration: Option<Duration>,
+    /// Round trip time of the CURRENT request
+    pub latency: Option<Duration>,
+    /// Blockstamp of the HEAD of the node
+    pub current: Option<Blockstamp>,
+    /// Software name and version of the node (if it sent its HEAD)
+    pub software: Option<String>,
+}
+
+/// Names of WS2Pv2 features
+fn features_names(features: WS2PFeatures) -> String {
+    let names: Vec<&str> = vec![
+        (features.def(), "DEF"),
+        (features.low(), "LOW"),
+        (features.abf(), "ABF"),
+        (features.zip(), "ZIP"),
+        (features.opm(), "OPM"),
+    ]
+    .into_iter()
+    .filter_map(|(enabled, name)| if enabled { Some(name) } else { None })
+    .collect();
+    names.join(" ")
+}
+
+impl fmt::Display for PingReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fn or_unknown<T: ToString>(value: Option<T>) -> String {
+            value.map_or_else(|| "unknown".to_owned(), |value| value.to_string())
+        }
+        writeln!(f, "Node: {}", or_unknown(self.remote_full_id))?;
+        writeln!(
+            f,
+            "Features: {}",
+            or_unknown(self.features.map(features_names))
+        )?;
+        writeln!(
+            f,
+            "Handshake: {}",
+            or_unknown(
+                self.handshake_duration
+                    .map(|d| format!("{} ms", d.as_millis()))
+            )
+        )?;
+        writeln!(
+            f,
+            "Latency: {}",
+            or_unknown(self.latency.map(|d| format!("{} ms", d.as_millis())))
+        )?;
+        writeln!(f, "HEAD: {}", or_unknown(self.current))?;
+        writeln!(f, "Software: {}", or_unknown(self.software.as_ref()))
+    }
+}
+
+/// State of a probe
+#[derive(Debug, Clone)]
+struct PingProbe {
+    start: Instant,
+    request_sent_at: Option<Instant>,
+    report: PingReport,
+}
+
+impl PingProbe {
+    fn new(start: Instant) -> Self {
+        PingProbe {
+            start,
+            request_sent_at: None,
+            report: PingReport::default(),
+        }
+    }
+    /// Connection established, returns the request to send
+    fn established(
+        &mut self,
+        remote_full_id: NodeFullId,
+        features: WS2PFeatures,
+        now: Instant,
+    ) -> WS2Pv2Request {
+        self.report.remote_full_id = Some(remote_full_id);
+        self.report.features = Some(features);
+        self.report.handshake_duration = Some(now.duration_since(self.start));
+        self.request_sent_at = Some(now);
+        WS2Pv2Request {
+            id: *PING_REQUEST_ID,
+            body: WS2Pv2RequestBody::Current,
+        }
+    }
+    /// Payload received from the probed node, returns `true` at the end of the probe
+    fn receive_payload(&mut self, payload: WS2Pv2MessagePayload, now: Instant) -> bool {
+        match payload {
+            WS2Pv2MessagePayload::ReqRes(response) if response.id == *PING_REQUEST_ID => {
+                if let WS2Pv2ReqResBody::Current(current) = response.body {
+                    self.report.current = Some(current);
+                    self.report.latency = self
+                        .request_sent_at
+                        .map(|request_sent_at| now.duration_since(request_sent_at));
+                    return true;
+                }
+            }
+            WS2Pv2MessagePayload::Heads3(heads) => {
+                if let Some(NodeFullId(_, remote_pubkey)) = self.report.remote_full_id {
+                    if let Some(head) = heads.iter().find(|head| head.pubkey == remote_pubkey) {
+                        self.report.software =
+                            Some(format!("{} {}", head.software, head.soft_version));
+                    }
+                }
+            }
+            _ => {}
+        }
+        false
+    }
+}
+
+/// Perform a one-off handshake with a WS2Pv2 endpoint, then request its current blockstamp
+pub fn ping(
+    currency: CurrencyName,
+    conf: &WS2PConf,
+    self_node: MySelfWs2pNode,
+    endpoint: EndpointEnum,
+) -> Result<PingReport, PingError> {
+    // The probe must not be affected by the connections of a running node
+    let mut orchestrator =
+        WS2POutgoingOrchestrator::new(currency, conf, self_node, WS2PPool::default());
+    orchestrator
+        .connect_to_ws2p_v2_endpoint(endpoint)
+        .map_err(|_| PingError::InvalidEndpoint)?;
+
+    let timeout = Duration::from_secs(*constants::WS2P_PING_TIMEOUT_IN_SECS);
+    let mut probe = PingProbe::new(Instant::now());
+    let result = loop {
+        let elapsed = probe.start.elapsed();
+        if elapsed >= timeout {
+            break Err(PingError::Timeout(Box::new(probe.report)));
+        }
+        match orchestrator.receiver.recv_timeout(timeout - elapsed) {
+            Ok(WS2PServiceMsg::OutgoingController { conn_id, msg }) => match *msg {
+                OrchestratorMsg::ControllerEvent {
+                    event:
+                        WS2PControllerEvent::RecvValidMsg {
+                            ws2p_msg: WS2PMessage::V2(msg_v2),
+                        },
+                    ..
+                } => {
+                    if probe.receive_payload(msg_v2.payload, Instant::now()) {
+                        break Ok(probe.report);
+                    }
+                }
+                msg => {
+                    if let OrchestratorMsg::ControllerEvent {
+                        event:
+                            WS2PControllerEvent::NewConnEstablished {
+                                remote_full_id,
+                                features,
+                                ..
+                            },
+                        ..
+                    } = msg
+                    {
+                        let request = probe.established(remote_full_id, features, Instant::now());
+                        sync::send_request(&orchestrator, &orchestrator.signator, conn_id, request);
+                    }
+                    orchestrator.process_controller_msg(conn_id, msg);
+                }
+            },
+            Ok(WS2PServiceMsg::OutgoingClosed { conn_id }) => {
+                let last_state = orchestrator
+                    .remove_connection(conn_id)
+                    .map_or(WS2PConnectionState::Close, |connection| connection.state);
+                break Err(PingError::ConnectionClosed(last_state));
+            }
+            Ok(WS2PServiceMsg::DursMsg(_)) | Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                break Err(PingError::ConnectionClosed(WS2PConnectionState::Close))
+            }
+        }
+    };
+    orchestrator.close_all_connections();
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dup_crypto::hashs::Hash;
+    use dup_crypto::keys::PubKey;
+    use durs_network_documents::NodeId;
+    use durs_ws2p_messages::v2::req_responses::WS2Pv2ReqRes;
+
+    #[test]
+    fn probe_remote_node() {
+        let start = Instant::now();
+        let mut probe = PingProbe::new(start);
+        let remote_full_id = NodeFullId(NodeId(2), PubKey::default());
+
+        let request = probe.established(
+            remote_full_id,
+            WS2PFeatures([9u8, 0, 0, 0]),
+            start + Duration::from_millis(40),
+        );
+        assert_eq!(WS2Pv2RequestBody::Current, request.body);
+
+        // Response to another request
+        let current = Blockstamp {
+            id: dubp_common_doc::BlockNumber(42),
+            hash: dubp_common_doc::BlockHash(Hash::default()),
+        };
+        assert!(!probe.receive_payload(
+            WS2Pv2MessagePayload::ReqRes(WS2Pv2ReqRes {
+                id: request.id + 1,
+                body: WS2Pv2ReqResBody::Current(current),
+            }),
+            start + Duration::from_millis(50),
+        ));
+        assert!(probe.receive_payload(
+            WS2Pv2MessagePayload::ReqRes(WS2Pv2ReqRes {
+                id: request.id,
+                body: WS2Pv2ReqResBody::Current(current),
+            }),
+            start + Duration::from_millis(65),
+        ));
+
+        assert_eq!(
+            PingReport {
+                remote_full_id: Some(remote_full_id),
+                features: Some(WS2PFeatures([9u8, 0, 0, 0])),
+                handshake_duration: Some(Duration::from_millis(40)),
+                latency: Some(Duration::from_millis(25)),
+                current: Some(current),
+                software: None,
+            },
+            probe.report
+        );
+        assert_eq!("DEF ZIP", features_names(WS2PFeatures([9u8, 0, 0, 0])));
+    }
+}
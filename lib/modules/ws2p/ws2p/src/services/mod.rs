@@ -16,11 +16,32 @@
 //! WS2P Services
 
 use dup_crypto::keys::KeyPairEnum;
+use durs_message::DursMsg;
 use durs_network_documents::*;
 use durs_ws2p_messages::v2::api_features::WS2PFeatures;
+use durs_ws2p_protocol::orchestrator::OrchestratorMsg;
 
 pub mod outgoing;
 
+/// Message received by the WS2P services
+#[derive(Debug)]
+pub enum WS2PServiceMsg {
+    /// Message from another module
+    DursMsg(Box<DursMsg>),
+    /// Message from the controller of an outgoing connection
+    OutgoingController {
+        /// Outgoing connection identifier
+        conn_id: usize,
+        /// Controller message
+        msg: Box<OrchestratorMsg<DursMsg>>,
+    },
+    /// The thread of an outgoing connection is ended
+    OutgoingClosed {
+        /// Outgoing connection identifier
+        conn_id: usize,
+    },
+}
+
 /// Websocket Error
 #[derive(Debug, Copy, Clone)]
 pub enum WsError {
@@ -15,24 +15,51 @@
 
 //! WS2P outgoing Services
 
-use crate::services::WsError;
+use crate::operator_msgs::OperatorMsgsSession;
+use crate::services::{WS2PServiceMsg, WsError};
 use crate::*;
 use dubp_currency_params::CurrencyName;
-use durs_network_documents::{NodeFullId, NodeId};
+use dup_crypto::keys::{KeyPair, SignatorEnum};
+use durs_common_tools::fns::thread::spawn_child;
+use durs_message::events::DursEvent;
+use durs_network::events::NetworkEvent;
+use durs_network_documents::NodeFullId;
+use durs_ws2p_messages::v2::api_features::WS2PFeatures;
+use durs_ws2p_messages::v2::payload_container::WS2Pv2MessagePayload;
+use durs_ws2p_messages::v2::WS2Pv2Message;
+use durs_ws2p_messages::WS2PMessage;
+use durs_ws2p_pool::endpoints::PoolEndpoints;
+use durs_ws2p_pool::{ConnectionDirection, PoolError, WS2PApi, WS2PPool};
 use durs_ws2p_protocol::connection_state::WS2PConnectionState;
-use durs_ws2p_protocol::controller::WebsocketActionOrder;
+use durs_ws2p_protocol::controller::{WS2PControllerEvent, WebsocketActionOrder};
 use durs_ws2p_protocol::orchestrator::OrchestratorMsg;
+use durs_ws2p_protocol::websocket::{WebsocketAction, WebsocketMessage};
 use durs_ws2p_protocol::MySelfWs2pNode;
 use std::collections::HashMap;
+use std::path::Path;
 use std::sync::mpsc;
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 /// Data allowing the service to manage an outgoing connection
 pub struct OutgoingConnection {
     /// Endpoint
     pub endpoint: EndpointEnum,
-    /// Controller channel
-    pub controller: mpsc::Sender<WebsocketActionOrder>,
+    /// Controller channel (known once the websocket is opened)
+    pub controller: Option<mpsc::Sender<WebsocketActionOrder>>,
+    /// Connection state
+    pub state: WS2PConnectionState,
+    /// Remote node full id (known once the connection is established)
+    pub remote_full_id: Option<NodeFullId>,
+    /// Features negotiated with the remote node (known once the connection is established)
+    pub features: Option<WS2PFeatures>,
+    /// Operator messages session (if feature OPM is negotiated)
+    pub operator_msgs: Option<OperatorMsgsSession>,
+}
+
+impl OutgoingConnection {
+    fn url(&self) -> String {
+        self.endpoint.get_url(false, false).unwrap_or_default()
+    }
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -51,18 +78,46 @@ pub struct WS2POutgoingOrchestrator {
     pub currency: CurrencyName,
     /// Local node datas
     pub self_node: MySelfWs2pNode,
+    /// Local node signator
+    pub signator: SignatorEnum,
     /// Outgoing connections quota
     pub quota: usize,
-    /// List of established connections
-    pub connections: HashMap<NodeFullId, OutgoingConnection>,
+    /// Outgoing connections in progress or established
+    pub connections: HashMap<usize, OutgoingConnection>,
     /// List of endpoinds whose last connection attempt failed
     pub endpoints_in_error: HashMap<NodeFullId, EndpointInError>,
     /// List of endpoints that have never been contacted
     pub never_try_endpoints: Vec<EndpointEnum>,
+    /// Endpoints of the nodes with which a connection has already been established
+    pub known_endpoints: PoolEndpoints,
+    /// Connections pool shared with the other WS2P modules
+    pub pool: WS2PPool,
+    /// Identifier of the next outgoing connection
+    pub next_conn_id: usize,
     /// Service receiver
-    pub receiver: mpsc::Receiver<OrchestratorMsg<DursMsg>>,
-    /// Orchestrator sender
-    pub sender: mpsc::Sender<OrchestratorMsg<DursMsg>>,
+    pub receiver: mpsc::Receiver<WS2PServiceMsg>,
+    /// Service sender
+    pub sender: mpsc::Sender<WS2PServiceMsg>,
+}
+
+/// Code of a connection state in `NetworkEvent::ConnectionStateChange` (same codes as WS2Pv1)
+pub fn network_state_code(state: WS2PConnectionState) -> u32 {
+    match state {
+        WS2PConnectionState::NeverTry => 0,
+        WS2PConnectionState::TryToOpenWS => 1,
+        WS2PConnectionState::WSError => 2,
+        WS2PConnectionState::TryToSendConnectMsg => 3,
+        WS2PConnectionState::Unreachable => 4,
+        WS2PConnectionState::WaitingConnectMsg => 5,
+        WS2PConnectionState::NoResponse | WS2PConnectionState::NegociationTimeout => 6,
+        WS2PConnectionState::ConnectMessOk => 7,
+        WS2PConnectionState::OkMsgOkWaitingAckMsg
+        | WS2PConnectionState::SecretFlagsOkWaitingAckMsg => 8,
+        WS2PConnectionState::AckMsgOk | WS2PConnectionState::SecretFlagsOk => 9,
+        WS2PConnectionState::Denial => 10,
+        WS2PConnectionState::Close => 11,
+        WS2PConnectionState::Established => 12,
+    }
 }
 
 impl WS2POutgoingOrchestrator {
@@ -71,39 +126,644 @@ impl WS2POutgoingOrchestrator {
         currency: CurrencyName,
         ws2p_conf: &WS2PConf,
         self_node: MySelfWs2pNode,
+        pool: WS2PPool,
     ) -> WS2POutgoingOrchestrator {
         // Create service channel
         let (sender, receiver) = mpsc::channel();
 
+        let signator = if let Ok(signator) = self_node.my_key_pair.generate_signator() {
+            signator
+        } else {
+            fatal_error!("WS2P: fail to generate signator !");
+        };
+
         WS2POutgoingOrchestrator {
             currency,
             quota: ws2p_conf.outcoming_quota,
             connections: HashMap::with_capacity(ws2p_conf.outcoming_quota),
             endpoints_in_error: HashMap::new(),
-            never_try_endpoints: Vec::new(),
+            never_try_endpoints: ws2p_conf.sync_endpoints.clone(),
+            known_endpoints: PoolEndpoints::default(),
+            pool,
+            next_conn_id: 0,
             self_node,
+            signator,
             receiver,
             sender,
         }
     }
 
-    /// Connect to WSPv2 Endpoint
-    pub fn connect_to_ws2p_v2_endpoint(
-        &self,
-        endpoint: &EndpointEnum,
-        remote_node_id_opt: Option<NodeId>,
-    ) -> Result<(), WsError> {
-        let expected_remote_full_id =
-            remote_node_id_opt.map(|remote_node_id| NodeFullId(remote_node_id, endpoint.pubkey()));
-        match controllers::outgoing_connections::connect_to_ws2p_v2_endpoint(
-            &self.currency,
-            &self.sender,
-            &self.self_node,
-            expected_remote_full_id,
-            endpoint,
-        ) {
-            Ok(_) => Ok(()),
-            Err(_) => Err(WsError::UnknownError),
+    /// Load the endpoints saved in file, those that are not already known will be tried
+    pub fn load_known_endpoints(&mut self, file_path: &Path) {
+        match PoolEndpoints::load(file_path) {
+            Ok(known_endpoints) => {
+                for endpoint in known_endpoints.endpoints() {
+                    if !self.never_try_endpoints.contains(endpoint) {
+                        self.never_try_endpoints.push(endpoint.clone());
+                    }
+                }
+                self.known_endpoints = known_endpoints;
+            }
+            Err(e) => warn!("WS2P: fail to load known endpoints: {:?}", e),
+        }
+    }
+
+    /// Connect to never tried endpoints, within the limit of the quota
+    /// (shared with the other WS2P modules)
+    pub fn connect_to_endpoints(&mut self) {
+        let pending_connections = self.connections.len() - self.count_established_connections();
+        let count = std::cmp::min(
+            self.pool
+                .free_rooms(ConnectionDirection::Outgoing, self.quota)
+                .saturating_sub(pending_connections),
+            self.never_try_endpoints.len(),
+        );
+        let endpoints: Vec<EndpointEnum> = self.never_try_endpoints.drain(..count).collect();
+        for endpoint in endpoints {
+            if let Err(e) = self.connect_to_ws2p_v2_endpoint(endpoint) {
+                warn!("WS2P: fail to connect to endpoint : {:?}", e);
+            }
+        }
+    }
+
+    /// Register a new outgoing connection to `endpoint`, return its identifier
+    pub fn add_connection(&mut self, endpoint: EndpointEnum) -> usize {
+        let conn_id = self.next_conn_id;
+        self.next_conn_id += 1;
+        self.connections.insert(
+            conn_id,
+            OutgoingConnection {
+                endpoint,
+                controller: None,
+                state: WS2PConnectionState::TryToOpenWS,
+                remote_full_id: None,
+                features: None,
+                operator_msgs: None,
+            },
+        );
+        conn_id
+    }
+
+    /// Connect to WSPv2 Endpoint in a dedicated thread
+    pub fn connect_to_ws2p_v2_endpoint(&mut self, endpoint: EndpointEnum) -> Result<(), WsError> {
+        if endpoint.get_url(true, false).is_none() {
+            return Err(WsError::UnknownError);
+        }
+        let conn_id = self.add_connection(endpoint.clone());
+
+        // The controller messages are tagged with the connection identifier
+        let (controller_sender, controller_receiver) = mpsc::channel();
+        let service_sender = self.sender.clone();
+        spawn_child(&format!("conn::{}::controller", conn_id), move || {
+            while let Ok(msg) = controller_receiver.recv() {
+                if service_sender
+                    .send(WS2PServiceMsg::OutgoingController {
+                        conn_id,
+                        msg: Box::new(msg),
+                    })
+                    .is_err()
+                {
+                    return;
+                }
+            }
+            let _ = service_sender.send(WS2PServiceMsg::OutgoingClosed { conn_id });
+        });
+
+        let currency = self.currency.clone();
+        let self_node = self.self_node.clone();
+        spawn_child(&format!("conn::{}", conn_id), move || {
+            if let Err(e) = controllers::outgoing_connections::connect_to_ws2p_v2_endpoint(
+                &currency,
+                &controller_sender,
+                &self_node,
+                None,
+                &endpoint,
+            ) {
+                warn!("WS2P: websocket error with {} : {}", endpoint.raw(), e);
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Process a message of the controller of an outgoing connection
+    pub fn process_controller_msg(
+        &mut self,
+        conn_id: usize,
+        msg: OrchestratorMsg<DursMsg>,
+    ) -> Option<NetworkEvent> {
+        let connection = self.connections.get_mut(&conn_id)?;
+        match msg {
+            OrchestratorMsg::ControllerSender(controller) => {
+                connection.controller = Some(controller);
+                None
+            }
+            OrchestratorMsg::ControllerEvent { event, .. } => match event {
+                WS2PControllerEvent::NewConnEstablished {
+                    remote_full_id,
+                    features,
+                    ..
+                } => {
+                    if let Err(PoolError::AlreadyConnected(api)) = self.pool.register(
+                        WS2PApi::V2,
+                        remote_full_id,
+                        ConnectionDirection::Outgoing,
+                    ) {
+                        info!(
+                            "WS2P: close connection with {}: already connected with {:?}",
+                            remote_full_id, api
+                        );
+                        if let Some(ref controller) = connection.controller {
+                            let _ = controller.send(WebsocketActionOrder::close());
+                        }
+                        return None;
+                    }
+                    info!("WS2P: connection established with {}", remote_full_id);
+                    self.known_endpoints.insert(connection.endpoint.clone());
+                    connection.remote_full_id = Some(remote_full_id);
+                    connection.features = Some(features);
+                    connection.state = WS2PConnectionState::Established;
+                    if features.opm() {
+                        match OperatorMsgsSession::new() {
+                            Ok(session) => {
+                                if let Some(ref controller) = connection.controller {
+                                    send_payload(
+                                        controller,
+                                        &self.currency,
+                                        &self.self_node,
+                                        &self.signator,
+                                        WS2Pv2MessagePayload::OperatorKey(session.public_key()),
+                                    );
+                                }
+                                connection.operator_msgs = Some(session);
+                            }
+                            Err(e) => {
+                                warn!("WS2P: fail to open operator messages session: {:?}", e)
+                            }
+                        }
+                    }
+                    Some(NetworkEvent::ConnectionStateChange(
+                        remote_full_id,
+                        network_state_code(WS2PConnectionState::Established),
+                        None,
+                        connection.url(),
+                    ))
+                }
+                WS2PControllerEvent::StateChange { new_state } => {
+                    connection.state = new_state;
+                    // Before the establishment, the remote node is unknown
+                    connection.remote_full_id.map(|remote_full_id| {
+                        NetworkEvent::ConnectionStateChange(
+                            remote_full_id,
+                            network_state_code(new_state),
+                            None,
+                            connection.url(),
+                        )
+                    })
+                }
+                WS2PControllerEvent::RecvValidMsg {
+                    ws2p_msg: WS2PMessage::V2(msg_v2),
+                } => {
+                    let (remote_full_id, session) =
+                        match (connection.remote_full_id, connection.operator_msgs.as_mut()) {
+                            (Some(remote_full_id), Some(session)) => (remote_full_id, session),
+                            _ => return None,
+                        };
+                    match msg_v2.payload {
+                        WS2Pv2MessagePayload::OperatorKey(remote_key) => {
+                            if let Err(e) = session.set_remote_key(&remote_key) {
+                                warn!(
+                                    "WS2P: invalid operator key from {}: {:?}",
+                                    remote_full_id, e
+                                );
+                            }
+                            None
+                        }
+                        WS2Pv2MessagePayload::OperatorMsg(ref operator_msg) => {
+                            match session.decrypt(operator_msg) {
+                                Ok(text) => {
+                                    Some(NetworkEvent::ReceiveOperatorMessage(remote_full_id, text))
+                                }
+                                Err(e) => {
+                                    warn!(
+                                        "WS2P: invalid operator message from {}: {:?}",
+                                        remote_full_id, e
+                                    );
+                                    None
+                                }
+                            }
+                        }
+                        _ => None,
+                    }
+                }
+                WS2PControllerEvent::RecvValidMsg { .. } => None,
+            },
+            OrchestratorMsg::ModuleMessage(_) => None,
+        }
+    }
+
+    /// Forget an outgoing connection whose thread is ended
+    pub fn remove_connection(&mut self, conn_id: usize) -> Option<OutgoingConnection> {
+        let connection = self.connections.remove(&conn_id)?;
+        if let Some(ref remote_full_id) = connection.remote_full_id {
+            self.pool.unregister(WS2PApi::V2, remote_full_id);
+        }
+        Some(connection)
+    }
+
+    /// Order all controllers to close their connection
+    pub fn close_all_connections(&self) {
+        for connection in self.connections.values() {
+            if let Some(ref controller) = connection.controller {
+                let _ = controller.send(WebsocketActionOrder::close());
+            }
+        }
+    }
+
+    /// Send a payload to all established connections.
+    /// Connections in low bandwidth mode receive its low bandwidth version, if any.
+    pub fn send_payload_to_all(&self, payload: &WS2Pv2MessagePayload) {
+        for connection in self.connections.values() {
+            let controller = match connection.controller {
+                Some(ref controller) if connection.state == WS2PConnectionState::Established => {
+                    controller
+                }
+                _ => continue,
+            };
+            let payload = match connection.features {
+                Some(features) if features.low() => {
+                    if let Some(payload) = payload.clone().low_bandwidth_version() {
+                        payload
+                    } else {
+                        continue;
+                    }
+                }
+                _ => payload.clone(),
+            };
+            send_payload(
+                controller,
+                &self.currency,
+                &self.self_node,
+                &self.signator,
+                payload,
+            );
+        }
+    }
+
+    /// Send an operator message to the connected nodes whose operator messages session is
+    /// established (all of them if `to` is None), return the number of recipients
+    pub fn send_operator_message(&mut self, to: Option<NodeFullId>, text: &str) -> usize {
+        let mut recipients_count = 0;
+        for connection in self.connections.values_mut() {
+            let (controller, remote_full_id, session) = match (
+                connection.controller.as_ref(),
+                connection.remote_full_id,
+                connection.operator_msgs.as_mut(),
+            ) {
+                (Some(controller), Some(remote_full_id), Some(session))
+                    if session.is_established() =>
+                {
+                    (controller, remote_full_id, session)
+                }
+                _ => continue,
+            };
+            if to.is_some() && to != Some(remote_full_id) {
+                continue;
+            }
+            match session.encrypt(text) {
+                Ok(operator_msg) => {
+                    send_payload(
+                        controller,
+                        &self.currency,
+                        &self.self_node,
+                        &self.signator,
+                        WS2Pv2MessagePayload::OperatorMsg(operator_msg),
+                    );
+                    recipients_count += 1;
+                }
+                Err(e) => warn!(
+                    "WS2P: fail to send operator message to {}: {:?}",
+                    remote_full_id, e
+                ),
+            }
+        }
+        recipients_count
+    }
+
+    /// Number of established connections
+    pub fn count_established_connections(&self) -> usize {
+        self.connections
+            .values()
+            .filter(|connection| connection.state == WS2PConnectionState::Established)
+            .count()
+    }
+}
+
+/// Order the controller of a connection to send a payload
+fn send_payload(
+    controller: &mpsc::Sender<WebsocketActionOrder>,
+    currency: &CurrencyName,
+    self_node: &MySelfWs2pNode,
+    signator: &SignatorEnum,
+    payload: WS2Pv2MessagePayload,
+) {
+    if let Ok((_, bin_msg)) = WS2Pv2Message::encapsulate_payload(
+        currency.clone(),
+        self_node.my_node_id,
+        signator,
+        payload,
+    ) {
+        let _ = controller.send(WebsocketActionOrder {
+            ws_action: WebsocketAction::SendMessage {
+                msg: WebsocketMessage::Bin(bin_msg),
+            },
+            new_state_if_success: None,
+            new_state_if_fail: WS2PConnectionState::Unreachable,
+        });
+    } else {
+        fatal_error!("Dev error: Fail to sign own message !");
+    }
+}
+
+/// Relay a network event to the router
+pub fn send_network_event(
+    router_sender: &mpsc::Sender<RouterThreadMessage<DursMsg>>,
+    event: NetworkEvent,
+) {
+    let module_event = match event {
+        NetworkEvent::ConnectionStateChange(..) => ModuleEvent::ConnectionsChangeNodeNetwork,
+        NetworkEvent::SyncEvent(_) => ModuleEvent::SyncEvent,
+        NetworkEvent::ReceiveOperatorMessage(..) => ModuleEvent::NewOperatorMessage,
+        _ => return,
+    };
+    if router_sender
+        .send(RouterThreadMessage::ModuleMessage(DursMsg::Event {
+            event_from: ModuleStaticName(constants::MODULE_NAME),
+            event_type: module_event,
+            event_content: DursEvent::NetworkEvent(event),
+        }))
+        .is_err()
+    {
+        fatal_error!("WS2P module fail to send network event to router !")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dup_crypto::keys::*;
+    use durs_network_documents::NodeId;
+    use durs_ws2p_messages::v2::connect::WS2Pv2ConnectType;
+    use durs_ws2p_protocol::controller::WS2PControllerId;
+
+    fn orchestrator() -> WS2POutgoingOrchestrator {
+        let seed = Seed32::new([1u8; 32]);
+        WS2POutgoingOrchestrator::new(
+            CurrencyName("g1".to_owned()),
+            &WS2PConf::default(),
+            MySelfWs2pNode {
+                my_node_id: NodeId(1),
+                my_key_pair: KeyPairEnum::Ed25519(ed25519::KeyPairFromSeed32Generator::generate(
+                    seed,
+                )),
+                my_features: WS2PFeatures([5u8, 0, 0, 0]),
+            },
+            WS2PPool::default(),
+        )
+    }
+
+    fn controller_event(event: WS2PControllerEvent) -> OrchestratorMsg<DursMsg> {
+        OrchestratorMsg::ControllerEvent {
+            controller_id: WS2PControllerId::Outgoing {
+                expected_remote_full_id: None,
+            },
+            event,
+        }
+    }
+
+    #[test]
+    fn connection_state_changes() {
+        let mut orchestrator = orchestrator();
+        assert_eq!(2, orchestrator.never_try_endpoints.len());
+        let endpoint = orchestrator.never_try_endpoints[0].clone();
+        let conn_id = orchestrator.add_connection(endpoint);
+        let remote_full_id = NodeFullId(NodeId(2), PubKey::default());
+
+        // The remote node is unknown before the establishment
+        assert_eq!(
+            None,
+            orchestrator.process_controller_msg(
+                conn_id,
+                controller_event(WS2PControllerEvent::StateChange {
+                    new_state: WS2PConnectionState::WaitingConnectMsg,
+                }),
+            )
+        );
+        assert_eq!(
+            Some(NetworkEvent::ConnectionStateChange(
+                remote_full_id,
+                12,
+                None,
+                "g1.dunitrust.org:443/ws2p".to_owned()
+            )),
+            orchestrator.process_controller_msg(
+                conn_id,
+                controller_event(WS2PControllerEvent::NewConnEstablished {
+                    conn_type: WS2Pv2ConnectType::OutgoingServer,
+                    remote_full_id,
+                    features: WS2PFeatures([5u8, 0, 0, 0]),
+                }),
+            )
+        );
+        assert_eq!(1, orchestrator.count_established_connections());
+        assert_eq!(
+            Some(NetworkEvent::ConnectionStateChange(
+                remote_full_id,
+                11,
+                None,
+                "g1.dunitrust.org:443/ws2p".to_owned()
+            )),
+            orchestrator.process_controller_msg(
+                conn_id,
+                controller_event(WS2PControllerEvent::StateChange {
+                    new_state: WS2PConnectionState::Close,
+                }),
+            )
+        );
+        assert_eq!(0, orchestrator.count_established_connections());
+
+        // Unknown connection
+        assert!(orchestrator.remove_connection(conn_id).is_some());
+        assert_eq!(
+            None,
+            orchestrator.process_controller_msg(
+                conn_id,
+                controller_event(WS2PControllerEvent::StateChange {
+                    new_state: WS2PConnectionState::Close,
+                }),
+            )
+        );
+    }
+
+    #[test]
+    fn do_not_connect_twice_to_the_same_node() {
+        let mut orchestrator = orchestrator();
+        let endpoint = orchestrator.never_try_endpoints[0].clone();
+        let remote_full_id = NodeFullId(NodeId(2), PubKey::default());
+        let established = |remote_full_id| {
+            controller_event(WS2PControllerEvent::NewConnEstablished {
+                conn_type: WS2Pv2ConnectType::OutgoingServer,
+                remote_full_id,
+                features: WS2PFeatures([5u8, 0, 0, 0]),
+            })
+        };
+
+        // The node is already connected with WS2Pv1
+        assert_eq!(
+            Ok(()),
+            orchestrator
+                .pool
+                .register(WS2PApi::V1, remote_full_id, ConnectionDirection::Incoming)
+        );
+        let conn_id = orchestrator.add_connection(endpoint.clone());
+        assert_eq!(
+            None,
+            orchestrator.process_controller_msg(conn_id, established(remote_full_id))
+        );
+        assert_eq!(0, orchestrator.count_established_connections());
+        assert!(orchestrator.remove_connection(conn_id).is_some());
+        assert!(orchestrator.known_endpoints.endpoints().is_empty());
+        assert!(orchestrator.pool.unregister(WS2PApi::V1, &remote_full_id));
+
+        // Once established, the connection is registered in the pool and its endpoint is known
+        let conn_id = orchestrator.add_connection(endpoint.clone());
+        assert!(orchestrator
+            .process_controller_msg(conn_id, established(remote_full_id))
+            .is_some());
+        assert!(orchestrator
+            .pool
+            .is_connected_with_other_api(WS2PApi::V1, &remote_full_id));
+        assert_eq!(&[endpoint], orchestrator.known_endpoints.endpoints());
+        assert!(orchestrator.remove_connection(conn_id).is_some());
+        assert_eq!(None, orchestrator.pool.connection(&remote_full_id));
+    }
+
+    #[test]
+    fn send_payload_to_low_bandwidth_connections() {
+        let mut orchestrator = orchestrator();
+        let mut receivers = Vec::new();
+        for low in &[false, true] {
+            let endpoint = orchestrator.never_try_endpoints[0].clone();
+            let conn_id = orchestrator.add_connection(endpoint);
+            let (controller_sender, controller_receiver) = mpsc::channel();
+            receivers.push(controller_receiver);
+            let connection = unwrap!(orchestrator.connections.get_mut(&conn_id));
+            connection.controller = Some(controller_sender);
+            connection.state = WS2PConnectionState::Established;
+            connection.features = Some(WS2PFeatures([5u8, 0, 0, 0]).with_low(*low));
+        }
+
+        // Blocks are sent to all connections
+        orchestrator.send_payload_to_all(&WS2Pv2MessagePayload::Blocks(vec![]));
+        assert!(receivers[0].try_recv().is_ok());
+        assert!(receivers[1].try_recv().is_ok());
+
+        // Pending documents are not sent to connections in low bandwidth mode
+        orchestrator.send_payload_to_all(&WS2Pv2MessagePayload::PendingTxs(vec![]));
+        assert!(receivers[0].try_recv().is_ok());
+        assert!(receivers[1].try_recv().is_err());
+    }
+
+    fn recv_payload(payload: WS2Pv2MessagePayload) -> OrchestratorMsg<DursMsg> {
+        controller_event(WS2PControllerEvent::RecvValidMsg {
+            ws2p_msg: WS2PMessage::V2(WS2Pv2Message {
+                currency_name: CurrencyName("g1".to_owned()),
+                issuer_node_id: NodeId(2),
+                issuer_pubkey: PubKey::default(),
+                payload,
+                message_hash: None,
+                signature: None,
+            }),
+        })
+    }
+
+    fn sent_payload(receiver: &mpsc::Receiver<WebsocketActionOrder>) -> WS2Pv2MessagePayload {
+        match unwrap!(receiver.try_recv()).ws_action {
+            WebsocketAction::SendMessage {
+                msg: WebsocketMessage::Bin(bin_msg),
+            } => match unwrap!(WS2PMessage::parse_and_check_bin_message(&bin_msg)) {
+                WS2PMessage::V2(msg_v2) => msg_v2.payload,
+                _ => panic!("unexpected message version"),
+            },
+            _ => panic!("unexpected websocket action"),
+        }
+    }
+
+    #[test]
+    fn exchange_operator_messages() {
+        let mut orchestrator = orchestrator();
+        let endpoint = orchestrator.never_try_endpoints[0].clone();
+        let conn_id = orchestrator.add_connection(endpoint);
+        let (controller_sender, controller_receiver) = mpsc::channel();
+        orchestrator.process_controller_msg(
+            conn_id,
+            OrchestratorMsg::ControllerSender(controller_sender),
+        );
+        let remote_full_id = NodeFullId(NodeId(2), PubKey::default());
+        orchestrator.process_controller_msg(
+            conn_id,
+            controller_event(WS2PControllerEvent::NewConnEstablished {
+                conn_type: WS2Pv2ConnectType::OutgoingServer,
+                remote_full_id,
+                features: WS2PFeatures([5u8, 0, 0, 0]).with_opm(true),
+            }),
+        );
+
+        // The local node sends its operator key
+        let local_key = match sent_payload(&controller_receiver) {
+            WS2Pv2MessagePayload::OperatorKey(key) => key,
+            payload => panic!("unexpected payload: {:?}", payload),
+        };
+        let mut remote_session = unwrap!(OperatorMsgsSession::new());
+        unwrap!(remote_session.set_remote_key(&local_key));
+        // No recipient before the reception of the remote key
+        assert_eq!(0, orchestrator.send_operator_message(None, "hello"));
+        assert_eq!(
+            None,
+            orchestrator.process_controller_msg(
+                conn_id,
+                recv_payload(WS2Pv2MessagePayload::OperatorKey(
+                    remote_session.public_key()
+                )),
+            )
+        );
+
+        // Receive an operator message
+        let operator_msg = unwrap!(remote_session.encrypt("maintenance at 20:00"));
+        assert_eq!(
+            Some(NetworkEvent::ReceiveOperatorMessage(
+                remote_full_id,
+                "maintenance at 20:00".to_owned()
+            )),
+            orchestrator.process_controller_msg(
+                conn_id,
+                recv_payload(WS2Pv2MessagePayload::OperatorMsg(operator_msg)),
+            )
+        );
+
+        // Send an operator message
+        assert_eq!(
+            0,
+            orchestrator
+                .send_operator_message(Some(NodeFullId(NodeId(3), PubKey::default())), "hello")
+        );
+        assert_eq!(1, orchestrator.send_operator_message(None, "hello"));
+        match sent_payload(&controller_receiver) {
+            WS2Pv2MessagePayload::OperatorMsg(operator_msg) => assert_eq!(
+                Ok("hello".to_owned()),
+                remote_session.decrypt(&operator_msg)
+            ),
+            payload => panic!("unexpected payload: {:?}", payload),
         }
     }
 }
@@ -0,0 +1,877 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//! Sub-module downloading the blockchain from WS2Pv2 sync endpoints.
+
+use crate::constants;
+use crate::services::outgoing::{send_network_event, WS2POutgoingOrchestrator};
+use crate::services::WS2PServiceMsg;
+use crate::*;
+use dubp_block_doc::block::BlockDocumentTrait;
+use dubp_block_doc::BlockDocument;
+use dubp_common_doc::traits::Document;
+use dubp_common_doc::{BlockNumber, Blockstamp};
+use dup_crypto::hashs::Hash;
+use dup_crypto::keys::{KeyPair, SignatorEnum};
+use durs_common_tools::timer::elapsed_since;
+use durs_common_tools::Percent;
+use durs_message::requests::{BlockchainRequest, DursReqContent};
+use durs_message::responses::{BlockchainResponse, DursResContent};
+use durs_network::events::{NetworkEvent, SyncEvent};
+use durs_network_documents::url::Url;
+use durs_ws2p_messages::v2::payload_container::WS2Pv2MessagePayload;
+use durs_ws2p_messages::v2::req_responses::{WS2Pv2ReqRes, WS2Pv2ReqResBody};
+use durs_ws2p_messages::v2::requests::{WS2Pv2Request, WS2Pv2RequestBody};
+use durs_ws2p_messages::v2::WS2Pv2Message;
+use durs_ws2p_messages::WS2PMessage;
+use durs_ws2p_protocol::connection_state::WS2PConnectionState;
+use durs_ws2p_protocol::controller::{WS2PControllerEvent, WebsocketActionOrder};
+use durs_ws2p_protocol::orchestrator::OrchestratorMsg;
+use durs_ws2p_protocol::websocket::{WebsocketAction, WebsocketMessage};
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// Request sent to a sync peer
+enum SyncRequest {
+    /// Current blockstamp of the peer
+    Current,
+    /// Block whose blockstamp is the sync target
+    TargetBlock,
+    /// Chunk of blocks beginning with this block number
+    Chunk(BlockNumber),
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+/// Established connection from which the blocks can be downloaded
+struct SyncPeer {
+    /// Current blockstamp of the peer
+    current: Option<Blockstamp>,
+    /// Pending request (identifier, request and sending time)
+    pending_req: Option<(u32, SyncRequest, Instant)>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+/// Consequences of a response of a sync peer
+struct SyncOutcome {
+    /// Events to send to the router
+    events: Vec<SyncEvent>,
+    /// Peers that have sent invalid datas
+    invalid_peers: Vec<usize>,
+}
+
+#[derive(Debug, Default)]
+/// Synchronization progress
+struct WS2PSyncState {
+    /// Last block number to download (sync option)
+    end: Option<u32>,
+    /// Local current blockstamp
+    local_current: Option<Blockstamp>,
+    /// Target blockstamp
+    target: Option<Blockstamp>,
+    /// Sync peers by connection identifier
+    peers: BTreeMap<usize, SyncPeer>,
+    /// First block number of the next chunk to request
+    next_chunk: Option<BlockNumber>,
+    /// First block number of the chunks to request again
+    chunks_to_retry: BTreeSet<BlockNumber>,
+    /// Chunks received before the previous ones (connection and blocks, by first block number)
+    received_chunks: BTreeMap<BlockNumber, (usize, Vec<BlockDocument>)>,
+    /// Next block number to send to the blockchain module and hash of its previous block
+    next: Option<(BlockNumber, Option<Hash>)>,
+    /// Number of blocks to download
+    blocks_count: u32,
+    /// Number of blocks downloaded
+    downloaded_blocks: u32,
+    /// Number of blocks sent to the blockchain module
+    sent_blocks: u32,
+    /// Identifier of the next request
+    next_req_id: u32,
+    /// All blocks are downloaded
+    finished: bool,
+}
+
+/// Download the blockchain from the sync endpoints matching the source, and send it to the blockchain module
+pub fn sync(
+    soft_meta_datas: &SoftwareMetaDatas<DuRsConf>,
+    keys: RequiredKeysContent,
+    conf: WS2PConf,
+    router_sender: mpsc::Sender<RouterThreadMessage<DursMsg>>,
+    sync_opts: SyncOpt,
+) -> Result<(), SyncError> {
+    println!("Download blockchain from network...");
+
+    // Get key_pair
+    let key_pair = if let RequiredKeysContent::NetworkKeyPair(key_pair) = keys {
+        key_pair
+    } else {
+        fatal_error!("WS2P: unexpected keys !");
+    };
+    let signator = if let Ok(signator) = key_pair.generate_signator() {
+        signator
+    } else {
+        fatal_error!("WS2P: fail to generate signator !");
+    };
+
+    // Select sync endpoints
+    let source = sync_opts
+        .source
+        .as_ref()
+        .and_then(Url::host)
+        .unwrap_or_default();
+    let sync_endpoints = select_sync_endpoints(&conf.sync_endpoints, sync_opts.source.as_ref());
+    if sync_endpoints.is_empty() {
+        return Err(SyncError::InvalidSource { source });
+    }
+
+    // Instantiate outgoing connections orchestrator
+    let mut orchestrator = WS2POutgoingOrchestrator::new(
+        soft_meta_datas.conf.get_currency(),
+        &conf,
+        MySelfWs2pNode {
+            my_node_id: NodeId(soft_meta_datas.conf.my_node_id()),
+            my_key_pair: key_pair,
+            my_features: conf.my_features(),
+        },
+        WS2PPool::shared(),
+    );
+    orchestrator.never_try_endpoints = sync_endpoints;
+
+    // Registration with the rooter
+    register_in_router(&router_sender, orchestrator.sender.clone());
+
+    // Request local current blockstamp
+    send_blockchain_request(&router_sender, BlockchainRequest::CurrentBlockstamp());
+
+    // Connect to sync endpoints
+    orchestrator.connect_to_endpoints();
+
+    let mut sync_state = WS2PSyncState {
+        end: sync_opts.end,
+        ..WS2PSyncState::default()
+    };
+    while !sync_state.finished {
+        match orchestrator.receiver.recv_timeout(Duration::from_millis(
+            *constants::WS2P_RECV_SERVICE_FREQ_IN_MS,
+        )) {
+            Ok(WS2PServiceMsg::DursMsg(durs_msg)) => match *durs_msg {
+                DursMsg::Stop => break,
+                DursMsg::Response {
+                    res_content:
+                        DursResContent::BlockchainResponse(BlockchainResponse::CurrentBlockstamp(
+                            current_blockstamp,
+                        )),
+                    ..
+                } => {
+                    sync_state.set_local_current(current_blockstamp);
+                }
+                _ => {}
+            },
+            Ok(WS2PServiceMsg::OutgoingController { conn_id, msg }) => match *msg {
+                OrchestratorMsg::ControllerEvent {
+                    event:
+                        WS2PControllerEvent::RecvValidMsg {
+                            ws2p_msg: WS2PMessage::V2(msg_v2),
+                        },
+                    ..
+                } => {
+                    if let WS2Pv2MessagePayload::ReqRes(response) = msg_v2.payload {
+                        let outcome = sync_state.receive_response(conn_id, response);
+                        for event in outcome.events {
+                            send_network_event(&router_sender, NetworkEvent::SyncEvent(event));
+                        }
+                        for conn_id in outcome.invalid_peers {
+                            sync_state.remove_peer(conn_id);
+                            close_connection(&orchestrator, conn_id, "Invalid sync response !");
+                        }
+                    }
+                }
+                msg @ OrchestratorMsg::ControllerEvent {
+                    event: WS2PControllerEvent::NewConnEstablished { .. },
+                    ..
+                } => {
+                    if let Some(event) = orchestrator.process_controller_msg(conn_id, msg) {
+                        send_network_event(&router_sender, event);
+                    }
+                    sync_state.add_peer(conn_id);
+                }
+                msg => {
+                    if let Some(event) = orchestrator.process_controller_msg(conn_id, msg) {
+                        send_network_event(&router_sender, event);
+                    }
+                    match orchestrator.connections.get(&conn_id) {
+                        Some(connection)
+                            if connection.state == WS2PConnectionState::Established => {}
+                        _ => sync_state.remove_peer(conn_id),
+                    }
+                }
+            },
+            Ok(WS2PServiceMsg::OutgoingClosed { conn_id }) => {
+                orchestrator.remove_connection(conn_id);
+                sync_state.remove_peer(conn_id);
+                // Replace the closed connection by another sync endpoint
+                orchestrator.connect_to_endpoints();
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                fatal_error!("Disconnected ws2p module !");
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+        }
+
+        let now = Instant::now();
+        for conn_id in sync_state.timed_out_peers(now) {
+            warn!("WS2P: sync request timeout !");
+            sync_state.remove_peer(conn_id);
+            close_connection(&orchestrator, conn_id, "Sync request timeout !");
+        }
+        if orchestrator.connections.is_empty() && orchestrator.never_try_endpoints.is_empty() {
+            return Err(SyncError::UnreachableSource { source });
+        }
+        for (conn_id, request) in sync_state.next_requests(now) {
+            send_request(&orchestrator, &signator, conn_id, request);
+        }
+    }
+
+    orchestrator.close_all_connections();
+
+    Ok(())
+}
+
+/// Host of an endpoint
+fn endpoint_host(endpoint: &EndpointEnum) -> Option<String> {
+    match endpoint {
+        EndpointEnum::V1(ref ep) => Some(ep.host.clone()),
+        EndpointEnum::V2(ref ep) => ep
+            .domain
+            .clone()
+            .or_else(|| ep.ip_v4.map(|ip| ip.to_string()))
+            .or_else(|| ep.ip_v6.map(|ip| ip.to_string())),
+    }
+}
+
+/// Select sync endpoints (from conf) matching the source
+fn select_sync_endpoints(
+    conf_sync_endpoints: &[EndpointEnum],
+    source: Option<&Url>,
+) -> Vec<EndpointEnum> {
+    let source_host = source.and_then(Url::host);
+    let source_port = source.and_then(Url::port);
+
+    conf_sync_endpoints
+        .iter()
+        .filter(|ep| {
+            source_host.is_none()
+                || (source_host == endpoint_host(ep)
+                    && source_port.map_or(ep.port(), usize::from) == ep.port())
+        })
+        .cloned()
+        .collect()
+}
+
+/// Send a request to the blockchain module
+fn send_blockchain_request(
+    router_sender: &mpsc::Sender<RouterThreadMessage<DursMsg>>,
+    req: BlockchainRequest,
+) {
+    if router_sender
+        .send(RouterThreadMessage::ModuleMessage(DursMsg::Request {
+            req_from: ModuleStaticName(constants::MODULE_NAME),
+            req_to: ModuleRole::BlockchainDatas,
+            req_id: ModuleReqId(0),
+            req_content: DursReqContent::BlockchainRequest(req),
+        }))
+        .is_err()
+    {
+        fatal_error!("WS2P module fail to send request to router !")
+    }
+}
+
+/// Order the controller of an outgoing connection to send a request
+pub fn send_request(
+    orchestrator: &WS2POutgoingOrchestrator,
+    signator: &SignatorEnum,
+    conn_id: usize,
+    request: WS2Pv2Request,
+) {
+    let controller = if let Some(controller) = orchestrator
+        .connections
+        .get(&conn_id)
+        .and_then(|connection| connection.controller.as_ref())
+    {
+        controller
+    } else {
+        return;
+    };
+    if let Ok((_, bin_request)) = WS2Pv2Message::encapsulate_payload(
+        orchestrator.currency.clone(),
+        orchestrator.self_node.my_node_id,
+        signator,
+        WS2Pv2MessagePayload::Request(request),
+    ) {
+        let _ = controller.send(WebsocketActionOrder {
+            ws_action: WebsocketAction::SendMessage {
+                msg: WebsocketMessage::Bin(bin_request),
+            },
+            new_state_if_success: None,
+            new_state_if_fail: WS2PConnectionState::Unreachable,
+        });
+    } else {
+        fatal_error!("Dev error: Fail to sign own request message !");
+    }
+}
+
+/// Order the controller of an outgoing connection to close it
+fn close_connection(orchestrator: &WS2POutgoingOrchestrator, conn_id: usize, reason: &str) {
+    if let Some(controller) = orchestrator
+        .connections
+        .get(&conn_id)
+        .and_then(|connection| connection.controller.as_ref())
+    {
+        let _ = controller.send(WebsocketActionOrder::close_with_reason(Some(
+            reason.to_owned(),
+        )));
+    }
+}
+
+/// Check that the chunk blocks follow each other from the expected block
+fn check_chunk_continuity(
+    first_number: BlockNumber,
+    previous_hash: Option<Hash>,
+    blocks: &[BlockDocument],
+) -> bool {
+    let mut expected = (first_number, previous_hash);
+    for block in blocks {
+        if block.number() != expected.0 || block.previous_hash() != expected.1 {
+            return false;
+        }
+        expected = (
+            BlockNumber(block.number().0 + 1),
+            block.hash().map(|block_hash| block_hash.0),
+        );
+    }
+    !blocks.is_empty()
+}
+
+/// Number of blocks of the chunk beginning with `from`
+fn chunk_size(from: BlockNumber, target: Blockstamp) -> u16 {
+    std::cmp::min(
+        u32::from(*constants::WS2P_SYNC_CHUNK_SIZE),
+        target.id.0 + 1 - from.0,
+    ) as u16
+}
+
+/// Number of the last block of the chunk beginning with `from`
+fn chunk_last_number(from: BlockNumber, target: Blockstamp) -> BlockNumber {
+    BlockNumber(from.0 + u32::from(chunk_size(from, target)) - 1)
+}
+
+impl WS2PSyncState {
+    /// Download blocks from a new established connection
+    fn add_peer(&mut self, conn_id: usize) {
+        self.peers.entry(conn_id).or_default();
+    }
+    /// Forget a peer, its pending chunk will be requested to another peer
+    fn remove_peer(&mut self, conn_id: usize) {
+        if let Some(SyncPeer {
+            pending_req: Some((_, SyncRequest::Chunk(from), _)),
+            ..
+        }) = self.peers.remove(&conn_id)
+        {
+            self.chunks_to_retry.insert(from);
+        }
+    }
+    /// Peers whose pending request has timed out
+    fn timed_out_peers(&self, now: Instant) -> Vec<usize> {
+        self.peers
+            .iter()
+            .filter_map(|(conn_id, peer)| {
+                peer.pending_req
+                    .map(|(_, _, sent_time)| (*conn_id, sent_time))
+            })
+            .filter(|(_, sent_time)| {
+                elapsed_since(now, *sent_time)
+                    > Duration::from_secs(*constants::WS2P_SYNC_REQUEST_TIMEOUT_IN_SECS)
+            })
+            .map(|(conn_id, _)| conn_id)
+            .collect()
+    }
+    /// Receive the local current blockstamp
+    fn set_local_current(&mut self, local_current: Blockstamp) {
+        self.local_current = Some(local_current);
+        self.plan_download();
+    }
+    /// Define the target blockstamp
+    fn set_target(&mut self, target: Blockstamp) -> Vec<SyncEvent> {
+        info!("WS2P: sync target blockstamp: {}", target);
+        self.target = Some(target);
+        self.plan_download();
+        vec![
+            SyncEvent::ReceiveTargetBlockstamp(target),
+            SyncEvent::ReceiveChunksSize(*constants::WS2P_SYNC_CHUNK_SIZE as usize),
+        ]
+    }
+    /// Define the blocks to download, once the local current and target blockstamps are known
+    fn plan_download(&mut self) {
+        if self.next.is_some() || self.finished {
+            return;
+        }
+        if let (Some(local_current), Some(target)) = (self.local_current, self.target) {
+            let next = if local_current == Blockstamp::default() {
+                (BlockNumber(0), None)
+            } else if target.id > local_current.id {
+                (
+                    BlockNumber(local_current.id.0 + 1),
+                    Some(local_current.hash.0),
+                )
+            } else {
+                println!("Your durs node is already synchronized.");
+                self.finished = true;
+                return;
+            };
+            self.next = Some(next);
+            self.next_chunk = Some(next.0);
+            self.blocks_count = target.id.0 + 1 - (next.0).0;
+        }
+    }
+    /// The target block is requested to a peer
+    fn is_awaiting_target(&self) -> bool {
+        self.peers
+            .values()
+            .filter_map(|peer| peer.pending_req)
+            .any(|(_, request, _)| request == SyncRequest::TargetBlock)
+    }
+    /// Requests to send to the peers without pending request
+    fn next_requests(&mut self, now: Instant) -> Vec<(usize, WS2Pv2Request)> {
+        let mut requests = Vec::new();
+        let idle_peers: Vec<(usize, Option<Blockstamp>)> = self
+            .peers
+            .iter()
+            .filter(|(_, peer)| peer.pending_req.is_none())
+            .map(|(conn_id, peer)| (*conn_id, peer.current))
+            .collect();
+        for (conn_id, peer_current) in idle_peers {
+            if let Some((request, body)) = self.next_request_for(peer_current) {
+                let id = self.next_req_id;
+                self.next_req_id = self.next_req_id.wrapping_add(1);
+                if let Some(peer) = self.peers.get_mut(&conn_id) {
+                    peer.pending_req = Some((id, request, now));
+                }
+                requests.push((conn_id, WS2Pv2Request { id, body }));
+            }
+        }
+        requests
+    }
+    /// Choose the next request for a peer according to its current blockstamp
+    fn next_request_for(
+        &mut self,
+        peer_current: Option<Blockstamp>,
+    ) -> Option<(SyncRequest, WS2Pv2RequestBody)> {
+        let peer_current = if let Some(peer_current) = peer_current {
+            peer_current
+        } else {
+            return Some((SyncRequest::Current, WS2Pv2RequestBody::Current));
+        };
+        let target = if let Some(target) = self.target {
+            target
+        } else {
+            return match self.end {
+                Some(end) if end <= peer_current.id.0 && !self.is_awaiting_target() => Some((
+                    SyncRequest::TargetBlock,
+                    WS2Pv2RequestBody::Chunk(BlockNumber(end), 1),
+                )),
+                _ => None,
+            };
+        };
+
+        // The peer must have all the blocks of the chunk
+        let retry = self
+            .chunks_to_retry
+            .iter()
+            .cloned()
+            .find(|from| chunk_last_number(*from, target) <= peer_current.id);
+        let from = if let Some(from) = retry {
+            self.chunks_to_retry.remove(&from);
+            from
+        } else {
+            let from = self.next_chunk?;
+            if from > target.id
+                || chunk_last_number(from, target) > peer_current.id
+                || self.received_chunks.len() >= *constants::WS2P_SYNC_MAX_BUFFERED_CHUNKS
+            {
+                return None;
+            }
+            self.next_chunk = Some(BlockNumber(chunk_last_number(from, target).0 + 1));
+            from
+        };
+        Some((
+            SyncRequest::Chunk(from),
+            WS2Pv2RequestBody::Chunk(from, chunk_size(from, target)),
+        ))
+    }
+    /// Highest current blockstamp of the peers, if all peers are below the end block
+    fn best_current_below_end(&self, end: u32) -> Option<Blockstamp> {
+        let mut best_current = None;
+        for peer in self.peers.values() {
+            match peer.current {
+                Some(current) if current.id.0 < end => match best_current {
+                    Some(Blockstamp { id, .. }) if id >= current.id => {}
+                    _ => best_current = Some(current),
+                },
+                _ => return None,
+            }
+        }
+        best_current
+    }
+    /// Treat a response of a sync peer
+    fn receive_response(&mut self, conn_id: usize, response: WS2Pv2ReqRes) -> SyncOutcome {
+        let mut outcome = SyncOutcome::default();
+        let request = match self.peers.get_mut(&conn_id) {
+            Some(peer) => match peer.pending_req {
+                Some((id, request, _)) if id == response.id => {
+                    peer.pending_req = None;
+                    if let WS2Pv2ReqResBody::Current(current) = response.body {
+                        peer.current = Some(current);
+                    }
+                    request
+                }
+                _ => return outcome,
+            },
+            None => return outcome,
+        };
+        match (request, response.body) {
+            (SyncRequest::Current, WS2Pv2ReqResBody::Current(current)) => {
+                if self.target.is_none() {
+                    match self.end {
+                        None => outcome.events = self.set_target(current),
+                        Some(end) => {
+                            // The end block may not exist yet, fall back on the best current block
+                            if let Some(best_current) = self.best_current_below_end(end) {
+                                outcome.events = self.set_target(best_current);
+                            }
+                        }
+                    }
+                }
+            }
+            (SyncRequest::TargetBlock, WS2Pv2ReqResBody::Chunk(blocks)) => match blocks.first() {
+                Some(block) if blocks.len() == 1 && self.end == Some(block.number().0) => {
+                    if self.target.is_none() {
+                        outcome.events = self.set_target(block.blockstamp());
+                    }
+                }
+                _ => {
+                    warn!("WS2P: receive invalid target block !");
+                    outcome.invalid_peers.push(conn_id);
+                }
+            },
+            (SyncRequest::Chunk(from), WS2Pv2ReqResBody::Chunk(blocks)) => {
+                self.receive_chunk(conn_id, from, blocks, &mut outcome);
+            }
+            (request, _) => {
+                warn!("WS2P: receive unexpected sync response !");
+                if let SyncRequest::Chunk(from) = request {
+                    self.chunks_to_retry.insert(from);
+                }
+                outcome.invalid_peers.push(conn_id);
+            }
+        }
+        outcome
+    }
+    /// Check a received chunk and send all the chunks that can be sent in order
+    fn receive_chunk(
+        &mut self,
+        conn_id: usize,
+        from: BlockNumber,
+        blocks: Vec<BlockDocument>,
+        outcome: &mut SyncOutcome,
+    ) {
+        let target = if let Some(target) = self.target {
+            target
+        } else {
+            return;
+        };
+        // The link with the previous chunk is checked when the chunk is sent
+        let valid = blocks.len() == chunk_size(from, target) as usize
+            && check_chunk_continuity(from, blocks[0].previous_hash(), &blocks)
+            && (blocks[blocks.len() - 1].number() < target.id
+                || blocks[blocks.len() - 1].blockstamp() == target);
+        if !valid {
+            warn!("WS2P: receive invalid sync chunk from #{} !", from);
+            self.chunks_to_retry.insert(from);
+            outcome.invalid_peers.push(conn_id);
+            return;
+        }
+        self.downloaded_blocks += blocks.len() as u32;
+        self.received_chunks.insert(from, (conn_id, blocks));
+
+        while let Some((next_number, previous_hash)) = self.next {
+            let (chunk_conn_id, blocks) =
+                if let Some(chunk) = self.received_chunks.remove(&next_number) {
+                    chunk
+                } else {
+                    break;
+                };
+            if blocks[0].previous_hash() != previous_hash {
+                warn!(
+                    "WS2P: sync chunk from #{} not follow the previous blocks !",
+                    next_number
+                );
+                self.downloaded_blocks -= blocks.len() as u32;
+                self.chunks_to_retry.insert(next_number);
+                outcome.invalid_peers.push(chunk_conn_id);
+                break;
+            }
+            let last_block = &blocks[blocks.len() - 1];
+            debug!(
+                "WS2P: receive sync chunk from #{} to #{}",
+                next_number,
+                last_block.number()
+            );
+            self.next = Some((
+                BlockNumber(last_block.number().0 + 1),
+                last_block.hash().map(|block_hash| block_hash.0),
+            ));
+            self.finished = last_block.number() >= target.id;
+            self.sent_blocks += blocks.len() as u32;
+            outcome.events.push(SyncEvent::ReceiveCorrectBlocksChunk {
+                blocks,
+                raw_blocks: None,
+            });
+        }
+        outcome.events.push(self.progression());
+    }
+    /// Download progression
+    fn progression(&self) -> SyncEvent {
+        let percent = |blocks: u32| {
+            unwrap!(Percent::new(
+                (u64::from(blocks) * 100 / u64::from(std::cmp::max(self.blocks_count, 1))) as u8
+            ))
+        };
+        SyncEvent::BarsProgressionChange {
+            milestones: percent(self.sent_blocks),
+            download: percent(self.downloaded_blocks),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dubp_blocks_tests_tools::mocks::gen_empty_timed_blocks_v10;
+    use std::str::FromStr;
+
+    fn response(id: u32, body: WS2Pv2ReqResBody) -> WS2Pv2ReqRes {
+        WS2Pv2ReqRes { id, body }
+    }
+
+    fn chunk_request(id: u32, from: u32, count: u16) -> WS2Pv2Request {
+        WS2Pv2Request {
+            id,
+            body: WS2Pv2RequestBody::Chunk(BlockNumber(from), count),
+        }
+    }
+
+    fn progression(milestones: u8, download: u8) -> SyncEvent {
+        SyncEvent::BarsProgressionChange {
+            milestones: unwrap!(Percent::new(milestones)),
+            download: unwrap!(Percent::new(download)),
+        }
+    }
+
+    /// Sync state with two peers whose current block is the target block
+    fn sync_state_with_two_peers(
+        blocks: &[BlockDocument],
+        now: Instant,
+    ) -> (WS2PSyncState, Blockstamp) {
+        let target = blocks[blocks.len() - 1].blockstamp();
+        let mut sync_state = WS2PSyncState::default();
+        sync_state.set_local_current(blocks[1].blockstamp());
+        sync_state.add_peer(0);
+        sync_state.add_peer(1);
+        assert_eq!(
+            vec![
+                (
+                    0,
+                    WS2Pv2Request {
+                        id: 0,
+                        body: WS2Pv2RequestBody::Current
+                    }
+                ),
+                (
+                    1,
+                    WS2Pv2Request {
+                        id: 1,
+                        body: WS2Pv2RequestBody::Current
+                    }
+                ),
+            ],
+            sync_state.next_requests(now)
+        );
+        assert_eq!(
+            SyncOutcome {
+                events: vec![
+                    SyncEvent::ReceiveTargetBlockstamp(target),
+                    SyncEvent::ReceiveChunksSize(250),
+                ],
+                invalid_peers: vec![],
+            },
+            sync_state.receive_response(0, response(0, WS2Pv2ReqResBody::Current(target)))
+        );
+        assert_eq!(
+            SyncOutcome::default(),
+            sync_state.receive_response(1, response(1, WS2Pv2ReqResBody::Current(target)))
+        );
+        (sync_state, target)
+    }
+
+    #[test]
+    fn parallel_chunks_download() {
+        let blocks = gen_empty_timed_blocks_v10(602, 10);
+        let now = Instant::now();
+        let (mut sync_state, _) = sync_state_with_two_peers(&blocks, now);
+
+        assert_eq!(
+            vec![
+                (0, chunk_request(2, 2, 250)),
+                (1, chunk_request(3, 252, 250))
+            ],
+            sync_state.next_requests(now)
+        );
+        // The second chunk is kept until the first one is received
+        assert_eq!(
+            SyncOutcome {
+                events: vec![progression(0, 41)],
+                invalid_peers: vec![],
+            },
+            sync_state.receive_response(
+                1,
+                response(3, WS2Pv2ReqResBody::Chunk(blocks[252..502].to_vec()))
+            )
+        );
+        assert_eq!(
+            vec![(1, chunk_request(4, 502, 100))],
+            sync_state.next_requests(now)
+        );
+        assert_eq!(
+            SyncOutcome {
+                events: vec![
+                    SyncEvent::ReceiveCorrectBlocksChunk {
+                        blocks: blocks[2..252].to_vec(),
+                        raw_blocks: None,
+                    },
+                    SyncEvent::ReceiveCorrectBlocksChunk {
+                        blocks: blocks[252..502].to_vec(),
+                        raw_blocks: None,
+                    },
+                    progression(83, 83),
+                ],
+                invalid_peers: vec![],
+            },
+            sync_state.receive_response(
+                0,
+                response(2, WS2Pv2ReqResBody::Chunk(blocks[2..252].to_vec()))
+            )
+        );
+        assert!(!sync_state.finished);
+        assert_eq!(
+            SyncOutcome {
+                events: vec![
+                    SyncEvent::ReceiveCorrectBlocksChunk {
+                        blocks: blocks[502..].to_vec(),
+                        raw_blocks: None,
+                    },
+                    progression(100, 100),
+                ],
+                invalid_peers: vec![],
+            },
+            sync_state.receive_response(
+                1,
+                response(4, WS2Pv2ReqResBody::Chunk(blocks[502..].to_vec()))
+            )
+        );
+        assert!(sync_state.finished);
+    }
+
+    #[test]
+    fn invalid_chunk_is_requested_again() {
+        let blocks = gen_empty_timed_blocks_v10(302, 10);
+        let now = Instant::now();
+        let (mut sync_state, _) = sync_state_with_two_peers(&blocks, now);
+
+        assert_eq!(
+            vec![
+                (0, chunk_request(2, 2, 250)),
+                (1, chunk_request(3, 252, 50))
+            ],
+            sync_state.next_requests(now)
+        );
+        // Blocks not following each other
+        let mut invalid_chunk = blocks[2..252].to_vec();
+        invalid_chunk.swap(0, 1);
+        let outcome =
+            sync_state.receive_response(0, response(2, WS2Pv2ReqResBody::Chunk(invalid_chunk)));
+        assert_eq!(vec![0], outcome.invalid_peers);
+        sync_state.remove_peer(0);
+
+        // The peer 1 has no pending request anymore once its chunk is received
+        assert_eq!(
+            SyncOutcome {
+                events: vec![progression(0, 16)],
+                invalid_peers: vec![],
+            },
+            sync_state.receive_response(
+                1,
+                response(3, WS2Pv2ReqResBody::Chunk(blocks[252..].to_vec()))
+            )
+        );
+        assert_eq!(
+            vec![(1, chunk_request(4, 2, 250))],
+            sync_state.next_requests(now)
+        );
+    }
+
+    #[test]
+    fn timed_out_chunk_is_requested_again() {
+        let blocks = gen_empty_timed_blocks_v10(302, 10);
+        let now = Instant::now();
+        let (mut sync_state, _) = sync_state_with_two_peers(&blocks, now);
+        sync_state.next_requests(now);
+        assert!(sync_state.timed_out_peers(now).is_empty());
+
+        sync_state.receive_response(
+            1,
+            response(3, WS2Pv2ReqResBody::Chunk(blocks[252..].to_vec())),
+        );
+        let later = now + Duration::from_secs(*constants::WS2P_SYNC_REQUEST_TIMEOUT_IN_SECS + 1);
+        assert_eq!(vec![0], sync_state.timed_out_peers(later));
+        sync_state.remove_peer(0);
+        assert_eq!(
+            vec![(1, chunk_request(4, 2, 250))],
+            sync_state.next_requests(later)
+        );
+    }
+
+    #[test]
+    fn select_sync_endpoints_by_source() {
+        let conf_endpoints = WS2PConf::default().sync_endpoints;
+
+        let source = unwrap!(Url::from_str("rs.g1.librelois.fr"));
+        assert_eq!(
+            vec![conf_endpoints[1].clone()],
+            select_sync_endpoints(&conf_endpoints, Some(&source))
+        );
+        let source = unwrap!(Url::from_str("rs.g1.librelois.fr:80"));
+        assert!(select_sync_endpoints(&conf_endpoints, Some(&source)).is_empty());
+        assert_eq!(conf_endpoints, select_sync_endpoints(&conf_endpoints, None));
+    }
+}
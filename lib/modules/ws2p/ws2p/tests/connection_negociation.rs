@@ -252,6 +252,7 @@ fn test_connection_negociation_success() {
         WS2PControllerEvent::NewConnEstablished {
             conn_type: WS2Pv2ConnectType::OutgoingServer,
             remote_full_id: server_node.get_full_id(),
+            features: WS2PFeatures([5u8, 0, 0, 0]),
         },
     );
     // Established for server
@@ -260,6 +261,7 @@ fn test_connection_negociation_success() {
         WS2PControllerEvent::NewConnEstablished {
             conn_type: WS2Pv2ConnectType::OutgoingServer,
             remote_full_id: client_node.get_full_id(),
+            features: WS2PFeatures([5u8, 0, 0, 0]),
         },
     );
 }
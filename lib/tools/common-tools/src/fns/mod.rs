@@ -20,4 +20,5 @@ pub mod arrays;
 pub mod bin_file;
 pub mod r#static;
 pub mod str_escape;
+pub mod thread;
 pub mod time;
@@ -0,0 +1,75 @@
+//  Copyright (C) 2019  Éloïs SANCHEZ
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Common rust functions for naming threads.
+//!
+//! Threads are named hierarchically from the thread that spawns them (`ws2p1::conn::<peer>`),
+//! so that they can be told apart in debuggers, profilers and flamegraphs.
+
+use std::thread::{self, JoinHandle};
+
+/// Separator between the levels of a thread name
+pub static THREAD_NAME_SEPARATOR: &str = "::";
+
+/// Name of a child thread of the current thread
+pub fn child_thread_name(child_name: &str) -> String {
+    if let Some(parent_name) = thread::current().name() {
+        format!("{}{}{}", parent_name, THREAD_NAME_SEPARATOR, child_name)
+    } else {
+        child_name.to_owned()
+    }
+}
+
+/// Spawn a thread named as a child of the current thread
+///
+/// Panics if the thread cannot be spawned, like `std::thread::spawn`.
+pub fn spawn_child<F, T>(child_name: &str, f: F) -> JoinHandle<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    thread::Builder::new()
+        .name(child_thread_name(child_name))
+        .spawn(f)
+        .expect("failed to spawn thread")
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_child_thread_name() {
+        let handle = thread::Builder::new()
+            .name("ws2p1".to_owned())
+            .spawn(|| {
+                spawn_child("conn", || {
+                    spawn_child("peer", || thread::current().name().map(ToOwned::to_owned))
+                        .join()
+                        .ok()
+                        .and_then(|name| name)
+                })
+                .join()
+                .ok()
+                .and_then(|name| name)
+            })
+            .expect("failed to spawn thread");
+        assert_eq!(
+            Some("ws2p1::conn::peer".to_owned()),
+            handle.join().ok().and_then(|name| name)
+        );
+    }
+}
@@ -30,6 +30,7 @@
 
 pub mod fns;
 pub mod macros;
+pub mod timer;
 pub mod traits;
 mod usizeser32;
 
@@ -16,3 +16,4 @@
 //! Common rust macros for DURS project.
 
 pub mod fatal_error;
+pub mod rate_limited_log;
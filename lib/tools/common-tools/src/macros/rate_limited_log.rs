@@ -0,0 +1,186 @@
+//  Copyright (C) 2019  Éloïs SANCHEZ
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Rate-limited logging macros for DURS project.
+//!
+//! Repetitive warnings (unreachable peers, failed connections...) are logged at most once per
+//! interval. The next logged message reports how many similar messages were suppressed meanwhile.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[doc(hidden)]
+pub use once_cell::sync::Lazy;
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Rate limiter of a log call site
+#[derive(Debug)]
+pub struct RateLimiter {
+    last_log_ms: AtomicU64,
+    suppressed: AtomicU64,
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        RateLimiter::new()
+    }
+}
+
+impl RateLimiter {
+    /// Create a rate limiter (usable in a static)
+    pub const fn new() -> RateLimiter {
+        RateLimiter {
+            last_log_ms: AtomicU64::new(0),
+            suppressed: AtomicU64::new(0),
+        }
+    }
+    /// Returns the number of suppressed messages if a message can be logged now,
+    /// `None` if the message must be suppressed.
+    pub fn check(&self, interval_in_secs: u64) -> Option<u64> {
+        self.check_at(now_ms(), interval_in_secs * 1_000)
+    }
+    fn check_at(&self, now_ms: u64, interval_ms: u64) -> Option<u64> {
+        let last_log_ms = self.last_log_ms.load(Ordering::Relaxed);
+        if (last_log_ms == 0 || now_ms.saturating_sub(last_log_ms) >= interval_ms)
+            && self
+                .last_log_ms
+                .compare_exchange(last_log_ms, now_ms, Ordering::Relaxed, Ordering::Relaxed)
+                .is_ok()
+        {
+            Some(self.suppressed.swap(0, Ordering::Relaxed))
+        } else {
+            self.suppressed.fetch_add(1, Ordering::Relaxed);
+            None
+        }
+    }
+}
+
+/// Rate limiter of a log call site, by key (for example one key per peer)
+#[derive(Debug, Default)]
+pub struct KeyedRateLimiter {
+    limiters: Mutex<HashMap<String, (u64, u64)>>,
+}
+
+impl KeyedRateLimiter {
+    /// Returns the number of suppressed messages for this key if a message can be logged now,
+    /// `None` if the message must be suppressed.
+    pub fn check(&self, key: &str, interval_in_secs: u64) -> Option<u64> {
+        self.check_at(key, now_ms(), interval_in_secs * 1_000)
+    }
+    fn check_at(&self, key: &str, now_ms: u64, interval_ms: u64) -> Option<u64> {
+        let mut limiters = self
+            .limiters
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        // Forget keys that have been quiet for a long time
+        if limiters.len() > 1_000 {
+            limiters
+                .retain(|_, (last_log_ms, _)| now_ms.saturating_sub(*last_log_ms) < interval_ms);
+        }
+        if let Some((last_log_ms, suppressed)) = limiters.get_mut(key) {
+            if now_ms.saturating_sub(*last_log_ms) >= interval_ms {
+                *last_log_ms = now_ms;
+                Some(std::mem::replace(suppressed, 0))
+            } else {
+                *suppressed += 1;
+                None
+            }
+        } else {
+            limiters.insert(key.to_owned(), (now_ms, 0));
+            Some(0)
+        }
+    }
+}
+
+/// Log a message at most once per interval (in seconds) for this call site.
+/// The number of suppressed messages is added to the next logged message.
+///
+/// `log_once_per!(60, log::Level::Warn, "WS2P: not found peer to send request !");`
+#[macro_export]
+macro_rules! log_once_per {
+    ($interval_in_secs:expr, $lvl:expr, $($arg:tt)+) => ({
+        static RATE_LIMITER: $crate::macros::rate_limited_log::RateLimiter =
+            $crate::macros::rate_limited_log::RateLimiter::new();
+        if let Some(suppressed) = RATE_LIMITER.check($interval_in_secs) {
+            $crate::__log_with_suppressed!($lvl, suppressed, $($arg)+);
+        }
+    });
+}
+
+/// Log a message at most once per interval (in seconds) for each key of this call site.
+/// The number of suppressed messages for the key is added to the next logged message.
+///
+/// `log_rate_limited!(peer_pubkey, 60, log::Level::Warn, "WS2P : {} is flooding us.", peer_pubkey);`
+#[macro_export]
+macro_rules! log_rate_limited {
+    ($key:expr, $interval_in_secs:expr, $lvl:expr, $($arg:tt)+) => ({
+        static RATE_LIMITER: $crate::macros::rate_limited_log::Lazy<
+            $crate::macros::rate_limited_log::KeyedRateLimiter,
+        > = $crate::macros::rate_limited_log::Lazy::new(Default::default);
+        if let Some(suppressed) = RATE_LIMITER.check(&$key.to_string(), $interval_in_secs) {
+            $crate::__log_with_suppressed!($lvl, suppressed, $($arg)+);
+        }
+    });
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log_with_suppressed {
+    ($lvl:expr, $suppressed:expr, $($arg:tt)+) => ({
+        if $suppressed > 0 {
+            log::log!(
+                $lvl,
+                "{} ({} similar messages suppressed)",
+                format_args!($($arg)+),
+                $suppressed
+            );
+        } else {
+            log::log!($lvl, $($arg)+);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rate_limiter() {
+        let rate_limiter = RateLimiter::new();
+        assert_eq!(Some(0), rate_limiter.check_at(1_000, 500));
+        assert_eq!(None, rate_limiter.check_at(1_200, 500));
+        assert_eq!(None, rate_limiter.check_at(1_400, 500));
+        assert_eq!(Some(2), rate_limiter.check_at(1_500, 500));
+        assert_eq!(Some(0), rate_limiter.check_at(2_000, 500));
+    }
+
+    #[test]
+    fn keyed_rate_limiter() {
+        let rate_limiter = KeyedRateLimiter::default();
+        assert_eq!(Some(0), rate_limiter.check_at("a", 1_000, 500));
+        assert_eq!(Some(0), rate_limiter.check_at("b", 1_100, 500));
+        assert_eq!(None, rate_limiter.check_at("a", 1_200, 500));
+        assert_eq!(Some(1), rate_limiter.check_at("a", 1_500, 500));
+        assert_eq!(None, rate_limiter.check_at("b", 1_500, 500));
+    }
+}
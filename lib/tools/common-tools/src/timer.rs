@@ -0,0 +1,197 @@
+//  Copyright (C) 2019  Éloïs SANCHEZ
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Timers based on a monotonic clock.
+//!
+//! The system clock may jump (NTP adjustments, suspended laptops, migrated VMs),
+//! so the timeouts must be measured with `Instant` rather than with `SystemTime`.
+
+use std::time::{Duration, Instant};
+
+/// Source of monotonic time
+pub trait MonotonicClock {
+    /// Current instant
+    fn now(&self) -> Instant;
+}
+
+/// Monotonic clock of the operating system
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StdMonotonicClock;
+
+impl MonotonicClock for StdMonotonicClock {
+    #[inline]
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+#[inline]
+/// Duration elapsed between `since` and `now` (zero if `since` is after `now`)
+pub fn elapsed_since(now: Instant, since: Instant) -> Duration {
+    if now > since {
+        now - since
+    } else {
+        Duration::from_secs(0)
+    }
+}
+
+/// Timer expiring after a fixed duration
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Timer {
+    start: Instant,
+    duration: Duration,
+}
+
+impl Timer {
+    /// Start a timer of `duration` at `now`
+    pub fn new(now: Instant, duration: Duration) -> Self {
+        Timer {
+            start: now,
+            duration,
+        }
+    }
+    /// Instant at which the timer was (re)started
+    pub fn start(&self) -> Instant {
+        self.start
+    }
+    /// Duration elapsed since the timer was (re)started
+    pub fn elapsed(&self, now: Instant) -> Duration {
+        elapsed_since(now, self.start)
+    }
+    /// Remaining duration before expiry
+    pub fn remaining(&self, now: Instant) -> Duration {
+        self.duration
+            .checked_sub(self.elapsed(now))
+            .unwrap_or_default()
+    }
+    /// The timer is expired (strictly more than its duration elapsed)
+    pub fn is_expired(&self, now: Instant) -> bool {
+        self.elapsed(now) > self.duration
+    }
+    /// Restart the timer at `now`
+    pub fn reset(&mut self, now: Instant) {
+        self.start = now;
+    }
+    /// Restart the timer if it is expired, return true if it was
+    pub fn reset_if_expired(&mut self, now: Instant) -> bool {
+        if self.is_expired(now) {
+            self.start = now;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Timer of a periodic action that may not have happened yet
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LastOccurrence(Option<Instant>);
+
+impl LastOccurrence {
+    /// Action that already happened at `now`
+    pub fn at(now: Instant) -> Self {
+        LastOccurrence(Some(now))
+    }
+    /// Action that never happened
+    pub fn never() -> Self {
+        LastOccurrence(None)
+    }
+    /// The action never happened or its last occurrence is older than `interval`
+    pub fn is_older_than(&self, now: Instant, interval: Duration) -> bool {
+        match self.0 {
+            Some(last) => elapsed_since(now, last) > interval,
+            None => true,
+        }
+    }
+    /// Record an occurrence at `now`
+    pub fn record(&mut self, now: Instant) {
+        self.0 = Some(now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    /// Clock driven by the test
+    #[derive(Debug)]
+    struct ManualClock(Cell<Instant>);
+
+    impl ManualClock {
+        fn advance(&self, secs: u64) {
+            self.0.set(self.0.get() + Duration::from_secs(secs));
+        }
+    }
+
+    impl MonotonicClock for ManualClock {
+        fn now(&self) -> Instant {
+            self.0.get()
+        }
+    }
+
+    #[test]
+    fn timer_expiry() {
+        let clock = ManualClock(Cell::new(Instant::now()));
+        let mut timer = Timer::new(clock.now(), Duration::from_secs(30));
+
+        clock.advance(30);
+        assert!(!timer.is_expired(clock.now()));
+        assert_eq!(Duration::from_secs(0), timer.remaining(clock.now()));
+        clock.advance(1);
+        assert!(timer.is_expired(clock.now()));
+        assert!(timer.reset_if_expired(clock.now()));
+        assert!(!timer.reset_if_expired(clock.now()));
+        assert_eq!(Duration::from_secs(30), timer.remaining(clock.now()));
+    }
+
+    #[test]
+    fn timer_with_instant_before_start() {
+        // A time source going backward never expires the timer nor panics
+        let now = Instant::now() + Duration::from_secs(3_600);
+        let timer = Timer::new(now, Duration::from_secs(30));
+        let before = now - Duration::from_secs(3_600);
+
+        assert_eq!(Duration::from_secs(0), timer.elapsed(before));
+        assert!(!timer.is_expired(before));
+        assert_eq!(Duration::from_secs(30), timer.remaining(before));
+    }
+
+    #[test]
+    fn timer_with_forward_jump() {
+        // A long suspend expires the timer only once
+        let clock = ManualClock(Cell::new(Instant::now()));
+        let mut timer = Timer::new(clock.now(), Duration::from_secs(30));
+
+        clock.advance(86_400);
+        assert!(timer.reset_if_expired(clock.now()));
+        clock.advance(1);
+        assert!(!timer.reset_if_expired(clock.now()));
+    }
+
+    #[test]
+    fn last_occurrence() {
+        let clock = ManualClock(Cell::new(Instant::now()));
+        let interval = Duration::from_secs(40);
+        let mut last = LastOccurrence::never();
+
+        assert!(last.is_older_than(clock.now(), interval));
+        last.record(clock.now());
+        assert!(!last.is_older_than(clock.now(), interval));
+        clock.advance(41);
+        assert!(last.is_older_than(clock.now(), interval));
+        assert!(!LastOccurrence::at(clock.now()).is_older_than(clock.now(), interval));
+    }
+}
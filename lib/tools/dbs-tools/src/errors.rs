@@ -34,6 +34,9 @@ pub enum DbError {
     /// Error with the file system
     #[fail(display = "Error with the file system")]
     FileSystemError(std::io::Error),
+    /// No migration of the database schema from this version
+    #[fail(display = "No migration of the database schema from version {}", _0)]
+    MissingMigration(usize),
     /// Serialization/Deserialization error
     #[fail(display = "Serialization/Deserialization error: {}", _0)]
     SerdeError(String),
@@ -43,6 +46,17 @@ pub enum DbError {
     /// Unknown error
     #[fail(display = "Unknown error")]
     UnknowError,
+    /// The database was written by a more recent software
+    #[fail(
+        display = "Unsupported database schema version {}, the maximum supported version is {}",
+        found, supported
+    )]
+    UnsupportedVersion {
+        /// Version of the database schema
+        found: usize,
+        /// Maximum version supported by the software
+        supported: usize,
+    },
     /// Abort write transaction
     #[fail(display = "Abort write transaction, reason: {}", _0)]
     WriteAbort {
@@ -1,14 +1,19 @@
 ///////////////////////
 /// OLD A SUPPRIMER ///
 ///////////////////////
+mod batch;
 mod file;
+mod migration;
 
+pub use batch::{BatchKey, BatchValue, WriteBatch};
 #[cfg(feature = "mock")]
 pub use file::MockKvFileDbReader;
 pub use file::{
     from_db_value, KvFileDbHandler, KvFileDbRead, KvFileDbReader, KvFileDbRoHandler,
-    KvFileDbSchema, KvFileDbStoreType, KvFileDbWriter, WriteResp,
+    KvFileDbSchema, KvFileDbStats, KvFileDbStoreType, KvFileDbWriter, WriteResp,
+    KV_FILE_DB_DATA_FILENAME,
 };
+pub use migration::{Migration, MigrationOptions, MigrationRegistry};
 pub use rkv::{
     store::multi::Iter, IntegerStore, MultiIntegerStore, MultiStore,
     OwnedValue as KvFileDbOwnedValue, Readable, SingleStore, Value as KvFileDbValue,
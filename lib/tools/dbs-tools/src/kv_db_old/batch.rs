@@ -0,0 +1,278 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Write batch: puts and deletes prepared outside of a write transaction (the values are
+//! already serialized), then applied together in a single write transaction.
+
+use super::file::{KvFileDbHandler, KvFileDbRead, KvFileDbStoreType, KvFileDbWriter, WriteResp};
+use crate::errors::DbError;
+use durs_common_tools::fatal_error;
+use rkv::Value;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// Key of a prepared entry
+pub enum BatchKey {
+    /// Key of a store with bytes keys
+    Bytes(Vec<u8>),
+    /// Key of a store with integer keys
+    Int(u32),
+}
+
+impl From<u32> for BatchKey {
+    fn from(key: u32) -> Self {
+        BatchKey::Int(key)
+    }
+}
+
+impl From<Vec<u8>> for BatchKey {
+    fn from(key: Vec<u8>) -> Self {
+        BatchKey::Bytes(key)
+    }
+}
+
+impl From<&[u8]> for BatchKey {
+    fn from(key: &[u8]) -> Self {
+        BatchKey::Bytes(key.to_vec())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// Value of a prepared entry
+pub enum BatchValue {
+    /// Serialized value
+    Blob(Vec<u8>),
+    /// Integer value
+    U64(u64),
+    /// String value
+    Str(String),
+}
+
+impl BatchValue {
+    fn as_db_value(&self) -> Value {
+        match *self {
+            BatchValue::Blob(ref bytes) => Value::Blob(bytes),
+            BatchValue::U64(v) => Value::U64(v),
+            BatchValue::Str(ref v) => Value::Str(v),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum BatchOp {
+    Put {
+        store_name: String,
+        key: BatchKey,
+        value: BatchValue,
+    },
+    /// Delete the key (all its values in a multi store)
+    Delete { store_name: String, key: BatchKey },
+    /// Delete a value of the key of a multi store
+    DeleteValue {
+        store_name: String,
+        key: BatchKey,
+        value: BatchValue,
+    },
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+/// Puts and deletes accumulated across several stores, applied in order in one write transaction
+pub struct WriteBatch {
+    ops: Vec<BatchOp>,
+}
+
+impl WriteBatch {
+    /// Create an empty write batch
+    pub fn new() -> WriteBatch {
+        WriteBatch::default()
+    }
+    /// Number of prepared operations
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+    /// Check if no operation is prepared
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+    /// Put a value (added to the values of the key in a multi store)
+    pub fn put<K: Into<BatchKey>>(&mut self, store_name: &str, key: K, value: BatchValue) {
+        self.ops.push(BatchOp::Put {
+            store_name: store_name.to_owned(),
+            key: key.into(),
+            value,
+        });
+    }
+    /// Delete a key (all its values in a multi store), nothing is done if the key doesn't exist
+    pub fn delete<K: Into<BatchKey>>(&mut self, store_name: &str, key: K) {
+        self.ops.push(BatchOp::Delete {
+            store_name: store_name.to_owned(),
+            key: key.into(),
+        });
+    }
+    /// Delete a value of a key of a multi store
+    pub fn delete_value<K: Into<BatchKey>>(&mut self, store_name: &str, key: K, value: BatchValue) {
+        self.ops.push(BatchOp::DeleteValue {
+            store_name: store_name.to_owned(),
+            key: key.into(),
+            value,
+        });
+    }
+    /// Append the operations of another batch
+    pub fn append(&mut self, mut other: WriteBatch) {
+        self.ops.append(&mut other.ops);
+    }
+    /// Apply the prepared operations in the write transaction `w`
+    pub fn apply(&self, db: &KvFileDbHandler, w: &mut KvFileDbWriter) -> Result<(), DbError> {
+        for op in &self.ops {
+            match *op {
+                BatchOp::Put {
+                    ref store_name,
+                    ref key,
+                    ref value,
+                } => match (store_type(db, store_name), key) {
+                    (KvFileDbStoreType::Single, BatchKey::Bytes(key)) => {
+                        db.get_store(store_name)
+                            .put(w.as_mut(), key, &value.as_db_value())?
+                    }
+                    (KvFileDbStoreType::SingleIntKey, BatchKey::Int(key)) => db
+                        .get_int_store(store_name)
+                        .put(w.as_mut(), *key, &value.as_db_value())?,
+                    (KvFileDbStoreType::Multi, BatchKey::Bytes(key)) => db
+                        .get_multi_store(store_name)
+                        .put(w.as_mut(), key, &value.as_db_value())?,
+                    (KvFileDbStoreType::MultiIntKey, BatchKey::Int(key)) => db
+                        .get_multi_int_store(store_name)
+                        .put(w.as_mut(), *key, &value.as_db_value())?,
+                    _ => wrong_key_type(store_name),
+                },
+                BatchOp::Delete {
+                    ref store_name,
+                    ref key,
+                } => match (store_type(db, store_name), key) {
+                    (KvFileDbStoreType::Single, BatchKey::Bytes(key)) => {
+                        let store = db.get_store(store_name);
+                        if store.get(w.as_ref(), key)?.is_some() {
+                            store.delete(w.as_mut(), key)?;
+                        }
+                    }
+                    (KvFileDbStoreType::SingleIntKey, BatchKey::Int(key)) => {
+                        let store = db.get_int_store(store_name);
+                        if store.get(w.as_ref(), *key)?.is_some() {
+                            store.delete(w.as_mut(), *key)?;
+                        }
+                    }
+                    (KvFileDbStoreType::Multi, BatchKey::Bytes(key)) => {
+                        let store = db.get_multi_store(store_name);
+                        if store.get_first(w.as_ref(), key)?.is_some() {
+                            store.delete_all(w.as_mut(), key)?;
+                        }
+                    }
+                    (KvFileDbStoreType::MultiIntKey, BatchKey::Int(key)) => {
+                        let store = db.get_multi_int_store(store_name);
+                        if store.get_first(w.as_ref(), *key)?.is_some() {
+                            store.delete_all(w.as_mut(), *key)?;
+                        }
+                    }
+                    _ => wrong_key_type(store_name),
+                },
+                BatchOp::DeleteValue {
+                    ref store_name,
+                    ref key,
+                    ref value,
+                } => match (store_type(db, store_name), key) {
+                    (KvFileDbStoreType::Multi, BatchKey::Bytes(key)) => db
+                        .get_multi_store(store_name)
+                        .delete(w.as_mut(), key, &value.as_db_value())?,
+                    (KvFileDbStoreType::MultiIntKey, BatchKey::Int(key)) => db
+                        .get_multi_int_store(store_name)
+                        .delete(w.as_mut(), *key, &value.as_db_value())?,
+                    _ => fatal_error!(
+                        "Dev error: delete a value of store '{}' which is not a multi store.",
+                        store_name
+                    ),
+                },
+            }
+        }
+        Ok(())
+    }
+    /// Apply the prepared operations in a new write transaction and commit it
+    pub fn commit(&self, db: &KvFileDbHandler) -> Result<(), DbError> {
+        db.write(|mut w| {
+            self.apply(db, &mut w)?;
+            Ok(WriteResp::from(w))
+        })
+    }
+}
+
+fn store_type(db: &KvFileDbHandler, store_name: &str) -> KvFileDbStoreType {
+    db.store_type(store_name)
+        .unwrap_or_else(|| fatal_error!("Dev error: store '{}' don't exist in DB.", store_name))
+}
+
+fn wrong_key_type(store_name: &str) -> ! {
+    fatal_error!("Dev error: wrong key type for store '{}'.", store_name)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::kv_db_old::KvFileDbSchema;
+    use std::collections::HashMap;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_write_batch() -> Result<(), DbError> {
+        let tmp_dir = tempdir().map_err(DbError::FileSystemError)?;
+        let mut stores = HashMap::new();
+        stores.insert("single".to_owned(), KvFileDbStoreType::Single);
+        stores.insert("int".to_owned(), KvFileDbStoreType::SingleIntKey);
+        stores.insert("multi".to_owned(), KvFileDbStoreType::Multi);
+        let db = KvFileDbHandler::open_db(tmp_dir.path(), &KvFileDbSchema { stores })?;
+
+        let mut batch = WriteBatch::new();
+        batch.put("single", &b"a"[..], BatchValue::Str("toto".to_owned()));
+        batch.put("int", 3, BatchValue::U64(42));
+        batch.put("multi", &b"m"[..], BatchValue::U64(1));
+        batch.put("multi", &b"m"[..], BatchValue::U64(2));
+        batch.delete_value("multi", &b"m"[..], BatchValue::U64(1));
+        // Deleting an unknown key does nothing
+        batch.delete("single", &b"unknown"[..]);
+        assert_eq!(6, batch.len());
+        batch.commit(&db)?;
+
+        db.read(|r| {
+            assert_eq!(
+                Some(Value::Str("toto")),
+                db.get_store("single").get(&r, b"a")?
+            );
+            assert_eq!(Some(Value::U64(42)), db.get_int_store("int").get(&r, 3)?);
+            assert_eq!(
+                Some(Value::U64(2)),
+                db.get_multi_store("multi").get_first(&r, b"m")?
+            );
+            Ok(())
+        })?;
+
+        let mut batch = WriteBatch::new();
+        batch.delete("single", &b"a"[..]);
+        batch.delete("multi", &b"m"[..]);
+        batch.commit(&db)?;
+        db.read(|r| {
+            assert_eq!(None, db.get_store("single").get(&r, b"a")?);
+            assert_eq!(None, db.get_multi_store("multi").get_first(&r, b"m")?);
+            Ok(())
+        })
+    }
+}
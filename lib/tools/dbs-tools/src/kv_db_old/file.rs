@@ -21,11 +21,14 @@ use log::error;
 use rkv::{DatabaseFlags, EnvironmentFlags, Manager, OwnedValue, Rkv, StoreOptions, Value};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
-use std::collections::HashMap;
-use std::fmt::Debug;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{self, Debug};
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock};
 
+/// Name of the file containing the datas of a Key-value file Database
+pub static KV_FILE_DB_DATA_FILENAME: &str = "data.mdb";
+
 /// Key-value database reader
 pub struct KvFileDbReader<'r>(&'r rkv::Reader<'r>);
 
@@ -119,7 +122,7 @@ impl KvFileDbRoHandler {
     /// Open Key-value file Database in read-only mode
     pub fn open_db_ro(path: &Path, schema: &KvFileDbSchema) -> Result<KvFileDbRoHandler, DbError> {
         let mut db_main_file = path.to_owned();
-        db_main_file.push("data.mdb");
+        db_main_file.push(KV_FILE_DB_DATA_FILENAME);
         if !db_main_file.as_path().is_file() {
             return Err(DbError::DBNotExist);
         }
@@ -330,6 +333,10 @@ impl KvFileDbHandler {
     fn arc(&self) -> &Arc<RwLock<Rkv>> {
         &self.arc
     }
+    /// Type of a store (None if the store doesn't exist)
+    pub fn store_type(&self, store_name: &str) -> Option<KvFileDbStoreType> {
+        self.schema.stores.get(store_name).copied()
+    }
     fn arc_clone(&self) -> Arc<RwLock<Rkv>> {
         self.arc().clone()
     }
@@ -340,21 +347,37 @@ impl KvFileDbHandler {
     /// Open Key-value file Database
     #[inline]
     pub fn open_db(path: &Path, schema: &KvFileDbSchema) -> Result<KvFileDbHandler, DbError> {
-        KvFileDbHandler::open_db_inner(path, schema, true)
+        KvFileDbHandler::open_db_inner(path, schema, true, false)
+    }
+    /// Open Key-value file Database with an environment that is not shared with the other
+    /// handlers of the process, the environment is closed when the handler is dropped.
+    /// Needed to replace the database files, the shared environments are never closed.
+    #[inline]
+    pub fn open_db_detached(
+        path: &Path,
+        schema: &KvFileDbSchema,
+    ) -> Result<KvFileDbHandler, DbError> {
+        KvFileDbHandler::open_db_inner(path, schema, true, true)
     }
     fn open_db_inner(
         path: &Path,
         schema: &KvFileDbSchema,
         first_open: bool,
+        detached: bool,
     ) -> Result<KvFileDbHandler, DbError> {
         let mut env_flags = EnvironmentFlags::NO_MEM_INIT;
         env_flags.insert(EnvironmentFlags::NO_SYNC);
-        let mut manager = Manager::singleton().write()?;
         let mut env = Rkv::environment_builder();
         env.set_flags(env_flags)
             .set_max_dbs(64)
             .set_map_size(std::u32::MAX as usize);
-        let arc = manager.get_or_create(path, |path| Rkv::from_env(path, env))?;
+        let arc = if detached {
+            Arc::new(RwLock::new(Rkv::from_env(path, env)?))
+        } else {
+            Manager::singleton()
+                .write()?
+                .get_or_create(path, |path| Rkv::from_env(path, env))?
+        };
 
         let mut stores = HashMap::new();
         for (store_name, store_type) in &schema.stores {
@@ -404,6 +427,89 @@ impl KvFileDbHandler {
             stores,
         })
     }
+    /// Get statistics of the database file
+    pub fn stats(&self) -> Result<KvFileDbStats, DbError> {
+        self.file_stats(&self.path.join(KV_FILE_DB_DATA_FILENAME))
+    }
+    fn file_stats(&self, file: &Path) -> Result<KvFileDbStats, DbError> {
+        let env = self.arc_clone();
+        let env = env.read()?;
+        let page_size = env.stat()?.page_size() as usize;
+        let pages_count = std::fs::metadata(file)
+            .map_err(DbError::FileSystemError)?
+            .len() as usize
+            / page_size;
+        // rkv only exposes the size of the free list through the load ratio of the map,
+        // which is (last used page - free pages) / pages of the map
+        let map_pages = env.info()?.map_size() / page_size;
+        let pages_in_use = (f64::from(env.load_ratio()?) * map_pages as f64).round() as usize;
+        Ok(KvFileDbStats {
+            page_size,
+            pages_count,
+            free_pages: pages_count.saturating_sub(pages_in_use),
+        })
+    }
+    /// Count the entries of each store (each value of a multi store is an entry).
+    /// Iterate over all the database, can be long.
+    pub fn entries_count(&self) -> Result<BTreeMap<String, usize>, DbError> {
+        let env = self.arc_clone();
+        let env = env.read()?;
+        // The stores are already opened by the handler, so LMDB gives back the same databases
+        // whatever the flags: an untyped view is enough to iterate over all their entries.
+        // The views are opened before the read transaction (one read transaction per thread).
+        let mut views = Vec::with_capacity(self.schema.stores.len());
+        for store_name in self.schema.stores.keys() {
+            views.push((
+                store_name,
+                env.open_single(
+                    store_name.as_str(),
+                    StoreOptions {
+                        create: false,
+                        flags: DatabaseFlags::empty(),
+                    },
+                )?,
+            ));
+        }
+
+        let reader = env.read()?;
+        let mut entries_count = BTreeMap::new();
+        for (store_name, view) in views {
+            let mut count = 0;
+            for entry in view.iter_start(&reader)? {
+                entry?;
+                count += 1;
+            }
+            entries_count.insert(store_name.to_owned(), count);
+        }
+        Ok(entries_count)
+    }
+    /// Copy the database file into the `backup_path` folder while the database is in use.
+    ///
+    /// A read transaction is not enough: it only protects the pages it reads, the writers keep
+    /// updating the other pages and the meta pages of the file. So a write transaction is opened
+    /// (and aborted) to block the writers of all processes during the copy, the readers are not blocked.
+    /// The file is first copied under a temporary name, so that a previous backup is only
+    /// replaced by a complete one.
+    pub fn backup_to(&self, backup_path: &Path) -> Result<KvFileDbStats, DbError> {
+        std::fs::create_dir_all(backup_path).map_err(DbError::FileSystemError)?;
+        let backup_file = backup_path.join(KV_FILE_DB_DATA_FILENAME);
+        let tmp_backup_file = backup_path.join(format!("{}.tmp", KV_FILE_DB_DATA_FILENAME));
+
+        {
+            let env = self.arc().read()?;
+            let _writer = env.write()?;
+            // Committed datas may not be synced on disk (NO_SYNC)
+            env.sync(true)?;
+            std::fs::copy(self.path.join(KV_FILE_DB_DATA_FILENAME), &tmp_backup_file)
+                .map_err(DbError::FileSystemError)?;
+        }
+        std::fs::File::open(&tmp_backup_file)
+            .and_then(|file| file.sync_all())
+            .map_err(DbError::FileSystemError)?;
+        std::fs::rename(&tmp_backup_file, &backup_file).map_err(DbError::FileSystemError)?;
+
+        self.file_stats(&backup_file)
+    }
     /// Persist DB datas on disk
     pub fn save(&self) -> Result<(), DbError> {
         Ok(self.arc_clone().read()?.sync(true)?)
@@ -422,6 +528,37 @@ impl KvFileDbHandler {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Statistics of a Key-value file Database
+pub struct KvFileDbStats {
+    /// Size of a page (in bytes)
+    pub page_size: usize,
+    /// Number of pages of the database file
+    pub pages_count: usize,
+    /// Number of pages of the database file that are not used (reclaimed by a compaction)
+    pub free_pages: usize,
+}
+
+impl KvFileDbStats {
+    /// Size of the database file (in bytes)
+    pub fn file_size(&self) -> usize {
+        self.pages_count * self.page_size
+    }
+}
+
+impl fmt::Display for KvFileDbStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} MiB ({} pages of {} bytes, {} free pages)",
+            self.file_size() / (1024 * 1024),
+            self.pages_count,
+            self.page_size,
+            self.free_pages
+        )
+    }
+}
+
 /// Write transaction response
 pub struct WriteResp<'w, D> {
     writer: KvFileDbWriter<'w>,
@@ -524,4 +661,80 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_open_db_detached() -> Result<(), DbError> {
+        let tmp_dir = tempdir().map_err(DbError::FileSystemError)?;
+        let mut stores = HashMap::new();
+        stores.insert("test1".to_owned(), KvFileDbStoreType::SingleIntKey);
+        let schema = KvFileDbSchema { stores };
+
+        {
+            let db = KvFileDbHandler::open_db_detached(tmp_dir.path(), &schema)?;
+            db.write(|mut w| {
+                db.get_int_store("test1")
+                    .put(w.as_mut(), 3, &Value::Str("toto"))?;
+                Ok(WriteResp::from(w))
+            })?;
+            db.save()?;
+        }
+
+        let db = KvFileDbHandler::open_db_detached(tmp_dir.path(), &schema)?;
+        let value = db.read(|r| {
+            if let Some(Value::Str(v)) = db.get_int_store("test1").get(&r, 3)? {
+                Ok(Some(v.to_owned()))
+            } else {
+                Ok(None)
+            }
+        })?;
+        assert_eq!(Some("toto".to_owned()), value);
+
+        let stats = db.stats()?;
+        assert!(stats.page_size > 0);
+        assert!(stats.pages_count > 0);
+        assert!(stats.free_pages < stats.pages_count);
+
+        let entries_count = db.entries_count()?;
+        assert_eq!(Some(&1), entries_count.get("test1"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_backup_to() -> Result<(), DbError> {
+        let tmp_dir = tempdir().map_err(DbError::FileSystemError)?;
+        let backup_dir = tempdir().map_err(DbError::FileSystemError)?;
+        let mut stores = HashMap::new();
+        stores.insert("test1".to_owned(), KvFileDbStoreType::SingleIntKey);
+        let schema = KvFileDbSchema { stores };
+
+        let db = KvFileDbHandler::open_db(tmp_dir.path(), &schema)?;
+        db.write(|mut w| {
+            db.get_int_store("test1")
+                .put(w.as_mut(), 3, &Value::Str("toto"))?;
+            Ok(WriteResp::from(w))
+        })?;
+
+        let stats = db.backup_to(backup_dir.path())?;
+        assert_eq!(db.stats()?, stats);
+        assert!(!backup_dir
+            .path()
+            .join(format!("{}.tmp", KV_FILE_DB_DATA_FILENAME))
+            .exists());
+
+        // Written after the backup
+        db.write(|mut w| {
+            db.get_int_store("test1")
+                .put(w.as_mut(), 3, &Value::Str("titi"))?;
+            Ok(WriteResp::from(w))
+        })?;
+
+        let backup_db = KvFileDbRoHandler::open_db_ro(backup_dir.path(), &schema)?;
+        assert_eq!(
+            Some("toto".to_owned()),
+            get_int_store_str_val(&backup_db, "test1", 3)?
+        );
+
+        Ok(())
+    }
 }
@@ -0,0 +1,259 @@
+//  Copyright (C) 2017-2019  The AXIOM TEAM Association.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Migrations of a database schema: each migration upgrades the database from a version
+//! of its schema to a more recent one, the migrations are chained up to the current version.
+//! The stores added to the schema are created when the database is opened, the migrations
+//! only have to fill them or to rewrite the entries whose format changed.
+
+use super::file::KvFileDbHandler;
+use crate::errors::DbError;
+use std::fmt;
+use std::path::PathBuf;
+
+#[derive(Clone, Copy)]
+/// Migration of a database from a version of its schema to a more recent one
+pub struct Migration {
+    /// Version of the schema before the migration
+    pub from: usize,
+    /// Version of the schema after the migration
+    pub to: usize,
+    /// Description of the migration
+    pub description: &'static str,
+    /// Migrate the datas of the database
+    pub run: fn(&mut KvFileDbHandler) -> Result<(), DbError>,
+}
+
+impl fmt::Debug for Migration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Migration({} -> {})", self.from, self.to)
+    }
+}
+
+impl fmt::Display for Migration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "v{} -> v{}: {}", self.from, self.to, self.description)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+/// Migration options
+pub struct MigrationOptions {
+    /// Only compute the migrations to apply
+    pub dry_run: bool,
+    /// Folder where the database is backed up before the first migration
+    pub backup_path: Option<PathBuf>,
+}
+
+#[derive(Clone, Debug)]
+/// Registry of the migrations of a database schema
+pub struct MigrationRegistry {
+    current_version: usize,
+    migrations: Vec<Migration>,
+}
+
+impl MigrationRegistry {
+    /// Create the registry of the migrations up to `current_version`
+    pub fn new(current_version: usize, migrations: Vec<Migration>) -> Self {
+        MigrationRegistry {
+            current_version,
+            migrations,
+        }
+    }
+    /// Current version of the schema
+    pub fn current_version(&self) -> usize {
+        self.current_version
+    }
+    /// Migrations to apply, in order, to a database whose schema is at `version`
+    pub fn plan(&self, version: usize) -> Result<Vec<Migration>, DbError> {
+        if version > self.current_version {
+            return Err(DbError::UnsupportedVersion {
+                found: version,
+                supported: self.current_version,
+            });
+        }
+
+        let mut plan = Vec::new();
+        let mut version = version;
+        while version < self.current_version {
+            let migration = self
+                .migrations
+                .iter()
+                .find(|m| m.from == version && m.to > version && m.to <= self.current_version)
+                .ok_or(DbError::MissingMigration(version))?;
+            plan.push(*migration);
+            version = migration.to;
+        }
+        Ok(plan)
+    }
+    /// Migrate the database whose schema is at `version` to the current version.
+    /// `set_version` persists the version of the schema after each migration, so that
+    /// an interrupted migration resumes from the last completed migration.
+    /// Return the applied migrations (the migrations to apply in dry-run mode).
+    pub fn migrate<F>(
+        &self,
+        db: &mut KvFileDbHandler,
+        version: usize,
+        options: &MigrationOptions,
+        set_version: F,
+    ) -> Result<Vec<Migration>, DbError>
+    where
+        F: Fn(&KvFileDbHandler, usize) -> Result<(), DbError>,
+    {
+        let plan = self.plan(version)?;
+        if plan.is_empty() || options.dry_run {
+            return Ok(plan);
+        }
+
+        if let Some(ref backup_path) = options.backup_path {
+            db.backup_to(backup_path)?;
+        }
+        for migration in &plan {
+            (migration.run)(db)?;
+            set_version(db, migration.to)?;
+            db.save()?;
+        }
+        Ok(plan)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::super::file::{KvFileDbRead, KvFileDbSchema, KvFileDbStoreType, WriteResp};
+    use super::*;
+    use crate::kv_db_old::KV_FILE_DB_DATA_FILENAME;
+    use rkv::Value;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tempfile::tempdir;
+
+    static VERSION: AtomicUsize = AtomicUsize::new(1);
+
+    fn put_migrated(db: &mut KvFileDbHandler, key: u32) -> Result<(), DbError> {
+        db.write(|mut w| {
+            db.get_int_store("test")
+                .put(w.as_mut(), key, &Value::Str("migrated"))?;
+            Ok(WriteResp::from(w))
+        })
+    }
+
+    fn migration_1_to_2(db: &mut KvFileDbHandler) -> Result<(), DbError> {
+        put_migrated(db, 2)
+    }
+
+    fn migration_2_to_3(db: &mut KvFileDbHandler) -> Result<(), DbError> {
+        put_migrated(db, 3)
+    }
+
+    fn registry() -> MigrationRegistry {
+        MigrationRegistry::new(
+            3,
+            vec![
+                Migration {
+                    from: 2,
+                    to: 3,
+                    description: "second",
+                    run: migration_2_to_3,
+                },
+                Migration {
+                    from: 1,
+                    to: 2,
+                    description: "first",
+                    run: migration_1_to_2,
+                },
+            ],
+        )
+    }
+
+    #[test]
+    fn test_migration_plan() {
+        let registry = registry();
+
+        let plan = registry.plan(1).expect("Fail to plan migrations");
+        assert_eq!(
+            vec![(1, 2), (2, 3)],
+            plan.iter().map(|m| (m.from, m.to)).collect::<Vec<_>>()
+        );
+        assert!(registry
+            .plan(3)
+            .expect("Fail to plan migrations")
+            .is_empty());
+        match registry.plan(4) {
+            Err(DbError::UnsupportedVersion {
+                found: 4,
+                supported: 3,
+            }) => {}
+            r => panic!("unexpected plan: {:?}", r),
+        }
+        match registry.plan(0) {
+            Err(DbError::MissingMigration(0)) => {}
+            r => panic!("unexpected plan: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_migrate() -> Result<(), DbError> {
+        let tmp_dir = tempdir().map_err(DbError::FileSystemError)?;
+        let backup_dir = tempdir().map_err(DbError::FileSystemError)?;
+        let mut stores = HashMap::new();
+        stores.insert("test".to_owned(), KvFileDbStoreType::SingleIntKey);
+        let mut db = KvFileDbHandler::open_db(tmp_dir.path(), &KvFileDbSchema { stores })?;
+        let registry = registry();
+        let set_version = |_: &KvFileDbHandler, version| {
+            VERSION.store(version, Ordering::SeqCst);
+            Ok(())
+        };
+        let count_entries = |db: &KvFileDbHandler| {
+            db.read(|r| {
+                let mut count = 0;
+                for key in 1..=3 {
+                    if db.get_int_store("test").get(&r, key)?.is_some() {
+                        count += 1;
+                    }
+                }
+                Ok(count)
+            })
+        };
+
+        // Dry run
+        let options = MigrationOptions {
+            dry_run: true,
+            backup_path: Some(backup_dir.path().to_owned()),
+        };
+        assert_eq!(
+            2,
+            registry.migrate(&mut db, 1, &options, set_version)?.len()
+        );
+        assert_eq!(1, VERSION.load(Ordering::SeqCst));
+        assert_eq!(0, count_entries(&db)?);
+        assert!(!backup_dir.path().join(KV_FILE_DB_DATA_FILENAME).exists());
+
+        // Migration
+        let options = MigrationOptions {
+            dry_run: false,
+            ..options
+        };
+        assert_eq!(
+            2,
+            registry.migrate(&mut db, 1, &options, set_version)?.len()
+        );
+        assert_eq!(3, VERSION.load(Ordering::SeqCst));
+        assert_eq!(2, count_entries(&db)?);
+        assert!(backup_dir.path().join(KV_FILE_DB_DATA_FILENAME).exists());
+
+        Ok(())
+    }
+}